@@ -26,6 +26,7 @@ use sysinfo::{get_current_pid, ProcessExt, System, SystemExt};
 use network::{SyncState, SyncProvider};
 use client::BlockchainEvents;
 use runtime_primitives::traits::{Header, As};
+use substrate_telemetry::SUBSTRATE_INFO;
 
 const TIMER_INTERVAL_MS: u64 = 5000;
 
@@ -44,7 +45,7 @@ pub fn start<C>(service: &Service<C>, exit: ::exit_future::Exit, handle: TaskExe
 	let mut sys = System::new();
 	let self_pid = get_current_pid();
 
-	let display_notifications = interval.map_err(|e| debug!("Timer error: {:?}", e)).for_each(move |_| {
+	let display_notifications = interval.map_err(|e| debug!(target: "substrate", "Timer error: {:?}", e)).for_each(move |_| {
 		let sync_status = network.status();
 
 		if let Ok(best_block) = client.best_block_header() {
@@ -69,6 +70,11 @@ pub fn start<C>(service: &Service<C>, exit: ::exit_future::Exit, handle: TaskExe
 				hash
 			);
 
+			let (finalized_number, finalized_hash): (u64, _) = match client.info() {
+				Ok(info) => (info.chain.finalized_number.as_(), Some(info.chain.finalized_hash)),
+				Err(_) => (0, None),
+			};
+
 			// get cpu usage and memory usage of this process
 			let (cpu_usage, memory) = if sys.refresh_process(self_pid) {
 				let proc = sys.get_process(self_pid).expect("Above refresh_process succeeds, this should be Some(), qed");
@@ -76,11 +82,14 @@ pub fn start<C>(service: &Service<C>, exit: ::exit_future::Exit, handle: TaskExe
 			} else { (0.0, 0) };
 
 			telemetry!(
+				SUBSTRATE_INFO;
 				"system.interval";
 				"status" => format!("{}{}", status, target),
 				"peers" => num_peers,
 				"height" => best_number,
 				"best" => ?hash,
+				"finalized_height" => finalized_number,
+				"finalized_hash" => ?finalized_hash,
 				"txcount" => txpool_status.ready,
 				"cpu" => cpu_usage,
 				"memory" => memory
@@ -101,7 +110,7 @@ pub fn start<C>(service: &Service<C>, exit: ::exit_future::Exit, handle: TaskExe
 	let txpool = service.transaction_pool();
 	let display_txpool_import = txpool.import_notification_stream().for_each(move |_| {
 		let status = txpool.status();
-		telemetry!("txpool.import"; "ready" => status.ready, "future" => status.future);
+		telemetry!(SUBSTRATE_INFO; "txpool.import"; "ready" => status.ready, "future" => status.future);
 		Ok(())
 	});
 
@@ -22,17 +22,15 @@
 #![warn(unused_extern_crates)]
 
 extern crate app_dirs;
-extern crate env_logger;
-extern crate atty;
 extern crate ansi_term;
 extern crate regex;
-extern crate time;
 extern crate fdlimit;
 extern crate futures;
 extern crate tokio;
 extern crate names;
 extern crate backtrace;
 extern crate sysinfo;
+extern crate rpassword;
 
 extern crate substrate_client as client;
 extern crate substrate_network as network;
@@ -44,10 +42,9 @@ extern crate substrate_primitives as primitives;
 extern crate slog;	// needed until we can reexport `slog_info` from `substrate_telemetry`
 #[macro_use]
 extern crate substrate_telemetry;
+extern crate substrate_logger;
 extern crate exit_future;
 
-#[macro_use]
-extern crate lazy_static;
 extern crate clap;
 #[macro_use]
 extern crate error_chain;
@@ -62,7 +59,7 @@ use network_libp2p::Protocol;
 use runtime_primitives::traits::As;
 use service::{
 	ServiceFactory, FactoryFullConfiguration, RuntimeGenesis,
-	FactoryGenesis, PruningMode, ChainSpec,
+	FactoryGenesis, PruningMode, SyncMode, ChainSpec,
 };
 use network::NonReservedPeerMode;
 use primitives::H256;
@@ -79,6 +76,9 @@ use regex::Regex;
 
 use futures::Future;
 
+/// Origins allowed to access the RPC servers unless `--rpc-cors` is given.
+const DEFAULT_RPC_CORS: &str = "http://localhost:*,http://127.0.0.1:*,https://localhost:*,https://127.0.0.1:*";
+
 /// Executable version. Used to pass version information from the root crate.
 pub struct VersionInfo {
 	/// Implementation version.
@@ -191,8 +191,7 @@ where
 	};
 
 	// TODO [ToDr] Split parameters parsing from actual execution.
-	let log_pattern = matches.value_of("log").unwrap_or("");
-	init_logger(log_pattern);
+	init_logger(&matches);
 	fdlimit::raise_fd_limit();
 
 	if let Some(matches) = matches.subcommand_matches("build-spec") {
@@ -250,15 +249,43 @@ where
 		.to_string_lossy()
 		.into();
 
+	config.password = if matches.is_present("password-interactive") {
+		rpassword::read_password_from_tty(Some("Key password: "))?
+	} else if let Some(path) = matches.value_of("password-filename") {
+		let password = fs::read_to_string(path)
+			.map_err(|e| error::ErrorKind::Input(format!("Unable to read password file '{}': {}", path, e)))?;
+		password.trim_right_matches(|c| c == '\r' || c == '\n').to_owned()
+	} else {
+		matches.value_of("password").unwrap_or_default().to_owned()
+	};
+
+	config.remote_signer = match matches.value_of("remote-signer") {
+		Some(address) => Some(address.parse()
+			.map_err(|_| error::ErrorKind::Input(format!("Invalid remote signer address '{}'", address)))?),
+		None => None,
+	};
+
 	config.database_path = db_path(&base_path, config.chain_spec.id()).to_string_lossy().into();
 
 	config.pruning = match matches.value_of("pruning") {
 		Some("archive") => PruningMode::ArchiveAll,
+		Some("finalized") => PruningMode::keep_finalized(),
 		None => PruningMode::default(),
 		Some(s) => PruningMode::keep_blocks(s.parse()
 			.map_err(|_| error::ErrorKind::Input("Invalid pruning mode specified".to_owned()))?),
 	};
 
+	config.sync_mode = match matches.value_of("sync") {
+		Some("fast") => SyncMode::Fast,
+		_ => SyncMode::Full,
+	};
+
+	config.fallback_slots = match matches.value_of("fallback-slots") {
+		Some(slots) => Some(slots.parse()
+			.map_err(|_| error::ErrorKind::Input(format!("Invalid number of fallback slots '{}'", slots)))?),
+		None => None,
+	};
+
 	let role =
 		if matches.is_present("light") {
 			config.block_execution_strategy = service::ExecutionStrategy::NativeWhenPossible;
@@ -280,6 +307,14 @@ where
 		};
 	}
 
+	config.fuel_limit = match matches.value_of("fuel-limit") {
+		Some(limit) => Some(limit.parse()
+			.map_err(|_| error::ErrorKind::Input(format!("Invalid fuel limit '{}'", limit)))?),
+		None => None,
+	};
+
+	config.offchain_worker = matches.is_present("offchain-worker");
+
 	config.roles = role;
 	{
 		config.network.boot_nodes.extend(matches
@@ -290,7 +325,7 @@ where
 		config.network.reserved_nodes.extend(matches
 			 .values_of("reserved-nodes")
 			 .map_or(Default::default(), |v| v.map(|n| n.to_owned()).collect::<Vec<_>>()));
-		if !config.network.reserved_nodes.is_empty() {
+		if !config.network.reserved_nodes.is_empty() || matches.is_present("reserved-only") {
 			config.network.non_reserved_mode = NonReservedPeerMode::Deny;
 		}
 
@@ -312,6 +347,11 @@ where
 		}
 
 		config.network.public_addresses = Vec::new();
+		for addr in matches.values_of("public-addr").unwrap_or_default() {
+			let addr = addr.parse().map_err(|_| "Invalid public multiaddress")?;
+			config.network.public_addresses.push(addr);
+		}
+		config.network.enable_upnp = !matches.is_present("no-upnp");
 
 		config.network.client_version = config.client_id();
 		config.network.use_secret = match matches.value_of("node-key").map(H256::from_str) {
@@ -343,12 +383,18 @@ where
 
 	config.rpc_http = Some(parse_address(&format!("{}:{}", rpc_interface, 9933), "rpc-port", &matches)?);
 	config.rpc_ws = Some(parse_address(&format!("{}:{}", ws_interface, 9944), "ws-port", &matches)?);
+	config.rpc_methods = match matches.value_of("rpc-methods") {
+		Some("auto") => service::RpcMethods::Auto,
+		Some("unsafe") => service::RpcMethods::Unsafe,
+		_ => service::RpcMethods::Safe,
+	};
+	config.rpc_cors = parse_cors(matches.value_of("rpc-cors").unwrap_or(DEFAULT_RPC_CORS));
 
 	// Override telemetry
 	if matches.is_present("no-telemetry") {
-		config.telemetry_url = None;
-	} else if let Some(url) = matches.value_of("telemetry-url") {
-		config.telemetry_url = Some(url.to_owned());
+		config.telemetry_endpoints = Vec::new();
+	} else if let Some(endpoints) = matches.values_of("telemetry-url") {
+		config.telemetry_endpoints = endpoints.map(parse_telemetry_endpoint).collect::<Result<_, _>>()?;
 	}
 
 	Ok(Action::RunService((config, exit)))
@@ -420,7 +466,9 @@ fn import_blocks<F, E>(matches: &clap::ArgMatches, spec: ChainSpec<FactoryGenesi
 		None => Box::new(stdin()),
 	};
 
-	Ok(service::chain_ops::import_blocks::<F, _, _>(config, exit, file)?)
+	let json = matches.is_present("json");
+
+	Ok(service::chain_ops::import_blocks::<F, _, _>(config, exit, file, json)?)
 }
 
 fn revert_chain<F>(matches: &clap::ArgMatches, spec: ChainSpec<FactoryGenesis<F>>) -> error::Result<()>
@@ -444,21 +492,31 @@ fn purge_chain<F>(matches: &clap::ArgMatches, spec: ChainSpec<FactoryGenesis<F>>
 	let base_path = base_path(matches);
 	let database_path = db_path(&base_path, spec.id());
 
-	print!("Are you sure to remove {:?}? (y/n)", &database_path);
-	stdout().flush().expect("failed to flush stdout");
+	if !database_path.exists() {
+		println!("{:?} does not exist.", &database_path);
+		return Ok(());
+	}
 
-	let mut input = String::new();
-	stdin().read_line(&mut input)?;
-	let input = input.trim();
+	if !matches.is_present("yes") {
+		print!("Are you sure to remove {:?}? (y/n)", &database_path);
+		stdout().flush().expect("failed to flush stdout");
 
-	match input.chars().nth(0) {
-		Some('y') | Some('Y') => {
-			fs::remove_dir_all(&database_path)?;
-			println!("{:?} removed.", &database_path);
-		},
-		_ => println!("Aborted"),
+		let mut input = String::new();
+		stdin().read_line(&mut input)?;
+		let input = input.trim();
+
+		match input.chars().nth(0) {
+			Some('y') | Some('Y') => {},
+			_ => {
+				println!("Aborted");
+				return Ok(());
+			},
+		}
 	}
 
+	fs::remove_dir_all(&database_path)?;
+	println!("{:?} removed.", &database_path);
+
 	Ok(())
 }
 
@@ -472,6 +530,24 @@ fn parse_address(default: &str, port_param: &str, matches: &clap::ArgMatches) ->
 	Ok(address)
 }
 
+fn parse_cors(origins: &str) -> Option<Vec<String>> {
+	if origins == "all" {
+		return None;
+	}
+
+	Some(origins.split(',').map(str::trim).filter(|o| !o.is_empty()).map(str::to_owned).collect())
+}
+
+fn parse_telemetry_endpoint(endpoint: &str) -> error::Result<(String, u8)> {
+	let mut parts = endpoint.trim().rsplitn(2, ' ');
+	match (parts.next(), parts.next()) {
+		(Some(verbosity), Some(url)) => verbosity.parse()
+			.map(|verbosity| (url.trim().to_owned(), verbosity))
+			.map_err(|_| error::ErrorKind::Input(format!("Invalid telemetry verbosity '{}'", verbosity)).into()),
+		_ => Ok((endpoint.trim().to_owned(), substrate_telemetry::SUBSTRATE_INFO)),
+	}
+}
+
 fn keystore_path(base_path: &Path, chain_id: &str) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("chains");
@@ -510,53 +586,14 @@ fn default_base_path() -> PathBuf {
 	).expect("app directories exist on all supported platforms; qed")
 }
 
-fn init_logger(pattern: &str) {
-	use ansi_term::Colour;
-
-	let mut builder = env_logger::Builder::new();
-	// Disable info logging by default for some modules:
-	builder.filter(Some("ws"), log::LevelFilter::Off);
-	builder.filter(Some("hyper"), log::LevelFilter::Warn);
-	// Enable info for others.
-	builder.filter(None, log::LevelFilter::Info);
-
-	if let Ok(lvl) = std::env::var("RUST_LOG") {
-		builder.parse(&lvl);
-	}
-
-	builder.parse(pattern);
-	let isatty = atty::is(atty::Stream::Stderr);
-	let enable_color = isatty;
-
-	builder.format(move |buf, record| {
-		let timestamp = time::strftime("%Y-%m-%d %H:%M:%S", &time::now()).expect("Error formatting log timestamp");
-
-		let mut output = if log::max_level() <= log::LevelFilter::Info {
-			format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
-		} else {
-			let name = ::std::thread::current().name().map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
-			format!("{} {} {} {}  {}", Colour::Black.bold().paint(timestamp), name, record.level(), record.target(), record.args())
-		};
-
-		if !enable_color {
-			output = kill_color(output.as_ref());
-		}
-
-		if !isatty && record.level() <= log::Level::Info && atty::is(atty::Stream::Stdout) {
-			// duplicate INFO/WARN output to console
-			println!("{}", output);
-		}
-		writeln!(buf, "{}", output)
-	});
-
-	builder.init();
-}
-
-fn kill_color(s: &str) -> String {
-	lazy_static! {
-		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").expect("Error initializing color regex");
+fn init_logger(matches: &clap::ArgMatches) {
+	let config = substrate_logger::Config {
+		pattern: matches.value_of("log").unwrap_or("").into(),
+		json: matches.is_present("log-json"),
+	};
+	if let Err(e) = substrate_logger::init(config) {
+		eprintln!("Failed to initialise the logger: {}", e);
 	}
-	RE.replace_all(s, "").to_string()
 }
 
 #[cfg(test)]
@@ -577,4 +614,18 @@ mod tests {
 		assert!(is_node_name_valid("www.visit.me").is_err());
 		assert!(is_node_name_valid("email@domain").is_err());
 	}
+
+	#[test]
+	fn parses_telemetry_endpoints() {
+		assert_eq!(parse_telemetry_endpoint("ws://telemetry.local").unwrap(), ("ws://telemetry.local".into(), 0));
+		assert_eq!(parse_telemetry_endpoint("ws://telemetry.local 5").unwrap(), ("ws://telemetry.local".into(), 5));
+		assert!(parse_telemetry_endpoint("ws://telemetry.local loud").is_err());
+	}
+
+	#[test]
+	fn parses_cors_origins() {
+		assert_eq!(parse_cors("all"), None);
+		assert_eq!(parse_cors("https://a.io, http://b.io"), Some(vec!["https://a.io".into(), "http://b.io".into()]));
+		assert_eq!(parse_cors(""), Some(vec![]));
+	}
 }
@@ -18,10 +18,9 @@
 
 use std::sync::Arc;
 
-use kvdb::{KeyValueDB, DBTransaction};
-
 use client::error::{Error as ClientError, ErrorKind as ClientErrorKind, Result as ClientResult};
 use codec::{Encode, Decode};
+use database::{Database, Transaction};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use utils::{self, db_err, meta_keys};
@@ -97,19 +96,19 @@ pub struct DbColumns {
 pub struct DbStorage {
 	name: Vec<u8>,
 	meta_key: Vec<u8>,
-	db: Arc<KeyValueDB>,
+	db: Arc<Database>,
 	columns: DbColumns,
 }
 
 impl DbStorage {
 	/// Create new database-backed list cache storage.
-	pub fn new(name: Vec<u8>, db: Arc<KeyValueDB>, columns: DbColumns) -> Self {
+	pub fn new(name: Vec<u8>, db: Arc<Database>, columns: DbColumns) -> Self {
 		let meta_key = meta::key(&name);
 		DbStorage { name, meta_key, db, columns }
 	}
 
 	/// Get reference to the database.
-	pub fn db(&self) -> &Arc<KeyValueDB> { &self.db }
+	pub fn db(&self) -> &Arc<Database> { &self.db }
 
 	/// Get reference to the database columns.
 	pub fn columns(&self) -> &DbColumns { &self.columns }
@@ -161,12 +160,12 @@ impl<Block: BlockT, T: CacheItemT> Storage<Block, T> for DbStorage {
 /// Database-backed list cache storage transaction.
 pub struct DbStorageTransaction<'a> {
 	storage: &'a DbStorage,
-	tx: &'a mut DBTransaction,
+	tx: &'a mut Transaction,
 }
 
 impl<'a> DbStorageTransaction<'a> {
 	/// Create new database transaction.
-	pub fn new(storage: &'a DbStorage, tx: &'a mut DBTransaction) -> Self {
+	pub fn new(storage: &'a DbStorage, tx: &'a mut Transaction) -> Self {
 		DbStorageTransaction { storage, tx }
 	}
 }
@@ -0,0 +1,165 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The key-value store both the full and the light backends are built on.
+//!
+//! Data is kept in column families, e.g. headers, bodies, state, justifications and aux
+//! data each have their own. All changes are written through a `Transaction`, which is
+//! applied atomically. RocksDB is used on disk, and an in-memory store for tests and
+//! throwaway nodes; any other store can be plugged in by implementing `Database`.
+
+use std::io;
+use std::sync::Arc;
+
+use client::LeafSetTransaction;
+use kvdb::{KeyValueDB, DBTransaction};
+use kvdb_memorydb::InMemory;
+use kvdb_rocksdb::Database as RocksDb;
+
+/// A column family of a database. `None` is the default column.
+pub type Column = Option<u32>;
+
+/// A value stored in a database.
+pub type Value = Vec<u8>;
+
+/// A single change of a `Transaction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+	/// Set the value under the key in the column.
+	Set(Column, Vec<u8>, Value),
+	/// Remove the value under the key in the column.
+	Remove(Column, Vec<u8>),
+}
+
+/// A set of changes to a database, applied atomically and in order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Transaction {
+	/// The changes of the transaction.
+	pub changes: Vec<Change>,
+}
+
+impl Transaction {
+	/// Create an empty transaction.
+	pub fn new() -> Self {
+		Transaction { changes: Vec::new() }
+	}
+
+	/// Set the value under the key in the column.
+	pub fn put(&mut self, col: Column, key: &[u8], value: &[u8]) {
+		self.changes.push(Change::Set(col, key.to_vec(), value.to_vec()));
+	}
+
+	/// Set the value under the key in the column, without copying the value.
+	pub fn put_vec(&mut self, col: Column, key: &[u8], value: Value) {
+		self.changes.push(Change::Set(col, key.to_vec(), value));
+	}
+
+	/// Remove the value under the key in the column.
+	pub fn delete(&mut self, col: Column, key: &[u8]) {
+		self.changes.push(Change::Remove(col, key.to_vec()));
+	}
+}
+
+impl LeafSetTransaction for Transaction {
+	fn put_vec(&mut self, col: Column, key: &[u8], value: Vec<u8>) {
+		Transaction::put_vec(self, col, key, value)
+	}
+
+	fn delete(&mut self, col: Column, key: &[u8]) {
+		Transaction::delete(self, col, key)
+	}
+}
+
+/// A key-value store with column families.
+pub trait Database: Send + Sync {
+	/// Get the value under the key in the column, if any.
+	fn get(&self, col: Column, key: &[u8]) -> io::Result<Option<Value>>;
+
+	/// Iterate over the entries of the column, starting from the first key with the prefix.
+	///
+	/// Entries come in key order; the iteration doesn't stop at the last key with the prefix.
+	fn iter_from_prefix<'a>(&'a self, col: Column, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a>;
+
+	/// Apply all changes of the transaction atomically.
+	fn write(&self, transaction: Transaction) -> io::Result<()>;
+}
+
+fn to_kvdb_transaction(transaction: Transaction) -> DBTransaction {
+	let mut kvdb_transaction = DBTransaction::with_capacity(transaction.changes.len());
+	for change in transaction.changes {
+		match change {
+			Change::Set(col, key, value) => kvdb_transaction.put_vec(col, &key, value),
+			Change::Remove(col, key) => kvdb_transaction.delete(col, &key),
+		}
+	}
+	kvdb_transaction
+}
+
+impl Database for RocksDb {
+	fn get(&self, col: Column, key: &[u8]) -> io::Result<Option<Value>> {
+		KeyValueDB::get(self, col, key).map(|v| v.map(|v| v.into_vec()))
+	}
+
+	fn iter_from_prefix<'a>(&'a self, col: Column, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		KeyValueDB::iter_from_prefix(self, col, prefix)
+	}
+
+	fn write(&self, transaction: Transaction) -> io::Result<()> {
+		KeyValueDB::write(self, to_kvdb_transaction(transaction))
+	}
+}
+
+impl Database for InMemory {
+	fn get(&self, col: Column, key: &[u8]) -> io::Result<Option<Value>> {
+		KeyValueDB::get(self, col, key).map(|v| v.map(|v| v.into_vec()))
+	}
+
+	fn iter_from_prefix<'a>(&'a self, col: Column, prefix: &'a [u8]) -> Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		KeyValueDB::iter_from_prefix(self, col, prefix)
+	}
+
+	fn write(&self, transaction: Transaction) -> io::Result<()> {
+		KeyValueDB::write(self, to_kvdb_transaction(transaction))
+	}
+}
+
+/// Create an empty in-memory database with the given number of columns.
+pub fn in_memory(columns: u32) -> Arc<Database> {
+	Arc::new(::kvdb_memorydb::create(columns))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn in_memory_database_keeps_columns_apart() {
+		let db = in_memory(2);
+		let mut transaction = Transaction::new();
+		transaction.put(Some(0), b"key", b"zero");
+		transaction.put(Some(1), b"key", b"one");
+		db.write(transaction).unwrap();
+
+		assert_eq!(db.get(Some(0), b"key").unwrap(), Some(b"zero".to_vec()));
+		assert_eq!(db.get(Some(1), b"key").unwrap(), Some(b"one".to_vec()));
+
+		let mut transaction = Transaction::new();
+		transaction.delete(Some(0), b"key");
+		db.write(transaction).unwrap();
+		assert!(db.get(Some(0), b"key").unwrap().is_none());
+		assert_eq!(db.iter_from_prefix(Some(1), b"k").count(), 1);
+	}
+}
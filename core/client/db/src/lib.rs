@@ -49,22 +49,25 @@ extern crate parity_codec_derive;
 #[cfg(test)]
 extern crate substrate_test_client as test_client;
 
-#[cfg(test)]
 extern crate kvdb_memorydb;
 
+pub mod database;
 pub mod light;
 
 mod cache;
 mod utils;
 
+pub use database::{Database, Transaction};
+pub use utils::NUM_COLUMNS;
+
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::io;
 
 use client::backend::NewBlockState;
 use codec::{Decode, Encode};
+use database::{Database, Transaction};
 use hash_db::Hasher;
-use kvdb::{KeyValueDB, DBTransaction};
 use trie::MemoryDB;
 use parking_lot::RwLock;
 use primitives::{H256, AuthorityId, Blake2Hasher, ChangesTrieConfiguration};
@@ -76,7 +79,7 @@ use state_machine::backend::Backend as StateBackend;
 use executor::RuntimeInfo;
 use state_machine::{CodeExecutor, DBValue, ExecutionStrategy};
 use utils::{Meta, db_err, meta_keys, open_database, read_db, block_id_to_lookup_key, read_meta};
-use client::LeafSet;
+use client::{LeafSet, FinalizationDisplaced};
 use state_db::StateDb;
 pub use state_db::PruningMode;
 
@@ -135,27 +138,27 @@ struct PendingBlock<Block: BlockT> {
 }
 
 // wrapper that implements trait required for state_db
-struct StateMetaDb<'a>(&'a KeyValueDB);
+struct StateMetaDb<'a>(&'a Database);
 
 impl<'a> state_db::MetaDb for StateMetaDb<'a> {
 	type Error = io::Error;
 
 	fn get_meta(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		self.0.get(columns::STATE_META, key).map(|r| r.map(|v| v.to_vec()))
+		self.0.get(columns::STATE_META, key)
 	}
 }
 
 /// Block database
 pub struct BlockchainDb<Block: BlockT> {
-	db: Arc<KeyValueDB>,
+	db: Arc<Database>,
 	meta: RwLock<Meta<NumberFor<Block>, Block::Hash>>,
 	leaves: RwLock<LeafSet<Block::Hash, NumberFor<Block>>>,
 }
 
 impl<Block: BlockT> BlockchainDb<Block> {
-	fn new(db: Arc<KeyValueDB>) -> Result<Self, client::error::Error> {
+	fn new(db: Arc<Database>) -> Result<Self, client::error::Error> {
 		let meta = read_meta::<Block>(&*db, columns::META, columns::HEADER)?;
-		let leaves = LeafSet::read_from_db(&*db, columns::META, meta_keys::LEAF_PREFIX)?;
+		let leaves = LeafSet::read_from_db(db.iter_from_prefix(columns::META, meta_keys::LEAF_PREFIX), meta_keys::LEAF_PREFIX)?;
 		Ok(BlockchainDb {
 			db,
 			leaves: RwLock::new(leaves),
@@ -186,6 +189,21 @@ impl<Block: BlockT> BlockchainDb<Block> {
 			meta.finalized_hash = hash;
 		}
 	}
+
+	fn is_descendent_of(
+		&self,
+		hash: Block::Hash,
+		ancestor: &Block::Hash,
+		ancestor_number: NumberFor<Block>,
+	) -> Result<bool, client::error::Error> {
+		use client::blockchain::HeaderBackend;
+		client::blockchain::is_descendent_of(
+			|h| self.expect_header(BlockId::Hash(h)),
+			hash,
+			ancestor,
+			ancestor_number,
+		)
+	}
 }
 
 impl<Block: BlockT> client::blockchain::HeaderBackend<Block> for BlockchainDb<Block> {
@@ -316,12 +334,14 @@ where Block: BlockT<Hash=H256>,
 	}
 
 	fn reset_storage(&mut self, mut top: StorageMap, children: ChildrenStorageMap) -> Result<H256, client::error::Error> {
-		// TODO: wipe out existing trie.
-
 		if top.iter().any(|(k, _)| well_known_keys::is_child_storage_key(k)) {
 			return Err(client::error::ErrorKind::GenesisInvalid.into());
 		}
 
+		// the storage is built from an empty trie rather than on top of the previous state.
+		let genesis_storage = DbGenesisStorage::new();
+		let root = genesis_storage.0.clone();
+		let empty_state = DbState::new(Arc::new(genesis_storage), root);
 		let mut transaction: MemoryDB<Blake2Hasher> = Default::default();
 
 		for (child_key, child_map) in children {
@@ -329,7 +349,7 @@ where Block: BlockT<Hash=H256>,
 				return Err(client::error::ErrorKind::GenesisInvalid.into());
 			}
 
-			let (root, is_default, update) = self.old_state.child_storage_root(&child_key, child_map.into_iter().map(|(k, v)| (k, Some(v))));
+			let (root, is_default, update) = empty_state.child_storage_root(&child_key, child_map.into_iter().map(|(k, v)| (k, Some(v))));
 			transaction.consolidate(update);
 
 			if !is_default {
@@ -337,7 +357,7 @@ where Block: BlockT<Hash=H256>,
 			}
 		}
 
-		let (root, update) = self.old_state.storage_root(top.into_iter().map(|(k, v)| (k, Some(v))));
+		let (root, update) = empty_state.storage_root(top.into_iter().map(|(k, v)| (k, Some(v))));
 		transaction.consolidate(update);
 
 		self.updates = transaction;
@@ -351,7 +371,7 @@ where Block: BlockT<Hash=H256>,
 }
 
 struct StorageDb<Block: BlockT> {
-	pub db: Arc<KeyValueDB>,
+	pub db: Arc<Database>,
 	pub state_db: StateDb<Block::Hash, H256>,
 }
 
@@ -367,7 +387,7 @@ impl<Block: BlockT> state_db::HashDb for StorageDb<Block> {
 	type Hash = H256;
 
 	fn get(&self, key: &H256) -> Result<Option<Vec<u8>>, Self::Error> {
-		self.db.get(columns::STATE, &key[..]).map(|r| r.map(|v| v.to_vec()))
+		self.db.get(columns::STATE, &key[..])
 	}
 }
 
@@ -389,21 +409,21 @@ impl state_machine::Storage<Blake2Hasher> for DbGenesisStorage {
 }
 
 pub struct DbChangesTrieStorage<Block: BlockT> {
-	db: Arc<KeyValueDB>,
+	db: Arc<Database>,
 	min_blocks_to_keep: Option<u64>,
 	_phantom: ::std::marker::PhantomData<Block>,
 }
 
 impl<Block: BlockT> DbChangesTrieStorage<Block> {
 	/// Commit new changes trie.
-	pub fn commit(&self, tx: &mut DBTransaction, mut changes_trie: MemoryDB<Blake2Hasher>) {
+	pub fn commit(&self, tx: &mut Transaction, mut changes_trie: MemoryDB<Blake2Hasher>) {
 		for (key, (val, _)) in changes_trie.drain() {
 			tx.put(columns::CHANGES_TRIE, &key[..], &val);
 		}
 	}
 
 	/// Prune obsolete changes tries.
-	pub fn prune(&self, config: Option<ChangesTrieConfiguration>, tx: &mut DBTransaction, block: NumberFor<Block>) {
+	pub fn prune(&self, config: Option<ChangesTrieConfiguration>, tx: &mut Transaction, block: NumberFor<Block>) {
 		// never prune on archive nodes
 		let min_blocks_to_keep = match self.min_blocks_to_keep {
 			Some(min_blocks_to_keep) => min_blocks_to_keep,
@@ -444,6 +464,7 @@ impl<Block: BlockT> state_machine::ChangesTrieRootsStorage<Blake2Hasher> for DbC
 impl<Block: BlockT> state_machine::ChangesTrieStorage<Blake2Hasher> for DbChangesTrieStorage<Block> {
 	fn get(&self, key: &H256) -> Result<Option<DBValue>, String> {
 		self.db.get(columns::CHANGES_TRIE, &key[..])
+			.map(|r| r.map(|v| DBValue::from_slice(&v)))
 			.map_err(|err| format!("{}", err))
 	}
 }
@@ -455,6 +476,7 @@ pub struct Backend<Block: BlockT> {
 	changes_tries_storage: DbChangesTrieStorage<Block>,
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
+	canonicalize_finalized_only: bool,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -464,24 +486,24 @@ impl<Block: BlockT> Backend<Block> {
 	pub fn new(config: DatabaseSettings, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
 		let db = open_database(&config, columns::META, "full")?;
 
-		Backend::from_kvdb(db as Arc<_>, config.pruning, canonicalization_delay)
+		Backend::from_database(db, config.pruning, canonicalization_delay)
 	}
 
 	#[cfg(test)]
 	fn new_test(keep_blocks: u32, canonicalization_delay: u64) -> Self {
-		use utils::NUM_COLUMNS;
-
-		let db = Arc::new(::kvdb_memorydb::create(NUM_COLUMNS));
-
-		Backend::from_kvdb(
-			db as Arc<_>,
+		Backend::from_database(
+			::database::in_memory(NUM_COLUMNS),
 			PruningMode::keep_blocks(keep_blocks),
 			canonicalization_delay,
 		).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>, pruning: PruningMode, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
+	/// Create a new instance of database backend on top of the given database.
+	///
+	/// The database must have `NUM_COLUMNS` columns.
+	pub fn from_database(db: Arc<Database>, pruning: PruningMode, canonicalization_delay: u64) -> Result<Self, client::error::Error> {
 		let is_archive_pruning = pruning.is_archive();
+		let canonicalize_finalized_only = pruning.is_finalized();
 		let blockchain = BlockchainDb::new(db.clone())?;
 		let map_e = |e: state_db::Error<io::Error>| ::client::error::Error::from(format!("State database error: {:?}", e));
 		let state_db: StateDb<Block::Hash, H256> = StateDb::new(pruning, &StateMetaDb(&*db)).map_err(map_e)?;
@@ -500,13 +522,14 @@ impl<Block: BlockT> Backend<Block> {
 			changes_tries_storage,
 			blockchain,
 			canonicalization_delay,
+			canonicalize_finalized_only,
 		})
 	}
 
 	// performs forced canonicaliziation with a delay after importning a non-finalized block.
 	fn force_delayed_canonicalize(
 		&self,
-		transaction: &mut DBTransaction,
+		transaction: &mut Transaction,
 		hash: Block::Hash,
 		number: NumberFor<Block>,
 	)
@@ -520,6 +543,11 @@ impl<Block: BlockT> Backend<Block> {
 				return Ok(())
 			}
 
+			// with finality-driven pruning only finalized blocks may become canonical.
+			if self.canonicalize_finalized_only && new_canonical > self.blockchain.meta.read().finalized_number.as_() {
+				return Ok(())
+			}
+
 			let hash = if new_canonical == number_u64 {
 				hash
 			} else {
@@ -541,7 +569,7 @@ impl<Block: BlockT> Backend<Block> {
 	// was not a child of the last finalized block.
 	fn note_finalized(
 		&self,
-		transaction: &mut DBTransaction,
+		transaction: &mut Transaction,
 		f_header: &Block::Header,
 		f_hash: Block::Hash,
 	) -> Result<(), client::error::Error> where
@@ -565,19 +593,73 @@ impl<Block: BlockT> Backend<Block> {
 			let commit = self.storage.state_db.canonicalize_block(&f_hash);
 			apply_state_commit(transaction, commit);
 
-			// read config from genesis, since it is readonly atm
-			use client::backend::Backend;
-			let changes_trie_config: Option<ChangesTrieConfiguration> = self.state_at(BlockId::Hash(parent_hash))?
-				.storage(well_known_keys::CHANGES_TRIE_CONFIG)?
-				.and_then(|v| Decode::decode(&mut &*v));
-			self.changes_tries_storage.prune(changes_trie_config, transaction, f_num);
+			// read config from genesis, since it is readonly atm. blocks imported by fast sync
+			// have no state and no changes tries, so there is nothing to prune for them.
+			if self.has_state(&parent_hash)? {
+				use client::backend::Backend;
+				let changes_trie_config: Option<ChangesTrieConfiguration> = self.state_at(BlockId::Hash(parent_hash))?
+					.storage(well_known_keys::CHANGES_TRIE_CONFIG)?
+					.and_then(|v| Decode::decode(&mut &*v));
+				self.changes_tries_storage.prune(changes_trie_config, transaction, f_num);
+			}
+		}
+
+		Ok(())
+	}
+
+	// Whether the state of a block is stored. Blocks imported by fast sync below its target only
+	// have a header.
+	fn has_state(&self, hash: &Block::Hash) -> Result<bool, client::error::Error> {
+		use client::blockchain::HeaderBackend;
+
+		// the parent of genesis has the empty state.
+		if *hash == Default::default() {
+			return Ok(true);
+		}
+		let header = self.blockchain.expect_header(BlockId::Hash(*hash))?;
+		let root = H256::from_slice(header.state_root().as_ref());
+		let root_node = state_machine::Storage::<Blake2Hasher>::get(&*self.storage, &root)?;
+		Ok(root_node.is_some())
+	}
+
+	/// Delete the blocks of the forks whose leaves were displaced by finalization.
+	///
+	/// Each fork is walked back from its leaf until the canonical chain is reached. The state of
+	/// these blocks has already been discarded by the state db when their level was canonicalized.
+	fn prune_displaced_forks(
+		&self,
+		transaction: &mut Transaction,
+		displaced: &FinalizationDisplaced<Block::Hash, NumberFor<Block>>,
+	) -> Result<(), client::error::Error> {
+		for leaf in displaced.leaves() {
+			let mut hash = leaf.clone();
+			loop {
+				let lookup_key = match self.storage.db.get(columns::HASH_LOOKUP, hash.as_ref()).map_err(db_err)? {
+					// canonical blocks are stored under short keys.
+					Some(ref key) if key.len() <= ::std::mem::size_of::<::utils::ShortBlockLookupKey>() => break,
+					Some(key) => key,
+					None => break,
+				};
+				let header = match self.storage.db.get(columns::HEADER, &lookup_key).map_err(db_err)? {
+					Some(header) => Block::Header::decode(&mut &header[..])
+						.ok_or_else(|| client::error::ErrorKind::Backend("Error decoding header".into()))?,
+					None => break,
+				};
+
+				trace!(target: "db", "Pruning block of dead fork {:?}", hash);
+				transaction.delete(columns::HEADER, &lookup_key);
+				transaction.delete(columns::BODY, &lookup_key);
+				transaction.delete(columns::JUSTIFICATION, &lookup_key);
+				transaction.delete(columns::HASH_LOOKUP, hash.as_ref());
+				hash = header.parent_hash().clone();
+			}
 		}
 
 		Ok(())
 	}
 }
 
-fn apply_state_commit(transaction: &mut DBTransaction, commit: state_db::CommitSet<H256>) {
+fn apply_state_commit(transaction: &mut Transaction, commit: state_db::CommitSet<H256>) {
 	for (key, val) in commit.data.inserted.into_iter() {
 		transaction.put(columns::STATE, &key[..], &val);
 	}
@@ -611,7 +693,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 	fn commit_operation(&self, mut operation: Self::BlockImportOperation)
 		-> Result<(), client::error::Error>
 	{
-		let mut transaction = DBTransaction::new();
+		let mut transaction = Transaction::new();
 
 		if let Some(pending_block) = operation.pending_block {
 			let hash = pending_block.header.hash();
@@ -684,8 +766,9 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 						let new_lookup_key = ::utils::number_to_lookup_key(enacted.number);
 
 						// change mapping from `number + hash -> header`
-						// to `number -> header`
-						let enacted_header = if let Some(header) = ::client::blockchain::HeaderBackend::<Block>::header(&self.blockchain, BlockId::Number(enacted.number))? {
+						// to `number -> header`. Enacted blocks aren't canonical yet, so they
+						// must be read by hash.
+						let enacted_header = if let Some(header) = ::client::blockchain::HeaderBackend::<Block>::header(&self.blockchain, BlockId::Hash(enacted.hash))? {
 							header
 						} else {
 							return Err(client::error::ErrorKind::UnknownBlock(format!("enacted {:?}", enacted)).into());
@@ -696,15 +779,15 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 						// if body is stored
 						// change mapping from `number + hash -> body`
 						// to `number -> body`
-						if let Some(enacted_body) = ::client::blockchain::Backend::<Block>::body(&self.blockchain, BlockId::Number(enacted.number))? {
+						if let Some(enacted_body) = ::client::blockchain::Backend::<Block>::body(&self.blockchain, BlockId::Hash(enacted.hash))? {
 							transaction.delete(columns::BODY, &prev_lookup_key);
 							transaction.put(columns::BODY, &new_lookup_key, &enacted_body.encode());
 						}
 
 						// if justification is stored
-						// change mapping from `number -> justification`
-						// to `number + hash -> justification`
-						if let Some(enacted_justification) = ::client::blockchain::Backend::<Block>::justification(&self.blockchain, BlockId::Number(enacted.number))? {
+						// change mapping from `number + hash -> justification`
+						// to `number -> justification`
+						if let Some(enacted_justification) = ::client::blockchain::Backend::<Block>::justification(&self.blockchain, BlockId::Hash(enacted.hash))? {
 							transaction.delete(columns::JUSTIFICATION, &prev_lookup_key);
 							transaction.put(columns::JUSTIFICATION, &new_lookup_key, &enacted_justification.encode());
 						}
@@ -764,11 +847,35 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 			{
 				let mut leaves = self.blockchain.leaves.write();
 				let displaced_leaf = leaves.import(hash, number, parent_hash);
+				let finalization_displaced = if finalized {
+					match leaves.finalize(&hash, &number, |leaf| self.blockchain.is_descendent_of(*leaf, &hash, number)) {
+						Ok(displaced) => Some(displaced),
+						Err(e) => {
+							if let Some(displaced_leaf) = displaced_leaf {
+								leaves.undo(displaced_leaf);
+							}
+							return Err(e);
+						},
+					}
+				} else {
+					None
+				};
 				leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+				let mut pruned = Ok(());
+				if let Some(ref finalization_displaced) = finalization_displaced {
+					finalization_displaced.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+					// forks can only be told apart from the finalized chain if it is the best chain.
+					if pending_block.leaf_state.is_best() {
+						pruned = self.prune_displaced_forks(&mut transaction, finalization_displaced);
+					}
+				}
 
-				let write_result = self.storage.db.write(transaction).map_err(db_err);
+				let write_result = pruned.and_then(|_| self.storage.db.write(transaction).map_err(db_err));
 				if let Err(e) = write_result {
 					// revert leaves set update, if there was one.
+					if let Some(finalization_displaced) = finalization_displaced {
+						leaves.undo_finalization(finalization_displaced);
+					}
 					if let Some(displaced_leaf) = displaced_leaf {
 						leaves.undo(displaced_leaf);
 					}
@@ -791,11 +898,30 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 		use runtime_primitives::traits::Header;
 
 		if let Some(header) = ::client::blockchain::HeaderBackend::header(&self.blockchain, block)? {
-			let mut transaction = DBTransaction::new();
+			let mut transaction = Transaction::new();
 			// TODO: ensure best chain contains this block.
 			let hash = header.hash();
 			self.note_finalized(&mut transaction, &header, hash.clone())?;
-			self.storage.db.write(transaction).map_err(db_err)?;
+
+			let mut leaves = self.blockchain.leaves.write();
+			let number = *header.number();
+			let displaced = leaves.finalize(&hash, &number, |leaf| self.blockchain.is_descendent_of(*leaf, &hash, number))?;
+			leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			displaced.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			// forks can only be told apart from the finalized chain if it is the best chain.
+			let is_canonical = self.storage.db.get(columns::HASH_LOOKUP, hash.as_ref()).map_err(db_err)?
+				.map_or(false, |key| key.len() <= ::std::mem::size_of::<::utils::ShortBlockLookupKey>());
+			let pruned = if is_canonical {
+				self.prune_displaced_forks(&mut transaction, &displaced)
+			} else {
+				Ok(())
+			};
+			if let Err(e) = pruned.and_then(|_| self.storage.db.write(transaction).map_err(db_err)) {
+				leaves.undo_finalization(displaced);
+				return Err(e);
+			}
+			drop(leaves);
+
 			self.blockchain.update_meta(hash, header.number().clone(), false, true);
 			Ok(())
 		} else {
@@ -809,16 +935,21 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 
 	fn revert(&self, n: NumberFor<Block>) -> Result<NumberFor<Block>, client::error::Error> {
 		use client::blockchain::HeaderBackend;
-		let mut best = self.blockchain.info()?.best_number;
+		let info = self.blockchain.info()?;
+		let mut best = info.best_number;
+		let finalized = info.finalized_number;
 		for c in 0 .. n.as_() {
-			if best == As::sa(0) {
+			// finalized blocks are never reverted.
+			if best <= finalized {
 				return Ok(As::sa(c))
 			}
-			let mut transaction = DBTransaction::new();
+			let mut transaction = Transaction::new();
 			match self.storage.state_db.revert_one() {
 				Some(commit) => {
 					apply_state_commit(&mut transaction, commit);
-					let _removed = best.clone();
+					let removed = self.blockchain.header(BlockId::Number(best))?.ok_or_else(
+						|| client::error::ErrorKind::UnknownBlock(
+							format!("Error reverting {}. Block header not found.", best)))?;
 					best -= As::sa(1);
 					let header = self.blockchain.header(BlockId::Number(best))?.ok_or_else(
 						|| client::error::ErrorKind::UnknownBlock(
@@ -826,10 +957,19 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 
 					let lookup_key = ::utils::number_to_lookup_key(header.number().clone());
 					transaction.put(columns::META, meta_keys::BEST_BLOCK, &lookup_key);
-					transaction.delete(columns::HASH_LOOKUP, header.hash().as_ref());
-					self.storage.db.write(transaction).map_err(db_err)?;
+					transaction.delete(columns::HASH_LOOKUP, removed.hash().as_ref());
+
+					let mut leaves = self.blockchain.leaves.write();
+					let reverted = leaves.revert(removed.hash(), removed.number().clone(), removed.parent_hash().clone());
+					leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+					reverted.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+					if let Err(e) = self.storage.db.write(transaction).map_err(db_err) {
+						leaves.undo_revert(reverted);
+						return Err(e);
+					}
+					drop(leaves);
+
 					self.blockchain.update_meta(header.hash().clone(), best.clone(), true, false);
-					self.blockchain.leaves.write().revert(header.hash().clone(), header.number().clone(), header.parent_hash().clone());
 				}
 				None => return Ok(As::sa(c))
 			}
@@ -867,7 +1007,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 	fn insert_aux<'a, 'b: 'a, 'c: 'a, I: IntoIterator<Item=&'a (&'c [u8], &'c [u8])>, D: IntoIterator<Item=&'a &'b [u8]>>
 		(&self, insert: I, delete: D) -> Result<(), client::error::Error>
 	{
-		let mut transaction = DBTransaction::new();
+		let mut transaction = Transaction::new();
 		for (k, v) in insert {
 			transaction.put(columns::AUX, k, v);
 		}
@@ -879,7 +1019,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 	}
 
 	fn get_aux(&self, key: &[u8]) -> Result<Option<Vec<u8>>, client::error::Error> {
-		Ok(self.storage.db.get(columns::AUX, key).map(|r| r.map(|v| v.to_vec())).map_err(db_err)?)
+		Ok(self.storage.db.get(columns::AUX, key).map_err(db_err)?)
 	}
 }
 
@@ -994,7 +1134,7 @@ mod tests {
 			db.storage.db.clone()
 		};
 
-		let backend = Backend::<Block>::from_kvdb(backing, PruningMode::keep_blocks(1), 0).unwrap();
+		let backend = Backend::<Block>::from_database(backing, PruningMode::keep_blocks(1), 0).unwrap();
 		assert_eq!(backend.blockchain().info().unwrap().best_number, 9);
 		for i in 0..10 {
 			assert!(backend.blockchain().hash(i).unwrap().is_some())
@@ -1264,7 +1404,7 @@ mod tests {
 		let root12 = read_changes_trie_root(&backend, 12); assert_eq!(backend.changes_tries_storage.root(12).unwrap(), Some(root12));
 
 		// now simulate finalization of block#12, causing prune of tries at #1..#4
-		let mut tx = DBTransaction::new();
+		let mut tx = Transaction::new();
 		backend.changes_tries_storage.prune(Some(config.clone()), &mut tx, 12);
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root1).unwrap().is_none());
@@ -1277,7 +1417,7 @@ mod tests {
 		assert!(backend.changes_tries_storage.get(&root8).unwrap().is_some());
 
 		// now simulate finalization of block#16, causing prune of tries at #5..#8
-		let mut tx = DBTransaction::new();
+		let mut tx = Transaction::new();
 		backend.changes_tries_storage.prune(Some(config.clone()), &mut tx, 16);
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root5).unwrap().is_none());
@@ -1288,7 +1428,7 @@ mod tests {
 		// now "change" pruning mode to archive && simulate finalization of block#20
 		// => no changes tries are pruned, because we never prune in archive mode
 		backend.changes_tries_storage.min_blocks_to_keep = None;
-		let mut tx = DBTransaction::new();
+		let mut tx = Transaction::new();
 		backend.changes_tries_storage.prune(Some(config), &mut tx, 20);
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root9).unwrap().is_some());
@@ -1381,4 +1521,117 @@ mod tests {
 		backend.insert_aux(&[], &[&b"test"[..]]).unwrap();
 		assert!(backend.get_aux(b"test").unwrap().is_none());
 	}
+
+	#[test]
+	fn revert_resets_best_block_and_leaves() {
+		use client::blockchain::Backend as BlockchainBackend;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let block0 = insert_header(&backend, 0, Default::default(), vec![], Default::default());
+		let block1 = insert_header(&backend, 1, block0, vec![], Default::default());
+		let block2 = insert_header(&backend, 2, block1, vec![], Default::default());
+		let block3 = insert_header(&backend, 3, block2, vec![], Default::default());
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block3]);
+
+		assert_eq!(backend.revert(2).unwrap(), 2);
+		assert_eq!(backend.blockchain().info().unwrap().best_number, 1);
+		assert_eq!(backend.blockchain().info().unwrap().best_hash, block1);
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block1]);
+		assert!(backend.blockchain().header(BlockId::Hash(block3)).unwrap().is_none());
+		assert!(backend.blockchain().header(BlockId::Hash(block2)).unwrap().is_none());
+
+		// the updated leaves survive a restart.
+		let reopened = Backend::<Block>::from_database(backend.storage.db.clone(), PruningMode::keep_blocks(10), 10).unwrap();
+		assert_eq!(reopened.blockchain().info().unwrap().best_number, 1);
+		assert_eq!(reopened.blockchain().leaves().unwrap(), vec![block1]);
+
+		// the finalized genesis is never reverted.
+		assert_eq!(reopened.revert(5).unwrap(), 1);
+		assert_eq!(reopened.blockchain().info().unwrap().best_hash, block0);
+		assert_eq!(reopened.blockchain().leaves().unwrap(), vec![block0]);
+	}
+
+	#[test]
+	fn justifications_follow_their_blocks_on_reorg() {
+		use client::blockchain::Backend as BlockchainBackend;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let insert = |number: u64, parent_hash: H256, fork: u8, state: NewBlockState| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: [fork; 32].into(),
+			};
+			let hash = header.hash();
+			let block_id = if number == 0 { BlockId::Hash(Default::default()) } else { BlockId::Hash(parent_hash) };
+			let mut op = backend.begin_operation(block_id).unwrap();
+			op.set_block_data(header, None, Some(vec![fork, number as u8]), state).unwrap();
+			backend.commit_operation(op).unwrap();
+			hash
+		};
+
+		// G -> A1, and G -> B1 -> B2 -> B3 where only B3 is imported as best.
+		let genesis = insert(0, Default::default(), 0, NewBlockState::Best);
+		let a1 = insert(1, genesis, 1, NewBlockState::Best);
+		let b1 = insert(1, genesis, 2, NewBlockState::Normal);
+		let b2 = insert(2, b1, 2, NewBlockState::Normal);
+		assert_eq!(backend.blockchain().justification(BlockId::Number(1)).unwrap(), Some(vec![1, 1]));
+		assert_eq!(backend.blockchain().justification(BlockId::Hash(b1)).unwrap(), Some(vec![2, 1]));
+
+		let b3 = insert(3, b2, 2, NewBlockState::Best);
+		assert_eq!(backend.blockchain().info().unwrap().best_hash, b3);
+		for (number, hash) in vec![(1, b1), (2, b2), (3, b3)] {
+			assert_eq!(backend.blockchain().hash(number).unwrap(), Some(hash));
+			assert_eq!(backend.blockchain().header(BlockId::Hash(hash)).unwrap().unwrap().hash(), hash);
+			assert_eq!(backend.blockchain().justification(BlockId::Number(number)).unwrap(), Some(vec![2, number as u8]));
+		}
+		assert_eq!(backend.blockchain().header(BlockId::Hash(a1)).unwrap().unwrap().hash(), a1);
+		assert_eq!(backend.blockchain().justification(BlockId::Hash(a1)).unwrap(), Some(vec![1, 1]));
+	}
+
+	#[test]
+	fn finalized_pruning_canonicalizes_only_finalized_blocks() {
+		let backend = Backend::<Block>::from_database(
+			::database::in_memory(NUM_COLUMNS),
+			PruningMode::keep_finalized(),
+			0,
+		).unwrap();
+		let genesis = insert_header(&backend, 0, Default::default(), Vec::new(), Default::default());
+		let block1 = insert_header(&backend, 1, genesis, Vec::new(), Default::default());
+		let _ = insert_header(&backend, 2, block1, Vec::new(), Default::default());
+
+		// without finality nothing gets canonicalized, whatever the delay.
+		assert_eq!(backend.storage.state_db.best_canonical(), 0);
+		assert!(!backend.storage.state_db.is_pruned(0));
+
+		backend.finalize_block(BlockId::Hash(block1)).unwrap();
+		assert_eq!(backend.storage.state_db.best_canonical(), 1);
+		assert!(backend.storage.state_db.is_pruned(0));
+		assert!(!backend.storage.state_db.is_pruned(1));
+	}
+
+	#[test]
+	fn finalization_prunes_blocks_of_dead_forks() {
+		use client::blockchain::Backend as BlockchainBackend;
+
+		// block tree:
+		// 0 -> a1 -> a2
+		// 0 -> b1
+		let backend = Backend::<Block>::new_test(10, 10);
+		let block0 = insert_header(&backend, 0, Default::default(), vec![], Default::default());
+		let a1 = insert_header(&backend, 1, block0, vec![], Default::default());
+		let b1 = insert_header(&backend, 1, block0, vec![], H256::from([1; 32]));
+		let a2 = insert_header(&backend, 2, a1, vec![], Default::default());
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![a2, b1]);
+
+		backend.finalize_block(BlockId::Hash(a1)).unwrap();
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![a2]);
+		assert!(backend.blockchain().header(BlockId::Hash(b1)).unwrap().is_none());
+		assert!(backend.state_at(BlockId::Hash(b1)).is_err());
+		assert!(backend.blockchain().header(BlockId::Hash(a1)).unwrap().is_some());
+		assert!(backend.blockchain().header(BlockId::Hash(a2)).unwrap().is_some());
+	}
+
 }
@@ -19,8 +19,6 @@
 use std::sync::Arc;
 use parking_lot::RwLock;
 
-use kvdb::{KeyValueDB, DBTransaction};
-
 use client::backend::NewBlockState;
 use client::blockchain::{BlockStatus, Cache as BlockchainCache,
 	HeaderBackend as BlockchainHeaderBackend, Info as BlockchainInfo};
@@ -28,6 +26,7 @@ use client::{cht, LeafSet};
 use client::error::{ErrorKind as ClientErrorKind, Result as ClientResult};
 use client::light::blockchain::Storage as LightBlockchainStorage;
 use codec::{Decode, Encode};
+use database::{Database, Transaction};
 use primitives::{AuthorityId, Blake2Hasher};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT,
@@ -48,7 +47,7 @@ pub(crate) mod columns {
 /// Light blockchain storage. Stores most recent headers + CHTs for older headers.
 /// Locks order: meta, leaves, cache.
 pub struct LightStorage<Block: BlockT> {
-	db: Arc<KeyValueDB>,
+	db: Arc<Database>,
 	meta: RwLock<Meta<<<Block as BlockT>::Header as HeaderT>::Number, Block::Hash>>,
 	leaves: RwLock<LeafSet<Block::Hash, NumberFor<Block>>>,
 	cache: DbCacheSync<Block>,
@@ -70,21 +69,20 @@ impl<Block> LightStorage<Block>
 	pub fn new(config: DatabaseSettings) -> ClientResult<Self> {
 		let db = open_database(&config, columns::META, "light")?;
 
-		Self::from_kvdb(db as Arc<_>)
+		Self::from_database(db)
 	}
 
 	#[cfg(test)]
 	pub(crate) fn new_test() -> Self {
 		use utils::NUM_COLUMNS;
 
-		let db = Arc::new(::kvdb_memorydb::create(NUM_COLUMNS));
-
-		Self::from_kvdb(db as Arc<_>).expect("failed to create test-db")
+		Self::from_database(::database::in_memory(NUM_COLUMNS)).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>) -> ClientResult<Self> {
+	/// Create new storage on top of the given database.
+	pub fn from_database(db: Arc<Database>) -> ClientResult<Self> {
 		let meta = read_meta::<Block>(&*db, columns::META, columns::HEADER)?;
-		let leaves = LeafSet::read_from_db(&*db, columns::META, meta_keys::LEAF_PREFIX)?;
+		let leaves = LeafSet::read_from_db(db.iter_from_prefix(columns::META, meta_keys::LEAF_PREFIX), meta_keys::LEAF_PREFIX)?;
 		let cache = DbCache::new(
 			db.clone(),
 			columns::HASH_LOOKUP,
@@ -182,9 +180,23 @@ impl<Block> BlockchainHeaderBackend<Block> for LightStorage<Block>
 }
 
 impl<Block: BlockT> LightStorage<Block> {
+	fn is_descendent_of(
+		&self,
+		hash: Block::Hash,
+		ancestor: &Block::Hash,
+		ancestor_number: NumberFor<Block>,
+	) -> ClientResult<bool> {
+		::client::blockchain::is_descendent_of(
+			|h| self.expect_header(BlockId::Hash(h)),
+			hash,
+			ancestor,
+			ancestor_number,
+		)
+	}
+
 	fn note_finalized(
 		&self,
-		transaction: &mut DBTransaction,
+		transaction: &mut Transaction,
 		header: &Block::Header,
 		hash: Block::Hash,
 	) -> ClientResult<()> {
@@ -239,7 +251,7 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 		authorities: Option<Vec<AuthorityId>>,
 		leaf_state: NewBlockState,
 	) -> ClientResult<()> {
-		let mut transaction = DBTransaction::new();
+		let mut transaction = Transaction::new();
 
 		let hash = header.hash();
 		let number = *header.number();
@@ -325,6 +337,23 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 		{
 			let mut leaves = self.leaves.write();
 			let displaced_leaf = leaves.import(hash, number, parent_hash);
+			let finalization_displaced = if finalized {
+				match leaves.finalize(&hash, &number, |leaf| self.is_descendent_of(*leaf, &hash, number)) {
+					Ok(displaced) => Some(displaced),
+					Err(e) => {
+						if let Some(displaced_leaf) = displaced_leaf {
+							leaves.undo(displaced_leaf);
+						}
+						return Err(e);
+					},
+				}
+			} else {
+				None
+			};
+			leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			if let Some(ref finalization_displaced) = finalization_displaced {
+				finalization_displaced.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			}
 
 			let mut cache = self.cache.0.write();
 			let cache_ops = cache.transaction(&mut transaction)
@@ -336,10 +365,13 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 				)?
 				.into_ops();
 
-			debug!("Light DB Commit {:?} ({})", hash, number);
+			debug!(target: "db", "Light DB Commit {:?} ({})", hash, number);
 			let write_result = self.db.write(transaction).map_err(db_err);
 			if let Err(e) = write_result {
 				// revert leaves set update if there was one.
+				if let Some(finalization_displaced) = finalization_displaced {
+					leaves.undo_finalization(finalization_displaced);
+				}
 				if let Some(displaced_leaf) = displaced_leaf {
 					leaves.undo(displaced_leaf);
 				}
@@ -366,12 +398,13 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 
 	fn finalize_header(&self, id: BlockId<Block>) -> ClientResult<()> {
 		if let Some(header) = self.header(id)? {
-			let mut transaction = DBTransaction::new();
+			let mut transaction = Transaction::new();
 			// TODO: ensure best chain contains this block.
 			let hash = header.hash();
 			let number = *header.number();
 			self.note_finalized(&mut transaction, &header, hash.clone())?;
 			{
+				let mut leaves = self.leaves.write();
 				let mut cache = self.cache.0.write();
 				let cache_ops = cache.transaction(&mut transaction)
 					.on_block_finalize(
@@ -380,7 +413,14 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 					)?
 					.into_ops();
 
-				self.db.write(transaction).map_err(db_err)?;
+				let displaced = leaves.finalize(&hash, &number, |leaf| self.is_descendent_of(*leaf, &hash, number))?;
+				leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+				displaced.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+
+				if let Err(e) = self.db.write(transaction).map_err(db_err) {
+					leaves.undo_finalization(displaced);
+					return Err(e);
+				}
 				cache.commit(cache_ops);
 			}
 			self.update_meta(hash, header.number().clone(), false, true);
@@ -20,12 +20,11 @@
 use std::sync::Arc;
 use std::io;
 
-use kvdb::{KeyValueDB, DBTransaction};
-use kvdb_rocksdb::{Database, DatabaseConfig};
+use kvdb_rocksdb::{Database as RocksDb, DatabaseConfig};
 
 use client;
 use codec::Decode;
-use trie::DBValue;
+use database::{Database, Transaction, Value};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::{As, Block as BlockT, Header as HeaderT, Zero};
 use DatabaseSettings;
@@ -111,7 +110,7 @@ pub fn lookup_key_to_number<N>(key: &[u8]) -> client::error::Result<N> where N:
 /// block lookup key is the DB-key header, block and justification are stored under.
 /// looks up lookup key by hash from DB as necessary.
 pub fn block_id_to_lookup_key<Block>(
-	db: &KeyValueDB,
+	db: &Database,
 	hash_lookup_col: Option<u32>,
 	id: BlockId<Block>
 ) -> Result<Option<Vec<u8>>, client::error::Error> where
@@ -120,9 +119,7 @@ pub fn block_id_to_lookup_key<Block>(
 	match id {
 		// numbers are solely looked up in canonical chain
 		BlockId::Number(n) => Ok(Some(number_to_lookup_key(n).to_vec())),
-		BlockId::Hash(h) => db.get(hash_lookup_col, h.as_ref()).map(|v|
-			v.map(|v| { v.into_vec() })
-		).map_err(db_err),
+		BlockId::Hash(h) => db.get(hash_lookup_col, h.as_ref()).map_err(db_err),
 	}
 }
 
@@ -134,11 +131,11 @@ pub fn db_err(err: io::Error) -> client::error::Error {
 }
 
 /// Open RocksDB database.
-pub fn open_database(config: &DatabaseSettings, col_meta: Option<u32>, db_type: &str) -> client::error::Result<Arc<KeyValueDB>> {
+pub fn open_database(config: &DatabaseSettings, col_meta: Option<u32>, db_type: &str) -> client::error::Result<Arc<Database>> {
 	let mut db_config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
 	db_config.memory_budget = config.cache_size;
 	let path = config.path.to_str().ok_or_else(|| client::error::ErrorKind::Backend("Invalid database path".into()))?;
-	let db = Database::open(&db_config, &path).map_err(db_err)?;
+	let db: Arc<Database> = Arc::new(RocksDb::open(&db_config, &path).map_err(db_err)?);
 
 	// check database type
 	match db.get(col_meta, meta_keys::TYPE).map_err(db_err)? {
@@ -149,17 +146,17 @@ pub fn open_database(config: &DatabaseSettings, col_meta: Option<u32>, db_type:
 			}
 		},
 		None => {
-			let mut transaction = DBTransaction::new();
+			let mut transaction = Transaction::new();
 			transaction.put(col_meta, meta_keys::TYPE, db_type.as_bytes());
 			db.write(transaction).map_err(db_err)?;
 		},
 	}
 
-	Ok(Arc::new(db))
+	Ok(db)
 }
 
 /// Read database column entry for the given block.
-pub fn read_db<Block>(db: &KeyValueDB, col_index: Option<u32>, col: Option<u32>, id: BlockId<Block>) -> client::error::Result<Option<DBValue>>
+pub fn read_db<Block>(db: &Database, col_index: Option<u32>, col: Option<u32>, id: BlockId<Block>) -> client::error::Result<Option<Value>>
 	where
 		Block: BlockT,
 {
@@ -171,7 +168,7 @@ pub fn read_db<Block>(db: &KeyValueDB, col_index: Option<u32>, col: Option<u32>,
 
 /// Read a header from the database.
 pub fn read_header<Block: BlockT>(
-	db: &KeyValueDB,
+	db: &Database,
 	col_index: Option<u32>,
 	col: Option<u32>,
 	id: BlockId<Block>,
@@ -188,7 +185,7 @@ pub fn read_header<Block: BlockT>(
 }
 
 /// Read meta from the database.
-pub fn read_meta<Block>(db: &KeyValueDB, col_meta: Option<u32>, col_header: Option<u32>) -> Result<
+pub fn read_meta<Block>(db: &Database, col_meta: Option<u32>, col_header: Option<u32>) -> Result<
 	Meta<<<Block as BlockT>::Header as HeaderT>::Number, Block::Hash>,
 	client::error::Error,
 >
@@ -217,7 +214,7 @@ pub fn read_meta<Block>(db: &KeyValueDB, col_meta: Option<u32>, col_header: Opti
 			}).map_err(db_err)?
 		{
 			let hash = header.hash();
-			debug!("DB Opened blockchain db, fetched {} = {:?} ({})", desc, hash, header.number());
+			debug!(target: "db", "DB Opened blockchain db, fetched {} = {:?} ({})", desc, hash, header.number());
 			Ok((hash, *header.number()))
 		} else {
 			Ok((genesis_hash.clone(), Zero::zero()))
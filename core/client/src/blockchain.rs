@@ -17,7 +17,7 @@
 //! Substrate blockchain trait
 
 use primitives::AuthorityId;
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor, One};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::Justification;
 
@@ -158,6 +158,28 @@ impl<Block: BlockT> TreeRoute<Block> {
 	}
 }
 
+/// Check whether the block with given hash descends from the block `ancestor` at `ancestor_number`,
+/// walking back its parents with `load_header`.
+///
+/// The ancestor itself is never loaded, so it may be a block that is still being imported.
+pub fn is_descendent_of<H, F>(
+	load_header: F,
+	hash: H::Hash,
+	ancestor: &H::Hash,
+	ancestor_number: H::Number,
+) -> Result<bool> where
+	H: HeaderT,
+	F: Fn(H::Hash) -> Result<H>,
+{
+	let child_number = ancestor_number + One::one();
+	let mut header = load_header(hash)?;
+	while *header.number() > child_number {
+		header = load_header(*header.parent_hash())?;
+	}
+
+	Ok(*header.number() == child_number && header.parent_hash() == ancestor)
+}
+
 /// Compute a tree-route between two blocks. See tree-route docs for more details.
 pub fn tree_route<Block: BlockT, Backend: HeaderBackend<Block>>(
 	backend: &Backend,
@@ -28,15 +28,16 @@ use runtime_primitives::{
 };
 use consensus::{ImportBlock, ImportResult, BlockOrigin};
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, As, NumberFor, CurrentHeight, BlockNumberToHash};
-use runtime_primitives::{ApplyResult, BuildStorage};
+use runtime_primitives::{ApplyResult, BuildStorage, StorageMap};
 use runtime_api as api;
 use primitives::{Blake2Hasher, H256, ChangesTrieConfiguration};
 use primitives::storage::{StorageKey, StorageData};
 use primitives::storage::well_known_keys;
 use codec::{Encode, Decode};
+use substrate_telemetry::SUBSTRATE_INFO;
 use state_machine::{
 	Backend as StateBackend, CodeExecutor,
-	ExecutionStrategy, ExecutionManager, prove_read,
+	ExecutionStrategy, ExecutionManager, prove_read, prove_read_keys, prove_child_read,
 	key_changes, key_changes_proof, OverlayedChanges
 };
 
@@ -87,6 +88,14 @@ pub trait BlockchainEvents<Block: BlockT> {
 pub trait ChainHead<Block: BlockT> {
 	/// Get best block header.
 	fn best_block_header(&self) -> Result<<Block as BlockT>::Header, error::Error>;
+	/// Get the header of the last finalized block.
+	fn finalized_block_header(&self) -> Result<<Block as BlockT>::Header, error::Error>;
+}
+
+/// Fetch block header by ID.
+pub trait BlockHeader<Block: BlockT> {
+	/// Get block header by ID. Returns `None` if the block is unknown.
+	fn block_header(&self, id: &BlockId<Block>) -> error::Result<Option<<Block as BlockT>::Header>>;
 }
 
 /// Fetch block body by ID.
@@ -275,6 +284,34 @@ impl<B, E, Block> Client<B, E, Block> where
 			.map(StorageData))
 	}
 
+	/// Get up to `max` storage entries at a given block, in key order, starting from the `start`
+	/// key (inclusive). Returns the entries along with a flag which is set when there are no more
+	/// entries past the returned ones. Child storage entries are not included.
+	pub fn storage_entries(&self, id: &BlockId<Block>, start: &[u8], max: usize) -> error::Result<(Vec<(Vec<u8>, Vec<u8>)>, bool)> {
+		let state = self.state_at(id)?;
+		let mut keys = Vec::new();
+		let mut complete = true;
+		state.for_keys_from(start, |key| {
+			if well_known_keys::is_child_storage_key(key) {
+				return true;
+			}
+			if keys.len() == max {
+				complete = false;
+				return false;
+			}
+			keys.push(key.to_vec());
+			true
+		});
+
+		let mut entries = Vec::with_capacity(keys.len());
+		for key in keys {
+			if let Some(value) = state.storage(&key).map_err(|e| error::Error::from_state(Box::new(e)))? {
+				entries.push((key, value));
+			}
+		}
+		Ok((entries, complete))
+	}
+
 	/// Get the code at a given block.
 	pub fn code_at(&self, id: &BlockId<Block>) -> error::Result<Vec<u8>> {
 		Ok(self.storage(id, &StorageKey(well_known_keys::CODE.to_vec()))?
@@ -310,6 +347,23 @@ impl<B, E, Block> Client<B, E, Block> where
 				.map_err(Into::into))
 	}
 
+	/// Reads storage values at a given block + keys, returning single read proof
+	/// that covers all of the keys.
+	pub fn read_proof_keys(&self, id: &BlockId<Block>, keys: &[Vec<u8>]) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| prove_read_keys(state, keys)
+				.map_err(Into::into))
+	}
+
+	/// Reads child storage value at a given block + storage_key + key, returning
+	/// read proof.
+	pub fn read_child_proof(&self, id: &BlockId<Block>, storage_key: &[u8], key: &[u8]) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| prove_child_read(state, storage_key, key)
+				.map(|(_, proof)| proof)
+				.map_err(Into::into))
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
@@ -329,6 +383,12 @@ impl<B, E, Block> Client<B, E, Block> where
 		let header = self.header(id)?.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{:?}", id)))?;
 		let block_num = *header.number();
 		let cht_num = cht::block_to_cht_number(cht_size, block_num).ok_or_else(proof_error)?;
+		// light clients only know roots of CHTs that are built from finalized blocks
+		let cht_end = cht::end_number(cht_size, cht_num);
+		if cht_end > self.backend.blockchain().info()?.finalized_number {
+			return Err(proof_error().into());
+		}
+
 		let cht_start = cht::start_number(cht_size, cht_num);
 		let headers = (cht_start.as_()..).map(|num| self.block_hash(As::sa(num)).unwrap_or_default());
 		let proof = cht::build_proof::<Block::Header, Blake2Hasher, _>(cht_size, cht_num, block_num, headers)
@@ -548,7 +608,7 @@ impl<B, E, Block> Client<B, E, Block> where
 							warn!("   Header {:?}", header);
 							warn!("   Native result {:?}", native_result);
 							warn!("   Wasm result {:?}", wasm_result);
-							telemetry!("block.execute.consensus_failure";
+							telemetry!(SUBSTRATE_INFO; "block.execute.consensus_failure";
 								"hash" => ?hash,
 								"origin" => ?origin,
 								"header" => ?header
@@ -559,6 +619,15 @@ impl<B, E, Block> Client<B, E, Block> where
 				);
 				let (_, storage_update, changes_update) = r?;
 				overlay.commit_prospective();
+				if overlay.storage(well_known_keys::CODE).is_some() {
+					// the native runtime is only used again for blocks whose on-chain version
+					// it matches, which the executor checks against the new code.
+					info!("Runtime code updated at block #{} ({})", import_headers.post().number(), hash);
+					telemetry!(SUBSTRATE_INFO; "block.runtime_updated";
+						"hash" => ?hash,
+						"number" => ?import_headers.post().number()
+					);
+				}
 				(Some(storage_update), Some(changes_update), Some(overlay.into_committed()))
 			},
 			None => (None, None, None)
@@ -574,7 +643,7 @@ impl<B, E, Block> Client<B, E, Block> where
 			::backend::NewBlockState::Normal
 		};
 
-		trace!("Imported {}, (#{}), best={}, origin={:?}", hash, import_headers.post().number(), is_new_best, origin);
+		trace!(target: "client", "Imported {}, (#{}), best={}, origin={:?}", hash, import_headers.post().number(), is_new_best, origin);
 
 		transaction.set_block_data(
 			import_headers.post().clone(),
@@ -626,6 +695,119 @@ impl<B, E, Block> Client<B, E, Block> where
 		Ok(ImportResult::Queued)
 	}
 
+	/// Record the number of the block which state is downloaded by fast sync, so that fast sync
+	/// is resumed on restart: the headers imported before it have no state.
+	pub fn set_fast_sync_target(&self, number: NumberFor<Block>) -> error::Result<()> {
+		self.backend.insert_aux(&[(FAST_SYNC_TARGET_KEY, &number.encode()[..])], &[])
+	}
+
+	/// The number of the block which state is downloaded by an unfinished fast sync, if any.
+	pub fn fast_sync_target(&self) -> error::Result<Option<NumberFor<Block>>> {
+		let target = self.backend.get_aux(FAST_SYNC_TARGET_KEY)?
+			.and_then(|target| NumberFor::<Block>::decode(&mut &target[..]));
+		let best_number = self.backend.blockchain().info()?.best_number;
+		Ok(target.filter(|target| *target > best_number))
+	}
+
+	/// Import a block header without executing the block. No state is stored for the block.
+	///
+	/// Used by fast sync for the part of the chain preceding the block which state is downloaded,
+	/// which must have been recorded with `set_fast_sync_target`. The header must be a child of
+	/// the best block: it is only linked to the chain by its parent hash, and becomes final along
+	/// with the fast sync target. A justification, if any, must have been checked by the caller.
+	pub fn import_header(&self, header: Block::Header, justification: Option<Justification>) -> error::Result<ImportResult> {
+		let parent_hash = header.parent_hash().clone();
+		let hash = header.hash();
+		let _import_lock = self.import_lock.lock();
+		match self.fast_sync_target()? {
+			Some(ref target) if header.number() < target => {},
+			_ => return Err(error::ErrorKind::Backend(format!("Header {} is not below the fast sync target", hash)).into()),
+		}
+		match self.backend.blockchain().status(BlockId::Hash(hash))? {
+			blockchain::BlockStatus::InChain => return Ok(ImportResult::AlreadyInChain),
+			blockchain::BlockStatus::Unknown => {},
+		}
+		match self.backend.blockchain().status(BlockId::Hash(parent_hash))? {
+			blockchain::BlockStatus::InChain => {},
+			blockchain::BlockStatus::Unknown => return Ok(ImportResult::UnknownParent),
+		}
+		if self.backend.blockchain().info()?.best_hash != parent_hash {
+			return Ok(ImportResult::UnknownParent);
+		}
+
+		let mut transaction = self.backend.begin_operation(BlockId::Hash(parent_hash))?;
+		transaction.set_block_data(header, None, justification, backend::NewBlockState::Best)?;
+		self.backend.commit_operation(transaction)?;
+		Ok(ImportResult::Queued)
+	}
+
+	/// Store a chunk of the state of a block that is going to be imported with
+	/// `import_block_with_state`. Chunks are kept in the auxiliary storage, by index, until then.
+	pub fn import_state_chunk(&self, block: &Block::Hash, index: u32, entries: Vec<(Vec<u8>, Vec<u8>)>) -> error::Result<()> {
+		let key = state_chunk_key(block, index);
+		self.backend.insert_aux(&[(&key[..], &entries.encode()[..])], &[])
+	}
+
+	/// Import a block along with its complete state, made of the first `chunks` chunks stored with
+	/// `import_state_chunk`, without executing it. The state is checked against the state root of
+	/// the header instead. The block and all of its ancestors are finalized.
+	///
+	/// Used by fast sync. The parent header must be the best block, but its state need not be
+	/// available. The stored chunks are removed, whether the import succeeds or not.
+	pub fn import_block_with_state(
+		&self,
+		header: Block::Header,
+		body: Option<Vec<Block::Extrinsic>>,
+		justification: Justification,
+		chunks: u32,
+	) -> error::Result<ImportResult> {
+		let parent_hash = header.parent_hash().clone();
+		let hash = header.hash();
+		let _import_lock = self.import_lock.lock();
+		match self.backend.blockchain().status(BlockId::Hash(hash))? {
+			blockchain::BlockStatus::InChain => return Ok(ImportResult::AlreadyInChain),
+			blockchain::BlockStatus::Unknown => {},
+		}
+		match self.backend.blockchain().status(BlockId::Hash(parent_hash))? {
+			blockchain::BlockStatus::InChain => {},
+			blockchain::BlockStatus::Unknown => return Ok(ImportResult::UnknownParent),
+		}
+		if self.backend.blockchain().info()?.best_hash != parent_hash {
+			return Ok(ImportResult::UnknownParent);
+		}
+
+		let chunk_keys: Vec<_> = (0..chunks).map(|index| state_chunk_key(&hash, index)).collect();
+		let mut state = StorageMap::new();
+		for key in &chunk_keys {
+			let entries = self.backend.get_aux(key)?
+				.and_then(|chunk| Vec::<(Vec<u8>, Vec<u8>)>::decode(&mut &chunk[..]));
+			match entries {
+				Some(entries) => state.extend(entries),
+				None => {
+					self.remove_state_chunks(&chunk_keys)?;
+					return Err(error::ErrorKind::Backend(format!("Missing state chunk of block {}", hash)).into());
+				},
+			}
+		}
+		self.remove_state_chunks(&chunk_keys)?;
+
+		let mut transaction = self.backend.begin_operation(BlockId::Hash(parent_hash))?;
+		let state_root = transaction.reset_storage(state, Default::default())?;
+		if state_root != *header.state_root() {
+			return Err(error::ErrorKind::InvalidStateRoot(format!("{}", hash)).into());
+		}
+		transaction.set_block_data(header, body, Some(justification), backend::NewBlockState::Best)?;
+		self.backend.commit_operation(transaction)?;
+		self.backend.insert_aux(&[], &[FAST_SYNC_TARGET_KEY])?;
+		self.apply_finality(hash, hash, true)?;
+		Ok(ImportResult::Queued)
+	}
+
+	fn remove_state_chunks(&self, keys: &[Vec<u8>]) -> error::Result<()> {
+		let keys: Vec<&[u8]> = keys.iter().map(|key| &key[..]).collect();
+		self.backend.insert_aux(&[], &keys)
+	}
+
 	/// Finalizes all blocks up to given.
 	fn apply_finality(&self, block: Block::Hash, best_block: Block::Hash, notify: bool) -> error::Result<()> {
 		// find tree route from last finalized to given block.
@@ -662,6 +844,14 @@ impl<B, E, Block> Client<B, E, Block> where
 			self.backend.finalize_block(BlockId::Hash(finalize_new.hash))?;
 		}
 
+		if let Some(finalized) = route_from_finalized.enacted().last() {
+			let height: u64 = finalized.number.as_();
+			telemetry!(SUBSTRATE_INFO; "notify.finalized";
+				"height" => height,
+				"best" => ?finalized.hash
+			);
+		}
+
 		if notify {
 			// sometimes when syncing, tons of blocks can be finalized at once.
 			// we'll send notifications spuriously in that case.
@@ -736,6 +926,11 @@ impl<B, E, Block> Client<B, E, Block> where
 		self.backend.blockchain().hash(block_number)
 	}
 
+	/// Compute a tree route between two blocks. See `TreeRoute` docs for more details.
+	pub fn tree_route(&self, from: &BlockId<Block>, to: &BlockId<Block>) -> error::Result<::blockchain::TreeRoute<Block>> {
+		::blockchain::tree_route(self.backend.blockchain(), *from, *to)
+	}
+
 	/// Convert an arbitrary block ID into a block hash.
 	pub fn block_hash_from_id(&self, id: &BlockId<Block>) -> error::Result<Option<Block::Hash>> {
 		match *id {
@@ -891,6 +1086,17 @@ impl<B, E, Block> Client<B, E, Block> where
 }
 
 
+/// Auxiliary storage key of the number of the block which state is downloaded by fast sync.
+const FAST_SYNC_TARGET_KEY: &[u8] = b"fast_sync_target";
+
+/// Auxiliary storage key of a chunk of the state of a block imported by fast sync.
+fn state_chunk_key<H: Encode>(block: &H, index: u32) -> Vec<u8> {
+	let mut key = b"fast_sync_state:".to_vec();
+	block.encode_to(&mut key);
+	index.encode_to(&mut key);
+	key
+}
+
 impl<B, E, Block> consensus::BlockImport<Block> for Client<B, E, Block> where
 	B: backend::Backend<Block, Blake2Hasher>,
 	E: CallExecutor<Block, Blake2Hasher> + Clone,
@@ -948,7 +1154,7 @@ impl<B, E, Block> consensus::BlockImport<Block> for Client<B, E, Block> where
 		);
 
 		*self.importing_block.write() = None;
-		telemetry!("block.import";
+		telemetry!(SUBSTRATE_INFO; "block.import";
 			"height" => height,
 			"best" => ?hash,
 			"origin" => ?origin
@@ -1025,6 +1231,21 @@ where
 	fn best_block_header(&self) -> error::Result<<Block as BlockT>::Header> {
 		Client::best_block_header(self)
 	}
+
+	fn finalized_block_header(&self) -> error::Result<<Block as BlockT>::Header> {
+		let finalized_hash = self.backend.blockchain().last_finalized()?;
+		Ok(self.header(&BlockId::Hash(finalized_hash))?.expect("Finalized block header must always exist"))
+	}
+}
+
+impl<B, E, Block> BlockHeader<Block> for Client<B, E, Block> where
+	B: backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	Block: BlockT<Hash=H256>,
+{
+	fn block_header(&self, id: &BlockId<Block>) -> error::Result<Option<<Block as BlockT>::Header>> {
+		self.header(id)
+	}
 }
 
 impl<B, E, Block> BlockBody<Block> for Client<B, E, Block> where
@@ -1137,6 +1358,18 @@ impl<B, E, Block> api::TaggedTransactionQueue<Block> for Client<B, E, Block> whe
 	}
 }
 
+impl<B, E, Block> api::OffchainWorker<Block> for Client<B, E, Block> where
+	B: backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	Block: BlockT<Hash=H256>,
+{
+	type Error = Error;
+
+	fn offchain_worker(&self, at: &BlockId<Block>, number: &NumberFor<Block>) -> Result<(), Self::Error> {
+		self.call_api_at(at, "offchain_worker", &(number))
+	}
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
 	use std::collections::HashMap;
@@ -1276,6 +1509,69 @@ pub(crate) mod tests {
 		assert_eq!(client.call_api::<_, u64>("balance_of", &Keyring::Ferdie.to_raw_public()).unwrap(), 42);
 	}
 
+	#[test]
+	fn block_with_state_import_works() {
+		let source = test_client::new();
+		let mut builder = source.new_block().unwrap();
+		builder.push_transfer(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let block1 = builder.bake().unwrap();
+		source.justify_and_import(BlockOrigin::Own, block1.clone()).unwrap();
+		let block2 = source.new_block().unwrap().bake().unwrap();
+		source.justify_and_import(BlockOrigin::Own, block2.clone()).unwrap();
+
+		let client = test_client::new();
+		// stores the state of block2 in chunks of 3 entries, without its last entry if `truncate`.
+		let import_state_chunks = |truncate: bool| {
+			let mut chunks = 0;
+			let mut start = Vec::new();
+			loop {
+				let (mut entries, complete) = source.storage_entries(&BlockId::Number(2), &start, 3).unwrap();
+				assert!(entries.len() <= 3);
+				if let Some(&(ref key, _)) = entries.last() {
+					start = key.clone();
+					start.push(0);
+				}
+				if complete && truncate {
+					entries.pop();
+				}
+				client.import_state_chunk(&block2.header.hash(), chunks, entries).unwrap();
+				chunks += 1;
+				if complete {
+					return chunks;
+				}
+			}
+		};
+
+		let justification = vec![1, 2, 3];
+		// headers are only imported below a recorded fast sync target.
+		assert!(client.import_header(block1.header.clone(), None).is_err());
+		client.set_fast_sync_target(2).unwrap();
+		// headers are only linked to the chain by their parent hash.
+		client.import_header(block1.header.clone(), None).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+		assert_eq!(client.info().unwrap().chain.finalized_number, 0);
+		// the target outlives the import of the headers, so that fast sync resumes on restart.
+		assert_eq!(client.fast_sync_target().unwrap(), Some(2));
+
+		let chunks = import_state_chunks(true);
+		assert!(client.import_block_with_state(block2.header.clone(), Some(block2.extrinsics.clone()), justification.clone(), chunks).is_err());
+		// the chunks are gone after a failed import.
+		assert!(client.import_block_with_state(block2.header.clone(), Some(block2.extrinsics.clone()), justification.clone(), chunks).is_err());
+		let chunks = import_state_chunks(false);
+		client.import_block_with_state(block2.header.clone(), Some(block2.extrinsics.clone()), justification, chunks).unwrap();
+
+		// the fast sync target finalizes the headers it was synced over.
+		assert_eq!(client.info().unwrap().chain.finalized_number, 2);
+		assert_eq!(client.info().unwrap().chain.best_number, 2);
+		assert_eq!(client.fast_sync_target().unwrap(), None);
+		assert_eq!(client.call_api::<_, u64>("balance_of", &Keyring::Ferdie.to_raw_public()).unwrap(), 42);
+	}
+
 	#[test]
 	fn client_uses_authorities_from_blockchain_cache() {
 		let client = test_client::new();
@@ -1575,6 +1871,45 @@ pub(crate) mod tests {
 		assert_eq!(None, client.best_containing(d2.hash().clone(), Some(0)).unwrap());
 	}
 
+	#[test]
+	fn finality_prunes_leaves_of_dead_forks() {
+		use test_client::blockchain::Backend as BlockchainBackendT;
+
+		// block tree:
+		// G -> A1 -> A2 -> A3
+		//      A1 -> B2
+		let client = test_client::new();
+
+		let a1 = client.new_block().unwrap().bake().unwrap();
+		client.justify_and_import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash())).unwrap().bake().unwrap();
+		client.justify_and_import(BlockOrigin::Own, a2.clone()).unwrap();
+
+		let a3 = client.new_block_at(&BlockId::Hash(a2.hash())).unwrap().bake().unwrap();
+		client.justify_and_import(BlockOrigin::Own, a3.clone()).unwrap();
+
+		let mut builder = client.new_block_at(&BlockId::Hash(a1.hash())).unwrap();
+		// this push is required as otherwise B2 has the same hash as A2 and won't get imported
+		builder.push_transfer(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 41,
+			nonce: 0,
+		}).unwrap();
+		let b2 = builder.bake().unwrap();
+		client.justify_and_import(BlockOrigin::Own, b2.clone()).unwrap();
+
+		assert_eq!(client.backend().blockchain().leaves().unwrap(), vec![a3.hash(), b2.hash()]);
+
+		client.finalize_block(BlockId::Hash(a1.hash()), true).unwrap();
+		assert_eq!(client.backend().blockchain().leaves().unwrap(), vec![a3.hash(), b2.hash()]);
+
+		client.finalize_block(BlockId::Hash(a2.hash()), true).unwrap();
+		assert_eq!(client.backend().blockchain().leaves().unwrap(), vec![a3.hash()]);
+		assert_eq!(client.info().unwrap().chain.finalized_hash, a2.hash());
+	}
+
 	#[test]
 	fn key_changes_works() {
 		let (client, _, test_cases) = prepare_client_with_key_changes();
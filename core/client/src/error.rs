@@ -135,6 +135,12 @@ error_chain! {
 			description("Potential long-range attack: block not in finalized chain."),
 			display("Potential long-range attack: block not in finalized chain."),
 		}
+
+		/// Imported state does not match the state root of the block header.
+		InvalidStateRoot(h: String) {
+			description("invalid state root"),
+			display("State does not match the state root of block {}", &*h),
+		}
 	}
 }
 
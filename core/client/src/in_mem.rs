@@ -98,6 +98,21 @@ struct BlockchainStorage<Block: BlockT> {
 	leaves: LeafSet<Block::Hash, NumberFor<Block>>,
 }
 
+impl<Block: BlockT> BlockchainStorage<Block> {
+	/// Remove the leaves of the forks that can never be finalized after the given block is.
+	fn finalize_leaves(&mut self, hash: &Block::Hash, number: &NumberFor<Block>) -> error::Result<()> {
+		let blocks = &self.blocks;
+		self.leaves.finalize(hash, number, |leaf| blockchain::is_descendent_of(
+			|h| blocks.get(&h).map(|block| block.header().clone())
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{:?}", h)).into()),
+			*leaf,
+			hash,
+			*number,
+		))?;
+		Ok(())
+	}
+}
+
 /// In-memory blockchain. Supports concurrent reads.
 pub struct Blockchain<Block: BlockT> {
 	storage: Arc<RwLock<BlockchainStorage<Block>>>,
@@ -207,6 +222,7 @@ impl<Block: BlockT> Blockchain<Block> {
 		storage.blocks.insert(hash.clone(), StoredBlock::new(header, body, justification));
 
 		if let NewBlockState::Final = new_state {
+			storage.finalize_leaves(&hash, &number)?;
 			storage.finalized_hash = hash;
 			storage.finalized_number = number.clone();
 		}
@@ -239,12 +255,16 @@ impl<Block: BlockT> Blockchain<Block> {
 	}
 
 	fn finalize_header(&self, id: BlockId<Block>) -> error::Result<()> {
-		let hash = match self.header(id)? {
-			Some(h) => h.hash(),
+		let header = match self.header(id)? {
+			Some(h) => h,
 			None => return Err(error::ErrorKind::UnknownBlock(format!("{}", id)).into()),
 		};
 
-		self.storage.write().finalized_hash = hash;
+		let hash = header.hash();
+		let mut storage = self.storage.write();
+		storage.finalize_leaves(&hash, header.number())?;
+		storage.finalized_hash = hash;
+		storage.finalized_number = *header.number();
 		Ok(())
 	}
 }
@@ -405,6 +425,8 @@ where
 			return Err(error::ErrorKind::GenesisInvalid.into());
 		}
 
+		// the storage is built from an empty trie rather than on top of the previous state.
+		let empty_state = InMemory::<H>::default();
 		let mut transaction: Vec<(Option<Vec<u8>>, Vec<u8>, Option<Vec<u8>>)> = Default::default();
 
 		for (child_key, child_map) in children {
@@ -412,7 +434,7 @@ where
 				return Err(error::ErrorKind::GenesisInvalid.into());
 			}
 
-			let (root, is_default, update) = self.old_state.child_storage_root(&child_key, child_map.into_iter().map(|(k, v)| (k, Some(v))));
+			let (root, is_default, update) = empty_state.child_storage_root(&child_key, child_map.into_iter().map(|(k, v)| (k, Some(v))));
 			transaction.consolidate(update);
 
 			if !is_default {
@@ -420,7 +442,7 @@ where
 			}
 		}
 
-		let (root, update) = self.old_state.storage_root(top.into_iter().map(|(k, v)| (k, Some(v))));
+		let (root, update) = empty_state.storage_root(top.into_iter().map(|(k, v)| (k, Some(v))));
 		transaction.consolidate(update);
 
 		self.new_state = Some(InMemory::from(transaction));
@@ -16,7 +16,6 @@
 
 use std::collections::BTreeSet;
 use std::cmp::{Ord, Ordering};
-use kvdb::{KeyValueDB, DBTransaction};
 use runtime_primitives::traits::SimpleArithmetic;
 use codec::{Encode, Decode};
 use error;
@@ -52,12 +51,59 @@ impl<H, N> PartialEq for LeafSetItem<H, N> where N: PartialEq {
 
 impl<H, N> Eq for LeafSetItem<H, N> where N: PartialEq {}
 
+/// A database transaction the leaf set is written to.
+pub trait LeafSetTransaction {
+	/// Set the value under the key in the column.
+	fn put_vec(&mut self, column: Option<u32>, key: &[u8], value: Vec<u8>);
+	/// Remove the value under the key in the column.
+	fn delete(&mut self, column: Option<u32>, key: &[u8]);
+}
+
 /// A displaced leaf after import.
 pub struct DisplacedLeaf<H, N> {
 	new_hash: H,
 	displaced: LeafSetItem<H, N>,
 }
 
+/// A leaf removed by reverting its block, together with its parent which became a leaf.
+pub struct RevertedLeaf<H, N> {
+	reverted: LeafSetItem<H, N>,
+	parent: LeafSetItem<H, N>,
+}
+
+impl<H: Encode, N> RevertedLeaf<H, N> {
+	/// Remove the reverted leaf from the database transaction.
+	pub fn prepare_transaction<T: LeafSetTransaction>(&self, tx: &mut T, column: Option<u32>, prefix: &[u8]) {
+		let mut buf = prefix.to_vec();
+		self.reverted.hash.using_encoded(|s| buf.extend(s));
+		tx.delete(column, &buf[..]);
+	}
+}
+
+/// Leaves displaced on finalization.
+pub struct FinalizationDisplaced<H, N> {
+	leaves: Vec<LeafSetItem<H, N>>,
+}
+
+impl<H, N> FinalizationDisplaced<H, N> {
+	/// The hashes of the displaced leaves.
+	pub fn leaves(&self) -> impl Iterator<Item=&H> {
+		self.leaves.iter().map(|item| &item.hash)
+	}
+}
+
+impl<H: Encode, N> FinalizationDisplaced<H, N> {
+	/// Remove the displaced leaves from the database transaction.
+	pub fn prepare_transaction<T: LeafSetTransaction>(&self, tx: &mut T, column: Option<u32>, prefix: &[u8]) {
+		let mut buf = prefix.to_vec();
+		for &LeafSetItem { ref hash, .. } in &self.leaves {
+			hash.using_encoded(|s| buf.extend(s));
+			tx.delete(column, &buf[..]);
+			buf.truncate(prefix.len()); // reuse allocation.
+		}
+	}
+}
+
 /// list of leaf hashes ordered by number (descending).
 /// stored in memory for fast access.
 /// this allows very fast checking and modification of active leaves.
@@ -77,11 +123,15 @@ impl<H, N> LeafSet<H, N> where
 		}
 	}
 
-	/// Read the leaf list from the DB, using given prefix for keys.
-	pub fn read_from_db(db: &KeyValueDB, column: Option<u32>, prefix: &[u8]) -> error::Result<Self> {
+	/// Read the leaf list from the DB entries, starting from the first key with given prefix.
+	///
+	/// Reading stops at the first key without the prefix.
+	pub fn read_from_db<I>(entries: I, prefix: &[u8]) -> error::Result<Self> where
+		I: IntoIterator<Item=(Box<[u8]>, Box<[u8]>)>,
+	{
 		let mut storage = BTreeSet::new();
 
-		for (key, value) in db.iter_from_prefix(column, prefix) {
+		for (key, value) in entries {
 			if !key.starts_with(prefix) { break }
 			let raw_hash = &mut &key[prefix.len()..];
 			let hash = match Decode::decode(raw_hash) {
@@ -133,12 +183,53 @@ impl<H, N> LeafSet<H, N> where
 	/// currently since revert only affects the canonical chain
 	/// we assume that parent has no further children
 	/// and we add it as leaf again
-	pub fn revert(&mut self, hash: H, number: N, parent_hash: H) {
-		self.storage.insert(LeafSetItem {
+	pub fn revert(&mut self, hash: H, number: N, parent_hash: H) -> RevertedLeaf<H, N> {
+		let parent = LeafSetItem {
 			hash: parent_hash,
 			number: number.clone() - N::one(),
-		});
-		self.storage.remove(&LeafSetItem { hash, number });
+		};
+		let reverted = LeafSetItem { hash, number };
+
+		self.storage.remove(&reverted);
+		self.storage.insert(parent.clone());
+		RevertedLeaf { reverted, parent }
+	}
+
+	/// Undo a revert operation.
+	pub fn undo_revert(&mut self, reverted: RevertedLeaf<H, N>) {
+		self.storage.remove(&reverted.parent);
+		self.storage.insert(reverted.reverted);
+	}
+
+	/// note a block as finalized. all leaves at or below the finalized block's number,
+	/// except the finalized block itself, and all higher leaves that don't descend from it,
+	/// as told by `is_descendent_of`, belong to forks that can never be finalized
+	/// and are removed from the set.
+	pub fn finalize<F>(&mut self, hash: &H, number: &N, is_descendent_of: F) -> error::Result<FinalizationDisplaced<H, N>> where
+		H: PartialEq,
+		F: Fn(&H) -> error::Result<bool>,
+	{
+		let mut displaced = Vec::new();
+		for item in &self.storage {
+			let is_dead = if item.number <= *number {
+				item.hash != *hash
+			} else {
+				!is_descendent_of(&item.hash)?
+			};
+			if is_dead {
+				displaced.push(item.clone());
+			}
+		}
+
+		for item in &displaced {
+			self.storage.remove(item);
+		}
+		Ok(FinalizationDisplaced { leaves: displaced })
+	}
+
+	/// Undo a finalization operation, restoring the displaced leaves.
+	pub fn undo_finalization(&mut self, displaced: FinalizationDisplaced<H, N>) {
+		self.storage.extend(displaced.leaves);
 	}
 
 	/// returns an iterator over all hashes in the leaf set
@@ -148,7 +239,7 @@ impl<H, N> LeafSet<H, N> where
 	}
 
 	/// Write the leaf list to the database transaction.
-	pub fn prepare_transaction(&self, tx: &mut DBTransaction, column: Option<u32>, prefix: &[u8]) {
+	pub fn prepare_transaction<T: LeafSetTransaction>(&self, tx: &mut T, column: Option<u32>, prefix: &[u8]) {
 		let mut buf = prefix.to_vec();
 		for &LeafSetItem { ref hash, ref number } in &self.storage {
 			hash.using_encoded(|s| buf.extend(s));
@@ -161,6 +252,17 @@ impl<H, N> LeafSet<H, N> where
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use kvdb::{KeyValueDB, DBTransaction};
+
+	impl LeafSetTransaction for DBTransaction {
+		fn put_vec(&mut self, column: Option<u32>, key: &[u8], value: Vec<u8>) {
+			DBTransaction::put_vec(self, column, key, value)
+		}
+
+		fn delete(&mut self, column: Option<u32>, key: &[u8]) {
+			DBTransaction::delete(self, column, key)
+		}
+	}
 
 	#[test]
 	fn it_works() {
@@ -199,7 +301,59 @@ mod tests {
 		set.prepare_transaction(&mut tx, None, PREFIX);
 		db.write(tx).unwrap();
 
-		let set2 = LeafSet::read_from_db(&db, None, PREFIX).unwrap();
+		let set2 = LeafSet::read_from_db(db.iter_from_prefix(None, PREFIX), PREFIX).unwrap();
+		assert_eq!(set, set2);
+	}
+
+	#[test]
+	fn finalization_prunes_dead_forks() {
+		const PREFIX: &[u8] = b"abcdefg";
+		let db = ::kvdb_memorydb::create(0);
+
+		let mut set = LeafSet::new();
+		set.import(0u32, 0u32, 0u32);
+
+		set.import(1_1, 1, 0);
+		set.import(2_1, 2, 1_1);
+		set.import(3_1, 3, 2_1);
+		set.import(1_2, 1, 0);
+
+		let mut tx = DBTransaction::new();
+		set.prepare_transaction(&mut tx, None, PREFIX);
+		db.write(tx).unwrap();
+
+		let displaced = set.finalize(&2_1, &2, |_| Ok(true)).unwrap();
+		assert_eq!(set.hashes(), vec![3_1]);
+
+		set.undo_finalization(displaced);
+		assert_eq!(set.hashes(), vec![3_1, 1_2]);
+
+		let displaced = set.finalize(&2_1, &2, |_| Ok(true)).unwrap();
+		let mut tx = DBTransaction::new();
+		set.prepare_transaction(&mut tx, None, PREFIX);
+		displaced.prepare_transaction(&mut tx, None, PREFIX);
+		db.write(tx).unwrap();
+
+		let set2 = LeafSet::read_from_db(db.iter_from_prefix(None, PREFIX), PREFIX).unwrap();
 		assert_eq!(set, set2);
 	}
+
+	#[test]
+	fn finalization_prunes_taller_dead_forks() {
+		let mut set = LeafSet::new();
+		set.import(0u32, 0u32, 0u32);
+
+		set.import(1_2, 1, 0);
+		set.import(2_2, 2, 1_2);
+		set.import(3_2, 3, 2_2);
+		set.import(1_1, 1, 0);
+		set.import(2_1, 2, 1_1);
+
+		let displaced = set.finalize(&1_1, &1, |hash| Ok(*hash == 2_1)).unwrap();
+		assert_eq!(set.hashes(), vec![2_1]);
+		assert_eq!(displaced.leaves().cloned().collect::<Vec<_>>(), vec![3_2]);
+
+		set.undo_finalization(displaced);
+		assert_eq!(set.hashes(), vec![3_2, 2_1]);
+	}
 }
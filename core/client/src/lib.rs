@@ -37,13 +37,13 @@ extern crate futures;
 extern crate parking_lot;
 extern crate hash_db;
 extern crate heapsize;
-extern crate kvdb;
 extern crate sr_api;
 
 #[macro_use] extern crate error_chain;
 #[macro_use] extern crate log;
 #[cfg_attr(test, macro_use)] extern crate substrate_executor as executor;
 #[cfg(test)] #[macro_use] extern crate hex_literal;
+#[cfg(test)] extern crate kvdb;
 #[cfg(test)] extern crate kvdb_memorydb;
 
 pub mod error;
@@ -64,12 +64,12 @@ pub use call_executor::{CallResult, CallExecutor, LocalCallExecutor};
 pub use client::{
 	new_with_backend,
 	new_in_mem,
-	BlockBody, BlockStatus, ImportNotifications, FinalityNotifications, BlockchainEvents,
-	Client, ClientInfo, ChainHead,
+	BlockBody, BlockHeader, BlockStatus, ImportNotifications, FinalityNotifications, BlockchainEvents,
+	BlockImportNotification, FinalityNotification, Client, ClientInfo, ChainHead,
 };
 pub use notifications::{StorageEventStream, StorageChangeSet};
 pub use state_machine::ExecutionStrategy;
-pub use leaves::LeafSet;
+pub use leaves::{LeafSet, LeafSetTransaction, FinalizationDisplaced};
 
 /// Traits for interfacing with the runtime from the client.
 pub mod runtime_api {
@@ -31,7 +31,7 @@ use backend::{Backend as ClientBackend, BlockImportOperation, RemoteBackend, New
 use blockchain::HeaderBackend as BlockchainHeaderBackend;
 use error::{Error as ClientError, ErrorKind as ClientErrorKind, Result as ClientResult};
 use light::blockchain::{Blockchain, Storage as BlockchainStorage};
-use light::fetcher::{Fetcher, RemoteReadRequest};
+use light::fetcher::{Fetcher, RemoteReadRequest, RemoteReadChildRequest};
 use hash_db::Hasher;
 use trie::MemoryDB;
 use heapsize::HeapSizeOf;
@@ -195,6 +195,27 @@ where
 	}
 }
 
+impl<Block, S, F> OnDemandState<Block, S, F>
+where
+	Block: BlockT,
+	S: BlockchainStorage<Block>,
+	F: Fetcher<Block>,
+{
+	/// Get header of the block this state is at, reading it from the local blockchain
+	/// on first access.
+	fn header(&self) -> ClientResult<Block::Header> {
+		if let Some(header) = self.cached_header.read().clone() {
+			return Ok(header);
+		}
+
+		let header = self.blockchain.upgrade()
+			.ok_or_else(|| ClientErrorKind::UnknownBlock(format!("{}", self.block)).into())
+			.and_then(|blockchain| blockchain.expect_header(BlockId::Hash(self.block)))?;
+		*self.cached_header.write() = Some(header.clone());
+		Ok(header)
+	}
+}
+
 impl<Block, S, F, H> StateBackend<H> for OnDemandState<Block, S, F>
 where
 	Block: BlockT,
@@ -207,27 +228,28 @@ where
 	type TrieBackendStorage = MemoryDB<H>;
 
 	fn storage(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
-		let mut header = self.cached_header.read().clone();
-		if header.is_none() {
-			let cached_header = self.blockchain.upgrade()
-				.ok_or_else(|| ClientErrorKind::UnknownBlock(format!("{}", self.block)).into())
-				.and_then(|blockchain| blockchain.expect_header(BlockId::Hash(self.block)))?;
-			header = Some(cached_header.clone());
-			*self.cached_header.write() = Some(cached_header);
-		}
-
+		let header = self.header()?;
 		self.fetcher.upgrade().ok_or(ClientErrorKind::NotAvailableOnLightClient)?
 			.remote_read(RemoteReadRequest {
 				block: self.block,
-				header: header.expect("if block above guarantees that header is_some(); qed"),
+				header,
 				key: key.to_vec(),
 				retry_count: None,
 			})
 			.into_future().wait()
 	}
 
-	fn child_storage(&self, _storage_key: &[u8], _key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
-		Err(ClientErrorKind::NotAvailableOnLightClient.into())
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		let header = self.header()?;
+		self.fetcher.upgrade().ok_or(ClientErrorKind::NotAvailableOnLightClient)?
+			.remote_read_child(RemoteReadChildRequest {
+				block: self.block,
+				header,
+				storage_key: storage_key.to_vec(),
+				key: key.to_vec(),
+				retry_count: None,
+			})
+			.into_future().wait()
 	}
 
 	fn for_keys_with_prefix<A: FnMut(&[u8])>(&self, _prefix: &[u8], _action: A) {
@@ -238,6 +260,10 @@ where
 		// whole state is not available on light node
 	}
 
+	fn for_keys_from<A: FnMut(&[u8]) -> bool>(&self, _start: &[u8], _action: A) {
+		// whole state is not available on light node
+	}
+
 	fn storage_root<I>(&self, _delta: I) -> (H::Out, Self::Transaction)
 	where
 		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
@@ -24,7 +24,7 @@ use heapsize::HeapSizeOf;
 use primitives::ChangesTrieConfiguration;
 use runtime_primitives::traits::{As, Block as BlockT, Header as HeaderT, NumberFor};
 use state_machine::{CodeExecutor, ChangesTrieRootsStorage, read_proof_check,
-	key_changes_proof_check};
+	read_child_proof_check, key_changes_proof_check};
 
 use call_executor::CallResult;
 use cht;
@@ -70,6 +70,21 @@ pub struct RemoteReadRequest<Header: HeaderT> {
 	pub retry_count: Option<usize>,
 }
 
+/// Remote storage read child request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteReadChildRequest<Header: HeaderT> {
+	/// Read at state of given block.
+	pub block: Header::Hash,
+	/// Header of block at which read is performed.
+	pub header: Header,
+	/// Storage key for child.
+	pub storage_key: Vec<u8>,
+	/// Child storage key to read.
+	pub key: Vec<u8>,
+	/// Number of times to retry request. None means that default RETRY_COUNT is used.
+	pub retry_count: Option<usize>,
+}
+
 /// Remote key changes read request.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RemoteChangesRequest<Header: HeaderT> {
@@ -108,6 +123,8 @@ pub trait Fetcher<Block: BlockT>: Send + Sync {
 	fn remote_header(&self, request: RemoteHeaderRequest<Block::Header>) -> Self::RemoteHeaderResult;
 	/// Fetch remote storage value.
 	fn remote_read(&self, request: RemoteReadRequest<Block::Header>) -> Self::RemoteReadResult;
+	/// Fetch remote child storage value.
+	fn remote_read_child(&self, request: RemoteReadChildRequest<Block::Header>) -> Self::RemoteReadResult;
 	/// Fetch remote call result.
 	fn remote_call(&self, request: RemoteCallRequest<Block::Header>) -> Self::RemoteCallResult;
 	/// Fetch remote changes ((block number, extrinsic index)) where given key has been changed
@@ -133,6 +150,12 @@ pub trait FetchChecker<Block: BlockT>: Send + Sync {
 		request: &RemoteReadRequest<Block::Header>,
 		remote_proof: Vec<Vec<u8>>
 	) -> ClientResult<Option<Vec<u8>>>;
+	/// Check remote child storage read proof.
+	fn check_read_child_proof(
+		&self,
+		request: &RemoteReadChildRequest<Block::Header>,
+		remote_proof: Vec<Vec<u8>>
+	) -> ClientResult<Option<Vec<u8>>>;
 	/// Check remote method execution proof.
 	fn check_execution_proof(
 		&self,
@@ -197,6 +220,16 @@ impl<E, Block, H> FetchChecker<Block> for LightDataChecker<E, H>
 		read_proof_check::<H>(root, remote_proof, &request.key).map_err(Into::into)
 	}
 
+	fn check_read_child_proof(
+		&self,
+		request: &RemoteReadChildRequest<Block::Header>,
+		remote_proof: Vec<Vec<u8>>
+	) -> ClientResult<Option<Vec<u8>>> {
+		let mut root: H::Out = Default::default();
+		root.as_mut().copy_from_slice(request.header.state_root().as_ref());
+		read_child_proof_check::<H>(root, remote_proof, &request.storage_key, &request.key).map_err(Into::into)
+	}
+
 	fn check_execution_proof(
 		&self,
 		request: &RemoteCallRequest<Block::Header>,
@@ -297,6 +330,10 @@ pub mod tests {
 			err("Not implemented on test node".into())
 		}
 
+		fn remote_read_child(&self, _request: RemoteReadChildRequest<Header>) -> Self::RemoteReadResult {
+			err("Not implemented on test node".into())
+		}
+
 		fn remote_call(&self, _request: RemoteCallRequest<Header>) -> Self::RemoteCallResult {
 			ok((*self.lock()).clone())
 		}
@@ -347,6 +384,7 @@ pub mod tests {
 			remote_client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
 			local_headers_hashes.push(remote_client.block_hash(i + 1).unwrap());
 		}
+		remote_client.finalize_block(BlockId::Number(4), true).unwrap();
 
 		// 'fetch' header proof from remote node
 		let remote_block_id = BlockId::Number(1);
@@ -384,6 +422,18 @@ pub mod tests {
 		}, Some(remote_block_header.clone()), remote_header_proof).unwrap(), remote_block_header);
 	}
 
+	#[test]
+	fn header_proof_is_not_generated_for_non_finalized_cht() {
+		let remote_client = test_client::new();
+		for _ in 0..4 {
+			let builder = remote_client.new_block().unwrap();
+			remote_client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		}
+		remote_client.finalize_block(BlockId::Number(3), true).unwrap();
+
+		assert!(remote_client.header_proof_with_cht_size(&BlockId::Number(1), 4).is_err());
+	}
+
 	#[test]
 	fn check_header_proof_fails_if_cht_root_is_invalid() {
 		let (local_checker, _, mut remote_block_header, remote_header_proof) = prepare_for_header_proof_check(true);
@@ -36,6 +36,10 @@ extern crate sr_primitives as runtime_primitives;
 extern crate sr_version as runtime_version;
 extern crate sr_io as runtime_io;
 extern crate tokio;
+#[macro_use]
+extern crate substrate_telemetry;
+#[macro_use]
+extern crate slog;	// needed until we can reexport `slog_info` from `substrate_telemetry`
 
 #[cfg(test)]
 extern crate substrate_keyring as keyring;
@@ -53,22 +57,30 @@ extern crate log;
 
 extern crate futures;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use std::marker::PhantomData;
 
-use codec::Encode;
-use consensus_common::{Authorities, BlockImport, Environment, Proposer};
-use client::ChainHead;
+use codec::{Encode, Decode};
+use consensus_common::{Authorities, BlockImport, Environment, Proposer, InherentData, InherentDataProviders};
+use client::{BlockHeader, ChainHead};
+use client::runtime_api::BlockBuilder as BlockBuilderAPI;
 use consensus_common::{ImportBlock, BlockOrigin};
+use consensus_common::offline_tracker::OfflineTracker;
 use runtime_primitives::{generic, generic::BlockId};
 use runtime_primitives::traits::{Block, Header, Digest, DigestItemFor};
 use network::import_queue::{Verifier, BasicQueue};
 use primitives::{AuthorityId, ed25519};
 
 use futures::{Stream, Future, IntoFuture, future::{self, Either}};
+use parking_lot::Mutex;
 use tokio::timer::Interval;
+use substrate_telemetry::CONSENSUS_INFO;
 
-pub use consensus_common::SyncOracle;
+pub use consensus_common::{
+	SyncOracle, Signer, RemoteSigner, InherentDataProviders, ProvideInherentData, TimestampInherentDataProvider,
+};
 
 /// A handle to the network. This is generally implemented by providing some
 /// handle to a gossip service or similar.
@@ -85,10 +97,37 @@ pub trait Network: Clone {
 /// Configuration for Aura consensus.
 #[derive(Clone)]
 pub struct Config {
-	/// The local authority keypair. Can be none if this is just an observer.
-	pub local_key: Option<Arc<ed25519::Pair>>,
+	/// The local authority signer. Can be none if this is just an observer.
+	pub local_key: Option<Arc<Signer>>,
 	/// The slot duration in seconds.
-	pub slot_duration: u64
+	pub slot_duration: u64,
+	/// Set when a finality gadget authors and justifies blocks alongside Aura. Blocks are then
+	/// authored and accepted on their slot alone only while finality is stalled.
+	pub fallback: Option<Fallback>,
+}
+
+/// When Aura takes over authoring from a finality gadget that stopped making progress, e.g.
+/// because fewer than two thirds of the authorities are online to justify blocks.
+///
+/// Finality is considered stalled at a slot once a number of slots passed since the slot of
+/// the last finalized block, so that all nodes agree on which blocks may be unjustified. The
+/// genesis block has no slot and counts as finalized at slot 0: Aura authors blocks until the
+/// first one gets finalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fallback {
+	after_slots: u64,
+}
+
+impl Fallback {
+	/// Fall back to slot authoring once no block got finalized for `after_slots` slots.
+	pub fn new(after_slots: u64) -> Self {
+		Fallback { after_slots }
+	}
+
+	/// Whether finality is stalled at the given slot, given the slot of the last finalized block.
+	pub fn is_degraded(&self, slot: u64, finalized_slot: u64) -> bool {
+		slot.saturating_sub(finalized_slot) >= self.after_slots
+	}
 }
 
 /// Get slot author for given block along with authorities.
@@ -128,6 +167,11 @@ pub trait CompatibleDigestItem: Sized {
 
 	/// If this item is an Aura seal, return the slot number and signature.
 	fn as_aura_seal(&self) -> Option<(u64, &ed25519::Signature)>;
+
+	/// If this item changes the set of Aura authorities, return the new authorities.
+	fn as_aura_authorities_change(&self) -> Option<&[AuthorityId]> {
+		None
+	}
 }
 
 impl CompatibleDigestItem for generic::DigestItem<primitives::H256, u64> {
@@ -158,6 +202,55 @@ impl CompatibleDigestItem for generic::DigestItem<primitives::H256, primitives::
 			_ => None
 		}
 	}
+	/// If this item changes the set of Aura authorities, return the new authorities.
+	fn as_aura_authorities_change(&self) -> Option<&[AuthorityId]> {
+		match self {
+			generic::DigestItem::AuthoritiesChange(ref authorities) => Some(authorities),
+			_ => None
+		}
+	}
+}
+
+fn slot_of<B: Block>(header: &B::Header) -> Option<u64> where DigestItemFor<B>: CompatibleDigestItem {
+	header.digest().logs().iter().filter_map(|item| item.as_aura_seal()).map(|(slot, _)| slot).next()
+}
+
+/// Note in the offline tracker which authorities proposed, given a block and its parent: the
+/// author of the block's slot did, the authors of the slots skipped since the parent did not.
+pub fn note_slot_authorship<B: Block>(
+	offline: &mut OfflineTracker,
+	header: &B::Header,
+	parent: &B::Header,
+	authorities: &[AuthorityId],
+) where
+	DigestItemFor<B>: CompatibleDigestItem,
+{
+	let slot = match slot_of::<B>(header) {
+		Some(slot) => slot,
+		None => return,
+	};
+
+	// the genesis block is unsealed, and skipping more than a full rotation of slots
+	// doesn't name any further authors.
+	let first_skipped = slot_of::<B>(parent).map_or(slot, |parent_slot| parent_slot + 1);
+	let first_skipped = ::std::cmp::max(first_skipped, slot.saturating_sub(authorities.len() as u64));
+
+	for skipped in first_skipped..slot {
+		if let Some(author) = slot_author(skipped, authorities) {
+			offline.note_round_end(author, false);
+		}
+	}
+	if let Some(author) = slot_author(slot, authorities) {
+		offline.note_round_end(author, true);
+	}
+
+	if first_skipped < slot {
+		telemetry!(CONSENSUS_INFO; "aura.skipped_slots";
+			"slot" => slot,
+			"skipped" => slot - first_skipped,
+			"parent_hash" => ?header.parent_hash()
+		);
+	}
 }
 
 /// Start the aura worker. This should be run in a tokio runtime.
@@ -182,6 +275,7 @@ pub fn start_aura<B, C, E, SO, Error>(
 		let env = env.clone();
 		let sync_oracle = sync_oracle.clone();
 
+		let fallback = config.fallback;
 		let local_keys = config.local_key.map(|pair| (pair.public(), pair));
 		let slot_duration = config.slot_duration;
 		let mut last_authored_slot = 0;
@@ -206,6 +300,20 @@ pub fn start_aura<B, C, E, SO, Error>(
 				if last_authored_slot >= slot_num { return Either::B(future::ok(())) }
 				last_authored_slot = slot_num;
 
+				// the finality gadget authors blocks as long as it makes progress.
+				if let Some(fallback) = fallback {
+					let finalized_slot = match client.finalized_block_header() {
+						Ok(header) => slot_of::<B>(&header).unwrap_or(0),
+						Err(e) => {
+							warn!(target:"aura", "Unable to author block in slot {}. no finalized block header: {:?}", slot_num, e);
+							return Either::B(future::ok(()))
+						}
+					};
+					if !fallback.is_degraded(slot_num, finalized_slot) {
+						return Either::B(future::ok(()))
+					}
+				}
+
 				let chain_head = match client.best_block_header() {
 					Ok(x) => x,
 					Err(e) => {
@@ -242,16 +350,31 @@ pub fn start_aura<B, C, E, SO, Error>(
 
 				let block_import = client.clone();
 				Either::A(proposal_work
-					.map(move |b| {
+					.map_err(|e| warn!("Failed to construct block: {:?}", e))
+					.and_then(move |b| {
 						let (header, body) = b.deconstruct();
 						let pre_hash = header.hash();
-						let parent_hash = header.parent_hash().clone();
 
 						// sign the pre-sealed hash of the block and then
 						// add it to a digest item.
 						let to_sign = (slot_num, pre_hash).encode();
-						let signature = key.sign(&to_sign[..]);
+						key.sign(&to_sign[..]).then(move |signature| Ok::<_, ()>((header, body, pre_hash, signature)))
+					})
+					.map(move |(header, body, pre_hash, signature)| {
+						let parent_hash = header.parent_hash().clone();
+						let signature = match signature {
+							Ok(signature) => signature,
+							Err(e) => {
+								warn!(target: "aura", "Unable to sign block built on {:?}: {:?}", parent_hash, e);
+								return;
+							}
+						};
 						let item = <DigestItemFor<B> as CompatibleDigestItem>::aura_seal(slot_num, signature);
+						telemetry!(CONSENSUS_INFO; "aura.pre_sealed_block";
+							"slot" => slot_num,
+							"pre_hash" => ?pre_hash,
+							"parent_hash" => ?parent_hash
+						);
 						let import_block = ImportBlock {
 							origin: BlockOrigin::Own,
 							header,
@@ -266,7 +389,6 @@ pub fn start_aura<B, C, E, SO, Error>(
 							warn!(target: "aura", "Error with block built on {:?}: {:?}", parent_hash, e);
 						}
 					})
-					.map_err(|e| warn!("Failed to construct block: {:?}", e))
 				)
 			})
 	};
@@ -343,29 +465,173 @@ fn check_header<B: Block>(slot_now: u64, mut header: B::Header, hash: B::Hash, a
 	}
 }
 
+/// Checks the justifications blocks come with, e.g. the proofs of a finality gadget running
+/// alongside Aura. The Aura seal of a block is checked regardless of its justification.
+pub trait JustificationVerifier<B: Block>: Send + Sync {
+	/// Check the justification of the block with the given hash, given the authorities at its
+	/// parent. Returns whether the justification finalizes the block.
+	fn verify_justification(&self, hash: B::Hash, authorities: &[AuthorityId], justification: &[u8]) -> Result<bool, String>;
+}
+
+/// No block is finalized by its justification, which is dropped.
+impl<B: Block> JustificationVerifier<B> for () {
+	fn verify_justification(&self, _hash: B::Hash, _authorities: &[AuthorityId], _justification: &[u8]) -> Result<bool, String> {
+		Ok(false)
+	}
+}
+
+/// Justifications made of the signatures of the block hash by more than two thirds of the
+/// authorities, encoded as a list of `(AuthorityId, ed25519::Signature)`. Blocks with an empty
+/// justification are not finalized.
+#[derive(Clone, Copy, Default)]
+pub struct AuthoritySignatures;
+
+impl<B: Block> JustificationVerifier<B> for AuthoritySignatures {
+	fn verify_justification(&self, hash: B::Hash, authorities: &[AuthorityId], justification: &[u8]) -> Result<bool, String> {
+		if justification.is_empty() {
+			return Ok(false);
+		}
+
+		let signatures = Vec::<(AuthorityId, ed25519::Signature)>::decode(&mut &justification[..])
+			.ok_or_else(|| "Justification is not a list of authority signatures".to_string())?;
+		let message = hash.encode();
+		let mut signers = Vec::with_capacity(signatures.len());
+		for (signer, signature) in signatures {
+			if !authorities.contains(&signer) {
+				return Err(format!("Signer {:?} is not an authority", signer));
+			}
+			if signers.contains(&signer) {
+				return Err(format!("Authority {:?} signed more than once", signer));
+			}
+			if !ed25519::verify_strong(&signature, &message[..], ed25519::Public(signer.0)) {
+				return Err(format!("Bad signature of authority {:?}", signer));
+			}
+			signers.push(signer);
+		}
+
+		if signers.len() * 3 > authorities.len() * 2 {
+			Ok(true)
+		} else {
+			Err(format!("Only {} of {} authorities signed", signers.len(), authorities.len()))
+		}
+	}
+}
+
 /// A verifier for Aura blocks.
-pub struct AuraVerifier<C> {
+///
+/// If it has inherent data providers, the inherents of every block are checked against the
+/// inherent data these expect, with the runtime reporting errors as `E`. Justifications are
+/// checked by `J`.
+///
+/// Blocks are verified in parallel, with the inherents checked once the parent is imported.
+pub struct AuraVerifier<C, E, J = ()> {
 	config: Config,
 	client: Arc<C>,
+	inherent_data_providers: Option<InherentDataProviders>,
+	justification_verifier: J,
+	// authorities at the blocks of the batch being verified, by encoded hash.
+	batch_authorities: Mutex<HashMap<Vec<u8>, Vec<AuthorityId>>>,
+	_error: PhantomData<fn() -> E>,
+}
+
+impl<C, E> AuraVerifier<C, E> {
+	/// Create a new verifier, which doesn't finalize blocks.
+	pub fn new(config: Config, client: Arc<C>, inherent_data_providers: Option<InherentDataProviders>) -> Self {
+		AuraVerifier {
+			config,
+			client,
+			inherent_data_providers,
+			justification_verifier: (),
+			batch_authorities: Default::default(),
+			_error: PhantomData,
+		}
+	}
 }
 
-impl<B: Block, C> Verifier<B> for AuraVerifier<C> where
-	C: Authorities<B> + BlockImport<B> + Send + Sync,
+impl<C, E, J> AuraVerifier<C, E, J> {
+	/// Check the justifications of blocks with the given verifier, finalizing the blocks
+	/// these are valid for.
+	pub fn with_justification_verifier<J2>(self, justification_verifier: J2) -> AuraVerifier<C, E, J2> {
+		AuraVerifier {
+			config: self.config,
+			client: self.client,
+			inherent_data_providers: self.inherent_data_providers,
+			justification_verifier,
+			batch_authorities: self.batch_authorities,
+			_error: PhantomData,
+		}
+	}
+
+	// the authorities at the given block, which is either part of the current batch or imported.
+	// blocks imported by fast sync have no state: their authorities are those set by the last
+	// change in the digests of their ancestors.
+	fn authorities_at<B: Block>(&self, hash: B::Hash) -> Result<Vec<AuthorityId>, String> where
+		C: Authorities<B> + BlockHeader<B>,
+		DigestItemFor<B>: CompatibleDigestItem,
+	{
+		if let Some(authorities) = self.batch_authorities.lock().get(&hash.encode()) {
+			return Ok(authorities.clone());
+		}
+
+		let mut id = BlockId::Hash(hash);
+		loop {
+			let error = match self.client.authorities(&id) {
+				Ok(authorities) => return Ok(authorities),
+				Err(e) => format!("Could not fetch authorities at {:?}: {:?}", hash, e),
+			};
+			let header = match self.client.block_header(&id) {
+				Ok(Some(header)) => header,
+				_ => return Err(error),
+			};
+			let changed = header.digest().logs().iter()
+				.filter_map(CompatibleDigestItem::as_aura_authorities_change)
+				.next()
+				.map(|authorities| authorities.to_vec());
+			if let Some(authorities) = changed {
+				return Ok(authorities);
+			}
+			id = BlockId::Hash(*header.parent_hash());
+		}
+	}
+
+	fn check_inherents<B: Block>(&self, block: B, parent_hash: B::Hash) -> Result<(), String> where
+		C: BlockBuilderAPI<B>,
+		<C as BlockBuilderAPI<B>>::Error: ::std::fmt::Debug,
+		E: Encode + Decode,
+	{
+		let providers = match self.inherent_data_providers {
+			Some(ref providers) => providers,
+			None => return Ok(()),
+		};
+
+		let hash = block.header().hash();
+		let expected = providers.create_expected_inherent_data()
+			.map_err(|e| format!("Could not gather inherent data to check {:?} against: {}", hash, e))?;
+		self.client.check_inherents::<InherentData, E>(&BlockId::Hash(parent_hash), &block, &expected)
+			.map_err(|e| format!("Could not check inherents of {:?}: {:?}", hash, e))?
+			.map_err(|_| format!("Block {:?} has invalid inherents", hash))
+	}
+}
+
+impl<B: Block, C, E, J> Verifier<B> for AuraVerifier<C, E, J> where
+	C: Authorities<B> + BlockHeader<B> + ChainHead<B> + BlockImport<B> + BlockBuilderAPI<B> + Send + Sync,
+	<C as BlockBuilderAPI<B>>::Error: ::std::fmt::Debug,
+	E: Encode + Decode,
+	J: JustificationVerifier<B>,
 	DigestItemFor<B>: CompatibleDigestItem,
 {
 	fn verify(
 		&self,
 		origin: BlockOrigin,
 		header: B::Header,
-		_justification: Vec<u8>,
+		justification: Vec<u8>,
 		body: Option<Vec<B::Extrinsic>>
 	) -> Result<(ImportBlock<B>, Option<Vec<AuthorityId>>), String> {
 		let slot_now = slot_now(self.config.slot_duration)
 			.ok_or("System time is before UnixTime?".to_owned())?;
 		let hash = header.hash();
 		let parent_hash = *header.parent_hash();
-		let authorities = self.client.authorities(&BlockId::Hash(parent_hash))
-			.map_err(|e| format!("Could not fetch authorities at {:?}: {:?}", parent_hash, e))?;
+		let authorities = self.authorities_at::<B>(parent_hash)?;
 
 		// we add one to allow for some small drift.
 		// FIXME: in the future, alter this queue to allow deferring of headers
@@ -375,15 +641,29 @@ impl<B: Block, C> Verifier<B> for AuraVerifier<C> where
 			CheckedHeader::Checked(pre_header, slot_num, sig) => {
 				let item = <DigestItemFor<B>>::aura_seal(slot_num, sig);
 
+				let finalized = self.justification_verifier.verify_justification(hash, &authorities, &justification)
+					.map_err(|e| format!("Bad justification for {:?}: {}", hash, e))?;
+
+				if let Some(fallback) = self.config.fallback {
+					if !finalized {
+						let finalized_slot = self.client.finalized_block_header()
+							.map(|header| slot_of::<B>(&header).unwrap_or(0))
+							.map_err(|e| format!("Could not fetch the last finalized block: {:?}", e))?;
+						if !fallback.is_degraded(slot_num, finalized_slot) {
+							return Err(format!("Block {:?} is not justified while finality is live", hash));
+						}
+					}
+				}
+
 				debug!(target: "aura", "Checked {:?}; importing.", pre_header);
 
 				let import_block = ImportBlock {
 					origin,
 					header: pre_header,
-					external_justification: Vec::new(),
+					external_justification: if finalized { justification } else { Vec::new() },
 					post_runtime_digests: vec![item],
 					body,
-					finalized: false,
+					finalized,
 					auxiliary: Vec::new(),
 				};
 
@@ -396,19 +676,75 @@ impl<B: Block, C> Verifier<B> for AuraVerifier<C> where
 			}
 		}
 	}
+
+	fn can_verify_in_parallel(&self) -> bool {
+		true
+	}
+
+	fn prepare_batch(&self, headers: &[&B::Header]) {
+		let mut batch_authorities = HashMap::new();
+		for header in headers {
+			let parent_hash = *header.parent_hash();
+			let changed = header.digest().logs().iter()
+				.filter_map(CompatibleDigestItem::as_aura_authorities_change)
+				.next()
+				.map(|authorities| authorities.to_vec());
+			let authorities = match changed {
+				Some(authorities) => authorities,
+				None => match batch_authorities.get(&parent_hash.encode()).cloned() {
+					Some(authorities) => authorities,
+					// unknown parents fail verification.
+					None => match self.authorities_at::<B>(parent_hash) {
+						Ok(authorities) => authorities,
+						Err(_) => continue,
+					},
+				},
+			};
+			batch_authorities.insert(header.hash().encode(), authorities);
+		}
+
+		*self.batch_authorities.lock() = batch_authorities;
+	}
+
+	fn check_before_import(&self, block: &ImportBlock<B>) -> Result<(), String> {
+		match block.body {
+			Some(ref body) => self.check_inherents(B::new(block.header.clone(), body.clone()), *block.header.parent_hash()),
+			None => Ok(()),
+		}
+	}
+
+	fn verify_justification(&self, header: &B::Header, justification: &[u8]) -> Result<(), String> {
+		let hash = header.hash();
+		let authorities = self.authorities_at::<B>(*header.parent_hash())?;
+		match self.justification_verifier.verify_justification(hash, &authorities, justification) {
+			Ok(true) => Ok(()),
+			Ok(false) => Err(format!("Justification does not finalize {:?}", hash)),
+			Err(e) => Err(format!("Bad justification for {:?}: {}", hash, e)),
+		}
+	}
 }
 
 /// The Aura import queue type.
-pub type AuraImportQueue<B, C> = BasicQueue<B, AuraVerifier<C>>;
+pub type AuraImportQueue<B, C, E, J = ()> = BasicQueue<B, AuraVerifier<C, E, J>>;
 
-/// Start an import queue for the Aura consensus algorithm.
-pub fn import_queue<B, C>(config: Config, client: Arc<C>) -> AuraImportQueue<B, C> where
+/// Start an import queue for the Aura consensus algorithm. Inherents are only checked if
+/// `inherent_data_providers` are given, and justifications are checked by `justification_verifier`.
+pub fn import_queue<B, C, E, J>(
+	config: Config,
+	client: Arc<C>,
+	inherent_data_providers: Option<InherentDataProviders>,
+	justification_verifier: J,
+) -> AuraImportQueue<B, C, E, J> where
 	B: Block,
-	C: Authorities<B> + BlockImport<B> + Send + Sync,
+	C: Authorities<B> + BlockHeader<B> + ChainHead<B> + BlockImport<B> + BlockBuilderAPI<B> + Send + Sync,
+	<C as BlockBuilderAPI<B>>::Error: ::std::fmt::Debug,
+	E: Encode + Decode,
+	J: JustificationVerifier<B>,
 	DigestItemFor<B>: CompatibleDigestItem,
 {
-	let verifier = Arc::new(AuraVerifier { config, client });
-	BasicQueue::new(verifier)
+	let verifier = AuraVerifier::new(config, client, inherent_data_providers)
+		.with_justification_verifier(justification_verifier);
+	BasicQueue::new(Arc::new(verifier))
 }
 
 
@@ -438,7 +774,7 @@ mod tests {
 		type Proposer = DummyProposer;
 		type Error = Error;
 
-		fn init(&self, parent_header: &<TestBlock as BlockT>::Header, _authorities: &[AuthorityId], _sign_with: Arc<ed25519::Pair>)
+		fn init(&self, parent_header: &<TestBlock as BlockT>::Header, _authorities: &[AuthorityId], _sign_with: Arc<Signer>)
 			-> Result<DummyProposer, Error>
 		{
 			Ok(DummyProposer(parent_header.number + 1, self.0.clone()))
@@ -458,12 +794,12 @@ mod tests {
 	const TEST_ROUTING_INTERVAL: Duration = Duration::from_millis(50);
 
 	pub struct AuraTestNet {
-		peers: Vec<Arc<Peer<AuraVerifier<PeersClient>>>>,
+		peers: Vec<Arc<Peer<AuraVerifier<PeersClient, ()>>>>,
 		started: bool
 	}
 
 	impl TestNetFactory for AuraTestNet {
-		type Verifier = AuraVerifier<PeersClient>;
+		type Verifier = AuraVerifier<PeersClient, ()>;
 
 		/// Create new test network with peers and given config.
 		fn from_config(_config: &ProtocolConfig) -> Self {
@@ -476,8 +812,9 @@ mod tests {
 		fn make_verifier(&self, client: Arc<PeersClient>, _cfg: &ProtocolConfig)
 			-> Arc<Self::Verifier>
 		{
-			let config = Config { local_key: None, slot_duration: SLOT_DURATION };
-			Arc::new(AuraVerifier { client, config })
+			let config = Config { local_key: None, slot_duration: SLOT_DURATION, fallback: None };
+			// the test runtime leaves inherents unchecked.
+			Arc::new(AuraVerifier::new(config, client, None))
 		}
 
 		fn peer(&self, i: usize) -> &Peer<Self::Verifier> {
@@ -530,8 +867,9 @@ mod tests {
 			);
 			let aura = start_aura(
 				Config {
-					local_key: Some(Arc::new(key.clone().into())),
-					slot_duration: SLOT_DURATION
+					local_key: Some(Arc::new(ed25519::Pair::from(key.clone()))),
+					slot_duration: SLOT_DURATION,
+					fallback: None,
 				},
 				client,
 				environ.clone(),
@@ -557,4 +895,126 @@ mod tests {
 
 		runtime.block_on(wait_for.select(drive_to_completion).map_err(|_| ())).unwrap();
 	}
+
+	struct FinalOnly;
+
+	impl JustificationVerifier<TestBlock> for FinalOnly {
+		fn verify_justification(&self, _hash: <TestBlock as BlockT>::Hash, _authorities: &[AuthorityId], justification: &[u8]) -> Result<bool, String> {
+			match justification {
+				b"" => Ok(false),
+				b"final" => Ok(true),
+				_ => Err("not a justification".into()),
+			}
+		}
+	}
+
+	// a header built on the best block, sealed by the author of the given slot.
+	fn sealed_header(client: &TestClient, slot: u64) -> <TestBlock as BlockT>::Header {
+		let authorities = client.authorities(&BlockId::Number(0)).unwrap();
+		let author = slot_author(slot, &authorities).unwrap();
+		let key = [Keyring::Alice, Keyring::Bob, Keyring::Charlie].iter()
+			.map(|k| ed25519::Pair::from(k.clone()))
+			.find(|k| k.public().0 == author.0)
+			.unwrap();
+
+		let mut header = client.new_block().unwrap().bake().unwrap().header;
+		let signature = key.sign(&(slot, header.hash()).encode());
+		header.digest_mut().push(CompatibleDigestItem::aura_seal(slot, signature));
+		header
+	}
+
+	#[test]
+	fn authority_signatures_finalize_blocks_signed_by_two_thirds_of_authorities() {
+		let client = test_client::new();
+		let hash = sealed_header(&client, 0).hash();
+		let authorities = client.authorities(&BlockId::Number(0)).unwrap();
+		let justification = |signers: &[Keyring]| signers.iter()
+			.map(|k| (AuthorityId::from(k.to_raw_public()), k.sign(&hash.encode())))
+			.collect::<Vec<_>>()
+			.encode();
+		let verify = |justification: &[u8]| JustificationVerifier::<TestBlock>::verify_justification(
+			&AuthoritySignatures, hash, &authorities, justification,
+		);
+
+		assert_eq!(verify(&[]), Ok(false));
+		assert_eq!(verify(&justification(&[Keyring::Alice, Keyring::Bob, Keyring::Charlie])), Ok(true));
+		// two of three authorities are not more than two thirds of them.
+		assert!(verify(&justification(&[Keyring::Alice, Keyring::Bob])).is_err());
+		assert!(verify(&justification(&[Keyring::Alice, Keyring::Alice, Keyring::Bob])).is_err());
+		assert!(verify(&justification(&[Keyring::Alice, Keyring::Bob, Keyring::Dave])).is_err());
+		assert!(verify(b"forged").is_err());
+	}
+
+	#[test]
+	fn verifier_finalizes_justified_blocks() {
+		let client = Arc::new(test_client::new());
+		let header = sealed_header(&client, 0);
+		let config = Config { local_key: None, slot_duration: SLOT_DURATION, fallback: None };
+		let verifier = AuraVerifier::<_, ()>::new(config.clone(), client.clone(), None)
+			.with_justification_verifier(FinalOnly);
+
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, header.clone(), b"final".to_vec(), None).unwrap();
+		assert!(block.finalized);
+		assert_eq!(block.external_justification, b"final".to_vec());
+
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, header.clone(), Vec::new(), None).unwrap();
+		assert!(!block.finalized);
+
+		assert!(verifier.verify(BlockOrigin::NetworkBroadcast, header.clone(), b"forged".to_vec(), None).is_err());
+
+		// without a justification verifier, justifications are dropped.
+		let verifier = AuraVerifier::<_, ()>::new(config, client, None);
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, header, b"final".to_vec(), None).unwrap();
+		assert!(!block.finalized);
+		assert!(block.external_justification.is_empty());
+	}
+
+	#[test]
+	fn verifier_accepts_slot_blocks_only_while_finality_is_stalled() {
+		let client = Arc::new(test_client::new());
+		let config = Config { local_key: None, slot_duration: SLOT_DURATION, fallback: Some(Fallback::new(10)) };
+		let verifier = AuraVerifier::<_, ()>::new(config, client.clone(), None)
+			.with_justification_verifier(FinalOnly);
+
+		// the genesis block counts as finalized at slot 0.
+		assert!(verifier.verify(BlockOrigin::NetworkBroadcast, sealed_header(&client, 9), Vec::new(), None).is_err());
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, sealed_header(&client, 10), Vec::new(), None).unwrap();
+		assert!(!block.finalized);
+
+		// justified blocks are accepted at any slot, and finalizing one makes finality live again.
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, sealed_header(&client, 10), b"final".to_vec(), Some(Vec::new())).unwrap();
+		assert!(block.finalized);
+		client.import_block(block, None).unwrap();
+		assert!(verifier.verify(BlockOrigin::NetworkBroadcast, sealed_header(&client, 19), Vec::new(), None).is_err());
+		let (block, _) = verifier.verify(BlockOrigin::NetworkBroadcast, sealed_header(&client, 20), Vec::new(), None).unwrap();
+		assert!(!block.finalized);
+	}
+	#[test]
+	fn skipped_slots_are_noted_offline() {
+		let authorities: Vec<AuthorityId> = (0..3u8).map(|i| [i; 32].into()).collect();
+		let sealed = |number: u64, slot: u64| {
+			let mut header = <TestBlock as BlockT>::Header::new(
+				number,
+				Default::default(),
+				Default::default(),
+				Default::default(),
+				Default::default(),
+			);
+			header.digest_mut().push(CompatibleDigestItem::aura_seal(slot, Default::default()));
+			header
+		};
+
+		let mut offline = OfflineTracker::new();
+		note_slot_authorship::<TestBlock>(&mut offline, &sealed(2, 5), &sealed(1, 3), &authorities);
+		assert_eq!(offline.missed_proposals(&authorities[4 % 3]), 1);
+		assert_eq!(offline.missed_proposals(&authorities[5 % 3]), 0);
+		assert_eq!(offline.missed_proposals(&authorities[3 % 3]), 0);
+
+		// a long gap only counts each author once.
+		note_slot_authorship::<TestBlock>(&mut offline, &sealed(3, 105), &sealed(2, 5), &authorities);
+		assert_eq!(offline.missed_proposals(&authorities[1]), 2);
+		assert_eq!(offline.missed_proposals(&authorities[2]), 1);
+		// the author of the block proposed again.
+		assert_eq!(offline.missed_proposals(&authorities[105 % 3]), 0);
+	}
 }
@@ -73,12 +73,24 @@ error_chain! {
 			display("Authoring in current build is not supported since it has no runtime."),
 		}
 
+		/// Remote signer failed to sign.
+		RemoteSigner(e: String) {
+			description("Remote signer error"),
+			display("Remote signer error: {}", e),
+		}
+
 		/// Justification requirements not met.
 		InvalidJustification {
 			description("Invalid justification"),
 			display("Invalid justification."),
 		}
 
+		/// Inherent data could not be gathered.
+		InherentData(e: String) {
+			description("Inherent data error"),
+			display("Inherent data error: {}", e),
+		}
+
 		/// Some other error.
 		Other(e: Box<::std::error::Error + Send>) {
 			description("Other error")
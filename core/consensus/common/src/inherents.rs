@@ -0,0 +1,164 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Gathering inherent data from the providers registered with a node.
+//!
+//! Block authors put what every provider gives into the inherent data of the blocks they build.
+//! Importing nodes ask the same providers what they expect of a block instead, and have the
+//! runtime check the block against that.
+
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use runtime_primitives::inherents::{InherentData, InherentIdentifier, well_known_identifiers};
+
+use error::{Error, ErrorKind};
+
+/// A source of one part of the inherent data.
+pub trait ProvideInherentData: Send + Sync {
+	/// The identifier of the part of the inherent data this provides.
+	fn inherent_identifier(&self) -> &'static InherentIdentifier;
+
+	/// Put this provider's part into the inherent data of a block about to be authored.
+	fn provide_inherent_data(&self, data: &mut InherentData) -> Result<(), Error>;
+
+	/// Put this provider's part into the inherent data that a block being imported is checked
+	/// against. By default this is what the provider would put into a block of its own.
+	fn expected_inherent_data(&self, data: &mut InherentData) -> Result<(), Error> {
+		self.provide_inherent_data(data)
+	}
+}
+
+/// The set of inherent data providers of a node. Clones share the same set.
+#[derive(Clone, Default)]
+pub struct InherentDataProviders {
+	providers: Arc<RwLock<Vec<Box<ProvideInherentData>>>>,
+}
+
+impl InherentDataProviders {
+	/// Create a new, empty set.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Register a provider. Fails if a provider for the same identifier is registered already.
+	pub fn register_provider<P: ProvideInherentData + 'static>(&self, provider: P) -> Result<(), Error> {
+		let mut providers = self.providers.write().expect("inherent data providers lock is never poisoned; qed");
+		if providers.iter().any(|p| p.inherent_identifier() == provider.inherent_identifier()) {
+			return Err(ErrorKind::InherentData(
+				format!("a provider for {:?} is registered already", provider.inherent_identifier())
+			).into());
+		}
+		providers.push(Box::new(provider));
+		Ok(())
+	}
+
+	/// Whether a provider for `identifier` is registered.
+	pub fn has_provider(&self, identifier: &InherentIdentifier) -> bool {
+		self.providers.read().expect("inherent data providers lock is never poisoned; qed")
+			.iter().any(|p| p.inherent_identifier() == identifier)
+	}
+
+	/// Gather the inherent data of a block about to be authored.
+	pub fn create_inherent_data(&self) -> Result<InherentData, Error> {
+		let mut data = InherentData::new();
+		for provider in self.providers.read().expect("inherent data providers lock is never poisoned; qed").iter() {
+			provider.provide_inherent_data(&mut data)?;
+		}
+		Ok(data)
+	}
+
+	/// Gather the inherent data that a block being imported is checked against.
+	pub fn create_expected_inherent_data(&self) -> Result<InherentData, Error> {
+		let mut data = InherentData::new();
+		for provider in self.providers.read().expect("inherent data providers lock is never poisoned; qed").iter() {
+			provider.expected_inherent_data(&mut data)?;
+		}
+		Ok(data)
+	}
+}
+
+/// Provides the current time, in seconds since the unix epoch.
+///
+/// Blocks being imported may be up to `max_drift` seconds ahead of the local clock.
+pub struct TimestampInherentDataProvider {
+	/// How many seconds ahead of the local clock a block's timestamp may be.
+	pub max_drift: u64,
+}
+
+impl TimestampInherentDataProvider {
+	/// Create a new instance allowing for the given drift.
+	pub fn new(max_drift: u64) -> Self {
+		TimestampInherentDataProvider { max_drift }
+	}
+
+	fn now() -> Result<u64, Error> {
+		SystemTime::now().duration_since(UNIX_EPOCH)
+			.map(|d| d.as_secs())
+			.map_err(|e| ErrorKind::InherentData(format!("current time is before unix epoch: {}", e)).into())
+	}
+}
+
+impl ProvideInherentData for TimestampInherentDataProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&well_known_identifiers::TIMESTAMP
+	}
+
+	fn provide_inherent_data(&self, data: &mut InherentData) -> Result<(), Error> {
+		data.put_data(well_known_identifiers::TIMESTAMP, &Self::now()?)
+			.map_err(|e| ErrorKind::InherentData(e.into()).into())
+	}
+
+	fn expected_inherent_data(&self, data: &mut InherentData) -> Result<(), Error> {
+		data.put_data(well_known_identifiers::TIMESTAMP, &(Self::now()? + self.max_drift))
+			.map_err(|e| ErrorKind::InherentData(e.into()).into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Fixed(u32);
+
+	impl ProvideInherentData for Fixed {
+		fn inherent_identifier(&self) -> &'static InherentIdentifier {
+			b"fixed000"
+		}
+
+		fn provide_inherent_data(&self, data: &mut InherentData) -> Result<(), Error> {
+			data.put_data(*self.inherent_identifier(), &self.0).map_err(|e| ErrorKind::InherentData(e.into()).into())
+		}
+	}
+
+	#[test]
+	fn providers_fill_in_their_parts() {
+		let providers = InherentDataProviders::new();
+		providers.register_provider(Fixed(7)).unwrap();
+		providers.register_provider(TimestampInherentDataProvider::new(30)).unwrap();
+		assert!(providers.register_provider(Fixed(8)).is_err());
+		assert!(providers.clone().has_provider(b"fixed000"));
+
+		let data = providers.create_inherent_data().unwrap();
+		let expected = providers.create_expected_inherent_data().unwrap();
+		assert_eq!(data.get_data::<u32>(b"fixed000"), Ok(Some(7)));
+		assert_eq!(expected.get_data::<u32>(b"fixed000"), Ok(Some(7)));
+
+		let now: u64 = data.get_data(&well_known_identifiers::TIMESTAMP).unwrap().unwrap();
+		let latest: u64 = expected.get_data(&well_known_identifiers::TIMESTAMP).unwrap().unwrap();
+		assert!(latest >= now + 30);
+	}
+}
@@ -29,17 +29,17 @@ extern crate futures;
 extern crate sr_version as runtime_version;
 extern crate sr_primitives as runtime_primitives;
 extern crate tokio;
+#[macro_use]
+extern crate serde_json;
 
 extern crate parity_codec as codec;
-#[macro_use]
-extern crate parity_codec_derive;
 
 #[macro_use]
 extern crate error_chain;
 
 use std::sync::Arc;
 
-use primitives::{ed25519, AuthorityId};
+use primitives::AuthorityId;
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::Block;
 use futures::prelude::*;
@@ -48,12 +48,19 @@ pub mod offline_tracker;
 pub mod error;
 mod block_import;
 pub mod evaluation;
+pub mod signer;
+pub mod inherents;
+pub mod round_timeout;
 
 // block size limit.
 const MAX_TRANSACTIONS_SIZE: usize = 4 * 1024 * 1024;
 
 pub use self::error::{Error, ErrorKind};
 pub use block_import::{BlockImport, ImportBlock, BlockOrigin, ImportResult};
+pub use signer::{Signer, RemoteSigner};
+pub use round_timeout::RoundTimeoutConfig;
+pub use inherents::{ProvideInherentData, InherentDataProviders, TimestampInherentDataProvider};
+pub use runtime_primitives::inherents::InherentData;
 
 /// Trait for getting the authorities at a given block.
 pub trait Authorities<B: Block> {
@@ -69,9 +76,9 @@ pub trait Environment<B: Block> {
 	type Error: From<Error>;
 
 	/// Initialize the proposal logic on top of a specific header. Provide
-	/// the authorities at that header, and a local key to sign any additional
+	/// the authorities at that header, and a signer to sign any additional
 	/// consensus messages with as well.
-	fn init(&self, parent_header: &B::Header, authorities: &[AuthorityId], sign_with: Arc<ed25519::Pair>)
+	fn init(&self, parent_header: &B::Header, authorities: &[AuthorityId], sign_with: Arc<Signer>)
 		-> Result<Self::Proposer, Self::Error>;
 }
 
@@ -88,25 +95,6 @@ pub trait Proposer<B: Block> {
 	fn propose(&self) -> Self::Create;
 }
 
-/// Inherent data to include in a block.
-#[derive(Encode, Decode)]
-pub struct InherentData {
-	/// Current timestamp.
-	pub timestamp: u64,
-	/// Indices of offline validators.
-	pub offline_indices: Vec<u32>,
-}
-
-impl InherentData {
-	/// Create a new `InherentData` instance.
-	pub fn new(timestamp: u64, offline_indices: Vec<u32>) -> Self {
-		Self {
-			timestamp,
-			offline_indices
-		}
-	}
-}
-
 /// An oracle for when major synchronization work is being undertaken.
 ///
 /// Generally, consensus authoring work isn't undertaken while well behind
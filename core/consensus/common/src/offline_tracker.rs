@@ -23,10 +23,13 @@ use std::time::{Instant, Duration};
 
 // time before we report a validator.
 const REPORT_TIME: Duration = Duration::from_secs(60 * 5);
+// number of consecutive rounds a validator may fail to propose in before we report it.
+const REPORT_MISSED_PROPOSALS: usize = 16;
 
 struct Observed {
 	last_round_end: Instant,
 	offline_since: Instant,
+	missed_proposals: usize,
 }
 
 impl Observed {
@@ -35,6 +38,7 @@ impl Observed {
 		Observed {
 			last_round_end: now,
 			offline_since: now,
+			missed_proposals: 0,
 		}
 	}
 
@@ -44,10 +48,14 @@ impl Observed {
 		self.last_round_end = now;
 		if was_online {
 			self.offline_since = now;
+			self.missed_proposals = 0;
+		} else {
+			self.missed_proposals += 1;
 		}
 	}
 
 	fn is_active(&self) -> bool {
+		if self.missed_proposals >= REPORT_MISSED_PROPOSALS { return false }
 		// can happen if clocks are not monotonic
 		if self.offline_since > self.last_round_end { return true }
 		self.last_round_end.duration_since(self.offline_since) < REPORT_TIME
@@ -80,6 +88,11 @@ impl OfflineTracker {
 			.note_round_end(was_online);
 	}
 
+	/// Number of consecutive rounds the validator failed to propose in.
+	pub fn missed_proposals(&self, validator: &AuthorityId) -> usize {
+		self.observed.get(validator).map_or(0, |observed| observed.missed_proposals)
+	}
+
 	/// Generate a vector of indices for offline account IDs.
 	pub fn reports(&self, validators: &[AuthorityId]) -> Vec<u32> {
 		validators.iter()
@@ -134,4 +147,26 @@ mod tests {
 		tracker.note_new_block(&[v, v3]);
 		assert_eq!(tracker.reports(&[v, v2, v3]), vec![0]);
 	}
+
+	#[test]
+	fn validator_missing_proposals_is_offline() {
+		let mut tracker = OfflineTracker::new();
+		let v = [0; 32].into();
+		let v2 = [1; 32].into();
+
+		for _ in 0..REPORT_MISSED_PROPOSALS - 1 {
+			tracker.note_round_end(v, false);
+		}
+		tracker.note_round_end(v2, false);
+		assert_eq!(tracker.missed_proposals(&v), REPORT_MISSED_PROPOSALS - 1);
+		assert!(tracker.reports(&[v, v2]).is_empty());
+
+		tracker.note_round_end(v, false);
+		assert_eq!(tracker.reports(&[v, v2]), vec![0]);
+
+		// proposing again resets the count.
+		tracker.note_round_end(v, true);
+		assert_eq!(tracker.missed_proposals(&v), 0);
+		assert!(tracker.reports(&[v, v2]).is_empty());
+	}
 }
@@ -0,0 +1,77 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Timeouts of the rounds of a round-based agreement.
+
+use std::cmp;
+use std::time::Duration;
+
+/// Schedule of the BFT round timeouts. The timeout of the first round is `base`, and doubles
+/// every `rounds_per_step` rounds, up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTimeoutConfig {
+	/// Timeout of the first rounds.
+	pub base: Duration,
+	/// Number of rounds after which the timeout doubles.
+	pub rounds_per_step: u32,
+	/// Maximum timeout of a round.
+	pub max: Duration,
+}
+
+impl Default for RoundTimeoutConfig {
+	fn default() -> Self {
+		RoundTimeoutConfig {
+			base: Duration::from_secs(10),
+			rounds_per_step: 8,
+			max: Duration::from_secs(640),
+		}
+	}
+}
+
+impl RoundTimeoutConfig {
+	/// Timeout of the given round.
+	pub fn duration(&self, round: u32) -> Duration {
+		let steps = round / cmp::max(1, self.rounds_per_step);
+		1u32.checked_shl(steps)
+			.and_then(|factor| self.base.checked_mul(factor))
+			.map_or(self.max, |timeout| cmp::min(timeout, self.max))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_timeout_grows_exponentially_up_to_cap() {
+		let config = RoundTimeoutConfig::default();
+		assert_eq!(config.duration(0), Duration::from_secs(10));
+		assert_eq!(config.duration(7), Duration::from_secs(10));
+		assert_eq!(config.duration(8), Duration::from_secs(20));
+		assert_eq!(config.duration(48), Duration::from_secs(640));
+		assert_eq!(config.duration(1000), Duration::from_secs(640));
+
+		let config = RoundTimeoutConfig {
+			base: Duration::from_secs(2),
+			rounds_per_step: 0,
+			max: Duration::from_secs(5),
+		};
+		assert_eq!(config.duration(0), Duration::from_secs(2));
+		assert_eq!(config.duration(1), Duration::from_secs(4));
+		assert_eq!(config.duration(2), Duration::from_secs(5));
+		assert_eq!(config.duration(u32::max_value()), Duration::from_secs(5));
+	}
+}
@@ -0,0 +1,279 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing of consensus messages, either with a local key pair or by a remote signer.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use futures::{future, Future, Stream};
+use futures::sync::{mpsc, oneshot};
+use primitives::{ed25519, Bytes};
+use serde_json::{self, Value};
+
+use error::{Error, ErrorKind};
+
+/// Timeout in seconds of a single request to a remote signer.
+const REMOTE_SIGNER_TIMEOUT_SECS: u64 = 5;
+
+/// A signature being produced by a `Signer`.
+pub type SignatureFuture = Box<Future<Item=ed25519::Signature, Error=Error> + Send>;
+
+/// Something that signs consensus messages on behalf of an authority.
+pub trait Signer: Send + Sync {
+	/// Public key of the authority.
+	fn public(&self) -> ed25519::Public;
+
+	/// Sign a message. Must not block: signers performing I/O do so off the calling thread.
+	fn sign(&self, message: &[u8]) -> SignatureFuture;
+}
+
+impl Signer for ed25519::Pair {
+	fn public(&self) -> ed25519::Public {
+		ed25519::Pair::public(self)
+	}
+
+	fn sign(&self, message: &[u8]) -> SignatureFuture {
+		Box::new(future::ok(ed25519::Pair::sign(self, message)))
+	}
+}
+
+/// Sign all given messages, resolving to their items paired with the signatures in the
+/// original order, or to the first error of the signer.
+pub fn sign_all<T>(signer: &Signer, messages: Vec<(T, Vec<u8>)>)
+	-> impl Future<Item=Vec<(T, ed25519::Signature)>, Error=Error>
+{
+	let (items, signatures): (Vec<_>, Vec<_>) = messages.into_iter()
+		.map(|(item, message)| (item, signer.sign(&message)))
+		.unzip();
+
+	future::join_all(signatures).map(move |signatures| items.into_iter().zip(signatures).collect())
+}
+
+/// A signer forwarding requests to an external process over JSON-RPC, so that the
+/// key itself never has to be present on the node (e.g. when it lives in an HSM).
+///
+/// Every request is made on a fresh connection to a local TCP socket and consists of
+/// a single line of JSON, answered by a single line of JSON. Two methods are used:
+/// `signer_publicKey`, returning the hex-encoded public key, and
+/// `signer_sign(public, message)`, returning the hex-encoded signature.
+///
+/// Signing requests are queued to a dedicated thread, so that the consensus tasks asking
+/// for signatures are never blocked on the socket.
+pub struct RemoteSigner {
+	public: ed25519::Public,
+	requests: mpsc::UnboundedSender<SignRequest>,
+}
+
+/// A message to sign and where to send the result to.
+type SignRequest = (Vec<u8>, oneshot::Sender<Result<ed25519::Signature, Error>>);
+
+impl RemoteSigner {
+	/// Connect to the signer listening on the given address and query its public key.
+	pub fn connect(address: SocketAddr) -> Result<Self, Error> {
+		let mut connection = Connection { address, next_id: 0 };
+
+		let public = connection.request("signer_publicKey", Vec::new())?;
+		if public.len() != 32 {
+			bail!(ErrorKind::RemoteSigner(format!("Invalid public key length {}", public.len())));
+		}
+		let public = ed25519::Public::from_slice(&public);
+
+		let (requests, pending) = mpsc::unbounded::<SignRequest>();
+		let signer_public = public.clone();
+		thread::Builder::new()
+			.name("remote-signer".into())
+			.spawn(move || {
+				// ends once the `RemoteSigner` is dropped.
+				for (message, result) in pending.wait().filter_map(|r| r.ok()) {
+					let _ = result.send(connection.sign(&signer_public, &message));
+				}
+			})
+			.map_err(|e| Error::from(ErrorKind::RemoteSigner(format!("Failed to spawn signer thread: {}", e))))?;
+
+		Ok(RemoteSigner { public, requests })
+	}
+}
+
+/// Connection details of a remote signer, only used from the signer thread once connected.
+struct Connection {
+	address: SocketAddr,
+	next_id: usize,
+}
+
+impl Connection {
+	fn sign(&mut self, public: &ed25519::Public, message: &[u8]) -> Result<ed25519::Signature, Error> {
+		let params = vec![
+			json!(Bytes(public.0.to_vec())),
+			json!(Bytes(message.to_vec())),
+		];
+		let signature = self.request("signer_sign", params)?;
+
+		// never hand out a signature that would be rejected by other nodes.
+		if signature.len() != 64 || !ed25519::verify(&signature, message, public) {
+			bail!(ErrorKind::RemoteSigner("Signer returned an invalid signature".into()));
+		}
+
+		Ok(ed25519::Signature::from_slice(&signature))
+	}
+
+	fn request(&mut self, method: &str, params: Vec<Value>) -> Result<Bytes, Error> {
+		let id = self.next_id;
+		self.next_id += 1;
+		let request = json!({
+			"jsonrpc": "2.0",
+			"id": id,
+			"method": method,
+			"params": params,
+		});
+
+		let remote_error = |e: ::std::io::Error| Error::from(ErrorKind::RemoteSigner(format!("{}", e)));
+		let timeout = Duration::from_secs(REMOTE_SIGNER_TIMEOUT_SECS);
+
+		let stream = TcpStream::connect_timeout(&self.address, timeout).map_err(remote_error)?;
+		stream.set_read_timeout(Some(timeout)).map_err(remote_error)?;
+		stream.set_write_timeout(Some(timeout)).map_err(remote_error)?;
+
+		let mut line = serde_json::to_vec(&request).expect("serialization of JSON values cannot fail; qed");
+		line.push(b'\n');
+		(&stream).write_all(&line).map_err(remote_error)?;
+
+		let mut response = String::new();
+		BufReader::new(&stream).read_line(&mut response).map_err(remote_error)?;
+
+		let invalid_response = |e: serde_json::Error| Error::from(ErrorKind::RemoteSigner(format!("Invalid response: {}", e)));
+		let response: Value = serde_json::from_str(&response).map_err(invalid_response)?;
+
+		if let Some(error) = response.get("error") {
+			bail!(ErrorKind::RemoteSigner(format!("Request failed: {}", error)));
+		}
+		if response.get("id") != Some(&json!(id)) {
+			bail!(ErrorKind::RemoteSigner("Response id does not match request".into()));
+		}
+
+		let result = response.get("result").cloned()
+			.ok_or_else(|| Error::from(ErrorKind::RemoteSigner("Response without result".into())))?;
+		serde_json::from_value(result).map_err(invalid_response)
+	}
+}
+
+impl Signer for RemoteSigner {
+	fn public(&self) -> ed25519::Public {
+		self.public.clone()
+	}
+
+	fn sign(&self, message: &[u8]) -> SignatureFuture {
+		let (result, signature) = oneshot::channel();
+		if self.requests.unbounded_send((message.to_vec(), result)).is_err() {
+			return Box::new(future::err(ErrorKind::RemoteSigner("Signer thread terminated".into()).into()));
+		}
+
+		Box::new(signature.then(|signature| match signature {
+			Ok(signature) => signature,
+			Err(_) => Err(ErrorKind::RemoteSigner("Signer thread terminated".into()).into()),
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::TcpListener;
+	use std::thread;
+
+	// serves `requests` requests with the given key pair, optionally corrupting signatures.
+	fn spawn_signer(seed: [u8; 32], requests: usize, corrupt: bool) -> SocketAddr {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+
+		thread::spawn(move || {
+			let pair = ed25519::Pair::from_seed(&seed);
+			for stream in listener.incoming().take(requests) {
+				let stream = stream.unwrap();
+				let mut line = String::new();
+				BufReader::new(&stream).read_line(&mut line).unwrap();
+
+				let request: Value = serde_json::from_str(&line).unwrap();
+				let result = match request["method"].as_str().unwrap() {
+					"signer_publicKey" => Bytes(pair.public().0.to_vec()),
+					"signer_sign" => {
+						let message: Bytes = serde_json::from_value(request["params"][1].clone()).unwrap();
+						let mut signature = pair.sign(&message).to_vec();
+						if corrupt {
+							signature[0] ^= 1;
+						}
+						Bytes(signature)
+					},
+					_ => panic!("unexpected method"),
+				};
+
+				let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+				let mut response = serde_json::to_vec(&response).unwrap();
+				response.push(b'\n');
+				(&stream).write_all(&response).unwrap();
+			}
+		});
+
+		address
+	}
+
+	#[test]
+	fn remote_signer_signs_with_remote_key() {
+		let pair = ed25519::Pair::from_seed(&[1; 32]);
+		let signer = RemoteSigner::connect(spawn_signer([1; 32], 2, false)).unwrap();
+
+		assert_eq!(Signer::public(&signer), pair.public());
+
+		let signature = Signer::sign(&signer, b"message").wait().unwrap();
+		assert_eq!(signature, pair.sign(b"message"));
+	}
+
+	#[test]
+	fn remote_signer_rejects_invalid_signature() {
+		let signer = RemoteSigner::connect(spawn_signer([1; 32], 2, true)).unwrap();
+
+		assert!(Signer::sign(&signer, b"message").wait().is_err());
+	}
+
+	#[test]
+	fn sign_all_keeps_items_in_order() {
+		let pair = ed25519::Pair::from_seed(&[1; 32]);
+		let messages = vec![(1, b"first".to_vec()), (2, b"second".to_vec())];
+
+		let signed = sign_all(&pair, messages).wait().unwrap();
+		assert_eq!(signed, vec![(1, pair.sign(b"first")), (2, pair.sign(b"second"))]);
+	}
+
+	#[test]
+	fn sign_all_fails_if_any_signature_fails() {
+		let signer = RemoteSigner::connect(spawn_signer([1; 32], 3, true)).unwrap();
+		let messages = vec![(1, b"first".to_vec()), (2, b"second".to_vec())];
+
+		assert!(sign_all(&signer, messages).wait().is_err());
+	}
+
+	#[test]
+	fn remote_signer_fails_when_unreachable() {
+		let address = {
+			let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+			listener.local_addr().unwrap()
+		};
+
+		assert!(RemoteSigner::connect(address).is_err());
+	}
+}
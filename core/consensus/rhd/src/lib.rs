@@ -41,6 +41,7 @@ extern crate substrate_client as client;
 extern crate substrate_consensus_common as consensus;
 extern crate substrate_transaction_pool as transaction_pool;
 extern crate srml_system;
+extern crate srml_consensus;
 extern crate srml_support as runtime_support;
 extern crate sr_primitives as runtime_primitives;
 extern crate sr_version as runtime_version;
@@ -51,6 +52,10 @@ extern crate rhododendron;
 extern crate futures;
 extern crate exit_future;
 extern crate tokio;
+#[macro_use]
+extern crate substrate_telemetry;
+#[macro_use]
+extern crate slog;	// needed until we can reexport `slog_info` from `substrate_telemetry`
 
 #[macro_use]
 extern crate log;
@@ -71,7 +76,7 @@ use std::time::{self, Instant, Duration};
 use codec::{Decode, Encode};
 use consensus::offline_tracker::OfflineTracker;
 use consensus::error::{ErrorKind as CommonErrorKind};
-use consensus::{Authorities, BlockImport, Environment, Proposer as BaseProposer};
+use consensus::{Authorities, BlockImport, Environment, Proposer as BaseProposer, Signer};
 use client::{Client as SubstrateClient, CallExecutor};
 use client::runtime_api::{Core, BlockBuilder as BlockBuilderAPI, OldTxQueue, BlockBuilderError};
 use runtime_primitives::generic::{BlockId, Era, ImportResult, ImportBlock, BlockOrigin};
@@ -80,6 +85,7 @@ use runtime_primitives::traits::{Block as BlockT, Hash as HashT, Header as Heade
 use runtime_primitives::Justification;
 use primitives::{AuthorityId, ed25519, Blake2Hasher, ed25519::LocalizedSignature};
 use srml_system::Trait as SystemT;
+use substrate_telemetry::CONSENSUS_INFO;
 
 use node_runtime::Runtime;
 use transaction_pool::txpool::{self, Pool as TransactionPool};
@@ -96,6 +102,7 @@ pub use rhododendron::{
 	Vote as RhdMessageVote, Communication as RhdCommunication,
 };
 pub use self::error::{Error, ErrorKind};
+pub use consensus::RoundTimeoutConfig;
 
 // pub mod misbehaviour_check;
 mod error;
@@ -253,7 +260,7 @@ struct BftInstance<B: Block, P> {
 	key: Arc<ed25519::Pair>,
 	authorities: Vec<AuthorityId>,
 	parent_hash: B::Hash,
-	round_timeout_multiplier: u64,
+	round_timeout: RoundTimeoutConfig,
 	cache: Arc<Mutex<RoundCache<B::Hash>>>,
 	proposer: P,
 }
@@ -265,18 +272,7 @@ impl<B: Block, P: LocalProposer<B>> BftInstance<B, P>
 
 {
 	fn round_timeout_duration(&self, round: u32) -> Duration {
-		// 2^(min(6, x/8)) * 10
-		// Grows exponentially starting from 10 seconds, capped at 640 seconds.
-		const ROUND_INCREMENT_STEP: u32 = 8;
-
-		let round = round / ROUND_INCREMENT_STEP;
-		let round = ::std::cmp::min(6, round);
-
-		let timeout = 1u64.checked_shl(round)
-			.unwrap_or_else(u64::max_value)
-			.saturating_mul(self.round_timeout_multiplier);
-
-		Duration::from_secs(timeout)
+		self.round_timeout.duration(round)
 	}
 
 	fn update_round_cache(&self, current_round: u32) {
@@ -355,10 +351,26 @@ impl<B: Block, P: LocalProposer<B>> rhododendron::Context for BftInstance<B, P>
 			collect_pubkeys(accumulator.voters()));
 		debug!(target: "rhd", "Round {} should end in at most {} seconds from now", next_round, round_timeout.as_secs());
 
+		let proposed = accumulator.proposal().is_some();
+		let timed_out = match reason {
+			AdvanceRoundReason::Timeout => true,
+			_ => false,
+		};
+		let primary = ::ed25519::Public::from_raw(self.round_proposer(round).0);
+		telemetry!(CONSENSUS_INFO; "rhd.view_change";
+			"parent_hash" => ?self.parent_hash,
+			"round" => round,
+			"next_round" => next_round,
+			"timed_out" => timed_out,
+			"primary" => ?primary,
+			"proposed" => proposed,
+			"next_timeout_secs" => round_timeout.as_secs()
+		);
+
 		self.update_round_cache(next_round);
 
-		if let AdvanceRoundReason::Timeout = reason {
-			self.proposer.on_round_end(round, accumulator.proposal().is_some());
+		if timed_out {
+			self.proposer.on_round_end(round, proposed);
 		}
 	}
 }
@@ -495,7 +507,7 @@ pub struct BftService<B: Block, P, I> {
 	client: Arc<I>,
 	live_agreement: Mutex<Option<(B::Header, AgreementHandle)>>,
 	round_cache: Arc<Mutex<RoundCache<B::Hash>>>,
-	round_timeout_multiplier: u64,
+	round_timeout: RoundTimeoutConfig,
 	key: Arc<ed25519::Pair>, // TODO: key changing over time.
 	factory: P,
 }
@@ -508,8 +520,18 @@ impl<B, P, I> BftService<B, P, I>
 		P::Proposer: BaseProposer<B,Error=Error>,
 		I: BlockImport<B> + Authorities<B>,
 {
-	/// Create a new service instance.
+	/// Create a new service instance, with the default round timeouts.
 	pub fn new(client: Arc<I>, key: Arc<ed25519::Pair>, factory: P) -> BftService<B, P, I> {
+		Self::with_round_timeout(client, key, factory, Default::default())
+	}
+
+	/// Create a new service instance, with the given schedule of round timeouts.
+	pub fn with_round_timeout(
+		client: Arc<I>,
+		key: Arc<ed25519::Pair>,
+		factory: P,
+		round_timeout: RoundTimeoutConfig,
+	) -> BftService<B, P, I> {
 		BftService {
 			client: client,
 			live_agreement: Mutex::new(None),
@@ -517,7 +539,7 @@ impl<B, P, I> BftService<B, P, I>
 				hash: None,
 				start_round: 0,
 			})),
-			round_timeout_multiplier: 10,
+			round_timeout,
 			key: key, // TODO: key changing over time.
 			factory,
 		}
@@ -586,7 +608,7 @@ impl<B, P, I> BftService<B, P, I>
 			proposer,
 			parent_hash: hash.clone(),
 			cache: self.round_cache.clone(),
-			round_timeout_multiplier: self.round_timeout_multiplier,
+			round_timeout: self.round_timeout,
 			key: self.key.clone(),
 			authorities: authorities,
 		};
@@ -1001,7 +1023,7 @@ impl<N, C, A> consensus::Environment<<C as AuthoringApi>::Block> for ProposerFac
 		&self,
 		parent_header: &<<C as AuthoringApi>::Block as BlockT>::Header,
 		authorities: &[AuthorityId],
-		sign_with: Arc<ed25519::Pair>,
+		sign_with: Arc<Signer>,
 	) -> Result<Self::Proposer, Error> {
 		use runtime_primitives::traits::Hash as HashT;
 		let parent_hash = parent_header.hash();
@@ -1035,7 +1057,8 @@ impl<N, C, A> consensus::Environment<<C as AuthoringApi>::Block> for ProposerFac
 			offline: self.offline.clone(),
 			validators,
 			minimum_timestamp: current_timestamp() + self.force_delay,
-			network: self.network.clone()
+			network: self.network.clone(),
+			handle: self.handle.clone(),
 		};
 
 		Ok(proposer)
@@ -1046,7 +1069,7 @@ impl<N, C, A> consensus::Environment<<C as AuthoringApi>::Block> for ProposerFac
 pub struct Proposer<C: AuthoringApi, A: txpool::ChainApi, N: Network> {
 	client: Arc<C>,
 	start: Instant,
-	local_key: Arc<ed25519::Pair>,
+	local_key: Arc<Signer>,
 	parent_hash: <<C as AuthoringApi>::Block as BlockT>::Hash,
 	parent_id: BlockId<<C as AuthoringApi>::Block>,
 	parent_number: <<<C as AuthoringApi>::Block as BlockT>::Header as HeaderT>::Number,
@@ -1056,6 +1079,7 @@ pub struct Proposer<C: AuthoringApi, A: txpool::ChainApi, N: Network> {
 	validators: Vec<AuthorityId>,
 	minimum_timestamp: u64,
 	network: N,
+	handle: TaskExecutor,
 }
 
 impl<C: AuthoringApi, A: txpool::ChainApi> Proposer<C, A> {
@@ -1123,7 +1147,7 @@ impl<C, A> BaseProposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
 								pending_size += encoded_size;
 							}
 							Err(e) => {
-								trace!(target: "transaction-pool", "Invalid transaction: {}", e);
+								trace!(target: "txpool", "Invalid transaction: {}", e);
 								unqueue_invalid.push(pending.hash.clone());
 							}
 						}
@@ -1253,9 +1277,10 @@ impl<C, A> LocalProposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
 		proposer
 	}
 
-	fn import_misbehavior(&self, _misbehavior: Vec<(AuthorityId, Misbehavior<<<C as AuthoringApi>::Block as BlockT>::Hash>)>) {
+	fn import_misbehavior(&self, misbehavior: Vec<(AuthorityId, Misbehavior<<<C as AuthoringApi>::Block as BlockT>::Hash>)>) {
 		use rhododendron::Misbehavior as GenericMisbehavior;
-		use runtime_primitives::bft::{MisbehaviorKind, MisbehaviorReport};
+		use runtime_primitives::Ed25519Signature;
+		use srml_consensus::{MisbehaviorKind, MisbehaviorReport};
 		use node_runtime::{Call, UncheckedExtrinsic, ConsensusCall};
 
 		let mut next_index = {
@@ -1271,24 +1296,27 @@ impl<C, A> LocalProposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
 			match cur_index {
 				Ok(cur_index) => cur_index + 1,
 				Err(e) => {
-					warn!(target: "consensus", "Error computing next transaction index: {:?}", e);
+					warn!(target: "rhd", "Error computing next transaction index: {:?}", e);
 					return;
 				}
 			}
 		};
 
+		let mut reports = Vec::new();
 		for (target, misbehavior) in misbehavior {
+			let sig = |s: LocalizedSignature| Ed25519Signature(s.signature.0.into());
 			let report = MisbehaviorReport {
 				parent_hash: self.parent_hash.into(),
 				parent_number: self.parent_number.as_(),
 				target,
 				misbehavior: match misbehavior {
 					GenericMisbehavior::ProposeOutOfTurn(_, _, _) => continue,
-					GenericMisbehavior::DoublePropose(_, _, _) => continue,
+					GenericMisbehavior::DoublePropose(round, (h1, s1), (h2, s2))
+						=> MisbehaviorKind::BftDoublePropose(round as u32, (h1.into(), sig(s1)), (h2.into(), sig(s2))),
 					GenericMisbehavior::DoublePrepare(round, (h1, s1), (h2, s2))
-						=> MisbehaviorKind::BftDoublePrepare(round as u32, (h1.into(), s1.signature), (h2.into(), s2.signature)),
+						=> MisbehaviorKind::BftDoublePrepare(round as u32, (h1.into(), sig(s1)), (h2.into(), sig(s2))),
 					GenericMisbehavior::DoubleCommit(round, (h1, s1), (h2, s2))
-						=> MisbehaviorKind::BftDoubleCommit(round as u32, (h1.into(), s1.signature), (h2.into(), s2.signature)),
+						=> MisbehaviorKind::BftDoubleCommit(round as u32, (h1.into(), sig(s1)), (h2.into(), sig(s2))),
 				}
 			};
 			let payload = (
@@ -1297,21 +1325,31 @@ impl<C, A> LocalProposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
 				Era::immortal(),
 				self.client.genesis_hash()
 			);
-			let signature = self.local_key.sign(&payload.encode()).into();
 			next_index += 1;
 
-			let local_id = self.local_key.public().0.into();
-			let extrinsic = UncheckedExtrinsic {
-				signature: Some((node_runtime::RawAddress::Id(local_id), signature, payload.0, Era::immortal())),
-				function: payload.1,
-			};
-			let uxt: <<C as AuthoringApi>::Block as BlockT>::Extrinsic = Decode::decode(
-				&mut extrinsic.encode().as_slice()).expect("Encoded extrinsic is valid");
-			let hash = BlockId::<<C as AuthoringApi>::Block>::hash(self.parent_hash);
-			if let Err(e) = self.transaction_pool.submit_one(&hash, uxt) {
-				warn!("Error importing misbehavior report: {:?}", e);
-			}
+			let message = payload.encode();
+			reports.push((payload, message));
 		}
+
+		// signing may wait on a remote signer, so sign and submit the reports on a separate task.
+		let local_id = self.local_key.public().0.into();
+		let hash = BlockId::<<C as AuthoringApi>::Block>::hash(self.parent_hash);
+		let transaction_pool = self.transaction_pool.clone();
+		let submit = consensus::signer::sign_all(&*self.local_key, reports)
+			.map(move |signed| for (payload, signature) in signed {
+				let extrinsic = UncheckedExtrinsic {
+					signature: Some((node_runtime::RawAddress::Id(local_id), signature.into(), payload.0, Era::immortal())),
+					function: payload.1,
+				};
+				let uxt: <<C as AuthoringApi>::Block as BlockT>::Extrinsic = Decode::decode(
+					&mut extrinsic.encode().as_slice()).expect("Encoded extrinsic is valid");
+				if let Err(e) = transaction_pool.submit_one(&hash, uxt) {
+					warn!(target: "rhd", "Error importing misbehavior report: {:?}", e);
+				}
+			})
+			.map_err(|e| warn!(target: "rhd", "Unable to sign misbehavior reports: {:?}", e));
+
+		self.handle.spawn(submit);
 	}
 
 	fn on_round_end(&self, round_number: u32, was_proposed: bool) {
@@ -1321,16 +1359,24 @@ impl<C, A> LocalProposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
 
 		// alter the message based on whether we think the empty proposer was forced to skip the round.
 		// this is determined by checking if our local validator would have been forced to skip the round.
+		let mut offline = self.offline.write();
+		offline.note_round_end(primary_validator, was_proposed);
+
 		if !was_proposed {
 			let public = ed25519::Public::from_raw(primary_validator.0);
+			let missed = offline.missed_proposals(&primary_validator);
 			info!(
-				"Potential Offline Validator: {} failed to propose during assigned slot: {}",
+				"Potential Offline Validator: {} failed to propose during assigned slot: {} ({} consecutive misses)",
 				public,
 				round_number,
+				missed,
+			);
+			telemetry!(CONSENSUS_INFO; "rhd.missed_proposal";
+				"validator" => ?public,
+				"round" => round_number,
+				"consecutive_misses" => missed
 			);
 		}
-
-		self.offline.write().note_round_end(primary_validator, was_proposed);
 	}
 }
 
@@ -1412,7 +1458,7 @@ mod tests {
 		type Proposer = DummyProposer;
 		type Error = Error;
 
-		fn init(&self, parent_header: &TestHeader, _authorities: &[AuthorityId], _sign_with: Arc<ed25519::Pair>)
+		fn init(&self, parent_header: &TestHeader, _authorities: &[AuthorityId], _sign_with: Arc<Signer>)
 			-> Result<DummyProposer, Error>
 		{
 			Ok(DummyProposer(parent_header.number + 1))
@@ -1455,7 +1501,7 @@ mod tests {
 				hash: None,
 				start_round: 0,
 			})),
-			round_timeout_multiplier: 10,
+			round_timeout: Default::default(),
 			key: Arc::new(Keyring::One.into()),
 			factory: DummyFactory
 		}
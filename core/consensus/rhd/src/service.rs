@@ -58,10 +58,10 @@ fn start_bft<F, C, Block>(
 	let mut handle = LocalThreadHandle::current();
 	match bft_service.build_upon(&header) {
 		Ok(Some(bft_work)) => if let Err(e) = handle.spawn_local(Box::new(bft_work)) {
-		    warn!(target: "bft", "Couldn't initialize BFT agreement: {:?}", e);
+		    warn!(target: "rhd", "Couldn't initialize BFT agreement: {:?}", e);
 		}
-		Ok(None) => trace!(target: "bft", "Could not start agreement on top of {}", header.hash()),
-		Err(e) => warn!(target: "bft", "BFT agreement error: {}", e),
+		Ok(None) => trace!(target: "rhd", "Could not start agreement on top of {}", header.hash()),
+		Err(e) => warn!(target: "rhd", "BFT agreement error: {}", e),
  	}
 }
 
@@ -140,12 +140,12 @@ impl Service {
 				let c = client.clone();
 				let s = bft_service.clone();
 
-				interval.map_err(|e| debug!(target: "bft", "Timer error: {:?}", e)).for_each(move |_| {
+				interval.map_err(|e| debug!(target: "rhd", "Timer error: {:?}", e)).for_each(move |_| {
 					if let Ok(best_block) = c.best_block_header() {
 						let hash = best_block.hash();
 
 						if hash == prev_best {
-							debug!(target: "bft", "Starting consensus round after a timeout");
+							debug!(target: "rhd", "Starting consensus round after a timeout");
 							start_bft(best_block, s.clone());
 						}
 						prev_best = hash;
@@ -158,7 +158,7 @@ impl Service {
 			runtime.spawn(timed);
 
 			if let Err(e) = runtime.block_on(exit) {
-				debug!("BFT event loop error {:?}", e);
+				debug!(target: "rhd", "BFT event loop error {:?}", e);
 			}
 		});
 		Service {
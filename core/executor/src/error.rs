@@ -64,6 +64,12 @@ error_chain! {
 			display("Invalid type returned (should be u64)"),
 		}
 
+		/// Runtime call has used all the fuel it had been given.
+		OutOfFuel {
+			description("out of fuel"),
+			display("Runtime call has run out of fuel"),
+		}
+
 		/// Runtime failed.
 		Runtime {
 			description("runtime failure"),
@@ -40,6 +40,8 @@ extern crate sr_version as runtime_version;
 extern crate substrate_trie as trie;
 
 extern crate wasmi;
+extern crate parity_wasm;
+extern crate pwasm_utils;
 extern crate byteorder;
 extern crate parking_lot;
 
@@ -70,7 +72,7 @@ mod native_executor;
 mod sandbox;
 
 pub mod error;
-pub use wasm_executor::WasmExecutor;
+pub use wasm_executor::{WasmExecutor, read_runtime_version};
 pub use native_executor::{with_native_environment, NativeExecutor, NativeExecutionDispatch};
 pub use state_machine::Externalities;
 pub use runtime_version::{RuntimeVersion, NativeVersion};
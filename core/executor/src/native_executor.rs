@@ -16,10 +16,11 @@
 
 use error::{Error, ErrorKind, Result};
 use state_machine::{CodeExecutor, Externalities};
-use wasm_executor::WasmExecutor;
+use wasm_executor::{WasmExecutor, WasmRuntimeVersionReader};
 use wasmi::Module as WasmModule;
 use runtime_version::{NativeVersion, RuntimeVersion};
 use std::collections::HashMap;
+use std::sync::Arc;
 use codec::Decode;
 use primitives::hashing::blake2_256;
 use parking_lot::{Mutex, MutexGuard};
@@ -31,10 +32,11 @@ use primitives::Blake2Hasher;
 
 enum RuntimePreproc {
 	InvalidCode,
-	ValidCode(WasmModule, Option<RuntimeVersion>),
+	ValidCode(Arc<WasmModule>, Option<RuntimeVersion>),
 }
 
-type CacheType = HashMap<[u8; 32], RuntimePreproc>;
+/// Keyed by the code hash and whether the module has been prepared for metered execution.
+type CacheType = HashMap<([u8; 32], bool), RuntimePreproc>;
 
 lazy_static! {
 	static ref RUNTIMES_CACHE: Mutex<CacheType> = Mutex::new(HashMap::new());
@@ -56,15 +58,16 @@ fn fetch_cached_runtime_version<'a, E: Externalities<Blake2Hasher>>(
 	cache: &'a mut MutexGuard<CacheType>,
 	ext: &mut E,
 	heap_pages: usize,
+	code_hash: [u8; 32],
 	code: &[u8]
-) -> Result<(&'a WasmModule, &'a Option<RuntimeVersion>)> {
-	let maybe_runtime_preproc = cache.entry(gen_cache_key(code))
-		.or_insert_with(|| match WasmModule::from_buffer(code) {
+) -> Result<(&'a Arc<WasmModule>, &'a Option<RuntimeVersion>)> {
+	let maybe_runtime_preproc = cache.entry((code_hash, wasm_executor.is_metered()))
+		.or_insert_with(|| match wasm_executor.prepare_module(code) {
 			Ok(module) => {
 				let version = wasm_executor.call_in_wasm_module(ext, heap_pages, &module, "version", &[])
 					.ok()
 					.and_then(|v| RuntimeVersion::decode(&mut v.as_slice()));
-				RuntimePreproc::ValidCode(module, version)
+				RuntimePreproc::ValidCode(Arc::new(module), version)
 			}
 			Err(e) => {
 				trace!(target: "executor", "Invalid code presented to executor ({:?})", e);
@@ -93,7 +96,9 @@ fn safe_call<F, U>(f: F) -> Result<U>
 pub fn with_native_environment<F, U>(ext: &mut Externalities<Blake2Hasher>, f: F) -> Result<U>
 where F: ::std::panic::UnwindSafe + FnOnce() -> U
 {
-	::runtime_io::with_externalities(ext, move || safe_call(f))
+	::runtime_io::with_runtime_version_reader(&mut WasmRuntimeVersionReader, move ||
+		::runtime_io::with_externalities(ext, move || safe_call(f))
+	)
 }
 
 /// Delegate for dispatching a CodeExecutor call to native code.
@@ -134,6 +139,16 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			native_version: D::native_version(),
 		}
 	}
+
+	/// Create new instance that meters calls to the WASM runtime, failing those that
+	/// execute more than `fuel_limit` instructions.
+	pub fn with_fuel_limit(fuel_limit: u64) -> Self {
+		NativeExecutor {
+			_dummy: Default::default(),
+			fallback: WasmExecutor::with_fuel_limit(fuel_limit),
+			native_version: D::native_version(),
+		}
+	}
 }
 
 impl<D: NativeExecutionDispatch> Clone for NativeExecutor<D> {
@@ -157,7 +172,8 @@ impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
 		heap_pages: usize,
 		code: &[u8],
 	) -> Option<RuntimeVersion> {
-		fetch_cached_runtime_version(&self.fallback, &mut RUNTIMES_CACHE.lock(), ext, heap_pages, code).ok()?.1.clone()
+		fetch_cached_runtime_version(&self.fallback, &mut RUNTIMES_CACHE.lock(), ext, heap_pages, gen_cache_key(code), code)
+			.ok()?.1.clone()
 	}
 }
 
@@ -173,18 +189,22 @@ impl<D: NativeExecutionDispatch> CodeExecutor<Blake2Hasher> for NativeExecutor<D
 		data: &[u8],
 		use_native: bool,
 	) -> (Result<Vec<u8>>, bool) {
-		let mut c = RUNTIMES_CACHE.lock();
-		let (module, onchain_version) = match fetch_cached_runtime_version(&self.fallback, &mut c, ext, heap_pages, code) {
-			Ok((module, onchain_version)) => (module, onchain_version),
-			Err(_) => return (Err(ErrorKind::InvalidCode(code.into()).into()), false),
+		let code_hash = gen_cache_key(code);
+		// the cache lock is released before the call, so that calls could be executed concurrently
+		let (module, onchain_version) = {
+			let mut c = RUNTIMES_CACHE.lock();
+			match fetch_cached_runtime_version(&self.fallback, &mut c, ext, heap_pages, code_hash, code) {
+				Ok((module, onchain_version)) => (module.clone(), onchain_version.clone()),
+				Err(_) => return (Err(ErrorKind::InvalidCode(code.into()).into()), false),
+			}
 		};
 		match (use_native, onchain_version.as_ref().map_or(false, |v| v.can_call_with(&self.native_version.runtime_version))) {
 			(_, false) => {
 				trace!(target: "executor", "Request for native execution failed (native: {}, chain: {})", self.native_version.runtime_version, onchain_version.as_ref().map_or_else(||"<None>".into(), |v| format!("{}", v)));
-				(self.fallback.call_in_wasm_module(ext, heap_pages, module, method, data), false)
+				(self.fallback.call_in_cached_wasm_module(ext, heap_pages, code_hash, &module, method, data), false)
 			}
 			(false, _) => {
-				(self.fallback.call_in_wasm_module(ext, heap_pages, module, method, data), false)
+				(self.fallback.call_in_cached_wasm_module(ext, heap_pages, code_hash, &module, method, data), false)
 			}
 			_ => {
 				trace!(target: "executor", "Request for native execution succeeded (native: {}, chain: {})", self.native_version.runtime_version, onchain_version.as_ref().map_or_else(||"<None>".into(), |v| format!("{}", v)));
@@ -225,3 +245,51 @@ macro_rules! native_executor_instance {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use state_machine::TestExternalities;
+
+	struct PanickingDispatch;
+
+	impl NativeExecutionDispatch for PanickingDispatch {
+		fn native_equivalent() -> &'static [u8] {
+			&[]
+		}
+
+		fn dispatch(_: &mut Externalities<Blake2Hasher>, _: &str, _: &[u8]) -> Result<Vec<u8>> {
+			panic!("native runtime should not be called when on-chain version mismatches")
+		}
+
+		fn native_version() -> NativeVersion {
+			NativeVersion {
+				runtime_version: RuntimeVersion {
+					spec_name: "native".into(),
+					impl_name: "native".into(),
+					authoring_version: 1,
+					spec_version: 1,
+					impl_version: 1,
+					apis: (&[][..]).into(),
+				},
+				can_author_with: Default::default(),
+			}
+		}
+
+		fn new() -> NativeExecutor<Self> {
+			NativeExecutor::new()
+		}
+	}
+
+	#[test]
+	fn falls_back_to_wasm_when_onchain_version_is_not_compatible() {
+		let mut ext = TestExternalities::default();
+		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
+		let executor = PanickingDispatch::new();
+
+		// test runtime doesn't export `version`, so it is never compatible with the native one
+		let (result, used_native) = executor.call(&mut ext, 8, &test_code[..], "test_empty_return", &[], true);
+		assert_eq!(result.unwrap(), vec![0u8; 0]);
+		assert!(!used_native);
+	}
+}
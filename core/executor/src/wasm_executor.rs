@@ -16,14 +16,17 @@
 
 //! Rust implementation of Substrate contracts.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use wasmi::{
-	Module, ModuleInstance, MemoryInstance, MemoryRef, TableRef, ImportsBuilder
+	Module, ModuleInstance, ModuleRef, MemoryInstance, MemoryRef, TableRef, ImportsBuilder,
+	GlobalRef, FuncRef, RuntimeValue
 };
 use wasmi::RuntimeValue::{I32, I64};
 use wasmi::memory_units::{Pages, Bytes};
-use state_machine::Externalities;
+use state_machine::{Externalities, TestExternalities};
 use error::{Error, ErrorKind, Result};
 use wasm_utils::UserError;
 use primitives::{blake2_256, twox_128, twox_256, ed25519};
@@ -41,6 +44,9 @@ struct Heap {
 impl Heap {
 	/// Construct new `Heap` struct with a given number of pages.
 	///
+	/// The memory is only grown if it hasn't already got the required
+	/// number of pages, which is the case for reused instances.
+	///
 	/// Returns `Err` if the heap couldn't allocate required
 	/// number of pages.
 	///
@@ -48,7 +54,12 @@ impl Heap {
 	/// limit and we are trying to allocate beyond that limit.
 	fn new(memory: &MemoryRef, pages: usize) -> Result<Self> {
 		let prev_page_count = memory.initial();
-		memory.grow(Pages(pages)).map_err(|_| Error::from(ErrorKind::Runtime))?;
+		let required_page_count = prev_page_count.0 + pages;
+		let current_page_count = memory.current_size().0;
+		if current_page_count < required_page_count {
+			memory.grow(Pages(required_page_count - current_page_count))
+				.map_err(|_| Error::from(ErrorKind::Runtime))?;
+		}
 		Ok(Heap {
 			end: Bytes::from(prev_page_count).0 as u32,
 		})
@@ -80,10 +91,13 @@ struct FunctionExecutor<'e, E: Externalities<Blake2Hasher> + 'e> {
 	table: Option<TableRef>,
 	ext: &'e mut E,
 	hash_lookup: HashMap<Vec<u8>, Vec<u8>>,
+	/// Fuel left for the call. `None` if the call is not metered.
+	fuel_left: Option<u64>,
+	out_of_fuel: bool,
 }
 
 impl<'e, E: Externalities<Blake2Hasher>> FunctionExecutor<'e, E> {
-	fn new(m: MemoryRef, heap_pages: usize, t: Option<TableRef>, e: &'e mut E) -> Result<Self> {
+	fn new(m: MemoryRef, heap_pages: usize, t: Option<TableRef>, e: &'e mut E, fuel_limit: Option<u64>) -> Result<Self> {
 		Ok(FunctionExecutor {
 			sandbox_store: sandbox::Store::new(),
 			heap: Heap::new(&m, heap_pages)?,
@@ -91,6 +105,8 @@ impl<'e, E: Externalities<Blake2Hasher>> FunctionExecutor<'e, E> {
 			table: t,
 			ext: e,
 			hash_lookup: HashMap::new(),
+			fuel_left: fuel_limit,
+			out_of_fuel: false,
 		})
 	}
 }
@@ -291,6 +307,23 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 			Ok(0)
 		}
 	},
+	// keys are passed as encoded `Vec<Vec<u8>>`; returns encoded `Vec<Option<Vec<u8>>>` with values of the keys.
+	ext_storage_batch_get(keys_data: *const u8, keys_len: u32, written_out: *mut u32) -> *mut u8 => {
+		use codec::{Decode, Encode};
+
+		let keys = this.memory.get(keys_data, keys_len as usize)
+			.map_err(|_| UserError("Invalid attempt to determine keys in ext_storage_batch_get"))?;
+		let keys: Vec<Vec<u8>> = Decode::decode(&mut &keys[..])
+			.ok_or_else(|| UserError("Invalid attempt to decode keys in ext_storage_batch_get"))?;
+		let keys = keys.iter().map(|key| &key[..]).collect::<Vec<_>>();
+		let values = this.ext.storage_batch(&keys).encode();
+
+		let offset = this.heap.allocate(values.len() as u32) as u32;
+		this.memory.set(offset, &values).map_err(|_| UserError("Invalid attempt to set memory in ext_storage_batch_get"))?;
+		this.memory.write_primitive(written_out, values.len() as u32)
+			.map_err(|_| UserError("Invalid attempt to write written_out in ext_storage_batch_get"))?;
+		Ok(offset)
+	},
 	// return 0 and place u32::max_value() into written_out if no value exists for the key.
 	ext_get_allocated_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
 		let storage_key = this.memory.get(
@@ -437,6 +470,70 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 	ext_chain_id() -> u64 => {
 		Ok(this.ext.chain_id())
 	},
+	ext_runtime_version(code_data: *const u8, code_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let code = this.memory.get(code_data, code_len as usize).map_err(|_| UserError("Invalid attempt to get code in ext_runtime_version"))?;
+		if let Some(value) = read_runtime_version(&code) {
+			let offset = this.heap.allocate(value.len() as u32) as u32;
+			this.memory.set(offset, &value).map_err(|_| UserError("Invalid attempt to set memory in ext_runtime_version"))?;
+			this.memory.write_primitive(written_out, value.len() as u32)
+				.map_err(|_| UserError("Invalid attempt to write written_out in ext_runtime_version"))?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| UserError("Invalid attempt to write failed written_out in ext_runtime_version"))?;
+			Ok(0)
+		}
+	},
+	ext_submit_extrinsic(data: *const u8, len: u32) -> u32 => {
+		let extrinsic = this.memory.get(data, len as usize).map_err(|_| UserError("Invalid attempt to get extrinsic in ext_submit_extrinsic"))?;
+		Ok(if ::runtime_io::submit_extrinsic(&extrinsic).is_ok() { 0 } else { 1 })
+	},
+	ext_local_storage_get(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let key = this.memory.get(key_data, key_len as usize).map_err(|_| UserError("Invalid attempt to determine key in ext_local_storage_get"))?;
+		if let Some(value) = ::runtime_io::local_storage_get(&key) {
+			let offset = this.heap.allocate(value.len() as u32) as u32;
+			this.memory.set(offset, &value).map_err(|_| UserError("Invalid attempt to set memory in ext_local_storage_get"))?;
+			this.memory.write_primitive(written_out, value.len() as u32)
+				.map_err(|_| UserError("Invalid attempt to write written_out in ext_local_storage_get"))?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| UserError("Invalid attempt to write failed written_out in ext_local_storage_get"))?;
+			Ok(0)
+		}
+	},
+	ext_local_storage_set(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32) => {
+		let key = this.memory.get(key_data, key_len as usize).map_err(|_| UserError("Invalid attempt to determine key in ext_local_storage_set"))?;
+		let value = this.memory.get(value_data, value_len as usize).map_err(|_| UserError("Invalid attempt to determine value in ext_local_storage_set"))?;
+		::runtime_io::local_storage_set(&key, &value);
+		Ok(())
+	},
+	ext_http_request(
+		method_data: *const u8,
+		method_len: u32,
+		uri_data: *const u8,
+		uri_len: u32,
+		body_data: *const u8,
+		body_len: u32,
+		written_out: *mut u32
+	) -> *mut u8 => {
+		let method = this.memory.get(method_data, method_len as usize).map_err(|_| UserError("Invalid attempt to determine method in ext_http_request"))?;
+		let uri = this.memory.get(uri_data, uri_len as usize).map_err(|_| UserError("Invalid attempt to determine uri in ext_http_request"))?;
+		let body = this.memory.get(body_data, body_len as usize).map_err(|_| UserError("Invalid attempt to determine body in ext_http_request"))?;
+		let method = String::from_utf8(method).map_err(|_| UserError("Invalid utf8 method in ext_http_request"))?;
+		let uri = String::from_utf8(uri).map_err(|_| UserError("Invalid utf8 uri in ext_http_request"))?;
+		if let Some(response) = ::runtime_io::http_request(&method, &uri, &body) {
+			let offset = this.heap.allocate(response.len() as u32) as u32;
+			this.memory.set(offset, &response).map_err(|_| UserError("Invalid attempt to set memory in ext_http_request"))?;
+			this.memory.write_primitive(written_out, response.len() as u32)
+				.map_err(|_| UserError("Invalid attempt to write written_out in ext_http_request"))?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| UserError("Invalid attempt to write failed written_out in ext_http_request"))?;
+			Ok(0)
+		}
+	},
 	ext_twox_128(data: *const u8, len: u32, out: *mut u8) => {
 		let result = if len == 0 {
 			let hashed = twox_128(&[0u8; 0]);
@@ -604,21 +701,190 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.sandbox_store.memory_teardown(memory_idx)?;
 		Ok(())
 	},
+	gas(amount: u32) => {
+		// injected by the gas metering instrumentation at the start of every metered block
+		if let Some(ref mut fuel_left) = this.fuel_left {
+			if *fuel_left < amount as u64 {
+				this.out_of_fuel = true;
+				Err(UserError("Runtime call has run out of fuel"))?;
+			}
+			*fuel_left -= amount as u64;
+		}
+		Ok(())
+	},
 	=> <'e, E: Externalities<Blake2Hasher> + 'e>
 );
 
+/// Maximal number of idle instances that are kept for every runtime on a thread.
+const MAX_POOLED_INSTANCES: usize = 2;
+/// Maximal number of different runtimes (code + heap pages) that have pooled instances on a thread.
+const MAX_POOLED_RUNTIMES: usize = 2;
+/// Maximal number of idle instances kept by all threads together.
+const MAX_POOLED_INSTANCES_TOTAL: usize = 8;
+
+/// Number of instances currently kept by the pools of all threads, idle or in use.
+static POOLED_INSTANCES_TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+/// Runtime instance that has been used before and could be reused for subsequent calls.
+struct PooledInstance {
+	instance: ModuleRef,
+	memory: MemoryRef,
+	table: Option<TableRef>,
+	/// Content of the initial memory pages (stack and data segments), right after instantiation.
+	initial_memory: Vec<u8>,
+	/// Size of the memory right after instantiation, including the heap pages.
+	initial_size: Pages,
+	/// Values of the mutable globals (e.g. the stack pointer) right after instantiation.
+	initial_globals: Vec<(GlobalRef, RuntimeValue)>,
+	/// Elements of the table right after instantiation.
+	initial_table: Vec<Option<FuncRef>>,
+	/// Whether the instance is counted in `POOLED_INSTANCES_TOTAL`.
+	counted: bool,
+}
+
+impl PooledInstance {
+	/// Snapshot the state of a freshly started instance.
+	fn new(instance: ModuleRef, memory: MemoryRef, table: Option<TableRef>) -> Result<Self> {
+		let initial_memory = memory.get(0, Bytes::from(memory.initial()).0)?;
+		let initial_globals = instance.globals().iter()
+			.filter(|global| global.is_mutable())
+			.map(|global| (global.clone(), global.get()))
+			.collect();
+		let initial_table = match table {
+			Some(ref table) => (0..table.current_size())
+				.map(|i| table.get(i))
+				.collect::<::std::result::Result<_, _>>()?,
+			None => Vec::new(),
+		};
+		Ok(PooledInstance {
+			initial_size: memory.current_size(),
+			instance,
+			memory,
+			table,
+			initial_memory,
+			initial_globals,
+			initial_table,
+			counted: false,
+		})
+	}
+
+	/// Restore the memory, globals and table of the instance to the state they have had right
+	/// after instantiation. Everything up to `heap_end` is considered dirty.
+	///
+	/// Fails if the state can't be restored, e.g. because the memory or the table has grown.
+	fn reset(&self, heap_end: u32) -> Result<()> {
+		if self.memory.current_size() != self.initial_size {
+			return Err(ErrorKind::Runtime.into());
+		}
+		self.memory.set(0, &self.initial_memory)?;
+		let initial_len = self.initial_memory.len();
+		let heap_end = heap_end as usize;
+		if heap_end > initial_len {
+			self.memory.clear(initial_len, 0, heap_end - initial_len)?;
+		}
+
+		for &(ref global, ref value) in &self.initial_globals {
+			global.set(value.clone())?;
+		}
+
+		if let Some(ref table) = self.table {
+			if table.current_size() as usize != self.initial_table.len() {
+				return Err(ErrorKind::Runtime.into());
+			}
+			for (i, element) in self.initial_table.iter().enumerate() {
+				table.set(i as u32, element.clone())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Drop for PooledInstance {
+	fn drop(&mut self) {
+		// only instances that made it into a pool are counted.
+		if self.counted {
+			POOLED_INSTANCES_TOTAL.fetch_sub(1, Ordering::SeqCst);
+		}
+	}
+}
+
+thread_local! {
+	/// Instances are not `Send`, so every thread has its own pool. Keyed by the code hash,
+	/// the number of heap pages the instance memory has been grown by and whether the
+	/// instance is metered.
+	static INSTANCES_POOL: RefCell<HashMap<([u8; 32], usize, bool), Vec<PooledInstance>>> = RefCell::new(HashMap::new());
+}
+
+/// The heap made available to the `version` call of `read_runtime_version`.
+const VERSION_HEAP_PAGES: usize = 64;
+
+/// Read the encoded runtime version of `code` by calling its `version` function in a fresh
+/// instance with empty storage. Returns `None` if `code` isn't valid wasm or has no version.
+pub fn read_runtime_version(code: &[u8]) -> Option<Vec<u8>> {
+	let executor = WasmExecutor::new();
+	let module = executor.prepare_module(code).ok()?;
+	let mut ext = TestExternalities::<Blake2Hasher>::default();
+	executor.call_in_wasm_module(&mut ext, VERSION_HEAP_PAGES, &module, "version", &[]).ok()
+}
+
+/// Reads runtime versions for natively executed runtimes with `read_runtime_version`.
+pub struct WasmRuntimeVersionReader;
+
+impl ::runtime_io::ReadRuntimeVersion for WasmRuntimeVersionReader {
+	fn read_runtime_version(&self, code: &[u8]) -> Option<Vec<u8>> {
+		read_runtime_version(code)
+	}
+}
+
 /// Wasm rust executor for contracts.
 ///
 /// Executes the provided code in a sandboxed wasm runtime.
 #[derive(Debug, Clone)]
 pub struct WasmExecutor {
+	fuel_limit: Option<u64>,
 }
 
 impl WasmExecutor {
 
 	/// Create a new instance.
 	pub fn new() -> Self {
-		WasmExecutor{}
+		WasmExecutor {
+			fuel_limit: None,
+		}
+	}
+
+	/// Create a new instance that meters every call. Calls that execute more than
+	/// `fuel_limit` instructions fail with `ErrorKind::OutOfFuel`.
+	///
+	/// Modules that are passed to this executor must be prepared with `prepare_module`.
+	pub fn with_fuel_limit(fuel_limit: u64) -> Self {
+		WasmExecutor {
+			fuel_limit: Some(fuel_limit),
+		}
+	}
+
+	/// Load the module from the code, injecting the gas metering if the executor is metered.
+	///
+	/// Fails with `ErrorKind::InvalidCode` if the code isn't a valid wasm module.
+	pub fn prepare_module(&self, code: &[u8]) -> Result<Module> {
+		if self.fuel_limit.is_none() {
+			return Module::from_buffer(code)
+				.map_err(|_| ErrorKind::InvalidCode(code.into()).into());
+		}
+
+		let module = ::parity_wasm::elements::deserialize_buffer(code)
+			.map_err(|_| Error::from(ErrorKind::InvalidCode(code.into())))?;
+		// every instruction costs one unit of fuel
+		let rules = ::pwasm_utils::rules::Set::new(1, Default::default());
+		let module = ::pwasm_utils::inject_gas_counter(module, &rules)
+			.map_err(|_| Error::from(ErrorKind::InvalidCode(code.into())))?;
+		Module::from_parity_wasm_module(module)
+			.map_err(|_| ErrorKind::InvalidCode(code.into()).into())
+	}
+
+	/// Returns true if calls of this executor are metered.
+	pub fn is_metered(&self) -> bool {
+		self.fuel_limit.is_some()
 	}
 
 	/// Call a given method in the given code.
@@ -631,7 +897,7 @@ impl WasmExecutor {
 		method: &str,
 		data: &[u8],
 		) -> Result<Vec<u8>> {
-		let module = ::wasmi::Module::from_buffer(code).expect("all modules compiled with rustc are valid wasm code; qed");
+		let module = self.prepare_module(code)?;
 		self.call_in_wasm_module(ext, heap_pages, &module, method, data)
 	}
 
@@ -644,61 +910,149 @@ impl WasmExecutor {
 		method: &str,
 		data: &[u8],
 	) -> Result<Vec<u8>> {
-		// start module instantiation. Don't run 'start' function yet.
-		let intermediate_instance = ModuleInstance::new(
-			module,
-			&ImportsBuilder::new()
-				.with_resolver("env", FunctionExecutor::<E>::resolver())
-		)?;
-
-		// extract a reference to a linear memory, optional reference to a table
-		// and then initialize FunctionExecutor.
-		let memory = intermediate_instance
-			.not_started_instance()
-			.export_by_name("memory")
-			// TODO: with code coming from the blockchain it isn't strictly been compiled with rustc anymore.
-			// these assumptions are probably not true anymore
-			.expect("all modules compiled with rustc should have an export named 'memory'; qed")
-			.as_memory()
-			.expect("in module generated by rustc export named 'memory' should be a memory; qed")
-			.clone();
-		let table: Option<TableRef> = intermediate_instance
-			.not_started_instance()
-			.export_by_name("__indirect_function_table")
-			.and_then(|e| e.as_table().cloned());
-
-		let mut fec = FunctionExecutor::new(memory.clone(), heap_pages, table, ext)?;
-
-		// finish instantiation by running 'start' function (if any).
-		let instance = intermediate_instance.run_start(&mut fec)?;
-		let size = data.len() as u32;
-		let offset = fec.heap.allocate(size);
-		memory.set(offset, &data)?;
-
-		let result = instance.invoke_export(
-			method,
-			&[
-				I32(offset as i32),
-				I32(size as i32)
-			],
-			&mut fec
-		);
-		let returned = match result {
-			Ok(x) => x,
-			Err(e) => {
-				trace!(target: "wasm-executor", "Failed to execute code with {} pages", heap_pages);
-				return Err(e.into())
+		let (instance, memory, _, mut fec) = instantiate(ext, heap_pages, module, self.fuel_limit)?;
+		invoke(&mut fec, &instance, &memory, heap_pages, method, data)
+	}
+
+	/// Call a given method in the given wasm-module runtime, reusing an instance of the
+	/// module that has been created by the previous call on this thread, if any.
+	///
+	/// `code_hash` must uniquely identify the code of the `module`. The instance memory is
+	/// reset after every successful call; instances that have trapped are never reused.
+	pub fn call_in_cached_wasm_module<E: Externalities<Blake2Hasher>>(
+		&self,
+		ext: &mut E,
+		heap_pages: usize,
+		code_hash: [u8; 32],
+		module: &Module,
+		method: &str,
+		data: &[u8],
+	) -> Result<Vec<u8>> {
+		let key = (code_hash, heap_pages, self.is_metered());
+		let pooled = INSTANCES_POOL.with(|pool| pool.borrow_mut().get_mut(&key).and_then(|instances| instances.pop()));
+		let (pooled, result, heap_end) = match pooled {
+			Some(pooled) => {
+				let mut fec = FunctionExecutor::new(
+					pooled.memory.clone(),
+					heap_pages,
+					pooled.table.clone(),
+					ext,
+					self.fuel_limit,
+				)?;
+				let result = invoke(&mut fec, &pooled.instance, &pooled.memory, heap_pages, method, data);
+				(pooled, result, fec.heap.end)
+			},
+			None => {
+				let (instance, memory, table, mut fec) = instantiate(ext, heap_pages, module, self.fuel_limit)?;
+				let pooled = PooledInstance::new(instance, memory, table)?;
+				let result = invoke(&mut fec, &pooled.instance, &pooled.memory, heap_pages, method, data);
+				(pooled, result, fec.heap.end)
 			},
 		};
 
-		if let Some(I64(r)) = returned {
-			let offset = r as u32;
-			let length = (r >> 32) as u32 as usize;
-			memory.get(offset, length)
-				.map_err(|_| ErrorKind::Runtime.into())
-		} else {
-			Err(ErrorKind::InvalidReturn.into())
+		if result.is_ok() && pooled.reset(heap_end).is_ok() {
+			INSTANCES_POOL.with(|pool| {
+				let mut pool = pool.borrow_mut();
+				if !pool.contains_key(&key) && pool.len() >= MAX_POOLED_RUNTIMES {
+					pool.clear();
+				}
+				let instances = pool.entry(key).or_insert_with(Vec::new);
+				if instances.len() >= MAX_POOLED_INSTANCES {
+					return;
+				}
+				let mut pooled = pooled;
+				if !pooled.counted {
+					if POOLED_INSTANCES_TOTAL.fetch_add(1, Ordering::SeqCst) >= MAX_POOLED_INSTANCES_TOTAL {
+						POOLED_INSTANCES_TOTAL.fetch_sub(1, Ordering::SeqCst);
+						return;
+					}
+					pooled.counted = true;
+				}
+				instances.push(pooled);
+			});
 		}
+
+		result
+	}
+}
+
+/// Instantiate the module and run its 'start' function (if any).
+fn instantiate<'e, E: Externalities<Blake2Hasher>>(
+	ext: &'e mut E,
+	heap_pages: usize,
+	module: &Module,
+	fuel_limit: Option<u64>,
+) -> Result<(ModuleRef, MemoryRef, Option<TableRef>, FunctionExecutor<'e, E>)> {
+	// start module instantiation. Don't run 'start' function yet.
+	let intermediate_instance = ModuleInstance::new(
+		module,
+		&ImportsBuilder::new()
+			.with_resolver("env", FunctionExecutor::<E>::resolver())
+	)?;
+
+	// extract a reference to a linear memory, optional reference to a table
+	// and then initialize FunctionExecutor.
+	let memory = intermediate_instance
+		.not_started_instance()
+		.export_by_name("memory")
+		// TODO: with code coming from the blockchain it isn't strictly been compiled with rustc anymore.
+		// these assumptions are probably not true anymore
+		.expect("all modules compiled with rustc should have an export named 'memory'; qed")
+		.as_memory()
+		.expect("in module generated by rustc export named 'memory' should be a memory; qed")
+		.clone();
+	let table: Option<TableRef> = intermediate_instance
+		.not_started_instance()
+		.export_by_name("__indirect_function_table")
+		.and_then(|e| e.as_table().cloned());
+
+	let mut fec = FunctionExecutor::new(memory.clone(), heap_pages, table.clone(), ext, fuel_limit)?;
+
+	// finish instantiation by running 'start' function (if any).
+	let instance = intermediate_instance.run_start(&mut fec)?;
+	Ok((instance, memory, table, fec))
+}
+
+/// Call a given method of the started instance.
+fn invoke<E: Externalities<Blake2Hasher>>(
+	fec: &mut FunctionExecutor<E>,
+	instance: &ModuleRef,
+	memory: &MemoryRef,
+	heap_pages: usize,
+	method: &str,
+	data: &[u8],
+) -> Result<Vec<u8>> {
+	let size = data.len() as u32;
+	let offset = fec.heap.allocate(size);
+	memory.set(offset, &data)?;
+
+	let result = instance.invoke_export(
+		method,
+		&[
+			I32(offset as i32),
+			I32(size as i32)
+		],
+		fec
+	);
+	let returned = match result {
+		Ok(x) => x,
+		Err(_) if fec.out_of_fuel => {
+			trace!(target: "executor", "Runtime call {} has run out of fuel", method);
+			return Err(ErrorKind::OutOfFuel.into())
+		},
+		Err(e) => {
+			trace!(target: "executor", "Failed to execute code with {} pages", heap_pages);
+			return Err(e.into())
+		},
+	};
+
+	if let Some(I64(r)) = returned {
+		let offset = r as u32;
+		let length = (r >> 32) as u32 as usize;
+		memory.get(offset, length)
+			.map_err(|_| ErrorKind::Runtime.into())
+	} else {
+		Err(ErrorKind::InvalidReturn.into())
 	}
 }
 
@@ -706,7 +1060,7 @@ impl WasmExecutor {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use codec::Encode;
+	use codec::{Decode, Encode};
 	use state_machine::TestExternalities;
 
 	#[test]
@@ -839,6 +1193,122 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn cached_instance_is_reset_and_reused() {
+		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
+		let module = Module::from_buffer(&test_code[..]).unwrap();
+		let code_hash = blake2_256(&test_code[..]);
+		let executor = WasmExecutor::new();
+
+		for _ in 0..3 {
+			let mut ext = TestExternalities::default();
+			ext.set_storage(b"foo".to_vec(), b"bar".to_vec());
+			let output = executor.call_in_cached_wasm_module(&mut ext, 8, code_hash, &module, "test_data_in", b"Hello world").unwrap();
+			assert_eq!(output, b"all ok!".to_vec());
+
+			let expected = TestExternalities::new(map![
+				b"input".to_vec() => b"Hello world".to_vec(),
+				b"foo".to_vec() => b"bar".to_vec(),
+				b"baz".to_vec() => b"bar".to_vec()
+			]);
+			assert_eq!(ext, expected);
+		}
+
+		INSTANCES_POOL.with(|pool| assert_eq!(pool.borrow().get(&(code_hash, 8, false)).map(Vec::len), Some(1)));
+	}
+
+	#[test]
+	fn trapped_instance_is_not_reused() {
+		let mut ext = TestExternalities::default();
+		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
+		let module = Module::from_buffer(&test_code[..]).unwrap();
+		let code_hash = blake2_256(&test_code[..]);
+
+		let output = WasmExecutor::new().call_in_cached_wasm_module(&mut ext, 8, code_hash, &module, "test_panic", &[]);
+		assert!(output.is_err());
+
+		INSTANCES_POOL.with(|pool| assert!(pool.borrow().get(&(code_hash, 8, false)).is_none()));
+	}
+
+	#[test]
+	fn metered_call_fails_when_out_of_fuel() {
+		let mut ext = TestExternalities::default();
+		let code = ::wabt::wat2wasm(r#"
+		(module
+			(memory (export "memory") 1)
+			(func (export "test_return") (param i32 i32) (result i64)
+				i64.const 0
+			)
+			(func (export "test_loop") (param i32 i32) (result i64)
+				(loop $l (br $l))
+				i64.const 0
+			)
+		)
+		"#).unwrap();
+
+		let executor = WasmExecutor::with_fuel_limit(1_000);
+		assert_eq!(executor.call(&mut ext, 1, &code, "test_return", &[]).unwrap(), vec![0u8; 0]);
+		match executor.call(&mut ext, 1, &code, "test_loop", &[]) {
+			Err(Error(ErrorKind::OutOfFuel, _)) => (),
+			result => panic!("unexpected result: {:?}", result),
+		}
+	}
+
+	#[test]
+	fn invalid_code_is_an_error() {
+		let mut ext = TestExternalities::default();
+		for executor in &[WasmExecutor::new(), WasmExecutor::with_fuel_limit(1_000)] {
+			match executor.call(&mut ext, 1, b"not wasm", "test_return", &[]) {
+				Err(Error(ErrorKind::InvalidCode(ref code), _)) if code == b"not wasm" => (),
+				result => panic!("unexpected result: {:?}", result),
+			}
+		}
+	}
+
+	#[test]
+	fn runtime_fuel_limit_is_respected() {
+		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
+
+		let mut ext = TestExternalities::default();
+		let output = WasmExecutor::with_fuel_limit(10_000_000).call(&mut ext, 8, &test_code[..], "test_data_in", b"Hello world").unwrap();
+		assert_eq!(output, b"all ok!".to_vec());
+
+		let mut ext = TestExternalities::default();
+		match WasmExecutor::with_fuel_limit(10).call(&mut ext, 8, &test_code[..], "test_data_in", b"Hello world") {
+			Err(Error(ErrorKind::OutOfFuel, _)) => (),
+			result => panic!("unexpected result: {:?}", result),
+		}
+	}
+
+	#[test]
+	fn storage_batch_get_should_work() {
+		let mut ext = TestExternalities::default();
+		ext.set_storage(b"foo".to_vec(), b"bar".to_vec());
+		ext.set_storage(b"baz".to_vec(), b"qux".to_vec());
+		let code = ::wabt::wat2wasm(r#"
+		(module
+			(import "env" "ext_storage_batch_get" (func $ext_storage_batch_get (param i32 i32 i32) (result i32)))
+			(memory (export "memory") 1)
+			(func (export "test_storage_batch") (param $data i32) (param $len i32) (result i64)
+				(local $ptr i32)
+				;; written_out is placed at the address 0
+				(set_local $ptr (call $ext_storage_batch_get (get_local $data) (get_local $len) (i32.const 0)))
+				(i64.or
+					(i64.extend_u/i32 (get_local $ptr))
+					(i64.shl (i64.extend_u/i32 (i32.load (i32.const 0))) (i64.const 32))
+				)
+			)
+		)
+		"#).unwrap();
+
+		let keys = vec![b"foo".to_vec(), b"missing".to_vec(), b"baz".to_vec()];
+		let output = WasmExecutor::new().call(&mut ext, 1, &code, "test_storage_batch", &keys.encode()).unwrap();
+		assert_eq!(
+			Vec::<Option<Vec<u8>>>::decode(&mut &output[..]).unwrap(),
+			vec![Some(b"bar".to_vec()), None, Some(b"qux".to_vec())],
+		);
+	}
+
 	#[test]
 	fn enumerated_trie_root_should_work() {
 		let mut ext = TestExternalities::<Blake2Hasher>::default();
@@ -27,6 +27,11 @@ extern crate sr_primitives as runtime_primitives;
 extern crate substrate_primitives;
 extern crate tokio;
 extern crate parity_codec as codec;
+extern crate substrate_consensus_common as consensus_common;
+#[macro_use]
+extern crate substrate_telemetry;
+#[macro_use]
+extern crate slog;	// needed until we can reexport `slog_info` from `substrate_telemetry`
 
 #[macro_use]
 extern crate log;
@@ -41,10 +46,13 @@ extern crate parking_lot;
 extern crate substrate_keyring as keyring;
 
 use futures::prelude::*;
+use futures::future::{self, Either};
 use futures::stream::Fuse;
 use futures::sync::mpsc;
 use client::{Client, ImportNotifications, backend::Backend, CallExecutor};
 use codec::{Encode, Decode};
+use consensus_common::Signer;
+use substrate_telemetry::{CONSENSUS_INFO, CONSENSUS_DEBUG};
 use runtime_primitives::traits::{As, NumberFor, Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
 use substrate_primitives::{ed25519, H256, AuthorityId, Blake2Hasher};
@@ -71,8 +79,8 @@ pub struct Config {
 	/// The voters.
 	// TODO: make dynamic
 	pub voters: Vec<AuthorityId>,
-	/// The local signing key.
-	pub local_key: Option<Arc<ed25519::Pair>>,
+	/// The local signer.
+	pub local_key: Option<Arc<Signer>>,
 }
 
 /// Errors that can occur while voting in GRANDPA.
@@ -333,7 +341,7 @@ fn checked_message_stream<Block: BlockT, S>(inner: S, voters: Vec<AuthorityId>)
 }
 
 fn outgoing_messages<Block: BlockT, N: Network>(
-	local_key: Option<Arc<ed25519::Pair>>,
+	local_key: Option<Arc<Signer>>,
 	voters: Vec<AuthorityId>,
 	round: u64,
 	network: N,
@@ -348,22 +356,31 @@ fn outgoing_messages<Block: BlockT, N: Network>(
 
 	let (tx, rx) = mpsc::unbounded();
 	let rx = rx
-		.map(move |msg: Message<Block>| {
+		.and_then(move |msg: Message<Block>| {
 			// when locals exist. sign messages on import
 			if let Some((ref pair, local_id)) = locals {
 				let encoded = msg.encode();
-				let signature = pair.sign(&encoded[..]);
-				let signed = SignedMessage::<Block> {
-					message: msg,
-					signature,
-					id: local_id,
-				};
-
-				// forward to network.
-				network.send_message(round, signed.encode());
-				Some(signed)
+				let network = network.clone();
+				Either::A(pair.sign(&encoded[..]).then(move |signature| {
+					let signature = match signature {
+						Ok(signature) => signature,
+						Err(e) => {
+							warn!(target: "afg", "Unable to sign outgoing message in round {}: {:?}", round, e);
+							return Ok(None);
+						}
+					};
+					let signed = SignedMessage::<Block> {
+						message: msg,
+						signature,
+						id: local_id,
+					};
+
+					// forward to network.
+					network.send_message(round, signed.encode());
+					Ok(Some(signed))
+				}))
 			} else {
-				None
+				Either::B(future::ok(None))
 			}
 		})
 		.filter_map(|x| x)
@@ -458,6 +475,8 @@ impl<B, E, Block: BlockT<Hash=H256>, N> voter::Environment<Block::Hash> for Envi
 		use client::BlockchainEvents;
 		use tokio::timer::Delay;
 
+		telemetry!(CONSENSUS_DEBUG; "afg.round_started"; "round" => round);
+
 		let now = Instant::now();
 		let prevote_timer = Delay::new(now + self.config.gossip_duration * 2);
 		let precommit_timer = Delay::new(now + self.config.gossip_duration * 4);
@@ -503,6 +522,11 @@ impl<B, E, Block: BlockT<Hash=H256>, N> voter::Environment<Block::Hash> for Envi
 	}
 
 	fn completed(&self, round: u64, state: RoundState<Block::Hash>) -> Result<(), Self::Error> {
+		telemetry!(CONSENSUS_DEBUG; "afg.round_completed";
+			"round" => round,
+			"finalized" => ?state.finalized
+		);
+
 		let encoded_state = (round, state).encode();
 		if let Err(e) = self.inner.backend()
 			.insert_aux(&[(LAST_COMPLETED_KEY, &encoded_state[..])], &[])
@@ -518,6 +542,11 @@ impl<B, E, Block: BlockT<Hash=H256>, N> voter::Environment<Block::Hash> for Envi
 		// TODO: don't unconditionally notify.
 		if let Err(e) = self.inner.finalize_block(BlockId::Hash(hash), true) {
 			warn!(target: "afg", "Error applying finality to block {:?}: {:?}", (hash, number), e);
+		} else {
+			telemetry!(CONSENSUS_INFO; "afg.finalized";
+				"number" => number,
+				"hash" => ?hash
+			);
 		}
 
 		// we return without error in all cases because not being able to finalize is
@@ -684,7 +713,7 @@ mod tests {
 				Config {
 					gossip_duration: TEST_GOSSIP_DURATION,
 					voters: voters.clone(),
-					local_key: Some(Arc::new(key.clone().into())),
+					local_key: Some(Arc::new(ed25519::Pair::from(key.clone()))),
 				},
 				client,
 				voters.iter().map(|&id| (id, 1)).collect(),
@@ -729,7 +758,7 @@ mod tests {
 		let mut runtime = current_thread::Runtime::new().unwrap();
 		let all_peers = peers.iter()
 			.cloned()
-			.map(|(id, key)| (id, Some(Arc::new(key.into()))))
+			.map(|(id, key)| (id, Some(Arc::new(ed25519::Pair::from(key)) as Arc<Signer>)))
 			.chain(::std::iter::once((3, None)));
 
 		for (peer_id, local_key) in all_peers {
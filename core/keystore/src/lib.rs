@@ -24,6 +24,7 @@ extern crate subtle;
 extern crate rand;
 extern crate serde_json;
 extern crate hex;
+extern crate parking_lot;
 
 #[macro_use]
 extern crate serde_derive;
@@ -39,10 +40,19 @@ use std::path::PathBuf;
 use std::fs::{self, File};
 use std::io::{self, Write};
 
+use parking_lot::RwLock;
+
 use substrate_primitives::{hashing::blake2_256, ed25519::{Pair, Public, PKCS_LEN}};
 
 pub use crypto::KEY_ITERATIONS;
 
+/// Default scrypt cost parameter used for newly generated keys.
+pub const SCRYPT_N: u32 = 1 << 14;
+/// Default scrypt parallelization parameter used for newly generated keys.
+pub const SCRYPT_P: u32 = 1;
+/// Default scrypt block size parameter used for newly generated keys.
+pub const SCRYPT_R: u32 = 8;
+
 error_chain! {
 	foreign_links {
 		Io(io::Error);
@@ -58,12 +68,42 @@ error_chain! {
 			description("Invalid PKCS#8 data"),
 			display("Invalid PKCS#8 data"),
 		}
+		InvalidKdfParams {
+			description("Invalid key derivation parameters"),
+			display("Invalid key derivation parameters"),
+		}
 	}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InvalidPassword;
 
+/// Key derivation function used to turn a password into an encryption key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Kdf {
+	/// PBKDF2 with the iteration count stored alongside the key.
+	Pbkdf2,
+	/// scrypt with the given parameters.
+	Scrypt { n: u32, p: u32, r: u32 },
+}
+
+impl Default for Kdf {
+	// key files written before the `kdf` field was introduced use PBKDF2.
+	fn default() -> Self {
+		Kdf::Pbkdf2
+	}
+}
+
+impl Kdf {
+	fn derive(&self, password: &str, salt: &[u8; 32], iterations: u32) -> Result<(Vec<u8>, Vec<u8>)> {
+		match *self {
+			Kdf::Pbkdf2 => Ok(crypto::derive_key_iterations(password.as_bytes(), salt, iterations)),
+			Kdf::Scrypt { n, p, r } => crypto::scrypt::derive_key(password.as_bytes(), salt, n, p, r)
+				.map_err(|_| ErrorKind::InvalidKdfParams.into()),
+		}
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 struct EncryptedKey {
 	mac: [u8; 32],
@@ -71,10 +111,23 @@ struct EncryptedKey {
 	ciphertext: Vec<u8>, // TODO: switch to fixed-size when serde supports
 	iv: [u8; 16],
 	iterations: u32,
+	#[serde(default)]
+	kdf: Kdf,
 }
 
 impl EncryptedKey {
 	fn encrypt(plain: &[u8; PKCS_LEN], password: &str, iterations: u32) -> Self {
+		Self::encrypt_with_kdf(plain, password, Kdf::Pbkdf2, iterations)
+			.expect("PBKDF2 derivation never fails; qed")
+	}
+
+	fn encrypt_scrypt(plain: &[u8; PKCS_LEN], password: &str) -> Self {
+		let kdf = Kdf::Scrypt { n: SCRYPT_N, p: SCRYPT_P, r: SCRYPT_R };
+		Self::encrypt_with_kdf(plain, password, kdf, 0)
+			.expect("default scrypt parameters are valid; qed")
+	}
+
+	fn encrypt_with_kdf(plain: &[u8; PKCS_LEN], password: &str, kdf: Kdf, iterations: u32) -> Result<Self> {
 		use rand::{Rng, OsRng};
 
 		let mut rng = OsRng::new().expect("OS Randomness available on all supported platforms; qed");
@@ -84,7 +137,7 @@ impl EncryptedKey {
 
 		// two parts of derived key
 		// DK = [ DK[0..15] DK[16..31] ] = [derived_left_bits, derived_right_bits]
-		let (derived_left_bits, derived_right_bits) = crypto::derive_key_iterations(password.as_bytes(), &salt, iterations);
+		let (derived_left_bits, derived_right_bits) = kdf.derive(password, &salt, iterations)?;
 
 		// preallocated (on-stack in case of `Secret`) buffer to hold cipher
 		// length = length(plain) as we are using CTR-approach
@@ -97,18 +150,18 @@ impl EncryptedKey {
 		// Blake2_256(DK[16..31] ++ <ciphertext>), where DK[16..31] - derived_right_bits
 		let mac = blake2_256(&crypto::derive_mac(&derived_right_bits, &*ciphertext));
 
-		EncryptedKey {
+		Ok(EncryptedKey {
 			salt,
 			iv,
 			mac,
 			iterations,
 			ciphertext,
-		}
+			kdf,
+		})
 	}
 
 	fn decrypt(&self, password: &str) -> Result<[u8; PKCS_LEN]> {
-		let (derived_left_bits, derived_right_bits) =
-			crypto::derive_key_iterations(password.as_bytes(), &self.salt, self.iterations);
+		let (derived_left_bits, derived_right_bits) = self.kdf.derive(password, &self.salt, self.iterations)?;
 
 		let mac = blake2_256(&crypto::derive_mac(&derived_right_bits, &self.ciphertext));
 
@@ -125,30 +178,160 @@ impl EncryptedKey {
 
 type Seed = [u8; 32];
 
+// PKCS#8 v2 framing of an ed25519 key pair, as produced by `Pair::generate_with_pkcs8`.
+const PKCS8_PREFIX: [u8; 16] = [0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20];
+const PKCS8_PUBLIC_PREFIX: [u8; 5] = [0xa1, 0x23, 0x03, 0x21, 0x00];
+
+fn seed_to_pkcs8(seed: &Seed, public: &Public) -> [u8; PKCS_LEN] {
+	let mut pkcs_bytes = [0; PKCS_LEN];
+	let (prefix, rest) = pkcs_bytes.split_at_mut(PKCS8_PREFIX.len());
+	let (secret, rest) = rest.split_at_mut(32);
+	let (public_prefix, public_bytes) = rest.split_at_mut(PKCS8_PUBLIC_PREFIX.len());
+
+	prefix.copy_from_slice(&PKCS8_PREFIX);
+	secret.copy_from_slice(&seed[..]);
+	public_prefix.copy_from_slice(&PKCS8_PUBLIC_PREFIX);
+	public_bytes.copy_from_slice(&public.0[..]);
+	pkcs_bytes
+}
+
+/// Identifier of the purpose a key is used for.
+pub type KeyTypeId = [u8; 4];
+
+/// Known key types.
+pub mod key_types {
+	use super::KeyTypeId;
+
+	/// Key used for block authorship.
+	pub const AUTHORING: KeyTypeId = *b"auth";
+	/// Key used for BFT consensus and finality voting.
+	pub const FINALITY: KeyTypeId = *b"fina";
+	/// Key used for parachain candidate validation.
+	pub const PARACHAIN_VALIDATION: KeyTypeId = *b"para";
+	/// Key types that make up a session key bundle, in registration order.
+	pub const SESSION: &[KeyTypeId] = &[AUTHORING, FINALITY, PARACHAIN_VALIDATION];
+}
+
+/// Public keys of a freshly generated session key bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionKeys(pub Vec<(KeyTypeId, Public)>);
+
+impl SessionKeys {
+	/// Get the public key of the given type, if part of the bundle.
+	pub fn get(&self, key_type: KeyTypeId) -> Option<&Public> {
+		self.0.iter().find(|k| k.0 == key_type).map(|k| &k.1)
+	}
+
+	/// Concatenation of the public keys in bundle order, suitable for on-chain registration.
+	pub fn to_raw_public(&self) -> Vec<u8> {
+		self.0.iter().flat_map(|k| (k.1).0.iter().cloned()).collect()
+	}
+}
+
 /// Key store.
+///
+/// Keys are kept encrypted on disk. Keys that have been unlocked with their password
+/// are cached in memory until they are explicitly locked again.
 pub struct Store {
 	path: PathBuf,
 	additional: HashMap<Public, Seed>,
+	unlocked: RwLock<HashMap<Public, [u8; PKCS_LEN]>>,
 }
 
 impl Store {
 	/// Create a new store at the given path.
 	pub fn open(path: PathBuf) -> Result<Self> {
 		fs::create_dir_all(&path)?;
-		Ok(Store { path, additional: HashMap::new() })
+		Ok(Store { path, additional: HashMap::new(), unlocked: RwLock::new(HashMap::new()) })
 	}
 
 	/// Generate a new key, placing it into the store.
+	///
+	/// The key is encrypted with a scrypt-derived key. It is not unlocked.
 	pub fn generate(&self, password: &str) -> Result<Pair> {
+		self.generate_inner(None, password)
+	}
+
+	/// Generate a new key of the given type, placing it into the store.
+	pub fn generate_by_type(&self, key_type: KeyTypeId, password: &str) -> Result<Pair> {
+		self.generate_inner(Some(key_type), password)
+	}
+
+	/// Generate a new key for every session key type, unlock them and make them the active keys
+	/// of their type. The previous keys are kept in the store.
+	///
+	/// Returns the public keys, which are to be registered on-chain.
+	pub fn rotate_keys(&self, password: &str) -> Result<SessionKeys> {
+		let mut keys = Vec::with_capacity(key_types::SESSION.len());
+		for key_type in key_types::SESSION {
+			let public = self.generate_by_type(*key_type, password)?.public();
+			self.unlock_by_type(*key_type, &public, password)?;
+			self.set_active_key(*key_type, &public)?;
+			keys.push((*key_type, public));
+		}
+
+		Ok(SessionKeys(keys))
+	}
+
+	/// Place a key of the given type, created from the given seed, into the store and make it
+	/// the active key of its type.
+	pub fn insert_by_type(&self, key_type: KeyTypeId, seed: &[u8; 32], password: &str) -> Result<Pair> {
+		let public = Pair::from_seed(seed).public();
+		let pkcs_bytes = seed_to_pkcs8(seed, &public);
+		let pair = Pair::from_pkcs8(&pkcs_bytes[..]).map_err(|_| Error::from(ErrorKind::InvalidPKCS8))?;
+
+		self.write_key(Some(key_type), &public, &pkcs_bytes, password)?;
+		self.set_active_key(key_type, &public)?;
+		Ok(pair)
+	}
+
+	/// Get the active key of the given type, i.e. the last one generated by `rotate_keys` or
+	/// placed with `insert_by_type`, if it is still stored.
+	pub fn active_key(&self, key_type: KeyTypeId) -> Result<Option<Public>> {
+		let name = match fs::read_to_string(self.active_key_path(key_type)) {
+			Ok(name) => name,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+
+		match hex::decode(name.trim()) {
+			Ok(ref hex) if hex.len() == 32 => {
+				let public = Public::from_slice(&hex[..]);
+
+				if self.key_file_path(Some(key_type), &public).exists() {
+					Ok(Some(public))
+				} else {
+					Ok(None)
+				}
+			}
+			_ => Ok(None),
+		}
+	}
+
+	fn set_active_key(&self, key_type: KeyTypeId, public: &Public) -> Result<()> {
+		let mut file = File::create(self.active_key_path(key_type))?;
+		file.write_all(hex::encode(public.as_slice()).as_bytes())?;
+
+		file.flush()?;
+
+		Ok(())
+	}
+
+	fn generate_inner(&self, key_type: Option<KeyTypeId>, password: &str) -> Result<Pair> {
 		let (pair, pkcs_bytes) = Pair::generate_with_pkcs8();
-		let key_file = EncryptedKey::encrypt(&pkcs_bytes, password, KEY_ITERATIONS as u32);
+		self.write_key(key_type, &pair.public(), &pkcs_bytes, password)?;
+		Ok(pair)
+	}
 
-		let mut file = File::create(self.key_file_path(&pair.public()))?;
+	fn write_key(&self, key_type: Option<KeyTypeId>, public: &Public, pkcs_bytes: &[u8; PKCS_LEN], password: &str) -> Result<()> {
+		let key_file = EncryptedKey::encrypt_scrypt(pkcs_bytes, password);
+
+		let mut file = File::create(self.key_file_path(key_type, public))?;
 		::serde_json::to_writer(&file, &key_file)?;
 
 		file.flush()?;
 
-		Ok(pair)
+		Ok(())
 	}
 
 	/// Create a new key from seed. Do not place it into the store.
@@ -180,27 +363,97 @@ impl Store {
 			let pair = Pair::from_seed(seed);
 			return Ok(pair);
 		}
-		let path = self.key_file_path(public);
+
+		self.decrypt_key(None, public, password).map(|(pair, _)| pair)
+	}
+
+	/// Load a key file of the given type with given public key.
+	pub fn load_by_type(&self, key_type: KeyTypeId, public: &Public, password: &str) -> Result<Pair> {
+		self.decrypt_key(Some(key_type), public, password).map(|(pair, _)| pair)
+	}
+
+	/// Decrypt the key with given public key and keep it in memory, so that it can
+	/// later be retrieved with `unlocked` without the password.
+	pub fn unlock(&self, public: &Public, password: &str) -> Result<Pair> {
+		if let Some(ref seed) = self.additional.get(public) {
+			return Ok(Pair::from_seed(seed));
+		}
+
+		self.unlock_inner(None, public, password)
+	}
+
+	/// Decrypt the key of the given type with given public key and keep it in memory.
+	pub fn unlock_by_type(&self, key_type: KeyTypeId, public: &Public, password: &str) -> Result<Pair> {
+		self.unlock_inner(Some(key_type), public, password)
+	}
+
+	fn unlock_inner(&self, key_type: Option<KeyTypeId>, public: &Public, password: &str) -> Result<Pair> {
+		let (pair, pkcs_bytes) = self.decrypt_key(key_type, public, password)?;
+		self.unlocked.write().insert(public.clone(), pkcs_bytes);
+		Ok(pair)
+	}
+
+	fn decrypt_key(&self, key_type: Option<KeyTypeId>, public: &Public, password: &str) -> Result<(Pair, [u8; PKCS_LEN])> {
+		let path = self.key_file_path(key_type, public);
 		let file = File::open(path)?;
 
 		let encrypted_key: EncryptedKey = ::serde_json::from_reader(&file)?;
 		let pkcs_bytes = encrypted_key.decrypt(password)?;
+		let pair = Pair::from_pkcs8(&pkcs_bytes[..]).map_err(|_| Error::from(ErrorKind::InvalidPKCS8))?;
+
+		Ok((pair, pkcs_bytes))
+	}
 
-		Pair::from_pkcs8(&pkcs_bytes[..]).map_err(|_| ErrorKind::InvalidPKCS8.into())
+	/// Remove the key with given public key from the in-memory cache of unlocked keys.
+	/// Returns `true` if the key was unlocked.
+	pub fn lock(&self, public: &Public) -> bool {
+		self.unlocked.write().remove(public).is_some()
 	}
 
-	/// Get public keys of all stored keys.
+	/// Remove all keys from the in-memory cache of unlocked keys.
+	pub fn lock_all(&self) {
+		self.unlocked.write().clear();
+	}
+
+	/// Get a previously unlocked key. Keys created from a seed are always unlocked.
+	pub fn unlocked(&self, public: &Public) -> Option<Pair> {
+		if let Some(ref seed) = self.additional.get(public) {
+			return Some(Pair::from_seed(seed));
+		}
+
+		self.unlocked.read().get(public).and_then(|pkcs_bytes| Pair::from_pkcs8(&pkcs_bytes[..]).ok())
+	}
+
+	/// Whether the key with given public key is currently unlocked.
+	pub fn is_unlocked(&self, public: &Public) -> bool {
+		self.additional.contains_key(public) || self.unlocked.read().contains_key(public)
+	}
+
+	/// Get public keys of all stored untyped keys.
 	pub fn contents(&self) -> Result<Vec<Public>> {
 		let mut public_keys: Vec<Public> = self.additional.keys().cloned().collect();
+		public_keys.extend(self.stored_keys(None)?);
+		Ok(public_keys)
+	}
+
+	/// Get public keys of all stored keys of the given type.
+	pub fn contents_by_type(&self, key_type: KeyTypeId) -> Result<Vec<Public>> {
+		self.stored_keys(Some(key_type))
+	}
+
+	fn stored_keys(&self, key_type: Option<KeyTypeId>) -> Result<Vec<Public>> {
+		let prefix = key_type.map(|t| hex::encode(&t[..])).unwrap_or_default();
+
+		let mut public_keys = Vec::new();
 		for entry in fs::read_dir(&self.path)? {
 			let entry = entry?;
 			let path = entry.path();
 
 			// skip directories and non-unicode file names (hex is unicode)
 			if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-				if name.len() != 64 { continue }
+				if name.len() != prefix.len() + 64 || !name.starts_with(&prefix) { continue }
 
-				match hex::decode(name) {
+				match hex::decode(&name[prefix.len()..]) {
 					Ok(ref hex) if hex.len() == 32 => {
 						let mut buf = [0; 32];
 						buf.copy_from_slice(&hex[..]);
@@ -215,9 +468,20 @@ impl Store {
 		Ok(public_keys)
 	}
 
-	fn key_file_path(&self, public: &Public) -> PathBuf {
+	// the active key of a type is recorded as its hex public key in `active-hex(key_type)`.
+	fn active_key_path(&self, key_type: KeyTypeId) -> PathBuf {
 		let mut buf = self.path.clone();
-		buf.push(hex::encode(public.as_slice()));
+		buf.push(format!("active-{}", hex::encode(&key_type[..])));
+		buf
+	}
+
+	// untyped keys are stored as `hex(public)`, typed keys as `hex(key_type) ++ hex(public)`.
+	fn key_file_path(&self, key_type: Option<KeyTypeId>, public: &Public) -> PathBuf {
+		let mut name = key_type.map(|t| hex::encode(&t[..])).unwrap_or_default();
+		name.push_str(&hex::encode(public.as_slice()));
+
+		let mut buf = self.path.clone();
+		buf.push(name);
 		buf
 	}
 }
@@ -255,6 +519,48 @@ mod tests {
 		assert!(encrypted_key.decrypt("thepassword").is_err());
 	}
 
+	#[test]
+	fn scrypt_encrypt_and_decrypt() {
+		let plain = [1; PKCS_LEN];
+		let encrypted_key = EncryptedKey::encrypt_scrypt(&plain, "thepassword");
+
+		assert_eq!(encrypted_key.decrypt("thepassword").unwrap()[..], plain[..]);
+		assert!(encrypted_key.decrypt("thepassword2").is_err());
+	}
+
+	#[test]
+	fn pbkdf2_key_file_without_kdf_field_decrypts() {
+		let plain = [1; PKCS_LEN];
+		let encrypted_key = EncryptedKey::encrypt(&plain, "thepassword", KEY_ITERATIONS as u32);
+
+		let mut json = ::serde_json::to_value(&encrypted_key).unwrap();
+		json.as_object_mut().unwrap().remove("kdf");
+		let decoded: EncryptedKey = ::serde_json::from_value(json).unwrap();
+
+		assert_eq!(decoded.kdf, Kdf::Pbkdf2);
+		assert_eq!(decoded.decrypt("thepassword").unwrap()[..], plain[..]);
+	}
+
+	#[test]
+	fn lock_and_unlock() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let key = store.generate("thepassword").unwrap();
+		assert!(!store.is_unlocked(&key.public()));
+		assert!(store.unlocked(&key.public()).is_none());
+
+		assert!(store.unlock(&key.public(), "notthepassword").is_err());
+		assert!(!store.is_unlocked(&key.public()));
+
+		store.unlock(&key.public(), "thepassword").unwrap();
+		assert_eq!(store.unlocked(&key.public()).unwrap().public(), key.public());
+
+		assert!(store.lock(&key.public()));
+		assert!(!store.lock(&key.public()));
+		assert!(store.unlocked(&key.public()).is_none());
+	}
+
 	#[test]
 	fn basic_store() {
 		let temp_dir = TempDir::new("keystore").unwrap();
@@ -272,6 +578,64 @@ mod tests {
 		assert_eq!(store.contents().unwrap()[0], key.public());
 	}
 
+	#[test]
+	fn typed_keys_are_kept_apart() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let untyped = store.generate("thepassword").unwrap();
+		let authoring = store.generate_by_type(key_types::AUTHORING, "thepassword").unwrap();
+
+		assert_eq!(store.contents().unwrap(), vec![untyped.public()]);
+		assert_eq!(store.contents_by_type(key_types::AUTHORING).unwrap(), vec![authoring.public()]);
+		assert!(store.contents_by_type(key_types::FINALITY).unwrap().is_empty());
+
+		let loaded = store.load_by_type(key_types::AUTHORING, &authoring.public(), "thepassword").unwrap();
+		assert_eq!(loaded.public(), authoring.public());
+		assert!(store.load_by_type(key_types::FINALITY, &authoring.public(), "thepassword").is_err());
+		assert!(store.load(&authoring.public(), "thepassword").is_err());
+	}
+
+	#[test]
+	fn insert_key_from_seed() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let seed = [42; 32];
+		let pair = store.insert_by_type(key_types::FINALITY, &seed, "thepassword").unwrap();
+		assert_eq!(pair.public(), Pair::from_seed(&seed).public());
+
+		assert_eq!(store.contents_by_type(key_types::FINALITY).unwrap(), vec![pair.public()]);
+		assert_eq!(store.active_key(key_types::FINALITY).unwrap(), Some(pair.public()));
+		let loaded = store.load_by_type(key_types::FINALITY, &pair.public(), "thepassword").unwrap();
+		assert_eq!(loaded.sign(b"message"), pair.sign(b"message"));
+	}
+
+	#[test]
+	fn rotate_keys_generates_session_bundle() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let keys = store.rotate_keys("thepassword").unwrap();
+		assert_eq!(keys.0.len(), key_types::SESSION.len());
+		assert_eq!(keys.to_raw_public().len(), 32 * key_types::SESSION.len());
+
+		for key_type in key_types::SESSION {
+			let public = keys.get(*key_type).unwrap();
+			assert_eq!(store.contents_by_type(*key_type).unwrap(), vec![public.clone()]);
+			assert!(store.is_unlocked(public));
+		}
+
+		let rotated = store.rotate_keys("thepassword").unwrap();
+		assert!(rotated.0.iter().all(|k| !keys.0.contains(k)));
+		assert_eq!(store.contents_by_type(key_types::AUTHORING).unwrap().len(), 2);
+
+		// the new keys are the active ones, whatever order the keys are listed in.
+		for key_type in key_types::SESSION {
+			assert_eq!(store.active_key(*key_type).unwrap().as_ref(), rotated.get(*key_type));
+		}
+	}
+
 	#[test]
 	fn test_generate_from_seed() {
 		let temp_dir = TempDir::new("keystore").unwrap();
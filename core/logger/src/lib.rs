@@ -0,0 +1,198 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+// tag::description[]
+//! The node's logger.
+//!
+//! Levels are configured per log target with `env_logger` directives, e.g.
+//! `sync=trace,txpool=debug`, and can be changed while the node is running with
+//! `set_log_level`. Records are written to stderr, either as text or as one JSON
+//! object per line for ingestion into log aggregators.
+//!
+//! Commonly used targets are `sync` and `sub-libp2p` for networking, `txpool`, `db`,
+//! `state-db`, `executor`, `afg` for GRANDPA, `aura`, `rhd` and `offchain`.
+// end::description[]
+
+extern crate ansi_term;
+extern crate atty;
+extern crate env_logger;
+#[macro_use]
+extern crate lazy_static;
+extern crate log;
+extern crate parking_lot;
+extern crate regex;
+#[macro_use]
+extern crate serde_json;
+extern crate time;
+
+use std::io::Write;
+
+use ansi_term::Colour;
+use log::Log;
+use parking_lot::RwLock;
+use regex::Regex;
+
+/// Directives that are always applied before the configured ones.
+const DEFAULT_DIRECTIVES: &str = "ws=off,hyper=warn,info";
+
+/// Logger configuration.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+	/// Directives given at startup. They are applied after the ones in `RUST_LOG`.
+	pub pattern: String,
+	/// Whether to write records as JSON rather than as text.
+	pub json: bool,
+}
+
+struct State {
+	config: Config,
+	logger: env_logger::Logger,
+}
+
+lazy_static! {
+	static ref STATE: RwLock<Option<State>> = RwLock::new(None);
+}
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl log::Log for Logger {
+	fn enabled(&self, metadata: &log::Metadata) -> bool {
+		STATE.read().as_ref().map_or(false, |state| state.logger.enabled(metadata))
+	}
+
+	fn log(&self, record: &log::Record) {
+		if let Some(state) = STATE.read().as_ref() {
+			state.logger.log(record);
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// Initialise the global logger.
+pub fn init(config: Config) -> Result<(), log::SetLoggerError> {
+	log::set_logger(&LOGGER)?;
+	let logger = build(&config, "");
+	log::set_max_level(logger.filter());
+	*STATE.write() = Some(State { config, logger });
+	Ok(())
+}
+
+/// Set the log levels at runtime.
+///
+/// The directives are applied after the ones given at startup and replace the ones
+/// set by any previous call, so an empty string restores the startup levels.
+pub fn set_log_level(directives: &str) -> Result<(), String> {
+	validate(directives)?;
+	let mut state = STATE.write();
+	let state = state.as_mut().ok_or_else(|| "Logger is not initialised".to_owned())?;
+	state.logger = build(&state.config, directives);
+	log::set_max_level(state.logger.filter());
+	Ok(())
+}
+
+/// Check that every `target=level` directive names a valid level.
+pub fn validate(directives: &str) -> Result<(), String> {
+	let directives = directives.split('/').next().unwrap_or("");
+	for directive in directives.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+		let mut parts = directive.splitn(2, '=');
+		let target = parts.next().unwrap_or("");
+		if let Some(level) = parts.next() {
+			if target.is_empty() || level.parse::<log::LevelFilter>().is_err() {
+				return Err(format!("Invalid log directive: {}", directive));
+			}
+		}
+	}
+	Ok(())
+}
+
+fn build(config: &Config, directives: &str) -> env_logger::Logger {
+	let mut builder = env_logger::Builder::new();
+	builder.parse(DEFAULT_DIRECTIVES);
+	if let Ok(lvl) = std::env::var("RUST_LOG") {
+		builder.parse(&lvl);
+	}
+	builder.parse(&config.pattern);
+	builder.parse(directives);
+
+	if config.json {
+		builder.format(|buf, record| {
+			let record = json!({
+				"ts": time::now_utc().rfc3339().to_string(),
+				"level": record.level().to_string(),
+				"target": record.target(),
+				"thread": ::std::thread::current().name(),
+				"msg": record.args().to_string(),
+			});
+			writeln!(buf, "{}", record)
+		});
+		return builder.build();
+	}
+
+	let isatty = atty::is(atty::Stream::Stderr);
+	let enable_color = isatty;
+
+	builder.format(move |buf, record| {
+		let timestamp = time::strftime("%Y-%m-%d %H:%M:%S", &time::now()).expect("Error formatting log timestamp");
+
+		let mut output = if log::max_level() <= log::LevelFilter::Info {
+			format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
+		} else {
+			let name = ::std::thread::current().name().map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
+			format!("{} {} {} {}  {}", Colour::Black.bold().paint(timestamp), name, record.level(), record.target(), record.args())
+		};
+
+		if !enable_color {
+			output = kill_color(output.as_ref());
+		}
+
+		if !isatty && record.level() <= log::Level::Info && atty::is(atty::Stream::Stdout) {
+			// duplicate INFO/WARN output to console
+			println!("{}", output);
+		}
+		writeln!(buf, "{}", output)
+	});
+
+	builder.build()
+}
+
+fn kill_color(s: &str) -> String {
+	lazy_static! {
+		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").expect("Error initializing color regex");
+	}
+	RE.replace_all(s, "").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn valid_directives_are_accepted() {
+		assert!(validate("").is_ok());
+		assert!(validate("debug").is_ok());
+		assert!(validate("sync=trace, txpool=debug,info").is_ok());
+		assert!(validate("sync=trace/import").is_ok());
+	}
+
+	#[test]
+	fn invalid_directives_are_rejected() {
+		assert!(validate("sync=loud").is_err());
+		assert!(validate("=debug").is_err());
+	}
+}
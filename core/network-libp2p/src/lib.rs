@@ -24,6 +24,7 @@
 extern crate parking_lot;
 extern crate fnv;
 extern crate futures;
+extern crate igd;
 extern crate tokio;
 extern crate tokio_io;
 extern crate tokio_timer;
@@ -46,6 +47,7 @@ extern crate assert_matches;
 
 mod custom_proto;
 mod error;
+mod nat;
 mod node_handler;
 mod secret;
 mod service_task;
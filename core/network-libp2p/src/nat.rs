@@ -0,0 +1,142 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Port mapping on the local gateway, so that nodes behind a home router can be dialed.
+
+use std::{iter, thread};
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+use futures::sync::mpsc;
+use igd::{self, PortMappingProtocol, SearchOptions};
+use libp2p::{Multiaddr, multiaddr::Protocol};
+
+/// How long to wait for a gateway to answer the UPnP discovery.
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the gateway keeps our port mappings for.
+const LEASE_DURATION: Duration = Duration::from_secs(20 * 60);
+
+/// How often the port mappings are renewed, well before their lease runs out.
+const RENEW_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Maps the TCP ports of the given listen addresses on the local gateway in the background,
+/// renewing the mappings until the returned receiver is dropped.
+///
+/// The external addresses are sent through the receiver after every renewal.
+pub fn spawn_port_mapping(listen_addrs: Vec<Multiaddr>) -> mpsc::UnboundedReceiver<Multiaddr> {
+	let (tx, rx) = mpsc::unbounded();
+	let spawned = thread::Builder::new()
+		.name("upnp".into())
+		.spawn(move || loop {
+			for addr in &listen_addrs {
+				if let Some(external) = map_tcp_address(addr) {
+					trace!(target: "sub-libp2p", "Mapped {} to external address {} with UPnP", addr, external);
+					if tx.unbounded_send(external).is_err() {
+						return;
+					}
+				}
+			}
+			thread::sleep(RENEW_INTERVAL);
+		});
+	if let Err(e) = spawned {
+		warn!(target: "sub-libp2p", "Failed to spawn the UPnP thread: {}", e);
+	}
+	rx
+}
+
+/// Description of the port mappings we create.
+const MAPPING_DESCRIPTION: &str = "substrate";
+
+/// Maps the TCP port of the given listen address on the local gateway using UPnP, for
+/// `LEASE_DURATION`.
+///
+/// Returns the external address other nodes can dial us on, or `None` if the address
+/// isn't an IPv4 TCP address or if no gateway accepted the mapping.
+fn map_tcp_address(listen_addr: &Multiaddr) -> Option<Multiaddr> {
+	let (listen_ip, port) = tcp_ipv4_endpoint(listen_addr)?;
+	if listen_ip.is_loopback() {
+		return None;
+	}
+
+	let gateway = igd::search_gateway(SearchOptions {
+		timeout: Some(SEARCH_TIMEOUT),
+		..Default::default()
+	}).map_err(|e| debug!(target: "sub-libp2p", "No UPnP gateway found: {}", e)).ok()?;
+
+	// find out the address of the interface we reach the gateway through.
+	let local_ip = if listen_ip.is_unspecified() {
+		let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+		socket.connect(gateway.addr).ok()?;
+		match socket.local_addr().ok()?.ip() {
+			IpAddr::V4(ip) => ip,
+			IpAddr::V6(_) => return None,
+		}
+	} else {
+		listen_ip
+	};
+
+	let external_ip = gateway.get_external_ip()
+		.map_err(|e| debug!(target: "sub-libp2p", "Failed to query external address from UPnP gateway: {}", e))
+		.ok()?;
+
+	let lease = LEASE_DURATION.as_secs() as u32;
+	gateway.add_port(PortMappingProtocol::TCP, port, SocketAddrV4::new(local_ip, port), lease, MAPPING_DESCRIPTION)
+		.map_err(|e| debug!(target: "sub-libp2p", "Failed to map port {} on UPnP gateway: {}", port, e))
+		.ok()?;
+
+	Some(tcp_ipv4_multiaddr(external_ip, port))
+}
+
+/// Extracts the IPv4 address and TCP port of a multiaddress of the form `/ip4/.../tcp/...`.
+fn tcp_ipv4_endpoint(addr: &Multiaddr) -> Option<(Ipv4Addr, u16)> {
+	let mut iter = addr.iter();
+	match (iter.next(), iter.next(), iter.next()) {
+		(Some(Protocol::Ip4(ip)), Some(Protocol::Tcp(port)), None) if port != 0 => Some((ip, port)),
+		_ => None,
+	}
+}
+
+fn tcp_ipv4_multiaddr(ip: Ipv4Addr, port: u16) -> Multiaddr {
+	iter::once(Protocol::Ip4(ip))
+		.chain(iter::once(Protocol::Tcp(port)))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn extracts_tcp_ipv4_endpoint() {
+		let addr: Multiaddr = "/ip4/0.0.0.0/tcp/30333".parse().unwrap();
+		assert_eq!(tcp_ipv4_endpoint(&addr), Some((Ipv4Addr::new(0, 0, 0, 0), 30333)));
+		assert_eq!(tcp_ipv4_multiaddr(Ipv4Addr::new(0, 0, 0, 0), 30333), addr);
+	}
+
+	#[test]
+	fn ignores_unsupported_addresses() {
+		for addr in &["/ip6/::1/tcp/30333", "/ip4/127.0.0.1/udp/30333", "/ip4/10.0.0.1/tcp/0", "/ip4/10.0.0.1/tcp/30333/ws"] {
+			let addr: Multiaddr = addr.parse().unwrap();
+			assert_eq!(tcp_ipv4_endpoint(&addr), None);
+		}
+	}
+
+	#[test]
+	fn does_not_map_loopback_addresses() {
+		let addr: Multiaddr = "/ip4/127.0.0.1/tcp/30333".parse().unwrap();
+		assert_eq!(map_tcp_address(&addr), None);
+	}
+}
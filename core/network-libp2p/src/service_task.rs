@@ -25,10 +25,10 @@ use libp2p::core::nodes::ConnectedPoint;
 use libp2p::kad::{KadSystem, KadSystemConfig, KadConnecController, KadPeer};
 use libp2p::kad::{KadConnectionType, KadQueryEvent};
 use parking_lot::Mutex;
+use nat;
 use rand;
 use secret::obtain_private_key;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::iter;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
@@ -72,10 +72,15 @@ where TProtos: IntoIterator<Item = RegisteredProtocol> {
 		}
 	}
 
-	// Register the external addresses provided by the user.
+	// Register the external addresses provided by the user, or map our ports on the gateway.
 	for addr in &config.public_addresses {
 		swarm.add_external_address(addr.clone());
 	}
+	let nat_addresses = if config.enable_upnp && config.public_addresses.is_empty() {
+		Some(nat::spawn_port_mapping(config.listen_addresses.clone()))
+	} else {
+		None
+	};
 
 	// Initialize the topology of the network.
 	let mut topology = if let Some(ref path) = config.net_config_path {
@@ -87,13 +92,14 @@ where TProtos: IntoIterator<Item = RegisteredProtocol> {
 		NetTopology::memory()
 	};
 
-	// Create the Kademlia system, containing the kbuckets.
+	// Create the Kademlia system, containing the kbuckets. The peers we remember from previous
+	// runs are used to seed the kbuckets, so that discovery doesn't depend on the boot nodes.
 	let kad_system = KadSystem::without_init(KadSystemConfig {
 		parallelism: 3,
 		local_peer_id,
 		kbuckets_timeout: Duration::from_secs(600),
 		request_timeout: Duration::from_secs(10),
-		known_initial_peers: iter::empty(),
+		known_initial_peers: topology.peers().cloned().collect::<Vec<_>>().into_iter(),
 	});
 
 	// Add the bootstrap nodes to the topology and connect to them.
@@ -107,12 +113,11 @@ where TProtos: IntoIterator<Item = RegisteredProtocol> {
 				}
 			},
 			Err(_) => {
-				// If the format of the bootstrap node is not a multiaddr, try to parse it as
-				// a `SocketAddr`. This corresponds to the format `IP:PORT`.
-				let addr = match bootnode.parse::<SocketAddr>() { 
-					Ok(SocketAddr::V4(socket)) => multiaddr![Ip4(*socket.ip()), Tcp(socket.port())],
-					Ok(SocketAddr::V6(socket)) => multiaddr![Ip6(*socket.ip()), Tcp(socket.port())],
-					_ => {
+				// If the format of the bootstrap node is not a multiaddr with a peer id, try to
+				// parse it as `IP:PORT` or `HOSTNAME:PORT`.
+				let addr = match parse_bootnode_addr(bootnode) {
+					Some(addr) => addr,
+					None => {
 						warn!(target: "sub-libp2p", "Not a valid bootnode address: {}", bootnode);
 						continue;
 					}
@@ -166,10 +171,30 @@ where TProtos: IntoIterator<Item = RegisteredProtocol> {
 		next_kad_random_query: Interval::new(Instant::now() + Duration::from_secs(5), Duration::from_secs(45)),
 		cleanup: Interval::new_interval(Duration::from_secs(60)),
 		injected_events: Vec::new(),
+		nat_addresses,
 		to_notify: None,
 	})
 }
 
+/// Parses a boot node address without a peer id. Accepts the formats `IP:PORT` and
+/// `HOSTNAME:PORT`, the latter being resolved through DNS every time it is dialed.
+fn parse_bootnode_addr(bootnode: &str) -> Option<Multiaddr> {
+	match bootnode.parse::<SocketAddr>() {
+		Ok(SocketAddr::V4(socket)) => return Some(multiaddr![Ip4(*socket.ip()), Tcp(socket.port())]),
+		Ok(SocketAddr::V6(socket)) => return Some(multiaddr![Ip6(*socket.ip()), Tcp(socket.port())]),
+		Err(_) => {},
+	}
+
+	let mut parts = bootnode.rsplitn(2, ':');
+	let port = parts.next()?.parse::<u16>().ok()?;
+	let host = parts.next()?;
+	if host.is_empty() || host.contains('/') || host.contains(':') {
+		return None;
+	}
+
+	format!("/dns4/{}/tcp/{}", host, port).parse().ok()
+}
+
 /// Event produced by the service.
 pub enum ServiceEvent {
 	/// Closed connection to a node.
@@ -280,6 +305,9 @@ pub struct Service {
 	/// Events to produce on the Stream.
 	injected_events: Vec<ServiceEvent>,
 
+	/// External addresses of the ports mapped on the gateway, if UPnP is enabled.
+	nat_addresses: Option<mpsc::UnboundedReceiver<Multiaddr>>,
+
 	/// Task to notify when elements are added to `injected_events`.
 	to_notify: Option<task::Task>,
 }
@@ -291,6 +319,13 @@ impl Service {
 		self.swarm.listeners()
 	}
 
+	/// Returns the addresses we're reachable on from the outside, either configured, mapped
+	/// on the gateway or observed by other nodes.
+	#[inline]
+	pub fn external_addresses(&self) -> impl Iterator<Item = &Multiaddr> {
+		self.swarm.external_addresses()
+	}
+
 	/// Returns the peer id of the local node.
 	#[inline]
 	pub fn peer_id(&self) -> &PeerId {
@@ -912,6 +947,25 @@ impl Service {
 		}
 	}
 
+	/// Polls the external addresses of the ports mapped on the gateway.
+	fn poll_nat(&mut self) -> Poll<Option<ServiceEvent>, IoError> {
+		loop {
+			let addr = match self.nat_addresses.as_mut().map(|rx| rx.poll()) {
+				None | Some(Ok(Async::NotReady)) => return Ok(Async::NotReady),
+				Some(Ok(Async::Ready(Some(addr)))) => addr,
+				Some(Ok(Async::Ready(None))) | Some(Err(())) => {
+					self.nat_addresses = None;
+					return Ok(Async::NotReady);
+				},
+			};
+
+			if !self.swarm.external_addresses().any(|a| a == &addr) {
+				info!(target: "sub-libp2p", "Mapped a listen port to external address {} with UPnP", addr);
+				self.swarm.add_external_address(addr);
+			}
+		}
+	}
+
 	/// Polls the stream that fires when we need to cleanup and flush the topology.
 	fn poll_cleanup(&mut self) -> Poll<Option<ServiceEvent>, IoError> {
 		loop {
@@ -978,9 +1032,32 @@ impl Stream for Service {
 			Async::NotReady => (),
 		}
 
+		match self.poll_nat()? {
+			Async::Ready(value) => return Ok(Async::Ready(value)),
+			Async::NotReady => (),
+		}
+
 		// The only way we reach this is if we went through all the `NotReady` paths above,
 		// ensuring the current task is registered everywhere.
 		self.to_notify = Some(task::current());
 		Ok(Async::NotReady)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::parse_bootnode_addr;
+
+	#[test]
+	fn parses_bootnode_addresses_without_peer_id() {
+		assert_eq!(parse_bootnode_addr("127.0.0.1:30333"), Some("/ip4/127.0.0.1/tcp/30333".parse().unwrap()));
+		assert_eq!(parse_bootnode_addr("[::1]:30333"), Some("/ip6/::1/tcp/30333".parse().unwrap()));
+		assert_eq!(
+			parse_bootnode_addr("bootnodes.example.com:30333"),
+			Some("/dns4/bootnodes.example.com/tcp/30333".parse().unwrap())
+		);
+		assert_eq!(parse_bootnode_addr("bootnodes.example.com"), None);
+		assert_eq!(parse_bootnode_addr(":30333"), None);
+		assert_eq!(parse_bootnode_addr("a/b:30333"), None);
+	}
+}
@@ -134,6 +134,11 @@ impl NetTopology {
 		});
 	}
 
+	/// Returns the peers we know of, whether or not they have a usable address.
+	pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+		self.store.keys()
+	}
+
 	/// Returns the known potential addresses of a peer, ordered by score. Excludes backed-off
 	/// addresses.
 	///
@@ -195,7 +200,7 @@ impl NetTopology {
 					continue;
 				}
 
-				peer_addrs.push(((peer, &addr.addr), score));
+				peer_addrs.push(((peer, &addr.addr), (score, addr.last_seen())));
 			}
 
 			for val in peer_addrs.drain(..) {
@@ -203,6 +208,7 @@ impl NetTopology {
 			}
 		}
 
+		// Prefer the highest scores, then the addresses we were connected to most recently.
 		addrs_out.sort_by(|a, b| b.1.cmp(&a.1));
 		(addrs_out.into_iter().map(|a| a.0), instant)
 	}
@@ -237,6 +243,7 @@ impl NetTopology {
 					connected_since: None,
 					score: BOOTSTRAP_NODE_SCORE,
 					latest_score_update: now,
+					last_seen: None,
 				}),
 			});
 		}
@@ -326,6 +333,7 @@ impl NetTopology {
 					connected_since: None,
 					score: initial_score,
 					latest_score_update: now,
+					last_seen: None,
 				}),
 			});
 		}
@@ -361,6 +369,7 @@ impl NetTopology {
 						connected_since: Some(now),
 						latest_score_update: now,
 						score: CONNECTED_MINIMUM_SCORE,
+						last_seen: Some(SystemTime::now()),
 					}),
 				});
 
@@ -495,6 +504,8 @@ struct AddrScore {
 	score: u32,
 	/// When we last updated the score.
 	latest_score_update: Instant,
+	/// When we were last connected to this address, if ever.
+	last_seen: Option<SystemTime>,
 }
 
 impl Addr {
@@ -505,6 +516,7 @@ impl Addr {
 		let now = Instant::now();
 		Addr::flush(&mut score, now);
 		score.connected_since = Some(now);
+		score.last_seen = Some(SystemTime::now());
 		if score.score < raise_to_min {
 			score.score = raise_to_min;
 		}
@@ -526,6 +538,7 @@ impl Addr {
 		let mut score = self.score.lock();
 		Addr::flush(&mut score, Instant::now());
 		score.connected_since = None;
+		score.last_seen = Some(SystemTime::now());
 		if score_diff >= 0 {
 			score.score = cmp::min(MAX_SCORE, score.score + score_diff as u32);
 		} else {
@@ -539,6 +552,11 @@ impl Addr {
 		score.connected_since.is_some()
 	}
 
+	/// Returns the last moment we were connected to this addr, if ever.
+	fn last_seen(&self) -> Option<SystemTime> {
+		self.score.lock().last_seen
+	}
+
 	/// Returns the score, and true if we are connected to this addr.
 	fn score_and_is_connected(&self) -> (u32, bool) {
 		let mut score = self.score.lock();
@@ -589,6 +607,8 @@ struct SerializedAddr {
 	addr: String,
 	expires: SystemTime,
 	score: u32,
+	#[serde(default)]
+	last_seen: Option<SystemTime>,
 }
 
 impl<'a> From<&'a Addr> for SerializedAddr {
@@ -597,6 +617,7 @@ impl<'a> From<&'a Addr> for SerializedAddr {
 			addr: addr.addr.to_string(),
 			expires: addr.expires,
 			score: addr.score(),
+			last_seen: addr.last_seen(),
 		}
 	}
 }
@@ -699,6 +720,7 @@ fn deserialize_tolerant(
 					connected_since: None,
 					score: addr.score,
 					latest_score_update: now,
+					last_seen: addr.last_seen,
 				}),
 			});
 		}
@@ -40,6 +40,8 @@ pub struct NetworkConfiguration {
 	pub listen_addresses: Vec<Multiaddr>,
 	/// Multiaddresses to advertise. Detected automatically if empty.
 	pub public_addresses: Vec<Multiaddr>,
+	/// Map the listening ports on the local gateway with UPnP and advertise the external address.
+	pub enable_upnp: bool,
 	/// List of initial node addresses
 	pub boot_nodes: Vec<String>,
 	/// Use provided node key instead of default
@@ -74,6 +76,7 @@ impl NetworkConfiguration {
 					.collect()
 			],
 			public_addresses: Vec::new(),
+			enable_upnp: false,
 			boot_nodes: Vec::new(),
 			use_secret: None,
 			in_peers: 25,
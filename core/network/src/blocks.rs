@@ -23,7 +23,8 @@ use network_libp2p::NodeIndex;
 use runtime_primitives::traits::{Block as BlockT, NumberFor, As};
 use message;
 
-const MAX_PARALLEL_DOWNLOADS: u32 = 1;
+// Maximum distance ahead of the common block we are allowed to buffer downloads for.
+const MAX_DOWNLOAD_AHEAD: u64 = 2048;
 
 /// Block data with origin.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,9 +83,8 @@ impl<B: BlockT> BlockCollection<B> {
 		}
 
 		match self.blocks.get(&start) {
-			Some(&BlockRangeState::Downloading { .. }) => {
-				trace!(target: "sync", "Ignored block data still marked as being downloaded: {}", start);
-				debug_assert!(false);
+			Some(&BlockRangeState::Downloading { .. }) if self.peer_requests.get(&who) != Some(&start) => {
+				trace!(target: "sync", "Ignored block data for range downloaded by another peer: {}", start);
 				return;
 			},
 			Some(&BlockRangeState::Complete(ref existing)) if existing.len() >= blocks.len() => {
@@ -99,7 +99,8 @@ impl<B: BlockT> BlockCollection<B> {
 	}
 
 	/// Returns a set of block hashes that require a header download. The returned set is marked as being downloaded.
-	pub fn needed_blocks(&mut self, who: NodeIndex, count: usize, peer_best: NumberFor<B>, common: NumberFor<B>) -> Option<Range<NumberFor<B>>> {
+	/// A range may be given to up to `max_parallel` peers at once.
+	pub fn needed_blocks(&mut self, who: NodeIndex, count: usize, peer_best: NumberFor<B>, common: NumberFor<B>, max_parallel: u32) -> Option<Range<NumberFor<B>>> {
 		// First block number that we need to download
 		let first_different = common + As::sa(1);
 		let count = As::sa(count as u64);
@@ -109,7 +110,7 @@ impl<B: BlockT> BlockCollection<B> {
 			loop {
 				let next = downloading_iter.next();
 				break match &(prev, next) {
-					&(Some((start, &BlockRangeState::Downloading { ref len, downloading })), _) if downloading < MAX_PARALLEL_DOWNLOADS =>
+					&(Some((start, &BlockRangeState::Downloading { ref len, downloading })), _) if downloading < max_parallel =>
 						(*start .. *start + *len, downloading),
 					&(Some((start, r)), Some((next_start, _))) if *start + r.len() < *next_start =>
 						(*start + r.len() .. cmp::min(*next_start, *start + r.len() + count), 0), // gap
@@ -131,6 +132,11 @@ impl<B: BlockT> BlockCollection<B> {
 			trace!(target: "sync", "Out of range for peer {} ({} vs {})", who, range.start, peer_best);
 			return None;
 		}
+		// do not buffer too many blocks ahead of the ones that are still missing
+		if range.start > first_different + As::sa(MAX_DOWNLOAD_AHEAD) {
+			trace!(target: "sync", "Too far ahead for peer {} ({} vs {})", who, range.start, first_different);
+			return None;
+		}
 		range.end = cmp::min(peer_best + As::sa(1), range.end);
 		self.peer_requests.insert(who, range.start);
 		self.blocks.insert(range.start, BlockRangeState::Downloading { len: range.end - range.start, downloading: downloading + 1 });
@@ -165,6 +171,7 @@ impl<B: BlockT> BlockCollection<B> {
 		drained
 	}
 
+	/// Stop tracking the download of the given peer, making its range available to other peers.
 	pub fn clear_peer_download(&mut self, who: NodeIndex) {
 		match self.peer_requests.entry(who) {
 			Entry::Occupied(entry) => {
@@ -177,10 +184,8 @@ impl<B: BlockT> BlockCollection<B> {
 					Some(&mut BlockRangeState::Downloading { .. }) => {
 						true
 					},
-					_ => {
-						debug_assert!(false);
-						false
-					}
+					// already completed by another peer, or drained since.
+					Some(&mut BlockRangeState::Complete(_)) | None => false,
 				};
 				if remove {
 					self.blocks.remove(&start);
@@ -235,18 +240,18 @@ mod test {
 		let peer2 = 2;
 
 		let blocks = generate_blocks(150);
-		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0), Some(1 .. 41));
-		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0), Some(41 .. 81));
-		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0), Some(81 .. 121));
+		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0, 1), Some(1 .. 41));
+		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0, 1), Some(41 .. 81));
+		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0, 1), Some(81 .. 121));
 
 		bc.clear_peer_download(peer1);
 		bc.insert(41, blocks[41..81].to_vec(), peer1);
 		assert_eq!(bc.drain(1), vec![]);
-		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0), Some(121 .. 151));
+		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0, 1), Some(121 .. 151));
 		bc.clear_peer_download(peer0);
 		bc.insert(1, blocks[1..11].to_vec(), peer0);
 
-		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0), Some(11 .. 41));
+		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0, 1), Some(11 .. 41));
 		assert_eq!(bc.drain(1), blocks[1..11].iter().map(|b| BlockData { block: b.clone(), origin: Some(0) }).collect::<Vec<_>>());
 
 		bc.clear_peer_download(peer0);
@@ -257,7 +262,7 @@ mod test {
 		assert_eq!(drained[30..], blocks[41..81].iter().map(|b| BlockData { block: b.clone(), origin: Some(1) }).collect::<Vec<_>>()[..]);
 
 		bc.clear_peer_download(peer2);
-		assert_eq!(bc.needed_blocks(peer2, 40, 150, 80), Some(81 .. 121));
+		assert_eq!(bc.needed_blocks(peer2, 40, 150, 80, 1), Some(81 .. 121));
 		bc.clear_peer_download(peer2);
 		bc.insert(81, blocks[81..121].to_vec(), peer2);
 		bc.clear_peer_download(peer1);
@@ -279,7 +284,64 @@ mod test {
 		let blocks = generate_blocks(10).into_iter().map(|b| BlockData { block: b, origin: None }).collect();
 		bc.blocks.insert(114305, BlockRangeState::Complete(blocks));
 
-		assert_eq!(bc.needed_blocks(0, 128, 10000, 000), Some(1 .. 100));
-		assert_eq!(bc.needed_blocks(0, 128, 10000, 600), Some(100 + 128 .. 100 + 128 + 128));
+		assert_eq!(bc.needed_blocks(0, 128, 10000, 000, 1), Some(1 .. 100));
+		assert_eq!(bc.needed_blocks(0, 128, 10000, 600, 1), Some(100 + 128 .. 100 + 128 + 128));
+	}
+
+	#[test]
+	fn reassigned_range() {
+		let mut bc = BlockCollection::new();
+		let peer0 = 0;
+		let peer1 = 1;
+
+		let blocks = generate_blocks(41);
+		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0, 1), Some(1 .. 41));
+
+		// peer0 stalls and the range is given to peer1
+		bc.clear_peer_download(peer0);
+		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0, 1), Some(1 .. 41));
+
+		// peer0 answers late and is ignored
+		bc.insert(1, blocks[1..41].to_vec(), peer0);
+		assert_eq!(bc.drain(1), vec![]);
+
+		bc.insert(1, blocks[1..41].to_vec(), peer1);
+		bc.clear_peer_download(peer1);
+
+		assert_eq!(bc.drain(1), blocks[1..41].iter().map(|b| BlockData { block: b.clone(), origin: Some(1) }).collect::<Vec<_>>());
+		assert!(is_empty(&bc));
+	}
+
+	#[test]
+	fn parallel_download() {
+		let mut bc = BlockCollection::new();
+		let peer0 = 0;
+		let peer1 = 1;
+		let peer2 = 2;
+
+		let blocks = generate_blocks(41);
+		assert_eq!(bc.needed_blocks(peer0, 40, 150, 0, 2), Some(1 .. 41));
+		assert_eq!(bc.needed_blocks(peer1, 40, 150, 0, 2), Some(1 .. 41));
+		assert_eq!(bc.needed_blocks(peer2, 40, 150, 0, 2), Some(41 .. 81));
+
+		// whichever peer answers first completes the range
+		bc.insert(1, blocks[1..41].to_vec(), peer1);
+		bc.clear_peer_download(peer1);
+		bc.insert(1, blocks[1..11].to_vec(), peer0);
+		bc.clear_peer_download(peer0);
+
+		assert_eq!(bc.drain(1), blocks[1..41].iter().map(|b| BlockData { block: b.clone(), origin: Some(1) }).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn download_ahead_limit() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		bc.blocks.insert(1, BlockRangeState::Downloading {
+			len: 2049,
+			downloading: 1,
+		});
+
+		assert_eq!(bc.needed_blocks(0, 128, 10000, 0, 1), None);
+		assert_eq!(bc.needed_blocks(0, 128, 10000, 1, 1), Some(2050 .. 2050 + 128));
 	}
 }
@@ -17,6 +17,7 @@
 //! Blockchain access trait
 
 use client::{self, Client as SubstrateClient, ClientInfo, BlockStatus, CallExecutor};
+use client::blockchain::TreeRoute;
 use client::error::Error;
 use consensus::BlockImport;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
@@ -49,12 +50,18 @@ pub trait Client<Block: BlockT>: Send + Sync {
 	/// Get block justification.
 	fn justification(&self, id: &BlockId<Block>) -> Result<Option<Justification>, Error>;
 
+	/// Get the tree route between two blocks.
+	fn tree_route(&self, from: &Block::Hash, to: &Block::Hash) -> Result<TreeRoute<Block>, Error>;
+
 	/// Get block header proof.
 	fn header_proof(&self, block_number: <Block::Header as HeaderT>::Number) -> Result<(Block::Header, Vec<Vec<u8>>), Error>;
 
 	/// Get storage read execution proof.
 	fn read_proof(&self, block: &Block::Hash, key: &[u8]) -> Result<Vec<Vec<u8>>, Error>;
 
+	/// Get child storage read execution proof.
+	fn read_child_proof(&self, block: &Block::Hash, storage_key: &[u8], key: &[u8]) -> Result<Vec<Vec<u8>>, Error>;
+
 	/// Get method execution proof.
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error>;
 
@@ -66,6 +73,32 @@ pub trait Client<Block: BlockT>: Send + Sync {
 		max: Block::Hash,
 		key: &[u8]
 	) -> Result<(NumberFor<Block>, Vec<Vec<u8>>), Error>;
+
+	/// Get up to `max` storage entries of a block, in key order, starting from the `start` key.
+	/// Also returns whether there are no more entries past the returned ones.
+	fn state_entries(&self, block: &Block::Hash, start: &[u8], max: usize) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Error>;
+
+	/// Record the number of the block which state is downloaded by fast sync.
+	fn set_fast_sync_target(&self, number: NumberFor<Block>) -> Result<(), Error>;
+
+	/// The number of the block which state is downloaded by an unfinished fast sync, if any.
+	fn fast_sync_target(&self) -> Result<Option<NumberFor<Block>>, Error>;
+
+	/// Import a block header, a child of the best block, without executing the block.
+	fn import_header(&self, header: Block::Header, justification: Option<Justification>) -> Result<ImportResult, Error>;
+
+	/// Store a chunk of the state of a block that is going to be imported with its state.
+	fn import_state_chunk(&self, block: &Block::Hash, index: u32, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), Error>;
+
+	/// Import a block along with its complete state, made of the `chunks` first stored state
+	/// chunks, without executing it.
+	fn import_block_with_state(
+		&self,
+		header: Block::Header,
+		body: Option<Vec<Block::Extrinsic>>,
+		justification: Justification,
+		chunks: u32,
+	) -> Result<ImportResult, Error>;
 }
 
 impl<B, E, Block> Client<Block> for SubstrateClient<B, E, Block> where
@@ -104,6 +137,10 @@ impl<B, E, Block> Client<Block> for SubstrateClient<B, E, Block> where
 		(self as &SubstrateClient<B, E, Block>).justification(id)
 	}
 
+	fn tree_route(&self, from: &Block::Hash, to: &Block::Hash) -> Result<TreeRoute<Block>, Error> {
+		(self as &SubstrateClient<B, E, Block>).tree_route(&BlockId::Hash(from.clone()), &BlockId::Hash(to.clone()))
+	}
+
 	fn header_proof(&self, block_number: <Block::Header as HeaderT>::Number) -> Result<(Block::Header, Vec<Vec<u8>>), Error> {
 		(self as &SubstrateClient<B, E, Block>).header_proof(&BlockId::Number(block_number))
 	}
@@ -112,6 +149,10 @@ impl<B, E, Block> Client<Block> for SubstrateClient<B, E, Block> where
 		(self as &SubstrateClient<B, E, Block>).read_proof(&BlockId::Hash(block.clone()), key)
 	}
 
+	fn read_child_proof(&self, block: &Block::Hash, storage_key: &[u8], key: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+		(self as &SubstrateClient<B, E, Block>).read_child_proof(&BlockId::Hash(block.clone()), storage_key, key)
+	}
+
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
 		(self as &SubstrateClient<B, E, Block>).execution_proof(&BlockId::Hash(block.clone()), method, data)
 	}
@@ -125,4 +166,34 @@ impl<B, E, Block> Client<Block> for SubstrateClient<B, E, Block> where
 	) -> Result<(NumberFor<Block>, Vec<Vec<u8>>), Error> {
 		(self as &SubstrateClient<B, E, Block>).key_changes_proof(first, last, max, key)
 	}
+
+	fn state_entries(&self, block: &Block::Hash, start: &[u8], max: usize) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Error> {
+		(self as &SubstrateClient<B, E, Block>).storage_entries(&BlockId::Hash(block.clone()), start, max)
+	}
+
+	fn set_fast_sync_target(&self, number: NumberFor<Block>) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block>).set_fast_sync_target(number)
+	}
+
+	fn fast_sync_target(&self) -> Result<Option<NumberFor<Block>>, Error> {
+		(self as &SubstrateClient<B, E, Block>).fast_sync_target()
+	}
+
+	fn import_header(&self, header: Block::Header, justification: Option<Justification>) -> Result<ImportResult, Error> {
+		(self as &SubstrateClient<B, E, Block>).import_header(header, justification)
+	}
+
+	fn import_state_chunk(&self, block: &Block::Hash, index: u32, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block>).import_state_chunk(block, index, entries)
+	}
+
+	fn import_block_with_state(
+		&self,
+		header: Block::Header,
+		body: Option<Vec<Block::Extrinsic>>,
+		justification: Justification,
+		chunks: u32,
+	) -> Result<ImportResult, Error> {
+		(self as &SubstrateClient<B, E, Block>).import_block_with_state(header, body, justification, chunks)
+	}
 }
@@ -16,17 +16,31 @@
 
 pub use service::Roles;
 
+/// Block synchronization mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncMode {
+	/// Download and execute all blocks.
+	Full,
+	/// Download headers and the state of a recent block, then continue with full sync.
+	/// Headers preceding that block are imported without verification or execution.
+	/// Only takes effect when starting from genesis.
+	Fast,
+}
+
 /// Protocol configuration
 #[derive(Clone)]
 pub struct ProtocolConfig {
 	/// Assigned roles.
 	pub roles: Roles,
+	/// Block synchronization mode.
+	pub sync_mode: SyncMode,
 }
 
 impl Default for ProtocolConfig {
 	fn default() -> ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
+			sync_mode: SyncMode::Full,
 		}
 	}
 }
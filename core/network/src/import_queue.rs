@@ -22,12 +22,14 @@
 //!
 //! The `ImportQueue` trait allows such verification strategies to be instantiated.
 //! The `BasicQueue` and `BasicVerifier` traits allow serial queues to be
-//! instantiated simply.
+//! instantiated simply. When the verifier doesn't need the state of the parent block,
+//! the `BasicQueue` verifies batches of blocks in parallel and only executes them serially.
 
 use std::collections::{HashSet, VecDeque};
 use std::sync::{Arc, Weak};
 use std::sync::atomic::{AtomicBool, Ordering};
 use parking_lot::{Condvar, Mutex, RwLock};
+use rayon::prelude::*;
 use network_libp2p::{NodeIndex, Severity};
 use primitives::AuthorityId;
 
@@ -46,7 +48,9 @@ pub use consensus::{ImportBlock, ImportResult, BlockOrigin};
 #[cfg(any(test, feature = "test-helpers"))]
 use std::cell::RefCell;
 
-/// Verify a justification of a block
+/// Consensus-specific verification of blocks received from the network, before they are
+/// executed by the client: checking the seal and the justification of the block, and
+/// stripping consensus digests off the header.
 pub trait Verifier<B: BlockT>: Send + Sync + Sized {
 	/// Verify the given data and return the ImportBlock and an optional
 	/// new set of validators to import. If not, err with an Error-Message
@@ -58,6 +62,29 @@ pub trait Verifier<B: BlockT>: Send + Sync + Sized {
 		justification: Vec<u8>,
 		body: Option<Vec<B::Extrinsic>>
 	) -> Result<(ImportBlock<B>, Option<Vec<AuthorityId>>), String>;
+
+	/// Whether blocks can be verified before their parent is imported. If so, the import queue
+	/// verifies batches of consecutive blocks in parallel on a worker pool.
+	fn can_verify_in_parallel(&self) -> bool {
+		false
+	}
+
+	/// Called with the headers of a batch, in ascending order, before the blocks of the batch are
+	/// verified in parallel. Lets the verifier gather what it reads from the chain for blocks
+	/// whose parent is part of the batch.
+	fn prepare_batch(&self, _headers: &[&B::Header]) {}
+
+	/// Checks of a verified block which need the state of its parent, run right before the
+	/// block is imported.
+	fn check_before_import(&self, _block: &ImportBlock<B>) -> Result<(), String> {
+		Ok(())
+	}
+
+	/// Check that the justification of a header finalizes it, without the body of the block or
+	/// the state of its parent. Used by fast sync for the headers preceding the downloaded state.
+	fn verify_justification(&self, _header: &B::Header, _justification: &[u8]) -> Result<(), String> {
+		Err("Justifications can not be checked without executing the block".into())
+	}
 }
 
 /// Blocks import queue API.
@@ -87,6 +114,12 @@ pub trait ImportQueue<B: BlockT>: Send + Sync {
 	fn is_importing(&self, hash: &B::Hash) -> bool;
 	/// Import bunch of blocks.
 	fn import_blocks(&self, origin: BlockOrigin, blocks: Vec<BlockData<B>>);
+	/// Verify and import blocks into the given chain on the calling thread, without
+	/// involving the sync. Stops at the first block that fails to import.
+	/// Returns the number of blocks imported.
+	fn import_blocks_blocking(&self, chain: &Client<B>, origin: BlockOrigin, blocks: Vec<BlockData<B>>) -> usize;
+	/// Check that the justification of a header finalizes it, using the verifier of the queue.
+	fn verify_justification(&self, header: &B::Header, justification: &[u8]) -> Result<(), String>;
 }
 
 /// Import queue status. It isn't completely accurate.
@@ -202,6 +235,14 @@ impl<B: BlockT, V: 'static + Verifier<B>> ImportQueue<B> for BasicQueue<B, V> {
 		queue.push_back((origin, blocks));
 		self.data.signal.notify_one();
 	}
+
+	fn import_blocks_blocking(&self, chain: &Client<B>, origin: BlockOrigin, blocks: Vec<BlockData<B>>) -> usize {
+		import_blocks_blocking(chain, origin, blocks, self.verifier.clone())
+	}
+
+	fn verify_justification(&self, header: &B::Header, justification: &[u8]) -> Result<(), String> {
+		self.verifier.verify_justification(header, justification)
+	}
 }
 
 impl<B: BlockT, V: 'static + Verifier<B>> Drop for BasicQueue<B, V> {
@@ -271,6 +312,8 @@ trait SyncLinkApi<B: BlockT> {
 	fn useless_peer(&mut self, who: NodeIndex, reason: &str);
 	/// Disconnect from peer and restart sync.
 	fn note_useless_and_restart_sync(&mut self, who: NodeIndex, reason: &str);
+	/// Note that the peer provided blocks which can't be connected to our chain and restart sync.
+	fn note_useless_fork_and_restart_sync(&mut self, who: NodeIndex);
 	/// Restart sync.
 	fn restart(&mut self);
 }
@@ -320,6 +363,13 @@ impl<'a, B: 'static + BlockT, E: 'a + ExecuteInContext<B>> SyncLinkApi<B> for Sy
 		})
 	}
 
+	fn note_useless_fork_and_restart_sync(&mut self, who: NodeIndex) {
+		self.with_sync(|sync, protocol| {
+			sync.note_useless_fork(protocol, who);
+			sync.restart(protocol);
+		})
+	}
+
 	fn restart(&mut self) {
 		self.with_sync(|sync, protocol| sync.restart(protocol))
 	}
@@ -346,13 +396,20 @@ enum BlockImportError {
 	/// Block is known to be Bad
 	BadBlock(Option<NodeIndex>),
 	/// Block has an unknown parent
-	UnknownParent,
+	UnknownParent(Option<NodeIndex>),
 	/// Other Error.
 	Error,
 }
 
+/// A block that passed verification, along with the peer it comes from and the new set of
+/// authorities it enacts, if any.
+type VerifiedBlock<B> = (Option<NodeIndex>, ImportBlock<B>, Option<Vec<AuthorityId>>);
+
+/// Number of blocks verified at once by verifiers which can verify in parallel.
+const PARALLEL_VERIFICATION_BATCH: usize = 32;
+
 /// Import a bunch of blocks.
-fn import_many_blocks<'a, B: BlockT, V: Verifier<B>>(
+fn import_many_blocks<'a, B: BlockT, V: 'static + Verifier<B>>(
 	link: &mut SyncLinkApi<B>,
 	qdata: Option<&AsyncImportQueueData<B>>,
 	blocks: (BlockOrigin, Vec<BlockData<B>>),
@@ -373,24 +430,42 @@ fn import_many_blocks<'a, B: BlockT, V: Verifier<B>>(
 		};
 	trace!(target:"sync", "Starting import of {} blocks {}", count, blocks_range);
 
-	// Blocks in the response/drain should be in ascending order.
-	for block in blocks {
-		let import_result = import_single_block(
-			link.chain(),
-			blocks_origin.clone(),
-			block,
-			verifier.clone(),
-		);
-		let is_import_failed = import_result.is_err();
-		imported += process_import_result(link, import_result);
-		if is_import_failed {
-			qdata.map(|qdata| *qdata.best_importing_number.write() = Zero::zero());
-			return true;
-		}
-
+	// Blocks in the response/drain should be in ascending order. They are either verified
+	// in batches on the worker pool, or one at a time right before being imported.
+	let batch_size = if verifier.can_verify_in_parallel() { PARALLEL_VERIFICATION_BATCH } else { 1 };
+	let mut blocks = blocks.into_iter();
+	loop {
 		if qdata.map(|qdata| qdata.is_stopping.load(Ordering::SeqCst)).unwrap_or_default() {
 			return false;
 		}
+
+		let batch: Vec<_> = blocks.by_ref().take(batch_size).collect();
+		if batch.is_empty() {
+			break;
+		}
+
+		let verified: Vec<_> = if batch_size > 1 {
+			verifier.prepare_batch(&batch.iter().filter_map(|b| b.block.header.as_ref()).collect::<Vec<_>>());
+			batch.into_par_iter()
+				.map(|block| verify_single_block(blocks_origin.clone(), block, &*verifier))
+				.collect()
+		} else {
+			batch.into_iter()
+				.map(|block| verify_single_block(blocks_origin.clone(), block, &*verifier))
+				.collect()
+		};
+
+		for verification in verified {
+			let import_result = verification.and_then(|(peer, block, new_authorities)|
+				import_verified_block(link.chain(), &*verifier, peer, block, new_authorities)
+			);
+			let is_import_failed = import_result.is_err();
+			imported += process_import_result(link, import_result);
+			if is_import_failed {
+				qdata.map(|qdata| *qdata.best_importing_number.write() = Zero::zero());
+				return true;
+			}
+		}
 	}
 
 	trace!(target: "sync", "Imported {} of {}", imported, count);
@@ -398,6 +473,26 @@ fn import_many_blocks<'a, B: BlockT, V: Verifier<B>>(
 	true
 }
 
+/// Import blocks one by one on the calling thread, stopping at the first failure.
+fn import_blocks_blocking<B: BlockT, V: Verifier<B>>(
+	chain: &Client<B>,
+	origin: BlockOrigin,
+	blocks: Vec<BlockData<B>>,
+	verifier: Arc<V>
+) -> usize
+{
+	let mut imported = 0;
+	for block in blocks {
+		let hash = block.block.hash.clone();
+		if let Err(e) = import_single_block(chain, origin.clone(), block, verifier.clone()) {
+			debug!(target: "sync", "Error importing block {:?}: {:?}", hash, e);
+			break;
+		}
+		imported += 1;
+	}
+	imported
+}
+
 /// Single block import function.
 fn import_single_block<B: BlockT, V: Verifier<B>>(
 	chain: &Client<B>,
@@ -405,6 +500,17 @@ fn import_single_block<B: BlockT, V: Verifier<B>>(
 	block: BlockData<B>,
 	verifier: Arc<V>
 ) -> Result<BlockImportResult<B::Hash, <<B as BlockT>::Header as HeaderT>::Number>, BlockImportError>
+{
+	let (peer, import_block, new_authorities) = verify_single_block(block_origin, block, &*verifier)?;
+	import_verified_block(chain, &*verifier, peer, import_block, new_authorities)
+}
+
+/// Check that a block is complete and verify it. Doesn't touch the chain.
+fn verify_single_block<B: BlockT, V: Verifier<B>>(
+	block_origin: BlockOrigin,
+	block: BlockData<B>,
+	verifier: &V
+) -> Result<VerifiedBlock<B>, BlockImportError>
 {
 	let peer = block.origin;
 	let block = block.block;
@@ -431,7 +537,6 @@ fn import_single_block<B: BlockT, V: Verifier<B>>(
 
 	let number = header.number().clone();
 	let hash = header.hash();
-	let parent = header.parent_hash().clone();
 	let (import_block, new_authorities) = verifier.verify(block_origin, header, justification, block.body)
 		.map_err(|msg| {
 			if let Some(peer) = peer {
@@ -442,6 +547,27 @@ fn import_single_block<B: BlockT, V: Verifier<B>>(
 			BlockImportError::VerificationFailed(peer, msg)
 		})?;
 
+	Ok((peer, import_block, new_authorities))
+}
+
+/// Finish checking a verified block against its parent, then execute and import it into the chain.
+fn import_verified_block<B: BlockT, V: Verifier<B>>(
+	chain: &Client<B>,
+	verifier: &V,
+	peer: Option<NodeIndex>,
+	import_block: ImportBlock<B>,
+	new_authorities: Option<Vec<AuthorityId>>
+) -> Result<BlockImportResult<B::Hash, <<B as BlockT>::Header as HeaderT>::Number>, BlockImportError>
+{
+	let number = import_block.header.number().clone();
+	let hash = import_block.header.hash();
+	let parent = import_block.header.parent_hash().clone();
+
+	verifier.check_before_import(&import_block).map_err(|msg| {
+		trace!(target: "sync", "Checking {}({}) before import failed: {}", number, hash, msg);
+		BlockImportError::VerificationFailed(peer, msg)
+	})?;
+
 	match chain.import(import_block, new_authorities) {
 		Ok(ImportResult::AlreadyInChain) => {
 			trace!(target: "sync", "Block already in chain {}: {:?}", number, hash);
@@ -457,7 +583,7 @@ fn import_single_block<B: BlockT, V: Verifier<B>>(
 		},
 		Ok(ImportResult::UnknownParent) => {
 			debug!(target: "sync", "Block with unknown parent {}: {:?}, parent: {:?}", number, hash, parent);
-			Err(BlockImportError::UnknownParent)
+			Err(BlockImportError::UnknownParent(peer))
 		},
 		Ok(ImportResult::KnownBad) => {
 			debug!(target: "sync", "Peer gave us a bad block {}: {:?}", number, hash);
@@ -509,7 +635,11 @@ fn process_import_result<'a, B: BlockT>(
 			}
 			0
 		},
-		Err(BlockImportError::UnknownParent) | Err(BlockImportError::Error) => {
+		Err(BlockImportError::UnknownParent(Some(peer))) => {
+			link.note_useless_fork_and_restart_sync(peer);
+			0
+		},
+		Err(BlockImportError::UnknownParent(None)) | Err(BlockImportError::Error) => {
 			link.restart();
 			0
 		},
@@ -567,6 +697,14 @@ impl<B: BlockT> Verifier<B> for PassThroughVerifier {
 			auxiliary: Vec::new(),
 		}, None))
 	}
+
+	fn can_verify_in_parallel(&self) -> bool {
+		true
+	}
+
+	fn verify_justification(&self, _header: &B::Header, _justification: &[u8]) -> Result<(), String> {
+		Ok(())
+	}
 }
 
 #[cfg(any(test, feature = "test-helpers"))]
@@ -624,6 +762,14 @@ impl<B: 'static + BlockT, V: 'static + Verifier<B>> ImportQueue<B> for SyncImpor
 	fn import_blocks(&self, origin: BlockOrigin, blocks: Vec<BlockData<B>>) {
 		self.1.call(origin, blocks);
 	}
+
+	fn import_blocks_blocking(&self, chain: &Client<B>, origin: BlockOrigin, blocks: Vec<BlockData<B>>) -> usize {
+		import_blocks_blocking(chain, origin, blocks, self.0.clone())
+	}
+
+	fn verify_justification(&self, header: &B::Header, justification: &[u8]) -> Result<(), String> {
+		self.0.verify_justification(header, justification)
+	}
 }
 
 #[cfg(test)]
@@ -667,6 +813,7 @@ pub mod tests {
 		fn maintain_sync(&mut self) { self.maintains += 1; }
 		fn useless_peer(&mut self, _: NodeIndex, _: &str) { self.disconnects += 1; }
 		fn note_useless_and_restart_sync(&mut self, _: NodeIndex, _: &str) { self.disconnects += 1; self.restarts += 1; }
+		fn note_useless_fork_and_restart_sync(&mut self, _: NodeIndex) { self.restarts += 1; }
 		fn restart(&mut self) { self.restarts += 1; }
 	}
 
@@ -726,6 +873,19 @@ pub mod tests {
 		);
 	}
 
+	#[test]
+	fn import_blocks_blocking_stops_at_first_failure() {
+		let (_, _, _, good) = prepare_good_block();
+		let (_, _, _, mut bad) = prepare_good_block();
+		bad.block.header = None;
+
+		let queue = BasicQueue::new(Arc::new(PassThroughVerifier(true)));
+		let chain = test_client::new();
+		assert_eq!(queue.import_blocks_blocking(&chain, BlockOrigin::File, vec![bad, good.clone()]), 0);
+		assert_eq!(queue.import_blocks_blocking(&chain, BlockOrigin::File, vec![good]), 1);
+		assert_eq!(chain.info().unwrap().chain.best_number, 1);
+	}
+
 	#[test]
 	fn process_import_result_works() {
 		let mut link = TestLink::new();
@@ -753,7 +913,12 @@ pub mod tests {
 		assert_eq!(link.disconnects, 1);
 
 		let mut link = TestLink::new();
-		assert_eq!(process_import_result::<Block>(&mut link, Err(BlockImportError::UnknownParent)), 0);
+		assert_eq!(process_import_result::<Block>(&mut link, Err(BlockImportError::UnknownParent(None))), 0);
+		assert_eq!(link.total(), 1);
+		assert_eq!(link.restarts, 1);
+
+		let mut link = TestLink::new();
+		assert_eq!(process_import_result::<Block>(&mut link, Err(BlockImportError::UnknownParent(Some(0)))), 0);
 		assert_eq!(link.total(), 1);
 		assert_eq!(link.restarts, 1);
 
@@ -763,6 +928,81 @@ pub mod tests {
 		assert_eq!(link.restarts, 1);
 	}
 
+	// a chain of `count` blocks built on genesis.
+	fn prepare_good_blocks(count: usize) -> Vec<BlockData<Block>> {
+		let client = test_client::new();
+		(0..count).map(|_| {
+			let block = client.new_block().unwrap().bake().unwrap();
+			client.justify_and_import(BlockOrigin::File, block).unwrap();
+			let id = BlockId::Number(client.info().unwrap().chain.best_number);
+			BlockData {
+				block: message::BlockData::<Block> {
+					hash: client.block_hash(client.info().unwrap().chain.best_number).unwrap().unwrap(),
+					header: client.header(&id).unwrap(),
+					body: client.body(&id).unwrap(),
+					receipt: None,
+					message_queue: None,
+					justification: client.justification(&id).unwrap(),
+				},
+				origin: Some(0),
+			}
+		}).collect()
+	}
+
+	#[derive(Default)]
+	struct BatchingVerifier {
+		batches: Mutex<Vec<usize>>,
+		checked: Mutex<usize>,
+	}
+
+	impl Verifier<Block> for BatchingVerifier {
+		fn verify(
+			&self,
+			origin: BlockOrigin,
+			header: <Block as BlockT>::Header,
+			justification: Vec<u8>,
+			body: Option<Vec<<Block as BlockT>::Extrinsic>>
+		) -> Result<(ImportBlock<Block>, Option<Vec<AuthorityId>>), String> {
+			PassThroughVerifier(true).verify(origin, header, justification, body)
+		}
+
+		fn can_verify_in_parallel(&self) -> bool {
+			true
+		}
+
+		fn prepare_batch(&self, headers: &[&<Block as BlockT>::Header]) {
+			self.batches.lock().push(headers.len());
+		}
+
+		fn check_before_import(&self, _block: &ImportBlock<Block>) -> Result<(), String> {
+			*self.checked.lock() += 1;
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn import_many_blocks_imports_blocks_verified_in_parallel() {
+		let blocks = prepare_good_blocks(3);
+
+		let mut link = TestLink::new();
+		assert!(import_many_blocks(&mut link, None, (BlockOrigin::File, blocks), Arc::new(PassThroughVerifier(true))));
+		assert_eq!(link.imported, 3);
+		assert_eq!(link.chain.info().unwrap().chain.best_number, 3);
+	}
+
+	#[test]
+	fn import_many_blocks_verifies_in_batches() {
+		let count = PARALLEL_VERIFICATION_BATCH + 2;
+		let blocks = prepare_good_blocks(count);
+		let verifier = Arc::new(BatchingVerifier::default());
+
+		let mut link = TestLink::new();
+		assert!(import_many_blocks(&mut link, None, (BlockOrigin::File, blocks), verifier.clone()));
+		assert_eq!(link.imported, count);
+		assert_eq!(*verifier.batches.lock(), vec![PARALLEL_VERIFICATION_BATCH, 2]);
+		assert_eq!(*verifier.checked.lock(), count);
+	}
+
 	#[test]
 	fn import_many_blocks_stops_when_stopping() {
 		let (_, _, _, block) = prepare_good_block();
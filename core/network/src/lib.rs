@@ -33,6 +33,7 @@ extern crate parity_codec as codec;
 extern crate futures;
 extern crate rustc_hex;
 extern crate rand;
+extern crate rayon;
 extern crate tokio;
 #[macro_use] extern crate log;
 #[macro_use] extern crate bitflags;
@@ -70,10 +71,10 @@ pub use chain::Client as ClientHandle;
 pub use service::{Service, FetchFuture, TransactionPool, Params, ManageNetwork, SyncProvider};
 pub use protocol::{ProtocolStatus, PeerInfo, Context};
 pub use sync::{Status as SyncStatus, SyncState};
-pub use network_libp2p::{NonReservedPeerMode, NetworkConfiguration, NodeIndex, ProtocolId, Severity, Protocol};
+pub use network_libp2p::{NonReservedPeerMode, NetworkConfiguration, NodeIndex, PeerId, ProtocolId, Severity, Protocol};
 pub use message::{generic as generic_message, RequestId, Status as StatusMessage};
 pub use error::Error;
-pub use config::{Roles, ProtocolConfig};
+pub use config::{Roles, ProtocolConfig, SyncMode};
 pub use on_demand::{OnDemand, OnDemandService, RemoteResponse};
 #[doc(hidden)]
 pub use runtime_primitives::traits::Block as BlockT;
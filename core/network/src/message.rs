@@ -19,10 +19,10 @@
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use codec::{Encode, Decode, Input, Output};
 pub use self::generic::{
-	BlockAnnounce, RemoteCallRequest, RemoteReadRequest,
+	BlockAnnounce, RemoteCallRequest, RemoteReadRequest, RemoteReadChildRequest,
 	RemoteHeaderRequest, RemoteHeaderResponse,
 	RemoteChangesRequest, RemoteChangesResponse,
-	FromBlock
+	StateRequest, FromBlock, BlockState
 };
 
 /// A unique ID of a request.
@@ -122,13 +122,25 @@ pub struct RemoteReadResponse {
 	pub proof: Vec<Vec<u8>>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+/// State chunk response.
+pub struct StateResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Storage entries, in key order.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Whether there are no more entries past the returned ones.
+	pub complete: bool,
+}
+
 /// Generic types.
 pub mod generic {
+	use codec::{Encode, Decode, Input, Output};
 	use runtime_primitives::Justification;
 	use service::Roles;
 	use super::{
 		BlockAttributes, RemoteCallResponse, RemoteReadResponse,
-		RequestId, Transactions, Direction
+		RequestId, Transactions, Direction, StateResponse
 	};
 	/// Consensus is opaque to us
 	pub type ConsensusMessage = Vec<u8>;
@@ -154,42 +166,70 @@ pub mod generic {
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	pub enum FromBlock<Hash, Number> {
 		/// Start with given hash.
+		#[codec(index = "0")]
 		Hash(Hash),
 		/// Start with given block number.
+		#[codec(index = "1")]
 		Number(Number),
 	}
 
 	/// A network message.
+	///
+	/// Every variant has its index pinned, so that adding or reordering variants doesn't change the
+	/// encoding of the others.
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	pub enum Message<Header, Hash, Number, Extrinsic> {
 		/// Status packet.
+		#[codec(index = "0")]
 		Status(Status<Hash, Number>),
 		/// Block request.
+		#[codec(index = "1")]
 		BlockRequest(BlockRequest<Hash, Number>),
 		/// Block response.
+		#[codec(index = "2")]
 		BlockResponse(BlockResponse<Header, Hash, Extrinsic>),
 		/// Block announce.
+		#[codec(index = "3")]
 		BlockAnnounce(BlockAnnounce<Header>),
 		/// Transactions.
+		#[codec(index = "4")]
 		Transactions(Transactions<Extrinsic>),
 		/// Consensus protocol message.
+		#[codec(index = "5")]
 		Consensus(Hash, ConsensusMessage), // topic, opaque Vec<u8>
 		/// Remote method call request.
+		#[codec(index = "6")]
 		RemoteCallRequest(RemoteCallRequest<Hash>),
 		/// Remote method call response.
+		#[codec(index = "7")]
 		RemoteCallResponse(RemoteCallResponse),
 		/// Remote storage read request.
+		#[codec(index = "8")]
 		RemoteReadRequest(RemoteReadRequest<Hash>),
 		/// Remote storage read response.
+		#[codec(index = "9")]
 		RemoteReadResponse(RemoteReadResponse),
 		/// Remote header request.
+		#[codec(index = "10")]
 		RemoteHeaderRequest(RemoteHeaderRequest<Number>),
 		/// Remote header response.
+		#[codec(index = "11")]
 		RemoteHeaderResponse(RemoteHeaderResponse<Header>),
 		/// Remote changes request.
+		#[codec(index = "12")]
 		RemoteChangesRequest(RemoteChangesRequest<Hash>),
 		/// Remote changes reponse.
+		#[codec(index = "13")]
 		RemoteChangesResponse(RemoteChangesResponse<Number>),
+		/// State chunk request.
+		#[codec(index = "14")]
+		StateRequest(StateRequest<Hash>),
+		/// State chunk response.
+		#[codec(index = "15")]
+		StateResponse(StateResponse),
+		/// Remote child storage read request.
+		#[codec(index = "16")]
+		RemoteReadChildRequest(RemoteReadChildRequest<Hash>),
 		/// Chain-specific message
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -238,11 +278,43 @@ pub mod generic {
 		pub blocks: Vec<BlockData<Header, Hash, Extrinsic>>,
 	}
 
+	/// Block state in the chain.
+	#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+	pub enum BlockState {
+		/// Block is not part of the best chain.
+		#[codec(index = "0")]
+		Normal,
+		/// Latest best block.
+		#[codec(index = "1")]
+		Best,
+	}
+
 	/// Announce a new complete relay chain block on the network.
-	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	#[derive(Debug, PartialEq, Eq, Clone)]
 	pub struct BlockAnnounce<H> {
 		/// New block header.
 		pub header: H,
+		/// Block state. Not sent to or by peers using protocol version 1.
+		pub state: Option<BlockState>,
+	}
+
+	// `state` is encoded after the header without an `Option` prefix, so that
+	// announcements of peers using protocol version 1 can still be decoded.
+	impl<H: Encode> Encode for BlockAnnounce<H> {
+		fn encode_to<T: Output>(&self, dest: &mut T) {
+			self.header.encode_to(dest);
+			if let Some(state) = self.state {
+				state.encode_to(dest);
+			}
+		}
+	}
+
+	impl<H: Decode> Decode for BlockAnnounce<H> {
+		fn decode<I: Input>(input: &mut I) -> Option<Self> {
+			let header = H::decode(input)?;
+			let state = BlockState::decode(input);
+			Some(BlockAnnounce { header, state })
+		}
 	}
 
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -269,6 +341,19 @@ pub mod generic {
 		pub key: Vec<u8>,
 	}
 
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Remote child storage read request.
+	pub struct RemoteReadChildRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block at which to perform call.
+		pub block: H,
+		/// Child Storage key.
+		pub storage_key: Vec<u8>,
+		/// Storage key.
+		pub key: Vec<u8>,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	/// Remote header request.
 	pub struct RemoteHeaderRequest<N> {
@@ -315,4 +400,49 @@ pub mod generic {
 		/// Changes proof.
 		pub proof: Vec<Vec<u8>>,
 	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Request for a chunk of the state of a block.
+	pub struct StateRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block which state is requested.
+		pub block: H,
+		/// Storage key to start the chunk from (including it).
+		pub start: Vec<u8>,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use codec::{Encode, Decode};
+	use test_client::runtime::Header;
+	use super::{BlockAnnounce, BlockState, FromBlock, generic::Message};
+
+	#[test]
+	fn block_announce_without_state_decodes() {
+		let header = Header {
+			parent_hash: Default::default(),
+			number: 1,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		let legacy = header.encode();
+		assert_eq!(BlockAnnounce::<Header>::decode(&mut &legacy[..]), Some(BlockAnnounce { header: header.clone(), state: None }));
+
+		let announce = BlockAnnounce { header, state: Some(BlockState::Best) };
+		let encoded = announce.encode();
+		assert_eq!(BlockAnnounce::<Header>::decode(&mut &encoded[..]), Some(announce));
+	}
+
+	#[test]
+	fn message_indices_are_pinned() {
+		type M = Message<(), (), (), ()>;
+		assert_eq!(M::Transactions(vec![]).encode()[0], 4);
+		assert_eq!(M::Consensus((), vec![]).encode()[0], 5);
+		assert_eq!(M::ChainSpecific(vec![]).encode()[0], 255);
+		assert_eq!(FromBlock::<(), u64>::Number(1).encode()[0], 1);
+		assert_eq!(BlockState::Best.encode(), vec![1]);
+	}
 }
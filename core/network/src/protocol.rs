@@ -40,7 +40,9 @@ use error;
 const REQUEST_TIMEOUT_SEC: u64 = 40;
 
 /// Current protocol version.
-pub (crate) const CURRENT_VERSION: u32 = 1;
+pub (crate) const CURRENT_VERSION: u32 = 2;
+/// Lowest version we support
+const MIN_VERSION: u32 = 1;
 
 // Maximum allowed entries in `BlockResponse`
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
@@ -48,6 +50,8 @@ const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
 /// for at least `LIGHT_MAXIMAL_BLOCKS_DIFFERENCE` blocks, we consider it unuseful
 /// and disconnect to free connection slot.
 const LIGHT_MAXIMAL_BLOCKS_DIFFERENCE: u64 = 8192;
+// Maximum allowed entries in `StateResponse`
+const MAX_STATE_ENTRIES_RESPONSE: usize = 4096;
 
 // Lock must always be taken in order declared here.
 pub struct Protocol<B: BlockT, S: Specialization<B>, H: ExHashT> {
@@ -195,7 +199,8 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 		specialization: S,
 	) -> error::Result<Self> {
 		let info = chain.info()?;
-		let sync = ChainSync::new(config.roles, &info, import_queue);
+		let fast_sync_target = chain.fast_sync_target()?;
+		let sync = ChainSync::new(config.roles, config.sync_mode, &info, fast_sync_target, import_queue);
 		let protocol = Protocol {
 			config: config,
 			context_data: ContextData {
@@ -273,10 +278,13 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 			GenericMessage::RemoteCallResponse(response) => self.on_remote_call_response(io, who, response),
 			GenericMessage::RemoteReadRequest(request) => self.on_remote_read_request(io, who, request),
 			GenericMessage::RemoteReadResponse(response) => self.on_remote_read_response(io, who, response),
+			GenericMessage::RemoteReadChildRequest(request) => self.on_remote_read_child_request(io, who, request),
 			GenericMessage::RemoteHeaderRequest(request) => self.on_remote_header_request(io, who, request),
 			GenericMessage::RemoteHeaderResponse(response) => self.on_remote_header_response(io, who, response),
 			GenericMessage::RemoteChangesRequest(request) => self.on_remote_changes_request(io, who, request),
 			GenericMessage::RemoteChangesResponse(response) => self.on_remote_changes_response(io, who, response),
+			GenericMessage::StateRequest(request) => self.on_state_request(io, who, request),
+			GenericMessage::StateResponse(response) => self.on_state_response(io, who, response),
 			other => self.specialization.write().on_message(&mut ProtocolContext::new(&self.context_data, io), who, &mut Some(other)),
 		}
 	}
@@ -391,6 +399,7 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 	/// Perform time based maintenance.
 	pub fn tick(&self, io: &mut SyncIo) {
 		self.maintain_peers(io);
+		self.sync.write().tick(&mut ProtocolContext::new(&self.context_data, io));
 		self.on_demand.as_ref().map(|s| s.maintain_peers(io));
 	}
 
@@ -416,6 +425,18 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}
 	}
 
+	/// Returns information about all connected peers.
+	pub fn peers(&self) -> Vec<(NodeIndex, PeerInfo<B>)> {
+		self.context_data.peers.read().iter().map(|(who, p)| {
+			(*who, PeerInfo {
+				roles: p.roles,
+				protocol_version: p.protocol_version,
+				best_hash: p.best_hash,
+				best_number: p.best_number,
+			})
+		}).collect()
+	}
+
 	#[allow(dead_code)]
 	pub fn peer_info(&self, peer: NodeIndex) -> Option<PeerInfo<B>> {
 		self.context_data.peers.read().get(&peer).map(|p| {
@@ -443,7 +464,7 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 				io.report_peer(who, Severity::Bad(&format!("Peer is on different chain (our genesis: {} theirs: {})", self.genesis_hash, status.genesis_hash)));
 				return;
 			}
-			if status.version != CURRENT_VERSION {
+			if status.version < MIN_VERSION || status.version > CURRENT_VERSION {
 				io.report_peer(who, Severity::Bad(&format!("Peer using unsupported protocol version {}", status.version)));
 				return;
 			}
@@ -572,18 +593,28 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 	pub fn on_block_announce(&self, io: &mut SyncIo, who: NodeIndex, announce: message::BlockAnnounce<B::Header>) {
 		let header = announce.header;
 		let hash = header.hash();
+		// peers using protocol version 1 only announce their best blocks.
+		let is_best = announce.state.map_or(true, |state| state == message::BlockState::Best);
 		{
 			let mut peers = self.context_data.peers.write();
 			if let Some(ref mut peer) = peers.get_mut(&who) {
 				peer.known_blocks.insert(hash.clone());
 			}
 		}
-		self.on_demand.as_ref().map(|s| s.on_block_announce(who, *header.number()));
-		self.sync.write().on_block_announce(&mut ProtocolContext::new(&self.context_data, io), who, hash, &header);
+		if is_best {
+			self.on_demand.as_ref().map(|s| s.on_block_announce(who, *header.number()));
+		}
+		self.sync.write().on_block_announce(&mut ProtocolContext::new(&self.context_data, io), who, hash, &header, is_best);
 	}
 
-	pub fn on_block_imported(&self, io: &mut SyncIo, hash: B::Hash, header: &B::Header) {
-		self.sync.write().update_chain_info(&header);
+	pub fn on_block_imported(&self, io: &mut SyncIo, hash: B::Hash, header: &B::Header, is_best: bool) {
+		{
+			let mut sync = self.sync.write();
+			if is_best {
+				sync.new_best_block(&mut ProtocolContext::new(&self.context_data, io), &hash, header);
+			}
+			sync.update_chain_info(&header);
+		}
 		self.specialization.write().on_block_imported(
 			&mut ProtocolContext::new(&self.context_data, io),
 			hash.clone(),
@@ -598,16 +629,22 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 		// send out block announcements
 		let mut peers = self.context_data.peers.write();
 
+		let state = if is_best { message::BlockState::Best } else { message::BlockState::Normal };
 		for (who, ref mut peer) in peers.iter_mut() {
 			if peer.known_blocks.insert(hash.clone()) {
 				trace!(target: "sync", "Announcing block {:?} to {}", hash, who);
 				self.send_message(io, *who, GenericMessage::BlockAnnounce(message::BlockAnnounce {
-					header: header.clone()
+					header: header.clone(),
+					state: if peer.protocol_version >= 2 { Some(state) } else { None },
 				}));
 			}
 		}
 	}
 
+	pub fn on_block_finalized(&self, header: &B::Header) {
+		self.sync.write().block_finalized(*header.number());
+	}
+
 	fn on_remote_call_request(&self, io: &mut SyncIo, who: NodeIndex, request: message::RemoteCallRequest<B::Hash>) {
 		trace!(target: "sync", "Remote call request {} from {} ({} at {})", request.id, who, request.method, request.block);
 		let proof = match self.context_data.chain.execution_proof(&request.block, &request.method, &request.data) {
@@ -644,6 +681,23 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 			id: request.id, proof,
 		}));
 	}
+
+	fn on_remote_read_child_request(&self, io: &mut SyncIo, who: NodeIndex, request: message::RemoteReadChildRequest<B::Hash>) {
+		trace!(target: "sync", "Remote read child request {} from {} ({} {} at {})",
+			request.id, who, request.storage_key.to_hex(), request.key.to_hex(), request.block);
+		let proof = match self.context_data.chain.read_child_proof(&request.block, &request.storage_key, &request.key) {
+			Ok(proof) => proof,
+			Err(error) => {
+				trace!(target: "sync", "Remote read child request {} from {} ({} {} at {}) failed with: {}",
+					request.id, who, request.storage_key.to_hex(), request.key.to_hex(), request.block, error);
+				Default::default()
+			},
+		};
+		self.send_message(io, who, GenericMessage::RemoteReadResponse(message::RemoteReadResponse {
+			id: request.id, proof,
+		}));
+	}
+
 	fn on_remote_read_response(&self, io: &mut SyncIo, who: NodeIndex, response: message::RemoteReadResponse) {
 		trace!(target: "sync", "Remote read response {} from {}", response.id, who);
 		self.on_demand.as_ref().map(|s| s.on_remote_read_response(io, who, response));
@@ -692,6 +746,27 @@ impl<B: BlockT, S: Specialization<B>, H: ExHashT> Protocol<B, S, H> {
 		self.on_demand.as_ref().map(|s| s.on_remote_changes_response(io, who, response));
 	}
 
+	fn on_state_request(&self, io: &mut SyncIo, who: NodeIndex, request: message::StateRequest<B::Hash>) {
+		trace!(target: "sync", "State request {} from {} ({} at {})",
+			request.id, who, request.start.to_hex(), request.block);
+		let (entries, complete) = match self.context_data.chain.state_entries(&request.block, &request.start, MAX_STATE_ENTRIES_RESPONSE) {
+			Ok(result) => result,
+			Err(error) => {
+				trace!(target: "sync", "State request {} from {} ({} at {}) failed with: {}",
+					request.id, who, request.start.to_hex(), request.block, error);
+				(Default::default(), false)
+			},
+		};
+		self.send_message(io, who, GenericMessage::StateResponse(message::StateResponse {
+			id: request.id, entries, complete,
+		}));
+	}
+
+	fn on_state_response(&self, io: &mut SyncIo, who: NodeIndex, response: message::StateResponse) {
+		trace!(target: "sync", "State response {} from {} ({} entries)", response.id, who, response.entries.len());
+		self.sync.write().on_state_data(&mut ProtocolContext::new(&self.context_data, io), who, response);
+	}
+
 
 	/// Execute a closure with access to a network context and specialization.
 	pub fn with_spec<F, U>(&self, io: &mut SyncIo, f: F) -> U
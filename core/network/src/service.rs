@@ -20,11 +20,11 @@ use std::{io, thread};
 use std::time::Duration;
 use futures::{self, Future, Stream, stream, sync::oneshot};
 use parking_lot::Mutex;
-use network_libp2p::{ProtocolId, PeerId, NetworkConfiguration, ErrorKind};
+use network_libp2p::{ProtocolId, PeerId, NodeIndex, NetworkConfiguration, ErrorKind};
 use network_libp2p::{start_service, Service as NetworkService, ServiceEvent as NetworkServiceEvent};
 use network_libp2p::{RegisteredProtocol, parse_str_addr, Protocol as Libp2pProtocol};
 use io::NetSyncIo;
-use protocol::{self, Protocol, ProtocolContext, Context, ProtocolStatus};
+use protocol::{self, Protocol, ProtocolContext, Context, ProtocolStatus, PeerInfo};
 use config::{ProtocolConfig};
 use error::Error;
 use chain::Client;
@@ -72,6 +72,12 @@ pub trait SyncProvider<B: BlockT>: Send + Sync {
 	fn status(&self) -> ProtocolStatus<B>;
 	/// Get this node id if available.
 	fn node_id(&self) -> Option<String>;
+	/// Get currently connected peers, with their peer ID if known.
+	fn peers(&self) -> Vec<(NodeIndex, Option<PeerId>, PeerInfo<B>)>;
+	/// Get this node's peer ID.
+	fn local_peer_id(&self) -> String;
+	/// Get the addresses this node is listening on or reachable at, with its peer ID appended.
+	fn listen_addresses(&self) -> Vec<String>;
 }
 
 pub trait ExHashT: ::std::hash::Hash + Eq + ::std::fmt::Debug + Clone + Send + Sync + 'static {}
@@ -161,8 +167,13 @@ impl<B: BlockT + 'static, S: Specialization<B>, H: ExHashT> Service<B, S, H> {
 	}
 
 	/// Called when a new block is imported by the client.
-	pub fn on_block_imported(&self, hash: B::Hash, header: &B::Header) {
-		self.handler.on_block_imported(&mut NetSyncIo::new(&self.network, self.protocol_id), hash, header)
+	pub fn on_block_imported(&self, hash: B::Hash, header: &B::Header, is_best: bool) {
+		self.handler.on_block_imported(&mut NetSyncIo::new(&self.network, self.protocol_id), hash, header, is_best)
+	}
+
+	/// Called when a new block is finalized by the client.
+	pub fn on_block_finalized(&self, header: &B::Header) {
+		self.handler.on_block_finalized(header)
 	}
 
 	/// Called when new transactons are imported by the client.
@@ -211,7 +222,8 @@ impl<B: BlockT + 'static, S: Specialization<B>, H: ExHashT> SyncProvider<B> for
 	fn node_id(&self) -> Option<String> {
 		let network = self.network.lock();
 		let ret = network
-			.listeners()
+			.external_addresses()
+			.chain(network.listeners())
 			.next()
 			.map(|addr| {
 				let mut addr = addr.clone();
@@ -220,6 +232,34 @@ impl<B: BlockT + 'static, S: Specialization<B>, H: ExHashT> SyncProvider<B> for
 			});
 		ret
 	}
+
+	fn peers(&self) -> Vec<(NodeIndex, Option<PeerId>, PeerInfo<B>)> {
+		let peers = self.handler.peers();
+		let network = self.network.lock();
+		peers.into_iter().map(|(who, info)| {
+			(who, network.peer_id_of_node(who).cloned(), info)
+		}).collect()
+	}
+
+	fn local_peer_id(&self) -> String {
+		self.network.lock().peer_id().to_base58()
+	}
+
+	fn listen_addresses(&self) -> Vec<String> {
+		let network = self.network.lock();
+		let mut addresses = network
+			.external_addresses()
+			.chain(network.listeners())
+			.map(|addr| {
+				let mut addr = addr.clone();
+				addr.append(Libp2pProtocol::P2p(network.peer_id().clone().into()));
+				addr.to_string()
+			})
+			.collect::<Vec<_>>();
+		addresses.sort();
+		addresses.dedup();
+		addresses
+	}
 }
 
 /// Trait for managing network
@@ -228,8 +268,8 @@ pub trait ManageNetwork: Send + Sync {
 	fn accept_unreserved_peers(&self);
 	/// Set to deny unreserved peers to connect
 	fn deny_unreserved_peers(&self);
-	/// Remove reservation for the peer
-	fn remove_reserved_peer(&self, peer: PeerId);
+	/// Remove reservation for the peer, given its address or `/p2p/<peer id>`
+	fn remove_reserved_peer(&self, peer: String) -> Result<(), String>;
 	/// Add reserved peer
 	fn add_reserved_peer(&self, peer: String) -> Result<(), String>;
 }
@@ -249,14 +289,16 @@ impl<B: BlockT + 'static, S: Specialization<B>, H: ExHashT> ManageNetwork for Se
 		}
 	}
 
-	fn remove_reserved_peer(&self, peer: PeerId) {
+	fn remove_reserved_peer(&self, peer: String) -> Result<(), String> {
+		let (peer_id, _) = parse_str_addr(&peer).map_err(|e| format!("{:?}", e))?;
 		// This method can disconnect a node, in which case we have to properly close it in the
 		// protocol.
-		let disconnected = self.network.lock().remove_reserved_peer(peer);
+		let disconnected = self.network.lock().remove_reserved_peer(peer_id);
 		if let Some(node_index) = disconnected {
 			let mut net_sync = NetSyncIo::new(&self.network, self.protocol_id);
 			self.handler.on_peer_disconnected(&mut net_sync, node_index)
 		}
+		Ok(())
 	}
 
 	fn add_reserved_peer(&self, peer: String) -> Result<(), String> {
@@ -391,7 +433,7 @@ fn run_thread<B: BlockT + 'static, S: Specialization<B>, H: ExHashT>(
 
 	futures::select_all(futures)
 		.and_then(move |_| {
-			debug!("Networking ended");
+			debug!(target: "sync", "Networking ended");
 			Ok(())
 		})
 		.map_err(|(r, _, _)| r)
@@ -16,22 +16,32 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use protocol::Context;
 use network_libp2p::{Severity, NodeIndex};
 use client::{BlockStatus, ClientInfo};
-use consensus::BlockOrigin;
+use consensus::{BlockOrigin, ImportResult};
 use client::error::Error as ClientError;
 use blocks::{self, BlockCollection};
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, As, NumberFor};
 use runtime_primitives::generic::BlockId;
 use message::{self, generic::Message as GenericMessage};
 use service::Roles;
+use config::SyncMode;
 use import_queue::ImportQueue;
 
 // Maximum blocks to request in a single packet.
 const MAX_BLOCKS_TO_REQUEST: usize = 128;
 // Maximum blocks to store in the import queue.
 const MAX_IMPORTING_BLOCKS: usize = 2048;
+// Maximum number of peers downloading the same block range at once.
+const MAX_PARALLEL_DOWNLOADS: u32 = 2;
+// Time after which a block range download is considered stalled and is offered to other peers.
+const BLOCK_DOWNLOAD_STALL_SEC: u64 = 10;
+// Distance from the best block of a peer to the block which state is downloaded by fast sync.
+const FAST_SYNC_TARGET_DISTANCE: u64 = 32;
+// Number of times a peer may provide blocks which can't be connected to our chain before it is dropped.
+const MAX_USELESS_FORKS: usize = 3;
 
 struct PeerSync<B: BlockT> {
 	pub common_hash: B::Hash,
@@ -39,14 +49,53 @@ struct PeerSync<B: BlockT> {
 	pub best_hash: B::Hash,
 	pub best_number: NumberFor<B>,
 	pub state: PeerSyncState<B>,
+	pub recently_requested: Option<Instant>,
+	pub useless_forks: usize,
 }
 
+/// Stage of the search for the common ancestor with a peer. The chain is first walked back
+/// with exponentially growing steps until a common block is found, then the range between
+/// it and the last mismatching block is bisected.
+#[derive(Eq, PartialEq, Debug)]
+enum AncestorSearchState<B: BlockT> {
+	/// Distance to the next block to request, below the current one.
+	ExponentialBackoff(NumberFor<B>),
+	/// Highest known common block and lowest known mismatching block.
+	BinarySearch(NumberFor<B>, NumberFor<B>),
+}
+
+// Implemented manually: deriving would require the block itself to be `Copy`.
+impl<B: BlockT> Clone for AncestorSearchState<B> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<B: BlockT> Copy for AncestorSearchState<B> {}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum PeerSyncState<B: BlockT> {
-	AncestorSearch(NumberFor<B>),
+	AncestorSearch(NumberFor<B>, AncestorSearchState<B>),
 	Available,
 	DownloadingNew(NumberFor<B>),
 	DownloadingStale(B::Hash),
+	DownloadingFastSyncTarget,
+	DownloadingState,
+}
+
+/// Progress of an ongoing fast sync. Headers are imported without execution up to the target
+/// block, which is then imported along with its complete state downloaded from peers. Only the
+/// justification of the target block is required: it finalizes the headers preceding it.
+struct FastSync<B: BlockT> {
+	/// Number of the block which state is downloaded. Chosen when the first peer is considered,
+	/// and moved on to the next block while the target has no justification.
+	target_number: Option<NumberFor<B>>,
+	/// Target block data, once downloaded.
+	target: Option<message::BlockData<B>>,
+	/// Number of state chunks downloaded so far. They are handed to the client as they arrive.
+	chunks: u32,
+	/// Storage key the next state chunk is requested from.
+	next_key: Vec<u8>,
 }
 
 /// Relay chain sync strategy.
@@ -56,8 +105,10 @@ pub struct ChainSync<B: BlockT> {
 	blocks: BlockCollection<B>,
 	best_queued_number: NumberFor<B>,
 	best_queued_hash: B::Hash,
+	best_finalized_number: NumberFor<B>,
 	required_block_attributes: message::BlockAttributes,
 	import_queue: Arc<ImportQueue<B>>,
+	fast_sync: Option<FastSync<B>>,
 }
 
 /// Reported sync state.
@@ -76,6 +127,8 @@ pub struct Status<B: BlockT> {
 	pub state: SyncState,
 	/// Target sync block number.
 	pub best_seen_block: Option<NumberFor<B>>,
+	/// Number of the best block imported or queued for import.
+	pub best_queued_number: NumberFor<B>,
 }
 
 impl<B: BlockT> Status<B> {
@@ -91,9 +144,34 @@ impl<B: BlockT> Status<B> {
 
 impl<B: BlockT> ChainSync<B> {
 	/// Create a new instance.
-	pub(crate) fn new(role: Roles, info: &ClientInfo<B>, import_queue: Arc<ImportQueue<B>>) -> Self {
+	///
+	/// `fast_sync_target` is the target of an unfinished fast sync, which is resumed whatever the
+	/// sync mode: the best block has no state until it completes.
+	pub(crate) fn new(
+		role: Roles,
+		sync_mode: SyncMode,
+		info: &ClientInfo<B>,
+		fast_sync_target: Option<NumberFor<B>>,
+		import_queue: Arc<ImportQueue<B>>,
+	) -> Self {
 		let mut required_block_attributes = message::BlockAttributes::HEADER | message::BlockAttributes::JUSTIFICATION;
-		if role.intersects(Roles::FULL | Roles::AUTHORITY) {
+		let is_full = role.intersects(Roles::FULL | Roles::AUTHORITY);
+		// a new fast sync is only started from genesis, as the state of an existing best block
+		// would be discarded.
+		let is_fast = fast_sync_target.is_some()
+			|| (sync_mode == SyncMode::Fast && info.chain.best_number == As::sa(0));
+		let fast_sync = if is_full && is_fast {
+			Some(FastSync {
+				target_number: fast_sync_target,
+				target: None,
+				chunks: 0,
+				next_key: Vec::new(),
+			})
+		} else {
+			None
+		};
+		// bodies are not downloaded until fast sync is complete.
+		if is_full && fast_sync.is_none() {
 			required_block_attributes |= message::BlockAttributes::BODY;
 		}
 
@@ -103,8 +181,10 @@ impl<B: BlockT> ChainSync<B> {
 			blocks: BlockCollection::new(),
 			best_queued_hash: info.best_queued_hash.unwrap_or(info.chain.best_hash),
 			best_queued_number: info.best_queued_number.unwrap_or(info.chain.best_number),
+			best_finalized_number: info.chain.finalized_number,
 			required_block_attributes,
 			import_queue,
+			fast_sync,
 		}
 	}
 
@@ -127,11 +207,14 @@ impl<B: BlockT> ChainSync<B> {
 		Status {
 			state: state,
 			best_seen_block: best_seen,
+			best_queued_number: self.best_queued_number,
 		}
 	}
 
 	/// Handle new connected peer.
 	pub(crate) fn new_peer(&mut self, protocol: &mut Context<B>, who: NodeIndex) {
+		// useless forks are remembered when sync is restarted.
+		let useless_forks = self.peers.get(&who).map_or(0, |peer| peer.useless_forks);
 		if let Some(info) = protocol.peer_info(who) {
 			match (block_status(&*protocol.client(), &*self.import_queue, info.best_hash), info.best_number) {
 				(Err(e), _) => {
@@ -145,17 +228,19 @@ impl<B: BlockT> ChainSync<B> {
 					protocol.report_peer(who, Severity::Bad(&format!("New peer with unknown genesis hash {} ({}).", info.best_hash, info.best_number)));
 				},
 				(Ok(BlockStatus::Unknown), _) => {
-					let our_best = self.best_queued_number;
-					if our_best > As::sa(0) {
+					let common_best = ::std::cmp::min(self.best_queued_number, info.best_number);
+					if common_best > As::sa(0) {
 						debug!(target:"sync", "New peer with unknown best hash {} ({}), searching for common ancestor.", info.best_hash, info.best_number);
 						self.peers.insert(who, PeerSync {
 							common_hash: self.genesis_hash,
 							common_number: As::sa(0),
 							best_hash: info.best_hash,
 							best_number: info.best_number,
-							state: PeerSyncState::AncestorSearch(our_best),
+							state: PeerSyncState::AncestorSearch(common_best, AncestorSearchState::ExponentialBackoff(As::sa(1))),
+							recently_requested: Some(Instant::now()),
+							useless_forks,
 						});
-						Self::request_ancestry(protocol, who, our_best)
+						Self::request_ancestry(protocol, who, common_best)
 					} else {
 						// We are at genesis, just start downloading
 						debug!(target:"sync", "New peer with best hash {} ({}).", info.best_hash, info.best_number);
@@ -165,6 +250,8 @@ impl<B: BlockT> ChainSync<B> {
 							best_hash: info.best_hash,
 							best_number: info.best_number,
 							state: PeerSyncState::Available,
+							recently_requested: None,
+							useless_forks,
 						});
 						self.download_new(protocol, who)
 					}
@@ -177,6 +264,8 @@ impl<B: BlockT> ChainSync<B> {
 						best_hash: info.best_hash,
 						best_number: info.best_number,
 						state: PeerSyncState::Available,
+						recently_requested: None,
+						useless_forks,
 					});
 				}
 			}
@@ -193,10 +282,11 @@ impl<B: BlockT> ChainSync<B> {
 		let new_blocks = if let Some(ref mut peer) = self.peers.get_mut(&who) {
 			match peer.state {
 				PeerSyncState::DownloadingNew(start_block) => {
+					self.blocks.insert(start_block, response.blocks, who);
 					self.blocks.clear_peer_download(who);
 					peer.state = PeerSyncState::Available;
+					peer.recently_requested = None;
 
-					self.blocks.insert(start_block, response.blocks, who);
 					self.blocks.drain(self.best_queued_number + As::sa(1))
 				},
 				PeerSyncState::DownloadingStale(_) => {
@@ -206,51 +296,103 @@ impl<B: BlockT> ChainSync<B> {
 						block: b
 					}).collect()
 				},
-				PeerSyncState::AncestorSearch(n) => {
-					match response.blocks.get(0) {
-						Some(ref block) => {
-							trace!(target: "sync", "Got ancestry block #{} ({}) from peer {}", n, block.hash, who);
-							match protocol.client().block_hash(n) {
-								Ok(Some(block_hash)) if block_hash == block.hash => {
-									if peer.common_number < n {
-										peer.common_hash = block.hash;
-										peer.common_number = n;
-									}
-									peer.state = PeerSyncState::Available;
-									trace!(target:"sync", "Found common ancestor for peer {}: {} ({})", who, block.hash, n);
-									vec![]
-								},
-								Ok(our_best) if n > As::sa(0) => {
-									trace!(target:"sync", "Ancestry block mismatch for peer {}: theirs: {} ({}), ours: {:?}", who, block.hash, n, our_best);
-									let n = n - As::sa(1);
-									peer.state = PeerSyncState::AncestorSearch(n);
-									Self::request_ancestry(protocol, who, n);
-									return None;
-								},
-								Ok(_) => { // genesis mismatch
-									trace!(target:"sync", "Ancestry search: genesis mismatch for peer {}", who);
-									protocol.report_peer(who, Severity::Bad("Ancestry search: genesis mismatch for peer"));
-									return None;
-								},
+				PeerSyncState::AncestorSearch(n, state) => {
+					let block_hash_match = match (response.blocks.get(0), protocol.client().block_hash(n)) {
+						(Some(ref block), Ok(our_hash)) => {
+							trace!(target: "sync", "Got ancestry block #{} ({}) from peer {}, ours: {:?}", n, block.hash, who, our_hash);
+							our_hash.map_or(false, |hash| hash == block.hash)
+						},
+						(None, _) => {
+							trace!(target:"sync", "Invalid response when searching for ancestor from {}", who);
+							protocol.report_peer(who, Severity::Bad("Invalid response when searching for ancestor"));
+							return None;
+						},
+						(_, Err(e)) => {
+							protocol.report_peer(who, Severity::Useless(&format!("Error answering legitimate blockchain query: {:?}", e)));
+							return None;
+						},
+					};
+					if block_hash_match && peer.common_number < n {
+						peer.common_hash = response.blocks[0].hash;
+						peer.common_number = n;
+					}
+					if !block_hash_match && n == As::sa(0) {
+						trace!(target:"sync", "Ancestry search: genesis mismatch for peer {}", who);
+						protocol.report_peer(who, Severity::Bad("Ancestry search: genesis mismatch for peer"));
+						return None;
+					}
+					match next_ancestor_search_state::<B>(state, n, block_hash_match) {
+						Some((state, n)) => {
+							peer.state = PeerSyncState::AncestorSearch(n, state);
+							peer.recently_requested = Some(Instant::now());
+							Self::request_ancestry(protocol, who, n);
+							return None;
+						},
+						None => {
+							trace!(target:"sync", "Found common ancestor for peer {}: {} ({})", who, peer.common_hash, peer.common_number);
+							peer.state = PeerSyncState::Available;
+							peer.recently_requested = None;
+							vec![]
+						},
+					}
+				},
+				PeerSyncState::DownloadingFastSyncTarget => {
+					peer.state = PeerSyncState::Available;
+					peer.recently_requested = None;
+					let best_queued_hash = self.best_queued_hash;
+					let block = response.blocks.into_iter().next();
+					let is_valid = block.as_ref().map_or(false, |b| b.body.is_some()
+						&& b.header.as_ref().map_or(false, |h| h.hash() == b.hash && *h.parent_hash() == best_queued_hash));
+					let is_justified = match block {
+						Some(message::generic::BlockData { header: Some(ref header), justification: Some(ref justification), .. }) if is_valid =>
+							match self.import_queue.verify_justification(header, justification) {
+								Ok(()) => true,
 								Err(e) => {
-									protocol.report_peer(who, Severity::Useless(&format!("Error answering legitimate blockchain query: {:?}", e)));
+									debug!(target: "sync", "Bad justification for fast sync target block {}: {}", header.hash(), e);
+									protocol.report_peer(who, Severity::Useless("Invalid fast sync target block"));
 									return None;
+								},
+							},
+						_ => false,
+					};
+					match (self.fast_sync.as_mut(), block) {
+						(None, _) => Vec::new(),
+						(Some(fast_sync), Some(block)) if is_valid => if is_justified {
+							trace!(target: "sync", "Got fast sync target block {} from {}", block.hash, who);
+							fast_sync.target = Some(block);
+							Vec::new()
+						} else {
+							// the state can only be imported along with a justified block.
+							trace!(target: "sync", "Fast sync target block {} is not justified, moving on to the next block", block.hash);
+							fast_sync.target_number = fast_sync.target_number.map(|n| n + As::sa(1));
+							if let Some(target_number) = fast_sync.target_number {
+								if let Err(e) = protocol.client().set_fast_sync_target(target_number) {
+									debug!(target: "sync", "Error recording fast sync target: {:?}", e);
 								}
 							}
+							vec![blocks::BlockData { block, origin: Some(who) }]
 						},
-						None => {
-							trace!(target:"sync", "Invalid response when searching for ancestor from {}", who);
-							protocol.report_peer(who, Severity::Bad("Invalid response when searching for ancestor"));
+						(Some(_), _) => {
+							protocol.report_peer(who, Severity::Useless("Invalid fast sync target block"));
 							return None;
-						}
+						},
 					}
 				},
-				PeerSyncState::Available => Vec::new(),
+				PeerSyncState::Available | PeerSyncState::DownloadingState => Vec::new(),
 			}
 		} else {
 			vec![]
 		};
 
+		// headers downloaded during fast sync are imported right away, without execution.
+		if self.fast_sync.is_some() {
+			if !new_blocks.is_empty() {
+				self.import_fast_sync_headers(protocol, new_blocks);
+			}
+			self.maintain_sync(protocol);
+			return None;
+		}
+
 		let best_seen = self.best_seen_block();
 		let is_best = new_blocks.first().and_then(|b| b.block.header.as_ref()).map(|h| best_seen.as_ref().map_or(false, |n| h.number() >= n));
 		let origin = if is_best.unwrap_or_default() { BlockOrigin::NetworkBroadcast } else { BlockOrigin::NetworkInitialSync };
@@ -266,6 +408,94 @@ impl<B: BlockT> ChainSync<B> {
 		Some((origin, new_blocks))
 	}
 
+	pub(crate) fn on_state_data(&mut self, protocol: &mut Context<B>, who: NodeIndex, response: message::StateResponse) {
+		let is_expected = match self.peers.get_mut(&who) {
+			Some(peer) => if peer.state == PeerSyncState::DownloadingState {
+				peer.state = PeerSyncState::Available;
+				peer.recently_requested = None;
+				true
+			} else {
+				false
+			},
+			None => false,
+		};
+		if !is_expected {
+			trace!(target: "sync", "Ignoring unexpected state response from {}", who);
+			return;
+		}
+
+		let complete = match self.fast_sync.as_mut() {
+			Some(fast_sync) => {
+				let is_valid = match response.entries.first() {
+					Some(&(ref key, _)) => *key >= fast_sync.next_key,
+					None => response.complete,
+				};
+				if !is_valid {
+					protocol.report_peer(who, Severity::Useless("Invalid state chunk"));
+					return;
+				}
+				if let Some(&(ref key, _)) = response.entries.last() {
+					fast_sync.next_key = key.clone();
+					fast_sync.next_key.push(0);
+				}
+				let target = match fast_sync.target {
+					Some(ref target) => target.hash,
+					None => return,
+				};
+				trace!(target: "sync", "Got {} state entries from {}, chunk #{}", response.entries.len(), who, fast_sync.chunks);
+				if let Err(e) = protocol.client().import_state_chunk(&target, fast_sync.chunks, response.entries) {
+					debug!(target: "sync", "Error storing state chunk of block {}: {:?}", target, e);
+					return;
+				}
+				fast_sync.chunks += 1;
+				response.complete
+			},
+			None => return,
+		};
+
+		if complete {
+			self.import_fast_sync_state(protocol, who);
+		}
+		self.maintain_sync(protocol);
+	}
+
+	/// Perform time based maintenance: ranges that peers are taking too long to deliver are
+	/// released, so that other peers may download them in parallel.
+	pub(crate) fn tick(&mut self, protocol: &mut Context<B>) {
+		let now = Instant::now();
+		let stall_timeout = Duration::from_secs(BLOCK_DOWNLOAD_STALL_SEC);
+		let mut stalled = false;
+		let mut unresponsive = Vec::new();
+		for (who, peer) in self.peers.iter_mut() {
+			let is_stalled = peer.recently_requested.map_or(false, |requested| now - requested > stall_timeout);
+			match peer.state {
+				PeerSyncState::DownloadingNew(start) if is_stalled => {
+					trace!(target: "sync", "Block download from {} starting at {} stalled, reassigning", who, start);
+					self.blocks.clear_peer_download(*who);
+					peer.recently_requested = None;
+					stalled = true;
+				},
+				PeerSyncState::DownloadingFastSyncTarget | PeerSyncState::DownloadingState if is_stalled => {
+					trace!(target: "sync", "Fast sync download from {} stalled, reassigning", who);
+					peer.state = PeerSyncState::Available;
+					peer.recently_requested = None;
+					stalled = true;
+				},
+				PeerSyncState::AncestorSearch(n, _) if is_stalled => {
+					trace!(target: "sync", "Ancestry search with {} stalled at #{}", who, n);
+					unresponsive.push(*who);
+				},
+				_ => (),
+			}
+		}
+		for who in unresponsive {
+			protocol.report_peer(who, Severity::Useless("Ancestry search timed out"));
+		}
+		if stalled {
+			self.maintain_sync(protocol);
+		}
+	}
+
 	pub fn maintain_sync(&mut self, protocol: &mut Context<B>) {
 		let peers: Vec<NodeIndex> = self.peers.keys().map(|p| *p).collect();
 		for peer in peers {
@@ -288,15 +518,47 @@ impl<B: BlockT> ChainSync<B> {
 		}
 	}
 
+	/// Called when a new best block has been imported. If it does not extend the best
+	/// queued block, the chain has switched forks and the sync state is rewound to the
+	/// common ancestor of both chains.
+	pub(crate) fn new_best_block(&mut self, protocol: &mut Context<B>, hash: &B::Hash, header: &B::Header) {
+		if self.best_queued_hash == *hash || self.best_queued_hash == *header.parent_hash() {
+			return;
+		}
+
+		let route = match protocol.client().tree_route(&self.best_queued_hash, hash) {
+			Ok(route) => route,
+			Err(e) => {
+				// the best queued block might not have been imported yet.
+				trace!(target: "sync", "Error computing route from best queued block {}: {:?}", self.best_queued_hash, e);
+				return;
+			},
+		};
+		if route.retracted().is_empty() {
+			return;
+		}
+
+		let common = route.common_block();
+		debug!(target: "sync", "Switched fork to {} ({}), common ancestor {} ({})", hash, header.number(), common.hash, common.number);
+		self.best_queued_hash = *hash;
+		self.best_queued_number = *header.number();
+		for (_, peer) in self.peers.iter_mut() {
+			if peer.common_number > common.number {
+				peer.common_number = common.number;
+				peer.common_hash = common.hash;
+			}
+		}
+	}
+
 	pub(crate) fn update_chain_info(&mut self, best_header: &B::Header) {
 		let hash = best_header.hash();
 		self.block_imported(&hash, best_header.number().clone())
 	}
 
-	pub(crate) fn on_block_announce(&mut self, protocol: &mut Context<B>, who: NodeIndex, hash: B::Hash, header: &B::Header) {
+	pub(crate) fn on_block_announce(&mut self, protocol: &mut Context<B>, who: NodeIndex, hash: B::Hash, header: &B::Header, is_best: bool) {
 		let number = *header.number();
 		if let Some(ref mut peer) = self.peers.get_mut(&who) {
-			if number > peer.best_number {
+			if is_best && number > peer.best_number {
 				peer.best_number = number;
 				peer.best_hash = hash;
 			}
@@ -307,10 +569,18 @@ impl<B: BlockT> ChainSync<B> {
 			return;
 		}
 
+		// new blocks are not of interest until fast sync is complete.
+		if self.fast_sync.is_some() {
+			return;
+		}
+
 		if !self.is_known_or_already_downloading(protocol, &hash) {
-			let stale = number <= self.best_queued_number;
+			// blocks which are not the best of the peer are downloaded individually.
+			let stale = number <= self.best_queued_number || !is_best;
 			if stale {
-				if !self.is_known_or_already_downloading(protocol, header.parent_hash()) {
+				if number <= self.best_finalized_number {
+					trace!(target: "sync", "Ignoring block announce below finalized block from {}: {} {:?}", who, hash, header);
+				} else if !self.is_known_or_already_downloading(protocol, header.parent_hash()) {
 					trace!(target: "sync", "Ignoring unknown stale block announce from {}: {} {:?}", who, hash, header);
 				} else {
 					trace!(target: "sync", "Considering new stale block announced from {}: {} {:?}", who, hash, header);
@@ -325,6 +595,26 @@ impl<B: BlockT> ChainSync<B> {
 		}
 	}
 
+	/// Called when a block has been finalized. Unknown blocks at or below the finalized
+	/// number are on abandoned forks and are not downloaded any more.
+	pub(crate) fn block_finalized(&mut self, number: NumberFor<B>) {
+		if number > self.best_finalized_number {
+			self.best_finalized_number = number;
+		}
+	}
+
+	/// Called when blocks provided by a peer could not be connected to our chain. Peers doing
+	/// so repeatedly are dropped, otherwise the common ancestor is searched again on restart.
+	pub(crate) fn note_useless_fork(&mut self, protocol: &mut Context<B>, who: NodeIndex) {
+		if let Some(peer) = self.peers.get_mut(&who) {
+			peer.useless_forks += 1;
+			debug!(target: "sync", "Peer {} provided a fork without known ancestor ({} times)", who, peer.useless_forks);
+			if peer.useless_forks >= MAX_USELESS_FORKS {
+				protocol.report_peer(who, Severity::Useless("Repeatedly provided blocks without known ancestor"));
+			}
+		}
+	}
+
 	fn is_known_or_already_downloading(&self, protocol: &mut Context<B>, hash: &B::Hash) -> bool {
 		self.peers.iter().any(|(_, p)| p.state == PeerSyncState::DownloadingStale(*hash))
 			|| block_status(&*protocol.client(), &*self.import_queue, *hash).ok().map_or(false, |s| s != BlockStatus::Unknown)
@@ -384,6 +674,11 @@ impl<B: BlockT> ChainSync<B> {
 
 	// Issue a request for a peer to download new blocks, if any are available
 	fn download_new(&mut self, protocol: &mut Context<B>, who: NodeIndex) {
+		if self.fast_sync.is_some() && self.download_fast_sync_data(protocol, who) {
+			return;
+		}
+		// during fast sync only headers preceding the target block are downloaded.
+		let fast_sync_target = self.fast_sync.as_ref().and_then(|f| f.target_number);
 		if let Some(ref mut peer) = self.peers.get_mut(&who) {
 			let import_status = self.import_queue.status();
 			// when there are too many blocks in the queue => do not try to download new blocks
@@ -397,7 +692,11 @@ impl<B: BlockT> ChainSync<B> {
 			trace!(target: "sync", "Considering new block download from {}, common block is {}, best is {:?}", who, common_number, peer.best_number);
 			match peer.state {
 				PeerSyncState::Available => {
-					if let Some(range) = self.blocks.needed_blocks(who, MAX_BLOCKS_TO_REQUEST, peer.best_number, common_number) {
+					let max_number = match fast_sync_target {
+						Some(target) => ::std::cmp::min(peer.best_number, target - As::sa(1)),
+						None => peer.best_number,
+					};
+					if let Some(range) = self.blocks.needed_blocks(who, MAX_BLOCKS_TO_REQUEST, max_number, common_number, MAX_PARALLEL_DOWNLOADS) {
 						trace!(target: "sync", "Requesting blocks from {}, ({} to {})", who, range.start, range.end);
 						let request = message::generic::BlockRequest {
 							id: 0,
@@ -408,6 +707,7 @@ impl<B: BlockT> ChainSync<B> {
 							max: Some((range.end - range.start).as_() as u32),
 						};
 						peer.state = PeerSyncState::DownloadingNew(range.start);
+						peer.recently_requested = Some(Instant::now());
 						protocol.send_message(who, GenericMessage::BlockRequest(request));
 					} else {
 						trace!(target: "sync", "Nothing to request");
@@ -418,6 +718,164 @@ impl<B: BlockT> ChainSync<B> {
 		}
 	}
 
+	// Issue a request for the fast sync target block or the next chunk of its state, once all the
+	// headers preceding the target are imported. Returns `true` if no headers should be requested.
+	fn download_fast_sync_data(&mut self, protocol: &mut Context<B>, who: NodeIndex) -> bool {
+		let (peer_best, is_available) = match self.peers.get(&who) {
+			Some(peer) => (peer.best_number, peer.state == PeerSyncState::Available),
+			None => return true,
+		};
+		let target_number = match self.fast_sync.as_ref().and_then(|f| f.target_number) {
+			Some(target_number) => target_number,
+			None if peer_best > As::sa(FAST_SYNC_TARGET_DISTANCE) => {
+				let target_number = peer_best - As::sa(FAST_SYNC_TARGET_DISTANCE);
+				info!(target: "sync", "Starting fast sync of the state at block #{}", target_number);
+				if let Err(e) = protocol.client().set_fast_sync_target(target_number) {
+					debug!(target: "sync", "Error recording fast sync target: {:?}", e);
+					return true;
+				}
+				if let Some(ref mut fast_sync) = self.fast_sync {
+					fast_sync.target_number = Some(target_number);
+				}
+				target_number
+			},
+			None => {
+				debug!(target: "sync", "Chain is too short for fast sync, falling back to full sync");
+				self.finish_fast_sync();
+				return false;
+			},
+		};
+		if self.best_queued_number + As::sa(1) < target_number {
+			return false;
+		}
+
+		let is_busy = self.peers.values().any(|p|
+			p.state == PeerSyncState::DownloadingFastSyncTarget || p.state == PeerSyncState::DownloadingState
+		);
+		if !is_available || is_busy || peer_best < target_number {
+			return true;
+		}
+
+		let request = match self.fast_sync.as_ref().and_then(|f| f.target.as_ref().map(|t| (t.hash, f.next_key.clone()))) {
+			None => {
+				trace!(target: "sync", "Requesting fast sync target block #{} from {}", target_number, who);
+				GenericMessage::BlockRequest(message::generic::BlockRequest {
+					id: 0,
+					fields: self.required_block_attributes | message::BlockAttributes::BODY,
+					from: message::FromBlock::Number(target_number),
+					to: None,
+					direction: message::Direction::Ascending,
+					max: Some(1),
+				})
+			},
+			Some((hash, start)) => {
+				trace!(target: "sync", "Requesting state of block {} from {}", hash, who);
+				GenericMessage::StateRequest(message::generic::StateRequest {
+					id: 0,
+					block: hash,
+					start,
+				})
+			},
+		};
+		if let Some(peer) = self.peers.get_mut(&who) {
+			peer.state = match request {
+				GenericMessage::BlockRequest(_) => PeerSyncState::DownloadingFastSyncTarget,
+				_ => PeerSyncState::DownloadingState,
+			};
+			peer.recently_requested = Some(Instant::now());
+		}
+		protocol.send_message(who, request);
+		true
+	}
+
+	// Import headers downloaded during fast sync. Each header must be a child of the previous one,
+	// and only the justifications which come with some of them are checked: all of them are
+	// finalized by the justification of the target block. Sync is restarted on failure.
+	fn import_fast_sync_headers(&mut self, protocol: &mut Context<B>, blocks: Vec<blocks::BlockData<B>>) {
+		for block in blocks {
+			let origin = block.origin;
+			let justification = block.block.justification;
+			let imported = match block.block.header {
+				Some(header) => {
+					let (hash, number) = (header.hash(), *header.number());
+					let verified = if hash != block.block.hash || *header.parent_hash() != self.best_queued_hash {
+						debug!(target: "sync", "Header {} does not extend the best queued block {}", hash, self.best_queued_hash);
+						false
+					} else if let Some(ref justification) = justification {
+						match self.import_queue.verify_justification(&header, justification) {
+							Ok(()) => true,
+							Err(e) => {
+								debug!(target: "sync", "Bad justification for header {}: {}", hash, e);
+								false
+							},
+						}
+					} else {
+						true
+					};
+					verified && match protocol.client().import_header(header, justification) {
+						Ok(ImportResult::Queued) | Ok(ImportResult::AlreadyInChain) => {
+							self.block_imported(&hash, number);
+							true
+						},
+						Ok(result) => {
+							debug!(target: "sync", "Error importing header {}: {:?}", hash, result);
+							false
+						},
+						Err(e) => {
+							debug!(target: "sync", "Error importing header {}: {:?}", hash, e);
+							false
+						},
+					}
+				},
+				None => false,
+			};
+			if !imported {
+				if let Some(who) = origin {
+					protocol.report_peer(who, Severity::Useless("Invalid header received during fast sync"));
+				}
+				self.restart(protocol);
+				return;
+			}
+		}
+	}
+
+	// Import the fast sync target block, which justification has been checked when it was
+	// downloaded, along with the downloaded state and switch to full sync. The state is
+	// downloaded again on failure.
+	fn import_fast_sync_state(&mut self, protocol: &mut Context<B>, who: NodeIndex) {
+		let (target, chunks) = match self.fast_sync.as_mut() {
+			Some(fast_sync) => {
+				fast_sync.next_key.clear();
+				(fast_sync.target.take(), ::std::mem::replace(&mut fast_sync.chunks, 0))
+			},
+			None => return,
+		};
+		let (header, body, justification) = match target {
+			Some(message::generic::BlockData { header: Some(header), body, justification: Some(justification), .. }) =>
+				(header, body, justification),
+			_ => return,
+		};
+
+		let (hash, number) = (header.hash(), *header.number());
+		match protocol.client().import_block_with_state(header, body, justification, chunks) {
+			Ok(ImportResult::Queued) | Ok(ImportResult::AlreadyInChain) => {
+				info!(target: "sync", "Fast sync complete at block #{} ({})", number, hash);
+				self.finish_fast_sync();
+				self.block_imported(&hash, number);
+			},
+			Ok(result) => debug!(target: "sync", "Error importing fast sync target block {}: {:?}", hash, result),
+			Err(e) => {
+				debug!(target: "sync", "Error importing state of block {}: {:?}", hash, e);
+				protocol.report_peer(who, Severity::Useless("Invalid state received during fast sync"));
+			},
+		}
+	}
+
+	fn finish_fast_sync(&mut self) {
+		self.fast_sync = None;
+		self.required_block_attributes |= message::BlockAttributes::BODY;
+	}
+
 	fn request_ancestry(protocol: &mut Context<B>, who: NodeIndex, block: NumberFor<B>) {
 		trace!(target: "sync", "Requesting ancestry block #{} from {}", block, who);
 		let request = message::generic::BlockRequest {
@@ -432,6 +890,44 @@ impl<B: BlockT> ChainSync<B> {
 	}
 }
 
+/// Returns the next state of an ancestor search and the block to request, given whether the
+/// block `n` of the peer matches ours. `None` once the common ancestor has been found.
+fn next_ancestor_search_state<B: BlockT>(
+	state: AncestorSearchState<B>,
+	n: NumberFor<B>,
+	block_hash_match: bool,
+) -> Option<(AncestorSearchState<B>, NumberFor<B>)> {
+	let two: NumberFor<B> = As::sa(2);
+	match state {
+		AncestorSearchState::ExponentialBackoff(distance) => {
+			if block_hash_match && distance == As::sa(1) {
+				// the block we started from is common, no need for a binary search.
+				return None;
+			}
+			if block_hash_match {
+				// the last mismatching block was requested before the distance was doubled.
+				let left = n;
+				let right = n + distance / two;
+				Some((AncestorSearchState::BinarySearch(left, right), left + (right - left) / two))
+			} else {
+				let next = if n > distance { n - distance } else { As::sa(0) };
+				Some((AncestorSearchState::ExponentialBackoff(distance * two), next))
+			}
+		},
+		AncestorSearchState::BinarySearch(mut left, mut right) => {
+			if left >= n {
+				return None;
+			}
+			if block_hash_match {
+				left = n;
+			} else {
+				right = n;
+			}
+			Some((AncestorSearchState::BinarySearch(left, right), left + (right - left) / two))
+		},
+	}
+}
+
 /// Get block status, taking into account import queue.
 fn block_status<B: BlockT>(
 	chain: &::chain::Client<B>,
@@ -166,7 +166,7 @@ impl<V: 'static + Verifier<Block>> Peer<V> {
 				Arc::downgrade(&self.sync.sync()),
 				Arc::downgrade(&self.executor),
 				Arc::downgrade(&self.sync.context_data().chain)).expect("Test ImportQueue always starts");
-		self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), info.chain.best_hash, &header);
+		self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), info.chain.best_hash, &header, true);
 	}
 
 	/// Called on connection to other indicated peer.
@@ -209,7 +209,7 @@ impl<V: 'static + Verifier<Block>> Peer<V> {
 	fn send_import_notifications(&self) {
 		let info = self.client.info().expect("In-mem client does not fail");
 		let header = self.client.header(&BlockId::Hash(info.chain.best_hash)).unwrap().unwrap();
-		self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), info.chain.best_hash, &header);
+		self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), info.chain.best_hash, &header, true);
 	}
 
 	/// Restart sync for a peer.
@@ -240,7 +240,7 @@ impl<V: 'static + Verifier<Block>> Peer<V> {
 			trace!("Generating {}, (#{}, parent={})", hash, block.header.number, block.header.parent_hash);
 			let header = block.header.clone();
 			self.client.justify_and_import(origin, block).unwrap();
-			self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), hash, &header);
+			self.sync.on_block_imported(&mut TestIo::new(&self.queue, None), hash, &header, true);
 		}
 	}
 
@@ -324,7 +324,37 @@ pub trait TestNetFactory: Sized {
 
 	/// Add a peer.
 	fn add_peer(&mut self, config: &ProtocolConfig) {
-		let client = Arc::new(test_client::new());
+		let peer = self.make_peer(Arc::new(test_client::new()), config);
+		self.mut_peers(|peers| {
+			peers.push(peer.clone())
+		});
+	}
+
+	/// Replace a peer by a new one using the same client, as if the node was restarted. The
+	/// packets sent to the peer which it has yet to receive are dropped.
+	fn reboot_peer(&mut self, i: usize, config: &ProtocolConfig) {
+		let client = self.peer(i).client.clone();
+		let peer = self.make_peer(client, config);
+		self.mut_peers(|peers| {
+			for j in 0..peers.len() {
+				if j != i {
+					peers[j].queue.write().retain(|packet| packet.recipient != i as NodeIndex);
+					peers[j].on_disconnect(i as NodeIndex);
+				}
+			}
+			peers[i] = peer.clone();
+			peers[i].start();
+			for j in 0..peers.len() {
+				if j != i {
+					peers[i].on_connect(j as NodeIndex);
+					peers[j].on_connect(i as NodeIndex);
+				}
+			}
+		});
+	}
+
+	/// Create a peer using the given client.
+	fn make_peer(&self, client: Arc<PeersClient>, config: &ProtocolConfig) -> Arc<Peer<Self::Verifier>> {
 		let tx_pool = Arc::new(EmptyTransactionPool);
 		let verifier = self.make_verifier(client.clone(), config);
 		let import_queue = Arc::new(SyncImportQueue::new(verifier));
@@ -340,16 +370,12 @@ pub trait TestNetFactory: Sized {
 			specialization
 		).unwrap();
 
-		let peer = Arc::new(Peer::new(
+		Arc::new(Peer::new(
 			client,
 			Arc::new(sync),
 			Arc::new(RwLock::new(VecDeque::new())),
 			import_queue
-		));
-
-		self.mut_peers(|peers| {
-			peers.push(peer.clone())
-		});
+		))
 	}
 
 	/// Start network.
@@ -18,7 +18,7 @@ use client::backend::Backend;
 use client::blockchain::HeaderBackend as BlockchainHeaderBackend;
 use consensus::BlockOrigin;
 use sync::SyncState;
-use Roles;
+use {Roles, SyncMode};
 use super::*;
 
 #[test]
@@ -45,6 +45,61 @@ fn sync_from_two_peers_with_ancestry_search_works() {
 	assert!(net.peer(0).client.backend().blockchain().canon_equals_to(net.peer(1).client.backend().blockchain()));
 }
 
+#[test]
+fn fast_sync_works() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+	let mut fast_config = ProtocolConfig::default();
+	fast_config.sync_mode = SyncMode::Fast;
+	net.add_peer(&fast_config);
+	net.add_peer(&ProtocolConfig::default());
+	net.add_peer(&ProtocolConfig::default());
+	net.peer(1).push_blocks(100, false);
+	net.peer(2).push_blocks(100, false);
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().canon_equals_to(net.peer(1).client.backend().blockchain()));
+	// blocks preceding the fast sync target are imported without bodies
+	assert!(net.peer(0).client.body(&BlockId::Number(1)).unwrap().is_none());
+	assert!(net.peer(0).client.body(&BlockId::Number(100)).unwrap().is_some());
+	assert_eq!(net.peer(0).sync.status().sync.state, SyncState::Idle);
+}
+
+#[test]
+fn fast_sync_resumes_after_restart() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(0);
+	let mut fast_config = ProtocolConfig::default();
+	fast_config.sync_mode = SyncMode::Fast;
+	net.add_peer(&fast_config);
+	net.add_peer(&ProtocolConfig::default());
+	net.peer(1).push_blocks(500, false);
+	net.start();
+	while net.peer(0).client.info().unwrap().chain.best_number == 0 {
+		net.sync_step();
+	}
+	// the headers imported so far have no state: fast sync must resume even in full sync mode.
+	assert!(net.peer(0).client.info().unwrap().chain.best_number < 468);
+	net.reboot_peer(0, &ProtocolConfig::default());
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().canon_equals_to(net.peer(1).client.backend().blockchain()));
+	assert!(net.peer(0).client.body(&BlockId::Number(1)).unwrap().is_none());
+	assert!(net.peer(0).client.body(&BlockId::Number(500)).unwrap().is_some());
+	assert_eq!(net.peer(0).client.fast_sync_target().unwrap(), None);
+}
+
+#[test]
+fn sync_to_deep_fork_works() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer(0).push_blocks(10, false);
+	net.peer(1).push_blocks(10, false);
+	net.peer(0).push_blocks(100, true);
+	net.peer(1).push_blocks(150, false);
+	net.restart_peer(0);
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().canon_equals_to(net.peer(1).client.backend().blockchain()));
+}
+
 #[test]
 fn sync_long_chain_works() {
 	let mut net = TestNet::new(2);
@@ -52,7 +52,7 @@ pub fn rpc_handler<Block: BlockT, ExHash, PendingExtrinsics, S, C, A, Y>(
 	S: apis::state::StateApi<Block::Hash, Metadata=Metadata>,
 	C: apis::chain::ChainApi<Block::Hash, Block::Header, NumberFor<Block>, Block::Extrinsic, Metadata=Metadata>,
 	A: apis::author::AuthorApi<ExHash, Block::Hash, Block::Extrinsic, PendingExtrinsics, Metadata=Metadata>,
-	Y: apis::system::SystemApi,
+	Y: apis::system::SystemApi<Block::Hash, NumberFor<Block>>,
 {
 	let mut io = pubsub::PubSubHandler::default();
 	io.extend_with(state.to_delegate());
@@ -63,23 +63,30 @@ pub fn rpc_handler<Block: BlockT, ExHash, PendingExtrinsics, S, C, A, Y>(
 }
 
 /// Start HTTP server listening on given address.
+///
+/// `cors` lists the origins allowed to make requests, `None` allows any origin.
 pub fn start_http(
 	addr: &std::net::SocketAddr,
+	cors: Option<&Vec<String>>,
 	io: RpcHandler,
 ) -> io::Result<http::Server> {
 	http::ServerBuilder::new(io)
 		.threads(4)
-		.rest_api(http::RestApi::Unsecure)
-		.cors(http::DomainsValidation::Disabled)
+		.rest_api(if cors.is_some() { http::RestApi::Secure } else { http::RestApi::Unsecure })
+		.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
 		.start_http(addr)
 }
 
 /// Start WS server listening on given address.
+///
+/// `cors` lists the origins allowed to connect, `None` allows any origin.
 pub fn start_ws(
 	addr: &std::net::SocketAddr,
+	cors: Option<&Vec<String>>,
 	io: RpcHandler,
 ) -> io::Result<ws::Server> {
 	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| Metadata::new(context.sender()))
+		.allowed_origins(map_cors(cors))
 		.start(addr)
 		.map_err(|err| match err {
 			ws::Error(ws::ErrorKind::Io(io), _) => io,
@@ -90,3 +97,7 @@ pub fn start_ws(
 			}
 		})
 }
+
+fn map_cors<T: for<'a> From<&'a str>>(cors: Option<&Vec<String>>) -> http::DomainsValidation<T> {
+	cors.map(|origins| origins.iter().map(AsRef::as_ref).map(Into::into).collect::<Vec<_>>()).into()
+}
@@ -17,15 +17,21 @@
 //! Authoring RPC module errors.
 
 use client;
+use keystore;
 use transaction_pool::txpool;
 use rpc;
 
 use errors;
+use policy::UnsafeRpcError;
 
 error_chain! {
 	links {
 		Pool(txpool::error::Error, txpool::error::ErrorKind) #[doc = "Pool error"];
 		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
+		Keystore(keystore::Error, keystore::ErrorKind) #[doc = "Keystore error"];
+	}
+	foreign_links {
+		UnsafeRpcCalled(UnsafeRpcError) #[doc = "Call to an unsafe RPC was denied."];
 	}
 	errors {
 		/// Not implemented yet
@@ -43,6 +49,16 @@ error_chain! {
 			description("extrinsic verification error"),
 			display("Extrinsic verification error: {}", e.description()),
 		}
+		/// Key type is not a 4-byte identifier.
+		BadKeyType {
+			description("bad key type"),
+			display("Key type must be exactly 4 bytes"),
+		}
+		/// Key seed is not 32 bytes.
+		BadSeed {
+			description("bad seed"),
+			display("Key seed must be exactly 32 bytes"),
+		}
 	}
 }
 
@@ -62,6 +78,17 @@ impl From<Error> for rpc::Error {
 				message: e.description().into(),
 				data: Some(format!("{:?}", e).into()),
 			},
+			Error(ErrorKind::BadKeyType, _) => rpc::Error {
+				code: rpc::ErrorCode::InvalidParams,
+				message: "Key type must be exactly 4 bytes.".into(),
+				data: None,
+			},
+			Error(ErrorKind::BadSeed, _) => rpc::Error {
+				code: rpc::ErrorCode::InvalidParams,
+				message: "Key seed must be exactly 32 bytes.".into(),
+				data: None,
+			},
+			Error(ErrorKind::UnsafeRpcCalled(e), _) => e.into(),
 			e => errors::internal(e),
 		}
 	}
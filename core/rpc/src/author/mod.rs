@@ -20,6 +20,7 @@ use std::sync::Arc;
 
 use client::{self, Client};
 use codec::Decode;
+use keystore::{KeyTypeId, Store as Keystore};
 use transaction_pool::{
 	txpool::{
 		ChainApi as PoolChainApi,
@@ -33,6 +34,7 @@ use transaction_pool::{
 };
 use jsonrpc_macros::pubsub;
 use jsonrpc_pubsub::SubscriptionId;
+use policy::UnsafeRpcError;
 use primitives::{Bytes, Blake2Hasher, H256};
 use rpc::futures::{Sink, Stream, Future};
 use runtime_primitives::{generic, traits};
@@ -61,6 +63,15 @@ build_rpc_trait! {
 		#[rpc(name = "author_pendingExtrinsics")]
 		fn pending_extrinsics(&self) -> Result<PendingExtrinsics>;
 
+		/// Insert a key of the given 4-byte type, created from a 32-byte seed, into the keystore.
+		/// Returns the public key. Unsafe.
+		#[rpc(name = "author_insertKey")]
+		fn insert_key(&self, Bytes, Bytes) -> Result<Bytes>;
+
+		/// Generate a new session key bundle and return its SCALE-encoded public keys. Unsafe.
+		#[rpc(name = "author_rotateKeys")]
+		fn rotate_keys(&self) -> Result<Bytes>;
+
 		#[pubsub(name = "author_extrinsicUpdate")] {
 			/// Submit an extrinsic to watch.
 			#[rpc(name = "author_submitAndWatchExtrinsic")]
@@ -74,6 +85,14 @@ build_rpc_trait! {
 	}
 }
 
+/// Keystore access given to the unsafe key management calls.
+pub struct KeyManagement {
+	/// Node keystore
+	pub keystore: Arc<Keystore>,
+	/// Password used to encrypt inserted and generated keys
+	pub password: String,
+}
+
 /// Authoring API
 pub struct Author<B, E, P> where
 	P: PoolChainApi + Sync + Send + 'static,
@@ -84,23 +103,33 @@ pub struct Author<B, E, P> where
 	pool: Arc<Pool<P>>,
 	/// Subscriptions manager
 	subscriptions: Subscriptions,
+	/// Keystore access. `None` if unsafe RPC calls are denied.
+	keys: Option<KeyManagement>,
 }
 
 impl<B, E, P> Author<B, E, P> where
 	P: PoolChainApi + Sync + Send + 'static,
 {
 	/// Create new instance of Authoring API.
+	///
+	/// Key management calls are only served when `keys` is given.
 	pub fn new(
 		client: Arc<Client<B, E, <P as PoolChainApi>::Block>>,
 		pool: Arc<Pool<P>>,
 		subscriptions: Subscriptions,
+		keys: Option<KeyManagement>,
 	) -> Self {
 		Author {
 			client,
 			pool,
 			subscriptions,
+			keys,
 		}
 	}
+
+	fn keys(&self) -> Result<&KeyManagement> {
+		self.keys.as_ref().ok_or_else(|| UnsafeRpcError.into())
+	}
 }
 
 impl<B, E, P> AuthorApi<ExHash<P>, BlockHash<P>, ExtrinsicFor<P>, Vec<ExtrinsicFor<P>>> for Author<B, E, P> where
@@ -131,6 +160,33 @@ impl<B, E, P> AuthorApi<ExHash<P>, BlockHash<P>, ExtrinsicFor<P>, Vec<ExtrinsicF
 		Ok(self.pool.ready().map(|tx| tx.data.clone()).collect())
 	}
 
+	fn insert_key(&self, key_type: Bytes, seed: Bytes) -> Result<Bytes> {
+		let keys = self.keys()?;
+
+		if key_type.len() != 4 {
+			return Err(error::ErrorKind::BadKeyType.into());
+		}
+		if seed.len() != 32 {
+			return Err(error::ErrorKind::BadSeed.into());
+		}
+
+		let mut id: KeyTypeId = Default::default();
+		id.copy_from_slice(&key_type);
+		let mut s = [0; 32];
+		s.copy_from_slice(&seed);
+
+		let public = keys.keystore.insert_by_type(id, &s, &keys.password)?.public();
+		keys.keystore.unlock_by_type(id, &public, &keys.password)?;
+
+		Ok(public.0.to_vec().into())
+	}
+
+	fn rotate_keys(&self) -> Result<Bytes> {
+		let keys = self.keys()?;
+
+		Ok(keys.keystore.rotate_keys(&keys.password)?.to_raw_public().into())
+	}
+
 	fn watch_extrinsic(&self, _metadata: Self::Metadata, subscriber: pubsub::Subscriber<Status<ExHash<P>, BlockHash<P>>>, xt: Bytes) {
 		let submit = || -> Result<_> {
 			let best_block_hash = self.client.info()?.chain.best_hash;
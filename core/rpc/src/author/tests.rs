@@ -27,6 +27,8 @@ use test_client::keyring::Keyring;
 use test_client::runtime::{Extrinsic, Transfer};
 use test_client;
 use tokio::runtime;
+use tempdir::TempDir;
+use keystore::key_types;
 
 fn uxt(sender: Keyring, nonce: u64) -> Extrinsic {
 	let tx = Transfer {
@@ -47,6 +49,7 @@ fn submit_transaction_should_not_cause_error() {
 		client: client.clone(),
 		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
 		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: None,
 	};
 	let h: H256 = hex!("e10ad66bce51ef3e2a1167934ce3740d2d8c703810f9b314e89f2e783f75e826").into();
 
@@ -67,6 +70,7 @@ fn submit_rich_transaction_should_not_cause_error() {
 		client: client.clone(),
 		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client.clone()))),
 		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: None,
 	};
 	let h: H256 = hex!("fccc48291473c53746cd267cf848449edd7711ee6511fba96919d5f9f4859e4f").into();
 
@@ -89,6 +93,7 @@ fn should_watch_extrinsic() {
 		client,
 		pool: pool.clone(),
 		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: None,
 	};
 	let (subscriber, id_rx, data) = ::jsonrpc_macros::pubsub::Subscriber::new_test("test");
 
@@ -129,6 +134,7 @@ fn should_return_pending_extrinsics() {
 		client,
 		pool: pool.clone(),
 		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: None,
 	};
 	let ex = uxt(Keyring::Alice, 0);
 	AuthorApi::submit_rich_extrinsic(&p, ex.clone()).unwrap();
@@ -137,3 +143,58 @@ fn should_return_pending_extrinsics() {
 		Ok(ref expected) if expected == &vec![ex]
 	);
 }
+
+#[test]
+fn key_management_is_denied_without_keystore_access() {
+	let runtime = runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let p = Author {
+		client: client.clone(),
+		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
+		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: None,
+	};
+
+	assert_matches!(
+		AuthorApi::rotate_keys(&p),
+		Err(error::Error(error::ErrorKind::UnsafeRpcCalled(_), _))
+	);
+	assert_matches!(
+		AuthorApi::insert_key(&p, b"fina".to_vec().into(), vec![1; 32].into()),
+		Err(error::Error(error::ErrorKind::UnsafeRpcCalled(_), _))
+	);
+}
+
+#[test]
+fn should_insert_and_rotate_keys() {
+	let runtime = runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let temp_dir = TempDir::new("keystore").unwrap();
+	let keystore = Arc::new(Keystore::open(temp_dir.path().to_owned()).unwrap());
+	let p = Author {
+		client: client.clone(),
+		pool: Arc::new(Pool::new(Default::default(), ChainApi::new(client))),
+		subscriptions: Subscriptions::new(runtime.executor()),
+		keys: Some(KeyManagement { keystore: keystore.clone(), password: "thepassword".into() }),
+	};
+
+	assert_matches!(
+		AuthorApi::insert_key(&p, b"fin".to_vec().into(), vec![1; 32].into()),
+		Err(error::Error(error::ErrorKind::BadKeyType, _))
+	);
+	assert_matches!(
+		AuthorApi::insert_key(&p, b"fina".to_vec().into(), vec![1; 31].into()),
+		Err(error::Error(error::ErrorKind::BadSeed, _))
+	);
+
+	let public = AuthorApi::insert_key(&p, b"fina".to_vec().into(), vec![1; 32].into()).unwrap();
+	let stored = keystore.contents_by_type(key_types::FINALITY).unwrap();
+	assert_eq!(stored.len(), 1);
+	assert_eq!(&stored[0].0[..], &public[..]);
+	assert!(keystore.is_unlocked(&stored[0]));
+
+	let session_keys = AuthorApi::rotate_keys(&p).unwrap();
+	assert_eq!(session_keys.len(), 32 * key_types::SESSION.len());
+	let authoring = keystore.contents_by_type(key_types::AUTHORING).unwrap();
+	assert_eq!(&authoring[0].0[..], &session_keys[..32]);
+}
@@ -57,7 +57,7 @@ build_rpc_trait! {
 		fn block_hash(&self, Trailing<Number>) -> Result<Option<Hash>>;
 
 		/// Get the runtime version.
-		#[rpc(name = "chain_getRuntimeVersion")]
+		#[rpc(name = "chain_getRuntimeVersion", alias = ["state_getRuntimeVersion", ])]
 		fn runtime_version(&self, Trailing<Hash>) -> Result<RuntimeVersion>;
 
 		#[pubsub(name = "chain_newHead")] {
@@ -70,6 +70,16 @@ build_rpc_trait! {
 			fn unsubscribe_new_head(&self, SubscriptionId) -> RpcResult<bool>;
 		}
 
+		#[pubsub(name = "chain_finalizedHead")] {
+			/// New finalized head subscription
+			#[rpc(name = "chain_subscribeFinalizedHeads")]
+			fn subscribe_finalized_heads(&self, Self::Metadata, pubsub::Subscriber<Header>);
+
+			/// Unsubscribe from finalized head subscription.
+			#[rpc(name = "chain_unsubscribeFinalizedHeads")]
+			fn unsubscribe_finalized_heads(&self, SubscriptionId) -> RpcResult<bool>;
+		}
+
 		#[pubsub(name = "chain_runtimeVersion")] {
 			/// New runtime version subscription
 			#[rpc(name = "chain_subscribeRuntimeVersion")]
@@ -175,6 +185,37 @@ impl<B, E, Block> ChainApi<Block::Hash, Block::Header, NumberFor<Block>, Block::
 		Ok(self.subscriptions.cancel(id))
 	}
 
+	fn subscribe_finalized_heads(&self, _metadata: Self::Metadata, subscriber: pubsub::Subscriber<Block::Header>) {
+		self.subscriptions.add(subscriber, |sink| {
+			// send last finalized head right at the start.
+			let header = self.client.info()
+				.map_err(error::Error::from)
+				.and_then(|info| self.header(Some(info.chain.finalized_hash).into()))
+				.and_then(|header| {
+					header.ok_or_else(|| self::error::ErrorKind::Unimplemented.into())
+				})
+				.map_err(Into::into);
+
+			// send further subscriptions
+			let stream = self.client.finality_notification_stream()
+				.map(|notification| Ok(notification.header))
+				.map_err(|e| warn!("Finality notification stream error: {:?}", e));
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(
+					stream::iter_result(vec![Ok(header)])
+						.chain(stream)
+				)
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_finalized_heads(&self, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+
 
 	fn subscribe_runtime_version(&self, _meta: Self::Metadata, subscriber: pubsub::Subscriber<RuntimeVersion>) {
 		let stream = match self.client.storage_changes_notification_stream(Some(&[storage::StorageKey(storage::well_known_keys::CODE.to_vec())])) {
@@ -24,11 +24,15 @@ extern crate jsonrpc_core as rpc;
 extern crate jsonrpc_pubsub;
 extern crate parking_lot;
 extern crate parity_codec as codec;
+extern crate serde;
+extern crate serde_json;
 extern crate substrate_client as client;
+extern crate substrate_keystore as keystore;
 extern crate substrate_transaction_pool as transaction_pool;
 extern crate substrate_primitives as primitives;
 extern crate sr_primitives as runtime_primitives;
 extern crate sr_version as runtime_version;
+extern crate substrate_metadata as runtime_metadata;
 extern crate tokio;
 
 #[macro_use]
@@ -37,6 +41,8 @@ extern crate error_chain;
 extern crate jsonrpc_macros;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate serde_derive;
 
 #[cfg(test)]
 #[macro_use]
@@ -50,6 +56,15 @@ extern crate substrate_test_client as test_client;
 extern crate substrate_consensus_common as consensus;
 #[cfg(test)]
 extern crate rustc_hex;
+#[cfg(test)]
+extern crate tempdir;
+#[cfg(test)]
+extern crate substrate_logger;
+#[cfg(test)]
+#[macro_use]
+extern crate parity_codec_derive;
+#[cfg(test)]
+extern crate srml_system;
 
 mod errors;
 mod helpers;
@@ -60,5 +75,6 @@ pub use subscriptions::Subscriptions;
 pub mod author;
 pub mod chain;
 pub mod metadata;
+pub mod policy;
 pub mod state;
 pub mod system;
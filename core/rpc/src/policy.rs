@@ -0,0 +1,47 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Policy on which RPC calls may be made, shared by all RPC modules.
+
+use std::{error, fmt};
+
+use rpc;
+
+/// Call to an unsafe RPC was denied, because only safe RPCs are exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsafeRpcError;
+
+impl fmt::Display for UnsafeRpcError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "RPC call is unsafe to be called externally")
+	}
+}
+
+impl error::Error for UnsafeRpcError {
+	fn description(&self) -> &str {
+		"call to an unsafe RPC was denied"
+	}
+}
+
+impl From<UnsafeRpcError> for rpc::Error {
+	fn from(_: UnsafeRpcError) -> Self {
+		rpc::Error {
+			code: rpc::ErrorCode::ServerError(2),
+			message: "RPC call is unsafe to be called externally. Restart the node with `--rpc-methods unsafe` to enable it.".into(),
+			data: None,
+		}
+	}
+}
@@ -30,6 +30,11 @@ error_chain! {
 			description("Invalid block range"),
 			display("Cannot resolve a block range ['{:?}' ... '{:?}]. {}", from, to, details),
 		}
+		/// The events of a block couldn't be decoded.
+		InvalidEvents(details: String) {
+			description("Invalid events"),
+			display("Cannot decode the events of the block. {}", details),
+		}
 		/// Not implemented yet
 		Unimplemented {
 			description("not implemented yet"),
@@ -0,0 +1,313 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of the events of a block, driven by the runtime metadata.
+//!
+//! The metadata names the module and the argument types of every event, along with the layouts
+//! of the named types. The events themselves are stored in the `Events` entry of the `System`
+//! storage.
+
+use std::collections::HashMap;
+
+use codec::{Compact, Decode};
+use runtime_metadata::{DecodeDifferent, RuntimeMetadata, TypeMetadata};
+use primitives::{Bytes, twox_128};
+use primitives::storage::StorageKey;
+
+// the storage entry the events are kept in.
+const EVENTS_PREFIX: &str = "System";
+const EVENTS_NAME: &str = "Events";
+
+// bound on the nesting of the type metadata, to catch cyclic layouts.
+const MAX_LAYOUT_DEPTH: usize = 32;
+
+/// The storage key of the events of a block.
+pub fn events_key() -> StorageKey {
+	StorageKey(twox_128(format!("{} {}", EVENTS_PREFIX, EVENTS_NAME).as_bytes()).to_vec())
+}
+
+/// The phase of a block's execution in which an event was deposited.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+	/// While applying the extrinsic with the given index.
+	ApplyExtrinsic(u32),
+	/// While finalising the block.
+	Finalization,
+}
+
+/// A decoded event.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Event {
+	/// When the event was deposited.
+	pub phase: Phase,
+	/// The module that deposited the event.
+	pub module: String,
+	/// The name of the event.
+	pub name: String,
+	/// The arguments of the event.
+	pub arguments: Vec<Argument>,
+}
+
+/// A decoded event argument.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Argument {
+	/// The type of the argument, as given by the metadata.
+	#[serde(rename = "type")]
+	pub ty: String,
+	/// The value of the argument.
+	pub value: Value,
+}
+
+/// The value of a decoded event argument.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+	/// A boolean.
+	Bool(bool),
+	/// An unsigned integer of up to 64 bits.
+	Number(u64),
+	/// A wider unsigned integer, in decimal.
+	BigNumber(String),
+	/// A fixed-size blob, e.g. an account id or a hash.
+	Bytes(Bytes),
+	/// The items of a vector or the fields of a tuple.
+	List(Vec<Value>),
+}
+
+/// The layout of an event argument type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+	/// A boolean.
+	Bool,
+	/// A little-endian unsigned integer of the given width in bytes.
+	Uint(usize),
+	/// A compact-encoded unsigned integer.
+	Compact,
+	/// A blob of the given size.
+	Fixed(usize),
+	/// A vector of the given layout.
+	Vec(Box<Layout>),
+	/// A tuple, or a struct, of the given layouts.
+	Tuple(Vec<Layout>),
+}
+
+/// The layouts of the event argument types.
+///
+/// Types are looked up in the layouts registered here, then in the type metadata of the
+/// runtime. A type is looked up by its full name and then by its last path segment, so that
+/// e.g. `T::AccountId` is found as `AccountId`. Primitive types, arrays, vectors, tuples and
+/// compact encodings of known types are known as well.
+#[derive(Debug, Clone, Default)]
+pub struct EventTypes {
+	layouts: HashMap<String, Layout>,
+}
+
+impl EventTypes {
+	/// Register (or replace) the layout of the type with the given name.
+	pub fn register(&mut self, name: &str, layout: Layout) {
+		self.layouts.insert(name.to_owned(), layout);
+	}
+
+	/// The layout of the type with the given name, if it's known, given the type metadata of
+	/// the runtime.
+	pub fn layout(&self, name: &str, types: &[TypeMetadata]) -> Option<Layout> {
+		self.resolve(name, types, 0)
+	}
+
+	fn resolve(&self, name: &str, types: &[TypeMetadata], depth: usize) -> Option<Layout> {
+		if depth > MAX_LAYOUT_DEPTH {
+			return None;
+		}
+		let name = name.trim();
+		if let Some(layout) = self.layouts.get(name) {
+			return Some(layout.clone());
+		}
+
+		match name {
+			"bool" => return Some(Layout::Bool),
+			"u8" => return Some(Layout::Uint(1)),
+			"u16" => return Some(Layout::Uint(2)),
+			"u32" => return Some(Layout::Uint(4)),
+			"u64" => return Some(Layout::Uint(8)),
+			"u128" => return Some(Layout::Uint(16)),
+			_ => {},
+		}
+		if let Some(inner) = generic_argument(name, "Compact") {
+			return match self.resolve(inner, types, depth + 1)? {
+				Layout::Uint(_) => Some(Layout::Compact),
+				_ => None,
+			};
+		}
+		if let Some(inner) = generic_argument(name, "Vec") {
+			return self.resolve(inner, types, depth + 1).map(|l| Layout::Vec(Box::new(l)));
+		}
+		if name.starts_with('[') && name.ends_with(']') {
+			let mut parts = name[1..name.len() - 1].splitn(2, ';');
+			let item = parts.next()?;
+			let len = parts.next()?.trim().parse().ok()?;
+			return match self.resolve(item, types, depth + 1)? {
+				Layout::Uint(1) => Some(Layout::Fixed(len)),
+				layout => Some(Layout::Tuple(vec![layout; len])),
+			};
+		}
+		if name.starts_with('(') && name.ends_with(')') {
+			return split_top_level(&name[1..name.len() - 1]).into_iter()
+				.map(|field| self.resolve(field, types, depth + 1))
+				.collect::<Option<Vec<_>>>()
+				.map(Layout::Tuple);
+		}
+
+		let declared = types.iter().find(|ty| match ty.name {
+			DecodeDifferent::Decoded(ref n) => n == name,
+			DecodeDifferent::Encode(n) => n == name,
+		});
+		if let Some(declared) = declared {
+			return match declared.layout {
+				DecodeDifferent::Decoded(ref layout) => self.resolve(layout, types, depth + 1),
+				DecodeDifferent::Encode(layout) => self.resolve(layout, types, depth + 1),
+			};
+		}
+
+		match name.rfind("::") {
+			Some(i) => self.resolve(&name[i + 2..], types, depth + 1),
+			None => None,
+		}
+	}
+
+	/// Decode the encoded event records of a block, using the given metadata of its runtime.
+	pub fn decode_events(&self, metadata: &RuntimeMetadata, data: &[u8]) -> Result<Vec<Event>, String> {
+		let has_events_storage = decoded(&metadata.modules)?.iter()
+			.filter_map(|module| module.storage.as_ref())
+			.filter_map(|storage| decoded(storage).ok())
+			.filter(|storage| decoded(&storage.prefix).map_or(false, |p| p == EVENTS_PREFIX))
+			.filter_map(|storage| decoded(&storage.functions).ok())
+			.any(|functions| functions.iter().any(|f| decoded(&f.name).map_or(false, |n| n == EVENTS_NAME)));
+		if !has_events_storage {
+			return Err(format!("Runtime has no {} {} storage", EVENTS_PREFIX, EVENTS_NAME));
+		}
+
+		let types = decoded(&metadata.types)?;
+		let modules = decoded(&metadata.outer_event.events)?;
+		let input = &mut &data[..];
+		let count = Compact::<u32>::decode(input).ok_or("Events are not a vector")?.0;
+
+		(0..count).map(|_| {
+			let phase = match u8::decode(input) {
+				Some(0) => Phase::ApplyExtrinsic(u32::decode(input).ok_or("Invalid extrinsic index")?),
+				Some(1) => Phase::Finalization,
+				_ => return Err("Invalid event phase".into()),
+			};
+
+			let module_index = u8::decode(input).ok_or("Missing event module")? as usize;
+			let &(ref module, ref events) = modules.get(module_index)
+				.ok_or_else(|| format!("Unknown event module {}", module_index))?;
+			let event_index = u8::decode(input).ok_or("Missing event")? as usize;
+			let event = events.get(event_index)
+				.ok_or_else(|| format!("Unknown event {} of module {}", event_index, module))?;
+			let name = decoded(&event.name)?;
+
+			let arguments = decoded(&event.arguments)?.iter().map(|ty| {
+				let layout = self.layout(ty, types)
+					.ok_or_else(|| format!("Unknown type {} of an argument of {}::{}", ty, module, name))?;
+				let value = decode_value(&layout, input)
+					.ok_or_else(|| format!("Invalid argument of {}::{}", module, name))?;
+				Ok(Argument { ty: ty.clone(), value })
+			}).collect::<Result<_, String>>()?;
+
+			Ok(Event { phase, module: module.clone(), name: name.clone(), arguments })
+		}).collect()
+	}
+}
+
+fn decoded<B, O>(value: &DecodeDifferent<B, O>) -> Result<&O, String> {
+	match value {
+		DecodeDifferent::Decoded(o) => Ok(o),
+		DecodeDifferent::Encode(_) => Err("Metadata is not decoded".into()),
+	}
+}
+
+// the argument of the given generic type, e.g. `T` of `Vec<T>`.
+fn generic_argument<'a>(name: &'a str, generic: &str) -> Option<&'a str> {
+	if name.starts_with(generic) && name[generic.len()..].starts_with('<') && name.ends_with('>') {
+		Some(&name[generic.len() + 1..name.len() - 1])
+	} else {
+		None
+	}
+}
+
+// split at the commas that aren't nested in brackets.
+fn split_top_level(s: &str) -> Vec<&str> {
+	let mut parts = Vec::new();
+	let (mut depth, mut start) = (0, 0);
+	for (i, c) in s.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				parts.push(&s[start..i]);
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	parts.push(&s[start..]);
+	parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+fn take(input: &mut &[u8], len: usize) -> Option<Vec<u8>> {
+	if input.len() < len {
+		return None;
+	}
+	let (taken, rest) = input.split_at(len);
+	*input = rest;
+	Some(taken.to_vec())
+}
+
+fn number(n: u128) -> Value {
+	if n <= u64::max_value() as u128 {
+		Value::Number(n as u64)
+	} else {
+		Value::BigNumber(n.to_string())
+	}
+}
+
+fn decode_value(layout: &Layout, input: &mut &[u8]) -> Option<Value> {
+	match *layout {
+		Layout::Bool => match take(input, 1)?[0] {
+			0 => Some(Value::Bool(false)),
+			1 => Some(Value::Bool(true)),
+			_ => None,
+		},
+		Layout::Uint(width) if width <= 8 => Some(Value::Number(
+			take(input, width)?.iter().rev().fold(0u64, |n, &b| (n << 8) | b as u64)
+		)),
+		Layout::Uint(width) if width <= 16 => Some(Value::BigNumber(
+			take(input, width)?.iter().rev().fold(0u128, |n, &b| (n << 8) | b as u128).to_string()
+		)),
+		Layout::Uint(_) => None,
+		Layout::Compact => Compact::<u128>::decode(input).map(|n| number(n.0)),
+		Layout::Fixed(len) => take(input, len).map(|b| Value::Bytes(Bytes(b))),
+		Layout::Vec(ref item) => {
+			let len = Compact::<u32>::decode(input)?.0;
+			(0..len).map(|_| decode_value(item, input)).collect::<Option<_>>().map(Value::List)
+		}
+		Layout::Tuple(ref fields) => fields.iter()
+			.map(|field| decode_value(field, input))
+			.collect::<Option<_>>()
+			.map(Value::List),
+	}
+}
@@ -22,6 +22,7 @@ use std::{
 };
 
 use client::{self, Client, CallExecutor, BlockchainEvents, runtime_api::Metadata};
+use codec::Decode;
 use jsonrpc_macros::Trailing;
 use jsonrpc_macros::pubsub;
 use jsonrpc_pubsub::SubscriptionId;
@@ -32,15 +33,18 @@ use primitives::{Blake2Hasher, Bytes};
 use rpc::Result as RpcResult;
 use rpc::futures::{stream, Future, Sink, Stream};
 use runtime_primitives::generic::BlockId;
-use runtime_primitives::traits::{Block as BlockT, Header};
+use runtime_primitives::traits::{As, Block as BlockT, Header, NumberFor};
+use runtime_metadata::RuntimeMetadata;
 
 use subscriptions::Subscriptions;
 
 mod error;
+mod events;
 #[cfg(test)]
 mod tests;
 
 use self::error::Result;
+pub use self::events::{Argument, Event, EventTypes, Layout, Phase, Value};
 
 build_rpc_trait! {
 	/// Substrate state API
@@ -63,10 +67,19 @@ build_rpc_trait! {
 		#[rpc(name = "state_getStorageSize", alias = ["state_getStorageSizeAt", ])]
 		fn storage_size(&self, StorageKey, Trailing<Hash>) -> Result<Option<u64>>;
 
+		/// Returns a proof of the storage entries at a block's state, which can be checked
+		/// against the state root of that block.
+		#[rpc(name = "state_getReadProof")]
+		fn read_proof(&self, Vec<StorageKey>, Trailing<Hash>) -> Result<Vec<Bytes>>;
+
 		/// Returns the runtime metadata as an opaque blob.
 		#[rpc(name = "state_getMetadata")]
 		fn metadata(&self, Trailing<Hash>) -> Result<Bytes>;
 
+		/// Returns the events deposited in a block, decoded with the runtime metadata at that block.
+		#[rpc(name = "state_getEvents")]
+		fn events(&self, Trailing<Hash>) -> Result<Vec<Event>>;
+
 		/// Query historical storage entries (by key) starting from a block given as the second parameter.
 		///
 		/// NOTE This first returned result contains the initial state of storage for all keys.
@@ -92,6 +105,8 @@ pub struct State<B, E, Block: BlockT> {
 	client: Arc<Client<B, E, Block>>,
 	/// Current subscriptions.
 	subscriptions: Subscriptions,
+	/// The layouts of the event argument types.
+	event_types: EventTypes,
 }
 
 impl<B, E, Block: BlockT> State<B, E, Block> {
@@ -100,8 +115,16 @@ impl<B, E, Block: BlockT> State<B, E, Block> {
 		Self {
 			client,
 			subscriptions,
+			event_types: Default::default(),
 		}
 	}
+
+	/// Decode event arguments with the given type layouts, taking precedence over the type
+	/// metadata of the runtime.
+	pub fn with_event_types(mut self, event_types: EventTypes) -> Self {
+		self.event_types = event_types;
+		self
+	}
 }
 
 impl<B, E, Block> State<B, E, Block> where
@@ -112,6 +135,97 @@ impl<B, E, Block> State<B, E, Block> where
 	fn unwrap_or_best(&self, hash: Trailing<Block::Hash>) -> Result<Block::Hash> {
 		::helpers::unwrap_or_else(|| Ok(self.client.info()?.chain.best_hash), hash)
 	}
+
+	/// Query historical storage changes by checking the keys at every block of the range.
+	fn query_storage_unfiltered(
+		&self,
+		blocks: &[Block::Hash],
+		keys: &[StorageKey],
+	) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
+		let mut result = Vec::new();
+		let mut last_state: HashMap<_, Option<_>> = Default::default();
+		for block in blocks.iter().cloned() {
+			let mut changes = vec![];
+			let id = BlockId::hash(block.clone());
+
+			for key in keys {
+				let (has_changed, data) = {
+					let curr_data = self.client.storage(&id, key)?;
+					let prev_data = last_state.get(key).and_then(|x| x.as_ref());
+
+					(curr_data.as_ref() != prev_data, curr_data)
+				};
+
+				if has_changed {
+					changes.push((key.clone(), data.clone()));
+				}
+
+				last_state.insert(key.clone(), data);
+			}
+
+			result.push(StorageChangeSet {
+				block,
+				changes,
+			});
+		}
+		Ok(result)
+	}
+
+	/// Query historical storage changes using the changes tries index.
+	///
+	/// Only blocks where a key has been changed are checked. Returns `None` if changes
+	/// tries are not supported or the range is not part of the canonical chain.
+	fn query_storage_changes_trie(
+		&self,
+		from_number: NumberFor<Block>,
+		blocks: &[Block::Hash],
+		keys: &[StorageKey],
+	) -> Result<Option<Vec<StorageChangeSet<Block::Hash>>>> {
+		let last = match blocks.last() {
+			Some(last) => *last,
+			None => return Ok(Some(Vec::new())),
+		};
+		let last_number = from_number + As::sa(blocks.len() as u64 - 1);
+		if self.client.block_hash(last_number)? != Some(last) {
+			return Ok(None);
+		}
+
+		let mut changes = vec![Vec::new(); blocks.len()];
+		let first_block = BlockId::hash(blocks[0]);
+		for key in keys {
+			// the initial state of every key is part of the first change set.
+			let mut last_data = self.client.storage(&first_block, key)?;
+			if last_data.is_some() {
+				changes[0].push((key.clone(), last_data.clone()));
+			}
+
+			if blocks.len() == 1 {
+				continue;
+			}
+
+			let mut changed_at = match self.client.key_changes(blocks[1], last, &key.0) {
+				Ok(changed_at) => changed_at,
+				Err(client::error::Error(client::error::ErrorKind::ChangesTriesNotSupported, _)) => return Ok(None),
+				Err(err) => return Err(err.into()),
+			};
+			changed_at.sort();
+			changed_at.dedup_by_key(|&mut (number, _)| number);
+
+			for (number, _) in changed_at {
+				let index = (number - from_number).as_() as usize;
+				let data = self.client.storage(&BlockId::hash(blocks[index]), key)?;
+				if data != last_data {
+					changes[index].push((key.clone(), data.clone()));
+					last_data = data;
+				}
+			}
+		}
+
+		Ok(Some(blocks.iter().cloned().zip(changes).map(|(block, changes)| StorageChangeSet {
+			block,
+			changes,
+		}).collect()))
+	}
 }
 
 impl<B, E, Block> StateApi<Block::Hash> for State<B, E, Block> where
@@ -149,11 +263,35 @@ impl<B, E, Block> StateApi<Block::Hash> for State<B, E, Block> where
 		Ok(self.storage(key, block)?.map(|x| x.0.len() as u64))
 	}
 
+	fn read_proof(&self, keys: Vec<StorageKey>, block: Trailing<Block::Hash>) -> Result<Vec<Bytes>> {
+		let block = self.unwrap_or_best(block)?;
+		trace!(target: "rpc", "Generating read proof at {:?} for {} keys", block, keys.len());
+		let keys = keys.into_iter().map(|key| key.0).collect::<Vec<_>>();
+		Ok(self.client.read_proof_keys(&BlockId::Hash(block), &keys)?
+			.into_iter()
+			.map(Bytes)
+			.collect())
+	}
+
 	fn metadata(&self, block: Trailing<Block::Hash>) -> Result<Bytes> {
 		let block = self.unwrap_or_best(block)?;
 		self.client.metadata(&BlockId::Hash(block)).map(Bytes).map_err(Into::into)
 	}
 
+	fn events(&self, block: Trailing<Block::Hash>) -> Result<Vec<Event>> {
+		let block = self.unwrap_or_best(block)?;
+		let id = BlockId::Hash(block);
+		trace!(target: "rpc", "Querying events at {:?}", block);
+		let data = match self.client.storage(&id, &events::events_key())? {
+			Some(data) => data.0,
+			None => return Ok(Vec::new()),
+		};
+		let metadata = RuntimeMetadata::decode(&mut &self.client.metadata(&id)?[..])
+			.ok_or_else(|| error::ErrorKind::InvalidEvents("Runtime metadata is invalid".into()))?;
+		self.event_types.decode_events(&metadata, &data)
+			.map_err(|e| error::ErrorKind::InvalidEvents(e).into())
+	}
+
 	fn query_storage(&self, keys: Vec<StorageKey>, from: Block::Hash, to: Trailing<Block::Hash>) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
 		let to = self.unwrap_or_best(to)?;
 
@@ -190,33 +328,10 @@ impl<B, E, Block> StateApi<Block::Hash> for State<B, E, Block> where
 					blocks.reverse();
 					blocks
 				};
-				let mut result = Vec::new();
-				let mut last_state: HashMap<_, Option<_>> = Default::default();
-				for block in blocks {
-					let mut changes = vec![];
-					let id = BlockId::hash(block.clone());
-
-					for key in &keys {
-						let (has_changed, data) = {
-							let curr_data = self.client.storage(&id, key)?;
-							let prev_data = last_state.get(key).and_then(|x| x.as_ref());
-
-							(curr_data.as_ref() != prev_data, curr_data)
-						};
-
-						if has_changed {
-							changes.push((key.clone(), data.clone()));
-						}
-
-						last_state.insert(key.clone(), data);
-					}
-
-					result.push(StorageChangeSet {
-						block,
-						changes,
-					});
+				match self.query_storage_changes_trie(*from.number(), &blocks, &keys)? {
+					Some(result) => Ok(result),
+					None => self.query_storage_unfiltered(&blocks, &keys),
 				}
-				Ok(result)
 			},
 			(from, to) => bail!(invalid_block_range(from, to, "Invalid range or unknown block".into())),
 		}
@@ -178,3 +178,184 @@ fn should_query_storage() {
 	});
 	assert_eq!(result.unwrap(), expected);
 }
+
+#[test]
+fn should_query_storage_using_changes_trie() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new_with_changes_trie());
+	let api = State::new(client.clone(), Subscriptions::new(core.executor()));
+
+	let add_block = |nonce| {
+		let mut builder = client.new_block().unwrap();
+		builder.push_transfer(runtime::Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce,
+		}).unwrap();
+		let block = builder.bake().unwrap();
+		let hash = block.header.hash();
+		client.justify_and_import(BlockOrigin::Own, block).unwrap();
+		hash
+	};
+	let block1_hash = add_block(0);
+	let block2_hash = add_block(1);
+	let genesis_hash = client.genesis_hash();
+
+	let key = StorageKey("a52da2b7c269da1366b3ed1cdb7299ce".from_hex().unwrap());
+	let change_set = |block, value| StorageChangeSet {
+		block,
+		changes: vec![(key.clone(), Some(StorageData(value)))],
+	};
+
+	// Query changes from block1 on: its value is the initial state of the range.
+	let result = api.query_storage(vec![key.clone()], block1_hash, None.into());
+
+	assert_eq!(result.unwrap(), vec![
+		change_set(block1_hash, vec![190, 3, 0, 0, 0, 0, 0, 0]),
+		change_set(block2_hash, vec![148, 3, 0, 0, 0, 0, 0, 0]),
+	]);
+
+	// Query all changes
+	let result = api.query_storage(vec![key.clone()], genesis_hash, None.into());
+
+	assert_eq!(result.unwrap(), vec![
+		change_set(genesis_hash, vec![232, 3, 0, 0, 0, 0, 0, 0]),
+		change_set(block1_hash, vec![190, 3, 0, 0, 0, 0, 0, 0]),
+		change_set(block2_hash, vec![148, 3, 0, 0, 0, 0, 0, 0]),
+	]);
+}
+
+#[test]
+fn should_return_read_proof() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = State::new(client, Subscriptions::new(core.executor()));
+
+	let code_key = StorageKey(::primitives::storage::well_known_keys::CODE.to_vec());
+	assert_matches!(
+		api.read_proof(vec![code_key, StorageKey(vec![10])], Some(genesis_hash).into()),
+		Ok(ref proof) if !proof.is_empty()
+	);
+}
+
+#[test]
+fn should_return_no_events_without_any() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = State::new(client, Subscriptions::new(core.executor()));
+
+	assert_matches!(api.events(Some(genesis_hash).into()), Ok(ref events) if events.is_empty());
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+enum SystemEvent {
+	ExtrinsicSuccess,
+	ExtrinsicFailed,
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+enum BalancesEvent {
+	Transfer([u8; 32], [u8; 32], u128, Vec<u32>),
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+enum TestEvent {
+	System(SystemEvent),
+	Balances(BalancesEvent),
+}
+
+#[test]
+fn should_decode_events_with_metadata() {
+	use codec::Encode;
+	use runtime_metadata::{
+		DecodeDifferent, EventMetadata, OuterEventMetadata, OuterDispatchMetadata, RuntimeModuleMetadata,
+		ModuleMetadata, CallMetadata, StorageMetadata, StorageFunctionMetadata, StorageFunctionModifier,
+		StorageFunctionType, TypeMetadata,
+	};
+	use srml_system::{EventRecord, Phase as SystemPhase};
+
+	let event = |name: &str, arguments: &[&str]| EventMetadata {
+		name: DecodeDifferent::Decoded(name.into()),
+		arguments: DecodeDifferent::Decoded(arguments.iter().map(|a| a.to_string()).collect()),
+		documentation: DecodeDifferent::Decoded(vec![]),
+	};
+	let ty = |name: &str, layout: &str| TypeMetadata {
+		name: DecodeDifferent::Decoded(name.into()),
+		layout: DecodeDifferent::Decoded(layout.into()),
+	};
+	let system = RuntimeModuleMetadata {
+		prefix: DecodeDifferent::Decoded("system".into()),
+		module: DecodeDifferent::Decoded(ModuleMetadata {
+			name: DecodeDifferent::Decoded("Module".into()),
+			call: CallMetadata {
+				name: DecodeDifferent::Decoded("Call".into()),
+				functions: DecodeDifferent::Decoded(vec![]),
+			},
+		}),
+		storage: Some(DecodeDifferent::Decoded(StorageMetadata {
+			prefix: DecodeDifferent::Decoded("System".into()),
+			functions: DecodeDifferent::Decoded(vec![StorageFunctionMetadata {
+				name: DecodeDifferent::Decoded("Events".into()),
+				modifier: StorageFunctionModifier::Default,
+				ty: StorageFunctionType::Plain(DecodeDifferent::Decoded("Vec<EventRecord<T::Event>>".into())),
+				documentation: DecodeDifferent::Decoded(vec![]),
+			}]),
+		})),
+	};
+	let mut metadata = RuntimeMetadata {
+		outer_event: OuterEventMetadata {
+			name: DecodeDifferent::Decoded("Event".into()),
+			events: DecodeDifferent::Decoded(vec![
+				("system".into(), vec![event("ExtrinsicSuccess", &[]), event("ExtrinsicFailed", &[])]),
+				("balances".into(), vec![event("Transfer", &["AccountId", "T::AccountId", "Balance", "Vec<u32>"])]),
+			]),
+		},
+		modules: DecodeDifferent::Decoded(vec![system]),
+		outer_dispatch: OuterDispatchMetadata {
+			name: DecodeDifferent::Decoded("Call".into()),
+			calls: DecodeDifferent::Decoded(vec![]),
+		},
+		types: DecodeDifferent::Decoded(vec![ty("AccountId", "[u8; 32]"), ty("Balance", "u128")]),
+	};
+
+	let data = vec![
+		EventRecord {
+			phase: SystemPhase::ApplyExtrinsic(1),
+			event: TestEvent::Balances(BalancesEvent::Transfer([1u8; 32], [2u8; 32], 5, vec![7])),
+		},
+		EventRecord { phase: SystemPhase::Finalization, event: TestEvent::System(SystemEvent::ExtrinsicFailed) },
+	].encode();
+
+	let events = EventTypes::default().decode_events(&metadata, &data).unwrap();
+	assert_eq!(events, vec![
+		Event {
+			phase: Phase::ApplyExtrinsic(1),
+			module: "balances".into(),
+			name: "Transfer".into(),
+			arguments: vec![
+				Argument { ty: "AccountId".into(), value: Value::Bytes(Bytes(vec![1u8; 32])) },
+				Argument { ty: "T::AccountId".into(), value: Value::Bytes(Bytes(vec![2u8; 32])) },
+				Argument { ty: "Balance".into(), value: Value::BigNumber("5".into()) },
+				Argument { ty: "Vec<u32>".into(), value: Value::List(vec![Value::Number(7)]) },
+			],
+		},
+		Event { phase: Phase::Finalization, module: "system".into(), name: "ExtrinsicFailed".into(), arguments: vec![] },
+	]);
+
+	// registered layouts take precedence over the type metadata.
+	let mut types = EventTypes::default();
+	types.register("Balance", Layout::Uint(8));
+	assert_eq!(types.layout("T::Balance", &[ty("Balance", "u128")]), Some(Layout::Uint(8)));
+	assert_eq!(types.layout("Compact<Balance>", &[ty("Balance", "u128")]), Some(Layout::Compact));
+
+	// an event of an unknown module can't be decoded.
+	let unknown = vec![EventRecord { phase: SystemPhase::Finalization, event: (2u8, 0u8) }].encode();
+	assert!(EventTypes::default().decode_events(&metadata, &unknown).is_err());
+
+	// nor can the events of a runtime without the type metadata of the arguments.
+	metadata.types = DecodeDifferent::Decoded(vec![]);
+	assert!(EventTypes::default().decode_events(&metadata, &data).is_err());
+}
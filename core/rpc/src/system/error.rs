@@ -19,14 +19,28 @@
 use rpc;
 
 use errors;
+use policy::UnsafeRpcError;
 
 error_chain! {
+	foreign_links {
+		UnsafeRpcCalled(UnsafeRpcError) #[doc = "Call to an unsafe RPC was denied."];
+	}
 	errors {
 		/// Not implemented yet
 		Unimplemented {
 			description("not yet implemented"),
 			display("Method Not Implemented"),
 		}
+		/// Invalid peer address.
+		InvalidPeer(e: String) {
+			description("invalid peer address"),
+			display("Invalid peer address: {}", e),
+		}
+		/// Invalid log level directives.
+		InvalidLogLevel(e: String) {
+			description("invalid log level"),
+			display("Invalid log level: {}", e),
+		}
 	}
 }
 
@@ -34,6 +48,17 @@ impl From<Error> for rpc::Error {
 	fn from(e: Error) -> Self {
 		match e {
 			Error(ErrorKind::Unimplemented, _) => errors::unimplemented(),
+			Error(ErrorKind::InvalidPeer(e), _) => rpc::Error {
+				code: rpc::ErrorCode::InvalidParams,
+				message: format!("Invalid peer address: {}", e),
+				data: None,
+			},
+			Error(ErrorKind::InvalidLogLevel(e), _) => rpc::Error {
+				code: rpc::ErrorCode::InvalidParams,
+				message: format!("Invalid log level: {}", e),
+				data: None,
+			},
+			Error(ErrorKind::UnsafeRpcCalled(e), _) => e.into(),
 			e => errors::internal(e),
 		}
 	}
@@ -21,11 +21,62 @@ pub mod error;
 #[cfg(test)]
 mod tests;
 
+use serde::Serialize;
+
 use self::error::Result;
 
+/// Properties of the chain, as given by its chain spec.
+pub type Properties = ::serde_json::map::Map<String, ::serde_json::Value>;
+
+/// Node health
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+	/// Number of connected peers
+	pub peers: usize,
+	/// Whether the node is doing major syncing work
+	pub is_syncing: bool,
+	/// Whether the node should have peers
+	///
+	/// False for nodes that are not given any boot or reserved nodes, e.g. on a development chain.
+	pub should_have_peers: bool,
+}
+
+/// Network peer information
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo<Hash, Number> {
+	/// Peer ID
+	pub peer_id: String,
+	/// Roles
+	pub roles: String,
+	/// Protocol version
+	pub protocol_version: u32,
+	/// Peer best block hash
+	pub best_hash: Hash,
+	/// Peer best block number
+	pub best_number: Number,
+}
+
+/// Network state
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkState<Hash, Number> {
+	/// Peer ID of this node
+	pub peer_id: String,
+	/// Addresses this node is listening on or reachable at
+	pub listen_addresses: Vec<String>,
+	/// Connected peers
+	pub peers: Vec<PeerInfo<Hash, Number>>,
+	/// Number of the best block imported or queued for import
+	pub best_number: Number,
+	/// Number of the best block seen among the peers, if any
+	pub best_seen_number: Option<Number>,
+}
+
 build_rpc_trait! {
 	/// Substrate system RPC API
-	pub trait SystemApi {
+	pub trait SystemApi<Hash, Number> {
 		/// Get the node's implementation name. Plain old string.
 		#[rpc(name = "system_name")]
 		fn system_name(&self) -> Result<String>;
@@ -37,5 +88,42 @@ build_rpc_trait! {
 		/// Get the chain's type. Given as a string identifier.
 		#[rpc(name = "system_chain")]
 		fn system_chain(&self) -> Result<String>;
+
+		/// Get the chain's properties, e.g. the token symbol.
+		#[rpc(name = "system_properties")]
+		fn system_properties(&self) -> Result<Properties>;
+
+		/// Return health status of the node.
+		#[rpc(name = "system_health")]
+		fn system_health(&self) -> Result<Health>;
+
+		/// Returns currently connected peers
+		#[rpc(name = "system_peers")]
+		fn system_peers(&self) -> Result<Vec<PeerInfo<Hash, Number>>>;
+
+		/// Returns the state of the network: our addresses, the connected peers and the sync heights.
+		///
+		/// This is an unsafe RPC.
+		#[rpc(name = "system_networkState")]
+		fn system_network_state(&self) -> Result<NetworkState<Hash, Number>>;
+
+		/// Adds a reserved peer, given as a multiaddress ending with its peer id.
+		///
+		/// This is an unsafe RPC.
+		#[rpc(name = "system_addReservedPeer")]
+		fn system_add_reserved_peer(&self, String) -> Result<()>;
+
+		/// Removes a reserved peer, given by its address or `/p2p/<peer id>`.
+		///
+		/// This is an unsafe RPC.
+		#[rpc(name = "system_removeReservedPeer")]
+		fn system_remove_reserved_peer(&self, String) -> Result<()>;
+
+		/// Sets the log levels, given as `target=level` directives, e.g. `sync=debug,txpool=trace`.
+		///
+		/// The directives replace the ones set by any previous call, on top of the levels the node
+		/// was started with. This is an unsafe RPC.
+		#[rpc(name = "system_setLogLevel")]
+		fn system_set_log_level(&self, String) -> Result<()>;
 	}
 }
@@ -16,8 +16,9 @@
 
 use super::*;
 use super::error::*;
+use policy::UnsafeRpcError;
 
-impl SystemApi for () {
+impl SystemApi<u64, u64> for () {
 	fn system_name(&self) -> Result<String> {
 		Ok("testclient".into())
 	}
@@ -27,12 +28,56 @@ impl SystemApi for () {
 	fn system_chain(&self) -> Result<String> {
 		Ok("testchain".into())
 	}
+	fn system_properties(&self) -> Result<Properties> {
+		let mut properties = Properties::new();
+		properties.insert("tokenSymbol".into(), "DEV".into());
+		Ok(properties)
+	}
+	fn system_health(&self) -> Result<Health> {
+		Ok(Health {
+			peers: 1,
+			is_syncing: false,
+			should_have_peers: true,
+		})
+	}
+	fn system_peers(&self) -> Result<Vec<PeerInfo<u64, u64>>> {
+		Ok(vec![PeerInfo {
+			peer_id: "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".into(),
+			roles: "FULL".into(),
+			protocol_version: 1,
+			best_hash: 5,
+			best_number: 1,
+		}])
+	}
+	fn system_network_state(&self) -> Result<NetworkState<u64, u64>> {
+		Ok(NetworkState {
+			peer_id: "QmYvxi8S8SKArUENWNmYmDYhXQSt6XEkuBmNz5hBMh3Jyt".into(),
+			listen_addresses: vec!["/ip4/127.0.0.1/tcp/30333/p2p/QmYvxi8S8SKArUENWNmYmDYhXQSt6XEkuBmNz5hBMh3Jyt".into()],
+			peers: self.system_peers()?,
+			best_number: 1,
+			best_seen_number: Some(1),
+		})
+	}
+	fn system_add_reserved_peer(&self, peer: String) -> Result<()> {
+		if peer.ends_with("/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV") {
+			Ok(())
+		} else {
+			Err(ErrorKind::InvalidPeer(peer).into())
+		}
+	}
+	fn system_remove_reserved_peer(&self, _peer: String) -> Result<()> {
+		Err(UnsafeRpcError.into())
+	}
+	fn system_set_log_level(&self, directives: String) -> Result<()> {
+		::substrate_logger::validate(&directives)
+			.map_err(|e| ErrorKind::InvalidLogLevel(e).into())
+	}
 }
 
 #[test]
 fn system_name_works() {
 	assert_eq!(
-		SystemApi::system_name(&()).unwrap(),
+		SystemApi::<u64, u64>::system_name(&()).unwrap(),
 		"testclient".to_owned()
 	);
 }
@@ -40,7 +85,7 @@ fn system_name_works() {
 #[test]
 fn system_version_works() {
 	assert_eq!(
-		SystemApi::system_version(&()).unwrap(),
+		SystemApi::<u64, u64>::system_version(&()).unwrap(),
 		"0.2.0".to_owned()
 	);
 }
@@ -48,7 +93,53 @@ fn system_version_works() {
 #[test]
 fn system_chain_works() {
 	assert_eq!(
-		SystemApi::system_chain(&()).unwrap(),
+		SystemApi::<u64, u64>::system_chain(&()).unwrap(),
 		"testchain".to_owned()
 	);
 }
+
+#[test]
+fn system_properties_works() {
+	let properties = SystemApi::<u64, u64>::system_properties(&()).unwrap();
+	assert_eq!(properties.get("tokenSymbol"), Some(&"DEV".into()));
+}
+
+#[test]
+fn system_health_serializes_in_camel_case() {
+	let health = SystemApi::<u64, u64>::system_health(&()).unwrap();
+	assert_eq!(
+		::serde_json::to_string(&health).unwrap(),
+		r#"{"peers":1,"isSyncing":false,"shouldHavePeers":true}"#
+	);
+}
+
+#[test]
+fn system_network_state_works() {
+	let state = SystemApi::<u64, u64>::system_network_state(&()).unwrap();
+	assert_eq!(state.peers.len(), 1);
+	assert_eq!(state.best_seen_number, Some(1));
+}
+
+#[test]
+fn system_peers_works() {
+	let peers = SystemApi::<u64, u64>::system_peers(&()).unwrap();
+	assert_eq!(peers.len(), 1);
+	assert_eq!(peers[0].best_number, 1);
+}
+
+#[test]
+fn system_reserved_peer_errors_are_reported() {
+	let error: ::rpc::Error = SystemApi::<u64, u64>::system_add_reserved_peer(&(), "/ip4/127.0.0.1".into()).unwrap_err().into();
+	assert_eq!(error.code, ::rpc::ErrorCode::InvalidParams);
+
+	let error: ::rpc::Error = SystemApi::<u64, u64>::system_remove_reserved_peer(&(), "/p2p/Qm".into()).unwrap_err().into();
+	assert_eq!(error.code, ::rpc::ErrorCode::ServerError(2));
+}
+
+#[test]
+fn system_set_log_level_errors_are_reported() {
+	assert!(SystemApi::<u64, u64>::system_set_log_level(&(), "sync=debug".into()).is_ok());
+
+	let error: ::rpc::Error = SystemApi::<u64, u64>::system_set_log_level(&(), "sync=loud".into()).unwrap_err().into();
+	assert_eq!(error.code, ::rpc::ErrorCode::InvalidParams);
+}
@@ -32,7 +32,8 @@ use codec::{Decode, Encode};
 use error;
 use chain_spec::ChainSpec;
 
-/// Export a range of blocks to a binary stream.
+/// Export a range of blocks to a stream, either SCALE-encoded and prefixed with the
+/// number of blocks or as JSON, one block per line.
 pub fn export_blocks<F, E, W>(config: FactoryFullConfiguration<F>, exit: E, mut output: W, from: FactoryBlockNumber<F>, to: Option<FactoryBlockNumber<F>>, json: bool) -> error::Result<()>
 	where F: ServiceFactory, E: Future<Item=(),Error=()> + Send + 'static, W: Write,
 {
@@ -66,7 +67,8 @@ pub fn export_blocks<F, E, W>(config: FactoryFullConfiguration<F>, exit: E, mut
 		match client.block(&BlockId::number(block))? {
 			Some(block) => {
 				if json {
-					serde_json::to_writer(&mut output, &block).map_err(|e| format!("Eror writing JSON: {}", e))?;
+					serde_json::to_writer(&mut output, &block).map_err(|e| format!("Error writing JSON: {}", e))?;
+					output.write(b"\n")?;
 				} else {
 					output.write(&block.encode())?;
 				}
@@ -84,9 +86,9 @@ pub fn export_blocks<F, E, W>(config: FactoryFullConfiguration<F>, exit: E, mut
 	Ok(())
 }
 
-/// Import blocks from a binary stream.
-pub fn import_blocks<F, E, R>(config: FactoryFullConfiguration<F>, exit: E, mut input: R) -> error::Result<()>
-	where F: ServiceFactory, E: Future<Item=(),Error=()> + Send + 'static, R: Read,
+/// Import blocks from a stream written by `export_blocks`, verifying and executing each of them.
+pub fn import_blocks<F, E, R>(config: FactoryFullConfiguration<F>, exit: E, mut input: R, json: bool) -> error::Result<()>
+	where F: ServiceFactory, E: Future<Item=(),Error=()> + Send + 'static, R: Read + 'static,
 {
 	let client = new_client::<F>(&config)?;
 	let queue = components::FullComponents::<F>::build_import_queue(&config, client.clone())?;
@@ -97,34 +99,50 @@ pub fn import_blocks<F, E, R>(config: FactoryFullConfiguration<F>, exit: E, mut
 		let _ = exit_send.send(());
 	});
 
-	let count: u32 = Decode::decode(&mut input).ok_or("Error reading file")?;
-	info!("Importing {} blocks", count);
-	let mut block_count = 0; 
-	for b in 0 .. count {
+	type FactorySignedBlock<F> = SignedBlock<
+		<<F as ServiceFactory>::Block as Block>::Header,
+		<<F as ServiceFactory>::Block as Block>::Extrinsic,
+	>;
+
+	let blocks: Box<Iterator<Item=Result<FactorySignedBlock<F>, String>>> = if json {
+		info!("Importing blocks");
+		Box::new(serde_json::Deserializer::from_reader(input).into_iter::<FactorySignedBlock<F>>()
+			.map(|block| block.map_err(|e| format!("Error reading JSON: {}", e))))
+	} else {
+		let count: u32 = Decode::decode(&mut input).ok_or("Error reading file")?;
+		info!("Importing {} blocks", count);
+		Box::new((0 .. count).map(move |b| FactorySignedBlock::<F>::decode(&mut input)
+			.ok_or_else(|| format!("Error reading block data at {}.", b))))
+	};
+
+	let mut block_count = 0;
+	for signed in blocks {
 		if exit_recv.try_recv().is_ok() {
 			break;
 		}
-		if let Some(signed) = SignedBlock::<<F::Block as Block>::Header, <F::Block as Block>::Extrinsic>::decode(&mut input) {
-			let header = signed.block.header;
-			let hash = header.hash();
-			let block  = message::BlockData::<F::Block> {
-				hash: hash,
-				justification: Some(signed.justification),
-				header: Some(header),
-				body: Some(signed.block.extrinsics),
-				receipt: None,
-				message_queue: None
-			};
-			// import queue handles verification and importing it into the client
-			queue.import_blocks(BlockOrigin::File, vec![BlockData::<F::Block> { block, origin: None }]);
-		} else {
-			warn!("Error reading block data at {}.", b);
-			break;
+
+		let signed = signed?;
+		let header = signed.block.header;
+		let number = header.number().clone();
+		let hash = header.hash();
+		let block = message::BlockData::<F::Block> {
+			hash: hash,
+			justification: Some(signed.justification),
+			header: Some(header),
+			body: Some(signed.block.extrinsics),
+			receipt: None,
+			message_queue: None
+		};
+
+		// the import queue handles verification and importing it into the client
+		let blocks = vec![BlockData::<F::Block> { block, origin: None }];
+		if queue.import_blocks_blocking(&*client, BlockOrigin::File, blocks) == 0 {
+			return Err(format!("Error importing block #{} ({})", number, hash).into());
 		}
 
-		block_count = b;
-		if b % 1000 == 0 {
-			info!("#{}", b);
+		block_count += 1;
+		if block_count % 1000 == 0 {
+			info!("#{}", number);
 		}
 	}
 	info!("Imported {} blocks. Best: #{}", block_count, client.info()?.chain.best_number);
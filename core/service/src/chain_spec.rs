@@ -79,6 +79,8 @@ enum Genesis<G> {
 	Raw(HashMap<StorageKey, StorageData>),
 }
 
+pub use substrate_rpc::system::Properties;
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct ChainSpecFile {
@@ -88,6 +90,7 @@ struct ChainSpecFile {
 	pub telemetry_url: Option<String>,
 	pub protocol_id: Option<String>,
 	pub consensus_engine: Option<String>,
+	pub properties: Option<Properties>,
 }
 
 /// A configuration of a chain. Can be used to build a genesis block.
@@ -130,6 +133,11 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		self.spec.consensus_engine.as_ref().map(String::as_str)
 	}
 
+	/// Returns the properties of the chain, empty if there are none.
+	pub fn properties(&self) -> Properties {
+		self.spec.properties.clone().unwrap_or_default()
+	}
+
 	/// Parse json content into a `ChainSpec`
 	pub fn from_embedded(json: &'static [u8]) -> Result<Self, String> {
 		let spec = json::from_slice(json).map_err(|e| format!("Error parsing spec file: {}", e))?;
@@ -158,6 +166,7 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		telemetry_url: Option<&str>,
 		protocol_id: Option<&str>,
 		consensus_engine: Option<&str>,
+		properties: Option<Properties>,
 	) -> Self
 	{
 		let spec = ChainSpecFile {
@@ -167,6 +176,7 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 			telemetry_url: telemetry_url.map(str::to_owned),
 			protocol_id: protocol_id.map(str::to_owned),
 			consensus_engine: consensus_engine.map(str::to_owned),
+			properties,
 		};
 		ChainSpec {
 			spec,
@@ -200,3 +210,61 @@ impl<G: RuntimeGenesis> ChainSpec<G> {
 		json::to_string_pretty(&spec).map_err(|e| format!("Error generating spec json: {}", e))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Serialize, Deserialize)]
+	struct TestGenesis(HashMap<String, String>);
+
+	impl BuildStorage for TestGenesis {
+		fn build_storage(self) -> Result<(StorageMap, ChildrenStorageMap), String> {
+			Ok((self.0.into_iter().map(|(k, v)| (k.into_bytes(), v.into_bytes())).collect(), Default::default()))
+		}
+	}
+
+	type TestSpec = ChainSpec<TestGenesis>;
+
+	fn example_spec() -> TestSpec {
+		TestSpec::from_json_file(PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/test-data/chain_spec.json"))).unwrap()
+	}
+
+	#[test]
+	fn should_deserialize_example_chain_spec() {
+		let spec = example_spec();
+
+		assert_eq!(spec.name(), "Flaming Fir");
+		assert_eq!(spec.id(), "flaming-fir");
+		assert_eq!(spec.boot_nodes().len(), 1);
+		assert_eq!(spec.protocol_id(), Some("fir"));
+		assert_eq!(spec.consensus_engine(), None);
+
+		let storage = (&spec).build_storage().unwrap().0;
+		assert_eq!(storage.get(&b"balance:alice"[..]), Some(&b"1000".to_vec()));
+	}
+
+	#[test]
+	fn raw_spec_builds_the_same_storage() {
+		let spec = example_spec();
+		let storage = (&spec).build_storage().unwrap().0;
+
+		let path = ::std::env::temp_dir().join(format!("substrate-raw-chain-spec-{}.json", ::std::process::id()));
+		::std::fs::write(&path, spec.clone().to_json(true).unwrap()).unwrap();
+		let raw_spec = TestSpec::from_json_file(path.clone()).unwrap();
+		let raw_storage = (&raw_spec).build_storage();
+		::std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(raw_spec.id(), spec.id());
+		assert_eq!(raw_spec.telemetry_url(), spec.telemetry_url());
+		assert_eq!(raw_storage.unwrap().0, storage);
+	}
+
+	#[test]
+	fn unknown_genesis_variant_is_rejected() {
+		let json = br#"{"name":"Test","id":"test","bootNodes":[],"genesis":{"other":{}}}"#;
+		let spec = TestSpec::from_embedded(json).unwrap();
+
+		assert!((&spec).build_storage().is_err());
+	}
+}
@@ -20,13 +20,41 @@ use std::net::SocketAddr;
 use transaction_pool;
 use chain_spec::ChainSpec;
 pub use client::ExecutionStrategy;
-pub use network::Roles;
+pub use network::{Roles, SyncMode};
 pub use network::NetworkConfiguration;
 pub use client_db::PruningMode;
 use runtime_primitives::BuildStorage;
 use serde::{Serialize, de::DeserializeOwned};
 use target_info::Target;
 
+/// Set of RPC methods exposed by the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// All methods if every RPC server listens on a loopback interface, safe ones otherwise.
+	Auto,
+	/// Only methods that are safe to expose publicly.
+	Safe,
+	/// All methods, including key management.
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> Self {
+		RpcMethods::Safe
+	}
+}
+
+impl RpcMethods {
+	/// Whether unsafe methods are exposed by RPC servers listening on the given addresses.
+	pub fn allow_unsafe<'a, I>(&self, addresses: I) -> bool where I: IntoIterator<Item=&'a SocketAddr> {
+		match *self {
+			RpcMethods::Safe => false,
+			RpcMethods::Unsafe => true,
+			RpcMethods::Auto => addresses.into_iter().all(|address| address.ip().is_loopback()),
+		}
+	}
+}
+
 /// Service configuration.
 #[derive(Clone)]
 pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
@@ -44,10 +72,19 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub network: NetworkConfiguration,
 	/// Path to key files.
 	pub keystore_path: String,
+	/// Password used to encrypt newly generated keys and to unlock the stored ones.
+	pub password: String,
+	/// Address of an external signer holding the authority key. `None` to sign with the keystore.
+	pub remote_signer: Option<SocketAddr>,
 	/// Path to the database.
 	pub database_path: String,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Block synchronization mode.
+	pub sync_mode: SyncMode,
+	/// Number of slots without a newly finalized block after which blocks are authored and
+	/// accepted on their slot alone. `None` if no finality gadget runs alongside slot authoring.
+	pub fallback_slots: Option<u64>,
 	/// Additional key seeds.
 	pub keys: Vec<String>,
 	/// Chain configuration.
@@ -60,12 +97,22 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub block_execution_strategy: ExecutionStrategy,
 	/// Runtime API execution strategy.
 	pub api_execution_strategy: ExecutionStrategy,
+	/// Maximum number of instructions a call into the Wasm runtime may execute. `None` if calls
+	/// are unmetered.
+	pub fuel_limit: Option<u64>,
+	/// Whether offchain workers run on the new best blocks of a full node.
+	pub offchain_worker: bool,
 	/// RPC over HTTP binding address. `None` if disabled.
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
-	/// Telemetry service URL. `None` if disabled.
-	pub telemetry_url: Option<String>,
+	/// Which RPC methods are exposed.
+	pub rpc_methods: RpcMethods,
+	/// Origins allowed to access the RPC servers. `None` if any origin is allowed.
+	pub rpc_cors: Option<Vec<String>>,
+	/// Telemetry service URLs, each with the maximum verbosity of the events sent to it.
+	/// Empty if disabled.
+	pub telemetry_endpoints: Vec<(String, u8)>,
 }
 
 impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C, G> {
@@ -81,18 +128,29 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			transaction_pool: Default::default(),
 			network: Default::default(),
 			keystore_path: Default::default(),
+			password: Default::default(),
+			remote_signer: None,
 			database_path: Default::default(),
 			keys: Default::default(),
 			custom: Default::default(),
 			pruning: PruningMode::default(),
+			sync_mode: SyncMode::Full,
+			fallback_slots: None,
 			block_execution_strategy: ExecutionStrategy::Both,
 			api_execution_strategy: ExecutionStrategy::Both,
+			fuel_limit: None,
+			offchain_worker: false,
 			rpc_http: None,
 			rpc_ws: None,
-			telemetry_url: None,
+			rpc_methods: Default::default(),
+			rpc_cors: None,
+			telemetry_endpoints: Vec::new(),
 		};
 		configuration.network.boot_nodes = configuration.chain_spec.boot_nodes().to_vec();
-		configuration.telemetry_url = configuration.chain_spec.telemetry_url().map(str::to_owned);
+		configuration.telemetry_endpoints = configuration.chain_spec.telemetry_url()
+			.map(|url| (url.to_owned(), ::tel::SUBSTRATE_INFO))
+			.into_iter()
+			.collect();
 		configuration
 	}
 
@@ -120,3 +178,18 @@ pub fn full_version_from_strs(impl_version: &str, impl_commit: &str) -> String {
 	format!("{}{}{}-{}", impl_version, commit_dash, impl_commit, platform())
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn auto_rpc_methods_are_unsafe_only_on_loopback() {
+		let local: SocketAddr = "127.0.0.1:9933".parse().unwrap();
+		let external: SocketAddr = "0.0.0.0:9944".parse().unwrap();
+
+		assert!(RpcMethods::Auto.allow_unsafe(&[local]));
+		assert!(!RpcMethods::Auto.allow_unsafe(&[local, external]));
+		assert!(!RpcMethods::Safe.allow_unsafe(&[local]));
+		assert!(RpcMethods::Unsafe.allow_unsafe(&[external]));
+	}
+}
@@ -22,17 +22,23 @@ use std::sync::Arc;
 use std::time::{self, Duration, Instant};
 use std;
 
-use client::{self, error, Client as SubstrateClient, CallExecutor};
+use client::{self, error, Client as SubstrateClient, CallExecutor, BlockImportNotification, FinalityNotification};
 use client::runtime_api::{Core, BlockBuilder as BlockBuilderAPI, id::BLOCK_BUILDER};
 use codec::{Decode, Encode};
-use consensus_common::{self, InherentData, evaluation, offline_tracker::OfflineTracker};
-use primitives::{H256, AuthorityId, ed25519, Blake2Hasher};
+use consensus_common::{self, InherentData, InherentDataProviders, Signer, evaluation, offline_tracker::OfflineTracker};
+use primitives::{H256, AuthorityId, Blake2Hasher};
+use runtime_primitives::ApplyError;
 use runtime_primitives::traits::{Block as BlockT, Hash as HashT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
+use runtime_primitives::inherents::well_known_identifiers;
 use transaction_pool::txpool::{self, Pool as TransactionPool};
 
+use futures::Future;
 use parking_lot::RwLock;
 
+use components::{Components, ComponentBlock};
+use Service;
+
 /// Shared offline validator tracker.
 pub type SharedOfflineTracker = Arc<RwLock<OfflineTracker>>;
 type Timestamp = u64;
@@ -40,6 +46,26 @@ type Timestamp = u64;
 // block size limit.
 const MAX_TRANSACTIONS_SIZE: usize = 4 * 1024 * 1024;
 
+/// Block authoring logic run by authority nodes, started with `Service::start_consensus`.
+///
+/// The engine decides when to propose and on top of which block (slots, rounds, mining, ...),
+/// typically building blocks with the service's `ProposerFactory`. Blocks authored by others
+/// are checked by the import queue's `Verifier`, so engines can be swapped without touching
+/// the network or the client.
+pub trait ConsensusEngine<C: Components>: Send + Sync + Sized + 'static {
+	/// Set the engine up for the given service, signing with the local authority key.
+	fn new(service: &Service<C>, signer: Arc<Signer>) -> Result<Self, error::Error>;
+
+	/// The future proposing blocks. Spawned once and dropped when the service exits.
+	fn propose(&self) -> Box<Future<Item=(), Error=()> + Send>;
+
+	/// Evaluate a newly imported block, e.g. to restart work on top of a new best block.
+	fn on_block_imported(&self, _notification: &BlockImportNotification<ComponentBlock<C>>) {}
+
+	/// Called when a block got finalized.
+	fn on_block_finalized(&self, _notification: &FinalityNotification<ComponentBlock<C>>) {}
+}
+
 /// Build new blocks.
 pub trait BlockBuilder<Block: BlockT> {
 	/// Push an extrinsic onto the block. Fails if the extrinsic is invalid.
@@ -118,6 +144,8 @@ pub struct ProposerFactory<C, A> where
 	pub offline: SharedOfflineTracker,
 	/// Force delay in evaluation this long.
 	pub force_delay: Timestamp,
+	/// The providers of the inherent data put into proposed blocks.
+	pub inherent_data_providers: InherentDataProviders,
 }
 
 impl<C, A> consensus_common::Environment<<C as AuthoringApi>::Block> for ProposerFactory<C, A> where
@@ -132,7 +160,7 @@ impl<C, A> consensus_common::Environment<<C as AuthoringApi>::Block> for Propose
 		&self,
 		parent_header: &<<C as AuthoringApi>::Block as BlockT>::Header,
 		_: &[AuthorityId],
-		_: Arc<ed25519::Pair>,
+		_: Arc<Signer>,
 	) -> Result<Self::Proposer, error::Error> {
 		let parent_hash = parent_header.hash();
 
@@ -154,6 +182,7 @@ impl<C, A> consensus_common::Environment<<C as AuthoringApi>::Block> for Propose
 			offline: self.offline.clone(),
 			authorities,
 			minimum_timestamp: current_timestamp() + self.force_delay,
+			inherent_data_providers: self.inherent_data_providers.clone(),
 		};
 
 		Ok(proposer)
@@ -171,6 +200,7 @@ pub struct Proposer<C: AuthoringApi, A: txpool::ChainApi> {
 	offline: SharedOfflineTracker,
 	authorities: Vec<AuthorityId>,
 	minimum_timestamp: u64,
+	inherent_data_providers: InherentDataProviders,
 }
 
 impl<C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Proposer<C, A> where
@@ -186,7 +216,14 @@ impl<C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Proposer<C
 
 		const MAX_VOTE_OFFLINE_SECONDS: Duration = Duration::from_secs(60);
 
-		let timestamp = ::std::cmp::max(self.minimum_timestamp, current_timestamp());
+		let mut inherent_data = self.inherent_data_providers.create_inherent_data()
+			.map_err(|e| error::Error::from(format!("Failed to gather inherent data: {}", e)))?;
+
+		let provided_timestamp = inherent_data.get_data::<Timestamp>(&well_known_identifiers::TIMESTAMP)
+			.map_err(|e| error::Error::from(format!("Failed to gather inherent data: {}", e)))?
+			.unwrap_or_else(current_timestamp);
+		let timestamp = ::std::cmp::max(self.minimum_timestamp, provided_timestamp);
+		inherent_data.replace_data(well_known_identifiers::TIMESTAMP, &timestamp);
 
 		let elapsed_since_start = self.start.elapsed();
 		let offline_indices = if elapsed_since_start > MAX_VOTE_OFFLINE_SECONDS {
@@ -201,37 +238,13 @@ impl<C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Proposer<C
 			)
 		}
 
-		let inherent_data = InherentData {
-			timestamp,
-			offline_indices,
-		};
+		inherent_data.replace_data(well_known_identifiers::OFFLINE_INDICES, &offline_indices);
 
 		let block = self.client.build_block(
 			&self.parent_id,
 			inherent_data,
-			|block_builder| {
-				let mut unqueue_invalid = Vec::new();
-				let mut pending_size = 0;
-				let pending_iterator = self.transaction_pool.ready();
-
-				for pending in pending_iterator {
-					// TODO [ToDr] Probably get rid of it, and validate in runtime.
-					let encoded_size = pending.data.encode().len();
-					if pending_size + encoded_size >= MAX_TRANSACTIONS_SIZE { break }
-
-					match block_builder.push_extrinsic(pending.data.clone()) {
-						Ok(()) => {
-							pending_size += encoded_size;
-						}
-						Err(e) => {
-							trace!(target: "transaction-pool", "Invalid transaction: {}", e);
-							unqueue_invalid.push(pending.hash.clone());
-						}
-					}
-				}
-
-				self.transaction_pool.remove_invalid(&unqueue_invalid);
-			})?;
+			|block_builder| push_ready_transactions(block_builder, &self.transaction_pool),
+		)?;
 
 		info!("Proposing block [number: {}; hash: {}; parent_hash: {}; extrinsics: [{}]]",
 			  block.header().number(),
@@ -256,8 +269,137 @@ impl<C, A> consensus_common::Proposer<<C as AuthoringApi>::Block> for Proposer<C
 	}
 }
 
+/// Push the ready transactions of the pool onto the block until it is full,
+/// removing the ones that fail to apply from the pool.
+fn push_ready_transactions<Block, A>(
+	block_builder: &mut BlockBuilder<Block>,
+	transaction_pool: &TransactionPool<A>,
+) where
+	Block: BlockT,
+	A: txpool::ChainApi<Block=Block>,
+{
+	let mut unqueue_invalid = Vec::new();
+	let mut pending_size = 0;
+	let pending_iterator = transaction_pool.ready();
+
+	for pending in pending_iterator {
+		// TODO [ToDr] Probably get rid of it, and validate in runtime.
+		let encoded_size = pending.data.encode().len();
+		if pending_size + encoded_size >= MAX_TRANSACTIONS_SIZE { break }
+
+		match block_builder.push_extrinsic(pending.data.clone()) {
+			Ok(()) => {
+				pending_size += encoded_size;
+			}
+			Err(error::Error(error::ErrorKind::ApplyExtinsicFailed(ApplyError::FullBlock), _)) => {
+				trace!(target: "txpool", "Block is full, leaving the remaining transactions in the pool");
+				break;
+			}
+			Err(e) => {
+				trace!(target: "txpool", "Invalid transaction: {}", e);
+				unqueue_invalid.push(pending.hash.clone());
+			}
+		}
+	}
+
+	transaction_pool.remove_invalid(&unqueue_invalid);
+}
+
 fn current_timestamp() -> Timestamp {
 	time::SystemTime::now().duration_since(time::UNIX_EPOCH)
 		.expect("now always later than unix epoch; qed")
 		.as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use keyring::Keyring::{self, Alice};
+	use runtime_primitives::generic;
+	use runtime_primitives::traits::BlakeTwo256;
+	use runtime_primitives::transaction_validity::TransactionValidity;
+	use test_client::runtime::{AccountId, Block, Extrinsic, Hash, Index, Transfer};
+	use transaction_pool::error;
+
+	struct TestApi;
+
+	impl txpool::ChainApi for TestApi {
+		type Block = Block;
+		type Hash = Hash;
+		type Error = error::Error;
+
+		fn validate_transaction(&self, _at: &BlockId<Block>, uxt: &txpool::ExtrinsicFor<Self>) -> error::Result<TransactionValidity> {
+			Ok(TransactionValidity::Valid {
+				priority: 1,
+				requires: vec![],
+				provides: vec![vec![uxt.transfer.nonce as u8]],
+				longevity: 64
+			})
+		}
+
+		fn block_id_to_number(&self, at: &BlockId<Block>) -> error::Result<Option<txpool::NumberFor<Self>>> {
+			Ok(match at {
+				generic::BlockId::Number(n) => Some(*n),
+				_ => Some(0),
+			})
+		}
+
+		fn block_id_to_hash(&self, at: &BlockId<Block>) -> error::Result<Option<txpool::BlockHash<Self>>> {
+			Ok(match at {
+				generic::BlockId::Hash(x) => Some(x.clone()),
+				_ => Some(Default::default()),
+			})
+		}
+
+		fn hash(&self, ex: &txpool::ExtrinsicFor<Self>) -> Hash {
+			BlakeTwo256::hash(&ex.encode())
+		}
+	}
+
+	/// Block builder with room for a fixed number of extrinsics.
+	struct LimitedBlockBuilder {
+		capacity: usize,
+		extrinsics: Vec<Extrinsic>,
+	}
+
+	impl BlockBuilder<Block> for LimitedBlockBuilder {
+		fn push_extrinsic(&mut self, extrinsic: Extrinsic) -> Result<(), client::error::Error> {
+			if self.extrinsics.len() == self.capacity {
+				return Err(client::error::ErrorKind::ApplyExtinsicFailed(ApplyError::FullBlock).into());
+			}
+			self.extrinsics.push(extrinsic);
+			Ok(())
+		}
+	}
+
+	fn uxt(who: Keyring, nonce: Index) -> Extrinsic {
+		let transfer = Transfer {
+			from: who.to_raw_public().into(),
+			to: AccountId::default(),
+			nonce,
+			amount: 1,
+		};
+		let signature = transfer.using_encoded(|e| who.sign(e));
+		Extrinsic {
+			transfer,
+			signature: signature.into(),
+		}
+	}
+
+	#[test]
+	fn should_keep_transactions_not_fitting_the_block_in_the_pool() {
+		let pool = TransactionPool::new(Default::default(), TestApi);
+		for nonce in 0..5 {
+			pool.submit_one(&BlockId::number(0), uxt(Alice, nonce)).unwrap();
+		}
+		let mut block_builder = LimitedBlockBuilder { capacity: 3, extrinsics: Vec::new() };
+
+		push_ready_transactions(&mut block_builder, &pool);
+
+		let included: Vec<_> = block_builder.extrinsics.iter().map(|xt| xt.transfer.nonce).collect();
+		assert_eq!(included, vec![0, 1, 2]);
+		let pending: Vec<_> = pool.ready().map(|tx| tx.data.transfer.nonce).collect();
+		assert_eq!(pending, vec![0, 1, 2, 3, 4]);
+	}
+}
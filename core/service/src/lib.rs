@@ -34,12 +34,18 @@ extern crate substrate_network as network;
 extern crate substrate_executor;
 extern crate substrate_client as client;
 extern crate substrate_client_db as client_db;
+extern crate sr_io as runtime_io;
 extern crate parity_codec as codec;
 extern crate substrate_transaction_pool as transaction_pool;
 extern crate substrate_rpc;
 extern crate substrate_rpc_servers as rpc;
+extern crate substrate_logger;
 extern crate target_info;
 extern crate tokio;
+#[cfg(test)]
+extern crate substrate_test_client as test_client;
+#[cfg(test)]
+extern crate substrate_keyring as keyring;
 
 #[macro_use]
 extern crate substrate_telemetry as tel;
@@ -55,35 +61,40 @@ extern crate serde_derive;
 mod components;
 mod error;
 mod chain_spec;
+mod offchain;
 pub mod config;
 pub mod chain_ops;
 pub mod consensus;
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use std::collections::HashMap;
 #[doc(hidden)]
 pub use std::{ops::Deref, result::Result, sync::Arc};
 use futures::prelude::*;
 use parking_lot::{Mutex, RwLock};
-use keystore::Store as Keystore;
+use keystore::{Store as Keystore, KeyTypeId, key_types};
 use client::BlockchainEvents;
-use runtime_primitives::traits::{Header, As};
+use runtime_primitives::traits::{Block as BlockT, Header, As, NumberFor};
 use runtime_primitives::generic::BlockId;
 use exit_future::Signal;
 #[doc(hidden)]
 pub use tokio::runtime::TaskExecutor;
-use substrate_executor::NativeExecutor;
+use substrate_executor::{NativeExecutor, NativeExecutionDispatch};
 use codec::{Encode, Decode};
 
 pub use self::error::{ErrorKind, Error};
-pub use config::{Configuration, Roles, PruningMode};
-pub use chain_spec::ChainSpec;
+pub use config::{Configuration, Roles, PruningMode, SyncMode, RpcMethods};
+pub use chain_spec::{ChainSpec, Properties};
 pub use transaction_pool::txpool::{self, Pool as TransactionPool, Options as TransactionPoolOptions, ChainApi, IntoPoolError};
 pub use client::ExecutionStrategy;
 
 use consensus_common::offline_tracker::OfflineTracker;
-pub use consensus::ProposerFactory;
+use consensus_common::{Signer, RemoteSigner, InherentDataProviders, TimestampInherentDataProvider, SyncOracle};
+pub use consensus::{ProposerFactory, ConsensusEngine};
 pub use components::{ServiceFactory, FullBackend, FullExecutor, LightBackend,
 	LightExecutor, Components, PoolApi, ComponentClient,
 	ComponentBlock, FullClient, LightClient, FullComponents, LightComponents,
@@ -99,7 +110,10 @@ pub struct Service<Components: components::Components> {
 	client: Arc<ComponentClient<Components>>,
 	network: Option<Arc<components::NetworkService<Components::Factory>>>,
 	transaction_pool: Arc<TransactionPool<Components::TransactionPoolApi>>,
-	keystore: Keystore,
+	keystore: Arc<Keystore>,
+	roles: Roles,
+	remote_signer: Option<SocketAddr>,
+	fallback_slots: Option<u64>,
 	exit: ::exit_future::Exit,
 	signal: Option<Signal>,
 	proposer: Arc<ProposerFactory<ComponentClient<Components>, Components::TransactionPoolApi>>,
@@ -112,7 +126,7 @@ pub struct Service<Components: components::Components> {
 pub fn new_client<Factory: components::ServiceFactory>(config: &FactoryFullConfiguration<Factory>)
 	-> Result<Arc<ComponentClient<components::FullComponents<Factory>>>, error::Error>
 {
-	let executor = NativeExecutor::new();
+	let executor = new_executor(config.fuel_limit);
 	let (client, _) = components::FullComponents::<Factory>::build_client(
 		config,
 		executor,
@@ -120,6 +134,14 @@ pub fn new_client<Factory: components::ServiceFactory>(config: &FactoryFullConfi
 	Ok(client)
 }
 
+/// Creates the executor for the client, metering calls into the Wasm runtime if `fuel_limit` is set.
+fn new_executor<D: NativeExecutionDispatch>(fuel_limit: Option<u64>) -> NativeExecutor<D> {
+	match fuel_limit {
+		Some(fuel_limit) => NativeExecutor::with_fuel_limit(fuel_limit),
+		None => NativeExecutor::new(),
+	}
+}
+
 impl<Components> Service<Components>
 	where
 		Components: components::Components,
@@ -137,24 +159,36 @@ impl<Components> Service<Components>
 		let (signal, exit) = ::exit_future::signal();
 
 		// Create client
-		let executor = NativeExecutor::new();
+		let executor = new_executor(config.fuel_limit);
 
 		let mut keystore = Keystore::open(config.keystore_path.as_str().into())?;
 		for seed in &config.keys {
 			keystore.generate_from_seed(seed)?;
 		}
 
+		let contents = keystore.contents()?;
+		for public_key in &contents {
+			keystore.unlock(public_key, &config.password)?;
+		}
+
 		// Keep the public key for telemetry
-		let public_key = match keystore.contents()?.get(0) {
+		let public_key = match contents.get(0) {
 			Some(public_key) => public_key.clone(),
 			None => {
-				let key = keystore.generate("")?;
+				let key = keystore.generate(&config.password)?;
+				keystore.unlock(&key.public(), &config.password)?;
 				let public_key = key.public();
 				info!("Generated a new keypair: {:?}", public_key);
 
 				public_key
 			}
 		};
+		for key_type in keystore::key_types::SESSION {
+			for public in keystore.contents_by_type(*key_type)? {
+				keystore.unlock_by_type(*key_type, &public, &config.password)?;
+			}
+		}
+		let keystore = Arc::new(keystore);
 
 		let (client, on_demand) = Components::build_client(&config, executor)?;
 		let import_queue = Components::build_import_queue(&config, client.clone())?;
@@ -162,7 +196,7 @@ impl<Components> Service<Components>
 
 		let version = config.full_version();
 		info!("Best block: #{}", best_header.number());
-		telemetry!("node.start"; "height" => best_header.number().as_(), "best" => ?best_header.hash());
+		telemetry!(tel::SUBSTRATE_INFO; "node.start"; "height" => best_header.number().as_(), "best" => ?best_header.hash());
 
 		let network_protocol = <Components::Factory>::build_network_protocol(&config)?;
 		let transaction_pool = Arc::new(
@@ -174,9 +208,13 @@ impl<Components> Service<Components>
 			client: client.clone(),
 		 };
 
+		// nodes that are given no boot or reserved nodes, e.g. on a development chain, may well run alone.
+		let should_have_peers = !config.network.boot_nodes.is_empty() || !config.network.reserved_nodes.is_empty();
+
 		let network_params = network::Params {
 			config: network::ProtocolConfig {
 				roles: config.roles,
+				sync_mode: config.sync_mode,
 			},
 			network_config: config.network,
 			chain: client.clone(),
@@ -201,14 +239,100 @@ impl<Components> Service<Components>
 			// block notifications
 			let network = Arc::downgrade(&network);
 			let txpool = transaction_pool.clone();
+			let events_client = client.clone();
+			let mut last_best = best_header.hash();
 
 			let events = client.import_notification_stream()
 				.for_each(move |notification| {
 					if let Some(network) = network.upgrade() {
-						network.on_block_imported(notification.hash, &notification.header);
+						network.on_block_imported(notification.hash, &notification.header, notification.is_new_best);
 					}
-					txpool.prune_tags(&BlockId::hash(notification.hash), notification.tags)
+					let at = BlockId::hash(notification.hash);
+					txpool.prune_tags(&at, notification.tags)
 						.map_err(|e| warn!("Error removing extrinsics: {:?}", e))?;
+
+					if notification.is_new_best {
+						// return extrinsics of the blocks retracted by a reorganisation to the pool.
+						if *notification.header.parent_hash() != last_best {
+							match events_client.tree_route(&BlockId::hash(last_best), &at) {
+								Ok(route) => {
+									for entry in route.retracted() {
+										txpool.on_block_retracted(entry.hash, entry.number);
+									}
+									let retracted = route.retracted().iter()
+										.filter_map(|entry| events_client.body(&BlockId::hash(entry.hash)).ok().and_then(|body| body))
+										.flat_map(|body| body);
+									if let Err(e) = txpool.submit_at(&at, retracted) {
+										warn!("Error re-submitting retracted extrinsics: {:?}", e);
+									}
+								},
+								Err(e) => warn!("Error computing tree route: {:?}", e),
+							}
+						}
+						last_best = notification.hash;
+					}
+					Ok(())
+				})
+				.select(exit.clone())
+				.then(|_| Ok(()));
+			task_executor.spawn(events);
+		}
+
+		if config.offchain_worker && config.roles.intersects(Roles::FULL | Roles::AUTHORITY) {
+			// offchain workers run on their own thread, so that their blocking HTTP requests never
+			// hold up the executor. at most one runs at a time: new best blocks are skipped meanwhile,
+			// as are those imported during a major sync.
+			let offchain_client = client.clone();
+			let txpool = transaction_pool.clone();
+			let sync_oracle = Arc::downgrade(&network);
+			let running = Arc::new(AtomicBool::new(false));
+
+			let events = client.import_notification_stream()
+				.filter(|notification| notification.is_new_best)
+				.for_each(move |notification| {
+					let number = *notification.header.number();
+					if sync_oracle.upgrade().map_or(true, |network| network.is_major_syncing()) {
+						return Ok(());
+					}
+					if running.swap(true, Ordering::SeqCst) {
+						trace!(target: "offchain", "Skipping offchain worker at block #{}, another one is running", number);
+						return Ok(());
+					}
+
+					let client = offchain_client.clone();
+					let txpool = txpool.clone();
+					let worker_running = running.clone();
+					let at = BlockId::hash(notification.hash);
+					let spawned = thread::Builder::new()
+						.name("offchain-worker".into())
+						.spawn(move || {
+							offchain::run::<Components>(&client, &txpool, at, number);
+							worker_running.store(false, Ordering::SeqCst);
+						});
+					if let Err(e) = spawned {
+						warn!("Unable to spawn offchain worker thread: {:?}", e);
+						running.store(false, Ordering::SeqCst);
+					}
+					Ok(())
+				})
+				.select(exit.clone())
+				.then(|_| Ok(()));
+			task_executor.spawn(events);
+		}
+
+		{
+			// finality notifications
+			let network = Arc::downgrade(&network);
+			let txpool = Arc::downgrade(&transaction_pool);
+
+			let events = client.finality_notification_stream()
+				.for_each(move |notification| {
+					if let Some(network) = network.upgrade() {
+						network.on_block_finalized(&notification.header);
+					}
+					if let Some(txpool) = txpool.upgrade() {
+						txpool.on_block_finalized(notification.hash, *notification.header.number());
+					}
 					Ok(())
 				})
 				.select(exit.clone())
@@ -234,19 +358,38 @@ impl<Components> Service<Components>
 		}
 
 		// RPC
+		let rpc_addresses: Vec<_> = config.rpc_http.iter().chain(config.rpc_ws.iter()).cloned().collect();
+		let allow_unsafe = config.rpc_methods.allow_unsafe(&rpc_addresses);
+		if config.rpc_methods == RpcMethods::Unsafe && rpc_addresses.iter().any(|a| !a.ip().is_loopback()) {
+			warn!("Exposing unsafe RPC methods on a public interface. Keys may be inserted by anyone able to reach the node.");
+		}
+
 		let rpc_config = RpcConfig {
 			chain_name: config.chain_spec.name().to_string(),
+			properties: config.chain_spec.properties(),
+			should_have_peers,
 			impl_name: config.impl_name,
 			impl_version: config.impl_version,
+			network: Arc::downgrade(&(network.clone() as Arc<RpcNetwork<ComponentBlock<Components>>>)),
+			allow_unsafe,
 		};
 
 		let (rpc_http, rpc_ws) = {
+
 			let handler = || {
 				let client = client.clone();
 				let subscriptions = rpc::apis::Subscriptions::new(task_executor.clone());
 				let chain = rpc::apis::chain::Chain::new(client.clone(), subscriptions.clone());
 				let state = rpc::apis::state::State::new(client.clone(), subscriptions.clone());
-				let author = rpc::apis::author::Author::new(client.clone(), transaction_pool.clone(), subscriptions.clone());
+				let keys = if allow_unsafe {
+					Some(rpc::apis::author::KeyManagement {
+						keystore: keystore.clone(),
+						password: config.password.clone(),
+					})
+				} else {
+					None
+				};
+				let author = rpc::apis::author::Author::new(client.clone(), transaction_pool.clone(), subscriptions.clone(), keys);
 				rpc::rpc_handler::<ComponentBlock<Components>, ComponentExHash<Components>, _, _, _, _, _>(
 					state,
 					chain,
@@ -255,43 +398,47 @@ impl<Components> Service<Components>
 				)
 			};
 			(
-				maybe_start_server(config.rpc_http, |address| rpc::start_http(address, handler()))?,
-				maybe_start_server(config.rpc_ws, |address| rpc::start_ws(address, handler()))?,
+				maybe_start_server(config.rpc_http, |address| rpc::start_http(address, config.rpc_cors.as_ref(), handler()))?,
+				maybe_start_server(config.rpc_ws, |address| rpc::start_ws(address, config.rpc_cors.as_ref(), handler()))?,
 			)
 		};
 
+		let inherent_data_providers = InherentDataProviders::new();
+		inherent_data_providers.register_provider(TimestampInherentDataProvider::new(0))
+			.map_err(|e| e.to_string())?;
+
 		let proposer = Arc::new(ProposerFactory {
 			client: client.clone(),
 			transaction_pool: transaction_pool.clone(),
 			offline: Arc::new(RwLock::new(OfflineTracker::new())),
-			force_delay: 0 // FIXME: allow this to be configured
+			force_delay: 0, // FIXME: allow this to be configured
+			inherent_data_providers,
 		});
 
 		// Telemetry
-		let telemetry = match config.telemetry_url {
-			Some(url) => {
-				let is_authority = config.roles == Roles::AUTHORITY;
-				let pubkey = format!("{}", public_key);
-				let name = config.name.clone();
-				let impl_name = config.impl_name.to_owned();
-				let version = version.clone();
-				let chain_name = config.chain_spec.name().to_owned();
-				Some(tel::init_telemetry(tel::TelemetryConfig {
-					url: url,
-					on_connect: Box::new(move || {
-						telemetry!("system.connected";
-							"name" => name.clone(),
-							"implementation" => impl_name.clone(),
-							"version" => version.clone(),
-							"config" => "",
-							"chain" => chain_name.clone(),
-							"pubkey" => &pubkey,
-							"authority" => is_authority
-						);
-					}),
-				}))
-			},
-			None => None,
+		let telemetry = if config.telemetry_endpoints.is_empty() {
+			None
+		} else {
+			let is_authority = config.roles == Roles::AUTHORITY;
+			let pubkey = format!("{}", public_key);
+			let name = config.name.clone();
+			let impl_name = config.impl_name.to_owned();
+			let version = version.clone();
+			let chain_name = config.chain_spec.name().to_owned();
+			Some(tel::init_telemetry(tel::TelemetryConfig {
+				endpoints: config.telemetry_endpoints.clone(),
+				on_connect: Box::new(move || {
+					telemetry!(tel::SUBSTRATE_INFO; "system.connected";
+						"name" => name.clone(),
+						"implementation" => impl_name.clone(),
+						"version" => version.clone(),
+						"config" => "",
+						"chain" => chain_name.clone(),
+						"pubkey" => &pubkey,
+						"authority" => is_authority
+					);
+				}),
+			}))
 		};
 
 		Ok(Service {
@@ -300,6 +447,9 @@ impl<Components> Service<Components>
 			transaction_pool: transaction_pool,
 			signal: Some(signal),
 			keystore: keystore,
+			roles: config.roles,
+			remote_signer: config.remote_signer,
+			fallback_slots: config.fallback_slots,
 			proposer,
 			exit,
 			_rpc_http: rpc_http,
@@ -324,6 +474,12 @@ impl<Components> Service<Components> where
 		self.proposer.clone()
 	}
 
+	/// Get the providers of the inherent data put into authored blocks. Register further
+	/// providers here before starting consensus.
+	pub fn inherent_data_providers(&self) -> InherentDataProviders {
+		self.proposer.inherent_data_providers.clone()
+	}
+
 	/// Get shared network instance.
 	pub fn network(&self) -> Arc<components::NetworkService<Components::Factory>> {
 		self.network.as_ref().expect("self.network always Some").clone()
@@ -335,14 +491,96 @@ impl<Components> Service<Components> where
 	}
 
 	/// Get shared keystore.
-	pub fn keystore(&self) -> &Keystore {
-		&self.keystore
+	pub fn keystore(&self) -> Arc<Keystore> {
+		self.keystore.clone()
 	}
 
 	/// Get a handle to a future that will resolve on exit.
 	pub fn on_exit(&self) -> ::exit_future::Exit {
 		self.exit.clone()
 	}
+
+	/// Get the number of slots without finality after which blocks are authored on their slot
+	/// alone, if a finality gadget runs alongside slot authoring.
+	pub fn fallback_slots(&self) -> Option<u64> {
+		self.fallback_slots
+	}
+
+	/// Get the active key of the given type, the last one rotated or inserted. Falls back to the
+	/// first unlocked key of the type, then to the first unlocked untyped key for keystores that
+	/// predate typed keys.
+	pub fn local_key(&self, key_type: KeyTypeId) -> Result<Option<primitives::ed25519::Pair>, error::Error> {
+		let active = self.keystore.active_key(key_type)?;
+		let typed = self.keystore.contents_by_type(key_type)?;
+		let untyped = self.keystore.contents()?;
+		Ok(active.iter().chain(typed.iter()).chain(untyped.iter()).filter_map(|public| self.keystore.unlocked(public)).next())
+	}
+
+	/// Get the key to vote on finality with, to be passed as the GRANDPA `local_key`.
+	///
+	/// `None` unless this node is an authority with a finality key available.
+	pub fn finality_key(&self) -> Result<Option<Arc<Signer>>, error::Error> {
+		if self.roles != Roles::AUTHORITY {
+			return Ok(None);
+		}
+
+		Ok(self.local_key(key_types::FINALITY)?.map(|key| Arc::new(key) as Arc<Signer>))
+	}
+
+	/// Start authoring blocks with the consensus engine `E`, signing with the remote signer if
+	/// one is configured and with the first authoring key of the keystore otherwise.
+	///
+	/// Does nothing unless this node is an authority with a key available. The engine is fed
+	/// block import and finality notifications until the service exits.
+	pub fn start_consensus<E>(&self, executor: &TaskExecutor) -> Result<Option<Arc<E>>, error::Error> where
+		E: ConsensusEngine<Components>,
+	{
+		if self.roles != Roles::AUTHORITY {
+			return Ok(None);
+		}
+
+		let signer: Arc<Signer> = match self.remote_signer {
+			Some(address) => Arc::new(RemoteSigner::connect(address)
+				.map_err(|e| format!("Unable to connect to remote signer at {}: {}", address, e))?),
+			None => match self.local_key(key_types::AUTHORING)? {
+				Some(key) => Arc::new(key),
+				None => return Ok(None),
+			},
+		};
+
+		info!("Using authority key {}", signer.public());
+		let engine = Arc::new(E::new(self, signer)?);
+
+		executor.spawn(engine.propose()
+			.select(self.exit.clone())
+			.then(|_| Ok(())));
+
+		{
+			let engine = engine.clone();
+			let events = self.client.import_notification_stream()
+				.for_each(move |notification| {
+					engine.on_block_imported(&notification);
+					Ok(())
+				})
+				.select(self.exit.clone())
+				.then(|_| Ok(()));
+			executor.spawn(events);
+		}
+
+		{
+			let engine = engine.clone();
+			let events = self.client.finality_notification_stream()
+				.for_each(move |notification| {
+					engine.on_block_finalized(&notification);
+					Ok(())
+				})
+				.select(self.exit.clone())
+				.then(|_| Ok(()));
+			executor.spawn(events);
+		}
+
+		Ok(Some(engine))
+	}
 }
 
 
@@ -376,14 +614,36 @@ fn maybe_start_server<T, F>(address: Option<SocketAddr>, start: F) -> Result<Opt
 	})
 }
 
+/// Network operations used by the system RPCs.
+trait RpcNetwork<B: BlockT>: network::SyncProvider<B> + network::ManageNetwork {}
+impl<B: BlockT, T: network::SyncProvider<B> + network::ManageNetwork> RpcNetwork<B> for T {}
+
 #[derive(Clone)]
-struct RpcConfig {
+struct RpcConfig<B: BlockT> {
 	chain_name: String,
+	properties: Properties,
+	should_have_peers: bool,
 	impl_name: &'static str,
 	impl_version: &'static str,
+	network: Weak<RpcNetwork<B>>,
+	allow_unsafe: bool,
 }
 
-impl substrate_rpc::system::SystemApi for RpcConfig {
+impl<B: BlockT> RpcConfig<B> {
+	fn network(&self) -> substrate_rpc::system::error::Result<Arc<RpcNetwork<B>>> {
+		self.network.upgrade().ok_or_else(|| "Network is shutting down".into())
+	}
+
+	fn ensure_unsafe_allowed(&self) -> substrate_rpc::system::error::Result<()> {
+		if self.allow_unsafe {
+			Ok(())
+		} else {
+			Err(substrate_rpc::policy::UnsafeRpcError.into())
+		}
+	}
+}
+
+impl<B: BlockT> substrate_rpc::system::SystemApi<B::Hash, NumberFor<B>> for RpcConfig<B> {
 	fn system_name(&self) -> substrate_rpc::system::error::Result<String> {
 		Ok(self.impl_name.into())
 	}
@@ -395,6 +655,60 @@ impl substrate_rpc::system::SystemApi for RpcConfig {
 	fn system_chain(&self) -> substrate_rpc::system::error::Result<String> {
 		Ok(self.chain_name.clone())
 	}
+
+	fn system_properties(&self) -> substrate_rpc::system::error::Result<Properties> {
+		Ok(self.properties.clone())
+	}
+
+	fn system_health(&self) -> substrate_rpc::system::error::Result<substrate_rpc::system::Health> {
+		let status = self.network()?.status();
+		Ok(substrate_rpc::system::Health {
+			peers: status.num_peers,
+			is_syncing: status.sync.is_major_syncing(),
+			should_have_peers: self.should_have_peers,
+		})
+	}
+
+	fn system_peers(&self) -> substrate_rpc::system::error::Result<Vec<substrate_rpc::system::PeerInfo<B::Hash, NumberFor<B>>>> {
+		Ok(self.network()?.peers().into_iter().map(|(_, peer_id, info)| substrate_rpc::system::PeerInfo {
+			peer_id: peer_id.map(|id| id.to_base58()).unwrap_or_default(),
+			roles: format!("{:?}", info.roles),
+			protocol_version: info.protocol_version,
+			best_hash: info.best_hash,
+			best_number: info.best_number,
+		}).collect())
+	}
+
+	fn system_network_state(&self) -> substrate_rpc::system::error::Result<substrate_rpc::system::NetworkState<B::Hash, NumberFor<B>>> {
+		self.ensure_unsafe_allowed()?;
+		let network = self.network()?;
+		let status = network.status();
+		Ok(substrate_rpc::system::NetworkState {
+			peer_id: network.local_peer_id(),
+			listen_addresses: network.listen_addresses(),
+			peers: self.system_peers()?,
+			best_number: status.sync.best_queued_number,
+			best_seen_number: status.sync.best_seen_block,
+		})
+	}
+
+	fn system_add_reserved_peer(&self, peer: String) -> substrate_rpc::system::error::Result<()> {
+		self.ensure_unsafe_allowed()?;
+		self.network()?.add_reserved_peer(peer)
+			.map_err(|e| substrate_rpc::system::error::ErrorKind::InvalidPeer(e).into())
+	}
+
+	fn system_remove_reserved_peer(&self, peer: String) -> substrate_rpc::system::error::Result<()> {
+		self.ensure_unsafe_allowed()?;
+		self.network()?.remove_reserved_peer(peer)
+			.map_err(|e| substrate_rpc::system::error::ErrorKind::InvalidPeer(e).into())
+	}
+
+	fn system_set_log_level(&self, directives: String) -> substrate_rpc::system::error::Result<()> {
+		self.ensure_unsafe_allowed()?;
+		substrate_logger::set_log_level(&directives)
+			.map_err(|e| substrate_rpc::system::error::ErrorKind::InvalidLogLevel(e).into())
+	}
 }
 
 /// Transaction pool adapter.
@@ -409,7 +723,7 @@ impl<C: Components> TransactionPoolAdapter<C> {
 		self.client.info()
 			.map(|info| BlockId::hash(info.chain.best_hash))
 			.map_err(|e| {
-				debug!("Error getting best block: {:?}", e);
+				debug!(target: "txpool", "Error getting best block: {:?}", e);
 			})
 			.ok()
 	}
@@ -428,7 +742,7 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 
 	fn import(&self, transaction: &ComponentExtrinsic<C>) -> Option<ComponentExHash<C>> {
 		if !self.imports_external_transactions {
-			debug!("Transaction rejected");
+			debug!(target: "txpool", "Transaction rejected");
 			return None;
 		}
 
@@ -442,18 +756,18 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 					Ok(e) => match e.kind() {
 						txpool::error::ErrorKind::AlreadyImported => Some(hash),
 						_ => {
-							debug!("Error adding transaction to the pool: {:?}", e);
+							debug!(target: "txpool", "Error adding transaction to the pool: {:?}", e);
 							None
 						},
 					},
 					Err(e) => {
-						debug!("Error converting pool error: {:?}", e);
+						debug!(target: "txpool", "Error converting pool error: {:?}", e);
 						None
 					},
 				}
 			}
 		} else {
-			debug!("Error decoding transaction");
+			debug!(target: "txpool", "Error decoding transaction");
 			None
 		}
 	}
@@ -0,0 +1,219 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offchain workers: runtime code executed on top of every new best block, with access to
+//! the transaction pool, a node-local storage and HTTP to public hosts.
+
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use client::backend::Backend;
+use client::runtime_api::{OffchainWorker, id::OFFCHAIN_WORKER};
+use codec::Decode;
+use runtime_io::{self, OffchainExt};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::NumberFor;
+use transaction_pool::txpool::Pool as TransactionPool;
+
+use components::{Components, ComponentBlock, ComponentClient, ComponentExtrinsic};
+
+/// Prefix of the offchain worker keys in the auxiliary database.
+const LOCAL_STORAGE_PREFIX: &[u8] = b"offchain:";
+/// Connect, read and write timeout of HTTP requests.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum size of an HTTP response, headers included.
+const MAX_HTTP_RESPONSE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Run the offchain worker of the runtime at block `at`, if the runtime has one.
+pub fn run<C: Components>(
+	client: &Arc<ComponentClient<C>>,
+	pool: &Arc<TransactionPool<C::TransactionPoolApi>>,
+	at: BlockId<ComponentBlock<C>>,
+	number: NumberFor<ComponentBlock<C>>,
+) {
+	match client.runtime_version_at(&at) {
+		Ok(ref version) if version.has_api(OFFCHAIN_WORKER, 1) => {},
+		Ok(_) => return,
+		Err(e) => {
+			warn!("Error reading runtime version at block #{}: {:?}", number, e);
+			return;
+		}
+	}
+
+	let mut ext = Ext::<C> { client: client.clone(), pool: pool.clone(), at: at.clone() };
+	let result = runtime_io::with_offchain(&mut ext, || client.offchain_worker(&at, &number));
+	if let Err(e) = result {
+		warn!("Offchain worker failed at block #{}: {:?}", number, e);
+	}
+}
+
+/// The host side of the offchain worker functions of the runtime.
+struct Ext<C: Components> {
+	client: Arc<ComponentClient<C>>,
+	pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+	at: BlockId<ComponentBlock<C>>,
+}
+
+impl<C: Components> Ext<C> {
+	fn local_storage_key(key: &[u8]) -> Vec<u8> {
+		let mut k = LOCAL_STORAGE_PREFIX.to_vec();
+		k.extend_from_slice(key);
+		k
+	}
+}
+
+impl<C: Components> OffchainExt for Ext<C> {
+	fn submit_extrinsic(&mut self, extrinsic: Vec<u8>) -> Result<(), ()> {
+		let xt: ComponentExtrinsic<C> = match Decode::decode(&mut &extrinsic[..]) {
+			Some(xt) => xt,
+			None => {
+				warn!("Offchain worker submitted an undecodable extrinsic");
+				return Err(());
+			}
+		};
+		self.pool.submit_one(&self.at, xt)
+			.map(|_| ())
+			.map_err(|e| warn!("Error submitting offchain worker extrinsic: {:?}", e))
+	}
+
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.client.backend().get_aux(&Self::local_storage_key(key))
+			.unwrap_or_else(|e| {
+				warn!("Error reading offchain worker storage: {:?}", e);
+				None
+			})
+	}
+
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		let key = Self::local_storage_key(key);
+		if let Err(e) = self.client.backend().insert_aux(&[(&key[..], value)], &[]) {
+			warn!("Error writing offchain worker storage: {:?}", e);
+		}
+	}
+
+	fn http_request(&mut self, method: &str, uri: &str, body: &[u8]) -> Option<Vec<u8>> {
+		http_request(method, uri, body)
+			.map_err(|e| debug!(target: "offchain", "HTTP {} {} failed: {}", method, uri, e))
+			.ok()
+	}
+}
+
+/// Split an `http://host[:port][/path]` URI into host, port and path.
+fn parse_uri(uri: &str) -> Result<(&str, u16, &str), String> {
+	let rest = if uri.starts_with("http://") {
+		&uri["http://".len()..]
+	} else {
+		return Err("only http:// URIs are supported".into());
+	};
+	let (authority, path) = match rest.find('/') {
+		Some(i) => (&rest[..i], &rest[i..]),
+		None => (rest, "/"),
+	};
+	let (host, port) = match authority.rfind(':') {
+		Some(i) => (&authority[..i], authority[i + 1..].parse().map_err(|_| "invalid port")?),
+		None => (authority, 80),
+	};
+	if host.is_empty() {
+		return Err("missing host".into());
+	}
+	Ok((host, port, path))
+}
+
+/// Whether the address is reachable on the internet: loopback, private, link-local and
+/// unspecified addresses are not.
+fn is_public(ip: &IpAddr) -> bool {
+	match *ip {
+		IpAddr::V4(ip) => !(ip.is_loopback() || ip.is_private() || ip.is_link_local()
+			|| ip.is_broadcast() || ip.is_unspecified() || ip.octets()[0] == 0),
+		IpAddr::V6(ip) => {
+			if ip.is_loopback() || ip.is_unspecified() {
+				return false;
+			}
+			if let Some(ip) = ip.to_ipv4() {
+				return is_public(&IpAddr::V4(ip));
+			}
+			let first = ip.segments()[0];
+			// unique local fc00::/7 and link-local fe80::/10.
+			first & 0xfe00 != 0xfc00 && first & 0xffc0 != 0xfe80
+		},
+	}
+}
+
+/// Perform a blocking HTTP/1.0 request and return the body of a 2xx response.
+fn http_request(method: &str, uri: &str, body: &[u8]) -> Result<Vec<u8>, String> {
+	use std::net::ToSocketAddrs;
+
+	let (host, port, path) = parse_uri(uri)?;
+	let addr = (host, port).to_socket_addrs().map_err(|e| e.to_string())?
+		.next()
+		.ok_or("host did not resolve")?;
+	// the node's own services, such as unsafe RPCs exposed on loopback, are out of reach.
+	if !is_public(&addr.ip()) {
+		return Err(format!("{} is not a public address", addr.ip()));
+	}
+
+	let mut stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT).map_err(|e| e.to_string())?;
+	stream.set_read_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+	stream.set_write_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+
+	write!(
+		stream,
+		"{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+		method, path, host, body.len(),
+	).map_err(|e| e.to_string())?;
+	stream.write_all(body).map_err(|e| e.to_string())?;
+
+	let mut response = Vec::new();
+	stream.take(MAX_HTTP_RESPONSE_SIZE).read_to_end(&mut response).map_err(|e| e.to_string())?;
+
+	let header_end = response.windows(4).position(|w| w == b"\r\n\r\n")
+		.ok_or("malformed response")?;
+	let status = ::std::str::from_utf8(&response[..header_end]).ok()
+		.and_then(|head| head.split_whitespace().nth(1))
+		.and_then(|code| code.parse::<u16>().ok())
+		.ok_or("malformed status line")?;
+	if status < 200 || status >= 300 {
+		return Err(format!("status {}", status));
+	}
+
+	Ok(response.split_off(header_end + 4))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_uri, is_public};
+
+	#[test]
+	fn parses_http_uris() {
+		assert_eq!(parse_uri("http://example.com"), Ok(("example.com", 80, "/")));
+		assert_eq!(parse_uri("http://127.0.0.1:8080/price?x=1"), Ok(("127.0.0.1", 8080, "/price?x=1")));
+		assert!(parse_uri("https://example.com/").is_err());
+		assert!(parse_uri("http://example.com:port/").is_err());
+		assert!(parse_uri("http:///path").is_err());
+	}
+
+	#[test]
+	fn only_public_addresses_are_reachable() {
+		for ip in &["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.0.1", "0.0.0.0", "::1", "::", "::ffff:127.0.0.1", "fd00::1", "fe80::1"] {
+			assert!(!is_public(&ip.parse().unwrap()), "{} is not public", ip);
+		}
+		for ip in &["8.8.8.8", "2001:4860:4860::8888"] {
+			assert!(is_public(&ip.parse().unwrap()), "{} is public", ip);
+		}
+	}
+}
@@ -45,6 +45,7 @@ use service::{
 	FactoryFullConfiguration,
 	FactoryChainSpec,
 	Roles,
+	SyncMode,
 	FactoryExtrinsic,
 };
 use network::{NetworkConfiguration, NonReservedPeerMode, Protocol, SyncProvider, ManageNetwork};
@@ -121,17 +122,25 @@ fn node_config<F: ServiceFactory> (
 		transaction_pool: Default::default(),
 		network: network_config,
 		keystore_path: root.join("key").to_str().unwrap().into(),
+		password: Default::default(),
+		remote_signer: None,
 		database_path: root.join("db").to_str().unwrap().into(),
 		pruning: Default::default(),
+		sync_mode: SyncMode::Full,
+		fallback_slots: None,
 		keys: keys,
 		chain_spec: (*spec).clone(),
 		custom: Default::default(),
 		name: format!("Node {}", index),
 		block_execution_strategy: ExecutionStrategy::NativeWhenPossible,
 		api_execution_strategy: ExecutionStrategy::NativeWhenPossible,
+		fuel_limit: None,
+		offchain_worker: false,
 		rpc_http: None,
 		rpc_ws: None,
-		telemetry_url: None,
+		rpc_methods: Default::default(),
+		rpc_cors: None,
+		telemetry_endpoints: Vec::new(),
 	}
 }
 
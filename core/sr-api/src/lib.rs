@@ -28,6 +28,7 @@ extern crate sr_version as runtime_version;
 
 #[doc(hidden)]
 pub use primitives::{traits::Block as BlockT, generic::BlockId, transaction_validity::TransactionValidity, ApplyResult};
+use primitives::traits::NumberFor;
 use runtime_version::{ApiId, RuntimeVersion};
 use rstd::vec::Vec;
 #[doc(hidden)]
@@ -441,6 +442,9 @@ pub mod id {
 
 	/// ApiId for the Metadata trait.
 	pub const METADATA: ApiId = *b"metadata";
+
+	/// ApiId for the OffchainWorker trait.
+	pub const OFFCHAIN_WORKER: ApiId = *b"offchwrk";
 }
 
 decl_apis! {
@@ -467,6 +471,13 @@ decl_apis! {
 		fn validate_transaction<TransactionValidity>(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity;
 	}
 
+	/// The `OffchainWorker` api trait that is called after a new best block got imported. The
+	/// runtime may use the offchain host functions of `sr-io`, e.g. to submit extrinsics to the
+	/// transaction pool; changes made to the state are discarded.
+	pub trait OffchainWorker<Block: BlockT> {
+		fn offchain_worker(number: NumberFor<Block>);
+	}
+
 	/// The `BlockBuilder` api trait that provides required functions for building a block for a runtime.
 	pub trait BlockBuilder<Block: BlockT> ExtraClientSide <OverlayedChanges> {
 		/// Initialise a block with the given header.
@@ -41,12 +41,43 @@ use hash_db::Hasher;
 
 environmental!(ext: trait Externalities<Blake2Hasher>);
 
+/// Something that can read the runtime version of a piece of wasm code.
+pub trait ReadRuntimeVersion {
+	/// Return the encoded `RuntimeVersion` of `code`, or `None` if `code` isn't a valid runtime.
+	fn read_runtime_version(&self, code: &[u8]) -> Option<Vec<u8>>;
+}
+
+environmental!(runtime_version_reader: trait ReadRuntimeVersion);
+
+/// The host functionality available to the runtime while it runs its offchain workers.
+pub trait OffchainExt {
+	/// Submit an encoded extrinsic to the transaction pool.
+	fn submit_extrinsic(&mut self, extrinsic: Vec<u8>) -> Result<(), ()>;
+
+	/// Get `key` from the local storage of the node, kept across offchain worker runs.
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Set `key` to `value` in the local storage of the node.
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]);
+
+	/// Perform an HTTP request and return the body of a successful response.
+	fn http_request(&mut self, method: &str, uri: &str, body: &[u8]) -> Option<Vec<u8>>;
+}
+
+environmental!(offchain: trait OffchainExt);
+
 /// Get `key` from storage and return a `Vec`, empty if there's a problem.
 pub fn storage(key: &[u8]) -> Option<Vec<u8>> {
 	ext::with(|ext| ext.storage(key).map(|s| s.to_vec()))
 		.expect("storage cannot be called outside of an Externalities-provided environment.")
 }
 
+/// Get values of all `keys` from storage, in the order the keys are given.
+pub fn storage_batch(keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+	ext::with(|ext| ext.storage_batch(keys))
+		.expect("storage_batch cannot be called outside of an Externalities-provided environment.")
+}
+
 /// Get `key` from child storage and return a `Vec`, empty if there's a problem.
 pub fn child_storage(storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
 	ext::with(|ext| ext.child_storage(storage_key, key).map(|s| s.to_vec()))
@@ -163,6 +194,43 @@ pub fn storage_changes_root(block: u64) -> Option<H256> {
 	).unwrap_or(None)
 }
 
+/// The encoded `RuntimeVersion` of the given wasm code, or `None` if it isn't a valid runtime or
+/// there is no reader available.
+pub fn runtime_version(code: &[u8]) -> Option<Vec<u8>> {
+	runtime_version_reader::with(|reader|
+		reader.read_runtime_version(code)
+	).unwrap_or(None)
+}
+
+/// Submit an encoded extrinsic to the transaction pool. Fails outside of offchain workers.
+pub fn submit_extrinsic(extrinsic: &[u8]) -> Result<(), ()> {
+	offchain::with(|ext|
+		ext.submit_extrinsic(extrinsic.to_vec())
+	).unwrap_or(Err(()))
+}
+
+/// Get `key` from the local storage of the node, `None` outside of offchain workers.
+pub fn local_storage_get(key: &[u8]) -> Option<Vec<u8>> {
+	offchain::with(|ext|
+		ext.local_storage_get(key)
+	).unwrap_or(None)
+}
+
+/// Set `key` to `value` in the local storage of the node. Does nothing outside of offchain workers.
+pub fn local_storage_set(key: &[u8], value: &[u8]) {
+	offchain::with(|ext|
+		ext.local_storage_set(key, value)
+	);
+}
+
+/// Perform an HTTP request and return the body of a successful response, `None` if the
+/// request failed or outside of offchain workers.
+pub fn http_request(method: &str, uri: &str, body: &[u8]) -> Option<Vec<u8>> {
+	offchain::with(|ext|
+		ext.http_request(method, uri, body)
+	).unwrap_or(None)
+}
+
 /// A trie root formed from the enumerated items.
 pub fn enumerated_trie_root<H>(input: &[&[u8]]) -> H::Out
 where
@@ -207,6 +275,18 @@ pub fn with_externalities<R, F: FnOnce() -> R>(ext: &mut Externalities<Blake2Has
 	ext::using(ext, f)
 }
 
+/// Execute the given closure with `runtime_version` routing into `reader`. Forwards the value that
+/// the closure returns.
+pub fn with_runtime_version_reader<R, F: FnOnce() -> R>(reader: &mut ReadRuntimeVersion, f: F) -> R {
+	runtime_version_reader::using(reader, f)
+}
+
+/// Execute the given closure with the offchain functions routing into `ext`. Forwards the value
+/// that the closure returns.
+pub fn with_offchain<R, F: FnOnce() -> R>(ext: &mut OffchainExt, f: F) -> R {
+	offchain::using(ext, f)
+}
+
 /// Trait for things which can be printed.
 pub trait Printable {
 	fn print(self);
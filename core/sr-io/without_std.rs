@@ -68,6 +68,7 @@ extern "C" {
 	fn ext_kill_child_storage(storage_key_data: *const u8, storage_key_len: u32);
 	fn ext_get_allocated_storage(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
 	fn ext_get_allocated_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
+	fn ext_storage_batch_get(keys_data: *const u8, keys_len: u32, written_out: *mut u32) -> *mut u8;
 	fn ext_get_storage_into(key_data: *const u8, key_len: u32, value_data: *mut u8, value_len: u32, value_offset: u32) -> u32;
 	fn ext_get_child_storage_into(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, value_data: *mut u8, value_len: u32, value_offset: u32) -> u32;
 	fn ext_storage_root(result: *mut u8);
@@ -75,6 +76,11 @@ extern "C" {
 	fn ext_storage_changes_root(block: u64, result: *mut u8) -> u32;
 	fn ext_blake2_256_enumerated_trie_root(values_data: *const u8, lens_data: *const u32, lens_len: u32, result: *mut u8);
 	fn ext_chain_id() -> u64;
+	fn ext_runtime_version(code_data: *const u8, code_len: u32, written_out: *mut u32) -> *mut u8;
+	fn ext_submit_extrinsic(data: *const u8, len: u32) -> u32;
+	fn ext_local_storage_get(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
+	fn ext_local_storage_set(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
+	fn ext_http_request(method_data: *const u8, method_len: u32, uri_data: *const u8, uri_len: u32, body_data: *const u8, body_len: u32, written_out: *mut u32) -> *mut u8;
 	fn ext_blake2_256(data: *const u8, len: u32, out: *mut u8);
 	fn ext_twox_128(data: *const u8, len: u32, out: *mut u8);
 	fn ext_twox_256(data: *const u8, len: u32, out: *mut u8);
@@ -119,6 +125,21 @@ pub fn storage(key: &[u8]) -> Option<Vec<u8>> {
 	}
 }
 
+/// Get values of all `keys` from storage, in the order the keys are given.
+pub fn storage_batch(keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+	use codec::{Encode, Decode};
+
+	let encoded_keys = keys.encode();
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_storage_batch_get(encoded_keys.as_ptr(), encoded_keys.len() as u32, &mut length);
+		let values = Decode::decode(&mut slice::from_raw_parts(ptr, length as usize))
+			.expect("ext_storage_batch_get always returns encoded values; qed");
+		ext_free(ptr);
+		values
+	}
+}
+
 /// Get `key` from child storage and return a `Vec`, empty if there's a problem.
 pub fn child_storage(storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
 	let mut length: u32 = 0;
@@ -317,6 +338,75 @@ pub fn chain_id() -> u64 {
 	}
 }
 
+/// The encoded `RuntimeVersion` of the given wasm code, or `None` if it isn't a valid runtime.
+pub fn runtime_version(code: &[u8]) -> Option<Vec<u8>> {
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_runtime_version(code.as_ptr(), code.len() as u32, &mut length);
+		if length == u32::max_value() {
+			None
+		} else {
+			let ret = slice::from_raw_parts(ptr, length as usize).to_vec();
+			ext_free(ptr);
+			Some(ret)
+		}
+	}
+}
+
+/// Submit an encoded extrinsic to the transaction pool. Fails outside of offchain workers.
+pub fn submit_extrinsic(extrinsic: &[u8]) -> Result<(), ()> {
+	unsafe {
+		if ext_submit_extrinsic(extrinsic.as_ptr(), extrinsic.len() as u32) == 0 {
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+}
+
+/// Get `key` from the local storage of the node, `None` outside of offchain workers.
+pub fn local_storage_get(key: &[u8]) -> Option<Vec<u8>> {
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_local_storage_get(key.as_ptr(), key.len() as u32, &mut length);
+		if length == u32::max_value() {
+			None
+		} else {
+			let ret = slice::from_raw_parts(ptr, length as usize).to_vec();
+			ext_free(ptr);
+			Some(ret)
+		}
+	}
+}
+
+/// Set `key` to `value` in the local storage of the node. Does nothing outside of offchain workers.
+pub fn local_storage_set(key: &[u8], value: &[u8]) {
+	unsafe {
+		ext_local_storage_set(key.as_ptr(), key.len() as u32, value.as_ptr(), value.len() as u32);
+	}
+}
+
+/// Perform an HTTP request and return the body of a successful response, `None` if the
+/// request failed or outside of offchain workers.
+pub fn http_request(method: &str, uri: &str, body: &[u8]) -> Option<Vec<u8>> {
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_http_request(
+			method.as_ptr(), method.len() as u32,
+			uri.as_ptr(), uri.len() as u32,
+			body.as_ptr(), body.len() as u32,
+			&mut length,
+		);
+		if length == u32::max_value() {
+			None
+		} else {
+			let ret = slice::from_raw_parts(ptr, length as usize).to_vec();
+			ext_free(ptr);
+			Some(ret)
+		}
+	}
+}
+
 /// Conduct a 256-bit Blake2 hash.
 pub fn blake2_256(data: &[u8]) -> [u8; 32] {
 	let mut result: [u8; 32] = Default::default();
@@ -29,11 +29,12 @@ use super::CheckedExtrinsic;
 pub struct SignatureContent<Address, Index, Signature>
 where
 	Address: Codec,
-	Index: HasCompact + Codec,
+	Index: HasCompact + Codec + Copy,
 	Signature: Codec,
 {
 	signed: Address,
 	signature: Signature,
+	#[codec(encoded_as = "<Index as HasCompact>::Type")]
 	index: Index,
 }
 
@@ -44,7 +45,7 @@ where
 pub struct UncheckedExtrinsic<Address, Index, Call, Signature>
 where
 	Address: Codec,
-	Index: HasCompact + Codec,
+	Index: HasCompact + Codec + Copy,
 	Signature: Codec,
 {
 	/// The signature, address and number of extrinsics have come before from
@@ -57,7 +58,7 @@ where
 impl<Address, Index, Signature, Call> UncheckedExtrinsic<Address, Index, Call, Signature>
 where
 	Address: Codec,
-	Index: HasCompact + Codec,
+	Index: HasCompact + Codec + Copy,
 	Signature: Codec,
 {
 	/// New instance of a signed extrinsic aka "transaction".
@@ -86,7 +87,7 @@ impl<Address, Index, Signature, Call, AccountId, Context> traits::Checkable<Cont
 	for UncheckedExtrinsic<Address, Index, Call, Signature>
 where
 	Address: Member + MaybeDisplay + Codec,
-	Index: Member + MaybeDisplay + SimpleArithmetic + Codec,
+	Index: Member + MaybeDisplay + SimpleArithmetic + Codec + Copy,
 	Call: Encode + Member,
 	Signature: Member + traits::Verify<Signer=AccountId> + Codec,
 	AccountId: Member + MaybeDisplay,
@@ -115,7 +116,7 @@ where
 	}
 }
 
-impl<Address: Codec, Index: HasCompact + Codec, Signature: Codec, Call: Decode> Decode
+impl<Address: Codec, Index: HasCompact + Codec + Copy, Signature: Codec, Call: Decode> Decode
 	for UncheckedExtrinsic<Address, Index, Call, Signature>
 {
 	fn decode<I: Input>(input: &mut I) -> Option<Self> {
@@ -132,7 +133,7 @@ impl<Address: Codec, Index: HasCompact + Codec, Signature: Codec, Call: Decode>
 	}
 }
 
-impl<Address: Codec, Index: HasCompact + Codec, Signature: Codec, Call: Encode> Encode
+impl<Address: Codec, Index: HasCompact + Codec + Copy, Signature: Codec, Call: Encode> Encode
 	for UncheckedExtrinsic<Address, Index, Call, Signature>
 {
 	fn encode(&self) -> Vec<u8> {
@@ -149,7 +150,7 @@ impl<Address, Index, Signature, Call> fmt::Debug
 	for UncheckedExtrinsic<Address, Index, Call, Signature>
 where
 	Address: fmt::Debug + Codec,
-	Index: fmt::Debug + HasCompact + Codec,
+	Index: fmt::Debug + HasCompact + Codec + Copy,
 	Signature: Codec,
 	Call: fmt::Debug,
 {
@@ -173,4 +174,14 @@ mod test {
 		let as_vec: Vec<u8> = Decode::decode(&mut encoded.as_slice()).unwrap();
 		assert_eq!(as_vec.encode(), encoded);
 	}
+
+	#[test]
+	fn signed_encoding_uses_compact_index() {
+		type Extrinsic = UncheckedExtrinsic<u32, u32, u32, u32>;
+		let ex = Extrinsic::new_signed(1, 42, 2, 3);
+		let encoded = ex.encode();
+		// length prefix, option tag, address, signature, compact index and function.
+		assert_eq!(encoded.len(), 1 + 1 + 4 + 4 + 1 + 4);
+		assert_eq!(Extrinsic::decode(&mut encoded.as_slice()).unwrap(), ex);
+	}
 }
@@ -20,12 +20,12 @@
 use std::fmt;
 
 use rstd::prelude::*;
-use codec::{Decode, Encode, Input};
+use codec::{Decode, Encode, Input, HasCompact};
 use traits::{self, Member, SimpleArithmetic, MaybeDisplay, CurrentHeight, BlockNumberToHash, Lookup,
 	Checkable, Extrinsic};
 use super::{CheckedExtrinsic, Era};
 
-const TRANSACTION_VERSION: u8 = 1;
+const TRANSACTION_VERSION: u8 = 2;
 
 /// A extrinsic right from the external world. This is unchecked and so
 /// can contain a signature.
@@ -58,10 +58,14 @@ impl<Address, Index, Call, Signature> UncheckedMortalExtrinsic<Address, Index, C
 	}
 }
 
-impl<Address, Index, Call, Signature> Extrinsic for UncheckedMortalExtrinsic<Address, Index, Call, Signature> {
+impl<Address: Encode, Index, Call, Signature> Extrinsic for UncheckedMortalExtrinsic<Address, Index, Call, Signature> {
 	fn is_signed(&self) -> Option<bool> {
 		Some(self.signature.is_some())
 	}
+
+	fn sender(&self) -> Option<Vec<u8>> {
+		self.signature.as_ref().map(|s| s.0.encode())
+	}
 }
 
 impl<Address, AccountId, Index, Call, Signature, Context, Hash, BlockNumber> Checkable<Context>
@@ -108,7 +112,7 @@ impl<Address, Index, Call, Signature> Decode
 where
 	Address: Decode,
 	Signature: Decode,
-	Index: Decode,
+	Index: HasCompact,
 	Call: Decode,
 {
 	fn decode<I: Input>(input: &mut I) -> Option<Self> {
@@ -127,7 +131,16 @@ where
 		}
 
 		Some(UncheckedMortalExtrinsic {
-			signature: if is_signed { Some(Decode::decode(input)?) } else { None },
+			signature: if is_signed {
+				Some((
+					Decode::decode(input)?,
+					Decode::decode(input)?,
+					<<Index as HasCompact>::Type>::decode(input)?.into(),
+					Decode::decode(input)?,
+				))
+			} else {
+				None
+			},
 			function: Decode::decode(input)?,
 		})
 	}
@@ -138,16 +151,19 @@ impl<Address, Index, Call, Signature> Encode
 where
 	Address: Encode,
 	Signature: Encode,
-	Index: Encode,
+	Index: HasCompact + Clone,
 	Call: Encode,
 {
 	fn encode(&self) -> Vec<u8> {
 		super::encode_with_vec_prefix::<Self, _>(|v| {
 			// 1 byte version id.
 			match self.signature.as_ref() {
-				Some(s) => {
+				Some(&(ref signed, ref signature, ref index, ref era)) => {
 					v.push(TRANSACTION_VERSION | 0b1000_0000);
-					s.encode_to(v);
+					signed.encode_to(v);
+					signature.encode_to(v);
+					<<Index as HasCompact>::Type>::from(index.clone()).encode_to(v);
+					era.encode_to(v);
 				}
 				None => {
 					v.push(TRANSACTION_VERSION & 0b0111_1111);
@@ -219,10 +235,21 @@ mod tests {
 		assert_eq!(Ex::decode(&mut &encoded[..]), Some(ux));
 	}
 
+	#[test]
+	fn signed_codec_should_encode_index_compactly() {
+		let signature = TestSig(DUMMY_ACCOUNTID, (1u64, DUMMY_FUNCTION, Era::immortal(), 0u64).encode());
+		let ux = Ex::new_signed(1, DUMMY_FUNCTION, DUMMY_ACCOUNTID, signature.clone(), Era::immortal());
+		let encoded = ux.encode();
+		// length prefix, version, address, signature, compact index, era and function.
+		assert_eq!(encoded.len(), 1 + 1 + 8 + signature.encode().len() + 1 + Era::immortal().encode().len() + 8);
+		assert_eq!(Ex::decode(&mut &encoded[..]), Some(ux));
+	}
+
 	#[test]
 	fn unsigned_check_should_work() {
 		let ux = Ex::new_unsigned(DUMMY_FUNCTION);
 		assert!(!ux.is_signed().unwrap_or(false));
+		assert_eq!(ux.sender(), None);
 		assert!(<Ex as Checkable<TestContext>>::check(ux, &TestContext).is_ok());
 	}
 
@@ -237,6 +264,7 @@ mod tests {
 	fn immortal_signed_check_should_work() {
 		let ux = Ex::new_signed(0, DUMMY_FUNCTION, DUMMY_ACCOUNTID, TestSig(DUMMY_ACCOUNTID, (DUMMY_ACCOUNTID, DUMMY_FUNCTION, Era::immortal(), 0u64).encode()), Era::immortal());
 		assert!(ux.is_signed().unwrap_or(false));
+		assert_eq!(ux.sender(), Some(DUMMY_ACCOUNTID.encode()));
 		assert_eq!(<Ex as Checkable<TestContext>>::check(ux, &TestContext), Ok(CEx { signed: Some((DUMMY_ACCOUNTID, 0)), function: DUMMY_FUNCTION }));
 	}
 
@@ -0,0 +1,112 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Inherent data: the information a block author puts into a block through inherent extrinsics
+//! (e.g. the current time), and that importing nodes check against what they expect.
+//!
+//! The data is made up of independent parts, each encoded and keyed by an identifier, so that
+//! the client can gather them from any number of providers and every runtime module picks out
+//! the part it knows about.
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+
+/// Identifies one part of the inherent data.
+pub type InherentIdentifier = [u8; 8];
+
+/// Identifiers of the parts of the inherent data that both the client and the runtime know about.
+pub mod well_known_identifiers {
+	use super::InherentIdentifier;
+
+	/// The timestamp of the block, in seconds since the unix epoch.
+	pub const TIMESTAMP: InherentIdentifier = *b"timstap0";
+	/// The indices of the authorities that are considered offline.
+	pub const OFFLINE_INDICES: InherentIdentifier = *b"offlnidx";
+}
+
+/// The inherent data of a block; a set of encoded parts keyed by `InherentIdentifier`.
+#[derive(Clone, Default, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+pub struct InherentData {
+	/// The parts, ordered by identifier.
+	data: Vec<(InherentIdentifier, Vec<u8>)>,
+}
+
+impl InherentData {
+	/// Create a new, empty instance.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Put `inherent` under `identifier`. Fails if there is already a part under `identifier`.
+	pub fn put_data<I: Encode>(&mut self, identifier: InherentIdentifier, inherent: &I) -> Result<(), &'static str> {
+		match self.data.binary_search_by_key(&identifier, |p| p.0) {
+			Ok(_) => Err("inherent data with this identifier already exists"),
+			Err(i) => {
+				self.data.insert(i, (identifier, inherent.encode()));
+				Ok(())
+			}
+		}
+	}
+
+	/// Put `inherent` under `identifier`, replacing what was there before.
+	pub fn replace_data<I: Encode>(&mut self, identifier: InherentIdentifier, inherent: &I) {
+		match self.data.binary_search_by_key(&identifier, |p| p.0) {
+			Ok(i) => self.data[i].1 = inherent.encode(),
+			Err(i) => self.data.insert(i, (identifier, inherent.encode())),
+		}
+	}
+
+	/// Get the part under `identifier`, or `None` if there is none. Fails if the part doesn't
+	/// decode as an `I`.
+	pub fn get_data<I: Decode>(&self, identifier: &InherentIdentifier) -> Result<Option<I>, &'static str> {
+		match self.data.binary_search_by_key(identifier, |p| p.0) {
+			Ok(i) => I::decode(&mut &self.data[i].1[..])
+				.map(Some)
+				.ok_or("inherent data could not be decoded"),
+			Err(_) => Ok(None),
+		}
+	}
+
+	/// The number of parts.
+	pub fn len(&self) -> usize {
+		self.data.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn inherent_data_parts_are_kept_apart() {
+		let mut data = InherentData::new();
+		data.put_data(*b"second00", &2u32).unwrap();
+		data.put_data(*b"first000", &vec![1u64]).unwrap();
+		assert!(data.put_data(*b"first000", &3u32).is_err());
+		assert_eq!(data.len(), 2);
+
+		assert_eq!(data.get_data::<Vec<u64>>(b"first000"), Ok(Some(vec![1])));
+		assert_eq!(data.get_data::<u32>(b"second00"), Ok(Some(2)));
+		assert_eq!(data.get_data::<u32>(b"missing0"), Ok(None));
+		assert!(data.get_data::<u64>(b"second00").is_err());
+
+		data.replace_data(*b"second00", &5u32);
+		let decoded = InherentData::decode(&mut &data.encode()[..]).unwrap();
+		assert_eq!(decoded.get_data::<u32>(b"second00"), Ok(Some(5)));
+		assert_eq!(decoded, data);
+	}
+}
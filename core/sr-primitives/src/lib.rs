@@ -60,7 +60,9 @@ pub mod testing;
 
 pub mod traits;
 pub mod generic;
+pub mod inherents;
 pub mod transaction_validity;
+pub mod weights;
 
 pub type Justification = Vec<u8>;
 
@@ -238,6 +240,8 @@ pub enum ApplyError {
 	Future = 2,
 	/// Sending account had too low a balance.
 	CantPay = 3,
+	/// The block has no room left for the extrinsic's weight.
+	FullBlock = 4,
 }
 
 impl codec::Encode for ApplyError {
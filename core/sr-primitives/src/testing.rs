@@ -48,11 +48,24 @@ impl traits::Digest for Digest {
 	}
 }
 
+/// A signature which is valid for a `u64` signer when it names that signer and carries
+/// the signed message verbatim.
+#[derive(Default, PartialEq, Eq, Clone, Serialize, Deserialize, Debug, Encode, Decode)]
+pub struct TestSignature(pub u64, pub Vec<u8>);
+
+impl traits::Verify for TestSignature {
+	type Signer = u64;
+	fn verify<L: traits::Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
+		*signer == self.0 && msg.get() == &self.1[..]
+	}
+}
+
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug, Encode, Decode)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
 pub struct Header {
 	pub parent_hash: H256,
+	#[codec(compact)]
 	pub number: u64,
 	pub state_root: H256,
 	pub extrinsics_root: H256,
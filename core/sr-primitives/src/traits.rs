@@ -24,6 +24,8 @@ use runtime_io;
 use substrate_primitives;
 use substrate_primitives::Blake2Hasher;
 use codec::{Codec, Encode, HasCompact};
+use weights::DispatchInfo;
+use inherents::InherentIdentifier;
 pub use integer_sqrt::IntegerSquareRoot;
 pub use num_traits::{Zero, One, Bounded};
 pub use num_traits::ops::checked::{CheckedAdd, CheckedSub, CheckedMul, CheckedDiv};
@@ -92,12 +94,17 @@ pub trait BlockNumberToHash {
 /// Simple payment making trait, operating on a single generic `AccountId` type.
 pub trait MakePayment<AccountId> {
 	/// Make some sort of payment concerning `who` for an extrinsic (transaction) of encoded length
-	/// `encoded_len` bytes. Return true iff the payment was successful.
-	fn make_payment(who: &AccountId, encoded_len: usize) -> Result<(), &'static str>;
+	/// `encoded_len` bytes whose dispatch has weight and class `info`. Return `Ok` iff the payment
+	/// was successful.
+	fn make_payment(who: &AccountId, encoded_len: usize, info: DispatchInfo) -> Result<(), &'static str>;
+
+	/// Settle the payment made by `who` for the same extrinsic once it has been dispatched with
+	/// `result`, e.g. by handing back the part of the fee that turned out not to be owed.
+	fn refund(_who: &AccountId, _encoded_len: usize, _info: DispatchInfo, _result: &Result<(), &'static str>) {}
 }
 
 impl<T> MakePayment<T> for () {
-	fn make_payment(_: &T, _: usize) -> Result<(), &'static str> { Ok(()) }
+	fn make_payment(_: &T, _: usize, _: DispatchInfo) -> Result<(), &'static str> { Ok(()) }
 }
 
 /// Extensible conversion trait. Generic over both source and destination types.
@@ -216,6 +223,17 @@ pub trait OnFinalise<BlockNumber> {
 
 impl<N> OnFinalise<N> for () {}
 
+/// The offchain worker trait. Implementing this lets you do work outside of block execution,
+/// e.g. to submit transactions, after a new best block got imported.
+///
+/// Runs with the state of the new best block, changes to it are discarded.
+pub trait OffchainWorker<BlockNumber> {
+	/// A new best block got imported. Implement to have something happen.
+	fn offchain_worker(_n: BlockNumber) {}
+}
+
+impl<N> OffchainWorker<N> for () {}
+
 macro_rules! tuple_impl {
 	($one:ident,) => {
 		impl<Number: Copy, $one: OnFinalise<Number>> OnFinalise<Number> for ($one,) {
@@ -223,6 +241,11 @@ macro_rules! tuple_impl {
 				$one::on_finalise(n);
 			}
 		}
+		impl<Number: Copy, $one: OffchainWorker<Number>> OffchainWorker<Number> for ($one,) {
+			fn offchain_worker(n: Number) {
+				$one::offchain_worker(n);
+			}
+		}
 	};
 	($first:ident, $($rest:ident,)+) => {
 		impl<
@@ -235,6 +258,16 @@ macro_rules! tuple_impl {
 				$($rest::on_finalise(n);)+
 			}
 		}
+		impl<
+			Number: Copy,
+			$first: OffchainWorker<Number>,
+			$($rest: OffchainWorker<Number>),+
+		> OffchainWorker<Number> for ($first, $($rest),+) {
+			fn offchain_worker(n: Number) {
+				$first::offchain_worker(n);
+				$($rest::offchain_worker(n);)+
+			}
+		}
 		tuple_impl!($($rest,)+);
 	}
 }
@@ -552,12 +585,17 @@ pub trait DigestItem: Codec + Member {
 
 /// Something that provides an inherent for a runtime.
 pub trait ProvideInherent {
+	/// The identifier of the part of the inherent data this provides for.
+	const INHERENT_IDENTIFIER: InherentIdentifier;
 	/// The inherent that is provided.
-	type Inherent: Encode + MaybeDecode;
+	type Inherent: Codec;
 	/// The error used by this trait.
 	type Error: Encode + MaybeDecode;
 	/// The call for setting the inherent.
 	type Call: Encode + MaybeDecode;
+	/// Whether blocks are rejected when the inherent data they are checked against has no part
+	/// for this module.
+	const INHERENT_REQUIRED: bool = true;
 
 	/// Create the inherent extrinsics.
 	///
@@ -577,4 +615,8 @@ pub trait Extrinsic {
 	/// Is this `Extrinsic` signed?
 	/// If no information are available about signed/unsigned, `None` should be returned.
 	fn is_signed(&self) -> Option<bool> { None }
+
+	/// The encoded sender of this `Extrinsic`, to tell apart transactions of different senders.
+	/// If no information are available about the sender, `None` should be returned.
+	fn sender(&self) -> Option<Vec<u8>> { None }
 }
@@ -0,0 +1,160 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dispatch weight interface.
+//!
+//! Every dispatchable call declares a weight, a measure of the resources its execution may
+//! consume, and a class, which decides how much of a block's weight budget it may draw from.
+
+/// Numeric measure of the resources consumed by a dispatch.
+pub type Weight = u32;
+
+/// The weight given to a call that doesn't declare one.
+pub const DEFAULT_WEIGHT: Weight = 10_000;
+
+/// The class of a dispatch.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq)]
+pub enum DispatchClass {
+	/// An ordinary transaction; it may only fill the part of the block not reserved for
+	/// operational dispatches.
+	Normal,
+	/// A dispatch that keeps the chain running (e.g. governance); it may fill the whole block.
+	Operational,
+}
+
+impl Default for DispatchClass {
+	fn default() -> Self {
+		DispatchClass::Normal
+	}
+}
+
+/// The weight and class of a particular dispatch.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Default)]
+pub struct DispatchInfo {
+	/// The weight of the dispatch.
+	pub weight: Weight,
+	/// The class of the dispatch.
+	pub class: DispatchClass,
+}
+
+/// Something (usually a call) whose dispatch has a known weight and class.
+pub trait GetDispatchInfo {
+	/// Return the weight and class of dispatching `self`.
+	fn get_dispatch_info(&self) -> DispatchInfo;
+}
+
+/// A weight annotation for a call that doesn't depend on its arguments. This is what the
+/// `#[weight = ...]` attribute of `decl_module!` expects.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum SimpleDispatchInfo {
+	/// A normal dispatch of the given weight.
+	FixedNormal(Weight),
+	/// An operational dispatch of the given weight.
+	FixedOperational(Weight),
+}
+
+impl Default for SimpleDispatchInfo {
+	fn default() -> Self {
+		SimpleDispatchInfo::FixedNormal(DEFAULT_WEIGHT)
+	}
+}
+
+impl From<SimpleDispatchInfo> for DispatchInfo {
+	fn from(s: SimpleDispatchInfo) -> DispatchInfo {
+		match s {
+			SimpleDispatchInfo::FixedNormal(weight) => DispatchInfo { weight, class: DispatchClass::Normal },
+			SimpleDispatchInfo::FixedOperational(weight) => DispatchInfo { weight, class: DispatchClass::Operational },
+		}
+	}
+}
+
+/// The factor by which the weight of a dispatch is multiplied when charging for it. It is fixed
+/// point with `WeightMultiplier::ACCURACY` parts making one, and never falls below one.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WeightMultiplier(u64);
+
+impl WeightMultiplier {
+	/// The number of parts making up one.
+	pub const ACCURACY: u64 = 1_000_000_000;
+
+	/// Construct a new instance from the number of `ACCURACY`ths, saturating at one from below.
+	pub fn from_parts(parts: u64) -> Self {
+		WeightMultiplier(parts.max(Self::ACCURACY))
+	}
+
+	/// The number of `ACCURACY`ths this represents.
+	pub fn into_parts(self) -> u64 {
+		self.0
+	}
+
+	/// Multiply `weight` by self, saturating on overflow.
+	pub fn apply_to(self, weight: Weight) -> u64 {
+		((weight as u128 * self.0 as u128) / Self::ACCURACY as u128).min(u64::max_value() as u128) as u64
+	}
+
+	/// The multiplier for the next block, given that `used` of the `limit` weight available to
+	/// normal dispatches was consumed in this one.
+	///
+	/// The aim is to keep blocks a quarter full: every block above that target raises the
+	/// multiplier in proportion to how far over it was, and every block below lowers it likewise.
+	pub fn next(self, used: Weight, limit: Weight) -> Self {
+		if limit == 0 {
+			return self;
+		}
+		let target = limit as u128 / 4;
+		let used = (used as u128).min(limit as u128);
+		let current = self.0 as u128;
+		// the change is a quarter of the relative distance from the target.
+		let delta = |distance: u128| current * distance / limit as u128 / 4;
+		let next = if used > target {
+			current.saturating_add(delta(used - target))
+		} else {
+			current.saturating_sub(delta(target - used))
+		};
+		Self::from_parts(next.min(u64::max_value() as u128) as u64)
+	}
+}
+
+impl Default for WeightMultiplier {
+	fn default() -> Self {
+		WeightMultiplier(Self::ACCURACY)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn multiplier_tracks_block_fullness() {
+		let one = WeightMultiplier::default();
+		assert_eq!(one.apply_to(1_000), 1_000);
+
+		// a quarter-full block leaves it alone.
+		assert_eq!(one.next(250, 1_000), one);
+		// a full block raises it by three sixteenths.
+		let raised = one.next(1_000, 1_000);
+		assert_eq!(raised.into_parts(), 1_187_500_000);
+		assert_eq!(raised.apply_to(1_000), 1_187);
+		// an empty block lowers it again, but never below one.
+		assert_eq!(raised.next(0, 1_000).into_parts(), 1_113_281_250);
+		assert_eq!(one.next(0, 1_000), one);
+	}
+}
@@ -132,6 +132,9 @@ pub enum PruningMode {
 	ArchiveAll,
 	/// Canonicalization discards non-canonical nodes. All the canonical nodes are kept in the DB.
 	ArchiveCanonical,
+	/// Only the state of the last canonical block is kept in the DB. Blocks are to be
+	/// canonicalized as they get finalized, never before.
+	Finalized,
 }
 
 impl PruningMode {
@@ -143,11 +146,24 @@ impl PruningMode {
 		})
 	}
 
+	/// Create a mode that keeps only the state of the last finalized block.
+	pub fn keep_finalized() -> PruningMode {
+		PruningMode::Finalized
+	}
+
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
 	pub fn is_archive(&self) -> bool {
 		match *self {
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => true,
-			PruningMode::Constrained(_) => false
+			PruningMode::Constrained(_) | PruningMode::Finalized => false
+		}
+	}
+
+	/// Is canonicalization driven by finality in this pruning mode?
+	pub fn is_finalized(&self) -> bool {
+		match *self {
+			PruningMode::Finalized => true,
+			_ => false,
 		}
 	}
 
@@ -174,14 +190,14 @@ struct StateDbSync<BlockHash: Hash, Key: Hash> {
 
 impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 	pub fn new<D: MetaDb>(mode: PruningMode, db: &D) -> Result<StateDbSync<BlockHash, Key>, Error<D::Error>> {
-		trace!("StateDb settings: {:?}", mode);
+		trace!(target: "state-db", "StateDb settings: {:?}", mode);
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(db)?;
 		let pruning: Option<RefWindow<BlockHash, Key>> = match mode {
 			PruningMode::Constrained(Constraints {
 				max_mem: Some(_),
 				..
 			}) => unimplemented!(),
-			PruningMode::Constrained(_) => Some(RefWindow::new(db)?),
+			PruningMode::Constrained(_) | PruningMode::Finalized => Some(RefWindow::new(db)?),
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 		Ok(StateDbSync {
@@ -208,7 +224,7 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 					meta: Default::default(),
 				})
 			},
-			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical => {
+			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical | PruningMode::Finalized => {
 				self.non_canonical.insert(hash, number, parent_hash, changeset)
 			}
 		}
@@ -226,7 +242,7 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 				commit.data.deleted.clear();
 				commit
 			},
-			PruningMode::Constrained(_) => {
+			PruningMode::Constrained(_) | PruningMode::Finalized => {
 				self.non_canonical.canonicalize(hash)
 			},
 		};
@@ -246,13 +262,18 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 	}
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) {
-		if let (&mut Some(ref mut pruning), &PruningMode::Constrained(ref constraints)) = (&mut self.pruning, &self.mode) {
+		let (max_blocks, max_mem) = match self.mode {
+			PruningMode::Constrained(ref constraints) => (constraints.max_blocks.unwrap_or(0), constraints.max_mem),
+			PruningMode::Finalized => (0, None),
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => return,
+		};
+		if let Some(ref mut pruning) = self.pruning {
 			loop {
-				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
+				if pruning.window_size() <= max_blocks as u64 {
 					break;
 				}
 
-				if constraints.max_mem.map_or(false, |m| pruning.mem_used() > m) {
+				if max_mem.map_or(false, |m| pruning.mem_used() > m) {
 					break;
 				}
 
@@ -273,7 +294,7 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 			PruningMode::ArchiveAll => {
 				Some(CommitSet::default())
 			},
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) | PruningMode::Finalized => {
 				self.non_canonical.revert_one()
 			},
 		}
@@ -63,6 +63,10 @@ pub trait Backend<H: Hasher> {
 	/// call `f` for each of those keys.
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F);
 
+	/// Retrieve all entries keys starting from the given key (inclusive), in key order, and
+	/// call `f` for each of those keys until it returns `false`.
+	fn for_keys_from<F: FnMut(&[u8]) -> bool>(&self, start: &[u8], f: F);
+
 	/// Calculate the storage root, with given delta over what is already stored in
 	/// the backend, and produce a "transaction" that can be used to commit.
 	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
@@ -230,6 +234,18 @@ impl<H: Hasher> Backend<H> for InMemory<H> where H::Out: HeapSizeOf {
 		self.inner.get(&Some(storage_key.to_vec())).map(|map| map.keys().for_each(|k| f(&k)));
 	}
 
+	fn for_keys_from<F: FnMut(&[u8]) -> bool>(&self, start: &[u8], mut f: F) {
+		let mut keys: Vec<_> = self.inner.get(&None).into_iter()
+			.flat_map(|map| map.keys().filter(|key| &key[..] >= start))
+			.collect();
+		keys.sort();
+		for key in keys {
+			if !f(key) {
+				break;
+			}
+		}
+	}
+
 	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
 	where
 		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
@@ -103,6 +103,11 @@ pub trait Externalities<H: Hasher> {
 	/// Read child storage of current contract being called.
 	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Read several storage entries of current contract being called at once.
+	fn storage_batch(&self, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+		keys.iter().map(|key| self.storage(key)).collect()
+	}
+
 	/// Set storage entry `key` of current contract being called (effective immediately).
 	fn set_storage(&mut self, key: Vec<u8>, value: Vec<u8>) {
 		self.place_storage(key, Some(value));
@@ -469,6 +474,87 @@ where
 	backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)
 }
 
+/// Generate storage read proof for several keys at once.
+///
+/// The resulting proof contains the trie nodes required to read every key, so
+/// it is more compact than a set of `prove_read` proofs for the same keys.
+pub fn prove_read_keys<B, H, I>(
+	backend: B,
+	keys: I,
+) -> Result<Vec<Vec<u8>>, Box<Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+
+	H::Out: Ord + HeapSizeOf
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	for key in keys {
+		proving_backend.storage(key.as_ref()).map_err(|e| Box::new(e) as Box<Error>)?;
+	}
+	Ok(proving_backend.extract_proof())
+}
+
+/// Check storage read proof for several keys, generated by `prove_read_keys` call.
+///
+/// Returns values of the keys in the same order as they have been given.
+pub fn read_proof_check_keys<H, I>(
+	root: H::Out,
+	proof: Vec<Vec<u8>>,
+	keys: I,
+) -> Result<Vec<Option<Vec<u8>>>, Box<Error>>
+where
+	H: Hasher,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+
+	H::Out: Ord + HeapSizeOf
+{
+	let backend = proving_backend::create_proof_check_backend::<H>(root, proof)?;
+	keys.into_iter()
+		.map(|key| backend.storage(key.as_ref()).map_err(|e| Box::new(e) as Box<Error>))
+		.collect()
+}
+
+/// Generate child storage read proof.
+pub fn prove_child_read<B, H>(
+	backend: B,
+	storage_key: &[u8],
+	key: &[u8],
+) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+
+	H::Out: Ord + HeapSizeOf
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	let result = proving_backend.child_storage(storage_key, key).map_err(|e| Box::new(e) as Box<Error>)?;
+	Ok((result, proving_backend.extract_proof()))
+}
+
+/// Check child storage read proof, generated by `prove_child_read` call.
+pub fn read_child_proof_check<H>(
+	root: H::Out,
+	proof: Vec<Vec<u8>>,
+	storage_key: &[u8],
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, Box<Error>>
+where
+	H: Hasher,
+
+	H::Out: Ord + HeapSizeOf
+{
+	let backend = proving_backend::create_proof_check_backend::<H>(root, proof)?;
+	backend.child_storage(storage_key, key).map_err(|e| Box::new(e) as Box<Error>)
+}
+
 /// Sets overlayed changes' changes trie configuration. Returns error if configuration
 /// differs from previous OR config decode has failed.
 pub(crate) fn set_changes_trie_config(overlay: &mut OverlayedChanges, config: Option<Vec<u8>>, final_check: bool) -> Result<(), Box<Error>> {
@@ -691,6 +777,58 @@ mod tests {
 		assert_eq!(local_result2, false);
 	}
 
+	#[test]
+	fn prove_read_keys_and_proof_check_works() {
+		// fetch read proof for several keys from 'remote' full node
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let remote_proof = prove_read_keys(remote_backend, &[&b"value2"[..], &b"key"[..], &b"missing"[..]]).unwrap();
+		// check proof locally
+		let local_result = read_proof_check_keys::<Blake2Hasher, _>(
+			remote_root,
+			remote_proof.clone(),
+			&[&b"value2"[..], &b"key"[..], &b"missing"[..]],
+		).unwrap();
+		assert_eq!(local_result, vec![Some(vec![24]), Some(b"value".to_vec()), None]);
+		// keys that were not part of the proof can not be checked
+		assert!(read_proof_check_keys::<Blake2Hasher, _>(remote_root, remote_proof, &[&[0xff][..]]).is_err());
+	}
+
+	#[test]
+	fn prove_child_read_and_proof_check_works() {
+		let child_storage_key = b":child_storage:default:sub1".to_vec();
+		let child_root = ::trie::child_trie_root::<Blake2Hasher, _, _, _>(
+			&child_storage_key,
+			vec![(b"value3".to_vec(), vec![142])],
+		);
+		let remote_backend: InMemory<Blake2Hasher> = vec![
+			(None, b"value2".to_vec(), Some(vec![24])),
+			(None, child_storage_key.clone(), Some(child_root)),
+			(Some(child_storage_key.clone()), b"value3".to_vec(), Some(vec![142])),
+		].into();
+		let remote_backend = remote_backend.try_into_trie_backend().unwrap();
+
+		// fetch child read proof from 'remote' full node
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let remote_proof = prove_child_read(remote_backend, &child_storage_key, b"value3").unwrap().1;
+		// check proof locally
+		let local_result1 = read_child_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof.clone(),
+			&child_storage_key,
+			b"value3",
+		).unwrap();
+		let local_result2 = read_child_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof.clone(),
+			&child_storage_key,
+			b"value2",
+		).unwrap();
+		// check that results are correct
+		assert_eq!(local_result1, Some(vec![142]));
+		assert_eq!(local_result2, None);
+	}
+
 	#[test]
 	fn cannot_change_changes_trie_config() {
 		assert!(execute(
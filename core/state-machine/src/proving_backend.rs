@@ -142,6 +142,10 @@ impl<S, H> Backend<H> for ProvingBackend<S, H>
 		self.backend.for_keys_with_prefix(prefix, f)
 	}
 
+	fn for_keys_from<F: FnMut(&[u8]) -> bool>(&self, start: &[u8], f: F) {
+		self.backend.for_keys_from(start, f)
+	}
+
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
 		self.backend.pairs()
 	}
@@ -76,6 +76,10 @@ impl<S: TrieBackendStorage<H>, H: Hasher> Backend<H> for TrieBackend<S, H> where
 		self.essence.for_keys_in_child_storage(storage_key, f)
 	}
 
+	fn for_keys_from<F: FnMut(&[u8]) -> bool>(&self, start: &[u8], f: F) {
+		self.essence.for_keys_from(start, f)
+	}
+
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
 		let mut read_overlay = MemoryDB::default();	// TODO: use new for correctness
 		let eph = Ephemeral::new(self.essence.backend_storage(), &mut read_overlay);
@@ -242,4 +246,17 @@ pub mod tests {
 		expected.insert(b"value2".to_vec());
 		assert_eq!(seen, expected);
 	}
+
+	#[test]
+	fn walking_from_key_works() {
+		let trie = test_trie();
+
+		let mut seen = Vec::new();
+		trie.for_keys_from(b"kez", |key| {
+			seen.push(key.to_vec());
+			seen.len() < 2
+		});
+
+		assert_eq!(seen, vec![b"value1".to_vec(), b"value2".to_vec()]);
+	}
 }
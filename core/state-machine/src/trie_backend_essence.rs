@@ -136,6 +136,37 @@ impl<S: TrieBackendStorage<H>, H: Hasher> TrieBackendEssence<S, H> where H::Out:
 			debug!(target: "trie", "Error while iterating by prefix: {}", e);
 		}
 	}
+
+	/// Execute given closure for all keys starting from the given key (inclusive), in key
+	/// order, until it returns `false`.
+	pub fn for_keys_from<F: FnMut(&[u8]) -> bool>(&self, start: &[u8], mut f: F) {
+		let mut read_overlay = MemoryDB::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+
+		let mut iter = move || -> Result<(), Box<TrieError<H::Out>>> {
+			let trie = TrieDB::<H>::new(&eph, &self.root)?;
+			let mut iter = trie.iter()?;
+
+			iter.seek(start)?;
+
+			for x in iter {
+				let (key, _) = x?;
+
+				if !f(&key) {
+					break;
+				}
+			}
+
+			Ok(())
+		};
+
+		if let Err(e) = iter() {
+			debug!(target: "trie", "Error while iterating from key: {}", e);
+		}
+	}
 }
 
 pub(crate) struct Ephemeral<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> {
@@ -19,32 +19,46 @@
 //!
 //! `telemetry` macro may be used anywhere in the Substrate codebase
 //! in order to send real-time logging information to the telemetry
-//! server (if there is one). We use the async drain adapter of `slog`
-//! so that the logging thread doesn't get held up at all.
+//! servers (if there are any). Every event has a verbosity level and
+//! is serialized as a line of JSON, which is sent to each server whose
+//! configured verbosity is at least the event's. We use the async drain
+//! adapter of `slog` so that the logging thread doesn't get held up at all.
 // end::description[]
 
 extern crate parking_lot;
 extern crate ws;
 extern crate slog_async;
 extern crate slog_json;
+// `log` is imported after `slog`, so that its macros shadow the same-named ones of `slog`.
 #[macro_use]
-extern crate log;
-#[macro_use(o, kv)]
 extern crate slog;
+#[macro_use]
+extern crate log;
 extern crate slog_scope;
 
-use std::{io, time, thread};
+use std::{cmp, fmt, io, time, thread};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use parking_lot::Mutex;
-use slog::Drain;
+use slog::{Drain, KV};
 pub use slog_scope::with_logger;
 
+/// General information about the node, sent to every endpoint.
+pub const SUBSTRATE_INFO: u8 = 0;
+/// Detailed information about the node.
+pub const SUBSTRATE_DEBUG: u8 = 9;
+/// General information about consensus and finality.
+pub const CONSENSUS_INFO: u8 = 1;
+/// Detailed information about consensus and finality rounds.
+pub const CONSENSUS_DEBUG: u8 = 5;
+
 /// Configuration for telemetry.
 pub struct TelemetryConfig {
-	/// URL of the telemetry WebSocket server.
-	pub url: String,
-	/// What do do when we connect to the server.
-	pub on_connect: Box<Fn() + Send + 'static>,
+	/// URLs of the telemetry WebSocket servers, each with the maximum
+	/// verbosity of the events sent to it.
+	pub endpoints: Vec<(String, u8)>,
+	/// What do do when we connect to a server.
+	pub on_connect: Box<Fn() + Send + Sync + 'static>,
 }
 
 /// Telemetry service guard.
@@ -53,49 +67,133 @@ pub type Telemetry = slog_scope::GlobalLoggerGuard;
 /// Size of the channel for passing messages to telemetry thread.
 const CHANNEL_SIZE: usize = 262144;
 
+/// Delay before reconnecting to a server after a connection that was established.
+const MIN_RECONNECT_DELAY: u64 = 1000;
+
+/// Upper bound on the delay before reconnecting to an unreachable server.
+const MAX_RECONNECT_DELAY: u64 = 60_000;
+
 /// Initialise telemetry.
 pub fn init_telemetry(config: TelemetryConfig) -> slog_scope::GlobalLoggerGuard {
-	let writer = TelemetryWriter::new();
-	let out_sync = writer.out.clone();
+	let config = Arc::new(config);
+	let mut drains = Vec::with_capacity(config.endpoints.len());
+
+	for (index, &(ref url, max_verbosity)) in config.endpoints.iter().enumerate() {
+		let writer = TelemetryWriter::new();
+		let out_sync = writer.out.clone();
+		drains.push(EndpointDrain {
+			max_verbosity,
+			drain: slog_json::Json::default(writer).fuse(),
+		});
+
+		let config = config.clone();
+		let url = url.clone();
+		thread::spawn(move || {
+			let mut delay = MIN_RECONNECT_DELAY;
+			loop {
+				trace!(target: "telemetry", "Connecting to Telemetry at {}...", url);
+				let connected = AtomicBool::new(false);
+				let _ = ws::connect(url.as_str(), |out| Connection::new(out, &*out_sync, &config, &connected));
+
+				delay = next_reconnect_delay(delay, connected.load(Ordering::Relaxed));
+				trace!(target: "telemetry", "Reconnecting to telemetry endpoint #{} in {} ms", index, delay);
+				thread::sleep(time::Duration::from_millis(delay));
+			}
+		});
+	}
+
 	let log = slog::Logger::root(
-		slog_async::Async::new(
-			slog_json::Json::default(writer).fuse()
-		).chan_size(CHANNEL_SIZE)
-		.overflow_strategy(slog_async::OverflowStrategy::DropAndReport)
-		.build().fuse(), o!()
+		slog_async::Async::new(Endpoints(drains))
+			.chan_size(CHANNEL_SIZE)
+			.overflow_strategy(slog_async::OverflowStrategy::DropAndReport)
+			.build().fuse(), o!()
 	);
-	let logger_guard = slog_scope::set_global_logger(log);
 
-	thread::spawn(move || {
-		loop {
-			trace!(target: "telemetry", "Connecting to Telemetry...");
-			let _ = ws::connect(config.url.as_str(), |out| Connection::new(out, &*out_sync, &config));
+	slog_scope::set_global_logger(log)
+}
+
+/// Send a structured event to the telemetry servers.
+///
+/// The first argument is the verbosity of the event, the second its name,
+/// followed by its fields.
+#[macro_export]
+macro_rules! telemetry {
+	( $verbosity:expr; $msg:expr; $($t:tt)* ) => {
+		$crate::with_logger(|l| slog_info!(l, $msg; "verbosity" => $verbosity as u8, $($t)* ))
+	}
+}
+
+// back off exponentially while the server is unreachable.
+fn next_reconnect_delay(current: u64, was_connected: bool) -> u64 {
+	if was_connected {
+		MIN_RECONNECT_DELAY
+	} else {
+		cmp::min(current.saturating_mul(2), MAX_RECONNECT_DELAY)
+	}
+}
+
+// extracts the verbosity of an event from its fields.
+struct VerbosityExtractor(Option<u8>);
+
+impl slog::Serializer for VerbosityExtractor {
+	fn emit_arguments(&mut self, _key: slog::Key, _val: &fmt::Arguments) -> slog::Result {
+		Ok(())
+	}
 
-			thread::sleep(time::Duration::from_millis(5000));
+	fn emit_u8(&mut self, key: slog::Key, val: u8) -> slog::Result {
+		if key == "verbosity" {
+			self.0 = Some(val);
 		}
-	});
+		Ok(())
+	}
+}
 
-	return logger_guard;
+fn verbosity(record: &slog::Record) -> u8 {
+	let mut extractor = VerbosityExtractor(None);
+	let _ = record.kv().serialize(record, &mut extractor);
+	extractor.0.unwrap_or(SUBSTRATE_INFO)
 }
 
-/// Exactly equivalent to `slog_scope::info`, provided as a convenience.
-#[macro_export]
-macro_rules! telemetry {
-	( $($t:tt)* ) => { $crate::with_logger(|l| slog_info!(l, $($t)* )) }
+struct EndpointDrain<D> {
+	max_verbosity: u8,
+	drain: D,
+}
+
+// dispatches every event to the endpoints interested in its verbosity.
+struct Endpoints<D>(Vec<EndpointDrain<D>>);
+
+impl<D: Drain<Ok=(), Err=slog::Never>> Drain for Endpoints<D> {
+	type Ok = ();
+	type Err = slog::Never;
+
+	fn log(&self, record: &slog::Record, values: &slog::OwnedKVList) -> Result<(), slog::Never> {
+		let verbosity = verbosity(record);
+		for endpoint in self.0.iter().filter(|e| verbosity <= e.max_verbosity) {
+			endpoint.drain.log(record, values)?;
+		}
+		Ok(())
+	}
 }
 
 struct Connection<'a> {
 	out: ws::Sender,
 	out_sync: &'a Mutex<Option<ws::Sender>>,
 	config: &'a TelemetryConfig,
+	connected: &'a AtomicBool,
 }
 
 impl<'a> Connection<'a> {
-	fn new(out: ws::Sender, out_sync: &'a Mutex<Option<ws::Sender>>, config: &'a TelemetryConfig) -> Self {
+	fn new(
+		out: ws::Sender,
+		out_sync: &'a Mutex<Option<ws::Sender>>,
+		config: &'a TelemetryConfig,
+		connected: &'a AtomicBool,
+	) -> Self {
 		Connection {
 			out,
 			out_sync,
 			config,
+			connected,
 		}
 	}
 }
@@ -104,6 +202,7 @@ impl<'a> ws::Handler for Connection<'a> {
 	fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
 		trace!(target: "telemetry", "Connected!");
 
+		self.connected.store(true, Ordering::Relaxed);
 		*self.out_sync.lock() = Some(self.out.clone());
 		(self.config.on_connect)();
 		Ok(())
@@ -115,12 +214,10 @@ impl<'a> ws::Handler for Connection<'a> {
 		trace!(target: "telemetry", "Connection closing due to ({:?}) {}", code, reason);
     }
 
-	fn on_error(&mut self, _: ws::Error) {
+	fn on_error(&mut self, e: ws::Error) {
 		*self.out_sync.lock() = None;
 
-		// Sleep to ensure that reconnecting isn't spamming logs.
-		// This happens in it's own thread so it won't block anything.
-		thread::sleep(time::Duration::from_millis(1000));
+		trace!(target: "telemetry", "Connection error: {}", e);
 	}
 }
 
@@ -181,3 +278,51 @@ impl io::Write for TelemetryWriter {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Mutex;
+
+	struct Collect(Arc<Mutex<Vec<String>>>);
+
+	impl Drain for Collect {
+		type Ok = ();
+		type Err = slog::Never;
+
+		fn log(&self, record: &slog::Record, _: &slog::OwnedKVList) -> Result<(), slog::Never> {
+			self.0.lock().unwrap().push(format!("{}", record.msg()));
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn events_are_filtered_by_endpoint_verbosity() {
+		let info = Arc::new(Mutex::new(Vec::new()));
+		let debug = Arc::new(Mutex::new(Vec::new()));
+		let drain = Endpoints(vec![
+			EndpointDrain { max_verbosity: SUBSTRATE_INFO, drain: Collect(info.clone()) },
+			EndpointDrain { max_verbosity: CONSENSUS_DEBUG, drain: Collect(debug.clone()) },
+		]);
+		let log = slog::Logger::root(drain, o!());
+
+		slog_info!(log, "block.import"; "verbosity" => SUBSTRATE_INFO, "height" => 1);
+		slog_info!(log, "afg.round_started"; "verbosity" => CONSENSUS_DEBUG, "round" => 1);
+		slog_info!(log, "system.debug"; "verbosity" => SUBSTRATE_DEBUG);
+
+		assert_eq!(*info.lock().unwrap(), vec!["block.import".to_owned()]);
+		assert_eq!(*debug.lock().unwrap(), vec!["block.import".to_owned(), "afg.round_started".to_owned()]);
+	}
+
+	#[test]
+	fn reconnect_backs_off_until_connected() {
+		let mut delay = MIN_RECONNECT_DELAY;
+		for _ in 0..10 {
+			delay = next_reconnect_delay(delay, false);
+		}
+		assert_eq!(delay, MAX_RECONNECT_DELAY);
+
+		assert_eq!(next_reconnect_delay(2 * MIN_RECONNECT_DELAY, false), 4 * MIN_RECONNECT_DELAY);
+		assert_eq!(next_reconnect_delay(delay, true), MIN_RECONNECT_DELAY);
+	}
+}
@@ -119,6 +119,10 @@ impl ExtrinsicT for Extrinsic {
 	fn is_signed(&self) -> Option<bool> {
 		Some(true)
 	}
+
+	fn sender(&self) -> Option<Vec<u8>> {
+		Some(self.transfer.from.encode())
+	}
 }
 
 /// An identifier for an account on this system.
@@ -19,6 +19,7 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
+	collections::HashMap,
 	hash,
 	sync::Arc,
 };
@@ -93,6 +94,15 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub requires: Vec<Tag>,
 	/// Tags that this transaction provides.
 	pub provides: Vec<Tag>,
+	/// Encoded sender of the transaction, if known.
+	pub sender: Option<Vec<u8>>,
+}
+
+/// Limit of the number of transactions in a queue.
+#[derive(Debug, Clone)]
+pub struct Limit {
+	/// Maximal number of transactions in the queue.
+	pub count: usize,
 }
 
 /// Transaction pool.
@@ -109,6 +119,8 @@ pub struct Transaction<Hash, Extrinsic> {
 pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	future: FutureTransactions<Hash, Ex>,
 	ready: ReadyTransactions<Hash, Ex>,
+	/// Priorities of the transactions of every known sender, in both queues.
+	senders: HashMap<Vec<u8>, HashMap<Hash, Priority>>,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
@@ -116,6 +128,7 @@ impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
 		BasePool {
 			future: Default::default(),
 			ready: Default::default(),
+			senders: Default::default(),
 		}
 	}
 }
@@ -140,6 +153,8 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 		trace!(target: "txpool", "[{:?}] {:?}", tx.transaction.hash, tx);
 		debug!(target: "txpool", "[{:?}] Importing to {}", tx.transaction.hash, if tx.is_ready() { "ready" } else { "future" });
 
+		self.note_sender(&tx.transaction);
+
 		// If all tags are not satisfied import to future.
 		if !tx.is_ready() {
 			let hash = tx.transaction.hash.clone();
@@ -174,6 +189,7 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 
 			// import this transaction
 			let current_hash = tx.transaction.hash.clone();
+			let current_sender = tx.transaction.sender.clone();
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
 					if !first {
@@ -183,16 +199,22 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 					removed.append(&mut replaced);
 				},
 				// transaction failed to be imported.
-				Err(e) => if first {
-					debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
-					return Err(e)
-				} else {
+				Err(e) => {
+					self.forget_sender(&current_sender, &current_hash);
+					if first {
+						debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
+						return Err(e)
+					}
 					failed.push(current_hash);
 				},
 			}
 			first = false;
 		}
 
+		for tx in &removed {
+			self.forget_sender(&tx.sender, &tx.hash);
+		}
+
 		// An edge case when importing transaction caused
 		// some future transactions to be imported and that
 		// future transactions pushed out current transaction.
@@ -201,7 +223,9 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_invalid(&promoted);
+			for tx in self.ready.remove_invalid(&promoted) {
+				self.forget_sender(&tx.sender, &tx.hash);
+			}
 
 			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
 			bail!(error::ErrorKind::CycleDetected)
@@ -225,6 +249,11 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 		self.future.all()
 	}
 
+	/// Returns the number of transactions of given sender in the Future queue.
+	pub fn futures_of(&self, sender: &[u8]) -> usize {
+		self.future.len_of(sender)
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -236,6 +265,9 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 	pub fn remove_invalid(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_invalid(hashes);
 		removed.extend(self.future.remove(hashes).into_iter().map(Arc::new));
+		for tx in &removed {
+			self.forget_sender(&tx.sender, &tx.hash);
+		}
 		removed
 	}
 
@@ -255,6 +287,9 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 			// and actually prune transactions in ready queue
 			pruned.append(&mut self.ready.prune_tags(tag));
 		}
+		for tx in &pruned {
+			self.forget_sender(&tx.sender, &tx.hash);
+		}
 
 		let mut promoted = vec![];
 		let mut failed = vec![];
@@ -276,6 +311,80 @@ impl<Hash: hash::Hash + Member, Ex: ::std::fmt::Debug> BasePool<Hash, Ex> {
 		}
 	}
 
+	/// Removes the lowest priority transactions until both queues fit into given limits.
+	///
+	/// The transactions of `sender`, if given, are first limited to `sender_limit`,
+	/// evicting the lowest priority ones of that sender. Only the sender whose transactions
+	/// were just imported needs to be checked, as the others can't exceed the limit.
+	///
+	/// NOTE removing a ready transaction also removes all transactions that depend on it.
+	/// Returns a list of removed transactions.
+	pub fn enforce_limits(
+		&mut self,
+		ready: &Limit,
+		future: &Limit,
+		sender_limit: &Limit,
+		sender: Option<&[u8]>,
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut removed = vec![];
+
+		if let Some(sender) = sender {
+			loop {
+				let worst = match self.senders.get(sender) {
+					Some(txs) if txs.len() > sender_limit.count => txs.iter()
+						// on equal priority prefer evicting a future transaction.
+						.min_by_key(|&(hash, priority)| (*priority, self.ready.contains(hash)))
+						.map(|(hash, _)| hash.clone()),
+					_ => None,
+				};
+
+				match worst {
+					Some(worst) => removed.append(&mut self.remove_invalid(&[worst])),
+					None => break,
+				}
+			}
+		}
+
+		while self.ready.len() > ready.count {
+			match self.ready.worst() {
+				Some(worst) => removed.append(&mut self.remove_invalid(&[worst])),
+				None => break,
+			}
+		}
+
+		while self.future.len() > future.count {
+			match self.future.worst() {
+				Some(worst) => removed.append(&mut self.remove_invalid(&[worst])),
+				None => break,
+			}
+		}
+
+		removed
+	}
+
+	fn note_sender(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(ref sender) = tx.sender {
+			self.senders.entry(sender.clone())
+				.or_insert_with(HashMap::new)
+				.insert(tx.hash.clone(), tx.priority);
+		}
+	}
+
+	fn forget_sender(&mut self, sender: &Option<Vec<u8>>, hash: &Hash) {
+		if let Some(ref sender) = *sender {
+			let is_empty = match self.senders.get_mut(sender) {
+				Some(txs) => {
+					txs.remove(hash);
+					txs.is_empty()
+				},
+				None => false,
+			};
+			if is_empty {
+				self.senders.remove(sender);
+			}
+		}
+	}
+
 	/// Get pool status.
 	pub fn status(&self) -> Status {
 		Status {
@@ -316,6 +425,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -323,6 +433,113 @@ mod tests {
 		assert_eq!(pool.ready.len(), 1);
 	}
 
+	#[test]
+	fn should_evict_lowest_priority_transactions_over_the_limit() {
+		// given
+		let mut pool = pool();
+		for (hash, priority) in vec![(1u64, 5u64), (2, 1), (3, 10)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				hash,
+				priority,
+				valid_till: 64u64,
+				requires: vec![],
+				provides: vec![vec![hash as u8]],
+				sender: None,
+			}).unwrap();
+		}
+		pool.import(Transaction {
+			data: vec![4u8],
+			hash: 4,
+			priority: 50u64,
+			valid_till: 64u64,
+			requires: vec![vec![0]],
+			provides: vec![],
+			sender: None,
+		}).unwrap();
+
+		// when
+		let removed = pool.enforce_limits(&Limit { count: 2 }, &Limit { count: 0 }, &Limit { count: 8 }, None);
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![2, 4]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3, 1]);
+		assert_eq!(pool.futures().count(), 0);
+	}
+
+	#[test]
+	fn should_evict_lowest_priority_transactions_of_a_sender_over_the_limit() {
+		// given
+		let mut pool = pool();
+		for (sender, hash, priority) in vec![(0u8, 1u64, 5u64), (0, 2, 1), (0, 3, 10), (1, 4, 0)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				hash,
+				priority,
+				valid_till: 64u64,
+				requires: vec![],
+				provides: vec![vec![hash as u8]],
+				sender: Some(vec![sender]),
+			}).unwrap();
+		}
+		pool.import(Transaction {
+			data: vec![5u8],
+			hash: 5,
+			priority: 1u64,
+			valid_till: 64u64,
+			requires: vec![vec![0]],
+			provides: vec![],
+			sender: Some(vec![0]),
+		}).unwrap();
+		let limit = Limit { count: 8 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit, &Limit { count: 2 }, Some(&[0][..]));
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![2, 5]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3, 1, 4]);
+		assert_eq!(pool.futures().count(), 0);
+		assert_eq!(pool.senders[&vec![0u8]].len(), 2);
+		assert_eq!(pool.senders[&vec![1u8]].len(), 1);
+	}
+
+	#[test]
+	fn should_count_future_transactions_of_a_sender() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			data: vec![2u8],
+			hash: 2,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires: vec![vec![1]],
+			provides: vec![vec![2]],
+			sender: Some(vec![0]),
+		}).unwrap();
+		assert_eq!(pool.futures_of(&[0]), 1);
+		assert_eq!(pool.futures_of(&[1]), 0);
+
+		// when
+		pool.import(Transaction {
+			data: vec![1u8],
+			hash: 1,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires: vec![],
+			provides: vec![vec![1]],
+			sender: Some(vec![0]),
+		}).unwrap();
+
+		// then
+		assert_eq!(pool.ready().count(), 2);
+		assert_eq!(pool.futures_of(&[0]), 0);
+	}
+
 	#[test]
 	fn should_not_import_same_transaction_twice() {
 		// given
@@ -336,6 +553,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -344,6 +562,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap_err();
 
 		// then
@@ -365,6 +584,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -375,6 +595,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![0]],
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -395,6 +616,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -403,6 +625,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -411,6 +634,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -419,6 +643,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -430,6 +655,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -460,6 +686,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -468,6 +695,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -480,6 +708,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -498,6 +727,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![0]],
+			sender: None,
 		}).unwrap();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), Some(4));
@@ -525,6 +755,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -533,6 +764,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -545,6 +777,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
+			sender: None,
 		}).unwrap();
 
 		// then
@@ -563,6 +796,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![0]],
+			sender: None,
 		}).unwrap_err();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), None);
@@ -585,6 +819,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -593,6 +828,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -601,6 +837,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -609,6 +846,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -617,6 +855,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
+			sender: None,
 		}).unwrap();
 		// future
 		pool.import(Transaction {
@@ -626,6 +865,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![11]],
 			provides: vec![],
+			sender: None,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 5);
 		assert_eq!(pool.future.len(), 1);
@@ -651,6 +891,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![100]],
+			sender: None,
 		}).unwrap();
 		// ready
 		pool.import(Transaction {
@@ -660,6 +901,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![],
 			provides: vec![vec![1]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -668,6 +910,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![3]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -676,6 +919,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
+			sender: None,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -684,6 +928,7 @@ mod tests {
 			valid_till: 64u64,
 			requires: vec![vec![3], vec![2]],
 			provides: vec![vec![4]],
+			sender: None,
 		}).unwrap();
 
 		assert_eq!(pool.ready().count(), 4);
@@ -45,6 +45,16 @@ error_chain! {
 			description("The priority is too low to replace transactions already in the pool."),
 			display("Too low priority ({} > {})", old, new)
 		}
+		/// Transaction was dropped right after it got imported, because of the pool limits.
+		ImmediatelyDropped {
+			description("Transaction couldn't enter the pool because of the limit."),
+			display("Immediately Dropped"),
+		}
+		/// Too many transactions of the same sender are waiting in the Future queue already.
+		TooManyWaiting {
+			description("Too many transactions of the same sender are waiting already."),
+			display("Too Many Waiting"),
+		}
 		/// Deps cycle detected and we couldn't import transaction.
 		CycleDetected {
 			description("Transaction was not imported because of detected cycle."),
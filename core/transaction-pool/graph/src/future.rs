@@ -73,6 +73,8 @@ pub struct FutureTransactions<Hash: hash::Hash + Eq, Ex> {
 	wanted_tags: HashMap<Tag, HashSet<Hash>>,
 	/// Transactions waiting for a particular other transaction
 	waiting: HashMap<Hash, WaitingTransaction<Hash, Ex>>,
+	/// Number of waiting transactions of every known sender
+	senders: HashMap<Vec<u8>, usize>,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for FutureTransactions<Hash, Ex> {
@@ -80,6 +82,7 @@ impl<Hash: hash::Hash + Eq, Ex> Default for FutureTransactions<Hash, Ex> {
 		FutureTransactions {
 			wanted_tags: Default::default(),
 			waiting: Default::default(),
+			senders: Default::default(),
 		}
 	}
 }
@@ -108,6 +111,10 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 			entry.insert(tx.transaction.hash.clone());
 		}
 
+		if let Some(ref sender) = tx.transaction.sender {
+			*self.senders.entry(sender.clone()).or_insert(0) += 1;
+		}
+
 		// Add the transaction to a by-hash waiting map
 		self.waiting.insert(tx.transaction.hash.clone(), tx);
 	}
@@ -136,6 +143,7 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 
 					if is_ready {
 						let tx = self.waiting.remove(&hash).expect(WAITING_PROOF);
+						self.forget_sender(&tx.transaction.sender);
 						became_ready.push(tx);
 					}
 				}
@@ -162,6 +170,7 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 						self.wanted_tags.remove(&tag);
 					}
 				}
+				self.forget_sender(&waiting_tx.transaction.sender);
 				// add to result
 				removed.push(waiting_tx.transaction)
 			}
@@ -174,8 +183,35 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.values().map(|waiting| &waiting.transaction)
 	}
 
+	/// Returns hash of the transaction with the lowest priority in the Future queue.
+	pub fn worst(&self) -> Option<Hash> {
+		self.waiting.values()
+			.min_by_key(|waiting| waiting.transaction.priority)
+			.map(|waiting| waiting.transaction.hash.clone())
+	}
+
 	/// Returns number of transactions in the Future queue.
 	pub fn len(&self) -> usize {
 		self.waiting.len()
 	}
+
+	/// Returns number of transactions of given sender in the Future queue.
+	pub fn len_of(&self, sender: &[u8]) -> usize {
+		self.senders.get(sender).cloned().unwrap_or(0)
+	}
+
+	fn forget_sender(&mut self, sender: &Option<Vec<u8>>) {
+		if let Some(ref sender) = *sender {
+			let is_empty = match self.senders.get_mut(sender) {
+				Some(count) => {
+					*count -= 1;
+					*count == 0
+				},
+				None => false,
+			};
+			if is_empty {
+				self.senders.remove(sender);
+			}
+		}
+	}
 }
@@ -16,26 +16,30 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::HashMap,
+	collections::{BTreeMap, HashMap},
 	hash,
+	mem,
 };
 use watcher;
 use sr_primitives::traits;
 
 /// Extrinsic pool default listener.
-pub struct Listener<H: hash::Hash + Eq, H2> {
-	watchers: HashMap<H, watcher::Sender<H, H2>>
+pub struct Listener<H: hash::Hash + Eq, H2: hash::Hash + Eq> {
+	watchers: HashMap<H, watcher::Sender<H, H2>>,
+	/// Extrinsics waiting for the finality of the blocks they were included in, by block number.
+	finality_watchers: BTreeMap<u64, HashMap<H2, Vec<H>>>,
 }
 
-impl<H: hash::Hash + Eq, H2> Default for Listener<H, H2> {
+impl<H: hash::Hash + Eq, H2: hash::Hash + Eq> Default for Listener<H, H2> {
 	fn default() -> Self {
 		Listener {
 			watchers: Default::default(),
+			finality_watchers: Default::default(),
 		}
 	}
 }
 
-impl<H: hash::Hash + traits::Member, H2: Clone> Listener<H, H2> {
+impl<H: hash::Hash + traits::Member, H2: hash::Hash + Eq + Clone> Listener<H, H2> {
 	fn fire<F>(&mut self, hash: &H, fun: F) where F: FnOnce(&mut watcher::Sender<H, H2>) {
 		let clean = if let Some(h) = self.watchers.get_mut(hash) {
 			fun(h);
@@ -85,12 +89,105 @@ impl<H: hash::Hash + traits::Member, H2: Clone> Listener<H, H2> {
 
 	/// Transaction was removed as invalid.
 	pub fn invalid(&mut self, tx: &H) {
-		warn!(target: "transaction-pool", "Extrinsic invalid: {:?}", tx);
+		warn!(target: "txpool", "Extrinsic invalid: {:?}", tx);
 		self.fire(tx, |watcher| watcher.invalid());
 	}
 
-	/// Transaction was pruned from the pool.
-	pub fn pruned(&mut self, header_hash: H2, tx: &H) {
-		self.fire(tx, |watcher| watcher.finalised(header_hash))
+	/// Transaction was pruned from the pool because it was included in a block.
+	///
+	/// Watchers are kept until the block is finalised, or a block at that height is.
+	pub fn pruned(&mut self, header_hash: H2, header_number: u64, tx: &H) {
+		if !self.watchers.contains_key(tx) {
+			return;
+		}
+
+		self.fire(tx, |watcher| watcher.in_block(header_hash.clone()));
+		self.finality_watchers.entry(header_number)
+			.or_insert_with(HashMap::new)
+			.entry(header_hash)
+			.or_insert_with(Vec::new)
+			.push(tx.clone());
+	}
+
+	/// Block with given hash and number has been retracted by a reorganisation.
+	///
+	/// Watchers of its extrinsics stop waiting for its finality.
+	pub fn retracted(&mut self, header_hash: &H2, header_number: u64) {
+		let (hashes, is_empty) = match self.finality_watchers.get_mut(&header_number) {
+			Some(blocks) => (blocks.remove(header_hash), blocks.is_empty()),
+			None => (None, false),
+		};
+		if is_empty {
+			self.finality_watchers.remove(&header_number);
+		}
+
+		for hash in hashes.unwrap_or_default() {
+			self.fire(&hash, |watcher| watcher.retracted(header_hash.clone()))
+		}
+	}
+
+	/// Block with given hash and number has been finalised.
+	///
+	/// Watchers of extrinsics in blocks at or below that height stop waiting. Blocks below it
+	/// are finalised if `canonical` returns them for their number, and retracted otherwise:
+	/// they may be ancestors finalised without a notification, or blocks of abandoned forks.
+	pub fn finalised(&mut self, header_hash: &H2, header_number: u64, canonical: impl Fn(u64) -> Option<H2>) {
+		let above = self.finality_watchers.split_off(&header_number.saturating_add(1));
+		let below = mem::replace(&mut self.finality_watchers, above);
+
+		for (number, blocks) in below {
+			let canonical_hash = if number == header_number {
+				Some(header_hash.clone())
+			} else {
+				canonical(number)
+			};
+
+			for (block_hash, hashes) in blocks {
+				let is_finalised = canonical_hash.as_ref() == Some(&block_hash);
+				for hash in hashes {
+					if is_finalised {
+						self.fire(&hash, |watcher| watcher.finalised(block_hash.clone()))
+					} else {
+						self.fire(&hash, |watcher| watcher.retracted(block_hash.clone()))
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::Stream;
+	use watcher::Status;
+
+	#[test]
+	fn should_stop_waiting_for_blocks_at_or_below_finalised_height() {
+		// given
+		let mut listener = Listener::<u64, u64>::default();
+		let in_fork = listener.create_watcher(1);
+		let in_skipped = listener.create_watcher(2);
+		let in_finalised = listener.create_watcher(3);
+		listener.pruned(11, 1, &1);
+		listener.pruned(2, 2, &2);
+		listener.pruned(3, 3, &3);
+
+		// when
+		listener.finalised(&3, 3, |number| Some(number));
+
+		// then
+		assert!(listener.finality_watchers.is_empty());
+		let mut stream = in_fork.into_stream().wait();
+		assert_eq!(stream.next(), Some(Ok(Status::InBlock(11))));
+		assert_eq!(stream.next(), Some(Ok(Status::Retracted(11))));
+		let mut stream = in_skipped.into_stream().wait();
+		assert_eq!(stream.next(), Some(Ok(Status::InBlock(2))));
+		assert_eq!(stream.next(), Some(Ok(Status::Finalised(2))));
+		assert_eq!(stream.next(), None);
+		let mut stream = in_finalised.into_stream().wait();
+		assert_eq!(stream.next(), Some(Ok(Status::InBlock(3))));
+		assert_eq!(stream.next(), Some(Ok(Status::Finalised(3))));
+		assert_eq!(stream.next(), None);
 	}
 }
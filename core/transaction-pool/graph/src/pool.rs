@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	hash,
 	sync::Arc,
 	time,
@@ -31,7 +31,7 @@ use futures::sync::mpsc;
 use parking_lot::{Mutex, RwLock};
 use sr_primitives::{
 	generic::BlockId,
-	traits::{self, As},
+	traits::{self, As, Extrinsic},
 	transaction_validity::{TransactionValidity, TransactionTag as Tag},
 };
 
@@ -72,12 +72,56 @@ pub trait ChainApi: Send + Sync {
 }
 
 /// Pool configuration options.
-#[derive(Debug, Clone, Default)]
-pub struct Options;
+#[derive(Debug, Clone)]
+pub struct Options {
+	/// Ready queue limits.
+	pub ready: base::Limit,
+	/// Future queue limits.
+	pub future: base::Limit,
+	/// Limits of the transactions of a single sender, in both queues.
+	///
+	/// Senders are told by `Extrinsic::sender`, the lowest priority transactions
+	/// of a sender are evicted first.
+	pub sender: base::Limit,
+	/// How many transactions of a single sender may wait in the Future queue.
+	///
+	/// Senders are told by `Extrinsic::sender`, transactions of unknown senders
+	/// are only bound by the Future queue limits.
+	pub future_per_sender: usize,
+	/// For how many blocks a transaction may stay in the Future queue.
+	pub future_lifetime: u64,
+	/// How long the transactions are banned for.
+	pub ban_time: time::Duration,
+	/// How long transactions which failed validation are rejected without validating them again.
+	///
+	/// Those which still fail validation after that are banned for `ban_time`.
+	pub invalid_time: time::Duration,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Options {
+			ready: base::Limit {
+				count: 8192,
+			},
+			future: base::Limit {
+				count: 512,
+			},
+			sender: base::Limit {
+				count: 512,
+			},
+			future_per_sender: 64,
+			future_lifetime: 64,
+			ban_time: time::Duration::from_secs(60 * 30),
+			invalid_time: time::Duration::from_secs(60),
+		}
+	}
+}
 
 /// Extrinsics pool.
 pub struct Pool<B: ChainApi> {
 	api: B,
+	options: Options,
 	listener: RwLock<Listener<ExHash<B>, BlockHash<B>>>,
 	pool: RwLock<base::BasePool<
 		ExHash<B>,
@@ -85,6 +129,8 @@ pub struct Pool<B: ChainApi> {
 	>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<()>>>,
 	rotator: PoolRotator<ExHash<B>>,
+	/// Block numbers at which the transactions in the Future queue were imported.
+	future_imported_at: RwLock<HashMap<ExHash<B>, u64>>,
 }
 
 impl<B: ChainApi> Pool<B> {
@@ -103,11 +149,16 @@ impl<B: ChainApi> Pool<B> {
 				if self.rotator.is_banned(&hash) {
 					bail!(error::Error::from(error::ErrorKind::TemporarilyBanned))
 				}
+				// reject recently invalid transactions without validating them again
+				if self.rotator.is_invalid(&time::Instant::now(), &hash) {
+					bail!(error::Error::from(error::ErrorKind::InvalidTransaction))
+				}
 
 				match self.api.validate_transaction(at, &xt)? {
 					TransactionValidity::Valid { priority, requires, provides, longevity } => {
 						Ok(base::Transaction {
-							data:  xt,
+							sender: xt.sender(),
+							data: xt,
 							hash,
 							priority,
 							requires,
@@ -116,6 +167,7 @@ impl<B: ChainApi> Pool<B> {
 						})
 					},
 					TransactionValidity::Invalid => {
+						self.rotator.note_invalid(&time::Instant::now(), &hash);
 						bail!(error::Error::from(error::ErrorKind::InvalidTransaction))
 					},
 					TransactionValidity::Unknown => {
@@ -125,15 +177,44 @@ impl<B: ChainApi> Pool<B> {
 				}
 			})
 			.map(|tx| {
-				let imported = self.pool.write().import(tx?)?;
+				let (imported, removed) = {
+					let tx = tx?;
+					let sender = tx.sender.clone();
+					let mut pool = self.pool.write();
+					let imported = pool.import(tx)?;
+					if let (&base::Imported::Future { ref hash }, Some(ref sender)) = (&imported, &sender) {
+						if pool.futures_of(sender) > self.options.future_per_sender {
+							pool.remove_invalid(&[hash.clone()]);
+							bail!(error::Error::from(error::ErrorKind::TooManyWaiting))
+						}
+					}
+					let removed = pool.enforce_limits(
+						&self.options.ready,
+						&self.options.future,
+						&self.options.sender,
+						sender.as_ref().map(|sender| &sender[..]),
+					);
+					(imported, removed)
+				};
+
+				if let base::Imported::Future { ref hash } = imported {
+					self.future_imported_at.write().insert(hash.clone(), block_number.as_());
+				}
 
 				if let base::Imported::Ready { .. } = imported {
 					self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(()).is_ok());
 				}
 
+				let hash = imported.hash().clone();
 				let mut listener = self.listener.write();
 				fire_events(&mut *listener, &imported);
-				Ok(imported.hash().clone())
+				for tx in &removed {
+					listener.dropped(&tx.hash, None);
+				}
+				if removed.iter().any(|tx| tx.hash == hash) {
+					bail!(error::Error::from(error::ErrorKind::ImmediatelyDropped))
+				}
+				Ok(hash)
 			})
 			.collect())
 	}
@@ -177,19 +258,56 @@ impl<B: ChainApi> Pool<B> {
 		{
 			let header_hash = self.api.block_id_to_hash(at)?
 				.ok_or_else(|| error::ErrorKind::Msg(format!("Invalid block id: {:?}", at)).into())?;
+			let header_number = self.api.block_id_to_number(at)?
+				.ok_or_else(|| error::ErrorKind::Msg(format!("Invalid block id: {:?}", at)).into())?
+				.as_();
 			let mut listener = self.listener.write();
 			for h in hashes {
-				listener.pruned(header_hash, &h)
+				listener.pruned(header_hash, header_number, &h)
 			}
 		}
+		// future transactions might have become invalid for good
+		self.revalidate_future(at);
 		// clear old transactions
 		self.clear_stale(at)?;
 		Ok(())
 	}
 
+	/// Re-validates transactions in the future queue and removes the ones that are not valid anymore.
+	///
+	/// Transactions with unknown validity, or which failed to validate, stay in the queue.
+	fn revalidate_future(&self, at: &BlockId<B::Block>) {
+		let futures = self.pool.read().futures()
+			.map(|tx| (tx.hash.clone(), tx.data.clone()))
+			.collect::<Vec<_>>();
+
+		let now = time::Instant::now();
+		let mut invalid = Vec::new();
+		for (hash, xt) in futures {
+			match self.api.validate_transaction(at, &xt) {
+				Ok(TransactionValidity::Invalid) => {
+					self.rotator.note_invalid(&now, &hash);
+					invalid.push(hash);
+				},
+				Ok(_) => {},
+				Err(e) => debug!(target: "txpool", "[{:?}] Error re-validating future transaction: {:?}", hash, e),
+			}
+		}
+
+		if !invalid.is_empty() {
+			debug!(target: "txpool", "Removing future transactions that became invalid: {:?}", invalid);
+			let removed = self.pool.write().remove_invalid(&invalid);
+			let mut listener = self.listener.write();
+			for tx in &removed {
+				listener.invalid(&tx.hash);
+			}
+		}
+	}
+
 	/// Removes stale transactions from the pool.
 	///
-	/// Stale transactions are transaction beyond their longevity period.
+	/// Stale transactions are transaction beyond their longevity period
+	/// and transactions that have been in the Future queue for longer than `future_lifetime` blocks.
 	/// Note this function does not remove transactions that are already included in the chain.
 	/// See `prune_tags` ifyou want this.
 	pub fn clear_stale(&self, at: &BlockId<B::Block>) -> Result<(), B::Error> {
@@ -205,10 +323,19 @@ impl<B: ChainApi> Pool<B> {
 		};
 		let futures_to_remove: Vec<ExHash<B>> = {
 			let p = self.pool.read();
+			let mut future_imported_at = self.future_imported_at.write();
+			// forget transactions that have left the future queue.
+			let in_future = p.futures().map(|tx| tx.hash.clone()).collect::<HashSet<_>>();
+			future_imported_at.retain(|hash, _| in_future.contains(hash));
 			let mut hashes = Vec::new();
 			for tx in p.futures() {
+				let imported_at = *future_imported_at.entry(tx.hash.clone()).or_insert(block_number);
 				if self.rotator.ban_if_stale(&now, block_number, &tx) {
 					hashes.push(tx.hash.clone());
+				} else if block_number.saturating_sub(imported_at) >= self.options.future_lifetime {
+					debug!(target: "txpool", "[{:?}] Expelling from the future queue after {} blocks", tx.hash, self.options.future_lifetime);
+					self.rotator.ban(&now, &[tx.hash.clone()]);
+					hashes.push(tx.hash.clone());
 				}
 			}
 			hashes
@@ -225,14 +352,15 @@ impl<B: ChainApi> Pool<B> {
 
 impl<B: ChainApi> Pool<B> {
 	/// Create a new transaction pool.
-	/// TODO [ToDr] Options
-	pub fn new(_options: Options, api: B) -> Self {
+	pub fn new(options: Options, api: B) -> Self {
 		Pool {
 			api,
 			listener: Default::default(),
 			pool: Default::default(),
 			import_notification_sinks: Default::default(),
-			rotator: Default::default(),
+			rotator: PoolRotator::new(options.ban_time, options.invalid_time),
+			future_imported_at: Default::default(),
+			options,
 		}
 	}
 
@@ -251,6 +379,23 @@ impl<B: ChainApi> Pool<B> {
 		}
 	}
 
+	/// Invoked when a block is finalised.
+	///
+	/// Notifies watchers of extrinsics included in that block, and in any other block
+	/// at or below its height.
+	pub fn on_block_finalized(&self, block_hash: BlockHash<B>, block_number: NumberFor<B>) {
+		self.listener.write().finalised(&block_hash, block_number.as_(), |number| {
+			self.api.block_id_to_hash(&BlockId::Number(As::sa(number))).ok().and_then(|hash| hash)
+		});
+	}
+
+	/// Invoked when a block is retracted by a reorganisation.
+	///
+	/// Notifies watchers of extrinsics included in that block.
+	pub fn on_block_retracted(&self, block_hash: BlockHash<B>, block_number: NumberFor<B>) {
+		self.listener.write().retracted(&block_hash, block_number.as_());
+	}
+
 	/// Remove from the pool.
 	pub fn remove_invalid(&self, hashes: &[ExHash<B>]) -> Vec<TransactionFor<B>> {
 		// temporarily ban invalid transactions
@@ -412,6 +557,92 @@ mod tests {
 		assert_matches!(res.unwrap_err().kind(), error::ErrorKind::TemporarilyBanned);
 	}
 
+	#[test]
+	fn should_drop_transactions_over_the_limit() {
+		// given
+		let pool = Pool::new(Options {
+			ready: base::Limit { count: 1 },
+			future: base::Limit { count: 0 },
+			..Default::default()
+		}, TestApi::default());
+
+		// when
+		pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 0,
+		})).unwrap();
+		let res = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 3,
+		}));
+
+		// then
+		assert_matches!(res.unwrap_err().kind(), error::ErrorKind::ImmediatelyDropped);
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(pool.status().future, 0);
+	}
+
+	#[test]
+	fn should_remove_future_transactions_that_became_invalid() {
+		// given
+		let pool = pool();
+		let xt = uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 3,
+		});
+		pool.submit_one(&BlockId::Number(0), xt.clone()).unwrap();
+		assert_eq!(pool.status().future, 1);
+
+		// when
+		pool.prune_tags(&BlockId::Number(4), vec![]).unwrap();
+
+		// then
+		assert_eq!(pool.status().future, 0);
+		// shortly rejected without validation, but not banned
+		assert_matches!(pool.submit_one(&BlockId::Number(0), xt.clone()).unwrap_err().kind(), error::ErrorKind::InvalidTransaction);
+		assert_matches!(pool.submit_one(&BlockId::Number(0), xt.clone()).unwrap_err().kind(), error::ErrorKind::InvalidTransaction);
+		assert!(!pool.rotator.is_banned(&pool.hash_of(&xt)));
+	}
+
+	#[test]
+	fn should_only_ban_transactions_invalid_again_after_revalidation() {
+		// given
+		let pool = Pool::new(Options {
+			invalid_time: time::Duration::from_millis(0),
+			..Default::default()
+		}, TestApi::default());
+		let xt1 = uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 0,
+		});
+		let xt2 = uxt(Transfer {
+			from: 2.into(),
+			to: 1.into(),
+			amount: 5,
+			nonce: 0,
+		});
+
+		// when
+		assert_matches!(pool.submit_one(&BlockId::Number(1), xt1.clone()).unwrap_err().kind(), error::ErrorKind::InvalidTransaction);
+		assert_matches!(pool.submit_one(&BlockId::Number(1), xt2.clone()).unwrap_err().kind(), error::ErrorKind::InvalidTransaction);
+
+		// then
+		// a transaction which became valid is imported
+		pool.submit_one(&BlockId::Number(0), xt1).unwrap();
+		assert_eq!(pool.status().ready, 1);
+		// one which is still invalid once validated again is banned
+		assert_matches!(pool.submit_one(&BlockId::Number(1), xt2.clone()).unwrap_err().kind(), error::ErrorKind::InvalidTransaction);
+		assert_matches!(pool.submit_one(&BlockId::Number(0), xt2).unwrap_err().kind(), error::ErrorKind::TemporarilyBanned);
+	}
+
 	#[test]
 	fn should_notify_about_pool_events() {
 		let stream = {
@@ -488,6 +719,75 @@ mod tests {
 		assert!(pool.rotator.is_banned(&hash3));
 	}
 
+	#[test]
+	fn should_expel_future_transactions_after_their_lifetime() {
+		// given
+		let pool = Pool::new(Options {
+			future_lifetime: 2,
+			..Default::default()
+		}, TestApi::default());
+		let hash = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 10,
+		})).unwrap();
+
+		// when
+		pool.clear_stale(&BlockId::Number(1)).unwrap();
+		assert_eq!(pool.status().future, 1);
+		pool.clear_stale(&BlockId::Number(2)).unwrap();
+
+		// then
+		assert_eq!(pool.status().future, 0);
+		assert!(pool.rotator.is_banned(&hash));
+	}
+
+	#[test]
+	fn should_reject_future_transactions_over_the_per_sender_limit() {
+		// given
+		let pool = Pool::new(Options {
+			future_per_sender: 2,
+			..Default::default()
+		}, TestApi::default());
+		// a single sender with a nonce gap
+		for nonce in 2..4 {
+			pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: 1.into(),
+				to: 2.into(),
+				amount: 5,
+				nonce,
+			})).unwrap();
+		}
+		assert_eq!(pool.status().future, 2);
+
+		// when
+		let res = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 5,
+		}));
+		let other = pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 2.into(),
+			to: 1.into(),
+			amount: 5,
+			nonce: 5,
+		}));
+
+		// then
+		assert_matches!(res.unwrap_err().kind(), error::ErrorKind::TooManyWaiting);
+		assert!(other.is_ok());
+		assert_eq!(pool.status().future, 3);
+		// transactions going to the Ready queue are not limited
+		pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: 1.into(),
+			to: 2.into(),
+			amount: 5,
+			nonce: 0,
+		})).unwrap();
+	}
+
 	mod listener {
 		use super::*;
 
@@ -512,10 +812,64 @@ mod tests {
 			// then
 			let mut stream = watcher.into_stream().wait();
 			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Ready)));
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::InBlock(2.into()))));
+
+			// when
+			pool.on_block_finalized(2.into(), 2);
+
+			// then
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Finalised(2.into()))));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_trigger_finalised_when_a_descendant_is_finalised() {
+			// given
+			let pool = pool();
+			let watcher = pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: 1.into(),
+				to: 2.into(),
+				amount: 5,
+				nonce: 0,
+			})).unwrap();
+			pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]]).unwrap();
+
+			// when
+			pool.on_block_finalized(4.into(), 4);
+
+			// then
+			let mut stream = watcher.into_stream().wait();
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Ready)));
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::InBlock(2.into()))));
 			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Finalised(2.into()))));
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_trigger_retracted_and_stop_waiting_for_finality() {
+			// given
+			let pool = pool();
+			let watcher = pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: 1.into(),
+				to: 2.into(),
+				amount: 5,
+				nonce: 0,
+			})).unwrap();
+			pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]]).unwrap();
+
+			// when
+			pool.on_block_retracted(2.into(), 2);
+			pool.on_block_finalized(2.into(), 2);
+			drop(pool);
+
+			// then
+			let mut stream = watcher.into_stream().wait();
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Ready)));
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::InBlock(2.into()))));
+			assert_eq!(stream.next(), Some(Ok(::watcher::Status::Retracted(2.into()))));
+			assert_eq!(stream.next(), None);
+		}
+
 		#[test]
 		fn should_trigger_future_and_ready_after_promoted() {
 			// given
@@ -385,6 +385,13 @@ impl<Hash: hash::Hash + Member, Ex> ReadyTransactions<Hash, Ex> {
 		}
 	}
 
+	/// Returns hash of the worst transaction in this queue, i.e. the one with the lowest priority.
+	pub fn worst(&self) -> Option<Hash> {
+		let ready = self.ready.read();
+		let worst = ready.values().map(|tx| &tx.transaction).min().map(|tx| tx.transaction.hash.clone());
+		worst
+	}
+
 	/// Returns number of transactions in this queue.
 	pub fn len(&self) -> usize {
 		self.ready.read().len()
@@ -470,6 +477,7 @@ mod tests {
 			valid_till: 2,
 			requires: vec![vec![1], vec![2]],
 			provides: vec![vec![3], vec![4]],
+			sender: None,
 		}
 	}
 
@@ -528,6 +536,7 @@ mod tests {
 			valid_till: u64::max_value(),	// use the max_value() here for testing.
 			requires: vec![tx1.provides[0].clone()],
 			provides: vec![],
+			sender: None,
 		};
 
 		// when
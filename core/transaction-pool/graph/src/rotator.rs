@@ -18,6 +18,8 @@
 //!
 //! Keeps only recent extrinsic and discard the ones kept for a significant amount of time.
 //! Discarded extrinsics are banned so that they don't get re-imported again.
+//! Also remembers extrinsics that failed validation, so that they are shortly rejected without
+//! re-validation and banned when they fail validation again later.
 
 use std::{
 	collections::HashMap,
@@ -38,15 +40,23 @@ const EXPECTED_SIZE: usize = 2048;
 pub struct PoolRotator<Hash> {
 	/// How long the extrinsic is banned for.
 	ban_time: Duration,
+	/// How long an extrinsic which failed validation is rejected without validating it again.
+	invalid_time: Duration,
 	/// Currently banned extrinsics.
 	banned_until: RwLock<HashMap<Hash, Instant>>,
+	/// The last time extrinsics failed validation, remembered for `ban_time`.
+	invalid_at: RwLock<HashMap<Hash, Instant>>,
 }
 
-impl<Hash: hash::Hash + Eq> Default for PoolRotator<Hash> {
-	fn default() -> Self {
+impl<Hash: hash::Hash + Eq> PoolRotator<Hash> {
+	/// Create a new rotator banning extrinsics for given amount of time and rejecting those which
+	/// failed validation for `invalid_time`.
+	pub fn new(ban_time: Duration, invalid_time: Duration) -> Self {
 		PoolRotator {
-			ban_time: Duration::from_secs(60 * 30),
+			ban_time,
+			invalid_time,
 			banned_until: Default::default(),
+			invalid_at: Default::default(),
 		}
 	}
 }
@@ -57,6 +67,34 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 		self.banned_until.read().contains_key(hash)
 	}
 
+	/// Returns `true` if extrinsic failed validation less than `invalid_time` ago.
+	pub fn is_invalid(&self, now: &Instant, hash: &Hash) -> bool {
+		self.invalid_at.read().get(hash).map_or(false, |at| *at + self.invalid_time > *now)
+	}
+
+	/// Remembers that extrinsic failed validation.
+	///
+	/// Extrinsics which fail validation again, once they are not rejected by `is_invalid` anymore,
+	/// are banned: they are unlikely to become valid.
+	pub fn note_invalid(&self, now: &Instant, hash: &Hash) {
+		let is_repeated = {
+			let mut invalid = self.invalid_at.write();
+			let is_repeated = invalid.insert(hash.clone(), *now).is_some();
+			if invalid.len() > 2 * EXPECTED_SIZE {
+				while invalid.len() > EXPECTED_SIZE {
+					if let Some(key) = invalid.keys().next().cloned() {
+						invalid.remove(&key);
+					}
+				}
+			}
+			is_repeated
+		};
+
+		if is_repeated {
+			self.ban(now, &[hash.clone()]);
+		}
+	}
+
 	/// Bans given set of hashes.
 	pub fn ban(&self, now: &Instant, hashes: &[Hash]) {
 		let mut banned = self.banned_until.write();
@@ -90,8 +128,11 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 	/// Removes timed bans.
 	pub fn clear_timeouts(&self, now: &Instant) {
 		let mut banned = self.banned_until.write();
-
 		banned.retain(|_, &mut v| v >= *now);
+
+		let mut invalid = self.invalid_at.write();
+		let ban_time = self.ban_time;
+		invalid.retain(|_, &mut v| v + ban_time >= *now);
 	}
 }
 
@@ -103,10 +144,7 @@ mod tests {
 	type Ex = ();
 
 	fn rotator() -> PoolRotator<Hash> {
-		PoolRotator {
-			ban_time: Duration::from_millis(10),
-			..Default::default()
-		}
+		PoolRotator::new(Duration::from_millis(10), Duration::from_millis(1))
 	}
 
 	fn tx() -> (Hash, Transaction<Hash, Ex>) {
@@ -118,6 +156,7 @@ mod tests {
 			valid_till: 1,
 			requires: vec![],
 			provides: vec![],
+			sender: None,
 		};
 
 		(hash, tx)
@@ -170,6 +209,35 @@ mod tests {
 		assert!(!rotator.is_banned(&hash));
 	}
 
+	#[test]
+	fn should_ban_extrinsic_invalid_again_after_revalidation() {
+		// given
+		let (hash, _) = tx();
+		let rotator = rotator();
+		let now = Instant::now();
+
+		// when
+		rotator.note_invalid(&now, &hash);
+
+		// then
+		assert!(rotator.is_invalid(&now, &hash));
+		assert!(!rotator.is_banned(&hash));
+
+		// it may be validated again shortly, and is banned if it is still invalid
+		let later = now + rotator.invalid_time;
+		assert!(!rotator.is_invalid(&later, &hash));
+		assert!(!rotator.is_banned(&hash));
+		rotator.note_invalid(&later, &hash);
+		assert!(rotator.is_banned(&hash));
+
+		// and both expire
+		let future = later + rotator.ban_time + rotator.ban_time;
+		rotator.clear_timeouts(&future);
+		assert!(!rotator.is_invalid(&future, &hash));
+		assert!(!rotator.is_banned(&hash));
+		assert!(rotator.invalid_at.read().is_empty());
+	}
+
 	#[test]
 	fn should_garbage_collect() {
 		// given
@@ -182,6 +250,7 @@ mod tests {
 				valid_till,
 				requires: vec![],
 				provides: vec![],
+				sender: None,
 			}
 		}
 
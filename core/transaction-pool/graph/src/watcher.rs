@@ -29,8 +29,12 @@ pub enum Status<H, H2> {
 	Future,
 	/// Extrinsic is part of the ready queue.
 	Ready,
+	/// Extrinsic has been included in block with given hash.
+	InBlock(H2),
 	/// Extrinsic has been finalised in block with given hash.
 	Finalised(H2),
+	/// The block this extrinsic was included in has been retracted by a reorganisation.
+	Retracted(H2),
 	/// Some state change (perhaps another extrinsic was included) rendered this extrinsic invalid.
 	Usurped(H),
 	/// The extrinsic has been broadcast to the given peers.
@@ -107,6 +111,16 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 		self.send(Status::Usurped(hash))
 	}
 
+	/// Extrinsic has been included in block with given hash.
+	pub fn in_block(&mut self, hash: H2) {
+		self.send(Status::InBlock(hash))
+	}
+
+	/// The block this extrinsic was included in has been retracted.
+	pub fn retracted(&mut self, hash: H2) {
+		self.send(Status::Retracted(hash))
+	}
+
 	/// Extrinsic has been finalised in block with given hash.
 	pub fn finalised(&mut self, hash: H2) {
 		self.send(Status::Finalised(hash));
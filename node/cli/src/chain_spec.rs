@@ -19,8 +19,8 @@
 use primitives::{AuthorityId, ed25519};
 use node_primitives::AccountId;
 use node_runtime::{GenesisConfig, ConsensusConfig, CouncilSeatsConfig, CouncilVotingConfig, DemocracyConfig,
-	SessionConfig, StakingConfig, TimestampConfig, BalancesConfig, TreasuryConfig, UpgradeKeyConfig,
-	ContractConfig, Permill, Perbill};
+	SessionConfig, StakingConfig, TimestampConfig, BalancesConfig, TreasuryConfig, UpgradeKeyConfig, IndicesConfig,
+	ContractConfig, SchedulerConfig, ProxyConfig, MultisigConfig, Permill, Perbill};
 use substrate_service;
 
 const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -57,14 +57,20 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 			authorities: initial_authorities.clone(),
 		}),
 		system: None,
+		indices: Some(IndicesConfig {
+			ids: endowed_accounts.clone(),
+			reclaim_delay: 7 * DAYS,
+		}),
 		balances: Some(BalancesConfig {
 			balances: endowed_accounts.iter().map(|&k|(k, 10_000_000 * DOLLARS)).collect(),
 			transaction_base_fee: 1 * CENTS,
 			transaction_byte_fee: 10 * MILLICENTS,
+			transaction_weight_fee: 1 * MILLICENTS / 1_000,
 			existential_deposit: 1 * DOLLARS,
 			transfer_fee: 1 * CENTS,
 			creation_fee: 1 * CENTS,
-			reclaim_rebate: 1 * CENTS,
+			vesting: vec![],
+			minimum_vested_transfer: 100 * DOLLARS,
 		}),
 		session: Some(SessionConfig {
 			validators: initial_authorities.iter().cloned().map(Into::into).collect(),
@@ -74,20 +80,28 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 			current_era: 0,
 			intentions: initial_authorities.iter().cloned().map(Into::into).collect(),
 			offline_slash: Perbill::from_billionths(1_000_000),
-			session_reward: Perbill::from_billionths(2_065),
+			ideal_staking_rate: Perbill::from_percent(50),
+			minimum_inflation: Perbill::from_billionths(25_000_000),
+			ideal_inflation: Perbill::from_percent(10),
+			year_length: 365 * 24 * 60 * 60,
+			history_depth: 84,
 			current_offline_slash: 0,
-			current_session_reward: 0,
 			validator_count: 7,
 			sessions_per_era: 12,
 			bonding_duration: 1 * DAYS,
 			offline_slash_grace: 4,
+			misbehavior_slash: Perbill::from_percent(10),
 			minimum_validator_count: 4,
 		}),
 		democracy: Some(DemocracyConfig {
 			launch_period: 5 * MINUTES,	// 1 day per public referendum
 			voting_period: 5 * MINUTES,	// 3 days to discuss & vote on an active referendum
+			enactment_period: 5 * MINUTES,	// delay between a referendum passing and its enactment
 			minimum_deposit: 50 * DOLLARS,	// 12000 as the minimum deposit for a referendum
 		}),
+		scheduler: Some(SchedulerConfig {
+			maximum_weight: 64 * 1024,	// bytes of scheduled calls dispatched per block
+		}),
 		council_seats: Some(CouncilSeatsConfig {
 			active_council: vec![],
 			candidacy_bond: 10 * DOLLARS,
@@ -114,6 +128,13 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 			spend_period: 1 * DAYS,
 			burn: Permill::from_percent(50),
 		}),
+		proxy: Some(ProxyConfig {
+			proxy_deposit: 1 * DOLLARS,
+		}),
+		multisig: Some(MultisigConfig {
+			deposit_base: 1 * DOLLARS,
+			deposit_factor: 10 * CENTS,
+		}),
 		contract: Some(ContractConfig {
 			contract_fee: 1 * CENTS,
 			call_base_fee: 1000,
@@ -140,6 +161,7 @@ pub fn staging_testnet_config() -> ChainSpec {
 		Some(STAGING_TELEMETRY_URL.into()),
 		None,
 		None,
+		None,
 	)
 }
 
@@ -158,13 +180,19 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, upgrade_key: AccountId
 			authorities: initial_authorities.clone(),
 		}),
 		system: None,
+		indices: Some(IndicesConfig {
+			ids: endowed_accounts.clone(),
+			reclaim_delay: 100,
+		}),
 		balances: Some(BalancesConfig {
 			transaction_base_fee: 1,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 500,
 			transfer_fee: 0,
 			creation_fee: 0,
-			reclaim_rebate: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 500,
 			balances: endowed_accounts.iter().map(|&k|(k, (1 << 60))).collect(),
 		}),
 		session: Some(SessionConfig {
@@ -179,16 +207,24 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, upgrade_key: AccountId
 			sessions_per_era: 5,
 			bonding_duration: 2 * 60 * 12,
 			offline_slash: Perbill::zero(),
-			session_reward: Perbill::zero(),
+			ideal_staking_rate: Perbill::from_percent(50),
+			minimum_inflation: Perbill::zero(),
+			ideal_inflation: Perbill::zero(),
+			year_length: 365 * 24 * 60 * 60,
+			history_depth: 84,
 			current_offline_slash: 0,
-			current_session_reward: 0,
 			offline_slash_grace: 0,
+			misbehavior_slash: Perbill::zero(),
 		}),
 		democracy: Some(DemocracyConfig {
 			launch_period: 9,
 			voting_period: 18,
+			enactment_period: 9,
 			minimum_deposit: 10,
 		}),
+		scheduler: Some(SchedulerConfig {
+			maximum_weight: 64 * 1024,
+		}),
 		council_seats: Some(CouncilSeatsConfig {
 			active_council: endowed_accounts.iter()
 				.filter(|a| initial_authorities.iter().find(|&b| a.0 == b.0).is_none())
@@ -216,6 +252,13 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, upgrade_key: AccountId
 			spend_period: 12 * 60 * 24,
 			burn: Permill::from_percent(50),
 		}),
+		proxy: Some(ProxyConfig {
+			proxy_deposit: 1_000,
+		}),
+		multisig: Some(MultisigConfig {
+			deposit_base: 1_000,
+			deposit_factor: 100,
+		}),
 		contract: Some(ContractConfig {
 			contract_fee: 21,
 			call_base_fee: 135,
@@ -240,7 +283,7 @@ fn development_config_genesis() -> GenesisConfig {
 
 /// Development config (single validator Alice)
 pub fn development_config() -> ChainSpec {
-	ChainSpec::from_genesis("Development", "development", development_config_genesis, vec![], None, None, None)
+	ChainSpec::from_genesis("Development", "development", development_config_genesis, vec![], None, None, None, None)
 }
 
 fn local_testnet_genesis() -> GenesisConfig {
@@ -254,7 +297,7 @@ fn local_testnet_genesis() -> GenesisConfig {
 
 /// Local testnet config (multivalidator Alice + Bob)
 pub fn local_testnet_config() -> ChainSpec {
-	ChainSpec::from_genesis("Local Testnet", "local_testnet", local_testnet_genesis, vec![], None, None, None)
+	ChainSpec::from_genesis("Local Testnet", "local_testnet", local_testnet_genesis, vec![], None, None, None, None)
 }
 
 #[cfg(test)]
@@ -271,7 +314,7 @@ mod tests {
 
 	/// Local testnet config (multivalidator Alice + Bob)
 	pub fn integration_test_config() -> ChainSpec {
-		ChainSpec::from_genesis("Integration Test", "test", local_testnet_genesis_instant, vec![], None, None, None)
+		ChainSpec::from_genesis("Integration Test", "test", local_testnet_genesis_instant, vec![], None, None, None, None)
 	}
 
 	#[test]
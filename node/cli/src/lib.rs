@@ -20,9 +20,12 @@
 #![warn(unused_extern_crates)]
 
 extern crate tokio;
+extern crate futures;
 
 extern crate substrate_cli as cli;
 extern crate substrate_primitives as primitives;
+extern crate substrate_client as client;
+extern crate sr_primitives as runtime_primitives;
 extern crate node_runtime;
 extern crate exit_future;
 #[macro_use]
@@ -21,16 +21,25 @@
 use std::sync::Arc;
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use node_primitives::Block;
-use node_runtime::GenesisConfig;
+use node_runtime::{GenesisConfig, InherentError};
 use substrate_service::{
 	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend,
 	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor,
-	Roles, TaskExecutor,
+	TaskExecutor, ConsensusEngine, ProposerFactory, NetworkService, ServiceFactory, Error,
 };
 use node_executor;
-use consensus::{import_queue, start_aura, Config as AuraConfig, AuraImportQueue};
+use client::BlockImportNotification;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::Header;
+use consensus::{
+	import_queue, start_aura, note_slot_authorship, Config as AuraConfig, AuraImportQueue, Signer,
+	AuthoritySignatures, Fallback, InherentDataProviders, TimestampInherentDataProvider,
+};
+use futures::Future;
 
 const AURA_SLOT_DURATION: u64 = 6;
+/// How many seconds ahead of the local clock the timestamp of an imported block may be.
+const MAX_TIMESTAMP_DRIFT: u64 = 60;
 
 construct_simple_protocol! {
 	/// Demo protocol attachment for substrate.
@@ -51,47 +60,84 @@ construct_service_factory! {
 		Genesis = GenesisConfig,
 		Configuration = (),
 		FullService = Service<FullComponents<Self>>
-			{ |config: FactoryFullConfiguration<Self>, executor: TaskExecutor| {
-				let is_auth = config.roles == Roles::AUTHORITY;
-				Service::<FullComponents<Factory>>::new(config, executor.clone()).map(move |service|{
-					if is_auth {
-						if let Ok(Some(Ok(key))) = service.keystore().contents()
-							.map(|keys| keys.get(0).map(|k| service.keystore().load(k, "")))
-						{
-							info!("Using authority key {}", key.public());
-							let task = start_aura(
-								AuraConfig {
-									local_key:  Some(Arc::new(key)),
-									slot_duration: AURA_SLOT_DURATION,
-								},
-								service.client(),
-								service.proposer(),
-								service.network(),
-							);
-
-							executor.spawn(task);
-						}
-					}
-
-					service
+			{ |config: FactoryFullConfiguration<Self>, executor: TaskExecutor|
+				Service::<FullComponents<Factory>>::new(config, executor.clone()).and_then(move |service| {
+					service.start_consensus::<AuraEngine>(&executor)?;
+					Ok(service)
 				})
-			}
-		},
+			},
 		LightService = Service<LightComponents<Self>>
 			{ |config, executor| Service::<LightComponents<Factory>>::new(config, executor) },
-		FullImportQueue = AuraImportQueue<Self::Block, FullClient<Self>>
-			{ |config, client| Ok(import_queue(AuraConfig {
+		FullImportQueue = AuraImportQueue<Self::Block, FullClient<Self>, InherentError, AuthoritySignatures>
+			{ |config, client| {
+					let inherent_data_providers = InherentDataProviders::new();
+					inherent_data_providers
+						.register_provider(TimestampInherentDataProvider::new(MAX_TIMESTAMP_DRIFT))
+						.map_err(|e| e.to_string())?;
+					Ok(import_queue(AuraConfig {
 						local_key: None,
-						slot_duration: 5
-					}, client)) },
-		LightImportQueue = AuraImportQueue<Self::Block, LightClient<Self>>
+						slot_duration: 5,
+						fallback: config.fallback_slots.map(Fallback::new),
+					}, client, Some(inherent_data_providers), AuthoritySignatures))
+				}
+			},
+		LightImportQueue = AuraImportQueue<Self::Block, LightClient<Self>, InherentError, AuthoritySignatures>
 			{ |config, client| Ok(import_queue(AuraConfig {
 						local_key: None,
-						slot_duration: 5
-					}, client)) },
+						slot_duration: 5,
+						fallback: config.fallback_slots.map(Fallback::new),
+					}, client, None, AuthoritySignatures)) },
 	}
 }
 
+/// Aura authoring: authorities take turns proposing in slots of `AURA_SLOT_DURATION` seconds.
+pub struct AuraEngine {
+	config: AuraConfig,
+	client: Arc<FullClient<Factory>>,
+	proposer: Arc<ProposerFactory<FullClient<Factory>, <Factory as ServiceFactory>::FullTransactionPoolApi>>,
+	network: Arc<NetworkService<Factory>>,
+}
+
+impl ConsensusEngine<FullComponents<Factory>> for AuraEngine {
+	fn new(service: &::substrate_service::Service<FullComponents<Factory>>, signer: Arc<Signer>) -> Result<Self, Error> {
+		Ok(AuraEngine {
+			config: AuraConfig {
+				local_key: Some(signer),
+				slot_duration: AURA_SLOT_DURATION,
+				fallback: service.fallback_slots().map(Fallback::new),
+			},
+			client: service.client(),
+			proposer: service.proposer(),
+			network: service.network(),
+		})
+	}
+
+	fn propose(&self) -> Box<Future<Item=(), Error=()> + Send> {
+		Box::new(start_aura(
+			self.config.clone(),
+			self.client.clone(),
+			self.proposer.clone(),
+			self.network.clone(),
+		))
+	}
+
+	fn on_block_imported(&self, notification: &BlockImportNotification<Block>) {
+		let parent_id = BlockId::hash(*notification.header.parent_hash());
+		let parent = match self.client.header(&parent_id) {
+			Ok(Some(parent)) => parent,
+			_ => return,
+		};
+		let authorities = match self.client.authorities_at(&parent_id) {
+			Ok(authorities) => authorities,
+			Err(e) => {
+				warn!("Unable to fetch authorities at block {:?}: {:?}", parent_id, e);
+				return;
+			}
+		};
+
+		note_slot_authorship::<Block>(&mut self.proposer.offline.write(), &notification.header, &parent, &authorities);
+	}
+}
 
 #[cfg(test)]
 mod tests {
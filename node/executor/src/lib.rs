@@ -25,6 +25,7 @@ extern crate node_runtime;
 #[cfg(test)] extern crate sr_primitives as runtime_primitives;
 #[cfg(test)] extern crate srml_support as runtime_support;
 #[cfg(test)] extern crate srml_balances as balances;
+#[cfg(test)] extern crate srml_indices as indices;
 #[cfg(test)] extern crate srml_session as session;
 #[cfg(test)] extern crate srml_staking as staking;
 #[cfg(test)] extern crate srml_system as system;
@@ -57,11 +58,11 @@ mod tests {
 	use node_primitives::{Hash, BlockNumber, AccountId};
 	use runtime_primitives::traits::{Header as HeaderT, Digest as DigestT};
 	use runtime_primitives::{generic, generic::Era, ApplyOutcome, ApplyError, ApplyResult, Perbill};
-	use {balances, staking, session, system, consensus, timestamp, treasury, contract};
+	use {balances, indices, staking, session, system, consensus, timestamp, treasury, contract};
 	use contract::ContractAddressFor;
 	use system::{EventRecord, Phase};
 	use node_runtime::{Header, Block, UncheckedExtrinsic, CheckedExtrinsic, Call, Runtime, Balances,
-		BuildStorage, GenesisConfig, BalancesConfig, SessionConfig, StakingConfig, System,
+		BuildStorage, GenesisConfig, IndicesConfig, BalancesConfig, SessionConfig, StakingConfig, System,
 		SystemConfig, Event, Log};
 	use wabt;
 
@@ -89,7 +90,7 @@ mod tests {
 				let pair = Pair::from(Keyring::from_public(Public::from_raw(signed.clone().into())).unwrap());
 				let signature = pair.sign(&payload.encode()).into();
 				UncheckedExtrinsic {
-					signature: Some((balances::address::Address::Id(signed), signature, payload.0, era)),
+					signature: Some((indices::address::Address::Id(signed), signature, payload.0, era)),
 					function: payload.1,
 				}
 			}
@@ -125,7 +126,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -146,7 +147,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -167,7 +168,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -192,7 +193,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -219,6 +220,10 @@ mod tests {
 				}) } else { None },
 				..Default::default()
 			}),
+			indices: Some(IndicesConfig {
+				ids: vec![alice(), charlie()],
+				reclaim_delay: 0,
+			}),
 			balances: Some(BalancesConfig {
 				balances: vec![
 					(alice(), 111),
@@ -226,10 +231,12 @@ mod tests {
 				],
 				transaction_base_fee: 1,
 				transaction_byte_fee: 0,
+				transaction_weight_fee: 0,
 				existential_deposit: 0,
 				transfer_fee: 0,
 				creation_fee: 0,
-				reclaim_rebate: 0,
+				vesting: vec![],
+				minimum_vested_transfer: 0,
 			}),
 			session: Some(SessionConfig {
 				session_length: 2,
@@ -243,16 +250,23 @@ mod tests {
 				minimum_validator_count: 0,
 				bonding_duration: 0,
 				offline_slash: Perbill::zero(),
-				session_reward: Perbill::zero(),
+				ideal_staking_rate: Perbill::zero(),
+				minimum_inflation: Perbill::zero(),
+				ideal_inflation: Perbill::zero(),
+				year_length: 0,
+				history_depth: 0,
 				current_offline_slash: 0,
-				current_session_reward: 0,
 				offline_slash_grace: 0,
+				misbehavior_slash: Perbill::zero(),
 			}),
 			democracy: Some(Default::default()),
+			scheduler: Some(Default::default()),
 			council_seats: Some(Default::default()),
 			council_voting: Some(Default::default()),
 			timestamp: Some(Default::default()),
 			treasury: Some(Default::default()),
+			proxy: Some(Default::default()),
+			multisig: Some(Default::default()),
 			contract: Some(Default::default()),
 			upgrade_key: Some(Default::default()),
 		}.build_storage().unwrap().0)
@@ -372,7 +386,11 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(1),
-					event: Event::balances(balances::RawEvent::NewAccount(bob(), 2, balances::NewAccountOutcome::NoHint))
+					event: Event::indices(indices::RawEvent::NewAccountIndex(bob(), 2))
+				},
+				EventRecord {
+					phase: Phase::ApplyExtrinsic(1),
+					event: Event::balances(balances::RawEvent::NewAccount(bob(), 69))
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(1),
@@ -687,7 +705,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -709,7 +727,7 @@ mod tests {
 			twox_128(<balances::ExistentialDeposit<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::CreationFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(<balances::TransferFee<Runtime>>::key()).to_vec() => vec![0u8; 16],
-			twox_128(<balances::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
+			twox_128(<indices::NextEnumSet<Runtime>>::key()).to_vec() => vec![0u8; 16],
 			twox_128(&<system::BlockHash<Runtime>>::key_for(0)).to_vec() => vec![0u8; 32]
 		]);
 
@@ -726,6 +744,25 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn check_inherents_requires_timestamp_data() {
+		use node_runtime::{InherentData, InherentDataExt, InherentError};
+		use runtime_primitives::inherents::well_known_identifiers;
+
+		let block = || Block::decode(&mut &block1(false).0[..]).unwrap();
+
+		match InherentData::new().check_inherents(block()) {
+			Err(InherentError::MissingInherentData(identifier)) =>
+				assert_eq!(identifier, well_known_identifiers::TIMESTAMP),
+			_ => panic!("a block checked without timestamp data must be rejected"),
+		}
+
+		// offline reports are optional, only the timestamp is needed.
+		let mut data = InherentData::new();
+		data.put_data(well_known_identifiers::TIMESTAMP, &42u64).unwrap();
+		assert!(data.check_inherents(block()).is_ok());
+	}
+
 	#[test]
 	fn full_native_block_import_works_with_changes_trie() {
 		let mut t = new_test_ext(true);
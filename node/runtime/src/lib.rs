@@ -45,6 +45,10 @@ extern crate srml_contract as contract;
 extern crate srml_council as council;
 extern crate srml_democracy as democracy;
 extern crate srml_executive as executive;
+extern crate srml_indices as indices;
+extern crate srml_multisig as multisig;
+extern crate srml_proxy as proxy;
+extern crate srml_scheduler as scheduler;
 extern crate srml_session as session;
 extern crate srml_staking as staking;
 extern crate srml_system as system;
@@ -56,7 +60,7 @@ extern crate sr_version as version;
 extern crate node_primitives;
 
 use rstd::prelude::*;
-use substrate_primitives::u32_trait::{_2, _4};
+use substrate_primitives::u32_trait::{_2, _3, _4};
 use node_primitives::{
 	AccountId, AccountIndex, Balance, BlockNumber, Hash, Index,
 	SessionKey, Signature
@@ -65,8 +69,9 @@ use runtime_api::{runtime::*, id::*};
 use runtime_primitives::ApplyResult;
 use runtime_primitives::transaction_validity::TransactionValidity;
 use runtime_primitives::generic;
-use runtime_primitives::traits::{Convert, BlakeTwo256, Block as BlockT};
+use runtime_primitives::traits::{Convert, BlakeTwo256, Block as BlockT, NumberFor};
 use version::RuntimeVersion;
+use srml_support::metadata::{DecodeDifferent, TypeMetadata};
 use council::{motions as council_motions, voting as council_voting};
 #[cfg(feature = "std")]
 use council::seats as council_seats;
@@ -90,12 +95,13 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: ver_str!("node"),
 	impl_name: ver_str!("substrate-node"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 0,
 	apis: apis_vec!([
 		(BLOCK_BUILDER, 1),
 		(TAGGED_TRANSACTION_QUEUE, 1),
-		(METADATA, 1)
+		(METADATA, 1),
+		(OFFCHAIN_WORKER, 1)
 	]),
 };
 
@@ -121,19 +127,27 @@ impl system::Trait for Runtime {
 	type Log = Log;
 }
 
+impl indices::Trait for Runtime {
+	type AccountIndex = AccountIndex;
+	type Event = Event;
+}
+
 impl balances::Trait for Runtime {
 	type Balance = Balance;
-	type AccountIndex = AccountIndex;
 	type OnFreeBalanceZero = (Staking, Contract);
 	type EnsureAccountLiquid = Staking;
+	type LockedBalance = Democracy;
+	type OnCollected = Treasury;
 	type Event = Event;
 }
 
 impl consensus::Trait for Runtime {
 	const NOTE_OFFLINE_POSITION: u32 = NOTE_OFFLINE_POSITION;
 	type Log = Log;
+	type Signature = Signature;
 	type SessionKey = SessionKey;
 	type OnOfflineValidator = Staking;
+	type OnMisbehavior = Staking;
 }
 
 impl timestamp::Trait for Runtime {
@@ -162,6 +176,13 @@ impl staking::Trait for Runtime {
 
 impl democracy::Trait for Runtime {
 	type Proposal = Call;
+	type CancellationOrigin = council_motions::EnsureProportionAtLeast<_2, _3>;
+	type Scheduler = Scheduler;
+	type Event = Event;
+}
+
+impl scheduler::Trait for Runtime {
+	type Call = Call;
 	type Event = Event;
 }
 
@@ -169,6 +190,46 @@ impl council::Trait for Runtime {
 	type Event = Event;
 }
 
+/// The classes of calls an account may let a proxy dispatch on its behalf.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum ProxyType {
+	/// Any call at all.
+	Any,
+	/// Staking and session key management, so that a validator's stash key can be kept cold.
+	Staking,
+	/// Voting and proposing in democracy, the council and the treasury.
+	Governance,
+}
+
+impl proxy::Filter<Call> for ProxyType {
+	fn filter(&self, call: &Call) -> bool {
+		match *self {
+			ProxyType::Any => true,
+			ProxyType::Staking => match *call {
+				Call::Staking(..) | Call::Session(..) => true,
+				_ => false,
+			},
+			ProxyType::Governance => match *call {
+				Call::Democracy(..) | Call::Council(..) | Call::CouncilVoting(..)
+					| Call::CouncilMotions(..) | Call::Treasury(..) => true,
+				_ => false,
+			},
+		}
+	}
+}
+
+impl proxy::Trait for Runtime {
+	type Call = Call;
+	type ProxyType = ProxyType;
+	type Event = Event;
+}
+
+impl multisig::Trait for Runtime {
+	type Call = Call;
+	type Event = Event;
+}
+
 impl council::voting::Trait for Runtime {
 	type Event = Event;
 }
@@ -182,6 +243,8 @@ impl council::motions::Trait for Runtime {
 impl treasury::Trait for Runtime {
 	type ApproveOrigin = council_motions::EnsureMembers<_4>;
 	type RejectOrigin = council_motions::EnsureMembers<_2>;
+	type Call = Call;
+	type Scheduler = Scheduler;
 	type Event = Event;
 }
 
@@ -203,24 +266,28 @@ construct_runtime!(
 		System: system::{default, Log(ChangesTrieRoot)},
 		Timestamp: timestamp::{Module, Call, Storage, Config<T>, Inherent},
 		Consensus: consensus::{Module, Call, Storage, Config<T>, Log(AuthoritiesChange), Inherent},
+		Indices: indices::{Module, Storage, Config<T>, Event<T>},
 		Balances: balances,
 		Session: session,
 		Staking: staking,
 		Democracy: democracy,
+		Scheduler: scheduler,
 		Council: council::{Module, Call, Storage, Event<T>},
 		CouncilVoting: council_voting,
 		CouncilMotions: council_motions::{Module, Call, Storage, Event<T>, Origin},
 		CouncilSeats: council_seats::{Config<T>},
 		Treasury: treasury,
+		Proxy: proxy,
+		Multisig: multisig,
 		Contract: contract::{Module, Call, Config<T>, Event<T>},
 		UpgradeKey: upgrade_key,
 	}
 );
 
 /// The address format for describing accounts.
-pub use balances::address::Address as RawAddress;
+pub use indices::address::Address as RawAddress;
 /// The address format for describing accounts.
-pub type Address = balances::Address<Runtime>;
+pub type Address = indices::Address<Runtime>;
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256, Log>;
 /// Block type as expected by this runtime.
@@ -234,7 +301,22 @@ pub type UncheckedExtrinsic = generic::UncheckedMortalExtrinsic<Address, Index,
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Index, Call>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = executive::Executive<Runtime, Block, balances::ChainContext<Runtime>, Balances, AllModules>;
+pub type Executive = executive::Executive<Runtime, Block, indices::ChainContext<Runtime>, Balances, AllModules>;
+
+/// How the types named in the metadata of this runtime, e.g. by the event arguments, are encoded.
+const TYPE_METADATA: &[TypeMetadata] = &[
+	TypeMetadata { name: DecodeDifferent::Encode("AccountId"), layout: DecodeDifferent::Encode("[u8; 32]") },
+	TypeMetadata { name: DecodeDifferent::Encode("AccountIndex"), layout: DecodeDifferent::Encode("u32") },
+	TypeMetadata { name: DecodeDifferent::Encode("Balance"), layout: DecodeDifferent::Encode("u128") },
+	TypeMetadata { name: DecodeDifferent::Encode("BlockNumber"), layout: DecodeDifferent::Encode("u64") },
+	TypeMetadata { name: DecodeDifferent::Encode("Hash"), layout: DecodeDifferent::Encode("[u8; 32]") },
+	TypeMetadata { name: DecodeDifferent::Encode("SessionKey"), layout: DecodeDifferent::Encode("[u8; 32]") },
+	TypeMetadata { name: DecodeDifferent::Encode("PropIndex"), layout: DecodeDifferent::Encode("u32") },
+	TypeMetadata { name: DecodeDifferent::Encode("ReferendumIndex"), layout: DecodeDifferent::Encode("u32") },
+	TypeMetadata { name: DecodeDifferent::Encode("ProposalIndex"), layout: DecodeDifferent::Encode("u32") },
+	TypeMetadata { name: DecodeDifferent::Encode("VoteThreshold"), layout: DecodeDifferent::Encode("u8") },
+	TypeMetadata { name: DecodeDifferent::Encode("Timepoint<BlockNumber>"), layout: DecodeDifferent::Encode("(BlockNumber, u32)") },
+];
 
 impl_apis! {
 	impl Core<Block, SessionKey> for Runtime {
@@ -253,7 +335,7 @@ impl_apis! {
 
 	impl Metadata<RuntimeMetadata> for Runtime {
 		fn metadata() -> RuntimeMetadata {
-			Runtime::metadata()
+			RuntimeMetadata { types: DecodeDifferent::Encode(TYPE_METADATA), ..Runtime::metadata() }
 		}
 	}
 
@@ -288,4 +370,10 @@ impl_apis! {
 			Executive::validate_transaction(tx)
 		}
 	}
+
+	impl OffchainWorker<Block> for Runtime {
+		fn offchain_worker(number: NumberFor<Block>) {
+			Executive::offchain_worker(number)
+		}
+	}
 }
@@ -33,6 +33,7 @@ extern crate parity_codec_derive;
 extern crate parity_codec as codec;
 extern crate sr_primitives as primitives;
 extern crate srml_system as system;
+extern crate srml_indices as indices;
 
 #[cfg(test)]
 extern crate sr_io as runtime_io;
@@ -41,27 +42,18 @@ extern crate substrate_primitives;
 
 use rstd::prelude::*;
 use rstd::{cmp, result};
-use codec::{Encode, Decode, Codec, Input, Output, HasCompact};
+use codec::{Codec, HasCompact};
 use runtime_support::{StorageValue, StorageMap, Parameter};
-use runtime_support::dispatch::Result;
-use primitives::traits::{Zero, One, SimpleArithmetic, MakePayment,
-	As, Lookup, Member, CheckedAdd, CheckedSub, CurrentHeight, BlockNumberToHash};
-use address::Address as RawAddress;
+use runtime_support::dispatch::{Result, DispatchClass, DispatchInfo, SimpleDispatchInfo};
+use primitives::weights::WeightMultiplier;
+use primitives::traits::{Zero, SimpleArithmetic, MakePayment, As, Member, CheckedAdd, CheckedSub};
+use indices::address::Address as RawAddress;
 use system::ensure_signed;
 
 mod mock;
 
-pub mod address;
 mod tests;
 
-/// Number of account IDs stored per enum set.
-const ENUM_SET_SIZE: usize = 64;
-
-/// The byte to identify intention to reclaim an existing account index.
-const RECLAIM_INDEX_MAGIC: usize = 0x69;
-
-pub type Address<T> = RawAddress<<T as system::Trait>::AccountId, <T as Trait>::AccountIndex>;
-
 /// The account with the given id was killed.
 pub trait OnFreeBalanceZero<AccountId> {
 	/// The account was the given id was killed.
@@ -93,6 +85,17 @@ impl<Balance> OnDilution<Balance> for () {
 	fn on_dilution(_minted: Balance, _portion: Balance) {}
 }
 
+/// Trait for a hook to get called when fees are paid or balance is slashed, taking funds out of
+/// circulation.
+pub trait OnCollected<Balance> {
+	/// `value` has been taken from an account and removed from the total issuance.
+	fn on_collected(value: Balance);
+}
+
+impl<Balance> OnCollected<Balance> for () {
+	fn on_collected(_value: Balance) {}
+}
+
 /// Determinator for whether a given account is able to transfer balance.
 pub trait EnsureAccountLiquid<AccountId> {
 	/// Returns `Ok` iff the account is able to transfer funds normally. `Err(...)`
@@ -104,12 +107,19 @@ impl<AccountId> EnsureAccountLiquid<AccountId> for () {
 	fn ensure_account_liquid(_who: &AccountId) -> Result { Ok(()) }
 }
 
-pub trait Trait: system::Trait {
+/// Determinator for how much of the free balance of a given account other modules keep locked.
+pub trait LockedBalance<AccountId, Balance> {
+	/// The part of the free balance of `who` which can't be transferred or reserved.
+	fn locked_balance(who: &AccountId) -> Balance;
+}
+
+impl<AccountId, Balance: Zero> LockedBalance<AccountId, Balance> for () {
+	fn locked_balance(_who: &AccountId) -> Balance { Zero::zero() }
+}
+
+pub trait Trait: indices::Trait {
 	/// The balance of an account.
-	type Balance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy + As<Self::AccountIndex> + As<usize> + As<u64>;
-	/// Type used for storing an account's index; implies the maximum number of accounts the system
-	/// can hold.
-	type AccountIndex: Parameter + Member + Codec + Default + SimpleArithmetic + As<u8> + As<u16> + As<u32> + As<u64> + As<usize> + Copy;
+	type Balance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy + As<usize> + As<u64>;
 	/// A function which is invoked when the free-balance has fallen below the existential deposit and
 	/// has been reduced to zero.
 	///
@@ -119,6 +129,12 @@ pub trait Trait: system::Trait {
 	/// A function that returns true iff a given account can transfer its funds to another account.
 	type EnsureAccountLiquid: EnsureAccountLiquid<Self::AccountId>;
 
+	/// A function that returns how much of an account's free balance is locked by other modules.
+	type LockedBalance: LockedBalance<Self::AccountId, Self::Balance>;
+
+	/// Handler for the fees paid and the balance slashed.
+	type OnCollected: OnCollected<Self::Balance>;
+
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -127,6 +143,10 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+		fn on_finalise() {
+			Self::update_weight_multiplier();
+		}
+
 		/// Transfer some liquid free balance to another staker.
 		pub fn transfer(
 			origin,
@@ -135,7 +155,7 @@ decl_module! {
 		) -> Result {
 			let transactor = ensure_signed(origin)?;
 
-			let dest = Self::lookup(dest)?;
+			let dest = <indices::Module<T>>::lookup(dest)?;
 			let value = value.into();
 			let from_balance = Self::free_balance(&transactor);
 			let to_balance = Self::free_balance(&dest);
@@ -154,6 +174,7 @@ decl_module! {
 				return Err("value too low to create account");
 			}
 			T::EnsureAccountLiquid::ensure_account_liquid(&transactor)?;
+			Self::ensure_vested(&transactor, new_from_balance)?;
 
 			// NOTE: total stake being stored in the same type means that this could never overflow
 			// but better to be safe than sorry.
@@ -165,6 +186,7 @@ decl_module! {
 			if transactor != dest {
 				Self::set_free_balance(&transactor, new_from_balance);
 				Self::decrease_total_stake_by(fee);
+				T::OnCollected::on_collected(fee);
 				Self::set_free_balance_creating(&dest, new_to_balance);
 				Self::deposit_event(RawEvent::Transfer(transactor, dest, value, fee));
 			}
@@ -172,13 +194,44 @@ decl_module! {
 			Ok(())
 		}
 
+		/// Transfer some liquid free balance to an account without a vesting schedule, locking it
+		/// there under `schedule`.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn vested_transfer(
+			origin,
+			dest: RawAddress<T::AccountId, T::AccountIndex>,
+			schedule: VestingSchedule<T::Balance, T::BlockNumber>
+		) -> Result {
+			let transactor = ensure_signed(origin)?;
+			let who = <indices::Module<T>>::lookup(dest.clone())?;
+			ensure!(who != transactor, "cannot make a vested transfer to oneself");
+			ensure!(!<Vesting<T>>::exists(&who), "destination already has a vesting schedule");
+			ensure!(!schedule.per_block.is_zero(), "vesting schedule must unlock some balance per block");
+			ensure!(schedule.locked >= Self::minimum_vested_transfer(), "vested transfer is too small");
+
+			Self::transfer(system::RawOrigin::Signed(transactor).into(), dest, schedule.locked.into())?;
+			<Vesting<T>>::insert(&who, schedule);
+			Self::update_lock(&who, &schedule);
+			Ok(())
+		}
+
+		/// Unlock the balance of the transactor that has vested by now.
+		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		fn vest(origin) -> Result {
+			let who = ensure_signed(origin)?;
+			let schedule = Self::vesting(&who).ok_or("no vesting schedule")?;
+			Self::update_lock(&who, &schedule);
+			Ok(())
+		}
+
 		/// Set the balances of a given account.
+		#[weight = SimpleDispatchInfo::FixedOperational(5_000)]
 		fn set_balance(
 			who: RawAddress<T::AccountId, T::AccountIndex>,
 			free: <T::Balance as HasCompact>::Type,
 			reserved: <T::Balance as HasCompact>::Type
 		) -> Result {
-			let who = Self::lookup(who)?;
+			let who = <indices::Module<T>>::lookup(who)?;
 			Self::set_free_balance(&who, free.into());
 			Self::set_reserved_balance(&who, reserved.into());
 			Ok(())
@@ -189,11 +242,10 @@ decl_module! {
 decl_event!(
 	pub enum Event<T> where
 		<T as system::Trait>::AccountId,
-		<T as Trait>::AccountIndex,
 		<T as Trait>::Balance
 	{
 		/// A new account was created.
-		NewAccount(AccountId, AccountIndex, NewAccountOutcome),
+		NewAccount(AccountId, Balance),
 		/// An account was reaped.
 		ReapedAccount(AccountId),
 		/// Transfer succeeded (from, to, value, fees).
@@ -209,19 +261,13 @@ decl_storage! {
 		}): T::Balance;
 		/// The minimum amount allowed to keep an account open.
 		pub ExistentialDeposit get(existential_deposit) config(): T::Balance;
-		/// The amount credited to a destination's account whose index was reclaimed.
-		pub ReclaimRebate get(reclaim_rebate) config(): T::Balance;
 		/// The fee required to make a transfer.
 		pub TransferFee get(transfer_fee) config(): T::Balance;
-		/// The fee required to create an account. At least as big as ReclaimRebate.
+		/// The fee required to create an account.
 		pub CreationFee get(creation_fee) config(): T::Balance;
-
-		/// The next free enumeration set.
-		pub NextEnumSet get(next_enum_set) build(|config: &GenesisConfig<T>| {
-			T::AccountIndex::sa(config.balances.len() / ENUM_SET_SIZE)
-		}): T::AccountIndex;
-		/// The enumeration sets.
-		pub EnumSet get(enum_set): map T::AccountIndex => Vec<T::AccountId>;
+		/// The minimum amount that can be locked by a vested transfer, so that an account can't
+		/// be kept from receiving a real vesting schedule by a dust one.
+		pub MinimumVestedTransfer get(minimum_vested_transfer) config(): T::Balance;
 
 		/// The 'free' balance of a given account.
 		///
@@ -250,6 +296,17 @@ decl_storage! {
 		/// collapsed to zero if it ever becomes less than `ExistentialDeposit`.
 		pub ReservedBalance get(reserved_balance): map T::AccountId => T::Balance;
 
+		/// The vesting schedule of a given account's free balance.
+		pub Vesting get(vesting) build(|config: &GenesisConfig<T>| genesis_vesting(config)): map T::AccountId => Option<VestingSchedule<T::Balance, T::BlockNumber>>;
+		/// The amount of the free balance of a given account that can't be transferred or reserved.
+		///
+		/// This is derived from the vesting schedule, and is updated by calling `vest`.
+		pub Locked get(locked) build(|config: &GenesisConfig<T>| {
+			genesis_vesting(config).into_iter()
+				.map(|(who, schedule)| (who, schedule.locked_at(Zero::zero())))
+				.collect::<Vec<_>>()
+		}): map T::AccountId => T::Balance;
+
 
 		// Payment stuff.
 
@@ -257,26 +314,62 @@ decl_storage! {
 		pub TransactionBaseFee get(transaction_base_fee) config(): T::Balance;
 		/// The fee to be paid for making a transaction; the per-byte portion.
 		pub TransactionByteFee get(transaction_byte_fee) config(): T::Balance;
+		/// The fee to be paid for making a transaction; the portion per unit of weight, before
+		/// it is scaled by `WeightFeeMultiplier`.
+		pub TransactionWeightFee get(transaction_weight_fee) config(): T::Balance;
+		/// The factor by which the weight of a transaction is scaled when charging for it. It
+		/// follows how full recent blocks have been.
+		pub WeightFeeMultiplier get(weight_fee_multiplier): WeightMultiplier;
 	}
 	add_extra_genesis {
 		config(balances): Vec<(T::AccountId, T::Balance)>;
-		build(|storage: &mut primitives::StorageMap, _: &mut primitives::ChildrenStorageMap, config: &GenesisConfig<T>| {
-			let ids: Vec<_> = config.balances.iter().map(|x| x.0.clone()).collect();
-			for i in 0..(ids.len() + ENUM_SET_SIZE - 1) / ENUM_SET_SIZE {
-				storage.insert(GenesisConfig::<T>::hash(&<EnumSet<T>>::key_for(T::AccountIndex::sa(i))).to_vec(),
-					ids[i * ENUM_SET_SIZE..ids.len().min((i + 1) * ENUM_SET_SIZE)].to_owned().encode());
-			}
-		});
+		/// Accounts whose whole genesis balance vests linearly over `length` blocks from `begin`,
+		/// as `(who, begin, length)`.
+		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber)>;
 	}
 }
 
-/// Whatever happened about the hint given when creating the new account.
+/// A linear schedule by which some amount of an account's free balance becomes transferable.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
-#[derive(Encode, Decode, PartialEq, Eq, Clone, Copy)]
-pub enum NewAccountOutcome {
-	NoHint,
-	GoodHint,
-	BadHint,
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Copy, Default)]
+pub struct VestingSchedule<Balance, BlockNumber> {
+	/// The amount locked until `starting_block`.
+	pub locked: Balance,
+	/// The amount unlocked with every block after `starting_block`.
+	pub per_block: Balance,
+	/// The block from which the amount starts to unlock.
+	pub starting_block: BlockNumber,
+}
+
+impl<Balance: SimpleArithmetic + Copy, BlockNumber: SimpleArithmetic + Copy> VestingSchedule<Balance, BlockNumber> {
+	/// The amount still locked at block `n`.
+	pub fn locked_at(&self, n: BlockNumber) -> Balance {
+		if n <= self.starting_block {
+			return self.locked;
+		}
+		let elapsed = Balance::sa((n - self.starting_block).as_());
+		match self.per_block.checked_mul(&elapsed) {
+			Some(unlocked) if unlocked < self.locked => self.locked - unlocked,
+			_ => Zero::zero(),
+		}
+	}
+}
+
+/// The vesting schedules of the genesis balances.
+#[cfg(feature = "std")]
+fn genesis_vesting<T: Trait>(config: &GenesisConfig<T>) -> Vec<(T::AccountId, VestingSchedule<T::Balance, T::BlockNumber>)> {
+	use primitives::traits::One;
+	config.vesting.iter().filter_map(|&(ref who, begin, length)| {
+		let locked = config.balances.iter().find(|&&(ref w, _)| w == who)?.1;
+		let length = <T::Balance as As<u64>>::sa(length.as_()).max(One::one());
+		// round up, so that everything is unlocked after `length` blocks.
+		let per_block = if (locked % length).is_zero() {
+			locked / length
+		} else {
+			locked / length + One::one()
+		};
+		Some((who.clone(), VestingSchedule { locked, per_block, starting_block: begin }))
+	}).collect()
 }
 
 /// Outcome of a balance update.
@@ -305,36 +398,12 @@ impl<T: Trait> Module<T> {
 	/// are no balance changes in the meantime.
 	pub fn can_reserve(who: &T::AccountId, value: T::Balance) -> bool {
 		if T::EnsureAccountLiquid::ensure_account_liquid(who).is_ok() {
-			Self::free_balance(who) >= value
+			Self::free_balance(who).checked_sub(&value).map_or(false, |b| Self::ensure_vested(who, b).is_ok())
 		} else {
 			false
 		}
 	}
 
-	/// Lookup an T::AccountIndex to get an Id, if there's one there.
-	pub fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
-		let enum_set_size = Self::enum_set_size();
-		let set = Self::enum_set(index / enum_set_size);
-		let i: usize = (index % enum_set_size).as_();
-		set.get(i).map(|x| x.clone())
-	}
-
-	/// `true` if the account `index` is ready for reclaim.
-	pub fn can_reclaim(try_index: T::AccountIndex) -> bool {
-		let enum_set_size = Self::enum_set_size();
-		let try_set = Self::enum_set(try_index / enum_set_size);
-		let i = (try_index % enum_set_size).as_();
-		i < try_set.len() && Self::total_balance(&try_set[i]).is_zero()
-	}
-
-	/// Lookup an address to get an Id, if there's one there.
-	pub fn lookup_address(a: address::Address<T::AccountId, T::AccountIndex>) -> Option<T::AccountId> {
-		match a {
-			address::Address::Id(i) => Some(i),
-			address::Address::Index(i) => Self::lookup_index(i),
-		}
-	}
-
 	//PUBLIC MUTABLES (DANGEROUS)
 
 	/// Set the free balance of an account to some new value.
@@ -397,16 +466,9 @@ impl<T: Trait> Module<T> {
 			UpdateBalanceOutcome::AccountKilled
 		} else {
 			if !<FreeBalance<T>>::exists(who) {
-				let outcome = Self::new_account(&who, balance);
-				let credit = match outcome {
-					NewAccountOutcome::GoodHint => balance + <Module<T>>::reclaim_rebate(),
-					_ => balance,
-				};
-				Self::set_free_balance(who, credit);
-				Self::increase_total_stake_by(credit - balance);
-			} else {
-				Self::set_free_balance(who, balance);
+				Self::new_account(&who, balance);
 			}
+			Self::set_free_balance(who, balance);
 
 			UpdateBalanceOutcome::Updated
 		}
@@ -437,6 +499,7 @@ impl<T: Trait> Module<T> {
 		if b < value {
 			return Err("account has too few funds")
 		}
+		Self::ensure_vested(who, b - value)?;
 		Ok(Self::set_free_balance(who, b - value))
 	}
 
@@ -450,6 +513,7 @@ impl<T: Trait> Module<T> {
 		let free_slash = cmp::min(free_balance, value);
 		Self::set_free_balance(who, free_balance - free_slash);
 		Self::decrease_total_stake_by(free_slash);
+		T::OnCollected::on_collected(free_slash);
 		if free_slash < value {
 			Self::slash_reserved(who, value - free_slash)
 		} else {
@@ -479,6 +543,7 @@ impl<T: Trait> Module<T> {
 			return Err("not enough free funds")
 		}
 		T::EnsureAccountLiquid::ensure_account_liquid(who)?;
+		Self::ensure_vested(who, b - value)?;
 		Self::set_reserved_balance(who, Self::reserved_balance(who) + value);
 		Self::set_free_balance(who, b - value);
 		Ok(())
@@ -510,6 +575,7 @@ impl<T: Trait> Module<T> {
 		let slash = cmp::min(b, value);
 		Self::set_reserved_balance(who, b - slash);
 		Self::decrease_total_stake_by(slash);
+		T::OnCollected::on_collected(slash);
 		if value == slash {
 			None
 		} else {
@@ -542,86 +608,52 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	fn enum_set_size() -> T::AccountIndex {
-		T::AccountIndex::sa(ENUM_SET_SIZE)
+	/// Ensure that leaving `who` with a free balance of `new_balance` doesn't touch funds which
+	/// are still locked by a vesting schedule or by another module.
+	fn ensure_vested(who: &T::AccountId, new_balance: T::Balance) -> Result {
+		if new_balance < Self::locked(who) {
+			return Err("vesting balance too high to send value");
+		}
+		if new_balance < T::LockedBalance::locked_balance(who) {
+			return Err("locked balance too high to send value");
+		}
+		Ok(())
 	}
 
-	/// Register a new account (with existential balance).
-	fn new_account(who: &T::AccountId, balance: T::Balance) -> NewAccountOutcome {
-		let enum_set_size = Self::enum_set_size();
-		let next_set_index = Self::next_enum_set();
-		let reclaim_index_magic = T::AccountIndex::sa(RECLAIM_INDEX_MAGIC);
-		let reclaim_index_modulus = T::AccountIndex::sa(256usize);
-		let quantization = T::AccountIndex::sa(256usize);
-
-		// A little easter-egg for reclaiming dead indexes..
-		let ret = {
-			// we quantise the number of accounts so it stays constant over a reasonable
-			// period of time.
-			let quantized_account_count: T::AccountIndex = (next_set_index * enum_set_size / quantization + One::one()) * quantization;
-			// then modify the starting balance to be modulo this to allow it to potentially
-			// identify an account index for reuse.
-			let maybe_try_index = balance % <T::Balance as As<T::AccountIndex>>::sa(quantized_account_count * reclaim_index_modulus);
-			let maybe_try_index = As::<T::AccountIndex>::as_(maybe_try_index);
-
-			// this identifier must end with magic byte 0x69 to trigger this check (a minor
-			// optimisation to ensure we don't check most unintended account creations).
-			if maybe_try_index % reclaim_index_modulus == reclaim_index_magic {
-				// reuse is probably intended. first, remove magic byte.
-				let try_index = maybe_try_index / reclaim_index_modulus;
-
-				// then check to see if this balance identifies a dead account index.
-				let set_index = try_index / enum_set_size;
-				let mut try_set = Self::enum_set(set_index);
-				let item_index = (try_index % enum_set_size).as_();
-				if item_index < try_set.len() {
-					if Self::total_balance(&try_set[item_index]).is_zero() {
-						// yup - this index refers to a dead account. can be reused.
-						try_set[item_index] = who.clone();
-						<EnumSet<T>>::insert(set_index, try_set);
-
-						Self::deposit_event(RawEvent::NewAccount(who.clone(), try_index, NewAccountOutcome::GoodHint));
-
-						return NewAccountOutcome::GoodHint
-					}
-				}
-				NewAccountOutcome::BadHint
-			} else {
-				NewAccountOutcome::NoHint
-			}
-		};
-
-		// insert normally as a back up
-		let mut set_index = next_set_index;
-		// defensive only: this loop should never iterate since we keep NextEnumSet up to date later.
-		let mut set = loop {
-			let set = Self::enum_set(set_index);
-			if set.len() < ENUM_SET_SIZE {
-				break set;
-			}
-			set_index += One::one();
-		};
-
-		let index = T::AccountIndex::sa(set_index.as_() * ENUM_SET_SIZE + set.len());
+	/// The fee owed for the weight of a dispatch at the current multiplier.
+	fn weight_fee(info: DispatchInfo) -> T::Balance {
+		let weight = Self::weight_fee_multiplier().apply_to(info.weight);
+		Self::transaction_weight_fee() * <T::Balance as As<u64>>::sa(weight)
+	}
 
-		// update set.
-		set.push(who.clone());
+	/// Move the weight fee multiplier towards keeping blocks at their target fullness.
+	fn update_weight_multiplier() {
+		let used = <system::Module<T>>::all_extrinsics_weight();
+		let limit = <system::Module<T>>::weight_limit(DispatchClass::Normal);
+		<WeightFeeMultiplier<T>>::mutate(|m| *m = m.next(used, limit));
+	}
 
-		// keep NextEnumSet up to date
-		if set.len() == ENUM_SET_SIZE {
-			<NextEnumSet<T>>::put(set_index + One::one());
+	/// Set the lock of `who` to what `schedule` leaves locked by now, dropping the schedule once
+	/// everything has vested.
+	fn update_lock(who: &T::AccountId, schedule: &VestingSchedule<T::Balance, T::BlockNumber>) {
+		let locked = schedule.locked_at(<system::Module<T>>::block_number());
+		if locked.is_zero() {
+			<Vesting<T>>::remove(who);
+			<Locked<T>>::remove(who);
+		} else {
+			<Locked<T>>::insert(who, locked);
 		}
+	}
 
-		// write set.
-		<EnumSet<T>>::insert(set_index, set);
-
-		Self::deposit_event(RawEvent::NewAccount(who.clone(), index, ret));
-
-		ret
+	/// Register a new account (with existential balance).
+	fn new_account(who: &T::AccountId, balance: T::Balance) {
+		<indices::Module<T>>::on_new_account(who);
+		Self::deposit_event(RawEvent::NewAccount(who.clone(), balance));
 	}
 
 	fn reap_account(who: &T::AccountId) {
 		<system::AccountNonce<T>>::remove(who);
+		<indices::Module<T>>::on_reaped_account(who);
 		Self::deposit_event(RawEvent::ReapedAccount(who.clone()));
 	}
 
@@ -629,6 +661,8 @@ impl<T: Trait> Module<T> {
 	fn on_free_too_low(who: &T::AccountId) {
 		Self::decrease_total_stake_by(Self::free_balance(who));
 		<FreeBalance<T>>::remove(who);
+		<Vesting<T>>::remove(who);
+		<Locked<T>>::remove(who);
 
 		T::OnFreeBalanceZero::on_free_balance_zero(who);
 
@@ -659,54 +693,20 @@ impl<T: Trait> Module<T> {
 			<TotalIssuance<T>>::put(v);
 		}
 	}
-
-	pub fn lookup(a: address::Address<T::AccountId, T::AccountIndex>) -> result::Result<T::AccountId, &'static str> {
-		match a {
-			address::Address::Id(i) => Ok(i),
-			address::Address::Index(i) => <Module<T>>::lookup_index(i).ok_or("invalid account index"),
-		}
-	}
-}
-
-pub struct ChainContext<T>(::rstd::marker::PhantomData<T>);
-impl<T> Default for ChainContext<T> {
-	fn default() -> Self {
-		ChainContext(::rstd::marker::PhantomData)
-	}
-}
-
-impl<T: Trait> Lookup for ChainContext<T> {
-	type Source = address::Address<T::AccountId, T::AccountIndex>;
-	type Target = T::AccountId;
-	fn lookup(&self, a: Self::Source) -> result::Result<Self::Target, &'static str> {
-		<Module<T>>::lookup(a)
-	}
-}
-
-impl<T: Trait> CurrentHeight for ChainContext<T> {
-	type BlockNumber = T::BlockNumber;
-	fn current_height(&self) -> Self::BlockNumber {
-		<system::Module<T>>::block_number()
-	}
-}
-
-impl<T: Trait> BlockNumberToHash for ChainContext<T> {
-	type BlockNumber = T::BlockNumber;
-	type Hash = T::Hash;
-	fn block_number_to_hash(&self, n: Self::BlockNumber) -> Option<Self::Hash> {
-		Some(<system::Module<T>>::block_hash(n))
-	}
 }
 
 impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
-	fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
+	fn make_payment(transactor: &T::AccountId, encoded_len: usize, info: DispatchInfo) -> Result {
 		let b = Self::free_balance(transactor);
-		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<u64>>::sa(encoded_len as u64);
+		let length_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<u64>>::sa(encoded_len as u64);
+		let transaction_fee = length_fee + Self::weight_fee(info);
 		if b < transaction_fee + Self::existential_deposit() {
 			return Err("not enough funds for transaction fee");
 		}
 		Self::set_free_balance(transactor, b - transaction_fee);
 		Self::decrease_total_stake_by(transaction_fee);
+		// a failed dispatch has used up its weight as well, so the whole fee is kept.
+		T::OnCollected::on_collected(transaction_fee);
 		Ok(())
 	}
 }
@@ -22,7 +22,7 @@ use primitives::BuildStorage;
 use primitives::testing::{Digest, DigestItem, Header};
 use substrate_primitives::{H256, Blake2Hasher};
 use runtime_io;
-use {GenesisConfig, Module, Trait, system};
+use {GenesisConfig, Module, Trait, system, indices};
 
 impl_outer_origin!{
 	pub enum Origin for Runtime {}
@@ -43,11 +43,16 @@ impl system::Trait for Runtime {
 	type Event = ();
 	type Log = DigestItem;
 }
+impl indices::Trait for Runtime {
+	type AccountIndex = u64;
+	type Event = ();
+}
 impl Trait for Runtime {
 	type Balance = u64;
-	type AccountIndex = u64;
 	type OnFreeBalanceZero = ();
 	type EnsureAccountLiquid = ();
+	type LockedBalance = ();
+	type OnCollected = ();
 	type Event = ();
 }
 
@@ -56,6 +61,7 @@ pub struct ExtBuilder {
 	transfer_fee: u64,
 	creation_fee: u64,
 	monied: bool,
+	vesting: bool,
 }
 impl Default for ExtBuilder {
 	fn default() -> Self {
@@ -64,6 +70,7 @@ impl Default for ExtBuilder {
 			transfer_fee: 0,
 			creation_fee: 0,
 			monied: false,
+			vesting: false,
 		}
 	}
 }
@@ -84,6 +91,10 @@ impl ExtBuilder {
 		self.monied = monied;
 		self
 	}
+	pub fn vesting(mut self, vesting: bool) -> Self {
+		self.vesting = vesting;
+		self
+	}
 	pub fn build(self) -> runtime_io::TestExternalities<Blake2Hasher> {
 		let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
 		let balance_factor = if self.existential_deposit > 0 {
@@ -91,18 +102,29 @@ impl ExtBuilder {
 		} else {
 			1
 		};
+		let balances = if self.monied {
+			vec![(1, 10 * balance_factor), (2, 20 * balance_factor), (3, 30 * balance_factor), (4, 40 * balance_factor)]
+		} else {
+			vec![(10, balance_factor), (20, balance_factor)]
+		};
+		t.extend(indices::GenesisConfig::<Runtime> {
+			ids: balances.iter().map(|&(who, _)| who).collect(),
+			reclaim_delay: 0,
+		}.build_storage().unwrap().0);
 		t.extend(GenesisConfig::<Runtime> {
-			balances: if self.monied {
-				vec![(1, 10 * balance_factor), (2, 20 * balance_factor), (3, 30 * balance_factor), (4, 40 * balance_factor)]
-			} else {
-				vec![(10, balance_factor), (20, balance_factor)]
-			},
+			balances,
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: self.existential_deposit,
 			transfer_fee: self.transfer_fee,
 			creation_fee: self.creation_fee,
-			reclaim_rebate: 0,
+			vesting: if self.vesting && self.monied {
+				vec![(1, 0, 10)]
+			} else {
+				vec![]
+			},
+			minimum_vested_transfer: 5,
 		}.build_storage().unwrap().0);
 		t.into()
 	}
@@ -110,3 +132,4 @@ impl ExtBuilder {
 
 pub type System = system::Module<Runtime>;
 pub type Balances = Module<Runtime>;
+pub type Indices = indices::Module<Runtime>;
@@ -19,7 +19,7 @@
 #![cfg(test)]
 
 use super::*;
-use mock::{Balances, ExtBuilder, Runtime, System};
+use mock::{Balances, ExtBuilder, Indices, Runtime, System};
 use runtime_io::with_externalities;
 
 #[test]
@@ -40,11 +40,11 @@ fn indexing_lookup_should_work() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(0), Some(1));
-			assert_eq!(Balances::lookup_index(1), Some(2));
-			assert_eq!(Balances::lookup_index(2), Some(3));
-			assert_eq!(Balances::lookup_index(3), Some(4));
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(0), Some(1));
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(2), Some(3));
+			assert_eq!(Indices::lookup_index(3), Some(4));
+			assert_eq!(Indices::lookup_index(4), None);
 		},
 	);
 }
@@ -57,9 +57,9 @@ fn default_indexing_on_new_accounts_should_work() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(4), None);
 			assert_ok!(Balances::transfer(Some(1).into(), 5.into(), 10.into()));
-			assert_eq!(Balances::lookup_index(4), Some(5));
+			assert_eq!(Indices::lookup_index(4), Some(5));
 		},
 	);
 }
@@ -73,10 +73,10 @@ fn default_indexing_on_new_accounts_should_work2() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(4), None);
 			// account 1 has 256 * 10 = 2560, account 5 is not exist, ext_deposit is 10, value is 10
 			assert_ok!(Balances::transfer(Some(1).into(), 5.into(), 10.into()));
-			assert_eq!(Balances::lookup_index(4), Some(5));
+			assert_eq!(Indices::lookup_index(4), Some(5));
 
 			assert_eq!(Balances::free_balance(&1), 256 * 10 - 10 - 50); // 10 is value, 50 is creation_free
 		},
@@ -92,13 +92,13 @@ fn default_indexing_on_new_accounts_should_not_work2() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(4), None);
 			// account 1 has 256 * 10 = 2560, account 5 is not exist, ext_deposit is 10, value is 9, not satisfies for ext_deposit
 			assert_noop!(
 				Balances::transfer(Some(1).into(), 5.into(), 9.into()),
 				"value too low to create account"
 			);
-			assert_eq!(Balances::lookup_index(4), None); // account 5 should not exist
+			assert_eq!(Indices::lookup_index(4), None); // account 5 should not exist
 			assert_eq!(Balances::free_balance(&1), 256 * 10);
 		},
 	);
@@ -152,16 +152,17 @@ fn reclaim_indexing_on_new_accounts_should_work() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(1), Some(2));
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(4), None);
 			assert_eq!(Balances::total_balance(&2), 256 * 20);
 
 			assert_ok!(Balances::transfer(Some(2).into(), 5.into(), (256 * 20).into())); // account 2 becomes zombie freeing index 1 for reclaim)
 			assert_eq!(Balances::total_balance(&2), 0);
+			assert_eq!(Indices::lookup_index(1), Some(5)); // account 5, created after that, takes index 1.
 
-			assert_ok!(Balances::transfer(Some(5).into(), 6.into(), (256 * 1 + 0x69).into())); // account 6 takes index 1.
-			assert_eq!(Balances::total_balance(&6), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(6));
+			assert_ok!(Balances::transfer(Some(5).into(), 6.into(), (256 * 1).into())); // account 6 gets a fresh index.
+			assert_eq!(Balances::total_balance(&6), 256 * 1);
+			assert_eq!(Indices::lookup_index(4), Some(6));
 		},
 	);
 }
@@ -174,16 +175,17 @@ fn reclaim_indexing_on_new_accounts_should_work2() {
 			.monied(true)
 			.build(),
 		|| {
-			assert_eq!(Balances::lookup_index(1), Some(2));
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(4), None);
 			assert_eq!(Balances::total_balance(&2), 256 * 20);
 
 			assert_ok!(Balances::transfer(Some(2).into(), 5.into(), (256 * 20 - 50).into())); // account 2 becomes zombie freeing index 1 for reclaim) 50 is creation fee
 			assert_eq!(Balances::total_balance(&2), 0);
+			assert_eq!(Indices::lookup_index(1), Some(5)); // account 5, created after that, takes index 1.
 
-			assert_ok!(Balances::transfer(Some(5).into(), 6.into(), (256 * 1 + 0x69).into())); // account 6 takes index 1.
-			assert_eq!(Balances::total_balance(&6), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(6));
+			assert_ok!(Balances::transfer(Some(5).into(), 2.into(), (256 * 1).into())); // account 2 comes back.
+			assert_eq!(Balances::total_balance(&2), 256 * 1);
+			assert_eq!(Indices::lookup_index(4), Some(2)); // but its old index is gone, so it gets a fresh one.
 		},
 	);
 }
@@ -197,8 +199,8 @@ fn reserved_balance_should_prevent_reclaim_count() {
 			.build(),
 		|| {
 			System::inc_account_nonce(&2);
-			assert_eq!(Balances::lookup_index(1), Some(2));
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(4), None);
 			assert_eq!(Balances::total_balance(&2), 256 * 20);
 
 			assert_ok!(Balances::reserve(&2, 256 * 19 + 1)); // account 2 becomes mostly reserved
@@ -206,18 +208,19 @@ fn reserved_balance_should_prevent_reclaim_count() {
 			assert_eq!(Balances::total_balance(&2), 256 * 19 + 1); // reserve still exists.
 			assert_eq!(System::account_nonce(&2), 1);
 
-			assert_ok!(Balances::transfer(Some(4).into(), 5.into(), (256 * 1 + 0x69).into())); // account 4 tries to take index 1 for account 5.
-			assert_eq!(Balances::total_balance(&5), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(2)); // but fails.
+			assert_ok!(Balances::transfer(Some(4).into(), 5.into(), (256 * 1).into())); // account 4 creates account 5.
+			assert_eq!(Balances::total_balance(&5), 256 * 1);
+			assert_eq!(Indices::lookup_index(1), Some(2)); // which can't take index 1 yet.
+			assert_eq!(Indices::lookup_index(4), Some(5));
 			assert_eq!(System::account_nonce(&2), 1);
 
 			assert_eq!(Balances::slash(&2, 256 * 18 + 2), None); // account 2 gets slashed
 			assert_eq!(Balances::total_balance(&2), 0); // "free" account deleted."
 			assert_eq!(System::account_nonce(&2), 0);
 
-			assert_ok!(Balances::transfer(Some(4).into(), 6.into(), (256 * 1 + 0x69).into())); // account 4 tries to take index 1 again for account 6.
-			assert_eq!(Balances::total_balance(&6), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(6)); // and succeeds.
+			assert_ok!(Balances::transfer(Some(4).into(), 6.into(), (256 * 1).into())); // account 4 creates account 6.
+			assert_eq!(Balances::total_balance(&6), 256 * 1);
+			assert_eq!(Indices::lookup_index(1), Some(6)); // which takes index 1.
 		},
 	);
 }
@@ -231,8 +234,8 @@ fn reserved_balance_should_prevent_reclaim_count2() {
 			.build(),
 		|| {
 			System::inc_account_nonce(&2);
-			assert_eq!(Balances::lookup_index(1), Some(2));
-			assert_eq!(Balances::lookup_index(4), None);
+			assert_eq!(Indices::lookup_index(1), Some(2));
+			assert_eq!(Indices::lookup_index(4), None);
 			assert_eq!(Balances::total_balance(&2), 256 * 20);
 
 			assert_ok!(Balances::reserve(&2, 256 * 19 + 1)); // account 2 becomes mostly reserved
@@ -240,18 +243,19 @@ fn reserved_balance_should_prevent_reclaim_count2() {
 			assert_eq!(Balances::total_balance(&2), 256 * 19 + 1); // reserve still exists.
 			assert_eq!(System::account_nonce(&2), 1);
 
-			assert_ok!(Balances::transfer(Some(4).into(), 5.into(), (256 * 1 + 0x69).into())); // account 4 tries to take index 1 for account 5.
-			assert_eq!(Balances::total_balance(&5), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(2)); // but fails.
+			assert_ok!(Balances::transfer(Some(4).into(), 5.into(), (256 * 1).into())); // account 4 creates account 5.
+			assert_eq!(Balances::total_balance(&5), 256 * 1);
+			assert_eq!(Indices::lookup_index(1), Some(2)); // which can't take index 1 yet.
+			assert_eq!(Indices::lookup_index(4), Some(5));
 			assert_eq!(System::account_nonce(&2), 1);
 
 			assert_eq!(Balances::slash(&2, 256 * 18 + 2), None); // account 2 gets slashed
 			assert_eq!(Balances::total_balance(&2), 0); // "free" account deleted."
 			assert_eq!(System::account_nonce(&2), 0);
 
-			assert_ok!(Balances::transfer(Some(4).into(), 6.into(), (256 * 1 + 0x69).into())); // account 4 tries to take index 1 again for account 6.
-			assert_eq!(Balances::total_balance(&6), 256 * 1 + 0x69);
-			assert_eq!(Balances::lookup_index(1), Some(6)); // and succeeds.
+			assert_ok!(Balances::transfer(Some(4).into(), 6.into(), (256 * 1).into())); // account 4 creates account 6.
+			assert_eq!(Balances::total_balance(&6), 256 * 1);
+			assert_eq!(Indices::lookup_index(1), Some(6)); // which takes index 1.
 		},
 	);
 }
@@ -498,3 +502,95 @@ fn transfer_overflow_isnt_exploitable() {
 		}
 	);
 }
+
+#[test]
+fn vesting_balance_should_be_locked() {
+	with_externalities(&mut ExtBuilder::default().monied(true).vesting(true).build(), || {
+		assert_eq!(Balances::vesting(&1), Some(VestingSchedule { locked: 10, per_block: 1, starting_block: 0 }));
+		assert_eq!(Balances::locked(&1), 10);
+		assert_noop!(Balances::transfer(Some(1).into(), 2.into(), 1.into()), "vesting balance too high to send value");
+		assert_noop!(Balances::reserve(&1, 1), "vesting balance too high to send value");
+
+		System::set_block_number(4);
+		// nothing is unlocked before vesting.
+		assert_noop!(Balances::transfer(Some(1).into(), 2.into(), 1.into()), "vesting balance too high to send value");
+		assert_ok!(Balances::vest(Some(1).into()));
+		assert_eq!(Balances::locked(&1), 6);
+		assert_ok!(Balances::transfer(Some(1).into(), 2.into(), 4.into()));
+		assert_noop!(Balances::transfer(Some(1).into(), 2.into(), 1.into()), "vesting balance too high to send value");
+
+		System::set_block_number(10);
+		assert_ok!(Balances::vest(Some(1).into()));
+		assert_eq!(Balances::vesting(&1), None);
+		assert_eq!(Balances::locked(&1), 0);
+		assert_ok!(Balances::transfer(Some(1).into(), 2.into(), 6.into()));
+		assert_noop!(Balances::vest(Some(1).into()), "no vesting schedule");
+	});
+}
+
+#[test]
+fn genesis_vesting_rounds_up_without_overflow() {
+	let config = |balance, length| GenesisConfig::<Runtime> {
+		balances: vec![(1, balance)],
+		transaction_base_fee: 0,
+		transaction_byte_fee: 0,
+		transaction_weight_fee: 0,
+		existential_deposit: 0,
+		transfer_fee: 0,
+		creation_fee: 0,
+		vesting: vec![(1, 0, length)],
+		minimum_vested_transfer: 0,
+	};
+	let per_block = |balance, length| genesis_vesting(&config(balance, length))[0].1.per_block;
+
+	assert_eq!(per_block(10, 5), 2);
+	assert_eq!(per_block(10, 3), 4);
+	assert_eq!(per_block(u64::max_value(), 2), u64::max_value() / 2 + 1);
+	assert_eq!(per_block(u64::max_value(), 0), u64::max_value());
+}
+
+#[test]
+fn vested_transfer_should_lock_the_balance() {
+	with_externalities(&mut ExtBuilder::default().monied(true).vesting(true).build(), || {
+		System::set_block_number(1);
+		let schedule = VestingSchedule { locked: 10, per_block: 2, starting_block: 2 };
+		assert_noop!(
+			Balances::vested_transfer(Some(3).into(), 1.into(), schedule),
+			"destination already has a vesting schedule"
+		);
+		assert_noop!(
+			Balances::vested_transfer(Some(3).into(), 3.into(), schedule),
+			"cannot make a vested transfer to oneself"
+		);
+		assert_noop!(
+			Balances::vested_transfer(Some(3).into(), 5.into(), VestingSchedule { locked: 4, per_block: 2, starting_block: 2 }),
+			"vested transfer is too small"
+		);
+		assert_ok!(Balances::vested_transfer(Some(3).into(), 5.into(), schedule));
+		assert_eq!(Balances::free_balance(&5), 10);
+		assert_eq!(Balances::locked(&5), 10);
+
+		System::set_block_number(3);
+		assert_ok!(Balances::vest(Some(5).into()));
+		assert_eq!(Balances::locked(&5), 8);
+		assert_noop!(Balances::transfer(Some(5).into(), 2.into(), 3.into()), "vesting balance too high to send value");
+		assert_ok!(Balances::transfer(Some(5).into(), 2.into(), 2.into()));
+	});
+}
+
+#[test]
+fn weight_fee_multiplier_should_follow_block_fullness() {
+	with_externalities(&mut ExtBuilder::default().build(), || {
+		use primitives::traits::OnFinalise;
+		let one = WeightMultiplier::default();
+		let limit = System::weight_limit(DispatchClass::Normal);
+
+		<Balances as OnFinalise<u64>>::on_finalise(1);
+		assert_eq!(Balances::weight_fee_multiplier(), one);
+
+		System::note_extrinsic_weight(DispatchInfo { weight: limit, class: DispatchClass::Normal });
+		<Balances as OnFinalise<u64>>::on_finalise(1);
+		assert_eq!(Balances::weight_fee_multiplier(), one.next(limit, limit));
+		assert!(Balances::weight_fee_multiplier() > one);
+	});
+}
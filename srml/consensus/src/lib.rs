@@ -37,20 +37,19 @@ extern crate sr_primitives as primitives;
 extern crate parity_codec as codec;
 extern crate srml_system as system;
 extern crate substrate_primitives;
-
-#[cfg(test)]
 extern crate sr_io as runtime_io;
 
 use rstd::prelude::*;
 use rstd::result;
-use parity_codec::Encode;
+use parity_codec::{Encode, Decode};
 use runtime_support::{storage, Parameter};
-use runtime_support::dispatch::Result;
+use runtime_support::dispatch::{Result, SimpleDispatchInfo};
 use runtime_support::storage::StorageValue;
 use runtime_support::storage::unhashed::StorageVec;
 use primitives::RuntimeString;
+use primitives::inherents::{InherentIdentifier, well_known_identifiers};
 use primitives::traits::{
-	MaybeSerializeDebug, Member, ProvideInherent, Block as BlockT
+	MaybeSerializeDebug, Member, ProvideInherent, Verify, Block as BlockT
 };
 use substrate_primitives::storage::well_known_keys;
 use system::{ensure_signed, ensure_inherent};
@@ -66,6 +65,23 @@ impl<S: codec::Codec + Default> StorageVec for AuthorityStorageVec<S> {
 
 pub type KeyValue = (Vec<u8>, Vec<u8>);
 
+/// The leading fields of an encoded `RuntimeVersion`; the rest of it can't be decoded within the
+/// runtime, and isn't needed to tell whether some code is an upgrade.
+#[derive(Decode)]
+struct RuntimeSpec {
+	spec_name: Vec<u8>,
+	_impl_name: Vec<u8>,
+	_authoring_version: u32,
+	spec_version: u32,
+}
+
+impl RuntimeSpec {
+	/// Read the runtime spec of the given wasm code, if it is a runtime.
+	fn of(code: &[u8]) -> Option<Self> {
+		runtime_io::runtime_version(code).and_then(|v| Self::decode(&mut &v[..]))
+	}
+}
+
 pub trait OnOfflineValidator {
 	fn on_offline_validator(validator_index: usize);
 }
@@ -74,6 +90,59 @@ impl OnOfflineValidator for () {
 	fn on_offline_validator(_validator_index: usize) {}
 }
 
+pub trait OnMisbehavior {
+	fn on_misbehavior(validator_index: usize);
+}
+
+impl OnMisbehavior for () {
+	fn on_misbehavior(_validator_index: usize) {}
+}
+
+/// An equivocation of an authority in a BFT round: the round number and two conflicting
+/// digests, each together with the authority's signature on it.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone)]
+pub enum MisbehaviorKind<Hash, Signature> {
+	/// Prepared two different digests in the same round.
+	BftDoublePrepare(u32, (Hash, Signature), (Hash, Signature)),
+	/// Committed to two different digests in the same round.
+	BftDoubleCommit(u32, (Hash, Signature), (Hash, Signature)),
+	/// Proposed two different blocks in the same round.
+	BftDoublePropose(u32, (Hash, Signature), (Hash, Signature)),
+}
+
+/// A report of an authority misbehaving while building on top of the given parent block.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone)]
+pub struct MisbehaviorReport<Hash, Number, SessionKey, Signature> {
+	/// The parent hash of the round in which the misbehavior happened.
+	pub parent_hash: Hash,
+	/// The parent number of the round in which the misbehavior happened.
+	pub parent_number: Number,
+	/// The misbehaving authority.
+	pub target: SessionKey,
+	/// The misbehavior itself.
+	pub misbehavior: MisbehaviorKind<Hash, Signature>,
+}
+
+pub type MisbehaviorReportFor<T> = MisbehaviorReport<
+	<T as system::Trait>::Hash,
+	<T as system::Trait>::BlockNumber,
+	<T as Trait>::SessionKey,
+	<T as Trait>::Signature,
+>;
+
+// the actions signed by authorities in a BFT round, encoded the same way as by the
+// agreement layer (the full proposal is never needed here, so it is left out).
+#[derive(Encode)]
+enum BftAction<Hash> {
+	Prepare(u32, Hash),
+	Commit(u32, Hash),
+	#[allow(dead_code)]
+	AdvanceRound(u32),
+	ProposeHeader(u32, Hash),
+}
+
 pub type Log<T> = RawLog<
 	<T as Trait>::SessionKey,
 >;
@@ -115,8 +184,12 @@ pub trait Trait: system::Trait {
 	/// Type for all log entries of this module.
 	type Log: From<Log<Self>> + Into<system::DigestItemOf<Self>>;
 
-	type SessionKey: Parameter + Default + MaybeSerializeDebug;
+	/// The signature authorities use to sign consensus messages.
+	type Signature: Parameter + Verify;
+	type SessionKey: Parameter + Default + MaybeSerializeDebug + Into<<Self::Signature as Verify>::Signer>;
 	type OnOfflineValidator: OnOfflineValidator;
+	/// Handler for authorities proven to have misbehaved.
+	type OnMisbehavior: OnMisbehavior;
 }
 
 decl_storage! {
@@ -124,6 +197,12 @@ decl_storage! {
 		// Authorities set actual at the block execution start. IsSome only if
 		// the set has been changed.
 		OriginalAuthorities: Option<Vec<T::SessionKey>>;
+		/// Authorities already reported for misbehaving on top of a given parent block.
+		ReportedMisbehavior get(reported_misbehavior): map (T::Hash, T::SessionKey) => bool;
+		/// The keys of `ReportedMisbehavior`, so that the reports can be cleared.
+		ReportedMisbehaviorKeys: Vec<(T::Hash, T::SessionKey)>;
+		/// Misbehavior on top of blocks before this one is no longer accepted.
+		MisbehaviorReportsFrom get(misbehavior_reports_from): T::BlockNumber;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::SessionKey>;
@@ -145,10 +224,21 @@ decl_storage! {
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
-		/// Report some misbehaviour.
-		fn report_misbehavior(origin, _report: Vec<u8>) -> Result {
+		/// Report an authority of the current set which equivocated while building on top of a
+		/// block of this chain. Each authority is punished at most once per parent block.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn report_misbehavior(
+			origin,
+			report: MisbehaviorReport<T::Hash, T::BlockNumber, T::SessionKey, T::Signature>
+		) -> Result {
 			ensure_signed(origin)?;
-			// TODO.
+			let validator_index = Self::check_misbehavior_report(&report)?;
+
+			let key = (report.parent_hash, report.target);
+			<ReportedMisbehavior<T>>::insert(&key, true);
+			<ReportedMisbehaviorKeys<T>>::mutate(|keys| keys.push(key));
+			T::OnMisbehavior::on_misbehavior(validator_index);
+
 			Ok(())
 		}
 
@@ -182,9 +272,12 @@ decl_module! {
 			Ok(())
 		}
 
-		/// Set the new code.
+		/// Set the new code. It must be a build of the same runtime with a higher `spec_version`.
+		#[weight = SimpleDispatchInfo::FixedOperational(1_000_000)]
 		pub fn set_code(new: Vec<u8>) -> Result {
+			Self::check_code_upgrade(&new)?;
 			storage::unhashed::put_raw(well_known_keys::CODE, &new);
+			<system::Module<T>>::deposit_event(system::Event::CodeUpdated.into());
 			Ok(())
 		}
 
@@ -233,6 +326,73 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Forget the misbehavior reports, e.g. at the end of an era.
+	///
+	/// Misbehavior on top of blocks before the current one can't be reported any more, so that
+	/// forgotten reports can't be replayed.
+	pub fn clear_misbehavior_reports() {
+		for key in <ReportedMisbehaviorKeys<T>>::take() {
+			<ReportedMisbehavior<T>>::remove(&key);
+		}
+		<MisbehaviorReportsFrom<T>>::put(<system::Module<T>>::block_number());
+	}
+
+	/// Check that a misbehavior report is valid and new, returning the index of the
+	/// misbehaving authority in the current set.
+	fn check_misbehavior_report(report: &MisbehaviorReportFor<T>) -> result::Result<usize, &'static str> {
+		if report.parent_number >= <system::Module<T>>::block_number()
+			|| <system::Module<T>>::block_hash(report.parent_number) != report.parent_hash
+		{
+			return Err("Misbehavior report parent is not a known block");
+		}
+		if report.parent_number < Self::misbehavior_reports_from() {
+			return Err("Misbehavior report is too old");
+		}
+
+		let validator_index = Self::authorities().iter().position(|a| a == &report.target)
+			.ok_or("Misbehaving authority is not part of the current set")?;
+
+		if Self::reported_misbehavior((report.parent_hash, report.target.clone())) {
+			return Err("Misbehavior already reported");
+		}
+
+		let (action_1, s_1, action_2, s_2) = match report.misbehavior {
+			MisbehaviorKind::BftDoublePrepare(round, (h_1, ref s_1), (h_2, ref s_2)) => {
+				ensure!(h_1 != h_2, "Prepared digests are not conflicting");
+				(BftAction::Prepare(round, h_1), s_1, BftAction::Prepare(round, h_2), s_2)
+			}
+			MisbehaviorKind::BftDoubleCommit(round, (h_1, ref s_1), (h_2, ref s_2)) => {
+				ensure!(h_1 != h_2, "Committed digests are not conflicting");
+				(BftAction::Commit(round, h_1), s_1, BftAction::Commit(round, h_2), s_2)
+			}
+			MisbehaviorKind::BftDoublePropose(round, (h_1, ref s_1), (h_2, ref s_2)) => {
+				ensure!(h_1 != h_2, "Proposed digests are not conflicting");
+				(BftAction::ProposeHeader(round, h_1), s_1, BftAction::ProposeHeader(round, h_2), s_2)
+			}
+		};
+
+		let signer: <T::Signature as Verify>::Signer = report.target.clone().into();
+		let signed = |action: BftAction<T::Hash>, signature: &T::Signature| {
+			let message = (report.parent_hash, action).encode();
+			signature.verify(&message[..], &signer)
+		};
+		ensure!(signed(action_1, s_1) && signed(action_2, s_2), "Invalid misbehavior signature");
+
+		Ok(validator_index)
+	}
+
+	/// Check that `new` is a runtime that may replace the current code.
+	fn check_code_upgrade(new: &[u8]) -> Result {
+		let current = storage::unhashed::get_raw(well_known_keys::CODE)
+			.and_then(|code| RuntimeSpec::of(&code))
+			.ok_or("Current code has no runtime version")?;
+		let new = RuntimeSpec::of(new).ok_or("New code has no runtime version")?;
+
+		ensure!(new.spec_name == current.spec_name, "New code is a different runtime");
+		ensure!(new.spec_version > current.spec_version, "New code must have a higher spec_version");
+		Ok(())
+	}
+
 	/// Save original authorities set.
 	fn save_original_authorities(current_authorities: Option<Vec<T::SessionKey>>) {
 		if OriginalAuthorities::<T>::get().is_some() {
@@ -251,9 +411,12 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> ProvideInherent for Module<T> {
+	const INHERENT_IDENTIFIER: InherentIdentifier = well_known_identifiers::OFFLINE_INDICES;
 	type Inherent = Vec<u32>;
 	type Call = Call<T>;
 	type Error = RuntimeString;
+	// only the author knows which authorities it saw offline; importing nodes don't provide this.
+	const INHERENT_REQUIRED: bool = false;
 
 	fn create_inherent_extrinsics(data: Self::Inherent) -> Vec<(u32, Self::Call)> {
 		vec![(T::NOTE_OFFLINE_POSITION, Call::note_offline(data))]
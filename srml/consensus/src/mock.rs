@@ -18,7 +18,7 @@
 
 #![cfg(test)]
 
-use primitives::{BuildStorage, testing::{Digest, DigestItem, Header}};
+use primitives::{BuildStorage, testing::{Digest, DigestItem, Header, TestSignature}};
 use runtime_io;
 use substrate_primitives::{H256, Blake2Hasher};
 use {GenesisConfig, Trait, Module, system};
@@ -33,8 +33,10 @@ pub struct Test;
 impl Trait for Test {
 	const NOTE_OFFLINE_POSITION: u32 = 1;
 	type Log = DigestItem;
+	type Signature = TestSignature;
 	type SessionKey = u64;
 	type OnOfflineValidator = ();
+	type OnMisbehavior = ();
 }
 impl system::Trait for Test {
 	type Origin = Origin;
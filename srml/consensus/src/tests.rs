@@ -18,10 +18,30 @@
 
 #![cfg(test)]
 
+use super::*;
 use primitives::{generic, testing, traits::OnFinalise};
+use primitives::testing::TestSignature;
 use runtime_io::with_externalities;
 use substrate_primitives::H256;
-use mock::{Consensus, System, new_test_ext};
+use mock::{Consensus, Origin, System, new_test_ext};
+
+fn sign(target: u64, parent_hash: H256, action: BftAction<H256>) -> TestSignature {
+	TestSignature(target, (parent_hash, action).encode())
+}
+
+fn double_prepare_report(target: u64, signer: u64, parent_hash: H256) -> MisbehaviorReport<H256, u64, u64, TestSignature> {
+	let (h_1, h_2) = (H256::from([1u8; 32]), H256::from([2u8; 32]));
+	MisbehaviorReport {
+		parent_hash,
+		parent_number: 1,
+		target,
+		misbehavior: MisbehaviorKind::BftDoublePrepare(
+			3,
+			(h_1, sign(signer, parent_hash, BftAction::Prepare(3, h_1))),
+			(h_2, sign(signer, parent_hash, BftAction::Prepare(3, h_2))),
+		),
+	}
+}
 
 #[test]
 fn authorities_change_logged() {
@@ -63,3 +83,115 @@ fn authorities_change_is_not_logged_when_changed_back_to_original() {
 		});
 	});
 }
+
+#[test]
+fn misbehavior_report_is_accepted_once() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let parent_hash = H256::from([42u8; 32]);
+		System::initialise(&2, &parent_hash, &Default::default());
+
+		assert!(!Consensus::reported_misbehavior((parent_hash, 2)));
+		assert_ok!(Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 2, parent_hash)));
+		assert!(Consensus::reported_misbehavior((parent_hash, 2)));
+
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 2, parent_hash)),
+			"Misbehavior already reported"
+		);
+	});
+}
+
+#[test]
+fn cleared_misbehavior_reports_cannot_be_replayed() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let parent_hash = H256::from([42u8; 32]);
+		System::initialise(&2, &parent_hash, &Default::default());
+		assert_ok!(Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 2, parent_hash)));
+
+		Consensus::clear_misbehavior_reports();
+		assert!(!Consensus::reported_misbehavior((parent_hash, 2)));
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 2, parent_hash)),
+			"Misbehavior report is too old"
+		);
+
+		// misbehavior on top of newer blocks is still accepted.
+		let parent_hash = H256::from([43u8; 32]);
+		System::initialise(&3, &parent_hash, &Default::default());
+		let mut report = double_prepare_report(2, 2, parent_hash);
+		report.parent_number = 2;
+		assert_ok!(Consensus::report_misbehavior(Origin::signed(1), report));
+	});
+}
+
+#[test]
+fn misbehavior_report_with_invalid_signatures_is_rejected() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let parent_hash = H256::from([42u8; 32]);
+		System::initialise(&2, &parent_hash, &Default::default());
+
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 3, parent_hash)),
+			"Invalid misbehavior signature"
+		);
+
+		let h = H256::from([1u8; 32]);
+		let mut report = double_prepare_report(2, 2, parent_hash);
+		report.misbehavior = MisbehaviorKind::BftDoubleCommit(
+			3,
+			(h, sign(2, parent_hash, BftAction::Commit(3, h))),
+			(h, sign(2, parent_hash, BftAction::Commit(3, h))),
+		);
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), report),
+			"Committed digests are not conflicting"
+		);
+	});
+}
+
+#[test]
+fn misbehavior_report_must_target_known_block_and_authority() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		let parent_hash = H256::from([42u8; 32]);
+		System::initialise(&2, &parent_hash, &Default::default());
+
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(4, 4, parent_hash)),
+			"Misbehaving authority is not part of the current set"
+		);
+		assert_noop!(
+			Consensus::report_misbehavior(Origin::signed(1), double_prepare_report(2, 2, H256::from([43u8; 32]))),
+			"Misbehavior report parent is not a known block"
+		);
+	});
+}
+
+// reads the code itself as the encoded runtime version.
+struct CodeIsVersion;
+
+impl runtime_io::ReadRuntimeVersion for CodeIsVersion {
+	fn read_runtime_version(&self, code: &[u8]) -> Option<Vec<u8>> {
+		if code.is_empty() { None } else { Some(code.to_vec()) }
+	}
+}
+
+fn code(spec_name: &[u8], spec_version: u32) -> Vec<u8> {
+	(spec_name.to_vec(), b"impl".to_vec(), 1u32, spec_version, 1u32).encode()
+}
+
+#[test]
+fn set_code_requires_a_higher_spec_version() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3]), || {
+		runtime_io::with_runtime_version_reader(&mut CodeIsVersion, || {
+			assert_noop!(Consensus::set_code(code(b"test", 2)), "Current code has no runtime version");
+			storage::unhashed::put_raw(well_known_keys::CODE, &code(b"test", 2));
+
+			assert_noop!(Consensus::set_code(vec![]), "New code has no runtime version");
+			assert_noop!(Consensus::set_code(code(b"other", 3)), "New code is a different runtime");
+			assert_noop!(Consensus::set_code(code(b"test", 2)), "New code must have a higher spec_version");
+
+			assert_ok!(Consensus::set_code(code(b"test", 3)));
+			assert_eq!(storage::unhashed::get_raw(well_known_keys::CODE), Some(code(b"test", 3)));
+		});
+	});
+}
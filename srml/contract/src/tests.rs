@@ -55,11 +55,16 @@ impl system::Trait for Test {
 	type Event = MetaEvent;
 	type Log = DigestItem;
 }
+impl indices::Trait for Test {
+	type AccountIndex = u64;
+	type Event = ();
+}
 impl balances::Trait for Test {
 	type Balance = u64;
-	type AccountIndex = u64;
 	type OnFreeBalanceZero = Contract;
 	type EnsureAccountLiquid = ();
+	type LockedBalance = ();
+	type OnCollected = ();
 	type Event = MetaEvent;
 }
 impl Trait for Test {
@@ -127,10 +132,12 @@ impl ExtBuilder {
 				balances: vec![],
 				transaction_base_fee: 0,
 				transaction_byte_fee: 0,
+				transaction_weight_fee: 0,
 				existential_deposit: self.existential_deposit,
 				transfer_fee: self.transfer_fee,
 				creation_fee: self.creation_fee,
-				reclaim_rebate: 0,
+				vesting: vec![],
+				minimum_vested_transfer: 0,
 			}.build_storage()
 			.unwrap().0,
 		);
@@ -224,8 +231,7 @@ fn contract_transfer() {
 				event: MetaEvent::balances(
 					balances::RawEvent::NewAccount(
 						CONTRACT_SHOULD_TRANSFER_TO,
-						0,
-						balances::NewAccountOutcome::NoHint
+						CONTRACT_SHOULD_TRANSFER_VALUE
 					)
 				),
 			},
@@ -544,8 +550,7 @@ fn contract_create() {
 				event: MetaEvent::balances(
 					balances::RawEvent::NewAccount(
 						derived_address,
-						0,
-						balances::NewAccountOutcome::NoHint
+						3
 					)
 				),
 			},
@@ -38,7 +38,10 @@ extern crate sr_io as runtime_io;
 #[macro_use] extern crate srml_support;
 extern crate sr_primitives as primitives;
 extern crate srml_balances as balances;
+extern crate srml_indices as indices;
 extern crate srml_democracy as democracy;
+#[cfg(test)]
+extern crate srml_scheduler as scheduler;
 extern crate srml_system as system;
 
 pub mod voting;
@@ -93,15 +96,26 @@ mod tests {
 		type Event = Event;
 		type Log = DigestItem;
 	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
 	impl balances::Trait for Test {
 		type Balance = u64;
-		type AccountIndex = u64;
 		type OnFreeBalanceZero = ();
 		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
 		type Event = Event;
 	}
+	impl scheduler::Trait for Test {
+		type Call = Call;
+		type Event = ();
+	}
 	impl democracy::Trait for Test {
 		type Proposal = Call;
+		type CancellationOrigin = system::EnsureRoot<u64>;
+		type Scheduler = scheduler::Module<Test>;
 		type Event = Event;
 	}
 	impl seats::Trait for Test {
@@ -122,14 +136,17 @@ mod tests {
 			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 0,
 			transfer_fee: 0,
 			creation_fee: 0,
-			reclaim_rebate: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
 		}.build_storage().unwrap().0);
 		t.extend(democracy::GenesisConfig::<Test>{
 			launch_period: 1,
 			voting_period: 3,
+			enactment_period: 0,
 			minimum_deposit: 1,
 		}.build_storage().unwrap().0);
 		t.extend(seats::GenesisConfig::<Test> {
@@ -21,7 +21,7 @@ use rstd::result;
 use codec::Compact;
 use substrate_primitives::u32_trait::Value as U32;
 use primitives::traits::{Hash, EnsureOrigin, MaybeSerializeDebug};
-use srml_support::dispatch::{Result, Dispatchable, Parameter};
+use srml_support::dispatch::{Result, Dispatchable, Parameter, SimpleDispatchInfo};
 use srml_support::{StorageValue, StorageMap};
 use super::{Trait as CouncilTrait, Module as Council};
 use system::{self, ensure_signed};
@@ -44,8 +44,8 @@ pub trait Trait: CouncilTrait + MaybeSerializeDebug {
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub enum Origin {
-	/// It has been condoned by a given number of council members.
-	Members(u32),
+	/// It has been condoned by a given number of council members, out of the given council size.
+	Members(u32, u32),
 }
 
 /// Event for this module.
@@ -69,6 +69,7 @@ decl_module! {
 	#[cfg_attr(feature = "std", serde(bound(deserialize = "<T as Trait>::Proposal: ::serde::de::DeserializeOwned")))]
 	pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
 		fn deposit_event() = default;
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
 		fn propose(origin, threshold: Compact<u32>, proposal: Box<<T as Trait>::Proposal>) -> Result {
 			let who = ensure_signed(origin)?;
 			let threshold = threshold.into();
@@ -80,7 +81,8 @@ decl_module! {
 			ensure!(!<ProposalOf<T>>::exists(proposal_hash), "duplicate proposals not allowed");
 
 			if threshold < 2 {
-				let ok = proposal.dispatch(Origin::Members(1).into()).is_ok();
+				let council_size = <Council<T>>::active_council().len() as u32;
+				let ok = proposal.dispatch(Origin::Members(1, council_size).into()).is_ok();
 				Self::deposit_event(RawEvent::Executed(proposal_hash, ok));
 			} else {
 				let index = Self::proposal_count();
@@ -94,6 +96,7 @@ decl_module! {
 			Ok(())
 		}
 
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
 		fn vote(origin, proposal: T::Hash, index: Compact<ProposalIndex>, approve: bool) -> Result {
 			let who = ensure_signed(origin)?;
 			let index = index.into();
@@ -140,7 +143,7 @@ decl_module! {
 
 					// execute motion, assuming it exists.
 					if let Some(p) = <ProposalOf<T>>::take(&proposal) {
-						let ok = p.dispatch(Origin::Members(threshold).into()).is_ok();
+						let ok = p.dispatch(Origin::Members(threshold, potential_votes).into()).is_ok();
 						Self::deposit_event(RawEvent::Executed(proposal, ok));
 					}
 				} else {
@@ -191,11 +194,22 @@ pub fn ensure_council_members<OuterOrigin>(o: OuterOrigin, n: u32) -> result::Re
 	where OuterOrigin: Into<Option<Origin>>
 {
 	match o.into() {
-		Some(Origin::Members(x)) if x >= n => Ok(n),
+		Some(Origin::Members(x, _)) if x >= n => Ok(n),
 		_ => Err("bad origin: expected to be a threshold number of council members"),
 	}
 }
 
+/// Ensure that the origin `o` represents at least the proportion `n / d` of the council members.
+/// Returns `Ok` with the number of members or an `Err` otherwise.
+pub fn ensure_council_proportion<OuterOrigin>(o: OuterOrigin, n: u32, d: u32) -> result::Result<u32, &'static str>
+	where OuterOrigin: Into<Option<Origin>>
+{
+	match o.into() {
+		Some(Origin::Members(x, total)) if x as u64 * d as u64 >= n as u64 * total as u64 => Ok(x),
+		_ => Err("bad origin: expected to be a proportion of council members"),
+	}
+}
+
 pub struct EnsureMembers<N: U32>(::rstd::marker::PhantomData<N>);
 impl<O, N: U32> EnsureOrigin<O> for EnsureMembers<N>
 	where O: Into<Option<Origin>>
@@ -206,6 +220,16 @@ impl<O, N: U32> EnsureOrigin<O> for EnsureMembers<N>
 	}
 }
 
+pub struct EnsureProportionAtLeast<N: U32, D: U32>(::rstd::marker::PhantomData<(N, D)>);
+impl<O, N: U32, D: U32> EnsureOrigin<O> for EnsureProportionAtLeast<N, D>
+	where O: Into<Option<Origin>>
+{
+	type Success = u32;
+	fn ensure_origin(o: O) -> result::Result<Self::Success, &'static str> {
+		ensure_council_proportion(o, N::VALUE, D::VALUE)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -225,7 +249,7 @@ mod tests {
 	}
 
 	fn set_balance_proposal(value: u64) -> Call {
-		Call::Balances(balances::Call::set_balance(balances::address::Address::Id(42), value.into(), 0.into()))
+		Call::Balances(balances::Call::set_balance(indices::address::Address::Id(42), value.into(), 0.into()))
 	}
 
 	#[test]
@@ -360,4 +384,11 @@ mod tests {
 			]);
 		});
 	}
+
+	#[test]
+	fn proportion_origin_works() {
+		assert_eq!(ensure_council_proportion(Some(super::Origin::Members(2, 3)), 2, 3), Ok(2));
+		assert!(ensure_council_proportion(Some(super::Origin::Members(2, 4)), 2, 3).is_err());
+		assert!(ensure_council_proportion(None::<super::Origin>, 1, 2).is_err());
+	}
 }
@@ -20,9 +20,10 @@ use rstd::prelude::*;
 use codec::{Compact, HasCompact};
 use primitives::traits::{Zero, One, As};
 use runtime_io::print;
-use srml_support::{StorageValue, StorageMap, dispatch::Result};
+use srml_support::{StorageValue, StorageMap, dispatch::{Result, SimpleDispatchInfo}};
 use democracy;
-use balances::{self, address::Address};
+use balances;
+use indices::{self, address::Address};
 use system::{self, ensure_signed};
 
 // no polynomial attacks:
@@ -91,6 +92,7 @@ decl_module! {
 
 		/// Set candidate approvals. Approval slots stay valid as long as candidates in those slots
 		/// are registered.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn set_approvals(origin, votes: Vec<bool>, index: Compact<VoteIndex>) -> Result {
 			let who = ensure_signed(origin)?;
 			let index: VoteIndex = index.into();
@@ -118,6 +120,7 @@ decl_module! {
 		/// the voter gave their last approval set.
 		///
 		/// May be called by anyone. Returns the voter deposit to `signed`.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
 		fn reap_inactive_voter(
 			origin,
 			reporter_index: Compact<u32>,
@@ -128,7 +131,7 @@ decl_module! {
 			let reporter = ensure_signed(origin)?;
 			let assumed_vote_index: VoteIndex = assumed_vote_index.into();
 
-			let who = <balances::Module<T>>::lookup(who)?;
+			let who = <indices::Module<T>>::lookup(who)?;
 			ensure!(!Self::presentation_active(), "cannot reap during presentation period");
 			ensure!(Self::voter_last_active(&reporter).is_some(), "reporter must be a voter");
 			let last_active = Self::voter_last_active(&who).ok_or("target for inactivity cleanup must be active")?;
@@ -189,6 +192,7 @@ decl_module! {
 		/// Submit oneself for candidacy.
 		///
 		/// Account must have enough transferrable funds in it to pay the bond.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn submit_candidacy(origin, slot: Compact<u32>) -> Result {
 			let who = ensure_signed(origin)?;
 
@@ -221,6 +225,7 @@ decl_module! {
 		/// Claim that `signed` is one of the top Self::carry_count() + current_vote().1 candidates.
 		/// Only works if the `block_number >= current_vote().0` and `< current_vote().0 + presentation_duration()``
 		/// `signed` should have at least
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
 		fn present_winner(
 			origin,
 			candidate: Address<T::AccountId, T::AccountIndex>,
@@ -231,7 +236,7 @@ decl_module! {
 			let total = total.into();
 			let index: VoteIndex = index.into();
 
-			let candidate = <balances::Module<T>>::lookup(candidate)?;
+			let candidate = <indices::Module<T>>::lookup(candidate)?;
 			ensure!(index == Self::vote_index(), "index not current");
 			let (_, _, expiring) = Self::next_finalise().ok_or("cannot present outside of presentation period")?;
 			let stakes = Self::snapshoted_stakes();
@@ -285,8 +290,9 @@ decl_module! {
 		/// Remove a particular member. A tally will happen instantly (if not already in a presentation
 		/// period) to fill the seat if removal means that the desired members are not met.
 		/// This is effective immediately.
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
 		fn remove_member(who: Address<T::AccountId, T::AccountIndex>) -> Result {
-			let who = <balances::Module<T>>::lookup(who)?;
+			let who = <indices::Module<T>>::lookup(who)?;
 			let new_council: Vec<(T::AccountId, T::BlockNumber)> = Self::active_council()
 				.into_iter()
 				.filter(|i| i.0 != who)
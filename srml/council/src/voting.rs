@@ -21,7 +21,7 @@ use rstd::borrow::Borrow;
 use codec::HasCompact;
 use primitives::traits::{Hash, As};
 use runtime_io::print;
-use srml_support::dispatch::Result;
+use srml_support::dispatch::{Result, SimpleDispatchInfo};
 use srml_support::{StorageValue, StorageMap, IsSubType};
 use {system, democracy};
 use super::{Trait as CouncilTrait, Module as Council};
@@ -35,6 +35,7 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn propose(origin, proposal: Box<T::Proposal>) -> Result {
 			let who = ensure_signed(origin)?;
 
@@ -70,6 +71,7 @@ decl_module! {
 			Ok(())
 		}
 
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn veto(origin, proposal_hash: T::Hash) -> Result {
 			let who = ensure_signed(origin)?;
 
@@ -255,7 +257,7 @@ mod tests {
 	}
 
 	fn set_balance_proposal(value: u64) -> Call {
-		Call::Balances(balances::Call::set_balance(balances::address::Address::Id(42), value.into(), 0.into()))
+		Call::Balances(balances::Call::set_balance(indices::address::Address::Id(42), value.into(), 0.into()))
 	}
 
 	fn cancel_referendum_proposal(id: u32) -> Call {
@@ -36,15 +36,19 @@ extern crate parity_codec as codec;
 extern crate sr_io as runtime_io;
 extern crate sr_primitives as primitives;
 extern crate srml_balances as balances;
+#[cfg(test)]
+extern crate srml_indices as indices;
+extern crate srml_scheduler as scheduler;
 extern crate srml_system as system;
 
 use rstd::prelude::*;
 use rstd::result;
 use codec::{HasCompact, Compact};
-use primitives::traits::{Zero, As, MaybeSerializeDebug};
+use primitives::traits::{Zero, As, MaybeSerializeDebug, EnsureOrigin};
 use srml_support::{StorageValue, StorageMap, Parameter, Dispatchable, IsSubType};
-use srml_support::dispatch::Result;
+use srml_support::dispatch::{Result, SimpleDispatchInfo};
 use system::ensure_signed;
+use scheduler::Schedule;
 
 mod vote_threshold;
 pub use vote_threshold::{Approved, VoteThreshold};
@@ -54,9 +58,83 @@ pub type PropIndex = u32;
 /// A referendum index.
 pub type ReferendumIndex = u32;
 
+/// The current version of the storage layout; older layouts are migrated by `migrate_storage`.
+const STORAGE_VERSION: u32 = 1;
+
+/// How strongly a vote is held: the more of the voter's balance is locked, and for the longer, the
+/// more the vote counts.
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub enum Conviction {
+	/// The vote counts at face value and nothing is locked.
+	None,
+	/// The vote counts twice, locking the balance for one enactment period.
+	Locked2x,
+	/// The vote counts thrice, locking the balance for two enactment periods.
+	Locked3x,
+	/// The vote counts four times, locking the balance for four enactment periods.
+	Locked4x,
+	/// The vote counts five times, locking the balance for eight enactment periods.
+	Locked5x,
+	/// The vote counts six times, locking the balance for sixteen enactment periods.
+	Locked6x,
+}
+
+impl Default for Conviction {
+	fn default() -> Self {
+		Conviction::None
+	}
+}
+
+impl Conviction {
+	/// The factor by which the voter's balance is multiplied in the tally.
+	pub fn multiplier(self) -> u64 {
+		match self {
+			Conviction::None => 1,
+			Conviction::Locked2x => 2,
+			Conviction::Locked3x => 3,
+			Conviction::Locked4x => 4,
+			Conviction::Locked5x => 5,
+			Conviction::Locked6x => 6,
+		}
+	}
+
+	/// The number of enactment periods the voter's balance stays locked after the referendum.
+	pub fn lock_periods(self) -> u64 {
+		match self {
+			Conviction::None => 0,
+			Conviction::Locked2x => 1,
+			Conviction::Locked3x => 2,
+			Conviction::Locked4x => 4,
+			Conviction::Locked5x => 8,
+			Conviction::Locked6x => 16,
+		}
+	}
+}
+
+/// Information concerning a referendum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub struct ReferendumInfo<BlockNumber, Proposal> {
+	/// When voting on this referendum will end.
+	pub end: BlockNumber,
+	/// The proposal being voted on.
+	pub proposal: Proposal,
+	/// The thresholding mechanism to determine whether it passed.
+	pub threshold: VoteThreshold,
+	/// The number of blocks after `end` at which the proposal is enacted, should it pass.
+	pub delay: BlockNumber,
+}
+
 pub trait Trait: balances::Trait + Sized {
 	type Proposal: Parameter + Dispatchable<Origin=Self::Origin> + IsSubType<Module<Self>> + MaybeSerializeDebug;
 
+	/// Origin from which a passed referendum may be cancelled before it is enacted.
+	type CancellationOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Schedules the enactment of passed proposals.
+	type Scheduler: Schedule<Self::BlockNumber, Self::Proposal>;
+
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
@@ -65,6 +143,7 @@ decl_module! {
 		fn deposit_event() = default;
 
 		/// Propose a sensitive action to be taken.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn propose(
 			origin,
 			proposal: Box<T::Proposal>,
@@ -88,6 +167,7 @@ decl_module! {
 		}
 
 		/// Propose a sensitive action to be taken.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
 		fn second(origin, proposal: Compact<PropIndex>) -> Result {
 			let who = ensure_signed(origin)?;
 			let proposal: PropIndex = proposal.into();
@@ -102,25 +182,34 @@ decl_module! {
 
 		/// Vote in a referendum. If `approve_proposal` is true, the vote is to enact the proposal;
 		/// false would be a vote to keep the status quo.
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
 		fn vote(origin, ref_index: Compact<ReferendumIndex>, approve_proposal: bool) -> Result {
 			let who = ensure_signed(origin)?;
-			let ref_index = ref_index.into();
-			ensure!(Self::is_active_referendum(ref_index), "vote given for invalid referendum.");
-			ensure!(!<balances::Module<T>>::total_balance(&who).is_zero(),
-					"transactor must have balance to signal approval.");
-			if !<VoteOf<T>>::exists(&(ref_index, who.clone())) {
-				<VotersFor<T>>::mutate(ref_index, |voters| voters.push(who.clone()));
-			}
-			<VoteOf<T>>::insert(&(ref_index, who), approve_proposal);
-			Ok(())
+			Self::do_vote(who, ref_index.into(), approve_proposal, Conviction::None)
+		}
+
+		/// Vote in a referendum with the given conviction. The transactor's free balance is locked
+		/// for some enactment periods after the referendum ends, and counts in the tally multiplied
+		/// by the conviction.
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
+		fn lock_vote(
+			origin,
+			ref_index: Compact<ReferendumIndex>,
+			approve_proposal: bool,
+			conviction: Conviction
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			Self::do_vote(who, ref_index.into(), approve_proposal, conviction)
 		}
 
 		/// Start a referendum.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
 		fn start_referendum(proposal: Box<T::Proposal>, vote_threshold: VoteThreshold) -> Result {
 			Self::inject_referendum(
 				<system::Module<T>>::block_number() + Self::voting_period(),
 				*proposal,
-				vote_threshold
+				vote_threshold,
+				Self::enactment_period()
 			).map(|_| ())
 		}
 
@@ -130,6 +219,23 @@ decl_module! {
 			Ok(())
 		}
 
+		/// Cancel a passed referendum that is queued for enactment at block `when`.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel_queued(origin, when: <T::BlockNumber as HasCompact>::Type, which: Compact<u32>) -> Result {
+			T::CancellationOrigin::ensure_origin(origin)?;
+			let when: T::BlockNumber = when.into();
+			let which: u32 = which.into();
+
+			let mut enactments = Self::enactments(when);
+			let position = enactments.iter().position(|&(index, _)| index == which)
+				.ok_or("proposal not found")?;
+			let (_, ref_index) = enactments.swap_remove(position);
+			T::Scheduler::cancel((when, which))?;
+			<Enactments<T>>::insert(when, enactments);
+			Self::deposit_event(RawEvent::Cancelled(ref_index));
+			Ok(())
+		}
+
 		fn on_finalise(n: T::BlockNumber) {
 			if let Err(e) = Self::end_block(n) {
 				runtime_io::print(e);
@@ -154,31 +260,45 @@ decl_storage! {
 
 		/// How often (in blocks) to check for new votes.
 		pub VotingPeriod get(voting_period) config(): T::BlockNumber = T::BlockNumber::sa(1000);
+		/// The delay (in blocks) between a referendum passing and its proposal being enacted.
+		pub EnactmentPeriod get(enactment_period) config(): T::BlockNumber = T::BlockNumber::sa(1000);
 
 		/// The next free referendum index, aka the number of referendums started so far.
 		pub ReferendumCount get(referendum_count) build(|_| 0 as ReferendumIndex): ReferendumIndex;
 		/// The next referendum index that should be tallied.
 		pub NextTally get(next_tally) build(|_| 0 as ReferendumIndex): ReferendumIndex;
 		/// Information concerning any given referendum.
-		pub ReferendumInfoOf get(referendum_info): map ReferendumIndex => Option<(T::BlockNumber, T::Proposal, VoteThreshold)>;
+		pub ReferendumInfoOf get(referendum_info): map ReferendumIndex => Option<ReferendumInfo<T::BlockNumber, T::Proposal>>;
+		/// The proposals scheduled for enactment at a given block, as the index of their task in the
+		/// scheduler's agenda together with the referendum they passed in.
+		pub Enactments get(enactments): map T::BlockNumber => Vec<(u32, ReferendumIndex)>;
 
 		/// Get the voters for the current proposal.
 		pub VotersFor get(voters_for): map ReferendumIndex => Vec<T::AccountId>;
 
 		/// Get the vote, if Some, of `who`.
 		pub VoteOf get(vote_of): map (ReferendumIndex, T::AccountId) => Option<bool>;
+		/// The conviction with which `who` voted, and the balance locked by the vote.
+		pub ConvictionOf get(conviction_of): map (ReferendumIndex, T::AccountId) => (Conviction, T::Balance);
+		/// The balance of accounts locked by their votes, and the block at which it becomes liquid.
+		pub Locks get(locks): map T::AccountId => Option<(T::Balance, T::BlockNumber)>;
+
+		/// The layout version of this module's storage.
+		pub StorageVersion get(storage_version) build(|_| STORAGE_VERSION): u32;
 	}
 }
 
 decl_event!(
 	/// An event in this module.
-	pub enum Event<T> where <T as balances::Trait>::Balance, <T as system::Trait>::AccountId {
+	pub enum Event<T> where <T as balances::Trait>::Balance, <T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber {
 		Tabled(PropIndex, Balance, Vec<AccountId>),
 		Started(ReferendumIndex, VoteThreshold),
 		Passed(ReferendumIndex),
 		NotPassed(ReferendumIndex),
 		Cancelled(ReferendumIndex),
 		Executed(ReferendumIndex, bool),
+		/// A passed referendum is queued for enactment at the given block.
+		Scheduled(ReferendumIndex, BlockNumber),
 	}
 );
 
@@ -201,7 +321,7 @@ impl<T: Trait> Module<T> {
 		let next = Self::next_tally();
 		let last = Self::referendum_count();
 		(next..last).into_iter()
-			.filter_map(|i| Self::referendum_info(i).map(|(n, p, t)| (i, n, p, t)))
+			.filter_map(|i| Self::referendum_info(i).map(|info| (i, info.end, info.proposal, info.threshold)))
 			.collect()
 	}
 
@@ -210,24 +330,41 @@ impl<T: Trait> Module<T> {
 		let next = Self::next_tally();
 		let last = Self::referendum_count();
 		(next..last).into_iter()
-			.filter_map(|i| Self::referendum_info(i).map(|(n, p, t)| (i, n, p, t)))
+			.filter_map(|i| Self::referendum_info(i).map(|info| (i, info.end, info.proposal, info.threshold)))
 			.take_while(|&(_, block_number, _, _)| block_number == n)
 			.collect()
 	}
 
-	/// Get the voters for the current proposal.
-	pub fn tally(ref_index: ReferendumIndex) -> (T::Balance, T::Balance) {
+	/// Get the votes for and against the current proposal, each balance multiplied by the voter's
+	/// conviction, along with the turnout: the sum of the voters' balances.
+	pub fn tally(ref_index: ReferendumIndex) -> (T::Balance, T::Balance, T::Balance) {
 		Self::voters_for(ref_index).iter()
-			.map(|a| (<balances::Module<T>>::total_balance(a), Self::vote_of((ref_index, a.clone())).unwrap_or(false)/*defensive only: all items come from `voters`; for an item to be in `voters` there must be a vote registered; qed*/))
-			.map(|(bal, vote)| if vote { (bal, Zero::zero()) } else { (Zero::zero(), bal) })
-			.fold((Zero::zero(), Zero::zero()), |(a, b), (c, d)| (a + c, b + d))
+			.map(|a| {
+				let (conviction, locked) = Self::conviction_of((ref_index, a.clone()));
+				let balance = match conviction {
+					Conviction::None => <balances::Module<T>>::total_balance(a),
+					_ => locked,
+				};
+				(
+					balance,
+					balance * T::Balance::sa(conviction.multiplier()),
+					Self::vote_of((ref_index, a.clone())).unwrap_or(false)/*defensive only: all items come from `voters`; for an item to be in `voters` there must be a vote registered; qed*/
+				)
+			})
+			.map(|(bal, votes, approve)| if approve { (votes, Zero::zero(), bal) } else { (Zero::zero(), votes, bal) })
+			.fold((Zero::zero(), Zero::zero(), Zero::zero()), |(a, b, c), (d, e, f)| (a + d, b + e, c + f))
 	}
 
 	// Exposed mutables.
 
 	/// Start a referendum. Can be called directly by the council.
 	pub fn internal_start_referendum(proposal: T::Proposal, vote_threshold: VoteThreshold) -> result::Result<ReferendumIndex, &'static str> {
-		<Module<T>>::inject_referendum(<system::Module<T>>::block_number() + <Module<T>>::voting_period(), proposal, vote_threshold)
+		<Module<T>>::inject_referendum(
+			<system::Module<T>>::block_number() + <Module<T>>::voting_period(),
+			proposal,
+			vote_threshold,
+			<Module<T>>::enactment_period()
+		)
 	}
 
 	/// Remove a referendum. Can be called directly by the council.
@@ -238,34 +375,95 @@ impl<T: Trait> Module<T> {
 
 	// private.
 
+	/// Record the vote of `who`, locking their free balance according to the conviction.
+	fn do_vote(who: T::AccountId, ref_index: ReferendumIndex, approve_proposal: bool, conviction: Conviction) -> Result {
+		Self::migrate_storage();
+
+		let info = Self::referendum_info(ref_index).ok_or("vote given for invalid referendum.")?;
+		ensure!(!<balances::Module<T>>::total_balance(&who).is_zero(),
+				"transactor must have balance to signal approval.");
+
+		let mut locked = Zero::zero();
+		if conviction.lock_periods() > 0 {
+			locked = <balances::Module<T>>::free_balance(&who);
+			ensure!(!locked.is_zero(), "transactor must have free balance to lock.");
+			let mut until = info.end + info.delay
+				+ Self::enactment_period() * T::BlockNumber::sa(conviction.lock_periods());
+			// locks only ever grow, both in amount and in duration.
+			if let Some((current, current_until)) = Self::locks(&who) {
+				if current_until > <system::Module<T>>::block_number() {
+					locked = rstd::cmp::max(locked, current);
+					until = rstd::cmp::max(until, current_until);
+				}
+			}
+			<Locks<T>>::insert(&who, (locked, until));
+		}
+
+		if !<VoteOf<T>>::exists(&(ref_index, who.clone())) {
+			<VotersFor<T>>::mutate(ref_index, |voters| voters.push(who.clone()));
+		}
+		<VoteOf<T>>::insert(&(ref_index, who.clone()), approve_proposal);
+		<ConvictionOf<T>>::insert(&(ref_index, who.clone()), (conviction, locked));
+		Ok(())
+	}
+
 	/// Start a referendum
 	fn inject_referendum(
 		end: T::BlockNumber,
 		proposal: T::Proposal,
-		vote_threshold: VoteThreshold
+		threshold: VoteThreshold,
+		delay: T::BlockNumber
 	) -> result::Result<ReferendumIndex, &'static str> {
+		Self::migrate_storage();
+
 		let ref_index = Self::referendum_count();
-		if ref_index > 0 && Self::referendum_info(ref_index - 1).map(|i| i.0 > end).unwrap_or(false) {
+		if ref_index > 0 && Self::referendum_info(ref_index - 1).map(|i| i.end > end).unwrap_or(false) {
 			Err("Cannot inject a referendum that ends earlier than preceeding referendum")?
 		}
 
 		<ReferendumCount<T>>::put(ref_index + 1);
-		<ReferendumInfoOf<T>>::insert(ref_index, (end, proposal, vote_threshold));
-		Self::deposit_event(RawEvent::Started(ref_index, vote_threshold));
+		<ReferendumInfoOf<T>>::insert(ref_index, ReferendumInfo { end, proposal, threshold, delay });
+		Self::deposit_event(RawEvent::Started(ref_index, threshold));
 		Ok(ref_index)
 	}
 
 	/// Remove all info on a referendum.
 	fn clear_referendum(ref_index: ReferendumIndex) {
 		<ReferendumInfoOf<T>>::remove(ref_index);
-		<VotersFor<T>>::remove(ref_index);
-		for v in Self::voters_for(ref_index) {
-			<VoteOf<T>>::remove((ref_index, v));
+		for v in <VotersFor<T>>::take(ref_index) {
+			<VoteOf<T>>::remove((ref_index, v.clone()));
+			<ConvictionOf<T>>::remove((ref_index, v));
 		}
 	}
 
+	/// Bring storage written by earlier versions of this module up to date.
+	fn migrate_storage() {
+		if Self::storage_version() >= STORAGE_VERSION {
+			return;
+		}
+
+		// Version 0 kept referendum information as a tuple, and enacted passed proposals
+		// immediately.
+		for index in Self::next_tally()..Self::referendum_count() {
+			let key = <ReferendumInfoOf<T>>::key_for(index);
+			if let Some((end, proposal, threshold)) = srml_support::storage::get::<(T::BlockNumber, T::Proposal, VoteThreshold)>(&key) {
+				<ReferendumInfoOf<T>>::insert(index, ReferendumInfo { end, proposal, threshold, delay: Zero::zero() });
+			}
+		}
+
+		<StorageVersion<T>>::put(STORAGE_VERSION);
+	}
+
+	/// Enact a passed proposal.
+	fn enact_proposal(proposal: T::Proposal, index: ReferendumIndex) {
+		let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
+		Self::deposit_event(RawEvent::Executed(index, ok));
+	}
+
 	/// Current era is ending; we should finish up any proposals.
 	fn end_block(now: T::BlockNumber) -> Result {
+		Self::migrate_storage();
+
 		// pick out another public referendum if it's time.
 		if (now % Self::launch_period()).is_zero() {
 			let mut public_props = Self::public_props();
@@ -282,36 +480,59 @@ impl<T: Trait> Module<T> {
 						<balances::Module<T>>::unreserve(d, deposit);
 					}
 					Self::deposit_event(RawEvent::Tabled(prop_index, deposit, depositors));
-					Self::inject_referendum(now + Self::voting_period(), proposal, VoteThreshold::SuperMajorityApprove)?;
+					Self::inject_referendum(
+						now + Self::voting_period(),
+						proposal,
+						VoteThreshold::SuperMajorityApprove,
+						Self::enactment_period()
+					)?;
 				}
 			}
 		}
 
 		// tally up votes for any expiring referenda.
 		for (index, _, proposal, vote_threshold) in Self::maturing_referendums_at(now) {
-			let (approve, against) = Self::tally(index);
+			let delay = Self::referendum_info(index).map(|i| i.delay).unwrap_or_else(Zero::zero);
+			let (approve, against, turnout) = Self::tally(index);
 			let total_issuance = <balances::Module<T>>::total_issuance();
 			Self::clear_referendum(index);
-			if vote_threshold.approved(approve, against, total_issuance) {
+			if vote_threshold.approved(approve, against, turnout, total_issuance) {
 				Self::deposit_event(RawEvent::Passed(index));
-				let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
-				Self::deposit_event(RawEvent::Executed(index, ok));
+				if delay.is_zero() {
+					Self::enact_proposal(proposal, index);
+				} else {
+					let (when, which) = T::Scheduler::schedule(now + delay, None, proposal)?;
+					<Enactments<T>>::mutate(when, |e| e.push((which, index)));
+					Self::deposit_event(RawEvent::Scheduled(index, when));
+				}
 			} else {
 				Self::deposit_event(RawEvent::NotPassed(index));
 			}
 			<NextTally<T>>::put(index + 1);
 		}
+
+		// the proposals due now are left to the scheduler, and can no longer be cancelled.
+		<Enactments<T>>::remove(now);
 		Ok(())
 	}
 }
 
+impl<T: Trait> balances::LockedBalance<T::AccountId, T::Balance> for Module<T> {
+	fn locked_balance(who: &T::AccountId) -> T::Balance {
+		match Self::locks(who) {
+			Some((locked, until)) if until > <system::Module<T>>::block_number() => locked,
+			_ => Zero::zero(),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use runtime_io::with_externalities;
 	use substrate_primitives::{H256, Blake2Hasher};
 	use primitives::BuildStorage;
-	use primitives::traits::{BlakeTwo256};
+	use primitives::traits::{BlakeTwo256, OnFinalise};
 	use primitives::testing::{Digest, DigestItem, Header};
 
 	impl_outer_origin! {
@@ -340,15 +561,26 @@ mod tests {
 		type Event = ();
 		type Log = DigestItem;
 	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
 	impl balances::Trait for Test {
 		type Balance = u64;
-		type AccountIndex = u64;
 		type OnFreeBalanceZero = ();
 		type EnsureAccountLiquid = ();
+		type LockedBalance = Democracy;
+		type OnCollected = ();
+		type Event = ();
+	}
+	impl scheduler::Trait for Test {
+		type Call = Call;
 		type Event = ();
 	}
 	impl Trait for Test {
 		type Proposal = Call;
+		type CancellationOrigin = system::EnsureRoot<u64>;
+		type Scheduler = Scheduler;
 		type Event = ();
 	}
 
@@ -358,14 +590,17 @@ mod tests {
 			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 0,
 			transfer_fee: 0,
 			creation_fee: 0,
-			reclaim_rebate: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
 		}.build_storage().unwrap().0);
 		t.extend(GenesisConfig::<Test>{
 			launch_period: 1,
 			voting_period: 1,
+			enactment_period: 0,
 			minimum_deposit: 1,
 		}.build_storage().unwrap().0);
 		runtime_io::TestExternalities::new(t)
@@ -374,6 +609,7 @@ mod tests {
 	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
 	type Democracy = Module<Test>;
+	type Scheduler = scheduler::Module<Test>;
 
 	#[test]
 	fn params_should_work() {
@@ -388,7 +624,7 @@ mod tests {
 	}
 
 	fn set_balance_proposal(value: u64) -> Call {
-		Call::Balances(balances::Call::set_balance(balances::address::Address::Id(42), value.into(), 0.into()))
+		Call::Balances(balances::Call::set_balance(indices::address::Address::Id(42), value.into(), 0.into()))
 	}
 
 	fn propose_set_balance(who: u64, value: u64, locked: u64) -> super::Result {
@@ -422,7 +658,7 @@ mod tests {
 			assert_eq!(Democracy::referendum_count(), 1);
 			assert_eq!(Democracy::voters_for(r), vec![1]);
 			assert_eq!(Democracy::vote_of((r, 1)), Some(true));
-			assert_eq!(Democracy::tally(r), (10, 0));
+			assert_eq!(Democracy::tally(r), (10, 0, 10));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
@@ -515,12 +751,12 @@ mod tests {
 	fn simple_passing_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), true));
 
 			assert_eq!(Democracy::voters_for(r), vec![1]);
 			assert_eq!(Democracy::vote_of((r, 1)), Some(true));
-			assert_eq!(Democracy::tally(r), (10, 0));
+			assert_eq!(Democracy::tally(r), (10, 0, 10));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
@@ -532,7 +768,7 @@ mod tests {
 	fn cancel_referendum_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), true));
 			assert_ok!(Democracy::cancel_referendum(r.into()));
 
@@ -542,16 +778,99 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn delayed_enactment_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 2).unwrap();
+			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), true));
+
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			assert_eq!(Balances::free_balance(&42), 0);
+			assert_eq!(Democracy::enactments(3), vec![(0, r)]);
+			assert_eq!(Scheduler::agenda(3), vec![Some(scheduler::Scheduled { call: set_balance_proposal(2), maybe_periodic: None })]);
+
+			System::set_block_number(3);
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			<Scheduler as OnFinalise<u64>>::on_finalise(3);
+			assert_eq!(Balances::free_balance(&42), 2);
+			assert!(Democracy::enactments(3).is_empty());
+		});
+	}
+
+	#[test]
+	fn cancel_queued_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 2).unwrap();
+			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), true));
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+
+			assert_noop!(Democracy::cancel_queued(Origin::signed(1), 3.into(), 0.into()), "bad origin: expected to be a root origin");
+			assert_ok!(Democracy::cancel_queued(Origin::ROOT, 3.into(), 0.into()));
+			assert_noop!(Democracy::cancel_queued(Origin::ROOT, 3.into(), 0.into()), "proposal not found");
+
+			System::set_block_number(3);
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			<Scheduler as OnFinalise<u64>>::on_finalise(3);
+			assert_eq!(Balances::free_balance(&42), 0);
+		});
+	}
+
+	#[test]
+	fn conviction_votes_are_weighted_and_locked() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<EnactmentPeriod<Test>>::put(2);
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
+			assert_ok!(Democracy::lock_vote(Origin::signed(1), r.into(), true, Conviction::Locked3x));
+			assert_ok!(Democracy::vote(Origin::signed(2), r.into(), false));
+
+			assert_eq!(Democracy::tally(r), (30, 20, 30));
+			assert_eq!(Democracy::locks(&1), Some((10, 5)));
+			assert_eq!(Democracy::locks(&2), None);
+
+			// only the voted balance is locked.
+			assert_ok!(Balances::transfer(Some(3).into(), 1.into(), 5.into()));
+			assert_noop!(Balances::transfer(Some(1).into(), 3.into(), 6.into()), "locked balance too high to send value");
+			assert_ok!(Balances::transfer(Some(1).into(), 3.into(), 5.into()));
+			assert_ok!(Balances::transfer(Some(2).into(), 3.into(), 20.into()));
+
+			System::set_block_number(5);
+			assert_ok!(Balances::transfer(Some(1).into(), 3.into(), 10.into()));
+		});
+	}
+
+	#[test]
+	fn storage_of_previous_version_should_be_migrated() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<StorageVersion<Test>>::put(0);
+			<ReferendumCount<Test>>::put(1);
+			srml_support::storage::put(
+				&<ReferendumInfoOf<Test>>::key_for(0),
+				&(1u64, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove)
+			);
+
+			assert_ok!(Democracy::vote(Origin::signed(1), 0.into(), true));
+			assert_eq!(Democracy::storage_version(), STORAGE_VERSION);
+			assert_eq!(Democracy::referendum_info(0).map(|i| i.delay), Some(0));
+
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			assert_eq!(Balances::free_balance(&42), 2);
+		});
+	}
+
 	#[test]
 	fn simple_failing_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), false));
 
 			assert_eq!(Democracy::voters_for(r), vec![1]);
 			assert_eq!(Democracy::vote_of((r, 1)), Some(false));
-			assert_eq!(Democracy::tally(r), (0, 10));
+			assert_eq!(Democracy::tally(r), (0, 10, 10));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
@@ -563,7 +882,7 @@ mod tests {
 	fn controversial_voting_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(1), r.into(), true));
 			assert_ok!(Democracy::vote(Origin::signed(2), r.into(), false));
 			assert_ok!(Democracy::vote(Origin::signed(3), r.into(), false));
@@ -571,7 +890,7 @@ mod tests {
 			assert_ok!(Democracy::vote(Origin::signed(5), r.into(), false));
 			assert_ok!(Democracy::vote(Origin::signed(6), r.into(), true));
 
-			assert_eq!(Democracy::tally(r), (110, 100));
+			assert_eq!(Democracy::tally(r), (110, 100, 210));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
@@ -583,11 +902,11 @@ mod tests {
 	fn controversial_low_turnout_voting_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(5), r.into(), false));
 			assert_ok!(Democracy::vote(Origin::signed(6), r.into(), true));
 
-			assert_eq!(Democracy::tally(r), (60, 50));
+			assert_eq!(Democracy::tally(r), (60, 50, 110));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
@@ -602,12 +921,12 @@ mod tests {
 			assert_eq!(Balances::total_issuance(), 210);
 
 			System::set_block_number(1);
-			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			let r = Democracy::inject_referendum(1, set_balance_proposal(2), VoteThreshold::SuperMajorityApprove, 0).unwrap();
 			assert_ok!(Democracy::vote(Origin::signed(4), r.into(), true));
 			assert_ok!(Democracy::vote(Origin::signed(5), r.into(), false));
 			assert_ok!(Democracy::vote(Origin::signed(6), r.into(), true));
 
-			assert_eq!(Democracy::tally(r), (100, 50));
+			assert_eq!(Democracy::tally(r), (100, 50, 150));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
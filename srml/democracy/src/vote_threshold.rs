@@ -32,10 +32,12 @@ pub enum VoteThreshold {
 }
 
 pub trait Approved<Balance> {
-	/// Given `approve` votes for and `against` votes against from a total electorate size of
-	/// `electorate` (`electorate - (approve + against)` are abstainers), then returns true if the
-	/// overall outcome is in favour of approval.
-	fn approved(&self, approve: Balance, against: Balance, electorate: Balance) -> bool;
+	/// Given `approve` votes for and `against` votes against cast by voters holding `turnout` in
+	/// total, from a total electorate size of `electorate` (`electorate - turnout` are abstainers),
+	/// then returns true if the overall outcome is in favour of approval.
+	///
+	/// The votes may be weighted, in which case they can add up to more than `turnout`.
+	fn approved(&self, approve: Balance, against: Balance, turnout: Balance, electorate: Balance) -> bool;
 }
 
 /// Return `true` iff `n1 / d1 < n2 / d2`. `d1` and `d2` may not be zero.
@@ -67,12 +69,11 @@ fn compare_rationals<T: Zero + Mul<T, Output = T> + Div<T, Output = T> + Rem<T,
 }
 
 impl<Balance: IntegerSquareRoot + Zero + Ord + Add<Balance, Output = Balance> + Mul<Balance, Output = Balance> + Div<Balance, Output = Balance> + Rem<Balance, Output = Balance> + Copy> Approved<Balance> for VoteThreshold {
-	/// Given `approve` votes for and `against` votes against from a total electorate size of
-	/// `electorate` (`electorate - (approve + against)` are abstainers), then returns true if the
-	/// overall outcome is in favour of approval.
-	fn approved(&self, approve: Balance, against: Balance, electorate: Balance) -> bool {
-		let voters = approve + against;
-		let sqrt_voters = voters.integer_sqrt();
+	/// Given `approve` votes for and `against` votes against cast by voters holding `turnout` in
+	/// total, from a total electorate size of `electorate` (`electorate - turnout` are abstainers),
+	/// then returns true if the overall outcome is in favour of approval.
+	fn approved(&self, approve: Balance, against: Balance, turnout: Balance, electorate: Balance) -> bool {
+		let sqrt_voters = turnout.integer_sqrt();
 		let sqrt_electorate = electorate.integer_sqrt();
 		if sqrt_voters.is_zero() { return false; }
 		match *self {
@@ -91,7 +92,15 @@ mod tests {
 
 	#[test]
 	fn should_work() {
-		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(60, 50, 210), false);
-		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(100, 50, 210), true);
+		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(60, 50, 110, 210), false);
+		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(100, 50, 150, 210), true);
+	}
+
+	#[test]
+	fn passes_only_with_unweighted_turnout() {
+		// 20 approving at three times their balance against 100: passes with their turnout of 120,
+		// but would fail if the weighted 160 were counted as turnout.
+		assert_eq!(VoteThreshold::SuperMajorityAgainst.approved(60, 100, 120, 400), true);
+		assert_eq!(VoteThreshold::SuperMajorityAgainst.approved(60, 100, 160, 400), false);
 	}
 }
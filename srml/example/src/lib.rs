@@ -56,6 +56,8 @@ extern crate srml_system as system;
 // general (though if you want your module to be able to work with tokens, then you
 // might find it useful).
 extern crate srml_balances as balances;
+#[cfg(test)]
+extern crate srml_indices as indices;
 
 use support::{StorageValue, dispatch::Result};
 use system::ensure_signed;
@@ -299,11 +301,16 @@ mod tests {
 		type Event = ();
 		type Log = DigestItem;
 	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
 	impl balances::Trait for Test {
 		type Balance = u64;
-		type AccountIndex = u64;
 		type OnFreeBalanceZero = ();
 		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
 		type Event = ();
 	}
 	impl Trait for Test {
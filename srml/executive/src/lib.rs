@@ -45,13 +45,16 @@ extern crate substrate_primitives;
 
 #[cfg(test)]
 extern crate srml_balances as balances;
+#[cfg(test)]
+extern crate srml_indices as indices;
 
 use rstd::prelude::*;
 use rstd::marker::PhantomData;
 use rstd::result;
 use primitives::traits::{self, Header, Zero, One, Checkable, Applyable, CheckEqual, OnFinalise,
-	MakePayment, Hash, As, Digest};
+	OffchainWorker, MakePayment, Hash, As, Digest, Extrinsic};
 use runtime_support::Dispatchable;
+use runtime_support::dispatch::GetDispatchInfo;
 use codec::{Codec, Encode};
 use system::extrinsics_root;
 use primitives::{ApplyOutcome, ApplyError};
@@ -63,6 +66,7 @@ mod internal {
 		Stale,
 		Future,
 		CantPay,
+		FullBlock,
 	}
 
 	pub enum ApplyOutcome {
@@ -84,11 +88,11 @@ impl<
 	System: system::Trait,
 	Block: traits::Block<Header=System::Header, Hash=System::Hash>,
 	Payment: MakePayment<System::AccountId>,
-	Finalisation: OnFinalise<System::BlockNumber>,
+	Finalisation: OnFinalise<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
 > Executive<System, Block, Context, Payment, Finalisation> where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
-	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
+	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable + GetDispatchInfo,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
 	/// Start the execution of a particular block.
@@ -110,6 +114,15 @@ impl<
 		let xts_root = extrinsics_root::<System::Hashing, _>(&block.extrinsics());
 		header.extrinsics_root().check_equal(&xts_root);
 		assert!(header.extrinsics_root() == &xts_root, "Transaction trie root must be valid.");
+
+		// check the inherent extrinsics all come before the signed ones.
+		let first_signed = block.extrinsics().iter().position(|xt| xt.is_signed() == Some(true));
+		if let Some(first_signed) = first_signed {
+			assert!(
+				block.extrinsics()[first_signed..].iter().all(|xt| xt.is_signed() != Some(false)),
+				"Inherent extrinsics must come before all signed extrinsics."
+			);
+		}
 	}
 
 	/// Actually execute all transitioning for `block`.
@@ -156,6 +169,7 @@ impl<
 			Err(internal::ApplyError::BadSignature(_)) => Err(ApplyError::BadSignature),
 			Err(internal::ApplyError::Stale) => Err(ApplyError::Stale),
 			Err(internal::ApplyError::Future) => Err(ApplyError::Future),
+			Err(internal::ApplyError::FullBlock) => Err(ApplyError::FullBlock),
 		}
 	}
 
@@ -168,6 +182,7 @@ impl<
 			Err(internal::ApplyError::CantPay) => panic!("All extrinsics should have sender able to pay their fees"),
 			Err(internal::ApplyError::BadSignature(_)) => panic!("All extrinsics should be properly signed"),
 			Err(internal::ApplyError::Stale) | Err(internal::ApplyError::Future) => panic!("All extrinsics should have the correct nonce"),
+			Err(internal::ApplyError::FullBlock) => panic!("All extrinsics should fit within the block weight limit"),
 		}
 	}
 
@@ -175,26 +190,37 @@ impl<
 	fn apply_extrinsic_no_note_with_len(uxt: Block::Extrinsic, encoded_len: usize) -> result::Result<internal::ApplyOutcome, internal::ApplyError> {
 		// Verify the signature is good.
 		let xt = uxt.check(&Default::default()).map_err(internal::ApplyError::BadSignature)?;
+		let index = xt.index().cloned();
+		let (f, s) = xt.deconstruct();
+		let info = f.get_dispatch_info();
 
-		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
+		if let (Some(sender), Some(index)) = (s.as_ref(), index) {
 			// check index
 			let expected_index = <system::Module<System>>::account_nonce(sender);
-			if index != &expected_index { return Err(
-				if index < &expected_index { internal::ApplyError::Stale } else { internal::ApplyError::Future }
+			if index != expected_index { return Err(
+				if index < expected_index { internal::ApplyError::Stale } else { internal::ApplyError::Future }
 			) }
 
+			// check there's room left in the block.
+			if !<system::Module<System>>::can_include_weight(info) {
+				return Err(internal::ApplyError::FullBlock)
+			}
+
 			// pay any fees.
-			Payment::make_payment(sender, encoded_len).map_err(|_| internal::ApplyError::CantPay)?;
+			Payment::make_payment(sender, encoded_len, info).map_err(|_| internal::ApplyError::CantPay)?;
 
 			// AUDIT: Under no circumstances may this function panic from here onwards.
 
 			// increment nonce in storage
 			<system::Module<System>>::inc_account_nonce(sender);
 		}
+		<system::Module<System>>::note_extrinsic_weight(info);
 
 		// decode parameters and dispatch
-		let (f, s) = xt.deconstruct();
-		let r = f.dispatch(s.into());
+		let r = f.dispatch(s.clone().into());
+		if let Some(ref sender) = s {
+			Payment::refund(sender, encoded_len, info, &r);
+		}
 		<system::Module<System>>::note_applied_extrinsic(&r);
 
 		r.map(|_| internal::ApplyOutcome::Success).or_else(|e| Ok(internal::ApplyOutcome::Fail(e)))
@@ -239,37 +265,53 @@ impl<
 			Err(_) => return TransactionValidity::Invalid,
 		};
 
-		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
+		let index = xt.index().cloned();
+		let (f, s) = xt.deconstruct();
+		let info = f.get_dispatch_info();
+
+		if let (Some(sender), Some(index)) = (s, index) {
+			// an extrinsic heavier than a whole block can never be included.
+			if info.weight > <system::Module<System>>::weight_limit(info.class) {
+				return TransactionValidity::Invalid
+			}
+
 			// pay any fees.
-			if Payment::make_payment(sender, encoded_len).is_err() {
+			if Payment::make_payment(&sender, encoded_len, info).is_err() {
 				return TransactionValidity::Invalid
 			}
 
 			// check index
-			let mut expected_index = <system::Module<System>>::account_nonce(sender);
-			if index < &expected_index {
+			let mut expected_index = <system::Module<System>>::account_nonce(&sender);
+			if index < expected_index {
 				return TransactionValidity::Invalid
 			}
-			if *index > expected_index + As::sa(256) {
+			if index > expected_index + As::sa(256) {
 				return TransactionValidity::Unknown
 			}
 
 			let mut deps = Vec::new();
-			while expected_index < *index {
-				deps.push((sender, expected_index).encode());
+			while expected_index < index {
+				deps.push((&sender, expected_index).encode());
 				expected_index = expected_index + One::one();
 			}
 
 			TransactionValidity::Valid {
 				priority: encoded_len as TransactionPriority,
 				requires: deps,
-				provides: vec![(sender, *index).encode()],
+				provides: vec![(sender, index).encode()],
 				longevity: TransactionLongevity::max_value(),
 			}
 		} else {
 			return TransactionValidity::Invalid
 		}
 	}
+
+	/// Run the offchain workers of all modules on top of the new best block `n`.
+	///
+	/// Changes made to the storage should be discarded.
+	pub fn offchain_worker(n: System::BlockNumber) {
+		<Finalisation as OffchainWorker<System::BlockNumber>>::offchain_worker(n)
+	}
 }
 
 #[cfg(test)]
@@ -309,16 +351,21 @@ mod tests {
 		type Event = MetaEvent;
 		type Log = DigestItem;
 	}
+	impl indices::Trait for Runtime {
+		type AccountIndex = u64;
+		type Event = ();
+	}
 	impl balances::Trait for Runtime {
 		type Balance = u64;
-		type AccountIndex = u64;
 		type OnFreeBalanceZero = ();
 		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
 		type Event = MetaEvent;
 	}
 
 	type TestXt = primitives::testing::TestXt<Call<Runtime>>;
-	type Executive = super::Executive<Runtime, Block<TestXt>, balances::ChainContext<Runtime>, balances::Module<Runtime>, ()>;
+	type Executive = super::Executive<Runtime, Block<TestXt>, indices::ChainContext<Runtime>, balances::Module<Runtime>, ()>;
 
 	#[test]
 	fn balance_transfer_dispatch_works() {
@@ -327,10 +374,12 @@ mod tests {
 			balances: vec![(1, 111)],
 			transaction_base_fee: 10,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			existential_deposit: 0,
 			transfer_fee: 0,
 			creation_fee: 0,
-			reclaim_rebate: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
 		}.build_storage().unwrap().0);
 		let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(2.into(), 69.into()));
 		let mut t = runtime_io::TestExternalities::<Blake2Hasher>::new(t);
@@ -343,6 +392,59 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn weight_fee_is_charged_for_failed_dispatch() {
+		let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Runtime> {
+			balances: vec![(1, 100_000)],
+			transaction_base_fee: 10,
+			transaction_byte_fee: 0,
+			transaction_weight_fee: 1,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
+		}.build_storage().unwrap().0);
+		let mut t = runtime_io::TestExternalities::<Blake2Hasher>::new(t);
+		with_externalities(&mut t, || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(),
+				[69u8; 32].into(), Digest::default()));
+			let weight = Call::<Runtime>::transfer(2.into(), 69.into()).get_dispatch_info().weight as u64;
+
+			let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(2.into(), 69.into()));
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Success));
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 100_000 - 69 - 10 - weight);
+
+			let xt = primitives::testing::TestXt(Some(1), 1, Call::transfer(2.into(), 200_000.into()));
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Fail));
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 100_000 - 69 - 20 - 2 * weight);
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight() as u64, 2 * weight);
+		});
+	}
+
+	#[test]
+	fn extrinsics_beyond_the_block_weight_limit_are_rejected() {
+		let mut t = system::GenesisConfig::<Runtime> {
+			maximum_block_weight: 10_000,
+			..Default::default()
+		}.build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Runtime> {
+			balances: vec![(1, 111)],
+			..Default::default()
+		}.build_storage().unwrap().0);
+		let mut t = runtime_io::TestExternalities::<Blake2Hasher>::new(t);
+		with_externalities(&mut t, || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(),
+				[69u8; 32].into(), Digest::default()));
+			// the default weight of a transfer only fits into the operational part of the block.
+			let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(2.into(), 69.into()));
+			assert_eq!(Executive::validate_transaction(xt.clone()), TransactionValidity::Invalid);
+			assert_eq!(Executive::apply_extrinsic(xt), Err(ApplyError::FullBlock));
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 111);
+		});
+	}
+
 	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
 		let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
 		t.extend(balances::GenesisConfig::<Runtime>::default().build_storage().unwrap().0);
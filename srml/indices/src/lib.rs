@@ -0,0 +1,297 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Indices: gives every account a compact index when it's first used, so that extrinsics can
+//! address it by an index of a few bytes rather than by its full id.
+//!
+//! The index of a reaped account can be reclaimed: once `ReclaimDelay` blocks have passed since
+//! the account was reaped, it's handed out to the next new account before any fresh index is.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate srml_support as runtime_support;
+
+extern crate sr_std as rstd;
+
+#[macro_use]
+extern crate parity_codec_derive;
+
+extern crate parity_codec as codec;
+extern crate sr_primitives as primitives;
+extern crate srml_system as system;
+
+#[cfg(test)]
+extern crate sr_io as runtime_io;
+#[cfg(test)]
+extern crate substrate_primitives;
+
+use rstd::prelude::*;
+use rstd::result;
+use codec::{Encode, Decode, Codec, Input, Output};
+use runtime_support::{StorageValue, StorageMap, Parameter};
+use primitives::traits::{One, SimpleArithmetic, As, Lookup, Member, CurrentHeight, BlockNumberToHash};
+use address::Address as RawAddress;
+
+mod mock;
+
+pub mod address;
+mod tests;
+
+/// Number of account IDs stored per enum set.
+const ENUM_SET_SIZE: usize = 64;
+
+pub type Address<T> = RawAddress<<T as system::Trait>::AccountId, <T as Trait>::AccountIndex>;
+
+pub trait Trait: system::Trait {
+	/// Type used for storing an account's index; implies the maximum number of accounts the system
+	/// can hold.
+	type AccountIndex: Parameter + Member + Codec + Default + SimpleArithmetic + As<u8> + As<u16> + As<u32> + As<u64> + As<usize> + Copy;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as Trait>::AccountIndex
+	{
+		/// An account was given an index.
+		NewAccountIndex(AccountId, AccountIndex),
+	}
+);
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Indices {
+		/// The next free enumeration set.
+		pub NextEnumSet get(next_enum_set) build(|config: &GenesisConfig<T>| {
+			T::AccountIndex::sa(config.ids.len() / ENUM_SET_SIZE)
+		}): T::AccountIndex;
+		/// The enumeration sets.
+		pub EnumSet get(enum_set): map T::AccountIndex => Vec<T::AccountId>;
+		/// The index of a given account, for as long as it isn't reaped.
+		pub IndexOf get(index_of) build(|config: &GenesisConfig<T>| {
+			config.ids.iter().enumerate()
+				.map(|(i, who)| (who.clone(), T::AccountIndex::sa(i)))
+				.collect::<Vec<_>>()
+		}): map T::AccountId => Option<T::AccountIndex>;
+		/// The number of blocks after an account is reaped before its index can be given to
+		/// another account.
+		pub ReclaimDelay get(reclaim_delay) config(): T::BlockNumber;
+		/// The index of a reaped account that hasn't been reclaimed yet, along with its position
+		/// in the reclaim queue.
+		pub ReapedIndexOf get(reaped_index_of): map T::AccountId => Option<(T::AccountIndex, u32)>;
+		/// The queue of the indices of reaped accounts, by position, along with the block from
+		/// which each can be reclaimed. An index taken back by its own account leaves a gap.
+		pub ReclaimQueue get(reclaim_queue): map u32 => Option<(T::AccountIndex, T::BlockNumber)>;
+		/// The position of the next index to reclaim in the queue.
+		pub NextReclaim get(next_reclaim): u32;
+		/// The number of indices ever put in the reclaim queue.
+		pub ReclaimCount get(reclaim_count): u32;
+	}
+	add_extra_genesis {
+		config(ids): Vec<T::AccountId>;
+		build(|storage: &mut primitives::StorageMap, _: &mut primitives::ChildrenStorageMap, config: &GenesisConfig<T>| {
+			for i in 0..(config.ids.len() + ENUM_SET_SIZE - 1) / ENUM_SET_SIZE {
+				storage.insert(GenesisConfig::<T>::hash(&<EnumSet<T>>::key_for(T::AccountIndex::sa(i))).to_vec(),
+					config.ids[i * ENUM_SET_SIZE..config.ids.len().min((i + 1) * ENUM_SET_SIZE)].to_owned().encode());
+			}
+		});
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// PUBLIC IMMUTABLES
+
+	/// Lookup an T::AccountIndex to get an Id, if there's one there.
+	pub fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
+		let enum_set_size = Self::enum_set_size();
+		let set = Self::enum_set(index / enum_set_size);
+		let i: usize = (index % enum_set_size).as_();
+		set.get(i).map(|x| x.clone())
+	}
+
+	/// `true` if the account `index` is ready for reclaim.
+	pub fn can_reclaim(try_index: T::AccountIndex) -> bool {
+		let now = <system::Module<T>>::block_number();
+		Self::lookup_index(try_index)
+			.and_then(|who| Self::reaped_index_of(who))
+			.filter(|&(index, _)| index == try_index)
+			.and_then(|(_, position)| Self::reclaim_queue(position))
+			.map_or(false, |(_, from)| from <= now)
+	}
+
+	/// Lookup an address to get an Id, if there's one there.
+	pub fn lookup_address(a: address::Address<T::AccountId, T::AccountIndex>) -> Option<T::AccountId> {
+		match a {
+			address::Address::Id(i) => Some(i),
+			address::Address::Index(i) => Self::lookup_index(i),
+		}
+	}
+
+	pub fn lookup(a: address::Address<T::AccountId, T::AccountIndex>) -> result::Result<T::AccountId, &'static str> {
+		match a {
+			address::Address::Id(i) => Ok(i),
+			address::Address::Index(i) => <Module<T>>::lookup_index(i).ok_or("invalid account index"),
+		}
+	}
+
+	//PUBLIC MUTABLES (DANGEROUS)
+
+	/// Give an index to `who`, an account that has just been created.
+	///
+	/// If `who` was reaped and its index hasn't been reclaimed since, it gets that index back.
+	/// Otherwise it reclaims the index at the head of the reclaim queue, if its delay is over,
+	/// or gets a fresh one.
+	pub fn on_new_account(who: &T::AccountId) -> T::AccountIndex {
+		if let Some(index) = Self::index_of(who) {
+			return index;
+		}
+
+		let index = match <ReapedIndexOf<T>>::take(who) {
+			Some((index, position)) => {
+				<ReclaimQueue<T>>::remove(position);
+				index
+			}
+			None => Self::reclaim(who).unwrap_or_else(|| Self::push_fresh(who)),
+		};
+
+		<IndexOf<T>>::insert(who, index);
+		Self::deposit_event(RawEvent::NewAccountIndex(who.clone(), index));
+		index
+	}
+
+	/// Make the index of `who`, an account that has just been reaped, reclaimable once
+	/// `ReclaimDelay` blocks have passed.
+	///
+	/// Until it's reclaimed, the index still looks up to `who`.
+	pub fn on_reaped_account(who: &T::AccountId) {
+		if let Some(index) = <IndexOf<T>>::take(who) {
+			let from = <system::Module<T>>::block_number() + Self::reclaim_delay();
+			let position = Self::reclaim_count();
+			<ReclaimQueue<T>>::insert(position, (index, from));
+			<ReclaimCount<T>>::put(position + 1);
+			<ReapedIndexOf<T>>::insert(who, (index, position));
+		}
+	}
+
+	/// Give `who` the index at the head of the reclaim queue, if its delay is over.
+	///
+	/// A gap at the head is skipped, and no index is reclaimed this time.
+	fn reclaim(who: &T::AccountId) -> Option<T::AccountIndex> {
+		let position = Self::next_reclaim();
+		if position == Self::reclaim_count() {
+			return None;
+		}
+
+		let index = match Self::reclaim_queue(position) {
+			Some((index, from)) => {
+				if from > <system::Module<T>>::block_number() {
+					return None;
+				}
+				index
+			}
+			None => {
+				<NextReclaim<T>>::put(position + 1);
+				return None;
+			}
+		};
+
+		<ReclaimQueue<T>>::remove(position);
+		<NextReclaim<T>>::put(position + 1);
+
+		let enum_set_size = Self::enum_set_size();
+		let i: usize = (index % enum_set_size).as_();
+		<EnumSet<T>>::mutate(index / enum_set_size, |set| {
+			<ReapedIndexOf<T>>::remove(&set[i]);
+			set[i] = who.clone();
+		});
+		Some(index)
+	}
+
+	fn enum_set_size() -> T::AccountIndex {
+		T::AccountIndex::sa(ENUM_SET_SIZE)
+	}
+
+	/// Append `who` to the next free enumeration set.
+	fn push_fresh(who: &T::AccountId) -> T::AccountIndex {
+		let mut set_index = Self::next_enum_set();
+		// defensive only: this loop should never iterate since we keep NextEnumSet up to date later.
+		let mut set = loop {
+			let set = Self::enum_set(set_index);
+			if set.len() < ENUM_SET_SIZE {
+				break set;
+			}
+			set_index += One::one();
+		};
+
+		let index = T::AccountIndex::sa(set_index.as_() * ENUM_SET_SIZE + set.len());
+
+		// update set.
+		set.push(who.clone());
+
+		// keep NextEnumSet up to date
+		if set.len() == ENUM_SET_SIZE {
+			<NextEnumSet<T>>::put(set_index + One::one());
+		}
+
+		// write set.
+		<EnumSet<T>>::insert(set_index, set);
+
+		index
+	}
+}
+
+pub struct ChainContext<T>(::rstd::marker::PhantomData<T>);
+impl<T> Default for ChainContext<T> {
+	fn default() -> Self {
+		ChainContext(::rstd::marker::PhantomData)
+	}
+}
+
+impl<T: Trait> Lookup for ChainContext<T> {
+	type Source = address::Address<T::AccountId, T::AccountIndex>;
+	type Target = T::AccountId;
+	fn lookup(&self, a: Self::Source) -> result::Result<Self::Target, &'static str> {
+		<Module<T>>::lookup(a)
+	}
+}
+
+impl<T: Trait> CurrentHeight for ChainContext<T> {
+	type BlockNumber = T::BlockNumber;
+	fn current_height(&self) -> Self::BlockNumber {
+		<system::Module<T>>::block_number()
+	}
+}
+
+impl<T: Trait> BlockNumberToHash for ChainContext<T> {
+	type BlockNumber = T::BlockNumber;
+	type Hash = T::Hash;
+	fn block_number_to_hash(&self, n: Self::BlockNumber) -> Option<Self::Hash> {
+		Some(<system::Module<T>>::block_hash(n))
+	}
+}
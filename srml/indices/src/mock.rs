@@ -0,0 +1,60 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use primitives::BuildStorage;
+use primitives::testing::{Digest, DigestItem, Header};
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use {GenesisConfig, Module, Trait, system};
+
+impl_outer_origin!{
+	pub enum Origin for Runtime {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Runtime;
+impl system::Trait for Runtime {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = ::primitives::traits::BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Header = Header;
+	type Event = ();
+	type Log = DigestItem;
+}
+impl Trait for Runtime {
+	type AccountIndex = u64;
+	type Event = ();
+}
+
+pub fn new_test_ext(ids: Vec<u64>, reclaim_delay: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
+	t.extend(GenesisConfig::<Runtime> {
+		ids,
+		reclaim_delay,
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+pub type Indices = Module<Runtime>;
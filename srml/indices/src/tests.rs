@@ -0,0 +1,95 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use super::*;
+use mock::{Indices, Runtime, new_test_ext};
+use runtime_io::with_externalities;
+
+#[test]
+fn genesis_accounts_are_indexed_in_order() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3, 4], 0), || {
+		assert_eq!(Indices::lookup_index(0), Some(1));
+		assert_eq!(Indices::lookup_index(3), Some(4));
+		assert_eq!(Indices::lookup_index(4), None);
+		assert_eq!(Indices::index_of(&2), Some(1));
+		assert_eq!(Indices::lookup(RawAddress::Index(2)), Ok(3));
+		assert_eq!(Indices::lookup(RawAddress::Id(9)), Ok(9));
+		assert_eq!(Indices::lookup(RawAddress::Index(7)), Err("invalid account index"));
+	});
+}
+
+#[test]
+fn new_accounts_fill_up_enum_sets() {
+	with_externalities(&mut new_test_ext((0..ENUM_SET_SIZE as u64 - 1).collect(), 0), || {
+		assert_eq!(Indices::on_new_account(&100), ENUM_SET_SIZE as u64 - 1);
+		assert_eq!(Indices::next_enum_set(), 1);
+		assert_eq!(Indices::on_new_account(&101), ENUM_SET_SIZE as u64);
+		assert_eq!(Indices::lookup_index(ENUM_SET_SIZE as u64), Some(101));
+		// an account that has an index keeps it.
+		assert_eq!(Indices::on_new_account(&100), ENUM_SET_SIZE as u64 - 1);
+	});
+}
+
+#[test]
+fn indices_of_reaped_accounts_are_reclaimed() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3, 4], 0), || {
+		Indices::on_reaped_account(&2);
+		Indices::on_reaped_account(&3);
+		assert!(Indices::can_reclaim(1));
+		assert_eq!(Indices::index_of(&2), None);
+		// until it's reclaimed, the index still points at the reaped account.
+		assert_eq!(Indices::lookup_index(1), Some(2));
+
+		// the oldest reaped index goes first.
+		assert_eq!(Indices::on_new_account(&5), 1);
+		assert_eq!(Indices::lookup_index(1), Some(5));
+		assert!(!Indices::can_reclaim(1));
+
+		// a reaped account that comes back gets its own index back.
+		Indices::on_reaped_account(&4);
+		assert_eq!(Indices::on_new_account(&4), 3);
+		assert_eq!(Indices::on_new_account(&6), 2);
+		assert_eq!(Indices::on_new_account(&7), 4);
+		assert_eq!(Indices::next_reclaim(), Indices::reclaim_count());
+	});
+}
+
+#[test]
+fn reaped_indices_are_only_reclaimed_after_the_delay() {
+	with_externalities(&mut new_test_ext(vec![1, 2, 3], 10), || {
+		system::Module::<Runtime>::set_block_number(5);
+		Indices::on_reaped_account(&2);
+		assert!(!Indices::can_reclaim(1));
+
+		// too early: a new account gets a fresh index...
+		system::Module::<Runtime>::set_block_number(14);
+		assert_eq!(Indices::on_new_account(&4), 3);
+		// ...but the reaped account can still have its own back.
+		assert_eq!(Indices::on_new_account(&2), 1);
+
+		Indices::on_reaped_account(&2);
+		system::Module::<Runtime>::set_block_number(23);
+		assert!(!Indices::can_reclaim(1));
+		system::Module::<Runtime>::set_block_number(24);
+		assert!(Indices::can_reclaim(1));
+		assert_eq!(Indices::on_new_account(&5), 1);
+		assert_eq!(Indices::lookup_index(1), Some(5));
+	});
+}
@@ -286,6 +286,17 @@ pub struct RuntimeModuleMetadata {
 	pub storage: Option<DecodeDifferent<FnEncode<StorageMetadata>, StorageMetadata>>,
 }
 
+/// How a type named in the metadata is encoded.
+///
+/// The layout is a type expression over `bool`, `u8` to `u128`, `[u8; N]`, `Compact<..>`,
+/// `Vec<..>`, tuples and the other named types.
+#[derive(Clone, PartialEq, Eq, Encode)]
+#[cfg_attr(feature = "std", derive(Decode, Debug, Serialize))]
+pub struct TypeMetadata {
+	pub name: DecodeDifferentStr,
+	pub layout: DecodeDifferentStr,
+}
+
 /// The metadata of a runtime.
 #[derive(Eq, Encode, PartialEq)]
 #[cfg_attr(feature = "std", derive(Decode, Debug, Serialize))]
@@ -293,4 +304,5 @@ pub struct RuntimeMetadata {
 	pub outer_event: OuterEventMetadata,
 	pub modules: DecodeDifferentArray<RuntimeModuleMetadata>,
 	pub outer_dispatch: OuterDispatchMetadata,
+	pub types: DecodeDifferentArray<TypeMetadata>,
 }
@@ -0,0 +1,482 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Multisig: dispatches calls from an account controlled by M of N signatories.
+//!
+//! The account is derived from the sorted signatories and the threshold, so it needs no setup.
+//! The first signatory to approve a call reserves a deposit and records the timepoint of its
+//! approval; the others must name that timepoint along with their approval, so that an approval
+//! can't be replayed against a later operation for the same call. The signatory giving the final
+//! approval must provide the call itself, which is then dispatched from the multisig account.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate sr_std as rstd;
+
+#[macro_use]
+extern crate srml_support as runtime_support;
+
+#[cfg(test)]
+extern crate sr_io as runtime_io;
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "std")]
+extern crate serde;
+
+#[macro_use]
+extern crate parity_codec_derive;
+
+extern crate parity_codec as codec;
+#[cfg(test)]
+extern crate substrate_primitives;
+extern crate sr_primitives as runtime_primitives;
+extern crate srml_system as system;
+extern crate srml_balances as balances;
+#[cfg(test)]
+extern crate srml_indices as indices;
+
+use rstd::prelude::*;
+use runtime_support::{StorageMap, Parameter, Dispatchable};
+use runtime_support::dispatch::Result;
+use runtime_primitives::traits::{As, Hash};
+use codec::{Encode, Decode};
+use system::ensure_signed;
+
+/// The maximum number of signatories of a multisig account.
+const MAX_SIGNATORIES: usize = 100;
+
+/// A point in the chain: a block and the index of an extrinsic in it.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct Timepoint<BlockNumber> {
+	/// The block number.
+	pub height: BlockNumber,
+	/// The index of the extrinsic in the block.
+	pub index: u32,
+}
+
+/// An operation of a multisig account awaiting approval.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Multisig<BlockNumber, Balance, AccountId> {
+	/// The timepoint of the first approval.
+	pub when: Timepoint<BlockNumber>,
+	/// The amount reserved from the depositor.
+	pub deposit: Balance,
+	/// The signatory who gave the first approval and reserved the deposit.
+	pub depositor: AccountId,
+	/// The signatories who approved so far.
+	pub approvals: Vec<AccountId>,
+}
+
+/// Our module's configuration trait.
+pub trait Trait: balances::Trait {
+	/// The aggregated call type, dispatched with the origin of the multisig account.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// The call, or only its hash, that an approval is given for.
+enum CallOrHash<Call, Hash> {
+	Call(Call),
+	Hash(Hash),
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Approve `call` for dispatch from the multisig account of the transactor and
+		/// `other_signatories` with `threshold`. The first approval must be given without a
+		/// timepoint; the others with the timepoint of the first. The call is dispatched on the
+		/// final approval.
+		fn as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call: Box<<T as Trait>::Call>
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, CallOrHash::Call(*call))
+		}
+
+		/// Approve the call with hash `call_hash`, without providing the call. Can't be used for the
+		/// final approval.
+		fn approve_as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call_hash: T::Hash
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, CallOrHash::Hash(call_hash))
+		}
+
+		/// Cancel an operation awaiting approval, returning the deposit. Only the depositor may do
+		/// so.
+		fn cancel_as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			timepoint: Timepoint<T::BlockNumber>,
+			call_hash: T::Hash
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::ensure_signatories(who.clone(), other_signatories, threshold)?;
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			let key = (id.clone(), call_hash);
+			let multisig = Self::multisigs(&key).ok_or("multisig operation not found")?;
+			ensure!(multisig.when == timepoint, "wrong timepoint");
+			ensure!(multisig.depositor == who, "only the depositor may cancel");
+
+			<Multisigs<T>>::remove(&key);
+			<balances::Module<T>>::unreserve(&who, multisig.deposit);
+			Self::deposit_event(RawEvent::MultisigCancelled(who, timepoint, id));
+			Ok(())
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Multisig {
+		/// The operations awaiting approval, by multisig account and call hash.
+		pub Multisigs get(multisigs): map (T::AccountId, T::Hash) => Option<Multisig<T::BlockNumber, T::Balance, T::AccountId>>;
+
+		/// The base amount reserved for an operation awaiting approval.
+		pub DepositBase get(deposit_base) config(): T::Balance;
+		/// The amount reserved per unit of threshold for an operation awaiting approval.
+		pub DepositFactor get(deposit_factor) config(): T::Balance;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber {
+		/// The first account started a new operation of the multisig account (second).
+		NewMultisig(AccountId, AccountId),
+		/// The first account approved an operation of the multisig account (third).
+		MultisigApproval(AccountId, Timepoint<BlockNumber>, AccountId),
+		/// The first account gave the final approval, and the call of the multisig account (third)
+		/// was dispatched, successfully or not.
+		MultisigExecuted(AccountId, Timepoint<BlockNumber>, AccountId, bool),
+		/// The first account cancelled an operation of the multisig account (third).
+		MultisigCancelled(AccountId, Timepoint<BlockNumber>, AccountId),
+	}
+);
+
+impl<T: Trait> Module<T> {
+	/// The account controlled by the given signatories, which must be sorted, with `threshold`.
+	pub fn multi_account_id(signatories: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let mut entropy = b"multisig".to_vec();
+		signatories.encode_to(&mut entropy);
+		threshold.encode_to(&mut entropy);
+		let hash = T::Hashing::hash(&entropy);
+		T::AccountId::decode(&mut hash.as_ref()).unwrap_or_default()
+	}
+
+	/// The timepoint of the extrinsic being applied.
+	pub fn timepoint() -> Timepoint<T::BlockNumber> {
+		Timepoint {
+			height: <system::Module<T>>::block_number(),
+			index: <system::Module<T>>::extrinsic_index().unwrap_or_default(),
+		}
+	}
+
+	/// Sort `who` in with `other_signatories`, checking that they make a valid set of signatories
+	/// for `threshold`.
+	fn ensure_signatories(
+		who: T::AccountId,
+		mut other_signatories: Vec<T::AccountId>,
+		threshold: u16
+	) -> rstd::result::Result<Vec<T::AccountId>, &'static str> {
+		other_signatories.push(who);
+		other_signatories.sort();
+		let signatories = other_signatories;
+
+		ensure!(signatories.len() <= MAX_SIGNATORIES, "too many signatories");
+		ensure!(signatories.windows(2).all(|w| w[0] != w[1]), "duplicate signatories");
+		ensure!(threshold >= 2, "threshold must be at least two");
+		ensure!(threshold as usize <= signatories.len(), "threshold exceeds the number of signatories");
+		Ok(signatories)
+	}
+
+	fn operate(
+		who: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_or_hash: CallOrHash<<T as Trait>::Call, T::Hash>
+	) -> Result {
+		let signatories = Self::ensure_signatories(who.clone(), other_signatories, threshold)?;
+		let id = Self::multi_account_id(&signatories, threshold);
+		let (call_hash, maybe_call) = match call_or_hash {
+			CallOrHash::Call(call) => (T::Hashing::hash_of(&call), Some(call)),
+			CallOrHash::Hash(call_hash) => (call_hash, None),
+		};
+		let key = (id.clone(), call_hash);
+
+		if let Some(mut multisig) = Self::multisigs(&key) {
+			let timepoint = maybe_timepoint.ok_or("timepoint of the first approval must be given")?;
+			ensure!(multisig.when == timepoint, "wrong timepoint");
+			ensure!(!multisig.approvals.contains(&who), "already approved");
+
+			if multisig.approvals.len() + 1 < threshold as usize {
+				multisig.approvals.push(who.clone());
+				<Multisigs<T>>::insert(&key, multisig);
+				Self::deposit_event(RawEvent::MultisigApproval(who, timepoint, id));
+				return Ok(());
+			}
+
+			let call = maybe_call.ok_or("the final approval must include the call")?;
+			<Multisigs<T>>::remove(&key);
+			<balances::Module<T>>::unreserve(&multisig.depositor, multisig.deposit);
+
+			let ok = call.dispatch(system::RawOrigin::Signed(id.clone()).into()).is_ok();
+			Self::deposit_event(RawEvent::MultisigExecuted(who, timepoint, id, ok));
+		} else {
+			ensure!(maybe_timepoint.is_none(), "multisig operation not found");
+
+			let deposit = Self::deposit_base() + Self::deposit_factor() * T::Balance::sa(threshold as u64);
+			<balances::Module<T>>::reserve(&who, deposit)
+				.map_err(|_| "not enough free funds for the multisig deposit")?;
+
+			<Multisigs<T>>::insert(&key, Multisig {
+				when: Self::timepoint(),
+				deposit,
+				depositor: who.clone(),
+				approvals: vec![who.clone()],
+			});
+			Self::deposit_event(RawEvent::NewMultisig(who, id));
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use substrate_primitives::{H256, Blake2Hasher};
+	use runtime_primitives::BuildStorage;
+	use runtime_primitives::traits::BlakeTwo256;
+	use runtime_primitives::testing::{Digest, DigestItem, Header};
+	use indices::address::Address;
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+			multisig::Multisig,
+		}
+	}
+
+	// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+	#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+	pub struct Test;
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
+		type Event = ();
+	}
+	impl Trait for Test {
+		type Call = Call;
+		type Event = ();
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Multisig = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test>{
+			balances: vec![(1, 10), (2, 10), (3, 10)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
+		}.build_storage().unwrap().0);
+		t.extend(GenesisConfig::<Test>{
+			deposit_base: 1,
+			deposit_factor: 1,
+		}.build_storage().unwrap().0);
+		runtime_io::TestExternalities::new(t)
+	}
+
+	fn transfer(dest: u64, value: u64) -> Call {
+		Call::Balances(balances::Call::transfer(Address::Id(dest), value.into()))
+	}
+
+	fn funded_multi_account(threshold: u16) -> u64 {
+		let multi = Multisig::multi_account_id(&[1, 2, 3], threshold);
+		assert_ok!(Balances::transfer(Origin::signed(1), Address::Id(multi), 5.into()));
+		multi
+	}
+
+	#[test]
+	fn multi_account_id_depends_on_signatories_and_threshold() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Multisig::multi_account_id(&[1, 2, 3], 2), Multisig::multi_account_id(&[1, 2, 3], 2));
+			assert!(Multisig::multi_account_id(&[1, 2, 3], 2) != Multisig::multi_account_id(&[1, 2, 3], 3));
+			assert!(Multisig::multi_account_id(&[1, 2, 3], 2) != Multisig::multi_account_id(&[1, 2, 4], 2));
+		});
+	}
+
+	#[test]
+	fn two_of_three_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let multi = funded_multi_account(2);
+			let timepoint = Multisig::timepoint();
+
+			assert_ok!(Multisig::as_multi(Origin::signed(1), 2, vec![3, 2], None, Box::new(transfer(6, 3))));
+			assert_eq!(Balances::reserved_balance(&1), 3);
+			assert_eq!(Balances::free_balance(&6), 0);
+
+			assert_ok!(Multisig::as_multi(Origin::signed(3), 2, vec![1, 2], Some(timepoint), Box::new(transfer(6, 3))));
+			assert_eq!(Balances::free_balance(&6), 3);
+			assert_eq!(Balances::free_balance(&multi), 2);
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Multisig::multisigs((multi, BlakeTwo256::hash_of(&transfer(6, 3)))), None);
+		});
+	}
+
+	#[test]
+	fn approvals_need_the_right_timepoint() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			funded_multi_account(2);
+			let timepoint = Multisig::timepoint();
+			let call_hash = BlakeTwo256::hash_of(&transfer(6, 3));
+
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(1), 2, vec![2, 3], Some(timepoint), call_hash),
+				"multisig operation not found"
+			);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 2, vec![2, 3], None, call_hash));
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], None, Box::new(transfer(6, 3))),
+				"timepoint of the first approval must be given"
+			);
+			let stale = Timepoint { height: 0, index: 0 };
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], Some(stale), Box::new(transfer(6, 3))),
+				"wrong timepoint"
+			);
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(2), 2, vec![1, 3], Some(timepoint), call_hash),
+				"the final approval must include the call"
+			);
+
+			assert_ok!(Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], Some(timepoint), Box::new(transfer(6, 3))));
+			assert_eq!(Balances::free_balance(&6), 3);
+		});
+	}
+
+	#[test]
+	fn three_of_three_should_collect_all_approvals() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			funded_multi_account(3);
+			let timepoint = Multisig::timepoint();
+			let call_hash = BlakeTwo256::hash_of(&transfer(6, 3));
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, call_hash));
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], Some(timepoint), call_hash),
+				"already approved"
+			);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], Some(timepoint), call_hash));
+			assert_eq!(Balances::free_balance(&6), 0);
+
+			assert_ok!(Multisig::as_multi(Origin::signed(3), 3, vec![1, 2], Some(timepoint), Box::new(transfer(6, 3))));
+			assert_eq!(Balances::free_balance(&6), 3);
+		});
+	}
+
+	#[test]
+	fn only_the_depositor_may_cancel() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			funded_multi_account(2);
+			let timepoint = Multisig::timepoint();
+			let call_hash = BlakeTwo256::hash_of(&transfer(6, 3));
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 2, vec![2, 3], None, call_hash));
+			assert_noop!(
+				Multisig::cancel_as_multi(Origin::signed(2), 2, vec![1, 3], timepoint, call_hash),
+				"only the depositor may cancel"
+			);
+			assert_ok!(Multisig::cancel_as_multi(Origin::signed(1), 2, vec![2, 3], timepoint, call_hash));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], Some(timepoint), Box::new(transfer(6, 3))),
+				"multisig operation not found"
+			);
+		});
+	}
+
+	#[test]
+	fn invalid_signatories_are_rejected() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![1, 2], None, Box::new(transfer(6, 3))),
+				"duplicate signatories"
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 1, vec![2, 3], None, Box::new(transfer(6, 3))),
+				"threshold must be at least two"
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 4, vec![2, 3], None, Box::new(transfer(6, 3))),
+				"threshold exceeds the number of signatories"
+			);
+		});
+	}
+}
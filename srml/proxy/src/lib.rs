@@ -0,0 +1,440 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proxy: lets an account authorise other accounts to dispatch some class of calls on its
+//! behalf, so that e.g. a validator's stash key can be kept cold.
+//!
+//! A proxy may be given a delay, in which case it has to announce the hash of each call that
+//! many blocks before dispatching it, leaving the proxied account time to reject it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate sr_std as rstd;
+
+#[macro_use]
+extern crate srml_support as runtime_support;
+
+#[cfg(test)]
+extern crate sr_io as runtime_io;
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "std")]
+extern crate serde;
+
+#[macro_use]
+extern crate parity_codec_derive;
+
+extern crate parity_codec as codec;
+#[cfg(test)]
+extern crate substrate_primitives;
+extern crate sr_primitives as runtime_primitives;
+extern crate srml_system as system;
+extern crate srml_balances as balances;
+extern crate srml_indices as indices;
+
+use rstd::prelude::*;
+use runtime_support::{StorageMap, Parameter, Dispatchable};
+use runtime_support::dispatch::Result;
+use runtime_primitives::traits::{Zero, As, Hash};
+use codec::HasCompact;
+use indices::address::Address;
+use system::ensure_signed;
+
+/// The maximum number of proxies an account may have.
+const MAX_PROXIES: usize = 32;
+
+/// The maximum number of announcements a proxy may have pending.
+const MAX_PENDING: usize = 32;
+
+/// Decides which calls a proxy of some type may dispatch.
+pub trait Filter<Call> {
+	/// Whether `call` may be dispatched by a proxy of this type.
+	fn filter(&self, call: &Call) -> bool;
+}
+
+/// The permission of an account to act on behalf of another.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProxyDefinition<AccountId, ProxyType, BlockNumber> {
+	/// The account which may act as proxy.
+	pub delegate: AccountId,
+	/// The class of calls the proxy may dispatch.
+	pub proxy_type: ProxyType,
+	/// The number of blocks a call has to be announced for before it may be dispatched.
+	pub delay: BlockNumber,
+}
+
+/// A call a delayed proxy intends to dispatch.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Announcement<AccountId, Hash, BlockNumber> {
+	/// The account on whose behalf the call is to be dispatched.
+	pub real: AccountId,
+	/// The hash of the call.
+	pub call_hash: Hash,
+	/// The block at which the call was announced.
+	pub height: BlockNumber,
+}
+
+/// Our module's configuration trait.
+pub trait Trait: balances::Trait {
+	/// The aggregated call type, dispatched with the origin of the proxied account.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+
+	/// The classes of calls that may be delegated to a proxy.
+	type ProxyType: Parameter + Filter<<Self as Trait>::Call>;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Let `delegate` dispatch calls of `proxy_type` on behalf of the transactor, once they
+		/// have been announced for `delay` blocks. A deposit is reserved for as long as the proxy
+		/// exists.
+		fn add_proxy(
+			origin,
+			delegate: Address<T::AccountId, T::AccountIndex>,
+			proxy_type: T::ProxyType,
+			delay: <T::BlockNumber as HasCompact>::Type
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			let delegate = <indices::Module<T>>::lookup(delegate)?;
+			let definition = ProxyDefinition { delegate, proxy_type, delay: delay.into() };
+
+			let mut proxies = Self::proxies(&who);
+			ensure!(proxies.len() < MAX_PROXIES, "too many proxies");
+			ensure!(!proxies.contains(&definition), "proxy already exists");
+			<balances::Module<T>>::reserve(&who, Self::proxy_deposit())
+				.map_err(|_| "not enough free funds for the proxy deposit")?;
+
+			Self::deposit_event(RawEvent::ProxyAdded(who.clone(), definition.delegate.clone()));
+			proxies.push(definition);
+			<Proxies<T>>::insert(&who, proxies);
+			Ok(())
+		}
+
+		/// Revoke the permission of `delegate` to dispatch calls of `proxy_type` on behalf of the
+		/// transactor, returning the deposit.
+		fn remove_proxy(
+			origin,
+			delegate: Address<T::AccountId, T::AccountIndex>,
+			proxy_type: T::ProxyType
+		) -> Result {
+			let who = ensure_signed(origin)?;
+			let delegate = <indices::Module<T>>::lookup(delegate)?;
+
+			let mut proxies = Self::proxies(&who);
+			let before = proxies.len();
+			proxies.retain(|p| p.delegate != delegate || p.proxy_type != proxy_type);
+			let removed = before - proxies.len();
+			ensure!(removed > 0, "proxy not found");
+
+			<balances::Module<T>>::unreserve(&who, Self::proxy_deposit() * T::Balance::sa(removed as u64));
+			if proxies.is_empty() {
+				<Proxies<T>>::remove(&who);
+			} else {
+				<Proxies<T>>::insert(&who, proxies);
+			}
+			Self::deposit_event(RawEvent::ProxyRemoved(who, delegate));
+			Ok(())
+		}
+
+		/// Announce the intention to dispatch the call with hash `call_hash` on behalf of `real`.
+		fn announce(origin, real: Address<T::AccountId, T::AccountIndex>, call_hash: T::Hash) -> Result {
+			let who = ensure_signed(origin)?;
+			let real = <indices::Module<T>>::lookup(real)?;
+			ensure!(Self::proxies(&real).iter().any(|p| p.delegate == who), "not a proxy of this account");
+
+			let mut announcements = Self::announcements(&who);
+			ensure!(announcements.len() < MAX_PENDING, "too many pending announcements");
+			announcements.push(Announcement {
+				real: real.clone(),
+				call_hash,
+				height: <system::Module<T>>::block_number(),
+			});
+			<Announcements<T>>::insert(&who, announcements);
+			Self::deposit_event(RawEvent::Announced(real, who, call_hash));
+			Ok(())
+		}
+
+		/// Withdraw an announcement made by the transactor.
+		fn remove_announcement(origin, real: Address<T::AccountId, T::AccountIndex>, call_hash: T::Hash) -> Result {
+			let who = ensure_signed(origin)?;
+			let real = <indices::Module<T>>::lookup(real)?;
+			Self::remove_announcements(&who, &real, call_hash)
+		}
+
+		/// Reject an announcement made by a proxy of the transactor, so that the call can't be
+		/// dispatched.
+		fn reject_announcement(origin, delegate: Address<T::AccountId, T::AccountIndex>, call_hash: T::Hash) -> Result {
+			let who = ensure_signed(origin)?;
+			let delegate = <indices::Module<T>>::lookup(delegate)?;
+			Self::remove_announcements(&delegate, &who, call_hash)
+		}
+
+		/// Dispatch `call` on behalf of `real`, of whom the transactor is a proxy. If the proxy is
+		/// delayed, the call must have been announced long enough ago.
+		fn proxy(origin, real: Address<T::AccountId, T::AccountIndex>, call: Box<<T as Trait>::Call>) -> Result {
+			let who = ensure_signed(origin)?;
+			let real = <indices::Module<T>>::lookup(real)?;
+			let call = *call;
+
+			let definition = Self::proxies(&real).into_iter()
+				.filter(|p| p.delegate == who && p.proxy_type.filter(&call))
+				.min_by_key(|p| p.delay)
+				.ok_or("no proxy permits this call")?;
+
+			if !definition.delay.is_zero() {
+				let call_hash = T::Hashing::hash_of(&call);
+				let now = <system::Module<T>>::block_number();
+				let mut announcements = Self::announcements(&who);
+				let position = announcements.iter()
+					.position(|a| a.real == real && a.call_hash == call_hash && a.height + definition.delay <= now)
+					.ok_or("call not announced long enough ago")?;
+				announcements.remove(position);
+				<Announcements<T>>::insert(&who, announcements);
+			}
+
+			let ok = call.dispatch(system::RawOrigin::Signed(real).into()).is_ok();
+			Self::deposit_event(RawEvent::ProxyExecuted(ok));
+			Ok(())
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The proxies of each account.
+		pub Proxies get(proxies): map T::AccountId => Vec<ProxyDefinition<T::AccountId, T::ProxyType, T::BlockNumber>>;
+		/// The announcements pending for each proxy.
+		pub Announcements get(announcements): map T::AccountId => Vec<Announcement<T::AccountId, T::Hash, T::BlockNumber>>;
+
+		/// The amount reserved for each proxy of an account.
+		pub ProxyDeposit get(proxy_deposit) config(): T::Balance;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::Hash {
+		/// The first account has made the second its proxy.
+		ProxyAdded(AccountId, AccountId),
+		/// The first account has revoked the second as its proxy.
+		ProxyRemoved(AccountId, AccountId),
+		/// The proxy (second) has announced a call with the given hash on behalf of the first.
+		Announced(AccountId, AccountId, Hash),
+		/// A call was dispatched by a proxy, successfully or not.
+		ProxyExecuted(bool),
+	}
+);
+
+impl<T: Trait> Module<T> {
+	/// Remove the announcements of `delegate` to dispatch the call with `call_hash` on behalf of
+	/// `real`.
+	fn remove_announcements(delegate: &T::AccountId, real: &T::AccountId, call_hash: T::Hash) -> Result {
+		let mut announcements = Self::announcements(delegate);
+		let before = announcements.len();
+		announcements.retain(|a| &a.real != real || a.call_hash != call_hash);
+		ensure!(announcements.len() < before, "announcement not found");
+
+		if announcements.is_empty() {
+			<Announcements<T>>::remove(delegate);
+		} else {
+			<Announcements<T>>::insert(delegate, announcements);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use runtime_io::with_externalities;
+	use substrate_primitives::{H256, Blake2Hasher};
+	use runtime_primitives::BuildStorage;
+	use runtime_primitives::traits::BlakeTwo256;
+	use runtime_primitives::testing::{Digest, DigestItem, Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+			proxy::Proxy,
+		}
+	}
+
+	#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+	pub enum ProxyType {
+		Any,
+		Transfer,
+	}
+
+	impl Filter<Call> for ProxyType {
+		fn filter(&self, call: &Call) -> bool {
+			match (self, call) {
+				(ProxyType::Any, _) => true,
+				(ProxyType::Transfer, Call::Balances(balances::Call::transfer(..))) => true,
+				_ => false,
+			}
+		}
+	}
+
+	// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+	#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+	pub struct Test;
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
+		type Event = ();
+	}
+	impl Trait for Test {
+		type Call = Call;
+		type ProxyType = ProxyType;
+		type Event = ();
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Proxy = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test>{
+			balances: vec![(1, 10), (2, 20), (3, 30)],
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
+		}.build_storage().unwrap().0);
+		t.extend(GenesisConfig::<Test>{
+			proxy_deposit: 1,
+		}.build_storage().unwrap().0);
+		runtime_io::TestExternalities::new(t)
+	}
+
+	fn transfer(dest: u64, value: u64) -> Call {
+		Call::Balances(balances::Call::transfer(Address::Id(dest), value.into()))
+	}
+
+	#[test]
+	fn proxies_can_only_dispatch_permitted_calls() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), Address::Id(2), ProxyType::Transfer, 0.into()));
+			assert_eq!(Balances::reserved_balance(&1), 1);
+
+			assert_ok!(Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(transfer(3, 5))));
+			assert_eq!(Balances::free_balance(&1), 4);
+			assert_eq!(Balances::free_balance(&3), 35);
+
+			let add_proxy = Call::Proxy(super::Call::add_proxy(Address::Id(2), ProxyType::Any, 0.into()));
+			assert_noop!(Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(add_proxy)), "no proxy permits this call");
+			assert_noop!(Proxy::proxy(Origin::signed(3), Address::Id(1), Box::new(transfer(3, 1))), "no proxy permits this call");
+		});
+	}
+
+	#[test]
+	fn removing_a_proxy_returns_the_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), Address::Id(2), ProxyType::Transfer, 0.into()));
+			assert_noop!(
+				Proxy::add_proxy(Origin::signed(1), Address::Id(2), ProxyType::Transfer, 0.into()),
+				"proxy already exists"
+			);
+			assert_noop!(Proxy::remove_proxy(Origin::signed(1), Address::Id(2), ProxyType::Any), "proxy not found");
+
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), Address::Id(2), ProxyType::Transfer));
+			assert_eq!(Balances::free_balance(&1), 10);
+			assert!(Proxy::proxies(&1).is_empty());
+			assert_noop!(Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(transfer(3, 5))), "no proxy permits this call");
+		});
+	}
+
+	#[test]
+	fn delayed_proxies_must_announce_calls() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), Address::Id(2), ProxyType::Any, 2.into()));
+			let call_hash = BlakeTwo256::hash_of(&transfer(3, 5));
+
+			assert_noop!(Proxy::announce(Origin::signed(3), Address::Id(1), call_hash), "not a proxy of this account");
+			assert_ok!(Proxy::announce(Origin::signed(2), Address::Id(1), call_hash));
+
+			System::set_block_number(2);
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(transfer(3, 5))),
+				"call not announced long enough ago"
+			);
+
+			System::set_block_number(3);
+			assert_ok!(Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(transfer(3, 5))));
+			assert_eq!(Balances::free_balance(&3), 35);
+			assert!(Proxy::announcements(&2).is_empty());
+		});
+	}
+
+	#[test]
+	fn rejected_announcements_cannot_be_dispatched() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), Address::Id(2), ProxyType::Any, 2.into()));
+			let call_hash = BlakeTwo256::hash_of(&transfer(3, 5));
+			assert_ok!(Proxy::announce(Origin::signed(2), Address::Id(1), call_hash));
+
+			assert_ok!(Proxy::reject_announcement(Origin::signed(1), Address::Id(2), call_hash));
+			assert_noop!(
+				Proxy::reject_announcement(Origin::signed(1), Address::Id(2), call_hash),
+				"announcement not found"
+			);
+
+			System::set_block_number(3);
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), Address::Id(1), Box::new(transfer(3, 5))),
+				"call not announced long enough ago"
+			);
+		});
+	}
+}
@@ -0,0 +1,449 @@
+// Copyright 2017-2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scheduler: dispatches calls with the root origin at a given future block, optionally
+//! repeating them periodically.
+//!
+//! The calls due at a block are dispatched when it is finalised, for as long as they fit in the
+//! block's weight budget. The remainder stays in its agenda, which is resumed in the following
+//! blocks before any newer one, so that the address of a task never changes until it is
+//! dispatched. The weight of a call is the length of its encoding.
+//!
+//! The repetitions of a periodic task are due relative to the block the task was due at rather
+//! than the one it was dispatched in, so that a delay does not shift all of its later runs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+extern crate substrate_primitives;
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate parity_codec_derive;
+extern crate sr_std as rstd;
+#[macro_use]
+extern crate srml_support;
+
+#[cfg(test)]
+extern crate sr_io as runtime_io;
+#[cfg(test)]
+extern crate srml_balances as balances;
+#[cfg(test)]
+extern crate srml_indices as indices;
+
+extern crate parity_codec as codec;
+extern crate sr_primitives as primitives;
+extern crate srml_system as system;
+
+use rstd::prelude::*;
+use rstd::result;
+use codec::{Encode, HasCompact, Compact};
+use primitives::traits::Zero;
+use srml_support::{StorageMap, Parameter, Dispatchable};
+use srml_support::dispatch::Result;
+
+/// The address of a scheduled task: the block it is due at and its index in that block's agenda.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// Something that can schedule calls for later dispatch with the root origin.
+pub trait Schedule<BlockNumber, Call> {
+	/// Schedule `call` for dispatch at block `when`. If `maybe_periodic` is `Some((period, count))`
+	/// it is dispatched again every `period` blocks, `count` more times.
+	fn schedule(
+		when: BlockNumber,
+		maybe_periodic: Option<(BlockNumber, u32)>,
+		call: Call
+	) -> result::Result<TaskAddress<BlockNumber>, &'static str>;
+
+	/// Cancel the task at `address`, along with any of its future repetitions.
+	fn cancel(address: TaskAddress<BlockNumber>) -> Result;
+}
+
+/// A call waiting in the agenda.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Scheduled<Call, BlockNumber> {
+	/// The call to dispatch.
+	pub call: Call,
+	/// The period and number of remaining repetitions, if the call is periodic.
+	pub maybe_periodic: Option<(BlockNumber, u32)>,
+}
+
+pub trait Trait: system::Trait {
+	/// The aggregated call type, dispatched with the root origin.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Schedule a call for dispatch at block `when`, optionally repeating it.
+		fn schedule(
+			when: <T::BlockNumber as HasCompact>::Type,
+			maybe_periodic: Option<(T::BlockNumber, u32)>,
+			call: Box<<T as Trait>::Call>
+		) -> Result {
+			Self::do_schedule(when.into(), maybe_periodic, *call).map(|_| ())
+		}
+
+		/// Cancel the task scheduled at block `when` with the given index in its agenda.
+		fn cancel(when: <T::BlockNumber as HasCompact>::Type, index: Compact<u32>) -> Result {
+			Self::do_cancel((when.into(), index.into()))
+		}
+
+		fn on_finalise(n: T::BlockNumber) {
+			Self::dispatch_agenda(n);
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// The tasks due at a given block. Cancelled and dispatched entries are left as `None`, so
+		/// that the addresses of the others stay the same.
+		pub Agenda get(agenda): map T::BlockNumber => Vec<Option<Scheduled<<T as Trait>::Call, T::BlockNumber>>>;
+
+		/// The past blocks whose agenda did not fit in the weight budget, oldest first.
+		pub Incomplete get(incomplete): Vec<T::BlockNumber>;
+
+		/// The total weight of the calls that may be dispatched in a single block.
+		pub MaximumWeight get(maximum_weight) config(): u32 = 64 * 1024;
+	}
+}
+
+decl_event!(
+	/// An event in this module.
+	pub enum Event<T> where <T as system::Trait>::BlockNumber {
+		/// A task was scheduled at the given address.
+		Scheduled(BlockNumber, u32),
+		/// The task at the given address was cancelled.
+		Cancelled(BlockNumber, u32),
+		/// The task at the given address was dispatched, successfully or not.
+		Dispatched(BlockNumber, u32, bool),
+	}
+);
+
+impl<T: Trait> Module<T> {
+	fn do_schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<(T::BlockNumber, u32)>,
+		call: <T as Trait>::Call
+	) -> result::Result<TaskAddress<T::BlockNumber>, &'static str> {
+		ensure!(when > <system::Module<T>>::block_number(), "scheduled block must be in the future");
+		if let Some((period, _)) = maybe_periodic {
+			ensure!(!period.is_zero(), "period must be non-zero");
+		}
+
+		let index = Self::push_task(when, Scheduled { call, maybe_periodic });
+		Self::deposit_event(RawEvent::Scheduled(when, index));
+		Ok((when, index))
+	}
+
+	fn do_cancel((when, index): TaskAddress<T::BlockNumber>) -> Result {
+		let mut agenda = Self::agenda(when);
+		if agenda.get_mut(index as usize).and_then(Option::take).is_none() {
+			return Err("task not found");
+		}
+		<Agenda<T>>::insert(when, agenda);
+		Self::deposit_event(RawEvent::Cancelled(when, index));
+		Ok(())
+	}
+
+	/// Append a task to the agenda of block `when`, returning its index there.
+	fn push_task(when: T::BlockNumber, task: Scheduled<<T as Trait>::Call, T::BlockNumber>) -> u32 {
+		let mut index = 0;
+		<Agenda<T>>::mutate(when, |agenda| {
+			index = agenda.len() as u32;
+			agenda.push(Some(task));
+		});
+		index
+	}
+
+	/// Dispatch the tasks due at block `n`, and those left over from earlier blocks, within the
+	/// weight budget. The first task is always dispatched, however heavy, so that the agenda cannot
+	/// get stuck.
+	fn dispatch_agenda(n: T::BlockNumber) {
+		let limit = Self::maximum_weight() as usize;
+		let mut weight = 0;
+		let mut exhausted = false;
+		let mut incomplete = Vec::new();
+
+		let mut due = Self::incomplete();
+		due.push(n);
+
+		let mut i = 0;
+		while i < due.len() {
+			let when = due[i];
+			i += 1;
+
+			let mut agenda = Self::agenda(when);
+			for index in 0..agenda.len() {
+				if exhausted {
+					break;
+				}
+
+				let task_weight = match agenda[index] {
+					Some(ref task) => task.call.encode().len(),
+					None => continue,
+				};
+				if weight > 0 && weight + task_weight > limit {
+					exhausted = true;
+					break;
+				}
+				weight += task_weight;
+
+				if let Some(task) = agenda[index].take() {
+					let ok = task.call.clone().dispatch(system::RawOrigin::Root.into()).is_ok();
+					Self::deposit_event(RawEvent::Dispatched(when, index as u32, ok));
+
+					if let Some((period, count)) = task.maybe_periodic {
+						if count > 0 {
+							let next = when + period;
+							let maybe_periodic = Some((period, count - 1));
+							Self::push_task(next, Scheduled { call: task.call, maybe_periodic });
+							// a repetition that is already due joins the blocks to process.
+							if next < n {
+								if let Err(position) = due.binary_search(&next) {
+									due.insert(position, next);
+								}
+							}
+						}
+					}
+				}
+			}
+
+			if agenda.iter().all(Option::is_none) {
+				<Agenda<T>>::remove(when);
+			} else {
+				<Agenda<T>>::insert(when, agenda);
+				incomplete.push(when);
+			}
+		}
+
+		<Incomplete<T>>::put(incomplete);
+	}
+}
+
+impl<T: Trait> Schedule<T::BlockNumber, <T as Trait>::Call> for Module<T> {
+	fn schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<(T::BlockNumber, u32)>,
+		call: <T as Trait>::Call
+	) -> result::Result<TaskAddress<T::BlockNumber>, &'static str> {
+		Self::do_schedule(when, maybe_periodic, call)
+	}
+
+	fn cancel(address: TaskAddress<T::BlockNumber>) -> Result {
+		Self::do_cancel(address)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::with_externalities;
+	use substrate_primitives::{H256, Blake2Hasher};
+	use primitives::BuildStorage;
+	use primitives::traits::{BlakeTwo256, OnFinalise};
+	use primitives::testing::{Digest, DigestItem, Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+		}
+	}
+
+	// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+	#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+	pub struct Test;
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = ();
+		type Event = ();
+	}
+	impl Trait for Test {
+		type Call = Call;
+		type Event = ();
+	}
+
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Scheduler = Module<Test>;
+
+	fn new_test_ext(maximum_weight: u32) -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test>::default().build_storage().unwrap().0);
+		t.extend(GenesisConfig::<Test>{
+			maximum_weight,
+		}.build_storage().unwrap().0);
+		runtime_io::TestExternalities::new(t)
+	}
+
+	fn set_balance(who: u64, value: u64) -> Call {
+		Call::Balances(balances::Call::set_balance(indices::address::Address::Id(who), value.into(), 0.into()))
+	}
+
+	fn finalise(n: u64) {
+		System::set_block_number(n);
+		<Scheduler as OnFinalise<u64>>::on_finalise(n);
+	}
+
+	#[test]
+	fn calls_are_dispatched_when_due() {
+		with_externalities(&mut new_test_ext(1024), || {
+			System::set_block_number(1);
+			assert_ok!(Scheduler::schedule(3.into(), None, Box::new(set_balance(42, 10))));
+			assert_noop!(Scheduler::schedule(1.into(), None, Box::new(set_balance(42, 20))), "scheduled block must be in the future");
+
+			finalise(2);
+			assert_eq!(Balances::free_balance(&42), 0);
+			finalise(3);
+			assert_eq!(Balances::free_balance(&42), 10);
+			assert!(Scheduler::agenda(3).is_empty());
+		});
+	}
+
+	#[test]
+	fn periodic_calls_are_repeated() {
+		with_externalities(&mut new_test_ext(1024), || {
+			System::set_block_number(1);
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, Some((2, 1)), set_balance(42, 10)));
+
+			finalise(2);
+			assert_eq!(Balances::free_balance(&42), 10);
+			assert_eq!(Scheduler::agenda(4), vec![Some(Scheduled { call: set_balance(42, 10), maybe_periodic: Some((2, 0)) })]);
+
+			finalise(4);
+			assert!(Scheduler::agenda(4).is_empty());
+			assert!(Scheduler::agenda(6).is_empty());
+		});
+	}
+
+	#[test]
+	fn cancelled_calls_are_not_dispatched() {
+		with_externalities(&mut new_test_ext(1024), || {
+			System::set_block_number(1);
+			let first = <Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(42, 10)).unwrap();
+			let second = <Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(43, 10)).unwrap();
+			assert_eq!(second, (2, 1));
+
+			assert_ok!(Scheduler::cancel(2.into(), first.1.into()));
+			assert_noop!(Scheduler::cancel(2.into(), first.1.into()), "task not found");
+
+			finalise(2);
+			assert_eq!(Balances::free_balance(&42), 0);
+			assert_eq!(Balances::free_balance(&43), 10);
+		});
+	}
+
+	#[test]
+	fn calls_over_the_weight_budget_are_carried_over() {
+		let weight = set_balance(42, 10).encode().len() as u32;
+		with_externalities(&mut new_test_ext(weight), || {
+			System::set_block_number(1);
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(42, 10)));
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(43, 10)));
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(3, None, set_balance(44, 10)));
+
+			finalise(2);
+			assert_eq!(Balances::free_balance(&42), 10);
+			assert_eq!(Balances::free_balance(&43), 0);
+			assert_eq!(Scheduler::agenda(2), vec![
+				None,
+				Some(Scheduled { call: set_balance(43, 10), maybe_periodic: None }),
+			]);
+			assert_eq!(Scheduler::incomplete(), vec![2]);
+
+			// the tasks left over are dispatched first.
+			finalise(3);
+			assert_eq!(Balances::free_balance(&43), 10);
+			assert_eq!(Balances::free_balance(&44), 0);
+			assert!(Scheduler::agenda(2).is_empty());
+			assert_eq!(Scheduler::incomplete(), vec![3]);
+
+			finalise(4);
+			assert_eq!(Balances::free_balance(&44), 10);
+			assert!(Scheduler::agenda(3).is_empty());
+			assert!(Scheduler::incomplete().is_empty());
+		});
+	}
+
+	#[test]
+	fn carried_over_calls_keep_their_address() {
+		let weight = set_balance(42, 10).encode().len() as u32;
+		with_externalities(&mut new_test_ext(weight), || {
+			System::set_block_number(1);
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(42, 10)));
+			let second = <Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(43, 10)).unwrap();
+
+			finalise(2);
+			assert_ok!(Scheduler::cancel(2.into(), second.1.into()));
+
+			finalise(3);
+			assert_eq!(Balances::free_balance(&43), 0);
+			assert!(Scheduler::agenda(2).is_empty());
+			assert!(Scheduler::incomplete().is_empty());
+		});
+	}
+
+	#[test]
+	fn delayed_periodic_calls_are_repeated_from_their_due_block() {
+		let weight = set_balance(42, 10).encode().len() as u32;
+		with_externalities(&mut new_test_ext(weight), || {
+			System::set_block_number(1);
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, None, set_balance(42, 10)));
+			assert_ok!(<Scheduler as Schedule<_, _>>::schedule(2, Some((3, 1)), set_balance(43, 10)));
+
+			finalise(2);
+			assert_eq!(Balances::free_balance(&43), 0);
+
+			finalise(3);
+			assert_eq!(Balances::free_balance(&43), 10);
+			assert!(Scheduler::agenda(6).is_empty());
+			assert_eq!(Scheduler::agenda(5), vec![Some(Scheduled { call: set_balance(43, 10), maybe_periodic: Some((3, 0)) })]);
+		});
+	}
+}
@@ -229,7 +229,7 @@ mod tests {
 	use substrate_primitives::{H256, Blake2Hasher};
 	use primitives::BuildStorage;
 	use primitives::traits::{Identity, BlakeTwo256};
-	use primitives::testing::{Digest, DigestItem, Header};
+	use primitives::testing::{Digest, DigestItem, Header, TestSignature};
 
 	impl_outer_origin!{
 		pub enum Origin for Test {}
@@ -240,8 +240,10 @@ mod tests {
 	impl consensus::Trait for Test {
 		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type Log = DigestItem;
+		type Signature = TestSignature;
 		type SessionKey = u64;
 		type OnOfflineValidator = ();
+		type OnMisbehavior = ();
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -38,6 +38,7 @@ extern crate parity_codec_derive;
 extern crate parity_codec as codec;
 extern crate sr_primitives as primitives;
 extern crate srml_balances as balances;
+extern crate srml_indices as indices;
 extern crate srml_consensus as consensus;
 extern crate srml_session as session;
 extern crate srml_system as system;
@@ -53,10 +54,11 @@ use rstd::prelude::*;
 use rstd::cmp;
 use codec::{HasCompact, Compact};
 use runtime_support::{Parameter, StorageValue, StorageMap};
-use runtime_support::dispatch::Result;
+use runtime_support::dispatch::{Result, SimpleDispatchInfo};
 use session::OnSessionChange;
-use primitives::{Perbill, traits::{Zero, One, Bounded, As}};
-use balances::{address::Address, OnDilution};
+use primitives::{Perbill, traits::{Zero, One, Bounded, As, SimpleArithmetic}};
+use balances::OnDilution;
+use indices::address::Address;
 use system::ensure_signed;
 
 mod mock;
@@ -64,6 +66,8 @@ mod mock;
 mod tests;
 
 const DEFAULT_MINIMUM_VALIDATOR_COUNT: u32 = 4;
+/// Maximum number of targets a single account may nominate.
+const MAX_NOMINATIONS: usize = 16;
 
 #[derive(PartialEq, Clone)]
 #[cfg_attr(test, derive(Debug))]
@@ -94,6 +98,29 @@ impl<B: Default + HasCompact + Copy> Default for ValidatorPrefs<B> {
 	}
 }
 
+/// The stake backing a validator during an era, and whether its reward was paid out yet.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub struct Exposure<AccountId, Balance> {
+	/// The validator.
+	pub validator: AccountId,
+	/// The validator's own stake.
+	pub own: Balance,
+	/// The nominators and the part of their stake backing the validator.
+	pub others: Vec<(AccountId, Balance)>,
+	/// Reward that the validator takes up-front, as set in its preferences.
+	pub validator_payment: Balance,
+	/// Whether the era's reward has been paid out.
+	pub paid: bool,
+}
+
+impl<AccountId, Balance: SimpleArithmetic + Copy> Exposure<AccountId, Balance> {
+	/// Total stake backing the validator.
+	pub fn total(&self) -> Balance {
+		self.others.iter().fold(self.own, |acc, &(_, b)| acc + b)
+	}
+}
+
 pub trait Trait: balances::Trait + session::Trait {
 	/// Some tokens minted.
 	type OnRewardMinted: OnDilution<<Self as balances::Trait>::Balance>;
@@ -112,7 +139,7 @@ decl_module! {
 		/// Effects will be felt at the beginning of the next era.
 		fn stake(origin) -> Result {
 			let who = ensure_signed(origin)?;
-			ensure!(Self::nominating(&who).is_none(), "Cannot stake if already nominating.");
+			ensure!(!Self::is_nominating(&who), "Cannot stake if already nominating.");
 			let mut intentions = <Intentions<T>>::get();
 			// can't be in the list twice.
 			ensure!(intentions.iter().find(|&t| t == &who).is_none(), "Cannot stake if already staked.");
@@ -136,20 +163,35 @@ decl_module! {
 			Self::apply_unstake(&who, intentions_index as usize)
 		}
 
-		fn nominate(origin, target: Address<T::AccountId, T::AccountIndex>) -> Result {
+		/// Nominate the given targets, splitting the transactor's stake evenly between them.
+		///
+		/// Effects will be felt at the beginning of the next era.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn nominate(origin, targets: Vec<Address<T::AccountId, T::AccountIndex>>) -> Result {
 			let who = ensure_signed(origin)?;
-			let target = <balances::Module<T>>::lookup(target)?;
 
-			ensure!(Self::nominating(&who).is_none(), "Cannot nominate if already nominating.");
+			ensure!(!targets.is_empty(), "Cannot nominate without targets.");
+			ensure!(targets.len() <= MAX_NOMINATIONS, "Cannot nominate so many targets.");
+			ensure!(!Self::is_nominating(&who), "Cannot nominate if already nominating.");
 			ensure!(Self::intentions().iter().find(|&t| t == &who).is_none(), "Cannot nominate if already staked.");
 
+			let mut lookups = Vec::with_capacity(targets.len());
+			for target in targets {
+				lookups.push(<indices::Module<T>>::lookup(target)?);
+			}
+			let mut targets = lookups;
+			targets.sort();
+			targets.dedup();
+
 			// update nominators_for
-			let mut t = Self::nominators_for(&target);
-			t.push(who.clone());
-			<NominatorsFor<T>>::insert(&target, t);
+			for target in targets.iter() {
+				let mut t = Self::nominators_for(target);
+				t.push(who.clone());
+				<NominatorsFor<T>>::insert(target, t);
+			}
 
 			// update nominating
-			<Nominating<T>>::insert(&who, &target);
+			<Nominations<T>>::insert(&who, targets);
 
 			// Update bondage
 			<Bondage<T>>::insert(&who, T::BlockNumber::max_value());
@@ -157,37 +199,62 @@ decl_module! {
 			Ok(())
 		}
 
-		/// Will panic if called when source isn't currently nominating target.
-		/// Updates Nominating, NominatorsFor and NominationBalance.
-		fn unnominate(origin, target_index: Compact<u32>) -> Result {
-			let source = ensure_signed(origin)?;
-			let target_index: u32 = target_index.into();
-			let target_index = target_index as usize;
-
-			let target = <Nominating<T>>::get(&source).ok_or("Account must be nominating")?;
-
-			let mut t = Self::nominators_for(&target);
-			if t.get(target_index) != Some(&source) {
-				return Err("Invalid target index")
-			}
-
-			// Ok - all valid.
+		/// Retract all nominations of the transactor. This replaces `unnominate`, which retracted
+		/// the single nomination an account could make, and keeps its call index.
+		///
+		/// Effects will be felt at the beginning of the next era; the funds stay bonded for
+		/// the bonding duration.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn chill(origin) -> Result {
+			let who = ensure_signed(origin)?;
+			Self::migrate_nomination(&who);
+			ensure!(!Self::nominating(&who).is_empty(), "Account must be nominating");
 
 			// update nominators_for
-			t.swap_remove(target_index);
-			<NominatorsFor<T>>::insert(&target, t);
-
-			// update nominating
-			<Nominating<T>>::remove(&source);
+			for target in <Nominations<T>>::take(&who).iter() {
+				let mut t = Self::nominators_for(target);
+				t.retain(|n| n != &who);
+				<NominatorsFor<T>>::insert(target, t);
+			}
 
 			// update bondage
 			<Bondage<T>>::insert(
-				source,
+				&who,
 				<system::Module<T>>::block_number() + Self::bonding_duration()
 			);
 			Ok(())
 		}
 
+		/// Pay out the reward of a finished era to a validator and its nominators.
+		///
+		/// `validator_index` is the position of the validator in `eras_stakers(era)`. Anyone may
+		/// make the payout, but it can only be made once.
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn payout_stakers(
+			origin,
+			era: <T::BlockNumber as HasCompact>::Type,
+			validator_index: Compact<u32>
+		) -> Result {
+			ensure_signed(origin)?;
+			let era: T::BlockNumber = era.into();
+			ensure!(era < Self::current_era(), "Can only pay out finished eras.");
+
+			let mut exposures = Self::eras_stakers(&era);
+			ensure!(!exposures.is_empty(), "No payouts recorded for this era.");
+
+			let validator_index: u32 = validator_index.into();
+			let exposure = {
+				let exposure = exposures.get_mut(validator_index as usize).ok_or("Invalid validator index")?;
+				ensure!(!exposure.paid, "Era already paid out for this validator.");
+				exposure.paid = true;
+				exposure.clone()
+			};
+			<ErasStakers<T>>::insert(&era, exposures);
+
+			Self::pay_exposure(&exposure, Self::eras_validator_reward(&era));
+			Ok(())
+		}
+
 		/// Set the given account's preference for slashing behaviour should they be a validator.
 		///
 		/// An error (no-op) if `Self::intentions()[intentions_index] != origin`.
@@ -228,7 +295,8 @@ decl_module! {
 		}
 
 		/// Force there to be a new era. This also forces a new session immediately after.
-		/// `apply_rewards` should be true for validators to get the session reward.
+		/// `apply_rewards` should be true for validators to accrue the session reward.
+		#[weight = SimpleDispatchInfo::FixedOperational(500_000)]
 		fn force_new_era(apply_rewards: bool) -> Result {
 			Self::apply_force_new_era(apply_rewards)
 		}
@@ -245,13 +313,18 @@ decl_module! {
 /// An event in this module.
 decl_event!(
 	pub enum Event<T> where <T as balances::Trait>::Balance, <T as system::Trait>::AccountId {
-		/// All validators have been rewarded by the given balance.
+		/// All validators have accrued the given balance as reward for the current era.
 		Reward(Balance),
+		/// One validator (and their nominators) has been paid the given reward of a past era.
+		Payout(AccountId, Balance),
 		/// One validator (and their nominators) has been given a offline-warning (they're still
 		/// within their grace). The accrued number of slashes is recorded, too.
 		OfflineWarning(AccountId, u32),
 		/// One validator (and their nominators) has been slashed by the given amount.
 		OfflineSlash(AccountId, Balance),
+		/// One validator (and their nominators) has been slashed by the given amount for
+		/// misbehaving, and was unstaked.
+		MisbehaviorSlash(AccountId, Balance),
 	}
 );
 
@@ -266,12 +339,20 @@ decl_storage! {
 		pub MinimumValidatorCount get(minimum_validator_count) config(): u32 = DEFAULT_MINIMUM_VALIDATOR_COUNT;
 		/// The length of a staking era in sessions.
 		pub SessionsPerEra get(sessions_per_era) config(): T::BlockNumber = T::BlockNumber::sa(1000);
-		/// Maximum reward, per validator, that is provided per acceptable session.
-		pub SessionReward get(session_reward) config(): Perbill = Perbill::from_billionths(60);
+		/// Proportion of the total issuance that should ideally be staked.
+		pub IdealStakingRate get(ideal_staking_rate) config(): Perbill = Perbill::from_percent(50);
+		/// Yearly inflation when nothing, or everything, is staked.
+		pub MinimumInflation get(minimum_inflation) config(): Perbill = Perbill::from_billionths(25_000_000);
+		/// Yearly inflation when exactly the ideal proportion of the total issuance is staked.
+		pub IdealInflation get(ideal_inflation) config(): Perbill = Perbill::from_percent(10);
+		/// The length of a year in timestamp units, over which the yearly inflation is minted.
+		pub YearLength get(year_length) config(): T::Moment = T::Moment::sa(365 * 24 * 60 * 60);
 		/// Slash, per validator that is taken for the first time they are found to be offline.
 		pub OfflineSlash get(offline_slash) config(): Perbill = Perbill::from_millionths(1000); // Perbill::from_fraction() is only for std, so use from_millionths().
 		/// Number of instances of offline reports before slashing begins for validators.
 		pub OfflineSlashGrace get(offline_slash_grace) config(): u32;
+		/// Proportion of the slashable balance taken from a validator proven to have misbehaved.
+		pub MisbehaviorSlash get(misbehavior_slash) config(): Perbill = Perbill::from_percent(10);
 		/// The length of the bonding duration in blocks.
 		pub BondingDuration get(bonding_duration) config(): T::BlockNumber = T::BlockNumber::sa(1000);
 
@@ -281,15 +362,17 @@ decl_storage! {
 		pub ValidatorPreferences get(validator_preferences): map T::AccountId => ValidatorPrefs<T::Balance>;
 		/// All the accounts with a desire to stake.
 		pub Intentions get(intentions) config(): Vec<T::AccountId>;
-		/// All nominator -> nominee relationships.
-		pub Nominating get(nominating): map T::AccountId => Option<T::AccountId>;
+		/// All nominator -> nominee relationships made before an account could nominate several
+		/// targets. They're moved to `Nominations` when the nominator next stakes, nominates or
+		/// chills.
+		pub Nominating: map T::AccountId => Option<T::AccountId>;
+		/// All nominator -> nominees relationships.
+		pub Nominations get(nominating): map T::AccountId => Vec<T::AccountId>;
 		/// Nominators for a particular account.
 		pub NominatorsFor get(nominators_for): map T::AccountId => Vec<T::AccountId>;
 		/// Nominators for a particular account that is in action right now.
 		pub CurrentNominatorsFor get(current_nominators_for): map T::AccountId => Vec<T::AccountId>;
 
-		/// Maximum reward, per validator, that is provided per acceptable session.
-		pub CurrentSessionReward get(current_session_reward) config(): T::Balance;
 		/// Slash, per validator that is taken for the first time they are found to be offline.
 		pub CurrentOfflineSlash get(current_offline_slash) config(): T::Balance;
 
@@ -301,6 +384,15 @@ decl_storage! {
 		/// The highest and lowest staked validator slashable balances.
 		pub StakeRange get(stake_range): PairOf<T::Balance>;
 
+		/// Number of past eras whose rewards can still be paid out.
+		pub HistoryDepth get(history_depth) config(): T::BlockNumber = T::BlockNumber::sa(84);
+		/// The stake backing each validator of an era.
+		pub ErasStakers get(eras_stakers): map T::BlockNumber => Vec<Exposure<T::AccountId, T::Balance>>;
+		/// The total stake backing the validators of an era.
+		pub ErasTotalStake get(eras_total_stake): map T::BlockNumber => T::Balance;
+		/// The reward accrued so far by each validator of an era.
+		pub ErasValidatorReward get(eras_validator_reward): map T::BlockNumber => T::Balance;
+
 		/// The block at which the `who`'s funds become entirely liquid.
 		pub Bondage get(bondage): map T::AccountId => T::BlockNumber;
 		/// The number of times a given validator has been reported offline. This gets decremented by one each era that passes.
@@ -318,6 +410,18 @@ impl<T: Trait> Module<T> {
 		<session::Module<T>>::apply_force_new_session(apply_rewards)
 	}
 
+	/// Move the nomination `who` made before nominations were lists, if any, to `Nominations`.
+	fn migrate_nomination(who: &T::AccountId) {
+		if let Some(target) = <Nominating<T>>::take(who) {
+			<Nominations<T>>::insert(who, vec![target]);
+		}
+	}
+
+	/// Whether `who` nominates anyone, including with a nomination not migrated yet.
+	fn is_nominating(who: &T::AccountId) -> bool {
+		!Self::nominating(who).is_empty() || <Nominating<T>>::exists(who)
+	}
+
 	// PUBLIC IMMUTABLES
 
 	/// The length of a staking era in blocks.
@@ -325,20 +429,55 @@ impl<T: Trait> Module<T> {
 		Self::sessions_per_era() * <session::Module<T>>::length()
 	}
 
+	/// The part of a nominator's balance backing each of its targets.
+	///
+	/// A nominator still in `Nominating` has a single target, so it backs it with its whole
+	/// balance.
+	pub fn nomination_share(nominator: &T::AccountId) -> T::Balance {
+		let targets = Self::nominating(nominator).len().max(1);
+		<balances::Module<T>>::total_balance(nominator) / <T::Balance as As<usize>>::sa(targets)
+	}
+
 	/// Balance of a (potential) validator that includes all nominators.
 	pub fn nomination_balance(who: &T::AccountId) -> T::Balance {
 		Self::nominators_for(who).iter()
-			.map(<balances::Module<T>>::total_balance)
+			.map(Self::nomination_share)
 			.fold(Zero::zero(), |acc, x| acc + x)
 	}
 
 	/// The total balance that can be slashed from an account.
 	pub fn slashable_balance(who: &T::AccountId) -> T::Balance {
 		Self::nominators_for(who).iter()
-			.map(<balances::Module<T>>::total_balance)
+			.map(Self::nomination_share)
 			.fold(<balances::Module<T>>::total_balance(who), |acc, x| acc + x)
 	}
 
+	/// The yearly inflation when `staked` out of `issuance` is staked.
+	///
+	/// Inflation grows linearly from the minimum when nothing is staked up to the ideal inflation
+	/// at the ideal staking rate, and then falls linearly back to the minimum when everything is.
+	pub fn inflation(staked: T::Balance, issuance: T::Balance) -> Perbill {
+		const BILLION: u64 = 1_000_000_000;
+		let ideal_rate = Self::ideal_staking_rate().times(BILLION);
+		let minimum = Self::minimum_inflation().times(BILLION);
+		let ideal = Self::ideal_inflation().times(BILLION).max(minimum);
+
+		let rate = if issuance.is_zero() {
+			0
+		} else {
+			// scale both down first so that the product with `BILLION` can't overflow.
+			let step = issuance / <T::Balance as As<u64>>::sa(BILLION) + One::one();
+			let staked = staked.min(issuance) / step;
+			<T::Balance as As<u64>>::as_(staked * <T::Balance as As<u64>>::sa(BILLION) / (issuance / step))
+		};
+		let inflation = if rate <= ideal_rate {
+			minimum + (ideal - minimum) * rate / ideal_rate.max(1)
+		} else {
+			minimum + (ideal - minimum) * (BILLION - rate) / (BILLION - ideal_rate)
+		};
+		Perbill::from_billionths(inflation as u32)
+	}
+
 	/// The block at which the `who`'s funds become entirely liquid.
 	pub fn unlock_block(who: &T::AccountId) -> LockStatus<T::BlockNumber> {
 		match Self::bondage(who) {
@@ -361,36 +500,52 @@ impl<T: Trait> Module<T> {
 
 		if let Some(rem) = <balances::Module<T>>::slash(v, slash) {
 			let noms = Self::current_nominators_for(v);
-			let total = noms.iter().map(<balances::Module<T>>::total_balance).fold(T::Balance::zero(), |acc, x| acc + x);
+			let total = noms.iter().map(Self::nomination_share).fold(T::Balance::zero(), |acc, x| acc + x);
 			if !total.is_zero() {
 				let safe_mul_rational = |b| b * rem / total;// TODO: avoid overflow
 				for n in noms.iter() {
-					let _ = <balances::Module<T>>::slash(n, safe_mul_rational(<balances::Module<T>>::total_balance(n)));	// best effort - not much that can be done on fail.
+					let _ = <balances::Module<T>>::slash(n, safe_mul_rational(Self::nomination_share(n)));	// best effort - not much that can be done on fail.
 				}
 			}
 		}
 	}
 
-	/// Reward a given validator by a specific amount. Add the reward to their, and their nominators'
-	/// balance, pro-rata.
-	fn reward_validator(who: &T::AccountId, reward: T::Balance) {
-		let off_the_table = reward.min(Self::validator_preferences(who).validator_payment);
-		let reward = reward - off_the_table;
-		let validator_cut = if reward.is_zero() {
+	/// Pay out a given validator's reward for an era. Add the reward to their, and their
+	/// nominators' balance, pro-rata to the stake they had backing the validator.
+	fn pay_exposure(exposure: &Exposure<T::AccountId, T::Balance>, reward: T::Balance) {
+		let off_the_table = reward.min(exposure.validator_payment);
+		let rest = reward - off_the_table;
+		let total = exposure.total();
+		let validator_cut = if rest.is_zero() {
 			Zero::zero()
 		} else {
-			let noms = Self::current_nominators_for(who);
-			let total = noms.iter()
-				.map(<balances::Module<T>>::total_balance)
-				.fold(<balances::Module<T>>::total_balance(who), |acc, x| acc + x)
-				.max(One::one());
-			let safe_mul_rational = |b| b * reward / total;// TODO: avoid overflow
-			for n in noms.iter() {
-				let _ = <balances::Module<T>>::reward(n, safe_mul_rational(<balances::Module<T>>::total_balance(n)));
+			let safe_mul_rational = |b| b * rest / total.max(One::one());// TODO: avoid overflow
+			for &(ref n, share) in exposure.others.iter() {
+				let _ = <balances::Module<T>>::reward(n, safe_mul_rational(share));
 			}
-			safe_mul_rational(<balances::Module<T>>::total_balance(who))
+			safe_mul_rational(exposure.own)
 		};
-		let _ = <balances::Module<T>>::reward(who, validator_cut + off_the_table);
+		let _ = <balances::Module<T>>::reward(&exposure.validator, validator_cut + off_the_table);
+		T::OnRewardMinted::on_dilution(reward, total);
+		Self::deposit_event(RawEvent::Payout(exposure.validator.clone(), reward));
+	}
+
+	/// Record the stake backing each of the given validators during an era.
+	fn record_exposures(era: T::BlockNumber, validators: &[T::AccountId]) {
+		let exposures = validators.iter()
+			.map(|v| Exposure {
+				validator: v.clone(),
+				own: <balances::Module<T>>::total_balance(v),
+				others: Self::current_nominators_for(v).into_iter()
+					.map(|n| { let share = Self::nomination_share(&n); (n, share) })
+					.collect(),
+				validator_payment: Self::validator_preferences(v).validator_payment,
+				paid: false,
+			})
+			.collect::<Vec<_>>();
+		let total = exposures.iter().fold(Zero::zero(), |acc: T::Balance, e| acc + e.total());
+		<ErasStakers<T>>::insert(&era, exposures);
+		<ErasTotalStake<T>>::insert(&era, total);
 	}
 
 	/// Actually carry out the unstake operation.
@@ -408,30 +563,45 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
-	/// Get the reward for the session, assuming it ends with this block.
+	/// Get the reward of all validators for the session, assuming it ends with this block.
+	///
+	/// The inflation for the current era's staking rate is minted over a year; a session that
+	/// takes longer than ideal is only rewarded for a proportionally shorter time.
 	fn this_session_reward(actual_elapsed: T::Moment) -> T::Balance {
+		let year_length: u64 = Self::year_length().as_();
+		if year_length == 0 {
+			return Zero::zero();
+		}
+		let issuance = <balances::Module<T>>::total_issuance();
+		let yearly = Self::inflation(Self::eras_total_stake(&Self::current_era()), issuance).times(issuance);
+
 		let ideal_elapsed = <session::Module<T>>::ideal_session_duration();
 		if ideal_elapsed.is_zero() {
-			return Self::current_session_reward();
+			let elapsed: u64 = actual_elapsed.as_();
+			return yearly * T::Balance::sa(elapsed) / T::Balance::sa(year_length);
 		}
+		let ideal: u64 = ideal_elapsed.as_();
 		let per65536: u64 = (T::Moment::sa(65536u64) * ideal_elapsed.clone() / actual_elapsed.max(ideal_elapsed)).as_();
-		Self::current_session_reward() * T::Balance::sa(per65536) / T::Balance::sa(65536u64)
+		yearly * T::Balance::sa(ideal) / T::Balance::sa(year_length)
+			* T::Balance::sa(per65536) / T::Balance::sa(65536u64)
 	}
 
 	/// Session has just changed. We need to determine whether we pay a reward, slash and/or
 	/// move to a new era.
 	fn new_session(actual_elapsed: T::Moment, should_reward: bool) {
 		if should_reward {
-			// apply good session reward
-			let reward = Self::this_session_reward(actual_elapsed);
+			let era = Self::current_era();
 			let validators = <session::Module<T>>::validators();
-			for v in validators.iter() {
-				Self::reward_validator(v, reward);
+			// eras entered without an election (e.g. genesis) have their stakes recorded lazily.
+			if !<ErasTotalStake<T>>::exists(&era) {
+				Self::record_exposures(era, &validators);
 			}
+
+			// accrue good session reward, to be paid out once the era is over.
+			let reward = Self::this_session_reward(actual_elapsed)
+				/ <T::Balance as As<usize>>::sa(validators.len().max(1));
+			<ErasValidatorReward<T>>::mutate(&era, |r| *r += reward);
 			Self::deposit_event(RawEvent::Reward(reward));
-			let total_minted = reward * <T::Balance as As<usize>>::sa(validators.len());
-			let total_rewarded_stake = Self::stake_range().1 * <T::Balance as As<usize>>::sa(validators.len());
-			T::OnRewardMinted::on_dilution(total_minted, total_rewarded_stake);
 		}
 
 		let session_index = <session::Module<T>>::current_index();
@@ -448,7 +618,20 @@ impl<T: Trait> Module<T> {
 	/// get a chance to set their session keys.
 	fn new_era() {
 		// Increment current era.
-		<CurrentEra<T>>::put(&(<CurrentEra<T>>::get() + One::one()));
+		let era = <CurrentEra<T>>::get() + One::one();
+		<CurrentEra<T>>::put(&era);
+
+		// Misbehavior is punished at most once per era.
+		<consensus::Module<T>>::clear_misbehavior_reports();
+
+		// Forget the payouts of eras that are too old.
+		let depth = Self::history_depth();
+		if era > depth {
+			let old = era - depth - One::one();
+			<ErasStakers<T>>::remove(&old);
+			<ErasTotalStake<T>>::remove(&old);
+			<ErasValidatorReward<T>>::remove(&old);
+		}
 
 		// Enact era length change.
 		if let Some(next_spe) = Self::next_sessions_per_era() {
@@ -499,11 +682,11 @@ impl<T: Trait> Module<T> {
 		for v in vals.iter() {
 			<CurrentNominatorsFor<T>>::insert(v, Self::nominators_for(v));
 		}
+		Self::record_exposures(era, vals);
 		<session::Module<T>>::set_validators(vals);
 
-		// Update the balances for slashing/rewarding according to the stakes.
+		// Update the balance for slashing according to the stakes.
 		<CurrentOfflineSlash<T>>::put(Self::offline_slash().times(stake_range.1));
-		<CurrentSessionReward<T>>::put(Self::session_reward().times(stake_range.1));
 	}
 }
 
@@ -559,3 +742,25 @@ impl<T: Trait> consensus::OnOfflineValidator for Module<T> {
 		Self::deposit_event(event);
 	}
 }
+
+impl<T: Trait> consensus::OnMisbehavior for Module<T> {
+	fn on_misbehavior(validator_index: usize) {
+		// the authority set is kept in the order of the validators by `session::set_validators`.
+		let v = match <session::Module<T>>::validators().get(validator_index) {
+			Some(v) => v.clone(),
+			None => return,
+		};
+		let slash = Self::misbehavior_slash().times(Self::slashable_balance(&v));
+		let _ = Self::slash_validator(&v, slash);
+
+		if let Some(pos) = Self::intentions().into_iter().position(|x| &x == &v) {
+			Self::apply_unstake(&v, pos)
+				.expect("pos derived correctly from Self::intentions(); \
+					apply_unstake can only fail if pos wrong; \
+					Self::intentions() doesn't change; qed");
+		}
+		let _ = Self::apply_force_new_era(false);
+
+		Self::deposit_event(RawEvent::MisbehaviorSlash(v, slash));
+	}
+}
@@ -20,10 +20,10 @@
 
 use primitives::BuildStorage;
 use primitives::{Perbill, traits::Identity};
-use primitives::testing::{Digest, DigestItem, Header};
+use primitives::testing::{Digest, DigestItem, Header, TestSignature};
 use substrate_primitives::{H256, Blake2Hasher};
 use runtime_io;
-use {GenesisConfig, Module, Trait, consensus, session, system, timestamp, balances};
+use {GenesisConfig, Module, Trait, consensus, session, system, timestamp, balances, indices};
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -35,8 +35,10 @@ pub struct Test;
 impl consensus::Trait for Test {
 	const NOTE_OFFLINE_POSITION: u32 = 1;
 	type Log = DigestItem;
+	type Signature = TestSignature;
 	type SessionKey = u64;
 	type OnOfflineValidator = ();
+	type OnMisbehavior = ();
 }
 impl system::Trait for Test {
 	type Origin = Origin;
@@ -50,11 +52,16 @@ impl system::Trait for Test {
 	type Event = ();
 	type Log = DigestItem;
 }
+impl indices::Trait for Test {
+	type AccountIndex = u64;
+	type Event = ();
+}
 impl balances::Trait for Test {
 	type Balance = u64;
-	type AccountIndex = u64;
 	type OnFreeBalanceZero = Staking;
 	type EnsureAccountLiquid = Staking;
+	type LockedBalance = ();
+	type OnCollected = ();
 	type Event = ();
 }
 impl session::Trait for Test {
@@ -105,10 +112,12 @@ pub fn new_test_ext(
 		},
 		transaction_base_fee: 0,
 		transaction_byte_fee: 0,
+		transaction_weight_fee: 0,
 		existential_deposit: ext_deposit,
 		transfer_fee: 0,
 		creation_fee: 0,
-		reclaim_rebate: 0,
+		vesting: vec![],
+		minimum_vested_transfer: 0,
 	}.build_storage().unwrap().0);
 	t.extend(GenesisConfig::<Test>{
 		sessions_per_era,
@@ -117,11 +126,16 @@ pub fn new_test_ext(
 		validator_count: 2,
 		minimum_validator_count: 0,
 		bonding_duration: sessions_per_era * session_length * 3,
-		session_reward: Perbill::from_millionths((1000000 * reward / balance_factor) as u32),
 		offline_slash: if monied { Perbill::from_percent(40) } else { Perbill::zero() },
-		current_session_reward: reward,
+		ideal_staking_rate: Perbill::from_percent(50),
+		minimum_inflation: Perbill::from_percent(reward as u32),
+		ideal_inflation: Perbill::from_percent(reward as u32),
+		// a session on time lasts a year.
+		year_length: 5 * session_length,
+		history_depth: 2,
 		current_offline_slash: 20,
 		offline_slash_grace: 0,
+		misbehavior_slash: if monied { Perbill::from_percent(50) } else { Perbill::zero() },
 	}.build_storage().unwrap().0);
 	t.extend(timestamp::GenesisConfig::<Test>{
 		period: 5
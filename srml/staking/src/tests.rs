@@ -19,7 +19,7 @@
 #![cfg(test)]
 
 use super::*;
-use consensus::OnOfflineValidator;
+use consensus::{OnMisbehavior, OnOfflineValidator};
 use runtime_io::with_externalities;
 use mock::{Balances, Session, Staking, System, Timestamp, Test, new_test_ext, Origin};
 
@@ -122,6 +122,10 @@ fn note_offline_force_unstake_session_change_should_work() {
 		assert_eq!(Staking::intentions(), vec![1, 20]);
 		assert_eq!(Balances::free_balance(&10), 10);
 		assert!(Staking::forcing_new_era().is_some());
+
+		// authorities without a validator are ignored.
+		Staking::on_misbehavior(5);
+		assert_eq!(Balances::free_balance(&20), 70);
 	});
 }
 
@@ -165,6 +169,21 @@ fn note_offline_auto_unstake_session_change_should_work() {
 }
 
 
+#[test]
+fn misbehavior_slashes_and_unstakes_validator() {
+	with_externalities(&mut new_test_ext(0, 3, 3, 0, true, 10), || {
+		Balances::set_free_balance(&10, 70);
+		Balances::set_free_balance(&20, 70);
+		assert_eq!(Staking::intentions(), vec![10, 20]);
+
+		Staking::on_misbehavior(0);
+		assert_eq!(Balances::free_balance(&10), 35);
+		assert_eq!(Balances::free_balance(&20), 70);
+		assert_eq!(Staking::intentions(), vec![20]);
+		assert!(Staking::forcing_new_era().is_some());
+	});
+}
+
 #[test]
 fn rewards_should_work() {
 	with_externalities(&mut new_test_ext(0, 3, 3, 0, true, 10), || {
@@ -180,19 +199,27 @@ fn rewards_should_work() {
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 0);
 		assert_eq!(Session::current_index(), 1);
-		assert_eq!(Balances::total_balance(&10), 11);
+		assert_eq!(Staking::eras_validator_reward(0), 5);
 		System::set_block_number(6);
 		Timestamp::set_timestamp(31);	// a little late
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 0);
 		assert_eq!(Session::current_index(), 2);
-		assert_eq!(Balances::total_balance(&10), 20);	// less reward
+		assert_eq!(Staking::eras_validator_reward(0), 9);	// less reward
+		assert_noop!(Staking::payout_stakers(Origin::signed(1), 0.into(), 0.into()), "Can only pay out finished eras.");
 		System::set_block_number(9);
 		Timestamp::set_timestamp(50);	// very late
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 1);
 		assert_eq!(Session::current_index(), 3);
-		assert_eq!(Balances::total_balance(&10), 27);	// much less reward
+		assert_eq!(Staking::eras_validator_reward(0), 12);	// much less reward
+		assert_eq!(Balances::total_balance(&10), 1);
+
+		assert_ok!(Staking::payout_stakers(Origin::signed(1), 0.into(), 0.into()));
+		assert_eq!(Balances::total_balance(&10), 13);
+		assert_eq!(Balances::total_balance(&20), 1);
+		assert_noop!(Staking::payout_stakers(Origin::signed(1), 0.into(), 0.into()), "Era already paid out for this validator.");
+		assert_noop!(Staking::payout_stakers(Origin::signed(1), 0.into(), 2.into()), "Invalid validator index");
 	});
 }
 
@@ -210,24 +237,38 @@ fn slashing_should_work() {
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 0);
 		assert_eq!(Session::current_index(), 1);
-		assert_eq!(Balances::total_balance(&10), 11);
+		assert_eq!(Balances::total_balance(&10), 1);
 
 		System::set_block_number(6);
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 0);
 		assert_eq!(Session::current_index(), 2);
-		assert_eq!(Balances::total_balance(&10), 21);
+		assert_eq!(Staking::eras_validator_reward(0), 10);
 
-		System::set_block_number(7);
+		System::set_block_number(9);
+		Session::check_rotate_session(System::block_number());
+		assert_eq!(Staking::current_era(), 1);
+		assert_eq!(Staking::eras_validator_reward(0), 15);
+		assert_ok!(Staking::payout_stakers(Origin::signed(1), 0.into(), 0.into()));
+		assert_ok!(Staking::payout_stakers(Origin::signed(1), 0.into(), 1.into()));
+		assert_eq!(Balances::total_balance(&10), 16);
+		assert_eq!(Balances::total_balance(&20), 16);
+
+		// the offline slash only follows the paid out stakes from the next era on.
+		System::set_block_number(18);
+		Session::check_rotate_session(System::block_number());
+		assert_eq!(Staking::current_era(), 2);
+		assert_eq!(Staking::current_offline_slash(), 6);
+
+		System::set_block_number(19);
 		System::set_extrinsic_index(1);
 		Staking::on_offline_validator(0);
 		Staking::on_offline_validator(1);
-		assert_eq!(Balances::total_balance(&10), 1);
+		assert_eq!(Balances::total_balance(&10), 10);
+		assert_eq!(Balances::total_balance(&20), 10);
 	});
 }
 
-
-
 #[test]
 fn staking_should_work() {
 	with_externalities(&mut new_test_ext(0, 1, 2, 0, true, 0), || {
@@ -302,42 +343,109 @@ fn nominating_and_rewards_should_work() {
 		assert_ok!(Staking::stake(Origin::signed(1)));
 		assert_ok!(Staking::stake(Origin::signed(2)));
 		assert_ok!(Staking::stake(Origin::signed(3)));
-		assert_ok!(Staking::nominate(Origin::signed(4), 1.into()));
+		assert_ok!(Staking::nominate(Origin::signed(4), vec![1.into()]));
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 1);
 		assert_eq!(Session::validators(), vec![1, 3]);	// 4 + 1, 3
+		assert_eq!(Staking::eras_validator_reward(0), 5);
 		assert_eq!(Balances::total_balance(&1), 10);
 		assert_eq!(Balances::total_balance(&2), 20);
 		assert_eq!(Balances::total_balance(&3), 30);
 		assert_eq!(Balances::total_balance(&4), 40);
 
 		System::set_block_number(2);
-		assert_ok!(Staking::unnominate(Origin::signed(4), 0.into()));
+		assert_ok!(Staking::chill(Origin::signed(4)));
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Staking::current_era(), 2);
 		assert_eq!(Session::validators(), vec![3, 2]);
-		assert_eq!(Balances::total_balance(&1), 16);
+		assert_eq!(Staking::eras_validator_reward(1), 5);
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 1.into(), 0.into()));
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 1.into(), 1.into()));
+		assert_eq!(Balances::total_balance(&1), 11);
 		assert_eq!(Balances::total_balance(&2), 20);
-		assert_eq!(Balances::total_balance(&3), 60);
-		assert_eq!(Balances::total_balance(&4), 64);
+		assert_eq!(Balances::total_balance(&3), 35);
+		assert_eq!(Balances::total_balance(&4), 44);
 
 		System::set_block_number(3);
 		assert_ok!(Staking::stake(Origin::signed(4)));
 		assert_ok!(Staking::unstake(Origin::signed(3), (Staking::intentions().iter().position(|&x| x == 3).unwrap() as u32).into()));
-		assert_ok!(Staking::nominate(Origin::signed(3), 1.into()));
+		assert_ok!(Staking::nominate(Origin::signed(3), vec![1.into()]));
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Session::validators(), vec![1, 4]);
-		assert_eq!(Balances::total_balance(&1), 16);
-		assert_eq!(Balances::total_balance(&2), 40);
-		assert_eq!(Balances::total_balance(&3), 80);
-		assert_eq!(Balances::total_balance(&4), 64);
+		assert_eq!(Staking::eras_validator_reward(2), 5);
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 2.into(), 0.into()));
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 2.into(), 1.into()));
+		assert_eq!(Balances::total_balance(&1), 11);
+		assert_eq!(Balances::total_balance(&2), 25);
+		assert_eq!(Balances::total_balance(&3), 40);
+		assert_eq!(Balances::total_balance(&4), 44);
 
 		System::set_block_number(4);
 		Session::check_rotate_session(System::block_number());
-		assert_eq!(Balances::total_balance(&1), 26);
-		assert_eq!(Balances::total_balance(&2), 40);
-		assert_eq!(Balances::total_balance(&3), 133);
-		assert_eq!(Balances::total_balance(&4), 128);
+		assert_eq!(Staking::eras_validator_reward(3), 6);
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 3.into(), 0.into()));
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 3.into(), 1.into()));
+		assert_eq!(Balances::total_balance(&1), 12);
+		assert_eq!(Balances::total_balance(&2), 25);
+		assert_eq!(Balances::total_balance(&3), 44);
+		assert_eq!(Balances::total_balance(&4), 50);
+
+		// payouts are only kept for `history_depth` past eras.
+		assert_eq!(Staking::eras_validator_reward(1), 0);
+		assert_noop!(Staking::payout_stakers(Origin::signed(10), 1.into(), 0.into()), "No payouts recorded for this era.");
+	});
+}
+
+#[test]
+fn nominating_multiple_targets_should_split_stake() {
+	with_externalities(&mut new_test_ext(0, 1, 1, 0, true, 10), || {
+		System::set_block_number(1);
+		assert_ok!(Staking::stake(Origin::signed(1)));
+		assert_ok!(Staking::stake(Origin::signed(2)));
+		assert_ok!(Staking::stake(Origin::signed(3)));
+		assert_noop!(Staking::nominate(Origin::signed(4), vec![]), "Cannot nominate without targets.");
+		assert_ok!(Staking::nominate(Origin::signed(4), vec![3.into(), 1.into(), 3.into()]));
+		assert_eq!(Staking::nominating(&4), vec![1, 3]);
+		assert_eq!(Staking::nomination_share(&4), 20);
+		assert_eq!(Staking::slashable_balance(&1), 30);
+		assert_eq!(Staking::slashable_balance(&2), 20);
+		assert_eq!(Staking::slashable_balance(&3), 50);
+		Session::check_rotate_session(System::block_number());
+		assert_eq!(Session::validators(), vec![3, 1]);	// 3 + 4/2, 1 + 4/2
+		assert_eq!(Staking::current_nominators_for(&1), vec![4]);
+		assert_eq!(Staking::current_nominators_for(&3), vec![4]);
+
+		System::set_block_number(2);
+		assert_noop!(Staking::chill(Origin::signed(2)), "Account must be nominating");
+		assert_ok!(Staking::chill(Origin::signed(4)));
+		assert!(Staking::nominating(&4).is_empty());
+		assert!(Staking::nominators_for(&1).is_empty());
+		assert!(Staking::nominators_for(&3).is_empty());
+		assert_eq!(Staking::unlock_block(&4), LockStatus::LockedUntil(2 + Staking::bonding_duration()));
+		Session::check_rotate_session(System::block_number());
+		assert_eq!(Session::validators(), vec![3, 2]);
+		assert!(Staking::current_nominators_for(&1).is_empty());
+	});
+}
+
+#[test]
+fn nominations_made_before_multiple_targets_are_migrated() {
+	with_externalities(&mut new_test_ext(0, 1, 1, 0, true, 10), || {
+		System::set_block_number(1);
+		assert_ok!(Staking::stake(Origin::signed(1)));
+		assert_ok!(Staking::stake(Origin::signed(2)));
+		assert_ok!(Staking::stake(Origin::signed(3)));
+		<Nominating<Test>>::insert(&4, Some(1));
+		<NominatorsFor<Test>>::insert(&1, vec![4]);
+		assert_eq!(Staking::nomination_share(&4), 40);
+
+		assert_noop!(Staking::stake(Origin::signed(4)), "Cannot stake if already nominating.");
+		assert_noop!(Staking::nominate(Origin::signed(4), vec![2.into()]), "Cannot nominate if already nominating.");
+
+		assert_ok!(Staking::chill(Origin::signed(4)));
+		assert!(!<Nominating<Test>>::exists(&4));
+		assert!(Staking::nominating(&4).is_empty());
+		assert!(Staking::nominators_for(&1).is_empty());
 	});
 }
 
@@ -346,7 +454,12 @@ fn rewards_with_off_the_table_should_work() {
 	with_externalities(&mut new_test_ext(0, 1, 1, 0, true, 10), || {
 		System::set_block_number(1);
 		assert_ok!(Staking::stake(Origin::signed(1)));
-		assert_ok!(Staking::nominate(Origin::signed(2), 1.into()));
+		assert_ok!(Staking::register_preferences(
+			Origin::signed(1),
+			(Staking::intentions().into_iter().position(|i| i == 1).unwrap() as u32).into(),
+			ValidatorPrefs { unstake_threshold: 3, validator_payment: 2 }
+		));
+		assert_ok!(Staking::nominate(Origin::signed(2), vec![1.into()]));
 		assert_ok!(Staking::stake(Origin::signed(3)));
 		Session::check_rotate_session(System::block_number());
 		assert_eq!(Session::validators(), vec![1, 3]);	// 1 + 2, 3
@@ -355,15 +468,30 @@ fn rewards_with_off_the_table_should_work() {
 		assert_eq!(Balances::total_balance(&3), 30);
 
 		System::set_block_number(2);
-		assert_ok!(Staking::register_preferences(
-			Origin::signed(1),
-			(Staking::intentions().into_iter().position(|i| i == 1).unwrap() as u32).into(),
-			ValidatorPrefs { unstake_threshold: 3, validator_payment: 4 }
-		));
 		Session::check_rotate_session(System::block_number());
-		assert_eq!(Balances::total_balance(&1), 22);
-		assert_eq!(Balances::total_balance(&2), 37);
-		assert_eq!(Balances::total_balance(&3), 60);
+		assert_eq!(Staking::eras_validator_reward(1), 5);
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 1.into(), 0.into()));
+		assert_ok!(Staking::payout_stakers(Origin::signed(10), 1.into(), 1.into()));
+		assert_eq!(Balances::total_balance(&1), 13);
+		assert_eq!(Balances::total_balance(&2), 22);
+		assert_eq!(Balances::total_balance(&3), 35);
+	});
+}
+
+#[test]
+fn inflation_follows_staking_rate() {
+	with_externalities(&mut new_test_ext(0, 1, 1, 0, true, 10), || {
+		<MinimumInflation<Test>>::put(Perbill::from_percent(2));
+		<IdealInflation<Test>>::put(Perbill::from_percent(10));
+		assert_eq!(Staking::ideal_staking_rate(), Perbill::from_percent(50));
+
+		assert_eq!(Staking::inflation(0, 100), Perbill::from_percent(2));
+		assert_eq!(Staking::inflation(25, 100), Perbill::from_percent(6));
+		assert_eq!(Staking::inflation(50, 100), Perbill::from_percent(10));
+		assert_eq!(Staking::inflation(75, 100), Perbill::from_percent(6));
+		assert_eq!(Staking::inflation(100, 100), Perbill::from_percent(2));
+		assert_eq!(Staking::inflation(0, 0), Perbill::from_percent(2));
+		assert_eq!(Staking::inflation(u64::max_value(), u64::max_value()), Perbill::from_percent(2));
 	});
 }
 
@@ -382,8 +510,8 @@ fn nominating_slashes_should_work() {
 		System::set_block_number(4);
 		assert_ok!(Staking::stake(Origin::signed(1)));
 		assert_ok!(Staking::stake(Origin::signed(3)));
-		assert_ok!(Staking::nominate(Origin::signed(2), 3.into()));
-		assert_ok!(Staking::nominate(Origin::signed(4), 1.into()));
+		assert_ok!(Staking::nominate(Origin::signed(2), vec![3.into()]));
+		assert_ok!(Staking::nominate(Origin::signed(4), vec![1.into()]));
 		Session::check_rotate_session(System::block_number());
 
 		assert_eq!(Staking::current_era(), 1);
@@ -411,10 +539,10 @@ fn double_staking_should_fail() {
 		System::set_block_number(1);
 		assert_ok!(Staking::stake(Origin::signed(1)));
 		assert_noop!(Staking::stake(Origin::signed(1)), "Cannot stake if already staked.");
-		assert_noop!(Staking::nominate(Origin::signed(1), 1.into()), "Cannot nominate if already staked.");
-		assert_ok!(Staking::nominate(Origin::signed(2), 1.into()));
+		assert_noop!(Staking::nominate(Origin::signed(1), vec![1.into()]), "Cannot nominate if already staked.");
+		assert_ok!(Staking::nominate(Origin::signed(2), vec![1.into()]));
 		assert_noop!(Staking::stake(Origin::signed(2)), "Cannot stake if already nominating.");
-		assert_noop!(Staking::nominate(Origin::signed(2), 1.into()), "Cannot nominate if already nominating.");
+		assert_noop!(Staking::nominate(Origin::signed(2), vec![1.into()]), "Cannot nominate if already nominating.");
 	});
 }
 
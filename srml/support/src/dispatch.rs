@@ -23,6 +23,9 @@ pub use rstd::result;
 #[cfg(feature = "std")]
 use serde;
 pub use codec::{Codec, Decode, Encode, Input, Output};
+pub use runtime_primitives::weights::{
+	Weight, DispatchClass, DispatchInfo, GetDispatchInfo, SimpleDispatchInfo
+};
 pub use substrate_metadata::{
 	ModuleMetadata, FunctionMetadata, DecodeDifferent,
 	CallMetadata, FunctionArgumentMetadata, OuterDispatchMetadata, OuterDispatchCall
@@ -78,6 +81,7 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = system
 			{}
 			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -95,6 +99,7 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{}
 			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -106,6 +111,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{}
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
 		$vis:vis fn deposit_event() = default;
@@ -117,6 +123,7 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $vis fn deposit_event() = default; }
 			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
 			[ $($t)* ]
 			$($rest)*
 		);
@@ -127,6 +134,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{}
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
 		$vis:vis fn deposit_event($($param_name:ident : $param:ty),* ) { $( $impl:tt )* }
@@ -138,6 +146,7 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $vis fn deposit_event($( $param_name: $param ),* ) { $( $impl )* } }
 			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
 			[ $($t)* ]
 			$($rest)*
 		);
@@ -148,6 +157,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 	    { $( $deposit_event:tt )* }
 		{}
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
 		fn on_finalise($($param_name:ident : $param:ty),* ) { $( $impl:tt )* }
@@ -159,6 +169,30 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $( $deposit_event )* }
 			{ fn on_finalise( $( $param_name : $param ),* ) { $( $impl )* } }
+			{ $( $offchain_worker )* }
+			[ $($t)* ]
+			$($rest)*
+		);
+	};
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $deposit_event:tt )* }
+		{ $( $on_finalise:tt )* }
+		{}
+		[ $($t:tt)* ]
+		$(#[doc = $doc_attr:tt])*
+		fn offchain_worker($($param_name:ident : $param:ty),* ) { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $deposit_event )* }
+			{ $( $on_finalise )* }
+			{ fn offchain_worker( $( $param_name : $param ),* ) { $( $impl )* } }
 			[ $($t)* ]
 			$($rest)*
 		);
@@ -169,8 +203,10 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{ $( $deposit_event:tt )* }
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
 		$fn_vis:vis fn $fn_name:ident($origin:ident $(, $param_name:ident : $param:ty)* ) -> $result:ty { $( $impl:tt )* }
 		$($rest:tt)*
 	) => {
@@ -180,9 +216,11 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $( $deposit_event )* }
 			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
 			[
 				$($t)*
 				$(#[doc = $doc_attr])*
+				#[weight = $weight]
 				$fn_vis fn $fn_name($origin $( , $param_name : $param )* ) -> $result { $( $impl )* }
 			]
 			$($rest)*
@@ -194,6 +232,64 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{ $( $deposit_event:tt )* }
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
+		[ $($t:tt)* ]
+		$(#[doc = $doc_attr:tt])*
+		$fn_vis:vis fn $fn_name:ident($origin:ident $(, $param_name:ident : $param:ty)* ) -> $result:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $deposit_event )* }
+			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
+			[
+				$($t)*
+				$(#[doc = $doc_attr])*
+				#[weight = $crate::dispatch::SimpleDispatchInfo::default()]
+				$fn_vis fn $fn_name($origin $( , $param_name : $param )* ) -> $result { $( $impl )* }
+			]
+			$($rest)*
+		);
+	};
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $deposit_event:tt )* }
+		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
+		[ $($t:tt)* ]
+		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
+		$fn_vis:vis fn $fn_name:ident($( $param_name:ident : $param:ty),* ) -> $result:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $deposit_event )* }
+			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
+			[
+				$($t)*
+				$(#[doc = $doc_attr])*
+				#[weight = $weight]
+				$fn_vis fn $fn_name(root $( , $param_name : $param )* ) -> $result { $( $impl )* }
+			]
+			$($rest)*
+		);
+	};
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $deposit_event:tt )* }
+		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
 		$fn_vis:vis fn $fn_name:ident($( $param_name:ident : $param:ty),* ) -> $result:ty { $( $impl:tt )* }
@@ -205,9 +301,11 @@ macro_rules! decl_module {
 			for enum $call_type where origin: $origin_type, system = $system
 			{ $( $deposit_event )* }
 			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
 			[
 				$($t)*
 				$(#[doc = $doc_attr])*
+				#[weight = $crate::dispatch::SimpleDispatchInfo::default()]
 				$fn_vis fn $fn_name(root $( , $param_name : $param )* ) -> $result { $( $impl )* }
 			]
 			$($rest)*
@@ -219,6 +317,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
 		{ $( $deposit_event:tt )* }
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 		[ $($t:tt)* ]
 	) => {
 		decl_module!(@imp
@@ -229,6 +328,7 @@ macro_rules! decl_module {
 			}
 			{ $( $deposit_event )* }
 			{ $( $on_finalise )* }
+			{ $( $offchain_worker )* }
 		);
 	};
 
@@ -310,6 +410,25 @@ macro_rules! decl_module {
 			for $module<$trait_instance> {}
 	};
 
+	(@impl_offchain_worker
+		$module:ident<$trait_instance:ident: $trait_name:ident>;
+		fn offchain_worker($param:ident : $param_ty:ty) { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name>
+			$crate::runtime_primitives::traits::OffchainWorker<$trait_instance::BlockNumber>
+			for $module<$trait_instance> {
+			fn offchain_worker($param: $param_ty) { $( $impl )* }
+		}
+	};
+
+	(@impl_offchain_worker
+		$module:ident<$trait_instance:ident: $trait_name:ident>;
+	) => {
+		impl<$trait_instance: $trait_name>
+			$crate::runtime_primitives::traits::OffchainWorker<$trait_instance::BlockNumber>
+			for $module<$trait_instance> {}
+	};
+
 	(@impl_function
 		$module:ident<$trait_instance:ident: $trait_name:ident>;
 		$origin_ty:ty;
@@ -341,6 +460,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident {
 			$(
 				$(#[doc = $doc_attr:tt])*
+				#[weight = $weight:expr]
 				$fn_vis:vis fn $fn_name:ident(
 					$from:ident $( , $param_name:ident : $param:ty)*
 				) -> $result:ty { $( $impl:tt )* }
@@ -348,6 +468,7 @@ macro_rules! decl_module {
 		}
 		{ $( $deposit_event:tt )* }
 		{ $( $on_finalise:tt )* }
+		{ $( $offchain_worker:tt )* }
 	) => {
 		// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 		#[derive(Clone, Copy, PartialEq, Eq)]
@@ -370,6 +491,12 @@ macro_rules! decl_module {
 			$( $on_finalise )*
 		}
 
+		decl_module! {
+			@impl_offchain_worker
+			$mod_type<$trait_instance: $trait_name>;
+			$( $offchain_worker )*
+		}
+
 		decl_module! {
 			@impl_deposit_event
 			$mod_type<$trait_instance: $trait_name>;
@@ -505,6 +632,21 @@ macro_rules! decl_module {
 				}
 			}
 		}
+		impl<$trait_instance: $trait_name> $crate::dispatch::GetDispatchInfo
+			for $call_type<$trait_instance>
+		{
+			fn get_dispatch_info(&self) -> $crate::dispatch::DispatchInfo {
+				match *self {
+					$(
+						$call_type::$fn_name( .. ) => {
+							let weight: $crate::dispatch::SimpleDispatchInfo = $weight;
+							weight.into()
+						},
+					)*
+					_ => unreachable!(),
+				}
+			}
+		}
 		impl<$trait_instance: $trait_name> $crate::dispatch::Callable
 			for $mod_type<$trait_instance>
 		{
@@ -628,6 +770,22 @@ macro_rules! impl_outer_dispatch {
 				}
 			}
 		}
+		impl $crate::dispatch::GetDispatchInfo for $call_type {
+			fn get_dispatch_info(&self) -> $crate::dispatch::DispatchInfo {
+				match *self {
+					$(
+						$call_type::$camelcase(ref call) => $crate::dispatch::GetDispatchInfo::get_dispatch_info(call),
+					)*
+				}
+			}
+		}
+		$(
+			impl From<$crate::dispatch::CallableCallFor<$camelcase>> for $call_type {
+				fn from(call: $crate::dispatch::CallableCallFor<$camelcase>) -> Self {
+					$call_type::$camelcase(call)
+				}
+			}
+		)*
 		$(
 			impl $crate::dispatch::IsSubType<$camelcase> for $call_type {
 				fn is_aux_sub_type(&self) -> Option<&<$camelcase as $crate::dispatch::Callable>::Call> {
@@ -837,9 +995,18 @@ mod tests {
 			/// Hi, this is a comment.
 			fn aux_0(_origin) -> Result { unreachable!() }
 			fn aux_1(_origin, _data: i32) -> Result { unreachable!() }
+			#[weight = SimpleDispatchInfo::FixedNormal(3)]
 			fn aux_2(_origin, _data: i32, _data2: String) -> Result { unreachable!() }
+			#[weight = SimpleDispatchInfo::FixedOperational(5)]
 			fn aux_3() -> Result { unreachable!() }
 			fn aux_4(_data: i32) -> Result { unreachable!() }
+
+			fn offchain_worker(_n: T::BlockNumber) {
+				if ::runtime_io::local_storage_get(b"ran").is_none() {
+					let _ = ::runtime_io::submit_extrinsic(b"xt");
+					::runtime_io::local_storage_set(b"ran", b"yes");
+				}
+			}
 		}
 	}
 
@@ -915,4 +1082,54 @@ mod tests {
 		let metadata = Module::<TraitImpl>::metadata();
 		assert_eq!(EXPECTED_METADATA, metadata);
 	}
+
+	#[test]
+	fn module_weights() {
+		let default = DispatchInfo { weight: ::runtime_primitives::weights::DEFAULT_WEIGHT, class: DispatchClass::Normal };
+		assert_eq!(Call::<TraitImpl>::aux_0().get_dispatch_info(), default);
+		assert_eq!(Call::<TraitImpl>::aux_2(1, "".into()).get_dispatch_info(),
+			DispatchInfo { weight: 3, class: DispatchClass::Normal });
+		assert_eq!(Call::<TraitImpl>::aux_3().get_dispatch_info(),
+			DispatchInfo { weight: 5, class: DispatchClass::Operational });
+		assert_eq!(Call::<TraitImpl>::aux_4(1).get_dispatch_info(), default);
+	}
+
+	#[derive(Default)]
+	struct TestOffchainExt {
+		extrinsics: Vec<Vec<u8>>,
+		storage: ::std::collections::HashMap<Vec<u8>, Vec<u8>>,
+	}
+
+	impl ::runtime_io::OffchainExt for TestOffchainExt {
+		fn submit_extrinsic(&mut self, extrinsic: Vec<u8>) -> ::std::result::Result<(), ()> {
+			self.extrinsics.push(extrinsic);
+			Ok(())
+		}
+
+		fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+			self.storage.get(key).cloned()
+		}
+
+		fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+			self.storage.insert(key.to_vec(), value.to_vec());
+		}
+
+		fn http_request(&mut self, _: &str, _: &str, _: &[u8]) -> Option<Vec<u8>> {
+			None
+		}
+	}
+
+	#[test]
+	fn offchain_worker_uses_host_functions() {
+		use runtime_primitives::traits::OffchainWorker;
+
+		let mut ext = TestOffchainExt::default();
+		::runtime_io::with_offchain(&mut ext, || {
+			<Module<TraitImpl> as OffchainWorker<u32>>::offchain_worker(1);
+			<Module<TraitImpl> as OffchainWorker<u32>>::offchain_worker(2);
+		});
+
+		assert_eq!(ext.extrinsics, vec![b"xt".to_vec()]);
+		assert_eq!(ext.storage.get(&b"ran"[..]), Some(&b"yes".to_vec()));
+	}
 }
@@ -18,11 +18,17 @@
 pub use rstd::{result::Result, vec::Vec};
 #[doc(hidden)]
 pub use runtime_primitives::traits::ProvideInherent;
+pub use runtime_primitives::inherents::{InherentData, InherentIdentifier};
 
 
 /// Implement the outer inherent.
 /// All given modules need to implement `ProvideInherent`.
 ///
+/// `$name` becomes an alias of `InherentData`, and the `InherentDataExt` trait gives it the means
+/// to create the inherent extrinsics of a block from it and to check a block against it. Each
+/// module only gets to see its own part of the data. Modules whose part is missing are skipped
+/// when creating the inherent extrinsics, and fail the check unless their inherent is optional.
+///
 /// # Example
 ///
 /// ```nocompile
@@ -70,12 +76,8 @@ macro_rules! impl_outer_inherent {
 		}
 	) => {
 		$( #[$attr] )*
-		// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
-		#[derive(Encode, Decode)]
 		/// Inherent data to include in a block.
-		pub struct $name {
-			$( $module: <$module_ty as $crate::inherent::ProvideInherent>::Inherent, )*
-		}
+		pub type $name = $crate::inherent::InherentData;
 
 		$(
 			$(
@@ -83,36 +85,48 @@ macro_rules! impl_outer_inherent {
 			)*
 		)*
 
-		impl $name {
-			/// Create a new instance.
-			pub fn new( $( $module: <$module_ty as $crate::inherent::ProvideInherent>::Inherent ),* ) -> Self {
-				Self {
-					$( $module, )*
-				}
-			}
+		/// Creating and checking the inherent extrinsics of a block.
+		pub trait InherentDataExt {
+			/// Create the inherent extrinsics of a block.
+			fn create_inherent_extrinsics(&self) -> $crate::inherent::Vec<$unchecked>;
+
+			/// Check the inherent extrinsics of `block`.
+			fn check_inherents(&self, block: $block) -> $crate::inherent::Result<(), $error>;
+		}
 
-			fn create_inherent_extrinsics(self) -> Vec<$unchecked> {
+		impl InherentDataExt for $crate::inherent::InherentData {
+			fn create_inherent_extrinsics(&self) -> $crate::inherent::Vec<$unchecked> {
 				let mut inherent = $crate::inherent::Vec::new();
 
 				$(
-					inherent.extend(
-						<$module_ty as $crate::inherent::ProvideInherent>::create_inherent_extrinsics(self.$module)
-							.into_iter()
-							.map(|v| (v.0, $unchecked::new_unsigned($call::$module_ty(v.1))))
-					);
+					let identifier = <$module_ty as $crate::inherent::ProvideInherent>::INHERENT_IDENTIFIER;
+					if let Ok(Some(data)) = self.get_data(&identifier) {
+						inherent.extend(
+							<$module_ty as $crate::inherent::ProvideInherent>::create_inherent_extrinsics(data)
+								.into_iter()
+								.map(|v| (v.0, $unchecked::new_unsigned($call::$module_ty(v.1))))
+						);
+					}
 				)*
 
 				inherent.as_mut_slice().sort_unstable_by_key(|v| v.0);
 				inherent.into_iter().map(|v| v.1).collect()
 			}
 
-			fn check_inherents(self, block: $block) -> $crate::inherent::Result<(), $error> {
+			fn check_inherents(&self, block: $block) -> $crate::inherent::Result<(), $error> {
 				$(
-					<$module_ty as $crate::inherent::ProvideInherent>::check_inherent(
-						&block, self.$module, &|xt| match xt.function {
-							Call::$module_ty(ref data) => Some(data),
-							_ => None,
-						}).map_err($error::$module_ty)?;
+					let identifier = <$module_ty as $crate::inherent::ProvideInherent>::INHERENT_IDENTIFIER;
+					match self.get_data(&identifier) {
+						Ok(Some(data)) => <$module_ty as $crate::inherent::ProvideInherent>::check_inherent(
+							&block, data, &|xt| match xt.function {
+								Call::$module_ty(ref data) => Some(data),
+								_ => None,
+							}).map_err($error::$module_ty)?,
+						Ok(None) => if <$module_ty as $crate::inherent::ProvideInherent>::INHERENT_REQUIRED {
+							return Err($error::MissingInherentData(identifier));
+						},
+						Err(_) => return Err($error::InvalidInherentData(identifier)),
+					}
 				)*
 				Ok(())
 			}
@@ -123,6 +137,10 @@ macro_rules! impl_outer_inherent {
 		#[cfg_attr(feature = "std", derive(Decode))]
 		pub enum $error {
 			$( $module_ty(<$module_ty as $crate::inherent::ProvideInherent>::Error), )*
+			/// The part of the inherent data under the given identifier could not be decoded.
+			InvalidInherentData($crate::inherent::InherentIdentifier),
+			/// The inherent data has no part under the given identifier.
+			MissingInherentData($crate::inherent::InherentIdentifier),
 		}
 	};
 }
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 pub use substrate_metadata::{
-	DecodeDifferent, FnEncode, RuntimeMetadata, RuntimeModuleMetadata
+	DecodeDifferent, FnEncode, RuntimeMetadata, RuntimeModuleMetadata, TypeMetadata
 };
 
 /// Implements the metadata support for the given runtime and all its modules.
@@ -38,6 +38,7 @@ macro_rules! impl_runtime_metadata {
 					outer_event: Self::outer_event_metadata(),
 					modules: __runtime_modules_to_metadata!($runtime;; $( $rest )*),
 					outer_dispatch: Self::outer_dispatch_metadata(),
+					types: $crate::metadata::DecodeDifferent::Encode(&[]),
 				}
 			}
 		}
@@ -339,7 +340,8 @@ mod tests {
 					index: 1,
 				}
 			])
-		}
+		},
+		types: DecodeDifferent::Encode(&[]),
 	};
 
 	#[test]
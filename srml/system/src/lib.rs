@@ -44,6 +44,7 @@ use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero,
 	Hash, Member, MaybeDisplay, EnsureOrigin, Digest as DigestT, As, CurrentHeight, BlockNumberToHash};
 use substrate_primitives::storage::well_known_keys;
 use runtime_support::{storage, StorageValue, StorageMap, Parameter};
+use runtime_support::dispatch::{Weight, DispatchClass, DispatchInfo};
 use safe_mix::TripletMix;
 
 #[cfg(any(feature = "std", test))]
@@ -125,6 +126,8 @@ decl_event!(
 		ExtrinsicSuccess,
 		/// An extrinsic failed.
 		ExtrinsicFailed,
+		/// The runtime code was replaced; blocks from the next one on are executed with the new code.
+		CodeUpdated,
 	}
 );
 
@@ -191,6 +194,11 @@ decl_storage! {
 		pub AccountNonce get(account_nonce): map T::AccountId => T::Index;
 
 		ExtrinsicCount: Option<u32>;
+		/// Total weight of all extrinsics applied so far in the current block.
+		AllExtrinsicsWeight get(all_extrinsics_weight): Weight;
+		/// The most weight that the extrinsics of a block may have in total. Only operational
+		/// dispatches may use the last quarter of it.
+		pub MaximumBlockWeight get(maximum_block_weight) config(): Weight = 4 * 1024 * 1024;
 		pub BlockHash get(block_hash) build(|_| vec![(T::BlockNumber::zero(), [69u8; 32])]): map T::BlockNumber => T::Hash;
 		ExtrinsicData get(extrinsic_data): map u32 => Vec<u8>;
 		RandomSeed get(random_seed) build(|_| [0u8; 32]): T::Hash;
@@ -281,6 +289,7 @@ impl<T: Trait> Module<T> {
 	pub fn finalise() -> T::Header {
 		<RandomSeed<T>>::kill();
 		<ExtrinsicCount<T>>::kill();
+		<AllExtrinsicsWeight<T>>::kill();
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
@@ -382,6 +391,26 @@ impl<T: Trait> Module<T> {
 		storage::unhashed::put(well_known_keys::EXTRINSIC_INDEX, &next_extrinsic_index);
 	}
 
+	/// The most weight that the extrinsics of the given class may have in total in a block.
+	pub fn weight_limit(class: DispatchClass) -> Weight {
+		let max = Self::maximum_block_weight();
+		match class {
+			DispatchClass::Normal => max / 4 * 3,
+			DispatchClass::Operational => max,
+		}
+	}
+
+	/// Whether an extrinsic of the given weight and class still fits into the current block.
+	pub fn can_include_weight(info: DispatchInfo) -> bool {
+		Self::all_extrinsics_weight().checked_add(info.weight)
+			.map_or(false, |total| total <= Self::weight_limit(info.class))
+	}
+
+	/// Account for the weight of an extrinsic that is about to be applied.
+	pub fn note_extrinsic_weight(info: DispatchInfo) {
+		<AllExtrinsicsWeight<T>>::put(Self::all_extrinsics_weight().saturating_add(info.weight));
+	}
+
 	/// To be called immediately after `note_applied_extrinsic` of the last extrinsic of the block
 	/// has been called.
 	pub fn note_finished_extrinsics() {
@@ -460,6 +489,7 @@ mod tests {
 			match e {
 				Event::ExtrinsicSuccess => 100,
 				Event::ExtrinsicFailed => 101,
+				Event::CodeUpdated => 102,
 			}
 		}
 	}
@@ -494,4 +524,24 @@ mod tests {
 			]);
 		});
 	}
+
+	#[test]
+	fn block_weight_is_limited_by_class() {
+		with_externalities(&mut new_test_ext(), || {
+			let normal = |weight| DispatchInfo { weight, class: DispatchClass::Normal };
+			let operational = |weight| DispatchInfo { weight, class: DispatchClass::Operational };
+			let max = System::maximum_block_weight();
+
+			System::initialise(&1, &[0u8; 32].into(), &[0u8; 32].into());
+			assert!(System::can_include_weight(normal(max / 4 * 3)));
+			System::note_extrinsic_weight(normal(max / 4 * 3));
+			assert!(!System::can_include_weight(normal(1)));
+			assert!(System::can_include_weight(operational(max / 4)));
+			assert!(!System::can_include_weight(operational(max / 4 + 1)));
+
+			System::note_finished_extrinsics();
+			System::finalise();
+			assert_eq!(System::all_extrinsics_weight(), 0);
+		});
+	}
 }
@@ -58,6 +58,7 @@ use codec::HasCompact;
 use runtime_support::{StorageValue, Parameter};
 use runtime_support::dispatch::Result;
 use runtime_primitives::RuntimeString;
+use runtime_primitives::inherents::{InherentIdentifier, well_known_identifiers};
 use runtime_primitives::traits::{
 	As, SimpleArithmetic, Zero, ProvideInherent, Block as BlockT, Extrinsic
 };
@@ -143,7 +144,10 @@ pub enum InherentError {
 	TimestampInFuture(u64),
 }
 
+/// The inherent data is the current time when authoring a block, and the latest time that the
+/// block may claim when checking one; allowing for clock drift is up to the node checking it.
 impl<T: Trait> ProvideInherent for Module<T> {
+	const INHERENT_IDENTIFIER: InherentIdentifier = well_known_identifiers::TIMESTAMP;
 	type Inherent = T::Moment;
 	type Call = Call<T>;
 	type Error = InherentError;
@@ -155,8 +159,6 @@ impl<T: Trait> ProvideInherent for Module<T> {
 	fn check_inherent<Block: BlockT, F: Fn(&Block::Extrinsic) -> Option<&Self::Call>>(
 			block: &Block, data: Self::Inherent, extract_function: &F
 	) -> result::Result<(), Self::Error> {
-		const MAX_TIMESTAMP_DRIFT: u64 = 60;
-
 		let xt = block.extrinsics().get(T::TIMESTAMP_SET_POSITION as usize)
 			.ok_or_else(|| InherentError::Other("No valid timestamp inherent in block".into()))?;
 
@@ -165,7 +167,7 @@ impl<T: Trait> ProvideInherent for Module<T> {
 			_ => return Err(InherentError::Other("No valid timestamp inherent in block".into())),
 		}.into().as_();
 
-		if t > data.as_() + MAX_TIMESTAMP_DRIFT {
+		if t > data.as_() {
 			Err(InherentError::TimestampInFuture(t))
 		} else {
 			Ok(())
@@ -181,7 +183,7 @@ mod tests {
 	use substrate_primitives::H256;
 	use runtime_primitives::BuildStorage;
 	use runtime_primitives::traits::BlakeTwo256;
-	use runtime_primitives::testing::{Digest, DigestItem, Header};
+	use runtime_primitives::testing::{Digest, DigestItem, Header, TestSignature};
 
 	impl_outer_origin! {
 		pub enum Origin for Test {}
@@ -204,8 +206,10 @@ mod tests {
 	impl consensus::Trait for Test {
 		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type Log = DigestItem;
+		type Signature = TestSignature;
 		type SessionKey = u64;
 		type OnOfflineValidator = ();
+		type OnMisbehavior = ();
 	}
 	impl Trait for Test {
 		const TIMESTAMP_SET_POSITION: u32 = 0;
@@ -41,14 +41,18 @@ extern crate substrate_primitives;
 extern crate sr_primitives as runtime_primitives;
 extern crate srml_system as system;
 extern crate srml_balances as balances;
+extern crate srml_indices as indices;
+extern crate srml_scheduler as scheduler;
 
 use rstd::prelude::*;
 use runtime_support::{StorageValue, StorageMap};
 use runtime_support::dispatch::Result;
 use runtime_primitives::{Permill, traits::{Zero, EnsureOrigin}};
 use codec::{HasCompact, Compact};
-use balances::{OnDilution, address::Address};
-use system::ensure_signed;
+use scheduler::{Schedule, TaskAddress};
+use balances::{OnDilution, OnCollected};
+use indices::address::Address;
+use system::{ensure_signed, ensure_root};
 
 /// Our module's configuration trait. All our types and consts go in here. If the
 /// module is dependent on specific other modules, then their configuration traits
@@ -62,6 +66,12 @@ pub trait Trait: balances::Trait {
 	/// Origin from which rejections must come.
 	type RejectOrigin: EnsureOrigin<Self::Origin>;
 
+	/// The aggregated call type, through which the spends are scheduled.
+	type Call: From<Call<Self>>;
+
+	/// Schedules the spend at the end of every spend period.
+	type Scheduler: Schedule<Self::BlockNumber, <Self as Trait>::Call>;
+
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -83,7 +93,7 @@ decl_module! {
 			beneficiary: Address<T::AccountId, T::AccountIndex>
 		) -> Result {
 			let proposer = ensure_signed(origin)?;
-			let beneficiary = <balances::Module<T>>::lookup(beneficiary)?;
+			let beneficiary = <indices::Module<T>>::lookup(beneficiary)?;
 			let value = value.into();
 
 			let bond = Self::calculate_bond(value);
@@ -115,6 +125,12 @@ decl_module! {
 			spend_period: <T::BlockNumber as HasCompact>::Type,
 			burn: Permill
 		) -> Result {
+			// the next spend is scheduled again with the new period.
+			if let Some(address) = Self::next_spend() {
+				T::Scheduler::cancel(address)?;
+				<NextSpend<T>>::kill();
+			}
+
 			<ProposalBond<T>>::put(proposal_bond);
 			<ProposalBondMinimum<T>>::put(proposal_bond_minimum.into());
 			<SpendPeriod<T>>::put(spend_period.into());
@@ -148,10 +164,24 @@ decl_module! {
 			Ok(())
 		}
 
+		/// Spend some funds, at the end of a spend period.
+		fn spend(origin) -> Result {
+			ensure_root(origin)?;
+			<NextSpend<T>>::kill();
+			Self::spend_funds();
+			Ok(())
+		}
+
 		fn on_finalise(n: T::BlockNumber) {
-			// Check to see if we should spend some funds!
-			if (n % Self::spend_period()).is_zero() {
-				Self::spend_funds();
+			// Make sure that the end of the current spend period is scheduled.
+			if Self::next_spend().is_none() {
+				let period = Self::spend_period();
+				let when = n - n % period + period;
+				let call: Call<T> = Call::spend();
+				match T::Scheduler::schedule(when, None, call.into()) {
+					Ok(address) => <NextSpend<T>>::put(address),
+					Err(e) => runtime_support::print(e),
+				}
 			}
 		}
 	}
@@ -197,6 +227,9 @@ decl_storage! {
 
 		/// Proposal indices that have been approved but not yet awarded.
 		Approvals get(approvals): Vec<ProposalIndex>;
+
+		/// The address of the scheduled spend that ends the current spend period.
+		NextSpend get(next_spend): Option<TaskAddress<T::BlockNumber>>;
 	}
 }
 
@@ -281,6 +314,13 @@ impl<T: Trait> OnDilution<T::Balance> for Module<T> {
 	}
 }
 
+impl<T: Trait> OnCollected<T::Balance> for Module<T> {
+	fn on_collected(value: T::Balance) {
+		// Fees paid and balance slashed accrue to the treasury rather than being burnt.
+		<Pot<T>>::mutate(|x| *x += value);
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -295,7 +335,13 @@ mod tests {
 		pub enum Origin for Test {}
 	}
 
-	#[derive(Clone, Eq, PartialEq)]
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			treasury::Treasury,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 	pub struct Test;
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -309,19 +355,32 @@ mod tests {
 		type Event = ();
 		type Log = DigestItem;
 	}
+	impl indices::Trait for Test {
+		type AccountIndex = u64;
+		type Event = ();
+	}
 	impl balances::Trait for Test {
 		type Balance = u64;
-		type AccountIndex = u64;
 		type OnFreeBalanceZero = ();
 		type EnsureAccountLiquid = ();
+		type LockedBalance = ();
+		type OnCollected = Treasury;
+		type Event = ();
+	}
+	impl scheduler::Trait for Test {
+		type Call = Call;
 		type Event = ();
 	}
 	impl Trait for Test {
 		type ApproveOrigin = system::EnsureRoot<u64>;
 		type RejectOrigin = system::EnsureRoot<u64>;
+		type Call = Call;
+		type Scheduler = Scheduler;
 		type Event = ();
 	}
+	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
+	type Scheduler = scheduler::Module<Test>;
 	type Treasury = Module<Test>;
 
 	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
@@ -330,10 +389,12 @@ mod tests {
 			balances: vec![(0, 100), (1, 99), (2, 1)],
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			transaction_weight_fee: 0,
 			transfer_fee: 0,
 			creation_fee: 0,
 			existential_deposit: 0,
-			reclaim_rebate: 0,
+			vesting: vec![],
+			minimum_vested_transfer: 0,
 		}.build_storage().unwrap().0);
 		t.extend(GenesisConfig::<Test>{
 			proposal_bond: Permill::from_percent(5),
@@ -341,9 +402,19 @@ mod tests {
 			spend_period: 2,
 			burn: Permill::from_percent(50),
 		}.build_storage().unwrap().0);
+		t.extend(scheduler::GenesisConfig::<Test>::default().build_storage().unwrap().0);
 		t.into()
 	}
 
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			let b = System::block_number() + 1;
+			System::set_block_number(b);
+			<Treasury as OnFinalise<u64>>::on_finalise(b);
+			<Scheduler as OnFinalise<u64>>::on_finalise(b);
+		}
+	}
+
 	#[test]
 	fn genesis_config_works() {
 		with_externalities(&mut new_test_ext(), || {
@@ -365,6 +436,39 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn collected_funds_go_to_pot() {
+		with_externalities(&mut new_test_ext(), || {
+			Treasury::on_collected(10);
+			Treasury::on_collected(5);
+			assert_eq!(Treasury::pot(), 15);
+		});
+	}
+
+	#[test]
+	fn slashed_balance_goes_to_pot() {
+		with_externalities(&mut new_test_ext(), || {
+			assert!(Balances::slash(&0, 10).is_none());
+			assert_eq!(Balances::free_balance(&0), 90);
+			assert_eq!(Treasury::pot(), 10);
+
+			assert_ok!(Balances::reserve(&1, 20));
+			assert!(Balances::slash_reserved(&1, 5).is_none());
+			assert_eq!(Balances::reserved_balance(&1), 15);
+			assert_eq!(Treasury::pot(), 15);
+		});
+	}
+
+	#[test]
+	fn rejected_proposal_bond_goes_to_pot() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100.into(), Address::Id(3)));
+			assert_ok!(Treasury::reject_proposal(Origin::ROOT, 0.into()));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Treasury::pot(), 5);
+		});
+	}
+
 	#[test]
 	fn spend_proposal_takes_min_deposit() {
 		with_externalities(&mut new_test_ext(), || {
@@ -398,7 +502,7 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100.into(), Address::Id(3)));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0.into()));
 
-			<Treasury as OnFinalise<u64>>::on_finalise(1);
+			run_to_block(1);
 			assert_eq!(Balances::free_balance(&3), 0);
 			assert_eq!(Treasury::pot(), 100);
 		});
@@ -409,7 +513,7 @@ mod tests {
 		with_externalities(&mut new_test_ext(), || {
 			Treasury::on_dilution(100, 100);
 
-			<Treasury as OnFinalise<u64>>::on_finalise(2);
+			run_to_block(2);
 			assert_eq!(Treasury::pot(), 50);
 		});
 	}
@@ -422,9 +526,10 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100.into(), Address::Id(3)));
 			assert_ok!(Treasury::reject_proposal(Origin::ROOT, 0.into()));
 
-			<Treasury as OnFinalise<u64>>::on_finalise(2);
+			run_to_block(2);
 			assert_eq!(Balances::free_balance(&3), 0);
-			assert_eq!(Treasury::pot(), 50);
+			// the slashed bond of 5 joins the pot of 100 before half of it is burnt.
+			assert_eq!(Treasury::pot(), 53);
 		});
 	}
 
@@ -472,7 +577,7 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100.into(), Address::Id(3)));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0.into()));
 
-			<Treasury as OnFinalise<u64>>::on_finalise(2);
+			run_to_block(2);
 			assert_eq!(Balances::free_balance(&3), 100);
 			assert_eq!(Treasury::pot(), 0);
 		});
@@ -486,13 +591,49 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 150.into(), Address::Id(3)));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0.into()));
 
-			<Treasury as OnFinalise<u64>>::on_finalise(2);
+			run_to_block(2);
 			assert_eq!(Treasury::pot(), 100);
 
 			Treasury::on_dilution(100, 100);
-			<Treasury as OnFinalise<u64>>::on_finalise(4);
+			run_to_block(4);
 			assert_eq!(Balances::free_balance(&3), 150);
 			assert_eq!(Treasury::pot(), 25);
 		});
 	}
+
+	#[test]
+	fn spend_is_scheduled_at_the_end_of_the_spend_period() {
+		with_externalities(&mut new_test_ext(), || {
+			Treasury::on_dilution(100, 100);
+			assert_noop!(Treasury::spend(Origin::signed(0)), "bad origin: expected to be a root origin");
+
+			run_to_block(1);
+			assert_eq!(Treasury::next_spend(), Some((2, 0)));
+
+			assert_ok!(Treasury::configure(Permill::from_percent(5), 1.into(), 3.into(), Permill::from_percent(50)));
+			assert_eq!(Treasury::next_spend(), None);
+			assert!(Scheduler::agenda(2).iter().all(Option::is_none));
+
+			run_to_block(2);
+			assert_eq!(Treasury::next_spend(), Some((3, 0)));
+			assert_eq!(Treasury::pot(), 100);
+
+			run_to_block(3);
+			assert_eq!(Treasury::pot(), 50);
+		});
+	}
+
+	#[test]
+	fn configure_changes_nothing_if_the_next_spend_cannot_be_cancelled() {
+		with_externalities(&mut new_test_ext(), || {
+			run_to_block(1);
+			assert_eq!(Treasury::next_spend(), Some((2, 0)));
+			assert_ok!(<Scheduler as Schedule<_, _>>::cancel((2, 0)));
+
+			assert_noop!(
+				Treasury::configure(Permill::from_percent(5), 1.into(), 3.into(), Permill::from_percent(50)),
+				"task not found"
+			);
+		});
+	}
 }